@@ -4,7 +4,7 @@
 //! Benchmarks for the `retention` function.
 //!
 //! Measures update throughput across condition counts and combine overhead.
-//! Retention uses O(1) state (u32 bitmask), enabling combine benchmarks
+//! Retention uses O(1) state (u64 bitmask), enabling combine benchmarks
 //! up to 1 billion elements without memory constraints.
 //!
 //! Uses Criterion with 100+ samples and 95% confidence intervals.
@@ -66,7 +66,7 @@ fn bench_retention_combine(c: &mut Criterion) {
             let states: Vec<RetentionState> = (0..n)
                 .map(|i| {
                     let mut s = RetentionState::new();
-                    s.conditions_met = 1u32 << (i % 8);
+                    s.conditions_met = 1u64 << (i % 8);
                     s.num_conditions = 8;
                     s
                 })
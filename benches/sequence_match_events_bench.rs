@@ -18,9 +18,9 @@ fn make_sequence_events(num_events: usize, num_conditions: usize) -> Vec<Event>
         .map(|i| {
             let step = i % (num_conditions * 2);
             let bitmask = if step < num_conditions {
-                1u32 << step
+                1u64 << step
             } else {
-                0u32
+                0u64
             };
             Event::new((i as i64) * 1_000_000, bitmask)
         })
@@ -70,7 +70,7 @@ fn bench_sequence_match_events_combine(c: &mut Criterion) {
                 .map(|i| {
                     let mut s = SequenceState::new();
                     s.set_pattern("(?1).*(?2).*(?3)");
-                    let bitmask = 1u32 << (i % 3);
+                    let bitmask = 1u64 << (i % 3);
                     s.update(Event::new((i as i64) * 1_000_000, bitmask));
                     s
                 })
@@ -154,10 +154,51 @@ fn bench_sequence_next_node_realistic(c: &mut Criterion) {
     group.finish();
 }
 
+/// Combine of parallel segments: 16 partial states, each sorted, with
+/// interleaved timestamps, as `DuckDB` hands a group scanned by several
+/// threads to finalize. Finalize merges the 16 runs instead of sorting.
+fn bench_sequence_next_node_segments(c: &mut Criterion) {
+    const SEGMENTS: usize = 16;
+    let mut group = c.benchmark_group("sequence_next_node_segments");
+
+    for &n in &[10_000_usize, 100_000, 1_000_000] {
+        group.throughput(Throughput::Elements(n as u64));
+        if n >= 1_000_000 {
+            group.sample_size(10);
+        }
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            let events = make_next_node_events(n, 3);
+            let states: Vec<SequenceNextNodeState> = (0..SEGMENTS)
+                .map(|segment| {
+                    let mut s = SequenceNextNodeState::new();
+                    s.direction = Some(Direction::Forward);
+                    s.base = Some(Base::FirstMatch);
+                    s.num_steps = 3;
+                    for e in events.iter().skip(segment).step_by(SEGMENTS) {
+                        s.update(e.clone());
+                    }
+                    s
+                })
+                .collect();
+
+            b.iter(|| {
+                let mut combined = SequenceNextNodeState::new();
+                for s in &states {
+                    combined.combine_in_place(black_box(s));
+                }
+                combined.finalize()
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_sequence_next_node,
     bench_sequence_next_node_combine,
-    bench_sequence_next_node_realistic
+    bench_sequence_next_node_realistic,
+    bench_sequence_next_node_segments
 );
 criterion_main!(benches);
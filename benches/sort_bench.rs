@@ -20,7 +20,7 @@ fn make_random_events(num_events: usize) -> Vec<Event> {
             let base = (num_events - i) as i64;
             let jitter = (i % 7) as i64;
             let ts = base * 1_000_000 + jitter * 100;
-            let bitmask = 1u32 << (i % 3);
+            let bitmask = 1u64 << (i % 3);
             Event::new(ts, bitmask)
         })
         .collect()
@@ -78,7 +78,7 @@ fn bench_sort_events_presorted(c: &mut Criterion) {
             let events: Vec<Event> = (0..n)
                 .map(|i| {
                     let ts = (i as i64) * 1_000_000;
-                    Event::new(ts, 1u32 << (i % 3))
+                    Event::new(ts, 1u64 << (i % 3))
                 })
                 .collect();
             b.iter(|| {
@@ -23,9 +23,9 @@ fn make_funnel_events(num_events: usize, num_conditions: usize) -> Vec<Event> {
             // Each condition fires for roughly 1/num_conditions of events
             let step = i % (num_conditions * 3);
             let bitmask = if step < num_conditions {
-                1u32 << step
+                1u64 << step
             } else {
-                0u32
+                0u64
             };
             Event::new((i as i64) * 1_000_000, bitmask)
         })
@@ -81,7 +81,7 @@ fn bench_window_funnel_combine(c: &mut Criterion) {
                 .map(|i| {
                     let mut s = WindowFunnelState::new();
                     s.window_size_us = 3_600_000_000;
-                    let bitmask = 1u32 << (i % 5);
+                    let bitmask = 1u64 << (i % 5);
                     s.update(Event::new((i as i64) * 1_000_000, bitmask), 5);
                     s
                 })
@@ -102,9 +102,50 @@ fn bench_window_funnel_combine(c: &mut Criterion) {
     group.finish();
 }
 
+/// A page view (entry) every second, an add-to-cart every 10 minutes and no
+/// purchase, so no chain completes: without entry pruning every entry scans a
+/// full one-hour window.
+fn make_dense_entry_events(num_events: usize) -> Vec<Event> {
+    (0..num_events)
+        .map(|i| {
+            let bitmask = if i % 600 == 599 { 0b010 } else { 0b001 };
+            Event::new((i as i64) * 1_000_000, bitmask)
+        })
+        .collect()
+}
+
+/// `finalize` prunes entries followed by another entry before any step-2
+/// event; `finalize_stats` scans from every entry. Same data, same result.
+fn bench_window_funnel_dense_entries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("window_funnel_dense_entries");
+
+    for &n in &[10_000_usize, 100_000, 1_000_000] {
+        group.throughput(Throughput::Elements(n as u64));
+        if n >= 1_000_000 {
+            group.sample_size(10);
+        }
+        let event_data = make_dense_entry_events(n);
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000; // 1 hour
+        for e in &event_data {
+            state.update(*e, 3);
+        }
+
+        group.bench_with_input(BenchmarkId::new("pruned", n), &state, |b, state| {
+            b.iter(|| black_box(state.clone()).finalize());
+        });
+        group.bench_with_input(BenchmarkId::new("full_scan", n), &state, |b, state| {
+            b.iter(|| black_box(state.clone()).finalize_stats().step);
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_window_funnel_finalize,
-    bench_window_funnel_combine
+    bench_window_funnel_combine,
+    bench_window_funnel_dense_entries
 );
 criterion_main!(benches);
@@ -113,7 +113,7 @@ add_header_html() {
 echo "Adding SPDX headers..."
 
 # Rust source files
-for f in $(find src benches -name '*.rs' | sort); do
+for f in $(find src benches tests -name '*.rs' | sort); do
     add_header_rust "$f"
 done
 
@@ -0,0 +1,85 @@
+#!/usr/bin/env python3
+# SPDX-License-Identifier: MIT
+# Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+# =============================================================================
+# encode_journeys.py — Convert a CSV export into a regression corpus .bjny file
+#
+# Input CSV (header row required):
+#   journey,ts_us,c1,c2,...,cN
+# where `journey` is any group key (it is discarded), `ts_us` is a timestamp in
+# microseconds and c1..cN are 0/1 (or true/false) condition flags.
+#
+# Timestamps are rebased so every journey starts at 0; group keys are replaced
+# by the journey's position in the file. Only relative times and condition
+# masks leave your machine. See tests/corpus/README.md for the format.
+#
+# Usage:
+#   python3 scripts/encode_journeys.py events.csv tests/corpus/mine.bjny
+#
+# Produce the CSV from DuckDB with, for example:
+#   COPY (SELECT user_id AS journey, epoch_us(ts) AS ts_us,
+#                (event = 'view')::INT AS c1, (event = 'cart')::INT AS c2
+#         FROM events ORDER BY user_id, ts) TO 'events.csv' (HEADER);
+# =============================================================================
+
+import csv
+import sys
+
+MAGIC = b"BJNY"
+VERSION = 1
+
+
+def varint(value):
+    out = bytearray()
+    while value >= 0x80:
+        out.append((value & 0x7F) | 0x80)
+        value >>= 7
+    out.append(value)
+    return bytes(out)
+
+
+def zigzag(value):
+    return (value << 1) ^ (value >> 63)
+
+
+def flag(text):
+    return text.strip().lower() not in ("", "0", "false", "f")
+
+
+def main(src, dst):
+    journeys = {}
+    with open(src, newline="") as f:
+        reader = csv.reader(f)
+        header = next(reader)
+        num_conditions = len(header) - 2
+        if not 1 <= num_conditions <= 32:
+            sys.exit(f"expected 1..32 condition columns, got {num_conditions}")
+        for row in reader:
+            mask = 0
+            for c, value in enumerate(row[2:]):
+                if flag(value):
+                    mask |= 1 << c
+            journeys.setdefault(row[0], []).append((int(row[1]), mask))
+
+    out = bytearray(MAGIC)
+    out.append(VERSION)
+    out += varint(len(journeys))
+    for events in journeys.values():
+        base = min(ts for ts, _ in events)
+        out.append(num_conditions)
+        out += varint(len(events))
+        prev = 0
+        for ts, mask in events:
+            ts -= base
+            out += varint(zigzag(ts - prev))
+            out += varint(mask)
+            prev = ts
+    with open(dst, "wb") as f:
+        f.write(out)
+    print(f"wrote {len(journeys)} journeys to {dst}")
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 3:
+        sys.exit("usage: encode_journeys.py events.csv out.bjny")
+    main(sys.argv[1], sys.argv[2])
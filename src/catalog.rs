@@ -0,0 +1,816 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `behavioral_version` / `behavioral_functions` — In-database introspection.
+//!
+//! [`FUNCTIONS`] lists every function the extension registers with its
+//! signatures, return type and accepted mode strings, so users can discover
+//! the overloads from SQL instead of reading the repository.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT behavioral_version();
+//! -- 0.5.0 (1a2b3c4)
+//!
+//! SELECT function_name, signature
+//! FROM behavioral_functions()
+//! WHERE function_name LIKE 'window_funnel%';
+//! ```
+//!
+//! The catalog is maintained by hand next to the registrations in `ffi`; the
+//! tests check that its mode lists agree with the parsers.
+
+/// Crate version, from `Cargo.toml`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the extension was built from, if `BEHAVIORAL_GIT_HASH` was set at
+/// build time (the `Makefile` sets it from `git rev-parse`).
+pub const GIT_HASH: Option<&str> = option_env!("BEHAVIORAL_GIT_HASH");
+
+/// Returns the version string reported by `behavioral_version()`:
+/// `VERSION`, followed by the commit in parentheses when known.
+#[must_use]
+pub fn version() -> String {
+    match GIT_HASH {
+        Some(hash) if !hash.is_empty() => format!("{VERSION} ({hash})"),
+        _ => VERSION.to_string(),
+    }
+}
+
+/// How a function is called from SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    /// Aggregate function, used with `GROUP BY`.
+    Aggregate,
+    /// Aggregate meant to be evaluated with `OVER (...)`.
+    Window,
+    /// Scalar function.
+    Scalar,
+    /// Table macro, used in `FROM`.
+    Table,
+}
+
+impl FunctionKind {
+    /// Name shown in the `function_type` column.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Aggregate => "aggregate",
+            Self::Window => "window",
+            Self::Scalar => "scalar",
+            Self::Table => "table",
+        }
+    }
+}
+
+/// One registered function.
+#[derive(Debug, Clone, Copy)]
+pub struct FunctionEntry {
+    /// SQL name.
+    pub name: &'static str,
+    /// How the function is called.
+    pub kind: FunctionKind,
+    /// One entry per overload group, with parameter names and types.
+    /// `[, ...]` marks a variable number of conditions.
+    pub signatures: &'static [&'static str],
+    /// Return type.
+    pub returns: &'static str,
+    /// Accepted values of the mode, direction or base strings, if any.
+    pub modes: &'static [&'static str],
+    /// One-line description.
+    pub description: &'static str,
+}
+
+/// `window_funnel` mode names: [`FunnelMode::MODE_NAMES`] followed by
+/// `skip(K)`, which takes `K` in `0..=63`, and the `tie_break(...)` orders.
+/// Modes combine with commas.
+///
+/// [`FunnelMode::MODE_NAMES`]: crate::window_funnel::FunnelMode::MODE_NAMES
+const FUNNEL_MODES: &[&str] = &[
+    "strict",
+    "strict_deduplication",
+    "strict_order",
+    "timestamp_dedup",
+    "strict_increase",
+    "strict_once",
+    "allow_reentry",
+    "entry_advances",
+    "strict_order_zero",
+    "strict_time",
+    "strict_dedup",
+    "skip(K)",
+    "tie_break(input)",
+    "tie_break(condition)",
+    "tie_break(argument)",
+];
+
+/// `sequence_next_node` directions followed by the options that may be
+/// appended to a direction with commas.
+const DIRECTIONS: &[&str] = &[
+    "forward",
+    "backward",
+    "strict_increase",
+    "session_gap(D)",
+    "missing_base(null)",
+    "missing_base(first)",
+    "missing_base(error)",
+];
+
+/// `sequence_next_node` directions and direction options followed by bases.
+const DIRECTIONS_AND_BASES: &[&str] = &[
+    "forward",
+    "backward",
+    "strict_increase",
+    "session_gap(D)",
+    "missing_base(null)",
+    "missing_base(first)",
+    "missing_base(error)",
+    "head",
+    "tail",
+    "first_match",
+    "last_match",
+];
+
+/// Every function registered by the extension, in registration order.
+pub const FUNCTIONS: &[FunctionEntry] = &[
+    FunctionEntry {
+        name: "sessionize",
+        kind: FunctionKind::Window,
+        signatures: &[
+            "sessionize(timestamp TIMESTAMP, gap INTERVAL)",
+            "sessionize(timestamp TIMESTAMP, gap INTERVAL, max_session_length INTERVAL)",
+        ],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Session ID of each row, split on gaps longer than gap",
+    },
+    FunctionEntry {
+        name: "sessionize_by",
+        kind: FunctionKind::Window,
+        signatures: &["sessionize_by(timestamp TIMESTAMP, gap INTERVAL, boundary_cond BOOLEAN)"],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Session ID of each row, split on long gaps and on rows where boundary_cond is true",
+    },
+    FunctionEntry {
+        name: "sessionize_info",
+        kind: FunctionKind::Window,
+        signatures: &[
+            "sessionize_info(timestamp TIMESTAMP, gap INTERVAL)",
+            "sessionize_info(timestamp TIMESTAMP, gap INTERVAL, max_session_length INTERVAL)",
+            "sessionize_info(timestamp TIMESTAMP, gap INTERVAL, boundary_cond BOOLEAN)",
+        ],
+        returns: "STRUCT",
+        modes: &[],
+        description: "Session ID of each row and whether the row starts its session",
+    },
+    FunctionEntry {
+        name: "sessionize_table",
+        kind: FunctionKind::Table,
+        signatures: &[
+            "sessionize_table(events VARCHAR, ts_col, gap INTERVAL, partition_col := NULL)",
+        ],
+        returns: "TABLE",
+        modes: &[],
+        description: "Rows of a table or view plus a session_id column",
+    },
+    FunctionEntry {
+        name: "behavioral_selfcheck",
+        kind: FunctionKind::Scalar,
+        signatures: &["behavioral_selfcheck()"],
+        returns: "STRUCT",
+        modes: &[],
+        description: "Runs the internal consistency suite against the loaded binary",
+    },
+    FunctionEntry {
+        name: "parse_duration",
+        kind: FunctionKind::Scalar,
+        signatures: &["parse_duration(text VARCHAR)"],
+        returns: "INTERVAL",
+        modes: &[],
+        description: "Parses a duration such as '1h30m' into an interval",
+    },
+    FunctionEntry {
+        name: "strict_condition",
+        kind: FunctionKind::Scalar,
+        signatures: &[
+            "strict_condition(cond BOOLEAN)",
+            "strict_condition(cond INTEGER)",
+        ],
+        returns: "BOOLEAN",
+        modes: &[],
+        description: "Passes a condition through, failing the query when it is NULL",
+    },
+    FunctionEntry {
+        name: "cohort_of",
+        kind: FunctionKind::Scalar,
+        signatures: &["cohort_of(ts TIMESTAMP, anchor TIMESTAMP, period INTERVAL)"],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Index of the fixed-length period after anchor that ts falls in",
+    },
+    FunctionEntry {
+        name: "sequence_pattern_check",
+        kind: FunctionKind::Scalar,
+        signatures: &["sequence_pattern_check(pattern VARCHAR)"],
+        returns: "STRUCT",
+        modes: &[],
+        description: "Validates a pattern string without running an aggregate",
+    },
+    FunctionEntry {
+        name: "sequence_pattern_normalize",
+        kind: FunctionKind::Scalar,
+        signatures: &["sequence_pattern_normalize(pattern VARCHAR)"],
+        returns: "VARCHAR",
+        modes: &[],
+        description: "Canonical text of a pattern, without whitespace and with lower-case units",
+    },
+    FunctionEntry {
+        name: "window_funnel_from_list",
+        kind: FunctionKind::Scalar,
+        signatures: &[
+            "window_funnel_from_list(window INTERVAL, events LIST(STRUCT(ts TIMESTAMP, mask BIGINT)))",
+            "window_funnel_from_list(window INTERVAL, mode VARCHAR, events LIST(STRUCT(ts TIMESTAMP, mask BIGINT)))",
+        ],
+        returns: "INTEGER",
+        modes: FUNNEL_MODES,
+        description: "window_funnel over a list of events with condition bitmasks",
+    },
+    FunctionEntry {
+        name: "sequence_match_from_list",
+        kind: FunctionKind::Scalar,
+        signatures: &[
+            "sequence_match_from_list(pattern VARCHAR, events LIST(STRUCT(ts TIMESTAMP, mask BIGINT)))",
+        ],
+        returns: "BOOLEAN",
+        modes: &[],
+        description: "sequence_match over a list of events with condition bitmasks",
+    },
+    FunctionEntry {
+        name: "window_funnel_merge",
+        kind: FunctionKind::Scalar,
+        signatures: &["window_funnel_merge(state1 BLOB, state2 BLOB)"],
+        returns: "BLOB",
+        modes: &[],
+        description: "Merge of two window_funnel_state states of the same funnel",
+    },
+    FunctionEntry {
+        name: "window_funnel_finalize",
+        kind: FunctionKind::Scalar,
+        signatures: &["window_funnel_finalize(state BLOB)"],
+        returns: "INTEGER",
+        modes: &[],
+        description: "window_funnel step of a window_funnel_state state",
+    },
+    FunctionEntry {
+        name: "retention_merge",
+        kind: FunctionKind::Scalar,
+        signatures: &["retention_merge(state1 BLOB, state2 BLOB)"],
+        returns: "BLOB",
+        modes: &[],
+        description: "Merge of two retention_state states",
+    },
+    FunctionEntry {
+        name: "retention_finalize",
+        kind: FunctionKind::Scalar,
+        signatures: &["retention_finalize(state BLOB)"],
+        returns: "BOOLEAN[]",
+        modes: &[],
+        description: "retention result of a retention_state state",
+    },
+    FunctionEntry {
+        name: "sessionize_merge",
+        kind: FunctionKind::Scalar,
+        signatures: &["sessionize_merge(state1 BLOB, state2 BLOB)"],
+        returns: "BLOB",
+        modes: &[],
+        description: "Merge of two sessionize_state states over disjoint time ranges",
+    },
+    FunctionEntry {
+        name: "sessionize_finalize",
+        kind: FunctionKind::Scalar,
+        signatures: &["sessionize_finalize(state BLOB)"],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Session count of a sessionize_state state",
+    },
+    FunctionEntry {
+        name: "behavioral_explain",
+        kind: FunctionKind::Table,
+        signatures: &["behavioral_explain(pattern VARCHAR)"],
+        returns: "TABLE",
+        modes: &[],
+        description: "One row per pattern step with its type and the execution path chosen",
+    },
+    FunctionEntry {
+        name: "behavioral_demo_events",
+        kind: FunctionKind::Table,
+        signatures: &["behavioral_demo_events(n_users BIGINT, seed BIGINT)"],
+        returns: "TABLE",
+        modes: &[],
+        description: "Deterministic synthetic clickstream events for examples and tests",
+    },
+    FunctionEntry {
+        name: "behavioral_version",
+        kind: FunctionKind::Scalar,
+        signatures: &["behavioral_version()"],
+        returns: "VARCHAR",
+        modes: &[],
+        description: "Extension version and, when known, build commit",
+    },
+    FunctionEntry {
+        name: "behavioral_functions",
+        kind: FunctionKind::Table,
+        signatures: &["behavioral_functions()"],
+        returns: "TABLE",
+        modes: &[],
+        description: "Lists the extension's functions, signatures and modes",
+    },
+    FunctionEntry {
+        name: "retention",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "retention(cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "retention(conditions BOOLEAN[])",
+        ],
+        returns: "BOOLEAN[]",
+        modes: &[],
+        description: "Whether each condition was met along with the first",
+    },
+    FunctionEntry {
+        name: "retention_counts",
+        kind: FunctionKind::Aggregate,
+        signatures: &["retention_counts(cond1 BOOLEAN, cond2 BOOLEAN [, ...])"],
+        returns: "BIGINT[]",
+        modes: &[],
+        description: "Per-condition retained counts, summed over rows",
+    },
+    FunctionEntry {
+        name: "retention_rate",
+        kind: FunctionKind::Aggregate,
+        signatures: &["retention_rate(cond1 BOOLEAN, cond2 BOOLEAN [, ...])"],
+        returns: "DOUBLE[]",
+        modes: &[],
+        description: "Retained counts divided by the anchor count",
+    },
+    FunctionEntry {
+        name: "retention_with_validity",
+        kind: FunctionKind::Aggregate,
+        signatures: &["retention_with_validity(cond1 BOOLEAN, cond2 BOOLEAN [, ...])"],
+        returns: "STRUCT(values BOOLEAN[], non_null_counts BIGINT[])",
+        modes: &[],
+        description: "Retention result with each condition's non-NULL row count",
+    },
+    FunctionEntry {
+        name: "retention_by_period",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "retention_by_period(base_ts TIMESTAMP, timestamp TIMESTAMP, period INTERVAL, n BIGINT)",
+        ],
+        returns: "BOOLEAN[]",
+        modes: &[],
+        description: "Whether each of n periods after base_ts had activity, as retention",
+    },
+    FunctionEntry {
+        name: "retention_state",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "retention_state(cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "retention_state(conditions BOOLEAN[])",
+        ],
+        returns: "BLOB",
+        modes: &[],
+        description: "Exported retention state, to merge and finalize later",
+    },
+    FunctionEntry {
+        name: "window_funnel",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "window_funnel(window INTERVAL, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel(window INTERVAL, mode VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel(window INTERVAL, mode VARCHAR, dedup_within INTERVAL, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel(window INTERVAL, mode VARCHAR, min_gaps INTERVAL[], timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel(window INTERVAL, timestamp TIMESTAMP, seq BIGINT, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel(window INTERVAL, mode VARCHAR, timestamp TIMESTAMP, seq BIGINT, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "INTEGER",
+        modes: FUNNEL_MODES,
+        description: "Furthest funnel step reached within the window",
+    },
+    FunctionEntry {
+        name: "window_funnel_mask",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "window_funnel_mask(window INTERVAL, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel_mask(window INTERVAL, mode VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "UINTEGER",
+        modes: FUNNEL_MODES,
+        description: "Bitmask of the funnel steps reached on the best chain",
+    },
+    FunctionEntry {
+        name: "window_funnel_flags",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "window_funnel_flags(window INTERVAL, timestamp TIMESTAMP, mask INTEGER, num_steps INTEGER)",
+            "window_funnel_flags(window INTERVAL, timestamp TIMESTAMP, mask UINTEGER, num_steps INTEGER)",
+        ],
+        returns: "INTEGER",
+        modes: &[],
+        description: "Funnel step over a per-event condition bitmask",
+    },
+    FunctionEntry {
+        name: "window_funnel_entry_ts",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "window_funnel_entry_ts(window INTERVAL, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel_entry_ts(window INTERVAL, mode VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "TIMESTAMP",
+        modes: FUNNEL_MODES,
+        description: "Entry event timestamp of the best funnel chain",
+    },
+    FunctionEntry {
+        name: "window_funnel_by",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "window_funnel_by(window INTERVAL, timestamp TIMESTAMP, dim VARCHAR, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel_by(window INTERVAL, mode VARCHAR, timestamp TIMESTAMP, dim VARCHAR, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "STRUCT(step INTEGER, entry_dim VARCHAR)",
+        modes: FUNNEL_MODES,
+        description: "Funnel step and the dimension value at the entry event",
+    },
+    FunctionEntry {
+        name: "window_funnel_debug",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "window_funnel_debug(window INTERVAL, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel_debug(window INTERVAL, mode VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "STRUCT(step INTEGER, events_collected BIGINT, events_after_filter BIGINT, chains_tried BIGINT, best_entry_ts TIMESTAMP, mode_flags INTEGER, mode VARCHAR)",
+        modes: FUNNEL_MODES,
+        description: "Funnel step with the statistics of its search, for debugging",
+    },
+    FunctionEntry {
+        name: "window_funnel_state",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "window_funnel_state(window INTERVAL, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "window_funnel_state(window INTERVAL, mode VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "BLOB",
+        modes: FUNNEL_MODES,
+        description: "Exported window_funnel state, to merge and finalize later",
+    },
+    FunctionEntry {
+        name: "conversion_rate",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "conversion_rate(window INTERVAL, timestamp TIMESTAMP, entry BOOLEAN, target BOOLEAN)",
+        ],
+        returns: "DOUBLE",
+        modes: &[],
+        description: "1.0 / 0.0 / NULL conversion of a two-step funnel",
+    },
+    FunctionEntry {
+        name: "time_to_convert",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "time_to_convert(window INTERVAL, timestamp TIMESTAMP, entry BOOLEAN, target BOOLEAN)",
+        ],
+        returns: "INTERVAL",
+        modes: &[],
+        description: "Entry-to-target time of a two-step funnel, NULL if not converted",
+    },
+    FunctionEntry {
+        name: "funnel_step",
+        kind: FunctionKind::Window,
+        signatures: &[
+            "funnel_step(window INTERVAL, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "INTEGER",
+        modes: &[],
+        description: "Funnel step the current row advanced a chain to, 0 if none",
+    },
+    FunctionEntry {
+        name: "sequence_match",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_match(pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "sequence_match(pattern VARCHAR, timestamp TIMESTAMP, seq BIGINT, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "sequence_match(pattern VARCHAR, order VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "BOOLEAN",
+        modes: &[],
+        description: "Whether the event sequence matches the pattern",
+    },
+    FunctionEntry {
+        name: "sequence_match_debug",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_match_debug(pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "STRUCT(matched BOOLEAN, events_collected BIGINT, events_stored BIGINT, execution_path VARCHAR, states_expanded BIGINT, sort_us BIGINT, match_us BIGINT)",
+        modes: &[],
+        description: "Pattern match with the statistics of its finalize, for debugging",
+    },
+    FunctionEntry {
+        name: "sequence_count",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_count(pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "sequence_count(pattern VARCHAR, timestamp TIMESTAMP, seq BIGINT, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Number of non-overlapping pattern matches",
+    },
+    FunctionEntry {
+        name: "sequence_count_within",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_count_within(window INTERVAL, pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Number of non-overlapping pattern matches spanning at most the window",
+    },
+    FunctionEntry {
+        name: "sequence_count_by",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_count_by(bucket INTERVAL, pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "LIST(STRUCT(bucket_start TIMESTAMP, count BIGINT))",
+        modes: &[],
+        description: "Number of non-overlapping pattern matches per time bucket of their first event",
+    },
+    FunctionEntry {
+        name: "sequence_match_any",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_match_any(patterns VARCHAR[], timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "INTEGER",
+        modes: &[],
+        description: "1-based index of the first pattern in the list that matches",
+    },
+    FunctionEntry {
+        name: "sequence_match_events",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_match_events(pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+            "sequence_match_events(pattern VARCHAR, labels VARCHAR[], timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "LIST(TIMESTAMP) | LIST(STRUCT(label VARCHAR, ts TIMESTAMP))",
+        modes: &[],
+        description: "Timestamps of the matched condition steps, labeled when labels are given",
+    },
+    FunctionEntry {
+        name: "sequence_match_spans",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_match_spans(pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "LIST(STRUCT(start_ts TIMESTAMP, end_ts TIMESTAMP))",
+        modes: &[],
+        description: "Start and end timestamps of each counted match",
+    },
+    FunctionEntry {
+        name: "sequence_match_values",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_match_values(pattern VARCHAR, timestamp TIMESTAMP, value VARCHAR, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "LIST(STRUCT(ts TIMESTAMP, value VARCHAR))",
+        modes: &[],
+        description: "Timestamps and values of the matched condition steps",
+    },
+    FunctionEntry {
+        name: "sequence_extract",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_extract(pattern VARCHAR, timestamp TIMESTAMP, value VARCHAR, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "LIST(STRUCT(name VARCHAR, value VARCHAR))",
+        modes: &[],
+        description: "Values of the events matched by the named (?N:name) steps",
+    },
+    FunctionEntry {
+        name: "sequence_duration",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_duration(pattern VARCHAR, timestamp TIMESTAMP, cond1 BOOLEAN, cond2 BOOLEAN [, ...])",
+        ],
+        returns: "INTERVAL",
+        modes: &[],
+        description: "Time between the first and last matched step of the first match",
+    },
+    FunctionEntry {
+        name: "sequence_next_node",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_next_node(direction VARCHAR, base VARCHAR, timestamp TIMESTAMP, event_column T, base_condition BOOLEAN, event1 BOOLEAN [, ...])",
+            "sequence_next_node(direction VARCHAR, base VARCHAR, window INTERVAL, timestamp TIMESTAMP, event_column T, base_condition BOOLEAN, event1 BOOLEAN [, ...])",
+        ],
+        returns: "T",
+        modes: DIRECTIONS_AND_BASES,
+        description: "Value of the event after (or before) the matched chain",
+    },
+    FunctionEntry {
+        name: "sequence_next_node_hist",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "sequence_next_node_hist(direction VARCHAR, timestamp TIMESTAMP, event_column T, base_condition BOOLEAN, event1 BOOLEAN [, ...])",
+            "sequence_next_node_hist(direction VARCHAR, window INTERVAL, timestamp TIMESTAMP, event_column T, base_condition BOOLEAN, event1 BOOLEAN [, ...])",
+        ],
+        returns: "LIST(STRUCT(key T, value BIGINT))",
+        modes: DIRECTIONS,
+        description: "Count of each next event value over all matches",
+    },
+    FunctionEntry {
+        name: "sessionize_approx",
+        kind: FunctionKind::Aggregate,
+        signatures: &["sessionize_approx(timestamp TIMESTAMP, gap INTERVAL, slack INTERVAL)"],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Session count over input with bounded disorder",
+    },
+    FunctionEntry {
+        name: "sessionize_state",
+        kind: FunctionKind::Aggregate,
+        signatures: &["sessionize_state(timestamp TIMESTAMP, gap INTERVAL)"],
+        returns: "BLOB",
+        modes: &[],
+        description: "Exported session state, to merge and finalize later",
+    },
+    FunctionEntry {
+        name: "first_event_after_session_start",
+        kind: FunctionKind::Window,
+        signatures: &[
+            "first_event_after_session_start(timestamp TIMESTAMP, value VARCHAR, gap INTERVAL)",
+        ],
+        returns: "VARCHAR",
+        modes: &[],
+        description: "Value of the first event of the current row's session",
+    },
+    FunctionEntry {
+        name: "last_event_before_session_end",
+        kind: FunctionKind::Window,
+        signatures: &[
+            "last_event_before_session_end(timestamp TIMESTAMP, value VARCHAR, gap INTERVAL)",
+        ],
+        returns: "VARCHAR",
+        modes: &[],
+        description: "Value of the last event of the current row's session",
+    },
+    FunctionEntry {
+        name: "session_number_of_event",
+        kind: FunctionKind::Window,
+        signatures: &["session_number_of_event(timestamp TIMESTAMP, gap INTERVAL)"],
+        returns: "BIGINT",
+        modes: &[],
+        description: "1-based position of the current row in its session",
+    },
+    FunctionEntry {
+        name: "events_in_session",
+        kind: FunctionKind::Window,
+        signatures: &["events_in_session(timestamp TIMESTAMP, gap INTERVAL)"],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Events from the current row to the end of its session",
+    },
+    FunctionEntry {
+        name: "event_gap_stats",
+        kind: FunctionKind::Aggregate,
+        signatures: &["event_gap_stats(timestamp TIMESTAMP)"],
+        returns: "STRUCT(min_gap_us BIGINT, max_gap_us BIGINT, median_gap_us BIGINT, count BIGINT)",
+        modes: &[],
+        description: "Minimum, maximum and approximate median gap between events",
+    },
+    FunctionEntry {
+        name: "group_array_sorted_by_ts",
+        kind: FunctionKind::Aggregate,
+        signatures: &["group_array_sorted_by_ts(value T, timestamp TIMESTAMP, max_n BIGINT)"],
+        returns: "LIST(T)",
+        modes: &[],
+        description: "Values of the earliest max_n events in timestamp order",
+    },
+    FunctionEntry {
+        name: "transition_matrix",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "transition_matrix(timestamp TIMESTAMP, state T)",
+            "transition_matrix(timestamp TIMESTAMP, state T, n_limit BIGINT)",
+        ],
+        returns: "LIST(STRUCT(from_state T, to_state T, count BIGINT))",
+        modes: &[],
+        description: "Counts of transitions between consecutive states in timestamp order",
+    },
+    FunctionEntry {
+        name: "markov_attribution",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "markov_attribution(timestamp TIMESTAMP, channel VARCHAR, converted BOOLEAN)",
+            "markov_attribution(path P, timestamp TIMESTAMP, channel VARCHAR, converted BOOLEAN)",
+        ],
+        returns: "LIST(STRUCT(channel VARCHAR, removal_effect DOUBLE, conversions DOUBLE))",
+        modes: &[],
+        description: "Markov-chain removal-effect attribution of conversions to channels",
+    },
+    FunctionEntry {
+        name: "path_count_sketch",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "path_count_sketch(timestamp TIMESTAMP, value VARCHAR, depth BIGINT)",
+            "path_count_sketch(path P, timestamp TIMESTAMP, value VARCHAR, depth BIGINT)",
+        ],
+        returns: "BIGINT",
+        modes: &[],
+        description: "Approximate number of distinct paths over each path's first depth events",
+    },
+    FunctionEntry {
+        name: "first_touch",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "first_touch(timestamp TIMESTAMP, channel T, converted BOOLEAN)",
+            "first_touch(timestamp TIMESTAMP, channel T, converted BOOLEAN, lookback INTERVAL)",
+        ],
+        returns: "T",
+        modes: &[],
+        description: "Channel of the first touchpoint at or before the first conversion",
+    },
+    FunctionEntry {
+        name: "last_touch",
+        kind: FunctionKind::Aggregate,
+        signatures: &[
+            "last_touch(timestamp TIMESTAMP, channel T, converted BOOLEAN)",
+            "last_touch(timestamp TIMESTAMP, channel T, converted BOOLEAN, lookback INTERVAL)",
+        ],
+        returns: "T",
+        modes: &[],
+        description: "Channel of the last touchpoint at or before the first conversion",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence_next_node::SequenceNextNodeState;
+    use crate::window_funnel::FunnelMode;
+
+    #[test]
+    fn test_version_starts_with_crate_version() {
+        assert!(version().starts_with(VERSION));
+        assert!(!VERSION.is_empty());
+    }
+
+    #[test]
+    fn test_names_unique_and_signatures_named() {
+        for (i, entry) in FUNCTIONS.iter().enumerate() {
+            assert!(
+                FUNCTIONS[..i].iter().all(|other| other.name != entry.name),
+                "{} listed twice",
+                entry.name
+            );
+            assert!(!entry.signatures.is_empty());
+            for signature in entry.signatures {
+                assert!(signature.starts_with(&format!("{}(", entry.name)));
+                assert!(signature.ends_with(')'));
+            }
+        }
+    }
+
+    #[test]
+    fn test_funnel_modes_parse() {
+        assert_eq!(
+            &FUNNEL_MODES[..FunnelMode::MODE_NAMES.len()],
+            FunnelMode::MODE_NAMES
+        );
+        for mode in FUNNEL_MODES {
+            let mode = mode.replace('K', "1");
+            assert!(FunnelMode::parse_mode_str(&mode).is_some(), "{mode}");
+        }
+    }
+
+    #[test]
+    fn test_directions_and_bases_parse() {
+        let (directions, bases) = DIRECTIONS_AND_BASES.split_at(DIRECTIONS.len());
+        assert_eq!(directions, DIRECTIONS);
+        let (directions, options) =
+            directions.split_at(directions.len() - SequenceNextNodeState::DIRECTION_OPTIONS.len());
+        assert_eq!(options, SequenceNextNodeState::DIRECTION_OPTIONS);
+        for direction in directions {
+            assert!(SequenceNextNodeState::parse_direction(direction).is_some());
+        }
+        for option in options {
+            let spec = format!("forward, {}", option.replace('D', "30m"));
+            assert!(SequenceNextNodeState::parse_direction_options(&spec).is_ok());
+        }
+        for base in bases {
+            assert!(SequenceNextNodeState::parse_base(base).is_some());
+        }
+    }
+}
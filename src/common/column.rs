@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Chunk-at-a-time conversion of input columns into [`Event`]s.
+//!
+//! The FFI update callbacks receive `DuckDB` data chunks of up to 2048 rows.
+//! Reading them row by row — one validity check and one type dispatch per
+//! condition per row — costs more than the aggregation itself on large scans.
+//! The helpers here work a column at a time instead: [`pack_column`] ORs one
+//! condition column into the per-row bitmasks in a single tight loop with no
+//! per-row branching, which the compiler auto-vectorizes, and [`ChunkEvents`]
+//! pairs the masks with the timestamp column and, for the overloads taking
+//! one, the sequence-number column.
+//!
+//! Validity follows `DuckDB`'s layout: one bit per row in little-endian `u64`
+//! words, bit set when the row is valid. An empty slice means every row is
+//! valid, which is what `duckdb_vector_get_validity` signals with a null
+//! pointer.
+
+use super::event::Event;
+use std::ops::Range;
+
+/// Returns whether `row` is valid under `validity` (empty means all valid).
+#[must_use]
+pub fn row_is_valid(validity: &[u64], row: usize) -> bool {
+    validity.is_empty() || (validity[row / 64] >> (row % 64)) & 1 == 1
+}
+
+/// Sets bit `bit` of `masks[row]` for every valid row whose value is non-zero.
+///
+/// `values` and `masks` must have the same length; `NULL` rows leave the bit
+/// clear, matching the `NULL`-is-`false` condition coercion.
+pub fn pack_column<T: Copy + Default + PartialEq>(
+    masks: &mut [u64],
+    bit: usize,
+    values: &[T],
+    validity: &[u64],
+) {
+    debug_assert_eq!(masks.len(), values.len());
+    let zero = T::default();
+    if validity.is_empty() {
+        for (mask, &value) in masks.iter_mut().zip(values) {
+            *mask |= u64::from(value != zero) << bit;
+        }
+    } else {
+        for (row, (mask, &value)) in masks.iter_mut().zip(values).enumerate() {
+            let valid = (validity[row / 64] >> (row % 64)) & 1;
+            *mask |= (u64::from(value != zero) & valid) << bit;
+        }
+    }
+}
+
+/// The events of one data chunk: a timestamp and a packed condition mask per
+/// row, plus the timestamp column's validity and optionally a sequence number
+/// per row.
+#[derive(Debug, Clone)]
+pub struct ChunkEvents {
+    events: Vec<Event>,
+    validity: Vec<u64>,
+    seqs: Vec<i64>,
+}
+
+impl ChunkEvents {
+    /// Pairs `timestamps` with the condition `masks` built by [`pack_column`].
+    ///
+    /// `validity` is the timestamp column's validity; rows with a `NULL`
+    /// timestamp are skipped by [`get`](Self::get) and [`rows`](Self::rows).
+    #[must_use]
+    pub fn new(timestamps: &[i64], validity: &[u64], masks: &[u64]) -> Self {
+        debug_assert_eq!(timestamps.len(), masks.len());
+        Self {
+            events: timestamps
+                .iter()
+                .zip(masks)
+                .map(|(&timestamp, &mask)| Event::new(timestamp, mask))
+                .collect(),
+            validity: validity.to_vec(),
+            seqs: Vec::new(),
+        }
+    }
+
+    /// Adds the sequence-number column: `values` with its `validity`. A
+    /// `NULL` sequence number reads as `i64::MIN`, sorting before the other
+    /// events of its timestamp.
+    #[must_use]
+    pub fn with_seqs(mut self, values: &[i64], validity: &[u64]) -> Self {
+        debug_assert_eq!(values.len(), self.events.len());
+        self.seqs = values
+            .iter()
+            .enumerate()
+            .map(|(row, &seq)| {
+                if row_is_valid(validity, row) {
+                    seq
+                } else {
+                    i64::MIN
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// Returns the event of `row`, or `None` if its timestamp is `NULL`.
+    #[must_use]
+    pub fn get(&self, row: usize) -> Option<Event> {
+        row_is_valid(&self.validity, row).then(|| self.events[row])
+    }
+
+    /// Returns the sequence number of `row`. Requires
+    /// [`with_seqs`](Self::with_seqs).
+    #[must_use]
+    pub fn seq(&self, row: usize) -> i64 {
+        self.seqs[row]
+    }
+
+    /// Returns the event of `row` and its sequence number, or `None` if its
+    /// timestamp is `NULL`. Requires [`with_seqs`](Self::with_seqs).
+    #[must_use]
+    pub fn get_with_seq(&self, row: usize) -> Option<(Event, i64)> {
+        self.get(row).map(|event| (event, self.seq(row)))
+    }
+
+    /// Returns the events of `range` with a non-`NULL` timestamp, in row order.
+    pub fn rows(&self, range: Range<usize>) -> impl Iterator<Item = Event> + '_ {
+        let start = range.start;
+        self.events[range]
+            .iter()
+            .enumerate()
+            .filter(move |&(offset, _)| row_is_valid(&self.validity, start + offset))
+            .map(|(_, &event)| event)
+    }
+
+    /// Returns the events of `range` with a non-`NULL` timestamp and their
+    /// sequence numbers, in row order. Requires [`with_seqs`](Self::with_seqs).
+    pub fn rows_with_seq(&self, range: Range<usize>) -> impl Iterator<Item = (Event, i64)> + '_ {
+        range.filter_map(|row| self.get_with_seq(row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_is_valid() {
+        assert!(row_is_valid(&[], 5000));
+        let validity = [0b101, 1 << 1];
+        assert!(row_is_valid(&validity, 0));
+        assert!(!row_is_valid(&validity, 1));
+        assert!(row_is_valid(&validity, 2));
+        assert!(!row_is_valid(&validity, 64));
+        assert!(row_is_valid(&validity, 65));
+    }
+
+    #[test]
+    fn test_pack_column_sets_bit_for_non_zero_values() {
+        let mut masks = [0u64; 4];
+        pack_column(&mut masks, 0, &[1u8, 0, 1, 0], &[]);
+        pack_column(&mut masks, 63, &[0i32, 7, -1, 0], &[]);
+        assert_eq!(masks, [1, 1 << 63, 1 | 1 << 63, 0]);
+    }
+
+    #[test]
+    fn test_pack_column_clears_null_rows() {
+        // Row 1 is NULL but holds a stale non-zero byte.
+        let mut masks = [0u64; 3];
+        pack_column(&mut masks, 2, &[1u8, 1, 1], &[0b101]);
+        assert_eq!(masks, [4, 0, 4]);
+    }
+
+    #[test]
+    fn test_pack_column_matches_row_by_row_packing() {
+        let rows = 130;
+        let bools: Vec<u8> = (0..rows).map(|r| u8::from(r % 3 == 0)).collect();
+        let ints: Vec<i32> = (0..rows).map(|r| (r % 5) as i32 - 2).collect();
+        let validity = [u64::MAX ^ 0b1000, 0xF0F0_F0F0_F0F0_F0F0, 0b11];
+        let mut masks = vec![0u64; rows];
+        pack_column(&mut masks, 0, &bools, &validity);
+        pack_column(&mut masks, 1, &ints, &[]);
+        for row in 0..rows {
+            let expected = u64::from(row_is_valid(&validity, row) && bools[row] != 0)
+                | u64::from(ints[row] != 0) << 1;
+            assert_eq!(masks[row], expected, "row {row}");
+        }
+    }
+
+    #[test]
+    fn test_chunk_events_skip_null_timestamps() {
+        let events = ChunkEvents::new(&[10, 20, 30, 40], &[0b1011], &[1, 2, 3, 0]);
+        assert_eq!(events.get(0), Some(Event::new(10, 1)));
+        assert_eq!(events.get(2), None);
+        let rows: Vec<Event> = events.rows(1..4).collect();
+        assert_eq!(rows, vec![Event::new(20, 2), Event::new(40, 0)]);
+        assert_eq!(events.rows(2..3).count(), 0);
+    }
+
+    #[test]
+    fn test_chunk_events_with_seqs() {
+        let events =
+            ChunkEvents::new(&[10, 20, 30], &[0b011], &[1, 2, 4]).with_seqs(&[7, 8, 9], &[0b101]);
+        assert_eq!(events.get_with_seq(0), Some((Event::new(10, 1), 7)));
+        // NULL sequence number sorts first; NULL timestamp skips the row.
+        assert_eq!(events.get_with_seq(1), Some((Event::new(20, 2), i64::MIN)));
+        assert_eq!(events.seq(2), 9);
+        assert_eq!(events.get_with_seq(2), None);
+        let rows: Vec<(Event, i64)> = events.rows_with_seq(0..3).collect();
+        assert_eq!(
+            rows,
+            vec![(Event::new(10, 1), 7), (Event::new(20, 2), i64::MIN)]
+        );
+    }
+
+    #[test]
+    fn test_chunk_events_all_valid() {
+        let events = ChunkEvents::new(&[5, 6], &[], &[0, 1]);
+        assert_eq!(events.rows(0..2).count(), 2);
+        assert_eq!(events.get(1), Some(Event::new(6, 1)));
+    }
+}
@@ -9,41 +9,52 @@
 //!
 //! # Bitmask Representation
 //!
-//! Conditions are stored as a `u32` bitmask rather than `Vec<bool>`.
+//! Conditions are stored as a `u64` bitmask rather than `Vec<bool>`.
 //! This eliminates per-event heap allocation (the dominant cost in event
 //! collection) and enables single-instruction condition checks via bitwise
-//! AND. The `DuckDB` function set registrations support up to 32 boolean
-//! conditions, matching `ClickHouse`'s limit.
+//! AND. The `DuckDB` function set registrations support up to 64 boolean
+//! conditions, twice `ClickHouse`'s limit of 32, for long funnels.
 //!
-//! Memory layout: `Event` is 16 bytes (i64 + u32 + 4 bytes padding) with
-//! `Copy` semantics, compared to the previous 32 bytes + heap allocation
-//! for `Vec<bool>`.
+//! Memory layout: `Event` is 16 bytes (i64 + u64) with `Copy` semantics,
+//! compared to the previous 32 bytes + heap allocation for `Vec<bool>`.
+//! Widening the mask from `u32` to `u64` only filled what used to be
+//! padding, so events with 32 or fewer conditions cost nothing extra.
+//!
+//! # Compression
+//!
+//! Per-account funnels can collect tens of millions of events in one group.
+//! [`WindowFunnelState`](crate::window_funnel::WindowFunnelState) and
+//! [`SequenceState`](crate::sequence::SequenceState) move every
+//! [`COMPRESSION_THRESHOLD`] buffered events into a [`CompressedEventBuffer`]
+//! (delta-of-delta timestamps, run-length encoded masks), typically 2-3 bytes
+//! per event instead of 16, and decompress them once in finalize.
+
+use std::cmp::Ordering;
 
 /// Maximum number of boolean conditions supported by event-collecting functions.
-pub const MAX_EVENT_CONDITIONS: usize = 32;
+pub const MAX_EVENT_CONDITIONS: usize = 64;
 
 /// A single timestamped event with associated boolean conditions.
 ///
 /// Used by `window_funnel`, `sequence_match`, and `sequence_count` to collect
 /// events during the `update` phase, then process them during `finalize`.
 ///
-/// Conditions are packed into a `u32` bitmask where bit `i` represents
-/// condition `i`. This supports up to 32 conditions, matching `ClickHouse`'s
-/// limit.
+/// Conditions are packed into a `u64` bitmask where bit `i` represents
+/// condition `i`. This supports up to 64 conditions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct Event {
     /// Timestamp in microseconds since Unix epoch.
     pub timestamp_us: i64,
     /// Bitmask of boolean conditions. Bit `i` is set if condition `i` was
-    /// satisfied at this timestamp. Supports up to 32 conditions (bits 0-31).
-    pub conditions: u32,
+    /// satisfied at this timestamp. Supports up to 64 conditions (bits 0-63).
+    pub conditions: u64,
 }
 
 impl Event {
     /// Creates a new event with the given timestamp and condition bitmask.
     #[must_use]
-    pub const fn new(timestamp_us: i64, conditions: u32) -> Self {
+    pub const fn new(timestamp_us: i64, conditions: u64) -> Self {
         Self {
             timestamp_us,
             conditions,
@@ -52,11 +63,11 @@ impl Event {
 
     /// Creates an event from a slice of boolean conditions.
     ///
-    /// Packs the booleans into a `u32` bitmask. Conditions beyond index 31
-    /// are silently ignored (matching the `DuckDB` function set limit of 32).
+    /// Packs the booleans into a `u64` bitmask. Conditions beyond index 63
+    /// are silently ignored (matching the `DuckDB` function set limit of 64).
     #[must_use]
     pub fn from_bools(timestamp_us: i64, conditions: &[bool]) -> Self {
-        let mut bitmask: u32 = 0;
+        let mut bitmask: u64 = 0;
         for (i, &cond) in conditions.iter().enumerate().take(MAX_EVENT_CONDITIONS) {
             if cond {
                 bitmask |= 1 << i;
@@ -70,7 +81,7 @@ impl Event {
 
     /// Returns true if the condition at the given index is satisfied.
     ///
-    /// Returns false if `idx >= 32` (out of bitmask range). Single bitwise
+    /// Returns false if `idx >= 64` (out of bitmask range). Single bitwise
     /// AND operation — branchless on most architectures.
     #[must_use]
     #[inline]
@@ -121,6 +132,44 @@ pub fn sort_events(events: &mut [Event]) {
     events.sort_unstable_by_key(|e| e.timestamp_us);
 }
 
+/// Sorts `items` in their `Ord` order (unstable), skipping the sort when
+/// they are already in order.
+///
+/// The presorted check of [`sort_events`] for states that keep entries other
+/// than [`Event`], whose order includes a tie-breaking value (e.g.
+/// `group_array_sorted_by_ts`).
+pub fn sort_presorted<T: Ord>(items: &mut [T]) {
+    if items.windows(2).all(|w| w[0] <= w[1]) {
+        return;
+    }
+    items.sort_unstable();
+}
+
+/// Sorts events by timestamp, breaking ties by sequence number, and applies
+/// the same permutation to `seqs`, the sequence number of each event (same
+/// index).
+///
+/// Used by the overloads taking a sequence-number column, where event streams
+/// carry many events per timestamp. The numbers are kept in a vector parallel
+/// to the events rather than in [`Event`], so events stay 16 bytes when no
+/// sequence column is given. Like [`sort_events`], the sort is skipped when
+/// the input is already in order.
+pub fn sort_events_by_seq(events: &mut [Event], seqs: &mut [i64]) {
+    debug_assert_eq!(events.len(), seqs.len());
+    let key = |i: usize| (events[i].timestamp_us, seqs[i]);
+    if (1..events.len()).all(|i| key(i - 1) <= key(i)) {
+        return;
+    }
+    let mut keyed: Vec<(Event, i64)> = events.iter().copied().zip(seqs.iter().copied()).collect();
+    keyed.sort_unstable_by_key(|&(event, seq)| (event.timestamp_us, seq));
+    for ((event, seq), (sorted_event, sorted_seq)) in
+        events.iter_mut().zip(seqs.iter_mut()).zip(keyed)
+    {
+        *event = sorted_event;
+        *seq = sorted_seq;
+    }
+}
+
 /// Merges two sorted event slices into a single sorted `Vec`.
 ///
 /// Both input slices must be sorted by timestamp. The result preserves the
@@ -130,26 +179,326 @@ pub fn sort_events(events: &mut [Event]) {
 /// the merge (no `clone()` needed).
 #[must_use]
 pub fn merge_sorted_events(a: &[Event], b: &[Event]) -> Vec<Event> {
+    merge_sorted_by(a, b, |x, y| x.timestamp_us.cmp(&y.timestamp_us))
+}
+
+/// Merges two slices sorted under `compare` into a single sorted `Vec`.
+///
+/// The generic form of [`merge_sorted_events`], shared with states that keep
+/// sorted entries other than [`Event`] (e.g. `group_array_sorted_by_ts`).
+/// Elements comparing equal are taken from `a` first, so the merge is stable.
+#[must_use]
+pub fn merge_sorted_by<T: Clone>(
+    a: &[T],
+    b: &[T],
+    compare: impl FnMut(&T, &T) -> Ordering,
+) -> Vec<T> {
     let mut result = Vec::with_capacity(a.len() + b.len());
+    merge_sorted_into(a, b, &mut result, compare);
+    result
+}
+
+/// Appends the merge of two slices sorted under `compare` to `out`, like
+/// [`merge_sorted_by`] without allocating a `Vec` per merge. Used to merge
+/// many runs pass by pass into one buffer.
+pub fn merge_sorted_into<T: Clone>(
+    a: &[T],
+    b: &[T],
+    out: &mut Vec<T>,
+    mut compare: impl FnMut(&T, &T) -> Ordering,
+) {
     let (mut i, mut j) = (0, 0);
     while i < a.len() && j < b.len() {
-        if a[i].timestamp_us <= b[j].timestamp_us {
-            result.push(a[i]);
-            i += 1;
-        } else {
-            result.push(b[j]);
+        if compare(&a[i], &b[j]) == Ordering::Greater {
+            out.push(b[j].clone());
             j += 1;
+        } else {
+            out.push(a[i].clone());
+            i += 1;
         }
     }
-    result.extend_from_slice(&a[i..]);
-    result.extend_from_slice(&b[j..]);
-    result
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+}
+
+/// Number of buffered events at which a state compresses them into a
+/// [`CompressedEventBuffer`] block (1 MiB of raw events).
+///
+/// Groups below this size never pay for compression.
+pub const COMPRESSION_THRESHOLD: usize = 1 << 16;
+
+/// Lossless compressed event storage for very large groups.
+///
+/// Events are stored in independently encoded blocks, in insertion order:
+///
+/// - Timestamps as zigzag varints of their delta-of-delta. Regularly spaced or
+///   repeated timestamps, the bulk of event streams, take one byte each.
+/// - Condition masks as runs of `(mask, count)` varint pairs. Funnel and
+///   pattern conditions are few and repeat, so a run usually takes two bytes.
+///
+/// Unlike [`RunLengthBuffer`](super::event_run::RunLengthBuffer), every event
+/// is kept with its exact timestamp and position, so decompressing yields the
+/// original sequence and results are unchanged. States compress their event
+/// buffer every [`COMPRESSION_THRESHOLD`] events and decompress it on
+/// finalize.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompressedEventBuffer {
+    blocks: Vec<CompressedBlock>,
+    len: usize,
+}
+
+/// One encoded run of events; see [`CompressedEventBuffer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CompressedBlock {
+    len: usize,
+    timestamps: Vec<u8>,
+    masks: Vec<u8>,
+}
+
+impl CompressedEventBuffer {
+    /// Creates an empty buffer. Does not allocate.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of events stored.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no events are stored.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the size of the encoded events in bytes.
+    #[must_use]
+    pub fn encoded_bytes(&self) -> usize {
+        self.blocks
+            .iter()
+            .map(|block| block.timestamps.len() + block.masks.len())
+            .sum()
+    }
+
+    /// Appends `events` as a new block, after the events already stored.
+    pub fn push_block(&mut self, events: &[Event]) {
+        if events.is_empty() {
+            return;
+        }
+        let mut timestamps = Vec::with_capacity(events.len());
+        let (mut prev, mut prev_delta) = (0i64, 0i64);
+        for event in events {
+            let delta = event.timestamp_us.wrapping_sub(prev);
+            write_varint(&mut timestamps, zigzag(delta.wrapping_sub(prev_delta)));
+            prev = event.timestamp_us;
+            prev_delta = delta;
+        }
+        let mut masks = Vec::new();
+        for run in events.chunk_by(|a, b| a.conditions == b.conditions) {
+            write_varint(&mut masks, run[0].conditions);
+            write_varint(&mut masks, run.len() as u64);
+        }
+        timestamps.shrink_to_fit();
+        masks.shrink_to_fit();
+        self.len += events.len();
+        self.blocks.push(CompressedBlock {
+            len: events.len(),
+            timestamps,
+            masks,
+        });
+    }
+
+    /// Appends the blocks of `other`, after the events already stored.
+    pub fn extend_from(&mut self, other: &Self) {
+        self.blocks.extend_from_slice(&other.blocks);
+        self.len += other.len;
+    }
+
+    /// Returns the OR of the condition masks of the stored events, read from
+    /// the mask runs without decoding timestamps.
+    #[must_use]
+    pub fn union_conditions(&self) -> u64 {
+        let mut union = 0;
+        for block in &self.blocks {
+            let mut masks = block.masks.as_slice();
+            while !masks.is_empty() {
+                union |= read_varint(&mut masks);
+                read_varint(&mut masks);
+            }
+        }
+        union
+    }
+
+    /// Appends the stored events to `out`, in insertion order.
+    pub fn decompress_into(&self, out: &mut Vec<Event>) {
+        out.reserve(self.len);
+        for block in &self.blocks {
+            let mut timestamps = block.timestamps.as_slice();
+            let mut masks = block.masks.as_slice();
+            let (mut prev, mut prev_delta) = (0i64, 0i64);
+            let mut remaining = block.len;
+            while remaining > 0 {
+                let mask = read_varint(&mut masks);
+                let count = (read_varint(&mut masks) as usize).min(remaining);
+                for _ in 0..count {
+                    prev_delta = prev_delta.wrapping_add(unzigzag(read_varint(&mut timestamps)));
+                    prev = prev.wrapping_add(prev_delta);
+                    out.push(Event::new(prev, mask));
+                }
+                remaining -= count;
+            }
+        }
+    }
+}
+
+/// Maps signed integers to unsigned ones, small magnitudes to small values.
+const fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Inverse of [`zigzag`].
+const fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Appends `value` as a LEB128 varint.
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads a LEB128 varint from the front of `input`, advancing it. Returns 0
+/// on exhausted input, which only a corrupted block can produce.
+fn read_varint(input: &mut &[u8]) -> u64 {
+    let mut value = 0u64;
+    let mut shift = 0;
+    while let Some((&byte, rest)) = input.split_first() {
+        *input = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            break;
+        }
+        shift += 7;
+    }
+    value
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sort_events_by_seq_breaks_timestamp_ties() {
+        let mut events = vec![Event::new(5, 1), Event::new(0, 2), Event::new(5, 4)];
+        let mut seqs = vec![9, 3, 2];
+        sort_events_by_seq(&mut events, &mut seqs);
+        assert_eq!(
+            events,
+            vec![Event::new(0, 2), Event::new(5, 4), Event::new(5, 1)]
+        );
+        assert_eq!(seqs, vec![3, 2, 9]);
+    }
+
+    #[test]
+    fn test_sort_events_by_seq_presorted_and_empty() {
+        let mut events = vec![Event::new(0, 1), Event::new(0, 2), Event::new(1, 4)];
+        let mut seqs = vec![1, 2, 0];
+        sort_events_by_seq(&mut events, &mut seqs);
+        assert_eq!(seqs, vec![1, 2, 0]);
+        sort_events_by_seq(&mut [], &mut []);
+    }
+
+    #[test]
+    fn test_merge_sorted_by_custom_order() {
+        // Compares keys only: equal keys keep `a` before `b`.
+        let a = [(1, "a1"), (3, "a3")];
+        let b = [(1, "b1"), (2, "b2"), (3, "b3")];
+        let merged = merge_sorted_by(&a, &b, |x, y| x.0.cmp(&y.0));
+        assert_eq!(
+            merged,
+            vec![(1, "a1"), (1, "b1"), (2, "b2"), (3, "a3"), (3, "b3")]
+        );
+    }
+
+    fn compression_round_trip(events: &[Event]) -> CompressedEventBuffer {
+        let mut buffer = CompressedEventBuffer::new();
+        for block in events.chunks(1000) {
+            buffer.push_block(block);
+        }
+        let mut decompressed = Vec::new();
+        buffer.decompress_into(&mut decompressed);
+        assert_eq!(decompressed, events);
+        assert_eq!(buffer.len(), events.len());
+        buffer
+    }
+
+    #[test]
+    fn test_compressed_buffer_union_conditions() {
+        let events: Vec<Event> = (0..3000)
+            .map(|i| Event::new(i, if i == 2500 { 1 << 63 } else { 1 << (i % 3) }))
+            .collect();
+        assert_eq!(
+            compression_round_trip(&events).union_conditions(),
+            0b111 | 1 << 63
+        );
+        assert_eq!(CompressedEventBuffer::new().union_conditions(), 0);
+    }
+
+    #[test]
+    fn test_compressed_buffer_round_trip() {
+        let regular: Vec<Event> = (0..5000)
+            .map(|i| Event::new(1_700_000_000_000_000 + i * 1_000_000, 1 << (i / 700 % 3)))
+            .collect();
+        let buffer = compression_round_trip(&regular);
+        // Evenly spaced timestamps and long mask runs: about one byte per event
+        assert!(buffer.encoded_bytes() < regular.len() * 2);
+
+        // Extremes, unsorted input and alternating masks still round-trip
+        let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+        let mut irregular = vec![
+            Event::new(i64::MIN, u64::MAX),
+            Event::new(i64::MAX, 1),
+            Event::new(0, 0),
+        ];
+        irregular.extend((0..3000).map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            Event::new((state >> 20) as i64 - (1 << 43), state >> 58)
+        }));
+        compression_round_trip(&irregular);
+        compression_round_trip(&[]);
+    }
+
+    #[test]
+    fn test_compressed_buffer_extend_keeps_order() {
+        let mut a = CompressedEventBuffer::new();
+        a.push_block(&[Event::new(5, 1), Event::new(3, 2)]);
+        let mut b = CompressedEventBuffer::new();
+        b.push_block(&[Event::new(1, 4)]);
+        a.extend_from(&b);
+        let mut out = vec![Event::new(9, 9)];
+        a.decompress_into(&mut out);
+        assert_eq!(
+            out,
+            vec![
+                Event::new(9, 9),
+                Event::new(5, 1),
+                Event::new(3, 2),
+                Event::new(1, 4)
+            ]
+        );
+    }
+
     #[test]
     fn test_event_creation() {
         let e = Event::from_bools(1_000_000, &[true, false, true]);
@@ -183,19 +532,19 @@ mod tests {
 
     #[test]
     fn test_condition_out_of_range() {
-        let e = Event::new(0, 0xFFFF_FFFF);
-        assert!(e.condition(31));
-        assert!(!e.condition(32)); // Out of u32 bitmask range
+        let e = Event::new(0, u64::MAX);
+        assert!(e.condition(63));
+        assert!(!e.condition(64)); // Out of u64 bitmask range
         assert!(!e.condition(100));
     }
 
     #[test]
-    fn test_from_bools_truncates_at_32() {
-        let conds = vec![true; 40]; // More than 32
+    fn test_from_bools_truncates_at_64() {
+        let conds = vec![true; 70]; // More than 64
         let e = Event::from_bools(0, &conds);
-        // Only first 32 should be set
-        assert!(e.condition(31));
-        assert!(!e.condition(32));
+        // Only first 64 should be set
+        assert!(e.condition(63));
+        assert!(!e.condition(64));
     }
 
     #[test]
@@ -316,18 +665,18 @@ mod tests {
 
     #[test]
     fn test_event_size() {
-        // Event should be 16 bytes: i64 (8) + u32 (4) + 4 padding
+        // Event should be 16 bytes: i64 (8) + u64 (8)
         assert_eq!(std::mem::size_of::<Event>(), 16);
     }
 
     // --- Session 3: Mutation-killing boundary tests ---
 
     #[test]
-    fn test_condition_boundary_idx_31_vs_32() {
-        // Kills mutant: replace `idx < 32` with `idx <= 32` in condition().
-        let e = Event::new(0, 0xFFFF_FFFF); // all 32 bits set
-        assert!(e.condition(31)); // bit 31 is valid
-        assert!(!e.condition(32)); // bit 32 is out of range
+    fn test_condition_boundary_idx_63_vs_64() {
+        // Kills mutant: replace `idx < 64` with `idx <= 64` in condition().
+        let e = Event::new(0, u64::MAX); // all 64 bits set
+        assert!(e.condition(63)); // bit 63 is valid
+        assert!(!e.condition(64)); // bit 64 is out of range
     }
 
     #[test]
@@ -349,6 +698,7 @@ mod tests {
         // Kills mutant: replace `!= 0` with `== 0` in has_any_condition().
         assert!(Event::new(0, 1).has_any_condition()); // only bit 0
         assert!(Event::new(0, 1 << 31).has_any_condition()); // only bit 31
+        assert!(Event::new(0, 1 << 63).has_any_condition()); // only bit 63
     }
 
     #[test]
@@ -453,18 +803,18 @@ mod tests {
         assert_eq!(merged[1].conditions, 0b10); // b's element second
     }
 
-    // --- 32-condition support tests ---
+    // --- 64-condition support tests ---
 
     #[test]
-    fn test_event_size_unchanged_with_u32() {
-        // Event stays at 16 bytes: i64 (8) + u32 (4) + 4 padding = 16
+    fn test_event_size_unchanged_with_u64() {
+        // Event stays at 16 bytes: the u64 mask fills the old u32 padding
         assert_eq!(std::mem::size_of::<Event>(), 16);
     }
 
     #[test]
     fn test_condition_9_through_31() {
         // Conditions beyond the old u8 limit should work
-        let mut bitmask: u32 = 0;
+        let mut bitmask: u64 = 0;
         bitmask |= 1 << 8; // condition 9 (0-indexed 8)
         bitmask |= 1 << 15; // condition 16
         bitmask |= 1 << 31; // condition 32
@@ -494,23 +844,36 @@ mod tests {
     }
 
     #[test]
-    fn test_condition_all_32_bits_set() {
-        let e = Event::new(0, 0xFFFF_FFFF);
-        for i in 0..32 {
+    fn test_condition_all_64_bits_set() {
+        let e = Event::new(0, u64::MAX);
+        for i in 0..64 {
             assert!(e.condition(i), "condition({i}) should be true");
         }
-        assert!(!e.condition(32));
+        assert!(!e.condition(64));
     }
 
     #[test]
-    fn test_from_bools_boundary_at_32() {
-        // 33 conditions: first 32 accepted, index 32 ignored
-        let mut conds = vec![false; 33];
-        conds[31] = true;
-        conds[32] = true; // beyond limit
+    fn test_from_bools_48_conditions() {
+        // Conditions beyond the old u32 limit should work
+        let mut conds = vec![false; 48];
+        conds[32] = true;
+        conds[47] = true;
         let e = Event::from_bools(0, &conds);
-        assert!(e.condition(31));
-        assert!(!e.condition(32));
+        assert!(e.condition(32));
+        assert!(e.condition(47));
+        assert!(!e.condition(31));
+        assert!(!e.condition(46));
+    }
+
+    #[test]
+    fn test_from_bools_boundary_at_64() {
+        // 65 conditions: first 64 accepted, index 64 ignored
+        let mut conds = vec![false; 65];
+        conds[63] = true;
+        conds[64] = true; // beyond limit
+        let e = Event::from_bools(0, &conds);
+        assert!(e.condition(63));
+        assert!(!e.condition(64));
     }
 
     // --- Session 7: Mutation-killing tests ---
@@ -543,13 +906,13 @@ mod tests {
     fn test_from_bools_single_true_each_position() {
         // Kills mutant: replacing |= with = in from_bools accumulation.
         // When called with only one true at position i, bit i must be set.
-        for i in 0..32usize {
-            let mut conds = vec![false; 32];
+        for i in 0..64usize {
+            let mut conds = vec![false; 64];
             conds[i] = true;
             let e = Event::from_bools(0, &conds);
             assert!(e.condition(i), "condition({i}) should be true");
             // Verify no other bits are set
-            assert_eq!(e.conditions, 1u32 << i, "only bit {i} should be set");
+            assert_eq!(e.conditions, 1u64 << i, "only bit {i} should be set");
         }
     }
 
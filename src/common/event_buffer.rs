@@ -0,0 +1,390 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Small-size-optimized event storage for aggregate states.
+//!
+//! In parallel plans `DuckDB` creates one partial state per group per thread
+//! and merges them with `combine`. When there are many groups with only a
+//! handful of events each, a `Vec<Event>` per state means a second heap
+//! allocation (plus capacity slack) for every group, and that overhead
+//! dominates the actual event data.
+//!
+//! [`EventBuffer`] stores up to [`INLINE_CAPACITY`] events directly inside the
+//! state and only promotes to a heap `Vec` once that is exceeded. Once
+//! promoted, it behaves exactly like a `Vec<Event>` (amortized O(1) push,
+//! doubling growth in `extend_from_slice`). It dereferences to `[Event]`, so
+//! slice-based helpers such as [`sort_events`](super::event::sort_events) and
+//! the pattern executor work unchanged.
+//!
+//! # Buffer Recycling
+//!
+//! Groups that outgrow the inline buffer still allocate, and on many-core
+//! machines those allocations contend in `malloc`. Heap buffers are therefore
+//! recycled through a per-thread pool: dropping a promoted buffer returns its
+//! `Vec` (cleared, capacity kept) to the pool of the dropping thread, and the
+//! next promotion on that thread takes it back instead of allocating. States
+//! of finished groups are destroyed on the worker threads that go on to build
+//! new states, so the pool is refilled where it is drained.
+//!
+//! The pool is per thread rather than per aggregation: an arena shared by all
+//! threads through the function's `extra_info` would need a lock on every
+//! allocation, and states are destroyed individually, which a bump arena
+//! cannot reclaim until the query ends. The pool holds at most
+//! [`POOL_BUFFERS`] buffers of at most [`POOL_MAX_CAPACITY`] events each, so
+//! an idle thread retains at most 512 KiB; larger buffers are freed as usual.
+
+use super::event::Event;
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+/// Number of events stored inline before promoting to a heap allocation.
+///
+/// Four 16-byte events (64 bytes) covers the typical per-thread partial state
+/// in combine-heavy plans while keeping the state within a cache line or two.
+pub const INLINE_CAPACITY: usize = 4;
+
+/// Maximum number of heap buffers kept in a thread's recycling pool.
+pub const POOL_BUFFERS: usize = 32;
+
+/// Largest capacity, in events, of a heap buffer returned to the pool (16 KiB).
+pub const POOL_MAX_CAPACITY: usize = 1024;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<Event>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns an empty `Vec` with room for at least `capacity` events, reusing a
+/// pooled buffer of this thread when one is available.
+fn take_vec(capacity: usize) -> Vec<Event> {
+    let Some(Some(mut vec)) = POOL.try_with(|pool| pool.borrow_mut().pop()).ok() else {
+        return Vec::with_capacity(capacity);
+    };
+    vec.reserve(capacity);
+    vec
+}
+
+/// Returns `vec` to this thread's pool, or frees it when it is too large or
+/// the pool is full.
+fn recycle_vec(mut vec: Vec<Event>) {
+    if vec.capacity() == 0 || vec.capacity() > POOL_MAX_CAPACITY {
+        return;
+    }
+    vec.clear();
+    // `try_with` fails while the thread's locals are being torn down; the
+    // buffer is then simply freed.
+    let _ = POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < POOL_BUFFERS {
+            pool.push(vec);
+        }
+    });
+}
+
+/// Returns the number of buffers in this thread's recycling pool.
+#[must_use]
+pub fn pooled_buffers() -> usize {
+    POOL.try_with(|pool| pool.borrow().len()).unwrap_or(0)
+}
+
+/// Placeholder for unused inline slots. Never observable through the slice view.
+const EMPTY_EVENT: Event = Event::new(0, 0);
+
+/// Event storage with inline capacity for [`INLINE_CAPACITY`] events.
+#[derive(Clone)]
+pub struct EventBuffer {
+    repr: Repr,
+}
+
+#[derive(Clone)]
+enum Repr {
+    Inline {
+        len: u8,
+        buf: [Event; INLINE_CAPACITY],
+    },
+    Heap(Vec<Event>),
+}
+
+impl EventBuffer {
+    /// Creates an empty buffer. Does not allocate.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            repr: Repr::Inline {
+                len: 0,
+                buf: [EMPTY_EVENT; INLINE_CAPACITY],
+            },
+        }
+    }
+
+    /// Creates an empty buffer able to hold `capacity` events without
+    /// reallocating. Only takes heap storage (pooled or newly allocated) when
+    /// `capacity` exceeds [`INLINE_CAPACITY`].
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        if capacity <= INLINE_CAPACITY {
+            Self::new()
+        } else {
+            Self {
+                repr: Repr::Heap(take_vec(capacity)),
+            }
+        }
+    }
+
+    /// Returns true if the events are stored inline (no heap allocation).
+    #[must_use]
+    pub const fn is_inline(&self) -> bool {
+        matches!(self.repr, Repr::Inline { .. })
+    }
+
+    /// Appends an event, promoting to heap storage if the inline buffer is full.
+    #[inline]
+    pub fn push(&mut self, event: Event) {
+        match &mut self.repr {
+            Repr::Inline { len, buf } if (*len as usize) < INLINE_CAPACITY => {
+                buf[*len as usize] = event;
+                *len += 1;
+            }
+            Repr::Inline { .. } => {
+                self.promote(INLINE_CAPACITY * 2).push(event);
+            }
+            Repr::Heap(vec) => vec.push(event),
+        }
+    }
+
+    /// Appends all events from `other`, promoting to heap storage if the
+    /// result does not fit inline.
+    pub fn extend_from_slice(&mut self, other: &[Event]) {
+        match &mut self.repr {
+            Repr::Inline { len, buf } if *len as usize + other.len() <= INLINE_CAPACITY => {
+                let start = *len as usize;
+                buf[start..start + other.len()].copy_from_slice(other);
+                *len += other.len() as u8;
+            }
+            Repr::Inline { len, .. } => {
+                let required = *len as usize + other.len();
+                self.promote(required).extend_from_slice(other);
+            }
+            Repr::Heap(vec) => vec.extend_from_slice(other),
+        }
+    }
+
+    /// Keeps only the events for which `keep` returns true, preserving order.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Event) -> bool) {
+        match &mut self.repr {
+            Repr::Inline { len, buf } => {
+                let mut kept = 0;
+                for i in 0..*len as usize {
+                    if keep(&buf[i]) {
+                        buf[kept] = buf[i];
+                        kept += 1;
+                    }
+                }
+                *len = kept as u8;
+            }
+            Repr::Heap(vec) => vec.retain(|event| keep(event)),
+        }
+    }
+
+    /// Moves inline events into a `Vec` with at least `capacity` slots and
+    /// returns it. Must only be called on inline storage.
+    fn promote(&mut self, capacity: usize) -> &mut Vec<Event> {
+        if let Repr::Inline { len, buf } = &self.repr {
+            let mut vec = take_vec(capacity.max(*len as usize));
+            vec.extend_from_slice(&buf[..*len as usize]);
+            self.repr = Repr::Heap(vec);
+        }
+        match &mut self.repr {
+            Repr::Heap(vec) => vec,
+            Repr::Inline { .. } => unreachable!("promote always leaves heap storage"),
+        }
+    }
+}
+
+impl Drop for EventBuffer {
+    fn drop(&mut self) {
+        if let Repr::Heap(vec) = &mut self.repr {
+            recycle_vec(std::mem::take(vec));
+        }
+    }
+}
+
+impl Default for EventBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for EventBuffer {
+    type Target = [Event];
+
+    #[inline]
+    fn deref(&self) -> &[Event] {
+        match &self.repr {
+            Repr::Inline { len, buf } => &buf[..*len as usize],
+            Repr::Heap(vec) => vec,
+        }
+    }
+}
+
+impl DerefMut for EventBuffer {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [Event] {
+        match &mut self.repr {
+            Repr::Inline { len, buf } => &mut buf[..*len as usize],
+            Repr::Heap(vec) => vec,
+        }
+    }
+}
+
+impl std::fmt::Debug for EventBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for EventBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for EventBuffer {}
+
+impl From<Vec<Event>> for EventBuffer {
+    fn from(vec: Vec<Event>) -> Self {
+        Self {
+            repr: Repr::Heap(vec),
+        }
+    }
+}
+
+impl FromIterator<Event> for EventBuffer {
+    fn from_iter<I: IntoIterator<Item = Event>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut buffer = Self::with_capacity(iter.size_hint().0);
+        for event in iter {
+            buffer.push(event);
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empties this thread's pool; tests may share a thread under
+    /// `--test-threads=1`.
+    fn clear_pool() {
+        POOL.with(|pool| pool.borrow_mut().clear());
+    }
+
+    fn events(n: usize) -> Vec<Event> {
+        (0..n).map(|i| Event::new(i as i64, 1)).collect()
+    }
+
+    #[test]
+    fn test_new_is_empty_and_inline() {
+        let buffer = EventBuffer::new();
+        assert!(buffer.is_empty());
+        assert!(buffer.is_inline());
+    }
+
+    #[test]
+    fn test_push_within_inline_capacity() {
+        let mut buffer = EventBuffer::new();
+        for e in events(INLINE_CAPACITY) {
+            buffer.push(e);
+        }
+        assert!(buffer.is_inline());
+        assert_eq!(&*buffer, events(INLINE_CAPACITY).as_slice());
+    }
+
+    #[test]
+    fn test_push_promotes_past_inline_capacity() {
+        let mut buffer = EventBuffer::new();
+        for e in events(INLINE_CAPACITY + 1) {
+            buffer.push(e);
+        }
+        assert!(!buffer.is_inline());
+        assert_eq!(&*buffer, events(INLINE_CAPACITY + 1).as_slice());
+    }
+
+    #[test]
+    fn test_extend_from_slice_inline_and_promoted() {
+        let mut buffer = EventBuffer::new();
+        buffer.extend_from_slice(&events(2));
+        assert!(buffer.is_inline());
+        buffer.extend_from_slice(&events(3));
+        assert!(!buffer.is_inline());
+        assert_eq!(buffer.len(), 5);
+        assert_eq!(buffer[4], Event::new(2, 1));
+    }
+
+    #[test]
+    fn test_with_capacity_only_allocates_when_needed() {
+        assert!(EventBuffer::with_capacity(INLINE_CAPACITY).is_inline());
+        assert!(!EventBuffer::with_capacity(INLINE_CAPACITY + 1).is_inline());
+    }
+
+    #[test]
+    fn test_deref_mut_allows_in_place_sort() {
+        let mut buffer: EventBuffer = [Event::new(3, 1), Event::new(1, 1), Event::new(2, 1)]
+            .into_iter()
+            .collect();
+        buffer.sort_unstable_by_key(|e| e.timestamp_us);
+        let ts: Vec<i64> = buffer.iter().map(|e| e.timestamp_us).collect();
+        assert_eq!(ts, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_retain_inline_and_promoted() {
+        let mut inline: EventBuffer = events(4).into_iter().collect();
+        inline.retain(|e| e.timestamp_us % 2 == 0);
+        assert!(inline.is_inline());
+        assert_eq!(&*inline, &[Event::new(0, 1), Event::new(2, 1)]);
+
+        let mut heap = EventBuffer::from(events(6));
+        heap.retain(|e| e.timestamp_us >= 3);
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap[0], Event::new(3, 1));
+    }
+
+    #[test]
+    fn test_dropped_heap_buffer_is_reused() {
+        clear_pool();
+        let mut buffer = EventBuffer::with_capacity(INLINE_CAPACITY + 1);
+        buffer.extend_from_slice(&events(INLINE_CAPACITY + 1));
+        let ptr = buffer.as_ptr();
+        drop(buffer);
+        assert_eq!(pooled_buffers(), 1);
+
+        let mut reused = EventBuffer::new();
+        reused.extend_from_slice(&events(INLINE_CAPACITY + 1));
+        assert_eq!(pooled_buffers(), 0);
+        assert_eq!(reused.as_ptr(), ptr);
+        assert_eq!(&*reused, events(INLINE_CAPACITY + 1).as_slice());
+    }
+
+    #[test]
+    fn test_pool_rejects_large_buffers_and_is_bounded() {
+        clear_pool();
+        drop(EventBuffer::from(events(POOL_MAX_CAPACITY + 1)));
+        assert_eq!(pooled_buffers(), 0);
+
+        let buffers: Vec<EventBuffer> = (0..=POOL_BUFFERS)
+            .map(|_| EventBuffer::from(events(INLINE_CAPACITY + 1)))
+            .collect();
+        drop(buffers);
+        assert_eq!(pooled_buffers(), POOL_BUFFERS);
+    }
+
+    #[test]
+    fn test_equality_ignores_representation() {
+        let inline: EventBuffer = events(3).into_iter().collect();
+        let heap = EventBuffer::from(events(3));
+        assert!(inline.is_inline());
+        assert!(!heap.is_inline());
+        assert_eq!(inline, heap);
+    }
+}
@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Run-length encoding of condition masks for telemetry-style event streams.
+//!
+//! Long idle periods often produce thousands of consecutive events with the
+//! same condition mask (e.g. heartbeats). [`RunLengthBuffer`] collapses such
+//! runs into a single [`EventRun`] of `(mask, first_ts, last_ts, count)`, so a
+//! run of any length costs 32 bytes instead of 16 bytes per event.
+//!
+//! Runs are only extended by events arriving in timestamp order; an event
+//! earlier than the current run's end starts a new run. Before pattern
+//! execution the runs are sorted and re-expanded by [`RunLengthBuffer::expand`]
+//! into at most `cap` events per run. The first expanded event carries the
+//! run's `first_ts`, the remaining ones its `last_ts`; timestamps of interior
+//! events are not retained.
+
+use super::event::Event;
+
+/// A run of consecutive events sharing the same condition mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventRun {
+    /// Condition bitmask shared by every event in the run.
+    pub conditions: u64,
+    /// Timestamp of the first event in the run (microseconds since epoch).
+    pub first_ts: i64,
+    /// Timestamp of the last event in the run (microseconds since epoch).
+    pub last_ts: i64,
+    /// Number of events in the run.
+    pub count: u64,
+}
+
+impl EventRun {
+    /// Creates a run holding a single event.
+    #[must_use]
+    pub const fn from_event(event: Event) -> Self {
+        Self {
+            conditions: event.conditions,
+            first_ts: event.timestamp_us,
+            last_ts: event.timestamp_us,
+            count: 1,
+        }
+    }
+
+    /// Returns true if `event` continues this run: same mask and not earlier
+    /// than the run's last event.
+    #[inline]
+    #[must_use]
+    pub const fn accepts(&self, event: &Event) -> bool {
+        let in_order = event.timestamp_us >= self.last_ts;
+        in_order && self.conditions == event.conditions
+    }
+}
+
+/// Event storage that collapses consecutive events with identical condition
+/// masks into [`EventRun`]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunLengthBuffer {
+    runs: Vec<EventRun>,
+}
+
+impl RunLengthBuffer {
+    /// Creates an empty buffer. Does not allocate.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { runs: Vec::new() }
+    }
+
+    /// The stored runs, in insertion order until [`sort`](Self::sort) is called.
+    #[must_use]
+    pub fn runs(&self) -> &[EventRun] {
+        &self.runs
+    }
+
+    /// Total number of events represented by the buffer.
+    #[must_use]
+    pub fn event_count(&self) -> u64 {
+        self.runs.iter().map(|r| r.count).sum()
+    }
+
+    /// Appends an event, extending the last run when the event continues it.
+    #[inline]
+    pub fn push(&mut self, event: Event) {
+        match self.runs.last_mut() {
+            Some(run) if run.accepts(&event) => {
+                run.last_ts = event.timestamp_us;
+                run.count += 1;
+            }
+            _ => self.runs.push(EventRun::from_event(event)),
+        }
+    }
+
+    /// Appends all runs from `other`.
+    pub fn extend_from_runs(&mut self, other: &[EventRun]) {
+        self.runs.extend_from_slice(other);
+    }
+
+    /// Sorts runs by timestamp and coalesces neighbouring runs with the same
+    /// mask that do not overlap in time.
+    ///
+    /// Runs built by different partial states may overlap when the input
+    /// was not ordered; overlapping runs are kept separate and ordered by
+    /// `first_ts`, so their relative order is approximate.
+    pub fn sort(&mut self) {
+        self.runs
+            .sort_by_key(|r| (r.first_ts, r.last_ts, r.conditions));
+        let mut merged: Vec<EventRun> = Vec::with_capacity(self.runs.len());
+        for run in self.runs.drain(..) {
+            match merged.last_mut() {
+                Some(prev) if prev.accepts(&Event::new(run.first_ts, run.conditions)) => {
+                    prev.last_ts = run.last_ts;
+                    prev.count += run.count;
+                }
+                _ => merged.push(run),
+            }
+        }
+        self.runs = merged;
+    }
+
+    /// Expands the runs into events, emitting at most `cap` events per run.
+    ///
+    /// A `cap` of at least the number of event-consuming pattern steps is
+    /// exact for existence matching without time constraints; `usize::MAX`
+    /// restores the full event count. `cap` is raised to 2 so that both the
+    /// first and last timestamp of a run are visible to time constraints.
+    #[must_use]
+    pub fn expand(&self, cap: usize) -> Vec<Event> {
+        let cap = cap.max(2) as u64;
+        let total: u64 = self.runs.iter().map(|r| r.count.min(cap)).sum();
+        let mut events = Vec::with_capacity(usize::try_from(total).unwrap_or(0));
+        for run in &self.runs {
+            events.push(Event::new(run.first_ts, run.conditions));
+            for _ in 1..run.count.min(cap) {
+                events.push(Event::new(run.last_ts, run.conditions));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_collapses_identical_masks() {
+        let mut buffer = RunLengthBuffer::new();
+        for ts in 0..1_000 {
+            buffer.push(Event::new(ts, 0b01));
+        }
+        assert_eq!(buffer.runs().len(), 1);
+        assert_eq!(buffer.event_count(), 1_000);
+        assert_eq!(
+            buffer.runs()[0],
+            EventRun {
+                conditions: 0b01,
+                first_ts: 0,
+                last_ts: 999,
+                count: 1_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_push_mask_change_starts_new_run() {
+        let mut buffer = RunLengthBuffer::new();
+        buffer.push(Event::new(0, 0b01));
+        buffer.push(Event::new(1, 0b10));
+        buffer.push(Event::new(2, 0b01));
+        assert_eq!(buffer.runs().len(), 3);
+    }
+
+    #[test]
+    fn test_push_out_of_order_starts_new_run() {
+        let mut buffer = RunLengthBuffer::new();
+        buffer.push(Event::new(10, 0b01));
+        buffer.push(Event::new(5, 0b01));
+        assert_eq!(buffer.runs().len(), 2);
+    }
+
+    #[test]
+    fn test_sort_coalesces_adjacent_runs() {
+        let mut buffer = RunLengthBuffer::new();
+        buffer.push(Event::new(20, 0b01));
+        buffer.push(Event::new(30, 0b01));
+        buffer.push(Event::new(0, 0b01));
+        buffer.push(Event::new(10, 0b01));
+        buffer.sort();
+        assert_eq!(buffer.runs().len(), 1);
+        assert_eq!(buffer.runs()[0].first_ts, 0);
+        assert_eq!(buffer.runs()[0].last_ts, 30);
+        assert_eq!(buffer.runs()[0].count, 4);
+    }
+
+    #[test]
+    fn test_sort_keeps_distinct_masks_in_time_order() {
+        let mut buffer = RunLengthBuffer::new();
+        buffer.push(Event::new(50, 0b10));
+        buffer.push(Event::new(0, 0b01));
+        buffer.push(Event::new(10, 0b01));
+        buffer.sort();
+        let masks: Vec<u64> = buffer.runs().iter().map(|r| r.conditions).collect();
+        assert_eq!(masks, vec![0b01, 0b10]);
+    }
+
+    #[test]
+    fn test_expand_caps_events_per_run() {
+        let mut buffer = RunLengthBuffer::new();
+        for ts in 0..100 {
+            buffer.push(Event::new(ts, 0b01));
+        }
+        buffer.push(Event::new(200, 0b10));
+        let events = buffer.expand(3);
+        let ts: Vec<i64> = events.iter().map(|e| e.timestamp_us).collect();
+        assert_eq!(ts, vec![0, 99, 99, 200]);
+        assert_eq!(buffer.expand(usize::MAX).len(), 101);
+    }
+
+    #[test]
+    fn test_expand_keeps_first_and_last_timestamp() {
+        let mut buffer = RunLengthBuffer::new();
+        for ts in [0, 5, 9] {
+            buffer.push(Event::new(ts, 0b01));
+        }
+        let ts: Vec<i64> = buffer.expand(1).iter().map(|e| e.timestamp_us).collect();
+        assert_eq!(ts, vec![0, 9]);
+    }
+}
@@ -3,5 +3,11 @@
 
 //! Common types and utilities shared across behavioral analytics functions.
 
+pub mod column;
 pub mod event;
+pub mod event_buffer;
+pub mod event_run;
+pub mod sketch;
+pub mod state_blob;
+pub mod stopwatch;
 pub mod timestamp;
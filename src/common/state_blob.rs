@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Binary encoding of aggregate states exported as `BLOB`s.
+//!
+//! An exported state (`window_funnel_state`, `retention_state`,
+//! `sessionize_state`) can be stored, merged with another one and finalized
+//! later, like `ClickHouse`'s `-State` and `-Merge` combinators. The encoding
+//! is a 4-byte tag naming the state type and format version, followed by
+//! little-endian fixed-width fields written by [`BlobWriter`] and read back by
+//! [`BlobReader`]. A blob produced by a
+//! different state type or format version is rejected by its tag rather than
+//! misread.
+
+/// Error reading a state blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobError {
+    /// The blob does not start with the expected tag: it holds another state
+    /// type or format version, or is not a state at all.
+    Tag,
+    /// The blob ends before the state does.
+    Truncated,
+    /// The blob continues after the state.
+    TrailingBytes,
+    /// A field holds a value the state cannot take.
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for BlobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tag => f.write_str("not a state of this function or format version"),
+            Self::Truncated => f.write_str("state is truncated"),
+            Self::TrailingBytes => f.write_str("unexpected bytes after the state"),
+            Self::Invalid(field) => write!(f, "invalid {field} in state"),
+        }
+    }
+}
+
+impl std::error::Error for BlobError {}
+
+/// Appends tagged little-endian fields to a byte buffer.
+#[derive(Debug)]
+pub struct BlobWriter {
+    bytes: Vec<u8>,
+}
+
+impl BlobWriter {
+    /// Starts a blob with `tag`.
+    #[must_use]
+    pub fn new(tag: [u8; 4]) -> Self {
+        Self {
+            bytes: tag.to_vec(),
+        }
+    }
+
+    /// Appends a byte.
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    /// Appends a `u16`.
+    pub fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends a `u64`.
+    pub fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends an `i64`.
+    pub fn i64(&mut self, value: i64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends a length prefix.
+    pub fn len(&mut self, len: usize) {
+        self.u64(len as u64);
+    }
+
+    /// Returns the blob.
+    #[must_use]
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads the fields written by [`BlobWriter`], in the same order.
+#[derive(Debug)]
+pub struct BlobReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BlobReader<'a> {
+    /// Checks that `bytes` starts with `tag` and positions the reader after
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::Tag`] when the tag differs.
+    pub fn new(bytes: &'a [u8], tag: [u8; 4]) -> Result<Self, BlobError> {
+        let rest = bytes.strip_prefix(&tag).ok_or(BlobError::Tag)?;
+        Ok(Self { bytes: rest })
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BlobError> {
+        let (head, rest) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .ok_or(BlobError::Truncated)?;
+        self.bytes = rest;
+        Ok(*head)
+    }
+
+    /// Reads a byte.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::Truncated`] at the end of the blob.
+    pub fn u8(&mut self) -> Result<u8, BlobError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    /// Reads a `u16`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::Truncated`] at the end of the blob.
+    pub fn u16(&mut self) -> Result<u16, BlobError> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    /// Reads a `u64`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::Truncated`] at the end of the blob.
+    pub fn u64(&mut self) -> Result<u64, BlobError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    /// Reads an `i64`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::Truncated`] at the end of the blob.
+    pub fn i64(&mut self) -> Result<i64, BlobError> {
+        self.take().map(i64::from_le_bytes)
+    }
+
+    /// Reads a length prefix of a sequence of items of `item_bytes` bytes
+    /// each, checking that the blob holds that many before anything is
+    /// allocated for them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::Truncated`] when the blob is too short for the
+    /// prefix or the items.
+    pub fn len(&mut self, item_bytes: usize) -> Result<usize, BlobError> {
+        let len = usize::try_from(self.u64()?).map_err(|_| BlobError::Truncated)?;
+        if len
+            .checked_mul(item_bytes)
+            .is_none_or(|n| n > self.bytes.len())
+        {
+            return Err(BlobError::Truncated);
+        }
+        Ok(len)
+    }
+
+    /// Checks that the whole blob was read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::TrailingBytes`] when bytes are left.
+    pub const fn finish(self) -> Result<(), BlobError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(BlobError::TrailingBytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAG: [u8; 4] = *b"TST\x01";
+
+    #[test]
+    fn test_round_trip() {
+        let mut w = BlobWriter::new(TAG);
+        w.u8(7);
+        w.u16(0xBEEF);
+        w.i64(-42);
+        w.len(1);
+        w.u64(u64::MAX);
+        let bytes = w.finish();
+
+        let mut r = BlobReader::new(&bytes, TAG).unwrap();
+        assert_eq!(r.u8(), Ok(7));
+        assert_eq!(r.u16(), Ok(0xBEEF));
+        assert_eq!(r.i64(), Ok(-42));
+        assert_eq!(r.len(8), Ok(1));
+        assert_eq!(r.u64(), Ok(u64::MAX));
+        assert_eq!(r.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_rejects_other_tags() {
+        assert_eq!(
+            BlobReader::new(b"TST\x02", TAG).unwrap_err(),
+            BlobError::Tag
+        );
+        assert_eq!(BlobReader::new(b"TS", TAG).unwrap_err(), BlobError::Tag);
+    }
+
+    #[test]
+    fn test_truncated_and_trailing() {
+        let mut w = BlobWriter::new(TAG);
+        w.u16(1);
+        let bytes = w.finish();
+
+        let mut r = BlobReader::new(&bytes[..5], TAG).unwrap();
+        assert_eq!(r.u16(), Err(BlobError::Truncated));
+
+        let r = BlobReader::new(&bytes, TAG).unwrap();
+        assert_eq!(r.finish(), Err(BlobError::TrailingBytes));
+    }
+
+    #[test]
+    fn test_len_checks_remaining_bytes() {
+        let mut w = BlobWriter::new(TAG);
+        w.len(3);
+        w.i64(1);
+        w.i64(2);
+        let bytes = w.finish();
+        let mut r = BlobReader::new(&bytes, TAG).unwrap();
+        assert_eq!(r.len(8), Err(BlobError::Truncated));
+
+        let mut w = BlobWriter::new(TAG);
+        w.u64(u64::MAX);
+        let bytes = w.finish();
+        let mut r = BlobReader::new(&bytes, TAG).unwrap();
+        assert_eq!(r.len(16), Err(BlobError::Truncated));
+    }
+}
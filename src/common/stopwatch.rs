@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Elapsed time for the timings that `sequence_match_debug` and
+//! `behavioral_selfcheck` report.
+//!
+//! [`std::time::Instant::now`] panics on `wasm32-unknown-unknown`, which has
+//! no clock, so there the stopwatch always reads zero. The timings are
+//! diagnostics only; no result depends on them. `DuckDB`-WASM builds
+//! extensions for `wasm32-unknown-emscripten`, whose clock works.
+
+#[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+use std::time::Instant;
+
+/// Measures the time since it was started.
+#[derive(Debug, Clone, Copy)]
+pub struct Stopwatch {
+    #[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+    started: Instant,
+}
+
+impl Stopwatch {
+    /// Starts a stopwatch.
+    #[must_use]
+    pub fn start() -> Self {
+        Self {
+            #[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns the microseconds elapsed since [`start`](Self::start),
+    /// saturating at `u64::MAX`; always 0 on targets without a clock.
+    #[must_use]
+    pub fn elapsed_us(&self) -> u64 {
+        #[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+        {
+            u64::try_from(self.started.elapsed().as_micros()).unwrap_or(u64::MAX)
+        }
+        #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+        {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elapsed_is_monotonic() {
+        let stopwatch = Stopwatch::start();
+        let first = stopwatch.elapsed_us();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = stopwatch.elapsed_us();
+        assert!(second >= first);
+        assert!(second >= 2_000);
+    }
+}
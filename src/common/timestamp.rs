@@ -6,6 +6,19 @@
 //! `DuckDB` stores timestamps internally as `i64` microseconds since Unix epoch.
 //! Intervals are stored as a struct with months, days, and microseconds components.
 //! We normalize everything to microseconds for consistent comparison.
+//!
+//! Human-readable durations such as `90m` or `1h30m` are parsed by
+//! [`parse_duration`], exposed to SQL as `parse_duration(text)`. Pattern time
+//! constraints share its unit table through [`time_unit_micros`].
+//!
+//! # Timestamp Arithmetic
+//!
+//! `DuckDB` timestamps span almost the whole `i64` range (`'infinity'` is
+//! `i64::MAX`), so the raw difference of two timestamps can overflow and flip
+//! sign. Gap and window checks go through [`elapsed_exceeds`], which compares
+//! in `i128` and is exact for every input; differences that are returned to
+//! the user go through [`elapsed_micros`], which fails with
+//! [`TimestampOverflow`] instead of wrapping.
 
 /// Microseconds per second.
 pub const MICROS_PER_SECOND: i64 = 1_000_000;
@@ -13,6 +26,50 @@ pub const MICROS_PER_SECOND: i64 = 1_000_000;
 /// Microseconds per day (`24 * 60 * 60 * 1_000_000`).
 pub const MICROS_PER_DAY: i64 = 86_400_000_000;
 
+/// Error for a timestamp difference that does not fit in `i64` microseconds,
+/// such as the time from `'-infinity'` to `'infinity'`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampOverflow;
+
+impl std::fmt::Display for TimestampOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("time difference overflows the microsecond range")
+    }
+}
+
+impl std::error::Error for TimestampOverflow {}
+
+/// Returns the microseconds from `from` to `to`.
+///
+/// # Errors
+///
+/// Returns [`TimestampOverflow`] when the difference does not fit in `i64`.
+#[inline]
+pub const fn elapsed_micros(from: i64, to: i64) -> Result<i64, TimestampOverflow> {
+    match to.checked_sub(from) {
+        Some(elapsed) => Ok(elapsed),
+        None => Err(TimestampOverflow),
+    }
+}
+
+/// Returns the microseconds from `from` to `to` as an `i128`, which cannot
+/// overflow.
+#[must_use]
+#[inline]
+pub const fn elapsed_wide(from: i64, to: i64) -> i128 {
+    to as i128 - from as i128
+}
+
+/// Returns true if more than `limit` microseconds pass from `from` to `to`.
+///
+/// Exact for all inputs, unlike `to - from > limit`, which overflows for
+/// timestamps far apart.
+#[must_use]
+#[inline]
+pub const fn elapsed_exceeds(from: i64, to: i64, limit: i64) -> bool {
+    elapsed_wide(from, to) > limit as i128
+}
+
 /// Extracts the microseconds component from a `DuckDB` interval.
 ///
 /// `DuckDB` intervals have three components: months, days, microseconds.
@@ -45,10 +102,155 @@ pub fn interval_to_micros(months: i32, days: i32, micros: i64) -> Option<i64> {
     day_micros.checked_add(micros)
 }
 
+/// Returns the number of microseconds in one `unit`, for the suffixes
+/// accepted by pattern time constraints: `us`, `ms`, `s`, `m` and `h`.
+#[must_use]
+pub fn time_unit_micros(unit: &str) -> Option<i64> {
+    match unit {
+        "us" => Some(1),
+        "ms" => Some(1_000),
+        "s" => Some(MICROS_PER_SECOND),
+        "m" => Some(60 * MICROS_PER_SECOND),
+        "h" => Some(3_600 * MICROS_PER_SECOND),
+        _ => None,
+    }
+}
+
+/// Parses a duration such as `90m`, `1h30m` or `2d 12h` into microseconds.
+///
+/// A duration is one or more `<integer><unit>` components, optionally
+/// separated by whitespace, whose values are added. Units are those of
+/// [`time_unit_micros`] plus `d` (24 hours). Every number needs a unit, so a
+/// bare `90` is rejected rather than guessed to be seconds.
+///
+/// # Errors
+///
+/// Returns a message with the byte offset of the problem for empty input,
+/// missing or unknown units, and durations that overflow `i64` microseconds.
+pub fn parse_duration(text: &str) -> Result<i64, String> {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    let mut total: i64 = 0;
+    let mut components = 0;
+    let overflow = || format!("duration '{text}' overflows the microsecond range");
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos == bytes.len() {
+            break;
+        }
+        let start = pos;
+        while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        if pos == start {
+            return Err(format!("expected a number at offset {start} in '{text}'"));
+        }
+        let value: i64 = text[start..pos].parse().map_err(|_| overflow())?;
+        let unit_start = pos;
+        while pos < bytes.len() && bytes[pos].is_ascii_alphabetic() {
+            pos += 1;
+        }
+        let unit = &text[unit_start..pos];
+        let micros_per_unit = match unit {
+            "" => {
+                return Err(format!(
+                    "missing unit after '{}' at offset {unit_start} in '{text}' (expected us, ms, s, m, h or d)",
+                    &text[start..pos]
+                ))
+            }
+            "d" => MICROS_PER_DAY,
+            _ => time_unit_micros(unit).ok_or_else(|| {
+                format!(
+                    "unknown unit '{unit}' at offset {unit_start} in '{text}' (expected us, ms, s, m, h or d)"
+                )
+            })?,
+        };
+        total = value
+            .checked_mul(micros_per_unit)
+            .and_then(|micros| total.checked_add(micros))
+            .ok_or_else(overflow)?;
+        components += 1;
+    }
+    if components == 0 {
+        return Err("empty duration".to_string());
+    }
+    Ok(total)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const MINUTE: i64 = 60 * MICROS_PER_SECOND;
+
+    #[test]
+    fn test_elapsed_arithmetic_at_extremes() {
+        assert_eq!(elapsed_micros(-5, 10), Ok(15));
+        assert_eq!(elapsed_micros(-i64::MAX, i64::MAX), Err(TimestampOverflow));
+        assert_eq!(elapsed_micros(i64::MAX, -2), Err(TimestampOverflow));
+        assert_eq!(elapsed_wide(-i64::MAX, i64::MAX), 2 * i128::from(i64::MAX));
+
+        // Wrapping subtraction would make these gaps negative
+        assert!(elapsed_exceeds(-i64::MAX, i64::MAX, MINUTE));
+        assert!(elapsed_exceeds(i64::MIN, 0, i64::MAX));
+        assert!(!elapsed_exceeds(i64::MAX, i64::MIN, 0));
+        assert!(!elapsed_exceeds(0, MINUTE, MINUTE));
+        assert!(elapsed_exceeds(0, MINUTE + 1, MINUTE));
+    }
+
+    #[test]
+    fn test_parse_duration_single_component() {
+        assert_eq!(parse_duration("90m"), Ok(90 * MINUTE));
+        assert_eq!(parse_duration("1500ms"), Ok(1_500_000));
+        assert_eq!(parse_duration("7us"), Ok(7));
+        assert_eq!(parse_duration("2d"), Ok(2 * MICROS_PER_DAY));
+        assert_eq!(parse_duration("0s"), Ok(0));
+    }
+
+    #[test]
+    fn test_parse_duration_components_are_added() {
+        assert_eq!(parse_duration("1h30m"), Ok(90 * MINUTE));
+        assert_eq!(parse_duration(" 1d 12h "), Ok(36 * 60 * MINUTE));
+        assert_eq!(parse_duration("30m30m"), Ok(60 * MINUTE));
+    }
+
+    #[test]
+    fn test_parse_duration_errors() {
+        assert_eq!(parse_duration(""), Err("empty duration".to_string()));
+        assert_eq!(parse_duration("  "), Err("empty duration".to_string()));
+        assert!(parse_duration("90")
+            .unwrap_err()
+            .starts_with("missing unit after '90' at offset 2"));
+        assert!(parse_duration("1h30x")
+            .unwrap_err()
+            .starts_with("unknown unit 'x' at offset 4"));
+        assert!(parse_duration("h")
+            .unwrap_err()
+            .starts_with("expected a number at offset 0"));
+        assert!(parse_duration("-5m").is_err());
+        assert!(parse_duration("1.5h").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_overflow() {
+        assert!(parse_duration("99999999999999999999s")
+            .unwrap_err()
+            .contains("overflows"));
+        assert!(parse_duration("200000000d")
+            .unwrap_err()
+            .contains("overflows"));
+        assert!(parse_duration("106751991d 106751991d").is_err());
+    }
+
+    #[test]
+    fn test_time_unit_micros() {
+        assert_eq!(time_unit_micros("h"), Some(3_600 * MICROS_PER_SECOND));
+        assert_eq!(time_unit_micros("d"), None);
+        assert_eq!(time_unit_micros("M"), None);
+    }
+
     #[test]
     fn test_interval_to_micros_basic() {
         // 30 minutes = 30 * 60 * 1_000_000 = 1_800_000_000 microseconds
@@ -0,0 +1,393 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `behavioral_demo_events` — Deterministic synthetic clickstream data.
+//!
+//! Generates the events of an online shop's users: page views grouped into
+//! sessions, and a signup → add to cart → checkout → purchase funnel that
+//! each session may advance. The rows exercise every function of the
+//! extension, and the same `(n_users, seed)` always produces the same rows,
+//! so documentation examples, tests and benchmarks can share one dataset.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT window_funnel(INTERVAL '1 day', event_time,
+//!          event_type = 'signup', event_type = 'add_to_cart',
+//!          event_type = 'purchase') AS steps,
+//!        count(*) AS users
+//! FROM (SELECT * FROM behavioral_demo_events(1000, 42))
+//! GROUP BY user_id
+//! ```
+//!
+//! # Shape of the Data
+//!
+//! Users are numbered from 1 and emitted in order, each user's events in
+//! strictly increasing time order. A user first visits at a random second of
+//! the 28 days from [`DEMO_START_US`] (2024-01-01 UTC) through an
+//! acquisition channel, and returns for up to [`MAX_SESSIONS`] sessions in
+//! total, each at least 45 minutes after the previous one; events within a
+//! session are 10 seconds to 5 minutes apart, so a 30 minute `sessionize`
+//! gap recovers the sessions exactly. Returning sessions come through
+//! `direct`, `email` or the acquisition channel.
+//!
+//! Each step of the funnel is reached with a fixed probability once the
+//! previous one is, in the session that reached it or a later one, and a
+//! purchase starts the funnel again from the cart, so some users buy more
+//! than once. Add to cart always follows a `/product` page view.
+//!
+//! # Implementation
+//!
+//! Every user's events come from a `SplitMix64` generator seeded with both
+//! `seed` and the user ID, using integer arithmetic only, so the rows of a
+//! user do not depend on `n_users` or on the platform: the first `k` users
+//! of a larger dataset are the dataset of `k` users. [`DemoEvents`] generates
+//! one user at a time and never holds more than one user's events.
+
+use std::vec::IntoIter;
+
+/// Start of the first-visit period: 2024-01-01 00:00:00 UTC, in microseconds
+/// since the epoch.
+pub const DEMO_START_US: i64 = 1_704_067_200_000_000;
+
+/// Largest accepted `n_users`.
+pub const MAX_DEMO_USERS: u64 = 10_000_000;
+
+/// Largest number of sessions of one user.
+pub const MAX_SESSIONS: u32 = 8;
+
+/// Event types, in funnel order after the initial page views.
+pub const EVENT_TYPES: [&str; 5] = ["page_view", "signup", "add_to_cart", "checkout", "purchase"];
+
+/// Acquisition channels, with their weights out of 100.
+const ACQUISITION_CHANNELS: [(&str, u64); 5] = [
+    ("organic", 35),
+    ("paid_search", 25),
+    ("social", 20),
+    ("email", 10),
+    ("referral", 10),
+];
+
+/// Pages of browsing page views.
+const CONTENT_PAGES: [&str; 5] = ["/home", "/pricing", "/product", "/blog", "/docs"];
+
+/// Per-mille probability of reaching signup, add to cart, checkout and
+/// purchase in a session, given the previous step.
+const STEP_PER_MILLE: [u64; 4] = [350, 450, 600, 700];
+
+/// Per-mille probability of one more session after each session.
+const RETURN_PER_MILLE: u64 = 550;
+
+const SECOND: i64 = 1_000_000;
+
+/// One generated event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DemoEvent {
+    /// User ID, from 1.
+    pub user_id: i64,
+    /// Event time in microseconds since the epoch.
+    pub event_time_us: i64,
+    /// One of [`EVENT_TYPES`].
+    pub event_type: &'static str,
+    /// Page path, e.g. `/checkout`.
+    pub page: &'static str,
+    /// Marketing channel of the event's session.
+    pub channel: &'static str,
+}
+
+/// Iterator over the events of users `1..=n_users`, in user then time order.
+#[derive(Debug, Clone)]
+pub struct DemoEvents {
+    seed: u64,
+    next_user: u64,
+    n_users: u64,
+    pending: IntoIter<DemoEvent>,
+}
+
+impl DemoEvents {
+    /// Events of `n_users` users generated from `seed`.
+    pub fn new(n_users: u64, seed: u64) -> Self {
+        Self {
+            seed,
+            next_user: 1,
+            n_users,
+            pending: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Iterator for DemoEvents {
+    type Item = DemoEvent;
+
+    fn next(&mut self) -> Option<DemoEvent> {
+        loop {
+            if let Some(event) = self.pending.next() {
+                return Some(event);
+            }
+            if self.next_user > self.n_users {
+                return None;
+            }
+            self.pending = user_events(self.next_user, self.seed).into_iter();
+            self.next_user += 1;
+        }
+    }
+}
+
+/// `SplitMix64`: small, fast and identical on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64, user_id: u64) -> Self {
+        Self(mix(seed ^ mix(user_id)))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.0)
+    }
+
+    /// Uniform value in `0..n`; the modulo bias is negligible for the small
+    /// `n` used here.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Uniform value in `lo..=hi`.
+    fn between(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + self.below((hi - lo + 1) as u64) as i64
+    }
+
+    fn chance(&mut self, per_mille: u64) -> bool {
+        self.below(1000) < per_mille
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// The `SplitMix64` output function.
+const fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Appends events to one user's timeline, keeping the session's channel.
+struct Timeline {
+    user_id: i64,
+    now_us: i64,
+    channel: &'static str,
+    events: Vec<DemoEvent>,
+}
+
+impl Timeline {
+    fn push(&mut self, event_type: &'static str, page: &'static str) {
+        self.events.push(DemoEvent {
+            user_id: self.user_id,
+            event_time_us: self.now_us,
+            event_type,
+            page,
+            channel: self.channel,
+        });
+    }
+
+    /// Advances to the next event of the current session.
+    fn step(&mut self, rng: &mut Rng) {
+        self.now_us += rng.between(10, 300) * SECOND;
+    }
+}
+
+/// Landing page of a user's first session.
+fn landing_page(channel: &str) -> &'static str {
+    match channel {
+        "paid_search" => "/pricing",
+        "social" => "/blog",
+        "email" => "/product",
+        _ => "/home",
+    }
+}
+
+/// The events of one user, in time order.
+pub fn user_events(user_id: u64, seed: u64) -> Vec<DemoEvent> {
+    let mut rng = Rng::new(seed, user_id);
+    let acquisition = {
+        let mut roll = rng.below(100);
+        let mut chosen = ACQUISITION_CHANNELS[0].0;
+        for &(channel, weight) in &ACQUISITION_CHANNELS {
+            if roll < weight {
+                chosen = channel;
+                break;
+            }
+            roll -= weight;
+        }
+        chosen
+    };
+    let mut timeline = Timeline {
+        user_id: user_id as i64,
+        now_us: DEMO_START_US + rng.between(0, 28 * 86_400 - 1) * SECOND,
+        channel: acquisition,
+        events: Vec::new(),
+    };
+
+    // Funnel steps reached so far: 0 = none, 1 = signup, 2 = add to cart,
+    // 3 = checkout. A purchase returns to 1.
+    let mut reached = 0;
+    for session in 0..MAX_SESSIONS {
+        if session > 0 {
+            if !rng.chance(RETURN_PER_MILLE) {
+                break;
+            }
+            timeline.now_us += SECOND
+                * if rng.chance(500) {
+                    rng.between(45 * 60, 6 * 3600)
+                } else {
+                    rng.between(86_400, 10 * 86_400)
+                };
+            timeline.channel = match rng.below(10) {
+                0..=4 => "direct",
+                5..=6 => "email",
+                _ => acquisition,
+            };
+        }
+
+        let first_page = if session == 0 {
+            landing_page(acquisition)
+        } else {
+            "/home"
+        };
+        timeline.push("page_view", first_page);
+        for _ in 0..rng.below(4) {
+            timeline.step(&mut rng);
+            let page = rng.pick(&CONTENT_PAGES);
+            timeline.push("page_view", page);
+        }
+
+        while rng.chance(STEP_PER_MILLE[reached]) {
+            timeline.step(&mut rng);
+            match reached {
+                0 => timeline.push("signup", "/signup"),
+                1 => {
+                    if timeline.events.last().is_some_and(|e| e.page != "/product") {
+                        timeline.push("page_view", "/product");
+                        timeline.step(&mut rng);
+                    }
+                    timeline.push("add_to_cart", "/product");
+                }
+                2 => timeline.push("checkout", "/checkout"),
+                _ => timeline.push("purchase", "/confirmation"),
+            }
+            reached = if reached == 3 { 1 } else { reached + 1 };
+        }
+    }
+    timeline.events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use crate::window_funnel::WindowFunnelState;
+
+    const MINUTE: i64 = 60 * SECOND;
+    const DAY: i64 = 24 * 60 * MINUTE;
+
+    fn events(n_users: u64, seed: u64) -> Vec<DemoEvent> {
+        DemoEvents::new(n_users, seed).collect()
+    }
+
+    #[test]
+    fn test_deterministic() {
+        assert_eq!(events(50, 42), events(50, 42));
+        assert_ne!(events(50, 42), events(50, 43));
+        assert!(events(0, 42).is_empty());
+    }
+
+    #[test]
+    fn test_users_independent_of_count() {
+        let small = events(10, 7);
+        let large = events(100, 7);
+        assert_eq!(small[..], large[..small.len()]);
+        assert!(large[small.len()..].iter().all(|e| e.user_id > 10));
+    }
+
+    #[test]
+    fn test_order_and_sessions() {
+        let all = events(200, 1);
+        let users: Vec<i64> = all.iter().map(|e| e.user_id).collect();
+        assert!(users.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(users.first(), Some(&1));
+        assert_eq!(users.last(), Some(&200));
+
+        for pair in all.windows(2).filter(|w| w[0].user_id == w[1].user_id) {
+            let gap = pair[1].event_time_us - pair[0].event_time_us;
+            assert!(gap >= 10 * SECOND);
+            // Within a session, or a new session starting on a page view
+            assert!(gap <= 5 * MINUTE || (gap >= 45 * MINUTE && pair[1].event_type == "page_view"));
+        }
+        for event in &all {
+            assert!(EVENT_TYPES.contains(&event.event_type));
+            let first_visit = event.event_time_us - DEMO_START_US;
+            assert!(first_visit >= 0);
+        }
+    }
+
+    #[test]
+    fn test_funnel_order() {
+        let all = events(500, 3);
+        let mut user = 0;
+        let mut reached = 0;
+        for event in &all {
+            if event.user_id != user {
+                user = event.user_id;
+                reached = 0;
+            }
+            match event.event_type {
+                "signup" => {
+                    assert_eq!(reached, 0);
+                    reached = 1;
+                }
+                "add_to_cart" => {
+                    assert_eq!(reached, 1);
+                    reached = 2;
+                }
+                "checkout" => {
+                    assert_eq!(reached, 2);
+                    reached = 3;
+                }
+                "purchase" => {
+                    assert_eq!(reached, 3);
+                    reached = 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_funnel_narrows() {
+        // window_funnel(INTERVAL '30 days', event_time, signup, add_to_cart,
+        // checkout, purchase) per user
+        let steps = ["signup", "add_to_cart", "checkout", "purchase"];
+        let mut reached_at_least = [0usize; 5];
+        let all = events(2000, 42);
+        for user in all.chunk_by(|a, b| a.user_id == b.user_id) {
+            let mut state = WindowFunnelState::new();
+            state.window_size_us = 30 * DAY;
+            state.params_set = true;
+            for event in user {
+                let mask = steps
+                    .iter()
+                    .position(|&s| s == event.event_type)
+                    .map_or(0, |i| 1u64 << i);
+                state.update(Event::new(event.event_time_us, mask), 4);
+            }
+            for count in &mut reached_at_least[..=state.finalize() as usize] {
+                *count += 1;
+            }
+        }
+        assert_eq!(reached_at_least[0], 2000);
+        assert!(reached_at_least.windows(2).all(|w| w[0] > w[1]));
+        assert!(reached_at_least[4] > 0);
+    }
+}
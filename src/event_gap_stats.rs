@@ -0,0 +1,344 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `event_gap_stats` — Distribution of gaps between consecutive timestamps.
+//!
+//! Summarizes the inactivity gaps within a group as
+//! `STRUCT(min_gap_us, max_gap_us, median_gap_us, count)`, which is the input
+//! needed to choose a [`sessionize`](crate::sessionize) threshold without
+//! `LAG` window gymnastics.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id, event_gap_stats(event_time ORDER BY event_time)
+//! FROM events
+//! GROUP BY user_id
+//! ```
+//!
+//! # Implementation
+//!
+//! Like `sessionize`, the state summarizes an ordered segment by its first
+//! and last timestamp, so two partial states over disjoint time ranges combine
+//! in O(1) by adding the single gap between them. The median is estimated from
+//! a log-linear histogram with four sub-buckets per power of two (relative
+//! error below 12.5%); min, max and count are exact.
+//!
+//! Gaps are only defined between consecutive timestamps, so each state must
+//! see its timestamps in order. Use `ORDER BY` inside the aggregate call.
+//! A timestamp earlier than the previous one does not produce a gap; partial
+//! states whose time ranges overlap are merged without the cross-state gap.
+//! A gap too large for `i64` microseconds, such as from `'-infinity'` to
+//! `'infinity'`, saturates to `i64::MAX` and lands in the last bucket.
+
+use crate::common::timestamp::elapsed_micros;
+
+/// Number of histogram buckets: four exact buckets for gaps 0..4, then four
+/// sub-buckets for each power of two from 2^2 up to 2^62.
+const NUM_BUCKETS: usize = 4 + 61 * 4;
+
+/// Returns the histogram bucket for a non-negative gap.
+const fn bucket_index(gap_us: i64) -> usize {
+    let gap = gap_us as u64;
+    if gap < 4 {
+        return gap as usize;
+    }
+    let log2 = 63 - gap.leading_zeros() as usize;
+    let sub = ((gap >> (log2 - 2)) & 3) as usize;
+    4 + (log2 - 2) * 4 + sub
+}
+
+/// Returns the midpoint of a histogram bucket.
+const fn bucket_midpoint(idx: usize) -> i64 {
+    if idx < 4 {
+        return idx as i64;
+    }
+    let log2 = (idx - 4) / 4 + 2;
+    let sub = ((idx - 4) % 4) as i64;
+    let width = 1i64 << (log2 - 2);
+    (4 + sub) * width + width / 2
+}
+
+/// Gap summary returned by [`EventGapStatsState::finalize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GapStats {
+    /// Smallest gap in microseconds.
+    pub min_gap_us: i64,
+    /// Largest gap in microseconds.
+    pub max_gap_us: i64,
+    /// Approximate median gap in microseconds.
+    pub median_gap_us: i64,
+    /// Number of gaps (timestamps minus one).
+    pub count: i64,
+}
+
+/// State for the `event_gap_stats` aggregate function.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct EventGapStatsState {
+    /// Earliest timestamp in this segment (microseconds since epoch).
+    pub first_ts: Option<i64>,
+    /// Latest timestamp in this segment (microseconds since epoch).
+    pub last_ts: Option<i64>,
+    /// Smallest gap seen.
+    pub min_gap_us: i64,
+    /// Largest gap seen.
+    pub max_gap_us: i64,
+    /// Number of gaps seen.
+    pub count: i64,
+    /// Gap histogram, allocated on the first gap.
+    histogram: Vec<u64>,
+}
+
+impl EventGapStatsState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            first_ts: None,
+            last_ts: None,
+            min_gap_us: i64::MAX,
+            max_gap_us: i64::MIN,
+            count: 0,
+            histogram: Vec::new(),
+        }
+    }
+
+    /// Records the gap from `from` to a timestamp `to` no earlier than it,
+    /// saturating at `i64::MAX`.
+    fn add_gap(&mut self, from: i64, to: i64) {
+        let gap_us = elapsed_micros(from, to).unwrap_or(i64::MAX);
+        if self.histogram.is_empty() {
+            self.histogram = vec![0; NUM_BUCKETS];
+        }
+        self.histogram[bucket_index(gap_us)] += 1;
+        self.min_gap_us = self.min_gap_us.min(gap_us);
+        self.max_gap_us = self.max_gap_us.max(gap_us);
+        self.count += 1;
+    }
+
+    /// Adds the next timestamp of an ordered stream.
+    ///
+    /// A timestamp earlier than the previous one only extends `first_ts`.
+    pub fn update(&mut self, timestamp_us: i64) {
+        match self.last_ts {
+            None => {
+                self.first_ts = Some(timestamp_us);
+                self.last_ts = Some(timestamp_us);
+            }
+            Some(prev) if timestamp_us >= prev => {
+                self.add_gap(prev, timestamp_us);
+                self.last_ts = Some(timestamp_us);
+            }
+            Some(_) => {
+                if self.first_ts.is_some_and(|first| timestamp_us < first) {
+                    self.first_ts = Some(timestamp_us);
+                }
+            }
+        }
+    }
+
+    /// Combines another state into `self` in O(1) (plus a fixed-size
+    /// histogram merge).
+    ///
+    /// The earlier segment (by `first_ts`) is treated as the left side; the
+    /// gap between its `last_ts` and the right side's `first_ts` is added when
+    /// the segments do not overlap.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        let (Some(self_first), Some(other_first)) = (self.first_ts, other.first_ts) else {
+            if self.first_ts.is_none() {
+                self.clone_from(other);
+            }
+            return;
+        };
+
+        let (left_last, right_first) = if self_first <= other_first {
+            (self.last_ts, other_first)
+        } else {
+            (other.last_ts, self_first)
+        };
+        if let Some(left_last) = left_last {
+            if right_first >= left_last {
+                self.add_gap(left_last, right_first);
+            }
+        }
+
+        if !other.histogram.is_empty() {
+            if self.histogram.is_empty() {
+                self.histogram = vec![0; NUM_BUCKETS];
+            }
+            for (dst, src) in self.histogram.iter_mut().zip(&other.histogram) {
+                *dst += src;
+            }
+        }
+        self.min_gap_us = self.min_gap_us.min(other.min_gap_us);
+        self.max_gap_us = self.max_gap_us.max(other.max_gap_us);
+        self.count += other.count;
+        self.first_ts = Some(self_first.min(other_first));
+        self.last_ts = self.last_ts.max(other.last_ts);
+    }
+
+    /// Returns the gap summary, or `None` if fewer than two timestamps were seen.
+    #[must_use]
+    pub fn finalize(&self) -> Option<GapStats> {
+        if self.count == 0 {
+            return None;
+        }
+        // Lower median: the gap of rank (count - 1) / 2
+        let target = (self.count as u64 - 1) / 2;
+        let mut seen = 0;
+        let mut median = self.max_gap_us;
+        for (idx, &n) in self.histogram.iter().enumerate() {
+            seen += n;
+            if seen > target {
+                median = bucket_midpoint(idx);
+                break;
+            }
+        }
+        Some(GapStats {
+            min_gap_us: self.min_gap_us,
+            max_gap_us: self.max_gap_us,
+            median_gap_us: median.clamp(self.min_gap_us, self.max_gap_us),
+            count: self.count,
+        })
+    }
+}
+
+impl Default for EventGapStatsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: i64 = 60_000_000;
+
+    fn state_from(timestamps: &[i64]) -> EventGapStatsState {
+        let mut s = EventGapStatsState::new();
+        for &ts in timestamps {
+            s.update(ts);
+        }
+        s
+    }
+
+    #[test]
+    fn test_empty_and_single_timestamp() {
+        assert_eq!(EventGapStatsState::new().finalize(), None);
+        assert_eq!(state_from(&[100]).finalize(), None);
+    }
+
+    #[test]
+    fn test_exact_min_max_count() {
+        let stats = state_from(&[0, MIN, 3 * MIN, 10 * MIN]).finalize().unwrap();
+        assert_eq!(stats.min_gap_us, MIN);
+        assert_eq!(stats.max_gap_us, 7 * MIN);
+        assert_eq!(stats.count, 3);
+    }
+
+    #[test]
+    fn test_median_within_relative_error() {
+        // Gaps: 1, 2, 5, 30, 120 minutes -> median 5 minutes
+        let stats = state_from(&[0, MIN, 3 * MIN, 8 * MIN, 38 * MIN, 158 * MIN])
+            .finalize()
+            .unwrap();
+        let error = (stats.median_gap_us - 5 * MIN).abs() as f64 / (5 * MIN) as f64;
+        assert!(
+            error < 0.125,
+            "median {} off by {error}",
+            stats.median_gap_us
+        );
+    }
+
+    #[test]
+    fn test_median_exact_for_small_gaps() {
+        let stats = state_from(&[0, 1, 3, 6]).finalize().unwrap();
+        assert_eq!(stats.median_gap_us, 2);
+    }
+
+    #[test]
+    fn test_duplicate_timestamps_are_zero_gaps() {
+        let stats = state_from(&[5, 5, 5]).finalize().unwrap();
+        assert_eq!(stats.min_gap_us, 0);
+        assert_eq!(stats.max_gap_us, 0);
+        assert_eq!(stats.median_gap_us, 0);
+        assert_eq!(stats.count, 2);
+    }
+
+    #[test]
+    fn test_out_of_order_timestamp_adds_no_gap() {
+        let stats = state_from(&[10, 20, 5]).finalize().unwrap();
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.max_gap_us, 10);
+    }
+
+    #[test]
+    fn test_infinite_gap_saturates() {
+        let stats = state_from(&[i64::MIN, 0, i64::MAX]).finalize().unwrap();
+        assert_eq!(stats.min_gap_us, i64::MAX);
+        assert_eq!(stats.count, 2);
+
+        let stats = state_from(&[i64::MIN, i64::MAX]).finalize().unwrap();
+        assert_eq!(stats.min_gap_us, i64::MAX);
+        assert_eq!(stats.max_gap_us, i64::MAX);
+        assert_eq!(bucket_index(stats.median_gap_us), NUM_BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_combine_infinite_cross_gap_saturates() {
+        let mut left = state_from(&[i64::MIN]);
+        left.combine_in_place(&state_from(&[i64::MAX]));
+        let stats = left.finalize().unwrap();
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.max_gap_us, i64::MAX);
+
+        let mut right = state_from(&[0, i64::MAX]);
+        right.combine_in_place(&state_from(&[i64::MIN]));
+        assert_eq!(right.finalize().unwrap().min_gap_us, i64::MAX);
+    }
+
+    #[test]
+    fn test_combine_disjoint_adds_cross_gap() {
+        let whole = state_from(&[0, MIN, 5 * MIN, 6 * MIN]).finalize();
+        let mut left = state_from(&[0, MIN]);
+        let right = state_from(&[5 * MIN, 6 * MIN]);
+        let mut swapped = right.clone();
+        left.combine_in_place(&right);
+        swapped.combine_in_place(&state_from(&[0, MIN]));
+        assert_eq!(left.finalize(), whole);
+        assert_eq!(swapped.finalize(), whole);
+        assert_eq!(left.finalize().unwrap().max_gap_us, 4 * MIN);
+    }
+
+    #[test]
+    fn test_combine_single_timestamp_partials() {
+        let mut target = EventGapStatsState::new();
+        for ts in [0, 10, 30] {
+            target.combine_in_place(&state_from(&[ts]));
+        }
+        let stats = target.finalize().unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.min_gap_us, 10);
+        assert_eq!(stats.max_gap_us, 20);
+    }
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let source = state_from(&[0, MIN, 3 * MIN]);
+        let mut target = EventGapStatsState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.finalize(), source.finalize());
+    }
+
+    #[test]
+    fn test_bucket_midpoint_in_bucket() {
+        for gap in [0, 1, 3, 4, 7, 100, 12_345, MIN, i64::MAX] {
+            let idx = bucket_index(gap);
+            assert!(idx < NUM_BUCKETS);
+            let mid = bucket_midpoint(idx);
+            assert_eq!(bucket_index(mid), idx, "gap {gap}");
+        }
+    }
+}
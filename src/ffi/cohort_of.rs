@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `cohort_of` scalar function.
+//!
+//! Registered with the raw `libduckdb-sys` scalar function API, like
+//! [`parse_duration`](super::parse_duration). The period is validated as the
+//! `retention_by_period` period is, and the index is computed by
+//! [`cohort_index`].
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::error::{interval_message, non_positive_message, overflow_message, report_scalar};
+use crate::retention::cohort_index;
+use libduckdb_sys::*;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+/// Registers the `cohort_of` function with `DuckDB`.
+///
+/// Signature: `cohort_of(TIMESTAMP, TIMESTAMP, INTERVAL) -> BIGINT`
+///
+/// Returns the index of the period of length `period` that `ts` falls in,
+/// counting from `anchor`; negative before the anchor. `NULL` in any argument
+/// produces `NULL`; a month-based or non-positive period fails the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_cohort_of(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, c"cohort_of".as_ptr());
+
+        for param_type in [
+            DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP,
+            DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP,
+            DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL,
+        ] {
+            let mut param = duckdb_create_logical_type(param_type);
+            duckdb_scalar_function_add_parameter(func, param);
+            duckdb_destroy_logical_type(&mut param);
+        }
+
+        let mut bigint = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BIGINT);
+        duckdb_scalar_function_set_return_type(func, bigint);
+        duckdb_destroy_logical_type(&mut bigint);
+
+        duckdb_scalar_function_set_function(func, Some(cohort_of_function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register cohort_of function");
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (TIMESTAMP,
+// TIMESTAMP, INTERVAL) as registered. `output` is a valid BIGINT vector sized
+// for the chunk.
+unsafe extern "C" fn cohort_of_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let ts_reader = VectorReader::new(input, 0);
+        let anchor_reader = VectorReader::new(input, 1);
+        let period_reader = VectorReader::new(input, 2);
+        let mut writer = VectorWriter::new(output);
+
+        for row in 0..row_count {
+            if !ts_reader.is_valid(row)
+                || !anchor_reader.is_valid(row)
+                || !period_reader.is_valid(row)
+            {
+                writer.set_null(row);
+                continue;
+            }
+
+            let iv = period_reader.read_interval(row);
+            let Some(period_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                report_scalar(
+                    info,
+                    &interval_message("cohort_of", iv.months, iv.days, iv.micros),
+                );
+                return;
+            };
+            if period_us <= 0 {
+                report_scalar(
+                    info,
+                    &non_positive_message("cohort_of", "period", period_us),
+                );
+                return;
+            }
+
+            match cohort_index(
+                anchor_reader.read_i64(row),
+                ts_reader.read_i64(row),
+                period_us,
+            ) {
+                Ok(index) => writer.write_i64(row, index),
+                Err(e) => {
+                    report_scalar(info, &overflow_message("cohort_of", &e));
+                    return;
+                }
+            }
+        }
+    }
+}
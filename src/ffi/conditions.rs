@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Shared reading of condition columns for the multi-condition aggregates.
+//!
+//! Every condition-taking function (`retention`, `window_funnel`, the
+//! `sequence_*` family) is registered twice per arity: once with `BOOLEAN`
+//! condition columns and once with `INTEGER` condition columns, so queries
+//! ported from systems that encode flags as `0`/`1` work unmodified.
+//!
+//! The arities are generated by `AggregateFunctionSetBuilder::overloads` over
+//! the shared [`MIN_CONDITIONS`]`..=`[`MAX_CONDITIONS`] range, so every
+//! function accepts the same condition counts. Two functions narrow the upper
+//! bound to 32 because they store or return a `u32` bitmask:
+//! `sequence_next_node` (which also accepts a single condition) and
+//! `window_funnel_mask`.
+//!
+//! Coercion is identical across functions:
+//!
+//! | Input | Result |
+//! |---|---|
+//! | `true` / non-zero integer | `true` |
+//! | `false` / `0` | `false` |
+//! | `NULL` | `false` |
+//!
+//! Narrower integer types (`TINYINT`, `SMALLINT`) reach the `INTEGER`
+//! overloads through `DuckDB`'s implicit widening casts.
+//!
+//! Event-collecting update callbacks read a whole chunk up front with
+//! [`chunk_events`]: condition columns are packed a column at a time by
+//! [`pack_column`] straight from the vector data and validity masks, instead
+//! of one [`ConditionReader::read`] call per condition per row.
+
+use crate::common::column::{pack_column, ChunkEvents};
+use libduckdb_sys::*;
+use quack_rs::types::TypeId;
+use quack_rs::vector::VectorReader;
+
+/// Minimum number of condition parameters of the multi-condition aggregates.
+pub const MIN_CONDITIONS: usize = 2;
+
+/// Maximum number of condition parameters: one bit each in the `u64` mask
+/// built by [`pack_chunk`].
+pub const MAX_CONDITIONS: usize = 64;
+
+/// Column types accepted for condition parameters, in registration order.
+pub const CONDITION_TYPES: [TypeId; 2] = [TypeId::Boolean, TypeId::Integer];
+
+/// Physical encoding of a condition column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionKind {
+    /// `BOOLEAN` column: one byte per row, non-zero is `true`.
+    Boolean,
+    /// `INTEGER` column: four bytes per row, non-zero is `true`.
+    Integer,
+}
+
+impl ConditionKind {
+    /// Maps a registered condition column type to its encoding.
+    ///
+    /// Anything other than `INTEGER` is read as `BOOLEAN`, which is the only
+    /// other type the condition overloads are registered with.
+    pub const fn from_type_id(type_id: TypeId) -> Self {
+        match type_id {
+            TypeId::Integer => Self::Integer,
+            _ => Self::Boolean,
+        }
+    }
+}
+
+/// Reads one condition column with NULL-safe, type-aware coercion.
+pub struct ConditionReader {
+    reader: VectorReader,
+    kind: ConditionKind,
+}
+
+impl ConditionReader {
+    /// Creates a reader for column `col_idx` of `chunk`, detecting whether the
+    /// column holds `BOOLEAN` or `INTEGER` data.
+    ///
+    /// # Safety
+    ///
+    /// `chunk` must be a valid data chunk and `col_idx` a valid column index.
+    pub unsafe fn new(chunk: duckdb_data_chunk, col_idx: usize) -> Self {
+        unsafe {
+            let vector = duckdb_data_chunk_get_vector(chunk, col_idx as idx_t);
+            Self {
+                reader: VectorReader::new(chunk, col_idx),
+                kind: vector_kind(vector),
+            }
+        }
+    }
+
+    /// Returns true if the condition at row `idx` is non-`NULL`.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than the chunk's row count.
+    pub unsafe fn is_valid(&self, idx: usize) -> bool {
+        unsafe { self.reader.is_valid(idx) }
+    }
+
+    /// Returns the coerced condition value at row `idx` (`NULL` is `false`).
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than the chunk's row count.
+    pub unsafe fn read(&self, idx: usize) -> bool {
+        unsafe {
+            if !self.reader.is_valid(idx) {
+                return false;
+            }
+            match self.kind {
+                ConditionKind::Boolean => self.reader.read_bool(idx),
+                ConditionKind::Integer => self.reader.read_i32(idx) != 0,
+            }
+        }
+    }
+}
+
+/// Creates condition readers for columns `start..end` of `chunk`.
+///
+/// # Safety
+///
+/// `chunk` must be a valid data chunk with at least `end` columns.
+pub unsafe fn condition_readers(
+    chunk: duckdb_data_chunk,
+    start: usize,
+    end: usize,
+) -> Vec<ConditionReader> {
+    (start..end)
+        .map(|c| unsafe { ConditionReader::new(chunk, c) })
+        .collect()
+}
+
+/// Detects whether `vector` holds `BOOLEAN` or `INTEGER` condition data.
+///
+/// # Safety
+///
+/// `vector` must be a valid vector.
+unsafe fn vector_kind(vector: duckdb_vector) -> ConditionKind {
+    unsafe {
+        let logical_type = duckdb_vector_get_column_type(vector);
+        let type_id = TypeId::from_duckdb_type(duckdb_get_type_id(logical_type));
+        duckdb_destroy_logical_type(&mut { logical_type });
+        ConditionKind::from_type_id(type_id)
+    }
+}
+
+/// Returns the first `row_count` values of `vector`'s data buffer.
+///
+/// # Safety
+///
+/// `vector` must be a flat vector of at least `row_count` values of type `T`,
+/// and the slice must not outlive the callback that received it.
+unsafe fn vector_data<'a, T>(vector: duckdb_vector, row_count: usize) -> &'a [T] {
+    unsafe { std::slice::from_raw_parts(duckdb_vector_get_data(vector).cast::<T>(), row_count) }
+}
+
+/// Returns the validity words of `vector`, or an empty slice when it has no
+/// validity mask (every row is valid).
+///
+/// # Safety
+///
+/// As for [`vector_data`].
+unsafe fn vector_validity<'a>(vector: duckdb_vector, row_count: usize) -> &'a [u64] {
+    unsafe {
+        let validity = duckdb_vector_get_validity(vector);
+        if validity.is_null() {
+            return &[];
+        }
+        std::slice::from_raw_parts(validity, row_count.div_ceil(64))
+    }
+}
+
+/// Packs condition columns `start..end` of `chunk` into one `u64` bitmask per
+/// row (bit `c` set when column `start + c` is true), a column at a time.
+/// At most 64 columns are supported.
+///
+/// `BOOLEAN` data is read as bytes, so the unspecified contents of `NULL`
+/// slots are never interpreted as `bool`.
+///
+/// # Safety
+///
+/// `chunk` must be a valid data chunk with at least `end` columns.
+pub unsafe fn pack_chunk(chunk: duckdb_data_chunk, start: usize, end: usize) -> Vec<u64> {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(chunk) as usize;
+        let mut masks = vec![0u64; row_count];
+        for (bit, col) in (start..end).enumerate() {
+            let vector = duckdb_data_chunk_get_vector(chunk, col as idx_t);
+            let validity = vector_validity(vector, row_count);
+            match vector_kind(vector) {
+                ConditionKind::Boolean => {
+                    pack_column::<u8>(&mut masks, bit, vector_data(vector, row_count), validity);
+                }
+                ConditionKind::Integer => {
+                    pack_column::<i32>(&mut masks, bit, vector_data(vector, row_count), validity);
+                }
+            }
+        }
+        masks
+    }
+}
+
+/// Reads the `TIMESTAMP` column `ts_col` and the condition columns
+/// `start..end` of `chunk` into one event per row.
+///
+/// # Safety
+///
+/// `chunk` must be a valid data chunk with a `TIMESTAMP` column at `ts_col`
+/// and at least `end` columns.
+pub unsafe fn chunk_events(
+    chunk: duckdb_data_chunk,
+    ts_col: usize,
+    start: usize,
+    end: usize,
+) -> ChunkEvents {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(chunk) as usize;
+        let masks = pack_chunk(chunk, start, end);
+        let vector = duckdb_data_chunk_get_vector(chunk, ts_col as idx_t);
+        ChunkEvents::new(
+            vector_data(vector, row_count),
+            vector_validity(vector, row_count),
+            &masks,
+        )
+    }
+}
+
+/// Like [`chunk_events`], with the `BIGINT` sequence-number column `seq_col`
+/// attached through [`ChunkEvents::with_seqs`].
+///
+/// # Safety
+///
+/// As for [`chunk_events`], and column `seq_col` must be `BIGINT`.
+pub unsafe fn chunk_events_with_seq(
+    chunk: duckdb_data_chunk,
+    ts_col: usize,
+    seq_col: usize,
+    start: usize,
+    end: usize,
+) -> ChunkEvents {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(chunk) as usize;
+        let vector = duckdb_data_chunk_get_vector(chunk, seq_col as idx_t);
+        chunk_events(chunk, ts_col, start, end).with_seqs(
+            vector_data(vector, row_count),
+            vector_validity(vector, row_count),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_condition_kind_from_type_id() {
+        assert_eq!(
+            ConditionKind::from_type_id(TypeId::Boolean),
+            ConditionKind::Boolean
+        );
+        assert_eq!(
+            ConditionKind::from_type_id(TypeId::Integer),
+            ConditionKind::Integer
+        );
+    }
+
+    #[test]
+    fn test_condition_range_fits_bitmask() {
+        assert!(MIN_CONDITIONS <= MAX_CONDITIONS);
+        assert_eq!(MAX_CONDITIONS, u64::BITS as usize);
+    }
+
+    #[test]
+    fn test_condition_types_cover_both_kinds() {
+        let kinds: Vec<ConditionKind> = CONDITION_TYPES
+            .iter()
+            .map(|&t| ConditionKind::from_type_id(t))
+            .collect();
+        assert_eq!(kinds, vec![ConditionKind::Boolean, ConditionKind::Integer]);
+    }
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `behavioral_demo_events` table function.
+//!
+//! Registered with the raw `libduckdb-sys` table function API like
+//! [`explain`](super::explain). The bind callback validates the arguments;
+//! the init callback creates the [`DemoEvents`] generator, which the main
+//! callback drains one chunk at a time, so the rows are never materialized.
+
+use crate::demo_events::{DemoEvents, MAX_DEMO_USERS};
+use crate::ffi::error::range_message;
+use libduckdb_sys::*;
+use quack_rs::vector::VectorWriter;
+use std::ffi::{c_void, CStr, CString};
+
+/// Result columns, in order.
+const COLUMNS: [(&CStr, DUCKDB_TYPE); 5] = [
+    (c"user_id", DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+    (c"event_time", DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP),
+    (c"event_type", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+    (c"page", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+    (c"channel", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+];
+
+/// Bind data: the generator arguments, `None` when either is `NULL`.
+struct DemoArgs {
+    args: Option<(u64, u64)>,
+}
+
+/// Registers the `behavioral_demo_events` function with `DuckDB`.
+///
+/// Signature: `behavioral_demo_events(BIGINT, BIGINT) -> TABLE(user_id
+/// BIGINT, event_time TIMESTAMP, event_type VARCHAR, page VARCHAR, channel
+/// VARCHAR)`
+///
+/// The events of `n_users` synthetic users generated from `seed`. A `NULL`
+/// argument produces no rows; `n_users` outside `0..=10000000` fails the
+/// query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_behavioral_demo_events(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_table_function();
+        duckdb_table_function_set_name(func, c"behavioral_demo_events".as_ptr());
+
+        let mut bigint = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BIGINT);
+        duckdb_table_function_add_parameter(func, bigint);
+        duckdb_table_function_add_parameter(func, bigint);
+        duckdb_destroy_logical_type(&mut bigint);
+
+        duckdb_table_function_set_bind(func, Some(demo_bind));
+        duckdb_table_function_set_init(func, Some(demo_init));
+        duckdb_table_function_set_function(func, Some(demo_function));
+
+        let result = duckdb_register_table_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register behavioral_demo_events function");
+        }
+
+        duckdb_destroy_table_function(&mut { func });
+    }
+}
+
+/// Reads BIGINT parameter `index`, `None` when it is `NULL`.
+///
+/// # Safety
+///
+/// `info` must be a valid bind info with a BIGINT parameter at `index`.
+unsafe fn bigint_parameter(info: duckdb_bind_info, index: idx_t) -> Option<i64> {
+    unsafe {
+        let mut param = duckdb_bind_get_parameter(info, index);
+        let value = (!duckdb_is_null_value(param)).then(|| duckdb_get_int64(param));
+        duckdb_destroy_value(&mut param);
+        value
+    }
+}
+
+// SAFETY: `info` is a valid bind info for a call with two BIGINT arguments.
+unsafe extern "C" fn demo_bind(info: duckdb_bind_info) {
+    unsafe {
+        for &(name, type_id) in &COLUMNS {
+            let mut column_type = duckdb_create_logical_type(type_id);
+            duckdb_bind_add_result_column(info, name.as_ptr(), column_type);
+            duckdb_destroy_logical_type(&mut column_type);
+        }
+
+        let n_users = bigint_parameter(info, 0);
+        let seed = bigint_parameter(info, 1);
+        if let Some(n) = n_users.filter(|&n| n < 0 || n as u64 > MAX_DEMO_USERS) {
+            let message = range_message(
+                "behavioral_demo_events",
+                "n_users",
+                n,
+                0,
+                MAX_DEMO_USERS as i64,
+            );
+            let message = CString::new(message).unwrap_or_default();
+            duckdb_bind_set_error(info, message.as_ptr());
+            return;
+        }
+
+        let args = DemoArgs {
+            args: n_users.zip(seed).map(|(n, seed)| (n as u64, seed as u64)),
+        };
+        duckdb_bind_set_bind_data(
+            info,
+            Box::into_raw(Box::new(args)).cast(),
+            Some(drop_boxed::<DemoArgs>),
+        );
+    }
+}
+
+// SAFETY: `info` is a valid init info whose bind succeeded with `DemoArgs`.
+unsafe extern "C" fn demo_init(info: duckdb_init_info) {
+    unsafe {
+        let args = &*duckdb_init_get_bind_data(info).cast::<DemoArgs>();
+        let (n_users, seed) = args.args.unwrap_or((0, 0));
+        duckdb_init_set_init_data(
+            info,
+            Box::into_raw(Box::new(DemoEvents::new(n_users, seed))).cast(),
+            Some(drop_boxed::<DemoEvents>),
+        );
+    }
+}
+
+// SAFETY: `info` carries the `DemoEvents` init data set above. `output` is a
+// valid data chunk with the columns of `COLUMNS`.
+unsafe extern "C" fn demo_function(info: duckdb_function_info, output: duckdb_data_chunk) {
+    unsafe {
+        let events = &mut *duckdb_function_get_init_data(info).cast::<DemoEvents>();
+
+        let capacity = duckdb_vector_size() as usize;
+        let mut writers: Vec<VectorWriter> = (0..COLUMNS.len())
+            .map(|col| VectorWriter::new(duckdb_data_chunk_get_vector(output, col as idx_t)))
+            .collect();
+
+        let mut rows = 0;
+        for (row, event) in events.by_ref().take(capacity).enumerate() {
+            writers[0].write_i64(row, event.user_id);
+            writers[1].write_i64(row, event.event_time_us);
+            writers[2].write_varchar(row, event.event_type);
+            writers[3].write_varchar(row, event.page);
+            writers[4].write_varchar(row, event.channel);
+            rows = row + 1;
+        }
+
+        duckdb_data_chunk_set_size(output, rows as idx_t);
+    }
+}
+
+/// Drops bind or init data created with `Box::into_raw(Box::new(T))`.
+unsafe extern "C" fn drop_boxed<T>(data: *mut c_void) {
+    unsafe { drop(Box::from_raw(data.cast::<T>())) }
+}
@@ -0,0 +1,359 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Error reporting from aggregate callbacks to `DuckDB`.
+//!
+//! Invalid arguments — malformed patterns, unknown `window_funnel` modes,
+//! unknown `sequence_next_node` directions or bases, month-based intervals,
+//! non-positive row limits, negative session lengths — abort the query with a message naming the
+//! function, the offending input and, where the parser knows it, the byte
+//! offset. So do results that cannot be represented, such as a time difference
+//! overflowing `i64` microseconds, and out-of-order events under
+//! `sequence_match`'s `'verify_sorted'`. They are never mapped to `NULL` or `0`, which would be
+//! indistinguishable from "no match".
+//!
+//! The C API has no bind callback for aggregates, so [`PatternCheck`] validates
+//! pattern strings in the update callback instead: a `(?N)` beyond the number of
+//! condition arguments fails on the first chunk rather than silently never
+//! matching.
+//!
+//! Messages are built by the `*_message` functions (pure, unit-tested) and
+//! delivered through [`report`], which wraps `duckdb_aggregate_function_set_error`.
+//! `DuckDB` checks the error flag after every update, combine and finalize
+//! callback, so reporting from any of them fails the query.
+//! Scalar functions report through [`report_scalar`] instead.
+
+use crate::common::timestamp::TimestampOverflow;
+use crate::pattern::cache;
+use crate::pattern::parser::PatternError;
+use crate::sequence::UnsortedEvents;
+use crate::sequence_next_node::NoBaseEvent;
+use crate::window_funnel::{FunnelMode, TieBreak};
+use libduckdb_sys::duckdb_function_info;
+use quack_rs::aggregate::AggregateFunctionInfo;
+
+/// Reports `message` as the error of the current aggregate callback.
+///
+/// # Safety
+///
+/// `info` must be the `duckdb_function_info` passed to the running callback.
+pub unsafe fn report(info: duckdb_function_info, message: &str) {
+    unsafe { AggregateFunctionInfo::new(info) }.set_error(message);
+}
+
+/// Reports `message` as the error of the current scalar function call.
+///
+/// NUL bytes, which an echoed input may contain, are dropped.
+///
+/// # Safety
+///
+/// `info` must be the `duckdb_function_info` passed to the running scalar
+/// function.
+pub unsafe fn report_scalar(info: duckdb_function_info, message: &str) {
+    let message = std::ffi::CString::new(message.replace('\0', "")).unwrap_or_default();
+    unsafe { libduckdb_sys::duckdb_scalar_function_set_error(info, message.as_ptr()) };
+}
+
+/// Validates the pattern strings seen by one update callback.
+///
+/// Each distinct pattern is compiled through the pattern cache and checked against the number of
+/// condition columns in the chunk; repeats of the last valid pattern (the
+/// common constant-pattern case) are a string comparison.
+pub struct PatternCheck {
+    function: &'static str,
+    conditions: usize,
+    last_valid: Option<String>,
+}
+
+impl PatternCheck {
+    /// Creates a check for `function` called with `conditions` condition
+    /// arguments.
+    pub const fn new(function: &'static str, conditions: usize) -> Self {
+        Self {
+            function,
+            conditions,
+            last_valid: None,
+        }
+    }
+
+    /// Returns the error message for `pattern`, or `None` if it is valid.
+    pub fn message(&mut self, pattern: &str) -> Option<String> {
+        if self.last_valid.as_deref() == Some(pattern) {
+            return None;
+        }
+        match cache::compile(pattern).and_then(|p| p.check_conditions(self.conditions)) {
+            Ok(()) => {
+                self.last_valid = Some(pattern.to_string());
+                None
+            }
+            Err(e) => Some(pattern_message(self.function, pattern, &e)),
+        }
+    }
+
+    /// Reports an invalid `pattern` and returns `false`; returns `true` if it
+    /// is valid.
+    ///
+    /// # Safety
+    ///
+    /// `info` must be the `duckdb_function_info` passed to the running callback.
+    pub unsafe fn check(&mut self, info: duckdb_function_info, pattern: &str) -> bool {
+        let Some(message) = self.message(pattern) else {
+            return true;
+        };
+        unsafe { report(info, &message) };
+        false
+    }
+}
+
+/// Message for a pattern that failed to parse.
+///
+/// Names the failing step and, when the parser has one, a corrected step,
+/// e.g. `... in pattern '(?1)(?t=>5)' near '(?t=>5)'; did you mean '(?t>=5)'?`.
+pub fn pattern_message(function: &str, pattern: &str, error: &PatternError) -> String {
+    let mut message = format!(
+        "{function}: {} at offset {} in pattern '{pattern}'",
+        error.message, error.position
+    );
+    if !error.token.is_empty() {
+        message.push_str(&format!(" near '{}'", error.token));
+    }
+    if let Some(suggestion) = &error.suggestion {
+        message.push_str(&format!("; did you mean '{suggestion}'?"));
+    }
+    message
+}
+
+/// Message for a `labels` list whose length differs from the number of
+/// `(?N)` steps in `pattern`.
+pub fn labels_message(function: &str, pattern: &str, labels: usize, steps: usize) -> String {
+    format!("{function}: {labels} labels for {steps} (?N) steps in pattern '{pattern}'")
+}
+
+/// Message for a `window_funnel` list of minimum step gaps whose length is
+/// not one less than the number of steps.
+pub fn min_gaps_message(function: &str, gaps: usize, steps: usize) -> String {
+    format!(
+        "{function}: {gaps} minimum gaps for {steps} steps (expected {}, one between each pair of consecutive steps)",
+        steps.saturating_sub(1)
+    )
+}
+
+/// Message for a `window_funnel` mode string with an unrecognized token.
+///
+/// `token` is the unrecognized mode name as returned by
+/// [`FunnelMode::parse_modes`]; its offset is recovered from `modes`.
+pub fn mode_message(function: &str, modes: &str, token: &str) -> String {
+    let offset = modes.find(token).unwrap_or(0);
+    format!(
+        "{function}: unknown mode '{token}' at offset {offset} in '{modes}' (expected a comma-separated list of: {}, skip(K), tie_break({}))",
+        FunnelMode::MODE_NAMES.join(", "),
+        TieBreak::NAMES.join("|")
+    )
+}
+
+/// Message for an interval that cannot be converted to exact microseconds.
+pub fn interval_message(function: &str, months: i32, days: i32, micros: i64) -> String {
+    if months != 0 {
+        format!(
+            "{function}: interval with {months} month(s) is not supported (months vary in length); use days, hours, minutes or seconds"
+        )
+    } else {
+        format!("{function}: interval of {days} days and {micros} microseconds overflows")
+    }
+}
+
+/// Message for an unrecognized `sequence_next_node` direction or base string.
+pub fn choice_message(function: &str, what: &str, value: &str, expected: &[&str]) -> String {
+    format!(
+        "{function}: unknown {what} '{value}' (expected one of: {})",
+        expected.join(", ")
+    )
+}
+
+/// Message for a row limit that is not a positive integer.
+pub fn limit_message(function: &str, what: &str, value: i64) -> String {
+    format!("{function}: {what} must be a positive integer, got {value}")
+}
+
+/// Message for an integer argument outside `min..=max`.
+pub fn range_message(function: &str, what: &str, value: i64, min: i64, max: i64) -> String {
+    format!("{function}: {what} must be between {min} and {max}, got {value}")
+}
+
+/// Message for a time difference that overflows `i64` microseconds.
+pub fn overflow_message(function: &str, error: &TimestampOverflow) -> String {
+    format!("{function}: {error}")
+}
+
+/// Message for events out of timestamp order under `'verify_sorted'`.
+pub fn order_message(function: &str, error: &UnsortedEvents) -> String {
+    format!("{function}: {error}")
+}
+
+/// Message for a group without a base event under `'missing_base(error)'`.
+pub fn base_message(function: &str, error: &NoBaseEvent) -> String {
+    format!("{function}: {error}")
+}
+
+/// Message for a negative interval argument, `value_us` in microseconds.
+pub fn negative_message(function: &str, what: &str, value_us: i64) -> String {
+    format!("{function}: {what} must not be negative, got {value_us} microseconds")
+}
+
+/// Message for an interval argument that must be positive, `value_us` in
+/// microseconds.
+pub fn non_positive_message(function: &str, what: &str, value_us: i64) -> String {
+    format!("{function}: {what} must be positive, got {value_us} microseconds")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::parser::parse_pattern;
+
+    #[test]
+    fn test_pattern_message_includes_offset_and_pattern() {
+        let err = parse_pattern("(?1)(?x)").unwrap_err();
+        let msg = pattern_message("sequence_match", "(?1)(?x)", &err);
+        assert!(msg.starts_with("sequence_match: "), "{msg}");
+        assert!(
+            msg.contains(&format!("at offset {}", err.position)),
+            "{msg}"
+        );
+        assert!(msg.ends_with("in pattern '(?1)(?x)' near '(?x)'"), "{msg}");
+    }
+
+    #[test]
+    fn test_pattern_message_includes_suggestion() {
+        let err = parse_pattern("(?1)(?t=>5)(?2)").unwrap_err();
+        assert_eq!(
+            pattern_message("sequence_match", "(?1)(?t=>5)(?2)", &err),
+            "sequence_match: expected comparison operator (>=, <=, >, <, ==, !=) after '(?t' at offset 7 in pattern '(?1)(?t=>5)(?2)' near '(?t=>5)'; did you mean '(?t>=5)'?"
+        );
+    }
+
+    #[test]
+    fn test_pattern_check_condition_count() {
+        let mut check = PatternCheck::new("sequence_count", 4);
+        assert_eq!(check.message("(?1).*(?4)"), None);
+        assert_eq!(check.message("(?1).*(?4)"), None);
+        assert_eq!(
+            check.message("(?1).*(?9)").as_deref(),
+            Some(
+                "sequence_count: unknown condition (?9), only 4 conditions supplied at offset 6 in pattern '(?1).*(?9)'"
+            )
+        );
+        assert!(check.message("(?1").is_some());
+    }
+
+    #[test]
+    fn test_mode_message_locates_token() {
+        let err = FunnelMode::parse_modes("strict, strcit_once").unwrap_err();
+        let msg = mode_message("window_funnel", "strict, strcit_once", &err);
+        assert!(
+            msg.starts_with("window_funnel: unknown mode 'strcit_once' at offset 8"),
+            "{msg}"
+        );
+        assert!(msg.contains("strict_once"), "{msg}");
+        assert!(
+            msg.ends_with("skip(K), tie_break(input|condition|argument))"),
+            "{msg}"
+        );
+    }
+
+    #[test]
+    fn test_interval_message_months_and_overflow() {
+        assert!(interval_message("window_funnel", 1, 0, 0).contains("1 month(s)"));
+        assert!(interval_message("sessionize", 0, i32::MAX, i64::MAX).contains("overflows"));
+    }
+
+    #[test]
+    fn test_choice_message_lists_expected() {
+        let msg = choice_message(
+            "sequence_next_node",
+            "direction",
+            "sideways",
+            &["forward", "backward"],
+        );
+        assert_eq!(
+            msg,
+            "sequence_next_node: unknown direction 'sideways' (expected one of: forward, backward)"
+        );
+    }
+
+    #[test]
+    fn test_limit_message_names_argument() {
+        assert_eq!(
+            limit_message("group_array_sorted_by_ts", "max_n", 0),
+            "group_array_sorted_by_ts: max_n must be a positive integer, got 0"
+        );
+    }
+
+    #[test]
+    fn test_range_message_names_bounds() {
+        assert_eq!(
+            range_message("window_funnel", "num_steps", 40, 1, 32),
+            "window_funnel: num_steps must be between 1 and 32, got 40"
+        );
+    }
+
+    #[test]
+    fn test_overflow_message() {
+        assert_eq!(
+            overflow_message("time_to_convert", &TimestampOverflow),
+            "time_to_convert: time difference overflows the microsecond range"
+        );
+    }
+
+    #[test]
+    fn test_order_message_names_timestamps() {
+        let error = UnsortedEvents {
+            previous_us: 300,
+            timestamp_us: 100,
+        };
+        assert_eq!(
+            order_message("sequence_match", &error),
+            "sequence_match: events are not in timestamp order with 'verify_sorted': an event at 100 microseconds arrived after one at 300"
+        );
+    }
+
+    #[test]
+    fn test_base_message_counts_events() {
+        assert_eq!(
+            base_message("sequence_next_node", &NoBaseEvent { events: 3 }),
+            "sequence_next_node: none of the group's 3 events satisfies the base condition with 'missing_base(error)'"
+        );
+    }
+
+    #[test]
+    fn test_negative_message_names_argument() {
+        assert_eq!(
+            negative_message("sessionize", "max_session_length", -60_000_000),
+            "sessionize: max_session_length must not be negative, got -60000000 microseconds"
+        );
+    }
+
+    #[test]
+    fn test_non_positive_message_names_argument() {
+        assert_eq!(
+            non_positive_message("retention_by_period", "period", 0),
+            "retention_by_period: period must be positive, got 0 microseconds"
+        );
+    }
+
+    #[test]
+    fn test_labels_message_counts_steps() {
+        assert_eq!(
+            labels_message("sequence_match_events", "(?1).*(?2)", 3, 2),
+            "sequence_match_events: 3 labels for 2 (?N) steps in pattern '(?1).*(?2)'"
+        );
+    }
+
+    #[test]
+    fn test_min_gaps_message_expects_one_per_transition() {
+        assert_eq!(
+            min_gaps_message("window_funnel", 3, 3),
+            "window_funnel: 3 minimum gaps for 3 steps (expected 2, one between each pair of consecutive steps)"
+        );
+    }
+}
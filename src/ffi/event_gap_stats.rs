@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `event_gap_stats` aggregate function.
+//!
+//! Uses [`quack_rs::aggregate::AggregateFunctionBuilder`] (single signature)
+//! with [`returns_logical`](quack_rs::aggregate::AggregateFunctionBuilder::returns_logical)
+//! for the `STRUCT` return type, and writes the result through
+//! [`quack_rs::vector::complex::StructVector`] field writers.
+
+use crate::event_gap_stats::EventGapStatsState;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::StructVector;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+impl quack_rs::aggregate::AggregateState for EventGapStatsState {}
+
+/// Registers the `event_gap_stats` function with `DuckDB`.
+///
+/// Signature: `event_gap_stats(TIMESTAMP) -> STRUCT(min_gap_us BIGINT,
+/// max_gap_us BIGINT, median_gap_us BIGINT, count BIGINT)`
+///
+/// Timestamps should be supplied in order (`event_gap_stats(ts ORDER BY ts)`).
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_event_gap_stats(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let builder = AggregateFunctionBuilder::new("event_gap_stats")
+        .param(TypeId::Timestamp)
+        .returns_logical(LogicalType::struct_type(&[
+            ("min_gap_us", TypeId::BigInt),
+            ("max_gap_us", TypeId::BigInt),
+            ("median_gap_us", TypeId::BigInt),
+            ("count", TypeId::BigInt),
+        ]));
+    let builder = with_lifecycle!(builder, EventGapStatsState)
+        .update(state_update)
+        .combine(state_combine)
+        .finalize(state_finalize);
+    unsafe { con.register_aggregate(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with a single TIMESTAMP column
+// as registered. `states` points to `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let ts_reader = VectorReader::new(input, 0);
+
+        for i in 0..row_count {
+            if !ts_reader.is_valid(i) {
+                continue;
+            }
+            let Some(state) = FfiState::<EventGapStatsState>::with_state_mut(*states.add(i)) else {
+                continue;
+            };
+            state.update(ts_reader.read_i64(i));
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<EventGapStatsState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<EventGapStatsState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB STRUCT vector with four BIGINT fields. NULL is written for
+// groups with no timestamps; the gap fields are NULL with a zero count for
+// groups with a single timestamp.
+unsafe extern "C" fn state_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+        let mut min_writer = StructVector::field_writer(result, 0);
+        let mut max_writer = StructVector::field_writer(result, 1);
+        let mut median_writer = StructVector::field_writer(result, 2);
+        let mut count_writer = StructVector::field_writer(result, 3);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<EventGapStatsState>::with_state(*source.add(i))
+                .filter(|s| s.first_ts.is_some())
+            else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            if let Some(gaps) = state.finalize() {
+                min_writer.write_i64(idx, gaps.min_gap_us);
+                max_writer.write_i64(idx, gaps.max_gap_us);
+                median_writer.write_i64(idx, gaps.median_gap_us);
+                count_writer.write_i64(idx, gaps.count);
+            } else {
+                min_writer.set_null(idx);
+                max_writer.set_null(idx);
+                median_writer.set_null(idx);
+                count_writer.write_i64(idx, 0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    const MIN: i64 = 60_000_000;
+
+    #[test]
+    fn test_event_gap_stats_config_propagation() {
+        // Zero-initialized target combine pattern (Session 10 bug).
+        let mut source = AggregateTestHarness::<EventGapStatsState>::new();
+        source.update(|s| {
+            s.update(0);
+            s.update(10 * MIN);
+        });
+
+        let mut target = AggregateTestHarness::<EventGapStatsState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.first_ts, Some(0));
+        assert_eq!(state.finalize().unwrap().max_gap_us, 10 * MIN);
+    }
+
+    #[test]
+    fn test_event_gap_stats_combine_ordered_partials() {
+        let mut early = AggregateTestHarness::<EventGapStatsState>::new();
+        early.update(|s| {
+            s.update(0);
+            s.update(MIN);
+        });
+
+        let mut late = AggregateTestHarness::<EventGapStatsState>::new();
+        late.update(|s| {
+            s.update(61 * MIN);
+            s.update(63 * MIN);
+        });
+
+        late.combine(&early, |src, tgt| tgt.combine_in_place(src));
+        let stats = late.finalize().finalize().unwrap();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.min_gap_us, MIN);
+        assert_eq!(stats.max_gap_us, 60 * MIN);
+    }
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `behavioral_explain` table function.
+//!
+//! Unlike the table macros of [`introspection`](super::introspection) and
+//! [`sessionize_table`](super::sessionize_table), the rows depend on a
+//! pattern argument, so this is a table function registered with the raw
+//! `libduckdb-sys` API. The bind callback parses the pattern with
+//! [`explain`] and stores the plan as bind data; the init callback creates a
+//! row cursor and the main callback emits the plan one chunk at a time
+//! through [`quack_rs::vector::VectorWriter`].
+
+use crate::ffi::error::pattern_message;
+use crate::pattern::executor::ExecutionPath;
+use crate::pattern::explain::{explain, StepPlan};
+use libduckdb_sys::*;
+use quack_rs::vector::VectorWriter;
+use std::ffi::{c_void, CStr, CString};
+
+/// Result columns, in order.
+const COLUMNS: [(&CStr, DUCKDB_TYPE); 8] = [
+    (c"step", DUCKDB_TYPE_DUCKDB_TYPE_INTEGER),
+    (c"position", DUCKDB_TYPE_DUCKDB_TYPE_INTEGER),
+    (c"text", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+    (c"step_type", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+    (c"condition", DUCKDB_TYPE_DUCKDB_TYPE_INTEGER),
+    (c"time_constraint", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+    (c"forces_nfa", DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+    (c"execution_path", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+];
+
+/// Bind data: the plan of the bound pattern.
+struct Plan {
+    path: ExecutionPath,
+    steps: Vec<StepPlan>,
+}
+
+/// Registers the `behavioral_explain` function with `DuckDB`.
+///
+/// Signature: `behavioral_explain(VARCHAR) -> TABLE(step INTEGER,
+/// position INTEGER, text VARCHAR, step_type VARCHAR, condition INTEGER,
+/// time_constraint VARCHAR, forces_nfa BOOLEAN, execution_path VARCHAR)`
+///
+/// One row per pattern step. A `NULL` pattern produces no rows; an invalid
+/// one fails the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_behavioral_explain(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_table_function();
+        duckdb_table_function_set_name(func, c"behavioral_explain".as_ptr());
+
+        let mut varchar = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR);
+        duckdb_table_function_add_parameter(func, varchar);
+        duckdb_destroy_logical_type(&mut varchar);
+
+        duckdb_table_function_set_bind(func, Some(explain_bind));
+        duckdb_table_function_set_init(func, Some(explain_init));
+        duckdb_table_function_set_function(func, Some(explain_function));
+
+        let result = duckdb_register_table_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register behavioral_explain function");
+        }
+
+        duckdb_destroy_table_function(&mut { func });
+    }
+}
+
+// SAFETY: `info` is a valid bind info for a call with one VARCHAR argument.
+unsafe extern "C" fn explain_bind(info: duckdb_bind_info) {
+    unsafe {
+        for &(name, type_id) in &COLUMNS {
+            let mut column_type = duckdb_create_logical_type(type_id);
+            duckdb_bind_add_result_column(info, name.as_ptr(), column_type);
+            duckdb_destroy_logical_type(&mut column_type);
+        }
+
+        let mut param = duckdb_bind_get_parameter(info, 0);
+        let pattern = if duckdb_is_null_value(param) {
+            None
+        } else {
+            let text = duckdb_get_varchar(param);
+            let pattern = CStr::from_ptr(text).to_string_lossy().into_owned();
+            duckdb_free(text.cast());
+            Some(pattern)
+        };
+        duckdb_destroy_value(&mut param);
+
+        let plan = match pattern {
+            None => Plan {
+                path: ExecutionPath::Nfa,
+                steps: Vec::new(),
+            },
+            Some(pattern) => match explain(&pattern) {
+                Ok((path, steps)) => Plan { path, steps },
+                Err(e) => {
+                    let message = pattern_message("behavioral_explain", &pattern, &e);
+                    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+                    duckdb_bind_set_error(info, message.as_ptr());
+                    return;
+                }
+            },
+        };
+        duckdb_bind_set_bind_data(
+            info,
+            Box::into_raw(Box::new(plan)).cast(),
+            Some(drop_boxed::<Plan>),
+        );
+    }
+}
+
+// SAFETY: `info` is a valid init info whose bind succeeded.
+unsafe extern "C" fn explain_init(info: duckdb_init_info) {
+    unsafe {
+        // Index of the next step to emit.
+        let cursor: Box<usize> = Box::default();
+        duckdb_init_set_init_data(
+            info,
+            Box::into_raw(cursor).cast(),
+            Some(drop_boxed::<usize>),
+        );
+    }
+}
+
+// SAFETY: `info` carries the `Plan` bind data and `usize` init data set
+// above. `output` is a valid data chunk with the columns of `COLUMNS`.
+unsafe extern "C" fn explain_function(info: duckdb_function_info, output: duckdb_data_chunk) {
+    unsafe {
+        let plan = &*duckdb_function_get_bind_data(info).cast::<Plan>();
+        let cursor = &mut *duckdb_function_get_init_data(info).cast::<usize>();
+
+        let capacity = duckdb_vector_size() as usize;
+        let rows = &plan.steps[*cursor..plan.steps.len().min(*cursor + capacity)];
+        let mut writers: Vec<VectorWriter> = (0..COLUMNS.len())
+            .map(|col| VectorWriter::new(duckdb_data_chunk_get_vector(output, col as idx_t)))
+            .collect();
+
+        for (row, step) in rows.iter().enumerate() {
+            writers[0].write_i32(row, step.step as i32);
+            writers[1].write_i32(row, step.position as i32);
+            writers[2].write_varchar(row, &step.text);
+            writers[3].write_varchar(row, step.kind);
+            match step.condition {
+                Some(condition) => writers[4].write_i32(row, condition as i32),
+                None => writers[4].set_null(row),
+            }
+            match &step.time_constraint {
+                Some(constraint) => writers[5].write_varchar(row, constraint),
+                None => writers[5].set_null(row),
+            }
+            writers[6].write_bool(row, step.forces_nfa);
+            writers[7].write_varchar(row, plan.path.as_str());
+        }
+
+        *cursor += rows.len();
+        duckdb_data_chunk_set_size(output, rows.len() as idx_t);
+    }
+}
+
+/// Drops bind or init data created with `Box::into_raw(Box::new(T))`.
+unsafe extern "C" fn drop_boxed<T>(data: *mut c_void) {
+    unsafe { drop(Box::from_raw(data.cast::<T>())) }
+}
@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `window_funnel_from_list` and
+//! `sequence_match_from_list` scalar functions.
+//!
+//! Both take a whole event list per row, as `LIST(STRUCT(ts TIMESTAMP,
+//! mask BIGINT))`, and evaluate it with a fresh [`WindowFunnelState`] or
+//! [`SequenceState`], so pre-aggregated event arrays need not be unnested
+//! back into rows. Registration uses the raw `libduckdb-sys` scalar function
+//! API, like [`selfcheck`](super::selfcheck); the two `window_funnel_from_list`
+//! overloads form a scalar function set. The list is read directly from its
+//! child vectors, as in the `retention(BOOLEAN[])` overload of
+//! [`retention`](super::retention).
+
+use crate::common::event::Event;
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::conditions::MAX_CONDITIONS;
+use crate::ffi::error::{
+    interval_message, mode_message, pattern_message, report_scalar, PatternCheck,
+};
+use crate::sequence::SequenceState;
+use crate::window_funnel::{FunnelMode, WindowFunnelState};
+use libduckdb_sys::*;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+/// Registers the `window_funnel_from_list` function with `DuckDB`.
+///
+/// Signatures:
+/// - `window_funnel_from_list(INTERVAL, LIST(STRUCT(ts TIMESTAMP, mask BIGINT))) -> INTEGER`
+/// - `window_funnel_from_list(INTERVAL, VARCHAR, LIST(STRUCT(ts TIMESTAMP, mask BIGINT))) -> INTEGER`
+///
+/// Bit `k` of `mask` is condition `k + 1`. A `NULL` argument produces `NULL`.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_window_funnel_from_list(con: duckdb_connection) {
+    unsafe {
+        let set = duckdb_create_scalar_function_set(c"window_funnel_from_list".as_ptr());
+        for with_mode in [false, true] {
+            let func = duckdb_create_scalar_function();
+            duckdb_scalar_function_set_name(func, c"window_funnel_from_list".as_ptr());
+
+            add_parameter(
+                func,
+                duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL),
+            );
+            if with_mode {
+                add_parameter(
+                    func,
+                    duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+                );
+            }
+            add_parameter(func, events_type());
+
+            let mut integer = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_INTEGER);
+            duckdb_scalar_function_set_return_type(func, integer);
+            duckdb_destroy_logical_type(&mut integer);
+
+            duckdb_scalar_function_set_function(
+                func,
+                Some(if with_mode {
+                    window_funnel_mode_function
+                } else {
+                    window_funnel_function
+                }),
+            );
+            duckdb_add_scalar_function_to_set(set, func);
+            duckdb_destroy_scalar_function(&mut { func });
+        }
+
+        let result = duckdb_register_scalar_function_set(con, set);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register window_funnel_from_list function");
+        }
+
+        duckdb_destroy_scalar_function_set(&mut { set });
+    }
+}
+
+/// Registers the `sequence_match_from_list` function with `DuckDB`.
+///
+/// Signature: `sequence_match_from_list(VARCHAR,
+/// LIST(STRUCT(ts TIMESTAMP, mask BIGINT))) -> BOOLEAN`
+///
+/// Bit `k` of `mask` is condition `(?k+1)`. A `NULL` pattern or list produces
+/// `NULL`; an invalid pattern fails the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sequence_match_from_list(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, c"sequence_match_from_list".as_ptr());
+
+        add_parameter(
+            func,
+            duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+        );
+        add_parameter(func, events_type());
+
+        let mut boolean = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN);
+        duckdb_scalar_function_set_return_type(func, boolean);
+        duckdb_destroy_logical_type(&mut boolean);
+
+        duckdb_scalar_function_set_function(func, Some(sequence_match_function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register sequence_match_from_list function");
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+/// Adds a parameter of `param_type` to `func` and destroys the type.
+unsafe fn add_parameter(func: duckdb_scalar_function, mut param_type: duckdb_logical_type) {
+    unsafe {
+        duckdb_scalar_function_add_parameter(func, param_type);
+        duckdb_destroy_logical_type(&mut param_type);
+    }
+}
+
+/// Creates the `LIST(STRUCT(ts TIMESTAMP, mask BIGINT))` event list type.
+unsafe fn events_type() -> duckdb_logical_type {
+    unsafe {
+        let mut types = [
+            duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP),
+            duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+        ];
+        let mut names = [c"ts".as_ptr(), c"mask".as_ptr()];
+        let mut event =
+            duckdb_create_struct_type(types.as_mut_ptr(), names.as_mut_ptr(), types.len() as idx_t);
+        for member in &mut types {
+            duckdb_destroy_logical_type(member);
+        }
+        let list = duckdb_create_list_type(event);
+        duckdb_destroy_logical_type(&mut event);
+        list
+    }
+}
+
+/// Pointers into an event list column and its child vectors, taken once per
+/// chunk.
+struct EventList {
+    /// NULL when every list is valid, as for the child validities below.
+    validity: *mut u64,
+    entries: *const duckdb_list_entry,
+    element_validity: *mut u64,
+    timestamps: *const i64,
+    ts_validity: *mut u64,
+    masks: *const i64,
+    mask_validity: *mut u64,
+}
+
+impl EventList {
+    /// Reads column `col` of `input`, which must be a
+    /// `LIST(STRUCT(ts TIMESTAMP, mask BIGINT))` column.
+    unsafe fn new(input: duckdb_data_chunk, col: usize) -> Self {
+        unsafe {
+            let vector = duckdb_data_chunk_get_vector(input, col as idx_t);
+            let child = duckdb_list_vector_get_child(vector);
+            let ts_vector = duckdb_struct_vector_get_child(child, 0);
+            let mask_vector = duckdb_struct_vector_get_child(child, 1);
+            Self {
+                validity: duckdb_vector_get_validity(vector),
+                entries: duckdb_vector_get_data(vector).cast::<duckdb_list_entry>(),
+                element_validity: duckdb_vector_get_validity(child),
+                timestamps: duckdb_vector_get_data(ts_vector).cast::<i64>(),
+                ts_validity: duckdb_vector_get_validity(ts_vector),
+                masks: duckdb_vector_get_data(mask_vector).cast::<i64>(),
+                mask_validity: duckdb_vector_get_validity(mask_vector),
+            }
+        }
+    }
+
+    /// Replaces `events` with the events of row `row` and returns `true`, or
+    /// returns `false` if the list is `NULL`.
+    ///
+    /// `NULL` elements and elements with a `NULL` timestamp or mask are
+    /// skipped.
+    unsafe fn read(&self, row: usize, events: &mut Vec<Event>) -> bool {
+        unsafe {
+            if !is_valid(self.validity, row as idx_t) {
+                return false;
+            }
+            let entry = *self.entries.add(row);
+            events.clear();
+            for j in entry.offset..entry.offset + entry.length {
+                if is_valid(self.element_validity, j)
+                    && is_valid(self.ts_validity, j)
+                    && is_valid(self.mask_validity, j)
+                {
+                    let ts = *self.timestamps.add(j as usize);
+                    let mask = *self.masks.add(j as usize);
+                    events.push(Event::new(ts, mask as u64));
+                }
+            }
+            true
+        }
+    }
+}
+
+/// Returns whether `row` is valid in `validity`, which is NULL when every row
+/// is.
+unsafe fn is_valid(validity: *mut u64, row: idx_t) -> bool {
+    validity.is_null() || unsafe { duckdb_validity_row_is_valid(validity, row) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL,
+// LIST(STRUCT(ts TIMESTAMP, mask BIGINT))) as registered. `output` is a valid
+// INTEGER vector sized for the chunk.
+unsafe extern "C" fn window_funnel_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe { evaluate_window_funnel(info, input, output, None) }
+}
+
+// SAFETY: as `window_funnel_function`, with a VARCHAR mode column between the
+// window and the list.
+unsafe extern "C" fn window_funnel_mode_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe { evaluate_window_funnel(info, input, output, Some(1)) }
+}
+
+/// Evaluates `window_funnel_from_list` for every row of `input`, reading the
+/// mode string from column `mode_col` when given.
+unsafe fn evaluate_window_funnel(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+    mode_col: Option<usize>,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let window_reader = VectorReader::new(input, 0);
+        let mode_reader = mode_col.map(|col| VectorReader::new(input, col));
+        let list = EventList::new(input, mode_col.map_or(1, |col| col + 1));
+        let mut writer = VectorWriter::new(output);
+
+        let mut events = Vec::new();
+        for row in 0..row_count {
+            let mode_valid = mode_reader.as_ref().is_none_or(|r| r.is_valid(row));
+            if !window_reader.is_valid(row) || !mode_valid || !list.read(row, &mut events) {
+                writer.set_null(row);
+                continue;
+            }
+
+            let iv = window_reader.read_interval(row);
+            let Some(window_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                let message =
+                    interval_message("window_funnel_from_list", iv.months, iv.days, iv.micros);
+                report_scalar(info, &message);
+                return;
+            };
+
+            let mut state = WindowFunnelState::new();
+            state.window_size_us = window_us;
+            if let Some(ref mode_reader) = mode_reader {
+                let s = mode_reader.read_str(row);
+                match FunnelMode::parse_modes(s) {
+                    Ok(mode) => state.mode = mode,
+                    Err(token) => {
+                        report_scalar(info, &mode_message("window_funnel_from_list", s, &token));
+                        return;
+                    }
+                }
+            }
+
+            state.update_list(events.iter().copied());
+            writer.write_i32(row, state.finalize() as i32);
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (VARCHAR,
+// LIST(STRUCT(ts TIMESTAMP, mask BIGINT))) as registered. `output` is a valid
+// BOOLEAN vector sized for the chunk.
+unsafe extern "C" fn sequence_match_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let pattern_reader = VectorReader::new(input, 0);
+        let list = EventList::new(input, 1);
+        let mut writer = VectorWriter::new(output);
+
+        // A mask has 64 bits, one per condition the aggregates accept.
+        let mut check = PatternCheck::new("sequence_match_from_list", MAX_CONDITIONS);
+        let mut events = Vec::new();
+        for row in 0..row_count {
+            if !pattern_reader.is_valid(row) || !list.read(row, &mut events) {
+                writer.set_null(row);
+                continue;
+            }
+
+            let pattern = pattern_reader.read_str(row);
+            if let Some(message) = check.message(pattern) {
+                report_scalar(info, &message);
+                return;
+            }
+
+            let mut state = SequenceState::new();
+            state.set_pattern(pattern);
+            state.update_batch(events.iter().copied());
+            match state.finalize_match() {
+                Ok(matched) => writer.write_bool(row, matched),
+                Err(e) => {
+                    report_scalar(
+                        info,
+                        &pattern_message("sequence_match_from_list", pattern, &e),
+                    );
+                    return;
+                }
+            }
+        }
+    }
+}
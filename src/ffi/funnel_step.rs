@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `funnel_step` window function.
+//!
+//! Registered with [`quack_rs::aggregate::AggregateFunctionSetBuilder`] over
+//! the same `(INTERVAL, TIMESTAMP, COND×N)` overloads as `window_funnel`, and
+//! evaluated as a window function through `DuckDB`'s segment tree like the
+//! [`session_edge`](super::session_edge) functions: the ordered
+//! [`FunnelStepState::combine_in_place`] carries the frame's last row to
+//! finalize.
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::conditions::{chunk_events, CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{interval_message, report};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::funnel_step::FunnelStepState;
+use crate::window_funnel::UNBOUNDED_WINDOW_US;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+impl quack_rs::aggregate::AggregateState for FunnelStepState {}
+
+/// Registers the `funnel_step` function with `DuckDB`.
+///
+/// Signature: `funnel_step(INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
+///
+/// Conditions may be `BOOLEAN` or `INTEGER` (see [`crate::ffi::conditions`]).
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_funnel_step(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("funnel_step").returns(TypeId::Integer);
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder.param(TypeId::Interval).param(TypeId::Timestamp);
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, FunnelStepState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL,
+// TIMESTAMP, BOOLEAN or INTEGER...) as registered. `states` points to
+// `row_count` aggregate state pointers. Rows are passed to each state in
+// frame order, which `FunnelStepState::update` relies on.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+        let num_conditions = col_count - 2;
+
+        let interval_reader = VectorReader::new(input, 0);
+        let events = chunk_events(input, 1, 2, col_count);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<FunnelStepState>::with_state_mut(*states.add(i)) else {
+                continue;
+            };
+
+            if !state.funnel.params_set {
+                if interval_reader.is_valid(i) {
+                    let iv = interval_reader.read_interval(i);
+                    let Some(window_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                        let message =
+                            interval_message("funnel_step", iv.months, iv.days, iv.micros);
+                        report(info, &message);
+                        return;
+                    };
+                    state.funnel.window_size_us = window_us;
+                } else {
+                    // NULL window: unbounded, as for window_funnel
+                    state.funnel.window_size_us = UNBOUNDED_WINDOW_US;
+                }
+                state.funnel.params_set = true;
+            }
+
+            state.update(events.get(i), num_conditions);
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+// `target` holds the preceding segment of the frame.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<FunnelStepState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<FunnelStepState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB INTEGER vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<FunnelStepState>::with_state_mut(*source.add(i)) else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            writer.write_i32(idx, state.finalize() as i32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<FunnelStepState>::new();
+        source.update(|s| {
+            s.funnel.window_size_us = 3_600_000_000;
+            s.funnel.params_set = true;
+            s.update(Some(Event::new(1_000_000, 0b01)), 2);
+            s.update(Some(Event::new(2_000_000, 0b10)), 2);
+        });
+        let mut target = AggregateTestHarness::<FunnelStepState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert_eq!(state.funnel.window_size_us, 3_600_000_000);
+        assert_eq!(state.finalize(), 2);
+    }
+}
@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `group_array_sorted_by_ts` aggregate function.
+//!
+//! One single-overload function set is registered per value column type
+//! (`VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID`), as for
+//! [`sequence_next_node`](super::sequence_next_node), whose [`NodeValue`]
+//! reads and writes the values. Each set returns `LIST(T)` through
+//! [`quack_rs::aggregate::AggregateFunctionSetBuilder::returns_logical`] and
+//! writes it with [`quack_rs::vector::complex::ListVector`].
+
+use crate::ffi::error::{limit_message, report};
+use crate::ffi::sequence_next_node::NodeValue;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::group_array_sorted::GroupArraySortedState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::ListVector;
+use quack_rs::vector::VectorReader;
+use std::sync::Arc;
+
+impl<V: NodeValue + Ord> quack_rs::aggregate::AggregateState for GroupArraySortedState<V> {}
+
+/// Registers the `group_array_sorted_by_ts` function with `DuckDB`.
+///
+/// Signature, for `T` in `VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID`:
+/// `group_array_sorted_by_ts(T, TIMESTAMP, BIGINT) -> LIST(T)`
+///
+/// Parameters:
+/// - `value`: Value to collect
+/// - `timestamp`: Event timestamp, the sort key
+/// - `max_n`: Maximum number of values returned (the earliest ones)
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_group_array_sorted_by_ts(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    unsafe {
+        register_for::<Arc<str>>(con, TypeId::Varchar)?;
+        register_for::<i64>(con, TypeId::BigInt)?;
+        register_for::<i32>(con, TypeId::Integer)?;
+        register_for::<i32>(con, TypeId::Date)?;
+        register_for::<i128>(con, TypeId::Uuid)
+    }
+}
+
+/// Registers the `group_array_sorted_by_ts` set whose value column is
+/// `value_type`, stored as `V`.
+unsafe fn register_for<V: NodeValue + Ord>(
+    con: &impl quack_rs::connection::Registrar,
+    value_type: TypeId,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let builder = AggregateFunctionSetBuilder::new("group_array_sorted_by_ts")
+        .returns_logical(LogicalType::list(value_type))
+        .overloads(1..=1, move |_, builder| {
+            let b = builder
+                .param(value_type) // value
+                .param(TypeId::Timestamp) // timestamp
+                .param(TypeId::BigInt); // max_n
+            with_lifecycle!(b, GroupArraySortedState<V>)
+                .update(state_update::<V>)
+                .combine(state_combine::<V>)
+                .finalize(state_finalize::<V>)
+        });
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (V, TIMESTAMP,
+// BIGINT) as registered for `V`. `states` points to `row_count` aggregate
+// state pointers.
+unsafe extern "C" fn state_update<V: NodeValue + Ord>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+
+        let value_reader = VectorReader::new(input, 0);
+        let ts_reader = VectorReader::new(input, 1);
+        let max_n_reader = VectorReader::new(input, 2);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<GroupArraySortedState<V>>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            if state.max_n == 0 && max_n_reader.is_valid(i) {
+                let max_n = max_n_reader.read_i64(i);
+                if max_n <= 0 {
+                    report(
+                        info,
+                        &limit_message("group_array_sorted_by_ts", "max_n", max_n),
+                    );
+                    return;
+                }
+                state.max_n = usize::try_from(max_n).unwrap_or(usize::MAX);
+            }
+
+            if !ts_reader.is_valid(i) {
+                continue;
+            }
+            let value = if value_reader.is_valid(i) {
+                Some(V::read(&value_reader, i))
+            } else {
+                None
+            };
+
+            state.update(ts_reader.read_i64(i), value);
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine<V: NodeValue + Ord>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<GroupArraySortedState<V>>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<GroupArraySortedState<V>>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(T) vector whose child has the physical type of `V`.
+unsafe extern "C" fn state_finalize<V: NodeValue + Ord>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<GroupArraySortedState<V>>::with_state_mut(*source.add(i))
+            else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let values = state.finalize();
+            let value_count = values.len() as u64;
+
+            // Reserve space in the list child vector before taking the child
+            // writer, since reserve may reallocate the child's data buffer.
+            ListVector::reserve(result, (list_offset + value_count) as usize);
+
+            let mut child_writer = ListVector::child_writer(result);
+            for (j, value) in values.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                match value {
+                    Some(v) => v.write(&mut child_writer, pos),
+                    None => child_writer.set_null(pos),
+                }
+            }
+
+            ListVector::set_entry(result, idx, list_offset, value_count);
+
+            list_offset += value_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<GroupArraySortedState>::new();
+        source.update(|s| {
+            s.max_n = 2;
+            s.update(3_000_000, Some(Arc::from("checkout")));
+            s.update(1_000_000, Some(Arc::from("home")));
+            s.update(2_000_000, None);
+        });
+        let mut target = AggregateTestHarness::<GroupArraySortedState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert_eq!(state.max_n, 2);
+        assert_eq!(state.finalize(), vec![Some(Arc::from("home")), None]);
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for `behavioral_version` and `behavioral_functions`.
+//!
+//! `behavioral_version` is a zero-argument scalar function registered with
+//! the raw `libduckdb-sys` API, like [`selfcheck`](super::selfcheck).
+//! `behavioral_functions` is a constant table, so it is created as a SQL table
+//! macro over a `VALUES` list generated from [`FUNCTIONS`], like
+//! [`sessionize_table`](super::sessionize_table).
+
+use crate::catalog::{version, FUNCTIONS};
+use libduckdb_sys::*;
+use quack_rs::vector::VectorWriter;
+use std::fmt::Write;
+
+/// Quotes `text` as a SQL string literal.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// `CREATE MACRO` statement defining `behavioral_functions`, with one row per
+/// signature of every entry in [`FUNCTIONS`].
+fn macro_sql() -> String {
+    let mut rows = String::new();
+    for entry in FUNCTIONS {
+        let modes: Vec<String> = entry.modes.iter().map(|mode| quote(mode)).collect();
+        for signature in entry.signatures {
+            if !rows.is_empty() {
+                rows.push_str(",\n    ");
+            }
+            let _ = write!(
+                rows,
+                "({}, {}, {}, {}, [{}]::VARCHAR[], {})",
+                quote(entry.name),
+                quote(entry.kind.as_str()),
+                quote(signature),
+                quote(entry.returns),
+                modes.join(", "),
+                quote(entry.description),
+            );
+        }
+    }
+    format!(
+        "CREATE OR REPLACE MACRO behavioral_functions() AS TABLE
+SELECT * FROM (VALUES
+    {rows}
+) AS t(function_name, function_type, signature, return_type, modes, description)"
+    )
+}
+
+/// Registers the `behavioral_version` function with `DuckDB`.
+///
+/// Signature: `behavioral_version() -> VARCHAR`
+///
+/// Returns the crate version, followed by the build commit in parentheses when
+/// it was known at build time (e.g. `0.5.0 (1a2b3c4)`).
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_behavioral_version(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, c"behavioral_version".as_ptr());
+
+        let mut varchar = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR);
+        duckdb_scalar_function_set_return_type(func, varchar);
+        duckdb_destroy_logical_type(&mut varchar);
+
+        duckdb_scalar_function_set_function(func, Some(version_function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register behavioral_version function");
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+/// Registers the `behavioral_functions` table macro with `DuckDB`.
+///
+/// Signature: `behavioral_functions() -> TABLE(function_name VARCHAR,
+/// function_type VARCHAR, signature VARCHAR, return_type VARCHAR,
+/// modes VARCHAR[], description VARCHAR)`
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_behavioral_functions(con: duckdb_connection) {
+    unsafe {
+        let sql = std::ffi::CString::new(macro_sql()).expect("macro SQL contains no NUL bytes");
+        let mut result: duckdb_result = std::mem::zeroed();
+        if duckdb_query(con, sql.as_ptr(), &mut result) != DuckDBSuccess {
+            eprintln!("behavioral: failed to register behavioral_functions macro");
+        }
+        duckdb_destroy_result(&mut result);
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with no columns; its size is the
+// number of rows to produce. `output` is a valid VARCHAR vector sized for at
+// least that many rows.
+unsafe extern "C" fn version_function(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let version = version();
+        let mut writer = VectorWriter::new(output);
+        for row in 0..row_count {
+            writer.write_varchar(row, &version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_sql_has_one_row_per_signature() {
+        let sql = macro_sql();
+        let signatures: usize = FUNCTIONS.iter().map(|entry| entry.signatures.len()).sum();
+        assert_eq!(sql.matches("::VARCHAR[]").count(), signatures);
+        assert!(sql.contains("'window_funnel', 'aggregate'"));
+        assert!(sql.contains("['strict', 'strict_order',"));
+        assert!(sql.contains("'sessionize', 'window'"));
+    }
+
+    #[test]
+    fn test_quote_escapes_single_quotes() {
+        assert_eq!(quote("a'b"), "'a''b'");
+        assert_eq!(quote("plain"), "'plain'");
+    }
+}
@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `markov_attribution` aggregate function.
+//!
+//! The overload without a path column is one function set over the condition
+//! types of the `converted` flag, read by [`ConditionReader`]. The overloads
+//! with a leading path column are registered as one more set per path type
+//! (`VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID`), with [`NodeValue`]
+//! reading the keys, as for [`transition_matrix`](super::transition_matrix).
+//! Every set returns `LIST(STRUCT(channel VARCHAR, removal_effect DOUBLE,
+//! conversions DOUBLE))`, written through [`ListVector`] and
+//! [`StructVector`] field writers.
+
+use crate::ffi::conditions::{ConditionReader, CONDITION_TYPES};
+use crate::ffi::sequence_next_node::NodeValue;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::markov_attribution::MarkovAttributionState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::{ListVector, StructVector};
+use quack_rs::vector::VectorReader;
+use std::sync::Arc;
+
+impl<K: Ord + Clone + Send + 'static> quack_rs::aggregate::AggregateState
+    for MarkovAttributionState<K>
+{
+}
+
+/// Registers the `markov_attribution` function with `DuckDB`.
+///
+/// Signatures, with `converted` as `BOOLEAN` or `INTEGER`:
+///
+/// 1. One path per group: `markov_attribution(TIMESTAMP, VARCHAR, converted)`
+/// 2. Paths keyed by a column, for `P` in `VARCHAR`, `BIGINT`, `INTEGER`,
+///    `DATE`, `UUID`: `markov_attribution(P, TIMESTAMP, VARCHAR, converted)`
+///
+/// Both return `LIST(STRUCT(channel VARCHAR, removal_effect DOUBLE,
+/// conversions DOUBLE))`, see [`MarkovAttributionState::finalize`].
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_markov_attribution(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("markov_attribution")
+        .returns_logical(LogicalType::list_from_logical(&result_entry()));
+    for cond_type in CONDITION_TYPES {
+        // (TIMESTAMP, VARCHAR channel, converted)
+        builder = builder.overloads(1..=1, move |_, builder| {
+            let b = builder
+                .param(TypeId::Timestamp) // timestamp
+                .param(TypeId::Varchar) // channel
+                .param(cond_type); // converted
+            with_lifecycle!(b, MarkovAttributionState)
+                .update(state_update)
+                .combine(state_combine::<()>)
+                .finalize(state_finalize::<()>)
+        });
+    }
+    unsafe {
+        con.register_aggregate_set(builder)?;
+        register_for::<Arc<str>>(con, TypeId::Varchar)?;
+        register_for::<i64>(con, TypeId::BigInt)?;
+        register_for::<i32>(con, TypeId::Integer)?;
+        register_for::<i32>(con, TypeId::Date)?;
+        register_for::<i128>(con, TypeId::Uuid)
+    }
+}
+
+/// The `STRUCT` of one result entry.
+fn result_entry() -> LogicalType {
+    LogicalType::struct_type(&[
+        ("channel", TypeId::Varchar),
+        ("removal_effect", TypeId::Double),
+        ("conversions", TypeId::Double),
+    ])
+}
+
+/// Registers the `markov_attribution` set whose path column is `path_type`,
+/// stored as `K`.
+unsafe fn register_for<K: NodeValue + Ord>(
+    con: &impl quack_rs::connection::Registrar,
+    path_type: TypeId,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("markov_attribution")
+        .returns_logical(LogicalType::list_from_logical(&result_entry()));
+    for cond_type in CONDITION_TYPES {
+        // (P path, TIMESTAMP, VARCHAR channel, converted)
+        builder = builder.overloads(1..=1, move |_, builder| {
+            let b = builder
+                .param(path_type) // path
+                .param(TypeId::Timestamp) // timestamp
+                .param(TypeId::Varchar) // channel
+                .param(cond_type); // converted
+            with_lifecycle!(b, MarkovAttributionState<K>)
+                .update(state_update_by_path::<K>)
+                .combine(state_combine::<K>)
+                .finalize(state_finalize::<K>)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (TIMESTAMP,
+// VARCHAR, converted) as registered. `states` points to `row_count` aggregate
+// state pointers.
+unsafe extern "C" fn state_update(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let ts_reader = VectorReader::new(input, 0);
+        let channel_reader = VectorReader::new(input, 1);
+        let converted_reader = ConditionReader::new(input, 2);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<MarkovAttributionState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            // Rows without a timestamp or a channel are not touchpoints
+            if !ts_reader.is_valid(i) || !channel_reader.is_valid(i) {
+                continue;
+            }
+
+            state.update(
+                (),
+                ts_reader.read_i64(i),
+                Arc::<str>::read(&channel_reader, i),
+                converted_reader.read(i),
+            );
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (K, TIMESTAMP,
+// VARCHAR, converted) as registered for `K`. `states` points to `row_count`
+// aggregate state pointers.
+unsafe extern "C" fn state_update_by_path<K: NodeValue + Ord>(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let path_reader = VectorReader::new(input, 0);
+        let ts_reader = VectorReader::new(input, 1);
+        let channel_reader = VectorReader::new(input, 2);
+        let converted_reader = ConditionReader::new(input, 3);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<MarkovAttributionState<K>>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            // Rows without a path, a timestamp or a channel are not touchpoints
+            if !path_reader.is_valid(i) || !ts_reader.is_valid(i) || !channel_reader.is_valid(i) {
+                continue;
+            }
+
+            state.update(
+                K::read(&path_reader, i),
+                ts_reader.read_i64(i),
+                Arc::<str>::read(&channel_reader, i),
+                converted_reader.read(i),
+            );
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine<K: Ord + Clone + Send + 'static>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<MarkovAttributionState<K>>::with_state(*source.add(i))
+            else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<MarkovAttributionState<K>>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(channel VARCHAR, removal_effect DOUBLE,
+// conversions DOUBLE)) vector. Empty list when the group has no touchpoints.
+unsafe extern "C" fn state_finalize<K: Ord + Clone + Send + 'static>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<MarkovAttributionState<K>>::with_state_mut(*source.add(i))
+            else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let channels = state.finalize();
+            let entry_count = channels.len() as u64;
+
+            // Reserve space in the list child vector before taking field writers,
+            // since reserve may reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + entry_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut channel_writer = StructVector::field_writer(child, 0);
+            let mut effect_writer = StructVector::field_writer(child, 1);
+            let mut conversions_writer = StructVector::field_writer(child, 2);
+            for (j, attribution) in channels.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                channel_writer.write_varchar(pos, &attribution.channel);
+                effect_writer.write_f64(pos, attribution.removal_effect);
+                conversions_writer.write_f64(pos, attribution.conversions);
+            }
+
+            ListVector::set_entry(result, idx, list_offset, entry_count);
+
+            list_offset += entry_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<MarkovAttributionState<i64>>::new();
+        source.update(|s| {
+            s.update(1, 2_000_000, Arc::from("search"), true);
+            s.update(1, 1_000_000, Arc::from("email"), false);
+        });
+        let mut target = AggregateTestHarness::<MarkovAttributionState<i64>>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        let channels = state.finalize();
+        assert_eq!(channels.len(), 2);
+        assert!(channels
+            .iter()
+            .all(|c| (c.removal_effect - 1.0).abs() < 1e-9));
+    }
+}
@@ -23,13 +23,62 @@
 //! - [`quack_rs::types::LogicalType::list()`] — RAII construction of `LIST(T)`
 //!   types for `returns_logical()` and function registration.
 //!
-//! All 6 aggregate functions use `AggregateFunctionSetBuilder` for registration,
-//! including `retention` and `sequence_match_events` which use
-//! `.returns_logical(LogicalType::list(...))` for their `LIST(T)` return types.
+//! All 34 multi-overload aggregate functions use `AggregateFunctionSetBuilder` for registration,
+//! including `retention`, `retention_counts`, `retention_rate`, `retention_by_period`,
+//! `sequence_match_events` and `group_array_sorted_by_ts` which use `.returns_logical(LogicalType::list(...))`
+//! for their `LIST(T)` return types,
+//! and `sequence_match_values`, `sequence_extract`, `sequence_match_spans`,
+//! `sequence_count_by`, `sequence_next_node_hist`, `transition_matrix` and
+//! `markov_attribution`
+//! which return `LIST(STRUCT(...))` and write their output through
+//! [`quack_rs::vector::complex::StructVector`] field writers, as does
+//! [`window_funnel_by`] for its `STRUCT(step, entry_dim)` result and
+//! [`window_funnel_debug`] and [`sequence_match_debug`] for their `STRUCT` of
+//! finalize statistics. `retention_with_validity` returns a `STRUCT` of two
+//! lists, written into the `STRUCT`'s child vectors.
+//! `sessionize_approx`, `event_gap_stats`, `sessionize_state` and the [`session_edge`] and
+//! [`session_position`] functions have a single signature and use
+//! [`quack_rs::aggregate::AggregateFunctionBuilder`]; `event_gap_stats` returns
+//! a `STRUCT` written through `StructVector` field writers. The session-edge
+//! and session-position functions are window functions in practice, but
+//! they only need the standard aggregate callbacks, so they do not share the
+//! raw registration of [`sessionize`]. [`funnel_step`] is the same kind of window function,
+//! with one overload per condition count.
 //!
 //! The [`sessionize`] module is the sole exception: it requires window function
 //! registration via the C API, which `quack-rs` does not support. It uses raw
 //! `libduckdb-sys` calls directly via [`Connection::as_raw_connection()`].
+//! [`sessionize_table`] likewise uses the raw connection to create a SQL table
+//! macro, since the C API cannot register table functions over a relation, and
+//! [`selfcheck`], [`parse_duration`], [`strict_condition`], [`cohort_of`] and
+//! [`sequence_pattern_check`] register the `behavioral_selfcheck`,
+//! `parse_duration`, `strict_condition`, `cohort_of`,
+//! `sequence_pattern_check` and `sequence_pattern_normalize` scalar functions
+//! with raw scalar function calls. So does [`from_list`] for
+//! `window_funnel_from_list` and `sequence_match_from_list`, which evaluate a
+//! whole event list per row, and [`window_funnel_state`],
+//! [`retention_state`] and [`sessionize_state`] for the `_merge` and
+//! `_finalize` functions that read exported states, sharing
+//! [`state_export`]. [`explain`] registers the `behavioral_explain`
+//! table function, whose rows depend on its pattern argument, with raw table
+//! function calls, and [`demo_events`] the `behavioral_demo_events`
+//! generator the same way.
+//! [`introspection`] does both: `behavioral_version` is a raw scalar function
+//! and `behavioral_functions` a table macro over the [`crate::catalog`] list.
+//!
+//! # State Lifecycle
+//!
+//! Every aggregate registers a destructor, since `DuckDB` destroys the states
+//! of failed and interrupted queries without finalizing them. Registrations
+//! set their size, init and destroy callbacks together through
+//! [`state_lifecycle::with_lifecycle!`], or [`state_lifecycle::BoxedState`]
+//! for [`sessionize`].
+//!
+//! # Errors
+//!
+//! Invalid arguments (malformed patterns, unknown modes, month intervals) are
+//! reported through [`error::report`] and fail the query instead of producing
+//! `NULL` or `0`.
 //!
 //! # Entry Point
 //!
@@ -37,12 +86,47 @@
 //! [`Connection`] implementing the [`Registrar`](quack_rs::connection::Registrar) trait — a version-agnostic API
 //! for registering extension components across `DuckDB` 1.4.x and 1.5.x.
 
+pub mod cohort_of;
+pub mod conditions;
+pub mod demo_events;
+pub mod error;
+pub mod event_gap_stats;
+pub mod explain;
+pub mod from_list;
+pub mod funnel_step;
+pub mod group_array_sorted;
+pub mod introspection;
+pub mod markov_attribution;
+pub mod parse_duration;
+pub mod path_count_sketch;
 pub mod retention;
+pub mod retention_state;
+pub mod selfcheck;
 pub mod sequence;
+pub mod sequence_count_by;
+pub mod sequence_duration;
+pub mod sequence_match_any;
+pub mod sequence_match_debug;
 pub mod sequence_match_events;
+pub mod sequence_match_spans;
+pub mod sequence_match_values;
 pub mod sequence_next_node;
+pub mod sequence_pattern_check;
+pub mod session_edge;
+pub mod session_position;
 pub mod sessionize;
+pub mod sessionize_approx;
+pub mod sessionize_state;
+pub mod sessionize_table;
+pub mod state_export;
+pub mod state_lifecycle;
+pub mod strict_condition;
+pub mod touch_attribution;
+pub mod transition_matrix;
 pub mod window_funnel;
+pub mod window_funnel_by;
+pub mod window_funnel_debug;
+pub mod window_funnel_state;
 
 use quack_rs::connection::Connection;
 use quack_rs::error::ExtensionError;
@@ -65,19 +149,76 @@ pub unsafe fn register_all(con: &Connection) -> Result<(), ExtensionError> {
     // Safety: The Connection holds a valid handle — obtained via duckdb_connect
     // in the entry_point_v2! macro and will be disconnected after registration.
 
-    // Sessionize requires raw window function FFI (not supported by Registrar).
+    // Sessionize requires raw window function FFI, sessionize_table is a SQL
+    // table macro, behavioral_selfcheck, parse_duration, strict_condition,
+    // cohort_of and behavioral_version are scalar functions and behavioral_functions is another table macro;
+    // none of these go through Registrar.
     unsafe {
         sessionize::register_sessionize(con.as_raw_connection());
+        sessionize::register_sessionize_by(con.as_raw_connection());
+        sessionize::register_sessionize_info(con.as_raw_connection());
+        sessionize_table::register_sessionize_table(con.as_raw_connection());
+        selfcheck::register_behavioral_selfcheck(con.as_raw_connection());
+        parse_duration::register_parse_duration(con.as_raw_connection());
+        strict_condition::register_strict_condition(con.as_raw_connection());
+        cohort_of::register_cohort_of(con.as_raw_connection());
+        sequence_pattern_check::register_sequence_pattern_check(con.as_raw_connection());
+        sequence_pattern_check::register_sequence_pattern_normalize(con.as_raw_connection());
+        from_list::register_window_funnel_from_list(con.as_raw_connection());
+        from_list::register_sequence_match_from_list(con.as_raw_connection());
+        window_funnel_state::register_window_funnel_merge(con.as_raw_connection());
+        window_funnel_state::register_window_funnel_finalize(con.as_raw_connection());
+        retention_state::register_retention_merge(con.as_raw_connection());
+        retention_state::register_retention_finalize(con.as_raw_connection());
+        sessionize_state::register_sessionize_merge(con.as_raw_connection());
+        sessionize_state::register_sessionize_finalize(con.as_raw_connection());
+        explain::register_behavioral_explain(con.as_raw_connection());
+        demo_events::register_behavioral_demo_events(con.as_raw_connection());
+        introspection::register_behavioral_version(con.as_raw_connection());
+        introspection::register_behavioral_functions(con.as_raw_connection());
     }
 
     // All aggregate functions use the Registrar trait for registration.
     unsafe {
         retention::register_retention(con)?;
+        retention::register_retention_counts(con)?;
+        retention::register_retention_rate(con)?;
+        retention::register_retention_with_validity(con)?;
+        retention::register_retention_by_period(con)?;
+        retention_state::register_retention_state(con)?;
         window_funnel::register_window_funnel(con)?;
+        window_funnel::register_window_funnel_mask(con)?;
+        window_funnel::register_window_funnel_flags(con)?;
+        window_funnel::register_window_funnel_entry_ts(con)?;
+        window_funnel_by::register_window_funnel_by(con)?;
+        window_funnel_debug::register_window_funnel_debug(con)?;
+        window_funnel_state::register_window_funnel_state(con)?;
+        window_funnel::register_conversion_rate(con)?;
+        window_funnel::register_time_to_convert(con)?;
+        funnel_step::register_funnel_step(con)?;
         sequence::register_sequence_match(con)?;
         sequence::register_sequence_count(con)?;
+        sequence::register_sequence_count_within(con)?;
+        sequence_count_by::register_sequence_count_by(con)?;
+        sequence_match_any::register_sequence_match_any(con)?;
+        sequence_match_debug::register_sequence_match_debug(con)?;
         sequence_match_events::register_sequence_match_events(con)?;
+        sequence_match_spans::register_sequence_match_spans(con)?;
+        sequence_match_values::register_sequence_match_values(con)?;
+        sequence_match_values::register_sequence_extract(con)?;
+        sequence_duration::register_sequence_duration(con)?;
         sequence_next_node::register_sequence_next_node(con)?;
+        sequence_next_node::register_sequence_next_node_hist(con)?;
+        sessionize_approx::register_sessionize_approx(con)?;
+        sessionize_state::register_sessionize_state(con)?;
+        session_edge::register_session_edges(con)?;
+        session_position::register_session_positions(con)?;
+        event_gap_stats::register_event_gap_stats(con)?;
+        group_array_sorted::register_group_array_sorted_by_ts(con)?;
+        transition_matrix::register_transition_matrix(con)?;
+        markov_attribution::register_markov_attribution(con)?;
+        path_count_sketch::register_path_count_sketch(con)?;
+        touch_attribution::register_touch_attribution(con)?;
     }
 
     Ok(())
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `parse_duration` scalar function.
+//!
+//! Like [`selfcheck`](super::selfcheck), registration uses the raw
+//! `libduckdb-sys` scalar function API. Input strings are read with
+//! [`quack_rs::vector::VectorReader`] and the result is written with
+//! [`quack_rs::vector::VectorWriter::write_interval`], as in
+//! [`sequence_duration`](super::sequence_duration).
+
+use crate::common::timestamp::parse_duration;
+use crate::ffi::error::report_scalar;
+use libduckdb_sys::*;
+use quack_rs::interval::DuckInterval;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+/// Registers the `parse_duration` function with `DuckDB`.
+///
+/// Signature: `parse_duration(VARCHAR) -> INTERVAL`
+///
+/// The result holds microseconds only (no month or day component), so
+/// `parse_duration('2d')` prints as `48:00:00` and compares equal to
+/// `INTERVAL '2 days'`. `NULL` input produces `NULL`; malformed input fails
+/// the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_parse_duration(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, c"parse_duration".as_ptr());
+
+        let mut varchar = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR);
+        duckdb_scalar_function_add_parameter(func, varchar);
+        duckdb_destroy_logical_type(&mut varchar);
+
+        let mut interval = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL);
+        duckdb_scalar_function_set_return_type(func, interval);
+        duckdb_destroy_logical_type(&mut interval);
+
+        duckdb_scalar_function_set_function(func, Some(parse_duration_function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register parse_duration function");
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one VARCHAR column as
+// registered. `output` is a valid INTERVAL vector sized for the chunk.
+unsafe extern "C" fn parse_duration_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let reader = VectorReader::new(input, 0);
+        let mut writer = VectorWriter::new(output);
+
+        for row in 0..row_count {
+            if !reader.is_valid(row) {
+                writer.set_null(row);
+                continue;
+            }
+            match parse_duration(reader.read_str(row)) {
+                Ok(micros) => writer.write_interval(
+                    row,
+                    DuckInterval {
+                        months: 0,
+                        days: 0,
+                        micros,
+                    },
+                ),
+                Err(message) => {
+                    report_scalar(info, &format!("parse_duration: {message}"));
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `path_count_sketch` aggregate function.
+//!
+//! The overload without a path column and the overloads with a leading path
+//! column, one per path type (`VARCHAR`, `BIGINT`, `INTEGER`, `DATE`,
+//! `UUID`), are registered like [`markov_attribution`](super::markov_attribution),
+//! with [`NodeValue`] reading the keys.
+
+use crate::ffi::error::{limit_message, report};
+use crate::ffi::sequence_next_node::NodeValue;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::path_count_sketch::PathCountSketchState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::{VectorReader, VectorWriter};
+use std::sync::Arc;
+
+impl<K: Ord + Clone + Send + 'static> quack_rs::aggregate::AggregateState
+    for PathCountSketchState<K>
+{
+}
+
+/// Registers the `path_count_sketch` function with `DuckDB`.
+///
+/// Signatures:
+///
+/// 1. One path per group: `path_count_sketch(TIMESTAMP, VARCHAR, BIGINT) -> BIGINT`
+/// 2. Paths keyed by a column, for `P` in `VARCHAR`, `BIGINT`, `INTEGER`,
+///    `DATE`, `UUID`: `path_count_sketch(P, TIMESTAMP, VARCHAR, BIGINT) -> BIGINT`
+///
+/// The `BIGINT` argument is the prefix depth, see
+/// [`PathCountSketchState::finalize`].
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_path_count_sketch(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    // (TIMESTAMP, VARCHAR value, BIGINT depth)
+    let builder = AggregateFunctionSetBuilder::new("path_count_sketch")
+        .returns(TypeId::BigInt)
+        .overloads(1..=1, |_, builder| {
+            let b = builder
+                .param(TypeId::Timestamp) // timestamp
+                .param(TypeId::Varchar) // value
+                .param(TypeId::BigInt); // depth
+            with_lifecycle!(b, PathCountSketchState)
+                .update(state_update)
+                .combine(state_combine::<()>)
+                .finalize(state_finalize::<()>)
+        });
+    unsafe {
+        con.register_aggregate_set(builder)?;
+        register_for::<Arc<str>>(con, TypeId::Varchar)?;
+        register_for::<i64>(con, TypeId::BigInt)?;
+        register_for::<i32>(con, TypeId::Integer)?;
+        register_for::<i32>(con, TypeId::Date)?;
+        register_for::<i128>(con, TypeId::Uuid)
+    }
+}
+
+/// Registers the `path_count_sketch` overload whose path column is
+/// `path_type`, stored as `K`.
+unsafe fn register_for<K: NodeValue + Ord>(
+    con: &impl quack_rs::connection::Registrar,
+    path_type: TypeId,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    // (P path, TIMESTAMP, VARCHAR value, BIGINT depth)
+    let builder = AggregateFunctionSetBuilder::new("path_count_sketch")
+        .returns(TypeId::BigInt)
+        .overloads(1..=1, move |_, builder| {
+            let b = builder
+                .param(path_type) // path
+                .param(TypeId::Timestamp) // timestamp
+                .param(TypeId::Varchar) // value
+                .param(TypeId::BigInt); // depth
+            with_lifecycle!(b, PathCountSketchState<K>)
+                .update(state_update_by_path::<K>)
+                .combine(state_combine::<K>)
+                .finalize(state_finalize::<K>)
+        });
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Sets the depth of `state` from row `i` of `depth_reader` (once per state).
+/// A depth below 1 is reported as an error and makes this return `false`.
+///
+/// # Safety
+///
+/// `info` must be the `duckdb_function_info` passed to the running callback,
+/// and `i` a valid, non-NULL row of the `BIGINT` column `depth_reader` reads.
+unsafe fn configure<K>(
+    info: duckdb_function_info,
+    state: &mut PathCountSketchState<K>,
+    depth_reader: &VectorReader,
+    i: usize,
+) -> bool {
+    if state.depth == 0 {
+        let depth = unsafe { depth_reader.read_i64(i) };
+        if depth < 1 {
+            unsafe { report(info, &limit_message("path_count_sketch", "depth", depth)) };
+            return false;
+        }
+        state.depth = usize::try_from(depth).unwrap_or(usize::MAX);
+    }
+    true
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (TIMESTAMP,
+// VARCHAR, BIGINT) as registered. `states` points to `row_count` aggregate
+// state pointers.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let ts_reader = VectorReader::new(input, 0);
+        let value_reader = VectorReader::new(input, 1);
+        let depth_reader = VectorReader::new(input, 2);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<PathCountSketchState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            // Rows without a timestamp, a value or a depth are not events
+            if !ts_reader.is_valid(i) || !value_reader.is_valid(i) || !depth_reader.is_valid(i) {
+                continue;
+            }
+            if !configure(info, state, &depth_reader, i) {
+                return;
+            }
+
+            state.update((), ts_reader.read_i64(i), value_reader.read_str(i));
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (K, TIMESTAMP,
+// VARCHAR, BIGINT) as registered for `K`. `states` points to `row_count`
+// aggregate state pointers.
+unsafe extern "C" fn state_update_by_path<K: NodeValue + Ord>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let path_reader = VectorReader::new(input, 0);
+        let ts_reader = VectorReader::new(input, 1);
+        let value_reader = VectorReader::new(input, 2);
+        let depth_reader = VectorReader::new(input, 3);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<PathCountSketchState<K>>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            // Rows without a path, a timestamp, a value or a depth are not events
+            if !path_reader.is_valid(i)
+                || !ts_reader.is_valid(i)
+                || !value_reader.is_valid(i)
+                || !depth_reader.is_valid(i)
+            {
+                continue;
+            }
+            if !configure(info, state, &depth_reader, i) {
+                return;
+            }
+
+            state.update(
+                K::read(&path_reader, i),
+                ts_reader.read_i64(i),
+                value_reader.read_str(i),
+            );
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine<K: Ord + Clone + Send + 'static>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<PathCountSketchState<K>>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<PathCountSketchState<K>>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB BIGINT vector. A group without events returns 0.
+unsafe extern "C" fn state_finalize<K: Ord + Clone + Send + 'static>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<PathCountSketchState<K>>::with_state(*source.add(i)) {
+                Some(state) => writer.write_i64(idx, state.finalize()),
+                None => writer.write_i64(idx, 0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<PathCountSketchState<i64>>::new();
+        source.update(|s| {
+            s.depth = 2;
+            s.update(1, 0, "home");
+            s.update(1, 1_000_000, "search");
+            s.update(2, 0, "pricing");
+        });
+        let mut target = AggregateTestHarness::<PathCountSketchState<i64>>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let state = target.finalize();
+        assert_eq!(state.depth, 2);
+        assert_eq!(state.finalize(), 2);
+    }
+}
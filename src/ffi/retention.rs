@@ -1,34 +1,77 @@
 // SPDX-License-Identifier: MIT
 // Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
 
-//! FFI registration for the `retention` aggregate function.
+//! FFI registration for the `retention`, `retention_counts`,
+//! `retention_rate`, `retention_with_validity` and `retention_by_period`
+//! aggregate functions.
 //!
 //! Uses [`quack_rs::aggregate::AggregateFunctionSetBuilder`] with
 //! [`returns_logical`][quack_rs::aggregate::AggregateFunctionSetBuilder::returns_logical]
-//! for `LIST(BOOLEAN)` return type registration.
+//! for `LIST(BOOLEAN)`, `LIST(BIGINT)` and `LIST(DOUBLE)` return type
+//! registration.
 //! Uses [`quack_rs::aggregate::FfiState`] for safe state management,
 //! [`quack_rs::vector::VectorReader`] for input, and
 //! [`quack_rs::vector::complex::ListVector`] + [`quack_rs::vector::VectorWriter`]
 //! for LIST output.
-
-use crate::retention::RetentionState;
+//!
+//! The condition-taking functions read their conditions the same way and
+//! share the generic `state_update` callback through the private
+//! `ConditionRow` trait; `retention_rate` also shares `retention_counts`'
+//! state and combine.
+//!
+//! The `retention(BOOLEAN[])` overload reads its list argument with raw
+//! `libduckdb-sys` calls (`duckdb_list_vector_get_child` and the
+//! `duckdb_list_entry` array), since [`VectorReader`] reads scalar columns only.
+//!
+//! `retention_by_period` shares `state_finalize`'s list writing through
+//! `write_bool_list`. `retention_with_validity` returns a `STRUCT` of two
+//! lists, written through `write_bool_list` and `write_i64_list` into the
+//! `STRUCT`'s child vectors; its update reads each condition's validity as
+//! well as its value, so it has its own update callback.
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::conditions::{condition_readers, CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{interval_message, limit_message, non_positive_message, report};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::retention::{
+    RetentionByPeriodState, RetentionCountsState, RetentionState, RetentionValidityState,
+};
 use libduckdb_sys::*;
-use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, AggregateState, FfiState};
 use quack_rs::types::{LogicalType, TypeId};
 use quack_rs::vector::complex::ListVector;
 use quack_rs::vector::{VectorReader, VectorWriter};
 
-/// Minimum number of boolean condition parameters for retention.
-const MIN_CONDITIONS: usize = 2;
-/// Maximum number of boolean condition parameters for retention.
-const MAX_CONDITIONS: usize = 32;
-
 impl quack_rs::aggregate::AggregateState for RetentionState {}
+impl quack_rs::aggregate::AggregateState for RetentionCountsState {}
+impl quack_rs::aggregate::AggregateState for RetentionByPeriodState {}
+impl quack_rs::aggregate::AggregateState for RetentionValidityState {}
+
+/// A state updated with one row of condition values.
+pub(crate) trait ConditionRow: AggregateState {
+    fn update_row(&mut self, conditions: &[bool]);
+}
+
+impl ConditionRow for RetentionState {
+    fn update_row(&mut self, conditions: &[bool]) {
+        self.update(conditions);
+    }
+}
+
+impl ConditionRow for RetentionCountsState {
+    fn update_row(&mut self, conditions: &[bool]) {
+        self.update(conditions);
+    }
+}
 
 /// Registers the `retention` function with `DuckDB` as a function set
-/// with overloads for 2..=32 boolean parameters.
+/// with overloads for 2..=64 boolean parameters and one `BOOLEAN[]` parameter.
+///
+/// Signatures:
+/// - `retention(BOOLEAN, BOOLEAN [, BOOLEAN ...]) -> BOOLEAN[]`
+/// - `retention(BOOLEAN[]) -> BOOLEAN[]`
 ///
-/// Signature: `retention(BOOLEAN, BOOLEAN [, BOOLEAN ...]) -> BOOLEAN[]`
+/// The list overload has no upper bound on the number of conditions.
 ///
 /// # Safety
 ///
@@ -40,26 +83,178 @@ impl quack_rs::aggregate::AggregateState for RetentionState {}
 pub unsafe fn register_retention(
     con: &impl quack_rs::connection::Registrar,
 ) -> Result<(), quack_rs::error::ExtensionError> {
-    let builder = AggregateFunctionSetBuilder::new("retention")
-        .returns_logical(LogicalType::list(TypeId::Boolean))
-        .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, |n, builder| {
+    let mut builder = AggregateFunctionSetBuilder::new("retention")
+        .returns_logical(LogicalType::list(TypeId::Boolean));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
             let mut b = builder;
             for _ in 0..n {
-                b = b.param(TypeId::Boolean);
+                b = b.param(cond_type);
             }
-            b.state_size(FfiState::<RetentionState>::size_callback)
-                .init(FfiState::<RetentionState>::init_callback)
-                .update(state_update)
+            with_lifecycle!(b, RetentionState)
+                .update(state_update::<RetentionState>)
                 .combine(state_combine)
                 .finalize(state_finalize)
-                .destructor(FfiState::<RetentionState>::destroy_callback)
+        });
+    }
+    builder = builder.overloads(1..=1, |_, builder| {
+        let b = builder.param_logical(LogicalType::list(TypeId::Boolean));
+        with_lifecycle!(b, RetentionState)
+            .update(list_update)
+            .combine(state_combine)
+            .finalize(state_finalize)
+    });
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `retention_counts` function with `DuckDB` as a function set
+/// with overloads for 2..=64 boolean parameters.
+///
+/// Signature: `retention_counts(BOOLEAN, BOOLEAN [, BOOLEAN ...]) -> BIGINT[]`
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_retention_counts(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("retention_counts")
+        .returns_logical(LogicalType::list(TypeId::BigInt));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder;
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, RetentionCountsState)
+                .update(state_update::<RetentionCountsState>)
+                .combine(counts_combine)
+                .finalize(counts_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `retention_rate` function with `DuckDB` as a function set
+/// with overloads for 2..=64 boolean parameters.
+///
+/// Signature: `retention_rate(BOOLEAN, BOOLEAN [, BOOLEAN ...]) -> DOUBLE[]`
+///
+/// The state is `retention_counts`'; finalize divides by the anchor count
+/// (see [`RetentionCountsState::finalize_rates`]).
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_retention_rate(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("retention_rate")
+        .returns_logical(LogicalType::list(TypeId::Double));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder;
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, RetentionCountsState)
+                .update(state_update::<RetentionCountsState>)
+                .combine(counts_combine)
+                .finalize(rate_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `retention_with_validity` function with `DuckDB` as a
+/// function set with overloads for 2..=64 boolean parameters.
+///
+/// Signature: `retention_with_validity(BOOLEAN, BOOLEAN [, BOOLEAN ...]) ->
+/// STRUCT(values BOOLEAN[], non_null_counts BIGINT[])`
+///
+/// `values` is the `retention` result; `non_null_counts` holds, per
+/// condition, the number of rows where it was non-`NULL` (see
+/// [`RetentionValidityState`]).
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_retention_with_validity(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("retention_with_validity").returns_logical(
+        LogicalType::struct_type_from_logical(&[
+            ("values", LogicalType::list(TypeId::Boolean)),
+            ("non_null_counts", LogicalType::list(TypeId::BigInt)),
+        ]),
+    );
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder;
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, RetentionValidityState)
+                .update(validity_update)
+                .combine(validity_combine)
+                .finalize(validity_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `retention_by_period` function with `DuckDB`.
+///
+/// Signature:
+/// `retention_by_period(TIMESTAMP, TIMESTAMP, INTERVAL, BIGINT) -> BOOLEAN[]`
+///
+/// Parameters:
+/// - `base_ts`: Cohort anchor timestamp; period 0 starts here
+/// - `ts`: Event timestamp
+/// - `period`: Period length (no month component)
+/// - `n`: Number of periods returned
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_retention_by_period(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let builder = AggregateFunctionSetBuilder::new("retention_by_period")
+        .returns_logical(LogicalType::list(TypeId::Boolean))
+        .overloads(1..=1, |_, builder| {
+            let b = builder
+                .param(TypeId::Timestamp) // base_ts
+                .param(TypeId::Timestamp) // ts
+                .param(TypeId::Interval) // period
+                .param(TypeId::BigInt); // n
+            with_lifecycle!(b, RetentionByPeriodState)
+                .update(period_update)
+                .combine(period_combine)
+                .finalize(period_finalize)
         });
     unsafe { con.register_aggregate_set(builder) }
 }
 
 // SAFETY: `input` is a valid DuckDB data chunk with N BOOLEAN columns (as registered).
 // `states` points to `row_count` aggregate state pointers initialized by `FfiState::init_callback`.
-unsafe extern "C" fn state_update(
+pub(crate) unsafe extern "C" fn state_update<S: ConditionRow>(
     _info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
@@ -68,22 +263,59 @@ unsafe extern "C" fn state_update(
         let row_count = duckdb_data_chunk_get_size(input) as usize;
         let col_count = duckdb_data_chunk_get_column_count(input) as usize;
 
-        // Read all boolean condition vectors using VectorReader
-        let readers: Vec<VectorReader> = (0..col_count)
-            .map(|c| VectorReader::new(input, c))
-            .collect();
+        // Read all condition vectors (BOOLEAN or INTEGER, NULL-safe)
+        let readers = condition_readers(input, 0, col_count);
 
         let mut conditions = Vec::with_capacity(col_count);
         for i in 0..row_count {
-            let Some(state) = FfiState::<RetentionState>::with_state_mut(*states.add(i)) else {
+            let Some(state) = FfiState::<S>::with_state_mut(*states.add(i)) else {
                 continue;
             };
 
             conditions.clear();
             for reader in &readers {
-                let valid = reader.is_valid(i);
-                let value = if valid { reader.read_bool(i) } else { false };
-                conditions.push(value);
+                conditions.push(reader.read(i));
+            }
+
+            state.update_row(&conditions);
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one LIST(BOOLEAN) column
+// (as registered). `states` points to `row_count` aggregate state pointers
+// initialized by `FfiState::init_callback`.
+pub(crate) unsafe extern "C" fn list_update(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let reader = VectorReader::new(input, 0);
+        let vector = duckdb_data_chunk_get_vector(input, 0);
+        let entries = duckdb_vector_get_data(vector).cast::<duckdb_list_entry>();
+        let child = duckdb_list_vector_get_child(vector);
+        let values = duckdb_vector_get_data(child).cast::<u8>();
+        // NULL when every child value is valid.
+        let validity = duckdb_vector_get_validity(child);
+
+        let mut conditions = Vec::new();
+        for i in 0..row_count {
+            // A NULL list contributes nothing, not even its length.
+            if !reader.is_valid(i) {
+                continue;
+            }
+            let Some(state) = FfiState::<RetentionState>::with_state_mut(*states.add(i)) else {
+                continue;
+            };
+
+            let entry = *entries.add(i);
+            conditions.clear();
+            for j in entry.offset..entry.offset + entry.length {
+                // NULL elements are false, as for the variadic overloads.
+                let valid = validity.is_null() || duckdb_validity_row_is_valid(validity, j);
+                conditions.push(valid && *values.add(j as usize) != 0);
             }
 
             state.update(&conditions);
@@ -92,7 +324,7 @@ unsafe extern "C" fn state_update(
 }
 
 // SAFETY: `source` and `target` point to `count` aggregate state pointers.
-unsafe extern "C" fn state_combine(
+pub(crate) unsafe extern "C" fn state_combine(
     _info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
     target: *mut duckdb_aggregate_state,
@@ -114,8 +346,7 @@ unsafe extern "C" fn state_combine(
 }
 
 // SAFETY: `source` points to `count` aggregate state pointers. `result` is a
-// valid DuckDB LIST(BOOLEAN) vector. We use ListVector + VectorWriter to write
-// entries: reserve space, set size, write list_entry offsets, then write child data.
+// valid DuckDB LIST(BOOLEAN) vector.
 unsafe extern "C" fn state_finalize(
     _info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
@@ -134,20 +365,326 @@ unsafe extern "C" fn state_finalize(
                 continue;
             };
 
-            let retention_result = state.finalize();
+            write_bool_list(result, idx, &state.finalize());
+        }
+    }
+}
+
+/// Appends `values` to the child of the `LIST(BOOLEAN)` vector `result` and
+/// points row `idx` at them.
+///
+/// We use `ListVector` + `VectorWriter` to write entries: reserve space, write
+/// the child data, then set the size and the row's list entry.
+///
+/// # Safety
+///
+/// `result` must be a valid `DuckDB` `LIST(BOOLEAN)` vector.
+pub(crate) unsafe fn write_bool_list(result: duckdb_vector, idx: usize, values: &[bool]) {
+    unsafe {
+        let current_size = ListVector::get_size(result) as u64;
+        let new_size = current_size + values.len() as u64;
+        ListVector::reserve(result, new_size as usize);
+
+        // Write boolean values to child vector
+        let mut child_writer = ListVector::child_writer(result);
+        for (j, &val) in values.iter().enumerate() {
+            child_writer.write_bool(current_size as usize + j, val);
+        }
+
+        ListVector::set_size(result, new_size as usize);
+        ListVector::set_entry(result, idx, current_size, values.len() as u64);
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn counts_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<RetentionCountsState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<RetentionCountsState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(BIGINT) vector.
+unsafe extern "C" fn counts_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut parent_writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<RetentionCountsState>::with_state(*source.add(i)) else {
+                parent_writer.set_null(idx);
+                continue;
+            };
+
+            write_i64_list(result, idx, &state.finalize());
+        }
+    }
+}
+
+/// Appends `values` to the child of the `LIST(BIGINT)` vector `result` and
+/// points row `idx` at them, like [`write_bool_list`].
+///
+/// # Safety
+///
+/// `result` must be a valid `DuckDB` `LIST(BIGINT)` vector.
+unsafe fn write_i64_list(result: duckdb_vector, idx: usize, values: &[i64]) {
+    unsafe {
+        let current_size = ListVector::get_size(result) as u64;
+        let new_size = current_size + values.len() as u64;
+        ListVector::reserve(result, new_size as usize);
+
+        let mut child_writer = ListVector::child_writer(result);
+        for (j, &val) in values.iter().enumerate() {
+            child_writer.write_i64(current_size as usize + j, val);
+        }
+
+        ListVector::set_size(result, new_size as usize);
+        ListVector::set_entry(result, idx, current_size, values.len() as u64);
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(DOUBLE) vector, written like `counts_finalize`. A group
+// without an anchor row has no rate and gets a NULL list.
+unsafe extern "C" fn rate_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut parent_writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(rates) = FfiState::<RetentionCountsState>::with_state(*source.add(i))
+                .and_then(RetentionCountsState::finalize_rates)
+            else {
+                parent_writer.set_null(idx);
+                continue;
+            };
 
             let current_size = ListVector::get_size(result) as u64;
-            let new_size = current_size + retention_result.len() as u64;
+            let new_size = current_size + rates.len() as u64;
             ListVector::reserve(result, new_size as usize);
 
-            // Write boolean values to child vector
             let mut child_writer = ListVector::child_writer(result);
-            for (j, &val) in retention_result.iter().enumerate() {
-                child_writer.write_bool(current_size as usize + j, val);
+            for (j, &val) in rates.iter().enumerate() {
+                child_writer.write_f64(current_size as usize + j, val);
             }
 
             ListVector::set_size(result, new_size as usize);
-            ListVector::set_entry(result, idx, current_size, retention_result.len() as u64);
+            ListVector::set_entry(result, idx, current_size, rates.len() as u64);
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with N BOOLEAN or INTEGER
+// columns (as registered). `states` points to `row_count` aggregate state
+// pointers.
+unsafe extern "C" fn validity_update(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+        let readers = condition_readers(input, 0, col_count);
+
+        let mut conditions = Vec::with_capacity(col_count);
+        for i in 0..row_count {
+            let Some(state) = FfiState::<RetentionValidityState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            conditions.clear();
+            for reader in &readers {
+                conditions.push(reader.is_valid(i).then(|| reader.read(i)));
+            }
+
+            state.update(&conditions);
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn validity_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<RetentionValidityState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<RetentionValidityState>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB STRUCT(values LIST(BOOLEAN), non_null_counts LIST(BIGINT))
+// vector; each list is written into its child vector of `result`.
+unsafe extern "C" fn validity_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut parent_writer = VectorWriter::new(result);
+        let values_vector = duckdb_struct_vector_get_child(result, 0);
+        let counts_vector = duckdb_struct_vector_get_child(result, 1);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<RetentionValidityState>::with_state(*source.add(i)) else {
+                parent_writer.set_null(idx);
+                continue;
+            };
+
+            let (values, non_null_counts) = state.finalize();
+            write_bool_list(values_vector, idx, &values);
+            write_i64_list(counts_vector, idx, &non_null_counts);
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (TIMESTAMP,
+// TIMESTAMP, INTERVAL, BIGINT) as registered. `states` points to `row_count`
+// aggregate state pointers.
+//
+// The period and period count are read from the first row where both are
+// non-NULL; a month-based or non-positive period, or a non-positive count,
+// fails the query.
+unsafe extern "C" fn period_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+
+        let base_reader = VectorReader::new(input, 0);
+        let ts_reader = VectorReader::new(input, 1);
+        let period_reader = VectorReader::new(input, 2);
+        let n_reader = VectorReader::new(input, 3);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<RetentionByPeriodState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            if state.num_periods == 0 && period_reader.is_valid(i) && n_reader.is_valid(i) {
+                let iv = period_reader.read_interval(i);
+                let Some(period_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                    let message =
+                        interval_message("retention_by_period", iv.months, iv.days, iv.micros);
+                    report(info, &message);
+                    return;
+                };
+                if period_us <= 0 {
+                    report(
+                        info,
+                        &non_positive_message("retention_by_period", "period", period_us),
+                    );
+                    return;
+                }
+                let n = n_reader.read_i64(i);
+                if n <= 0 {
+                    report(info, &limit_message("retention_by_period", "n", n));
+                    return;
+                }
+                state.period_us = period_us;
+                state.num_periods = usize::try_from(n).unwrap_or(usize::MAX);
+            }
+
+            if !base_reader.is_valid(i) || !ts_reader.is_valid(i) {
+                continue;
+            }
+            state.update(base_reader.read_i64(i), ts_reader.read_i64(i));
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn period_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<RetentionByPeriodState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<RetentionByPeriodState>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(BOOLEAN) vector.
+unsafe extern "C" fn period_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut parent_writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<RetentionByPeriodState>::with_state(*source.add(i)) else {
+                parent_writer.set_null(idx);
+                continue;
+            };
+
+            write_bool_list(result, idx, &state.finalize());
         }
     }
 }
@@ -221,4 +758,75 @@ mod tests {
         );
         assert_eq!(state.finalize(), vec![true, true, true]);
     }
+
+    #[test]
+    fn test_retention_combine_propagates_wide_conditions() {
+        // List input longer than 64: the zero-initialized target must pick up
+        // the wide words as well as the inline mask.
+        let mut conditions = vec![false; 100];
+        conditions[0] = true;
+        conditions[99] = true;
+        let mut source = AggregateTestHarness::<RetentionState>::new();
+        source.update(|s| s.update(&conditions));
+
+        let mut target = AggregateTestHarness::<RetentionState>::new();
+        target.combine(&source, |src, tgt| {
+            let combined = tgt.combine(src);
+            *tgt = combined;
+        });
+
+        let state = target.finalize();
+        assert_eq!(state.wide_conditions_met, vec![1 << 35]);
+        assert_eq!(state.num_conditions, 100);
+        assert!(state.finalize()[99]);
+    }
+
+    #[test]
+    fn test_retention_counts_combine_propagates_counts() {
+        // Zero-initialized target combine pattern (Session 10 bug).
+        let mut source = AggregateTestHarness::<RetentionCountsState>::new();
+        source.update(|s| {
+            s.update(&[true, true, false]);
+            s.update(&[true, false, false]);
+        });
+
+        let mut target = AggregateTestHarness::<RetentionCountsState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.finalize(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_retention_with_validity_combine_propagates_counts() {
+        let mut source = AggregateTestHarness::<RetentionValidityState>::new();
+        source.update(|s| {
+            s.update(&[Some(true), None, Some(true)]);
+            s.update(&[Some(false), Some(false), None]);
+        });
+
+        let mut target = AggregateTestHarness::<RetentionValidityState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.finalize(), (vec![true, false, true], vec![2, 1, 1]));
+    }
+
+    #[test]
+    fn test_retention_by_period_combine_propagates_config() {
+        let mut source = AggregateTestHarness::<RetentionByPeriodState>::new();
+        source.update(|s| {
+            s.period_us = 3_600_000_000;
+            s.num_periods = 3;
+            s.update(0, 0);
+            s.update(0, 7_200_000_000);
+        });
+
+        let mut target = AggregateTestHarness::<RetentionByPeriodState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.num_periods, 3);
+        assert_eq!(state.finalize(), vec![true, false, true]);
+    }
 }
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `retention_state` aggregate function and the
+//! `retention_merge` and `retention_finalize` scalar functions.
+//!
+//! `retention_state` shares the update and combine callbacks of
+//! [`retention`](super::retention) and finalizes to the `BLOB` of
+//! [`RetentionState::to_blob`]. The two scalar functions are registered
+//! through [`state_export`](super::state_export); `retention_finalize`
+//! writes its list like `retention` does.
+
+use crate::common::state_blob::BlobError;
+use crate::ffi::conditions::{CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::retention::{list_update, state_combine, state_update, write_bool_list};
+use crate::ffi::state_export::{
+    finalize_rows, register_finalize, register_merge, write_blob, ExportedState,
+};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::retention::RetentionState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::VectorWriter;
+
+/// Registers the `retention_state` function with `DuckDB` as a function set
+/// with the overloads of `retention`.
+///
+/// Signatures:
+/// - `retention_state(BOOLEAN, BOOLEAN [, BOOLEAN ...]) -> BLOB`
+/// - `retention_state(BOOLEAN[]) -> BLOB`
+///
+/// The result is the exported state (see [`RetentionState::to_blob`]), to be
+/// combined with `retention_merge` and finished with `retention_finalize`.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_retention_state(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("retention_state").returns(TypeId::Blob);
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder;
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, RetentionState)
+                .update(state_update::<RetentionState>)
+                .combine(state_combine)
+                .finalize(state_finalize_blob)
+        });
+    }
+    builder = builder.overloads(1..=1, |_, builder| {
+        let b = builder.param_logical(LogicalType::list(TypeId::Boolean));
+        with_lifecycle!(b, RetentionState)
+            .update(list_update)
+            .combine(state_combine)
+            .finalize(state_finalize_blob)
+    });
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `retention_merge` function with `DuckDB`.
+///
+/// Signature: `retention_merge(BLOB, BLOB) -> BLOB`
+///
+/// Returns the state of the rows of both states: each condition is met if it
+/// was met in either. A `NULL` argument is an empty state, so the result is
+/// the other argument; both `NULL` produces `NULL`. Malformed blobs fail the
+/// query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_retention_merge(con: duckdb_connection) {
+    unsafe { register_merge::<RetentionState>(con) }
+}
+
+/// Registers the `retention_finalize` function with `DuckDB`.
+///
+/// Signature: `retention_finalize(BLOB) -> BOOLEAN[]`
+///
+/// Returns the array `retention` would have returned for the rows of the
+/// state. `NULL` input produces `NULL`; a malformed blob fails the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_retention_finalize(con: duckdb_connection) {
+    unsafe {
+        let mut boolean = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN);
+        let list = duckdb_create_list_type(boolean);
+        duckdb_destroy_logical_type(&mut boolean);
+        register_finalize(
+            con,
+            c"retention_finalize",
+            list,
+            retention_finalize_function,
+        );
+    }
+}
+
+impl ExportedState for RetentionState {
+    const NAME: &'static str = "retention";
+
+    fn from_blob(bytes: &[u8]) -> Result<Self, BlobError> {
+        Self::from_blob(bytes)
+    }
+
+    fn to_blob(&self) -> Vec<u8> {
+        self.to_blob()
+    }
+
+    fn merge(self, other: &Self) -> Result<Self, &'static str> {
+        Ok(self.combine(other))
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB BLOB vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize_blob(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<RetentionState>::with_state(*source.add(i)) {
+                Some(state) => write_blob(result, idx, &state.to_blob()),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one BLOB column as
+// registered. `output` is a valid LIST(BOOLEAN) vector sized for the chunk.
+unsafe extern "C" fn retention_finalize_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        finalize_rows::<RetentionState>(info, input, output, |_, row, state| {
+            write_bool_list(output, row, &state.finalize());
+        });
+    }
+}
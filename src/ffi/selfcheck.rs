@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `behavioral_selfcheck` scalar function.
+//!
+//! The function takes no arguments, so registration uses the raw
+//! `libduckdb-sys` scalar function API directly, like [`sessionize`](super::sessionize).
+//! The `STRUCT` result is written through
+//! [`quack_rs::vector::complex::StructVector`] field writers, as in
+//! [`event_gap_stats`](super::event_gap_stats).
+
+use crate::selfcheck::{run, SelfCheckReport};
+use libduckdb_sys::*;
+use quack_rs::vector::complex::StructVector;
+use std::ffi::CStr;
+
+/// `STRUCT` fields of the result, in order.
+const FIELDS: [(&CStr, DUCKDB_TYPE); 8] = [
+    (c"passed", DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+    (c"parser_passed", DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+    (c"parser_us", DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+    (c"funnel_passed", DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+    (c"funnel_us", DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+    (c"combine_passed", DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+    (c"combine_us", DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+    (c"total_us", DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+];
+
+/// Registers the `behavioral_selfcheck` function with `DuckDB`.
+///
+/// Signature: `behavioral_selfcheck() -> STRUCT(passed BOOLEAN,
+/// parser_passed BOOLEAN, parser_us BIGINT, funnel_passed BOOLEAN,
+/// funnel_us BIGINT, combine_passed BOOLEAN, combine_us BIGINT, total_us BIGINT)`
+///
+/// The function is volatile: timings differ between calls, and the suites must
+/// run at execution time rather than be folded into a constant at bind time.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_behavioral_selfcheck(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, c"behavioral_selfcheck".as_ptr());
+
+        let mut types: Vec<duckdb_logical_type> = FIELDS
+            .iter()
+            .map(|&(_, type_id)| duckdb_create_logical_type(type_id))
+            .collect();
+        let mut names: Vec<*const std::ffi::c_char> =
+            FIELDS.iter().map(|(name, _)| name.as_ptr()).collect();
+        let ret_type = duckdb_create_struct_type(
+            types.as_mut_ptr(),
+            names.as_mut_ptr(),
+            FIELDS.len() as idx_t,
+        );
+        duckdb_scalar_function_set_return_type(func, ret_type);
+        duckdb_destroy_logical_type(&mut { ret_type });
+        for mut member in types {
+            duckdb_destroy_logical_type(&mut member);
+        }
+
+        duckdb_scalar_function_set_volatile(func);
+        duckdb_scalar_function_set_function(func, Some(selfcheck_function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register behavioral_selfcheck function");
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+/// Returns each suite's `(passed, elapsed_us)` pair in [`FIELDS`] order.
+const fn field_values(report: &SelfCheckReport) -> [(bool, i64); 3] {
+    [
+        (report.parser.passed, report.parser.elapsed_us),
+        (report.funnel.passed, report.funnel.elapsed_us),
+        (report.combine.passed, report.combine.elapsed_us),
+    ]
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with no columns; its size is the
+// number of rows to produce. `output` is a valid STRUCT vector with the eight
+// fields of `FIELDS`, sized for at least that many rows.
+unsafe extern "C" fn selfcheck_function(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let report = run();
+
+        let mut passed_writer = StructVector::field_writer(output, 0);
+        let mut total_writer = StructVector::field_writer(output, FIELDS.len() - 1);
+        let mut suite_writers: Vec<_> = (0..3)
+            .map(|suite| {
+                (
+                    StructVector::field_writer(output, 1 + 2 * suite),
+                    StructVector::field_writer(output, 2 + 2 * suite),
+                )
+            })
+            .collect();
+
+        for row in 0..row_count {
+            passed_writer.write_bool(row, report.passed());
+            total_writer.write_i64(row, report.total_us());
+            for ((passed, elapsed_us), (suite_passed_writer, us_writer)) in
+                field_values(&report).into_iter().zip(&mut suite_writers)
+            {
+                suite_passed_writer.write_bool(row, passed);
+                us_writer.write_i64(row, elapsed_us);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_match_report_layout() {
+        // Field 0 is the overall result, the last is the total, and each suite
+        // contributes a (passed, us) pair in the order of `field_values`.
+        let names: Vec<&str> = FIELDS.iter().map(|(n, _)| n.to_str().unwrap()).collect();
+        assert_eq!(names[0], "passed");
+        assert_eq!(names[FIELDS.len() - 1], "total_us");
+        let report = run();
+        assert_eq!(1 + 2 * field_values(&report).len() + 1, FIELDS.len());
+        for (suite, prefix) in ["parser", "funnel", "combine"].iter().enumerate() {
+            assert_eq!(names[1 + 2 * suite], format!("{prefix}_passed"));
+            assert_eq!(names[2 + 2 * suite], format!("{prefix}_us"));
+        }
+    }
+}
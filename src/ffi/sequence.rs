@@ -1,29 +1,40 @@
 // SPDX-License-Identifier: MIT
 // Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
 
-//! FFI registration for `sequence_match` and `sequence_count` aggregate functions.
+//! FFI registration for the `sequence_match`, `sequence_count` and
+//! `sequence_count_within` aggregate functions.
 //!
 //! Uses [`quack_rs::aggregate::AggregateFunctionSetBuilder`] for function set
 //! registration, [`quack_rs::aggregate::FfiState`] for safe state management,
 //! and [`quack_rs::vector::VectorReader`] for safe vector reading.
 
-use crate::common::event::Event;
-use crate::sequence::SequenceState;
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::conditions::{
+    chunk_events, chunk_events_with_seq, CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS,
+};
+use crate::ffi::error::{
+    choice_message, interval_message, order_message, pattern_message, report, PatternCheck,
+};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sequence::{InputOrder, SequenceState};
 use libduckdb_sys::*;
-use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, AggregateState, FfiState};
 use quack_rs::types::TypeId;
 use quack_rs::vector::{VectorReader, VectorWriter};
-
-/// Minimum number of boolean condition parameters for sequence functions.
-const MIN_CONDITIONS: usize = 2;
-/// Maximum number of boolean condition parameters for sequence functions.
-const MAX_CONDITIONS: usize = 32;
+use std::sync::Arc;
 
 impl quack_rs::aggregate::AggregateState for SequenceState {}
 
 /// Registers the `sequence_match` function with `DuckDB`.
 ///
-/// Signature: `sequence_match(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BOOLEAN`
+/// Signatures:
+///
+/// 1. `sequence_match(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BOOLEAN`
+/// 2. With sequence number: `sequence_match(VARCHAR, TIMESTAMP, BIGINT, BOOLEAN, BOOLEAN [, ...]) -> BOOLEAN`
+/// 3. With input order: `sequence_match(VARCHAR, VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BOOLEAN`
+///
+/// The BIGINT orders events with the same timestamp (see
+/// [`SequenceState::seqs`]). The second VARCHAR is the [`InputOrder`].
 ///
 /// # Safety
 ///
@@ -35,26 +46,58 @@ impl quack_rs::aggregate::AggregateState for SequenceState {}
 pub unsafe fn register_sequence_match(
     con: &impl quack_rs::connection::Registrar,
 ) -> Result<(), quack_rs::error::ExtensionError> {
-    let builder = AggregateFunctionSetBuilder::new("sequence_match")
-        .returns(TypeId::Boolean)
-        .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, |n, builder| {
-            let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
-            for _ in 0..n {
-                b = b.param(TypeId::Boolean);
-            }
-            b.state_size(FfiState::<SequenceState>::size_callback)
-                .init(FfiState::<SequenceState>::init_callback)
-                .update(sequence_state_update)
-                .combine(sequence_state_combine)
-                .finalize(match_state_finalize)
-                .destructor(FfiState::<SequenceState>::destroy_callback)
-        });
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_match").returns(TypeId::Boolean);
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, SequenceState)
+                    .update(match_state_update)
+                    .combine(sequence_state_combine)
+                    .finalize(match_state_finalize)
+            })
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp)
+                    .param(TypeId::BigInt);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, SequenceState)
+                    .update(match_state_update_with_seq)
+                    .combine(sequence_state_combine)
+                    .finalize(match_state_finalize)
+            })
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, SequenceState)
+                    .update(match_state_update_with_order)
+                    .combine(sequence_state_combine)
+                    .finalize(match_state_finalize)
+            });
+    }
     unsafe { con.register_aggregate_set(builder) }
 }
 
 /// Registers the `sequence_count` function with `DuckDB`.
 ///
-/// Signature: `sequence_count(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BIGINT`
+/// Signatures:
+///
+/// 1. `sequence_count(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BIGINT`
+/// 2. With sequence number: `sequence_count(VARCHAR, TIMESTAMP, BIGINT, BOOLEAN, BOOLEAN [, ...]) -> BIGINT`
+///
+/// The BIGINT orders events with the same timestamp (see
+/// [`SequenceState::seqs`]).
 ///
 /// # Safety
 ///
@@ -66,29 +109,81 @@ pub unsafe fn register_sequence_match(
 pub unsafe fn register_sequence_count(
     con: &impl quack_rs::connection::Registrar,
 ) -> Result<(), quack_rs::error::ExtensionError> {
-    let builder = AggregateFunctionSetBuilder::new("sequence_count")
-        .returns(TypeId::BigInt)
-        .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, |n, builder| {
-            let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_count").returns(TypeId::BigInt);
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, SequenceState)
+                    .update(count_state_update)
+                    .combine(sequence_state_combine)
+                    .finalize(count_state_finalize)
+            })
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp)
+                    .param(TypeId::BigInt);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, SequenceState)
+                    .update(count_state_update_with_seq)
+                    .combine(sequence_state_combine)
+                    .finalize(count_state_finalize)
+            });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `sequence_count_within` function with `DuckDB`.
+///
+/// Signature: `sequence_count_within(INTERVAL, VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BIGINT`
+///
+/// Counts like `sequence_count`, but only matches whose first and last
+/// matched events are at most the INTERVAL apart (see
+/// [`SequenceState::window_us`]).
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_count_within(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder =
+        AggregateFunctionSetBuilder::new("sequence_count_within").returns(TypeId::BigInt);
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder
+                .param(TypeId::Interval)
+                .param(TypeId::Varchar)
+                .param(TypeId::Timestamp);
             for _ in 0..n {
-                b = b.param(TypeId::Boolean);
+                b = b.param(cond_type);
             }
-            b.state_size(FfiState::<SequenceState>::size_callback)
-                .init(FfiState::<SequenceState>::init_callback)
-                .update(sequence_state_update)
+            with_lifecycle!(b, SequenceState)
+                .update(count_within_state_update)
                 .combine(sequence_state_combine)
-                .finalize(count_state_finalize)
-                .destructor(FfiState::<SequenceState>::destroy_callback)
+                .finalize(count_within_state_finalize)
         });
+    }
     unsafe { con.register_aggregate_set(builder) }
 }
 
 // -- sequence_match finalize --
 
 // SAFETY: `source` points to `count` aggregate state pointers. `result` is a
-// valid DuckDB BOOLEAN vector. Pattern errors produce NULL output via validity bitmap.
+// valid DuckDB BOOLEAN vector. Pattern and `verify_sorted` order errors are
+// reported as query errors.
 unsafe extern "C" fn match_state_finalize(
-    _info: duckdb_function_info,
+    info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
     result: duckdb_vector,
     count: idx_t,
@@ -105,24 +200,61 @@ unsafe extern "C" fn match_state_finalize(
                 continue;
             };
 
+            if let Err(e) = state.verify_order() {
+                report(info, &order_message("sequence_match", &e));
+                return;
+            }
             match state.finalize_match() {
                 Ok(matched) => writer.write_bool(idx, matched),
-                Err(_) => writer.set_null(idx),
+                Err(e) => {
+                    let pattern = state.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_match", pattern, &e));
+                    return;
+                }
             }
         }
     }
 }
 
-// -- sequence_count finalize --
+// -- sequence_count / sequence_count_within finalize --
 
-// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
-// valid DuckDB BIGINT vector. Pattern errors produce NULL output via validity bitmap.
+// SAFETY: see `count_finalize`.
 unsafe extern "C" fn count_state_finalize(
-    _info: duckdb_function_info,
+    info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
     result: duckdb_vector,
     count: idx_t,
     offset: idx_t,
+) {
+    unsafe { count_finalize(info, source, result, count, offset, "sequence_count") }
+}
+
+// SAFETY: see `count_finalize`.
+unsafe extern "C" fn count_within_state_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe { count_finalize(info, source, result, count, offset, "sequence_count_within") }
+}
+
+/// Finalize body shared by `sequence_count` and `sequence_count_within`;
+/// `function` names the caller in error messages.
+///
+/// # Safety
+///
+/// `source` must point to `count` aggregate state pointers and `result` must
+/// be a valid DuckDB BIGINT vector. Pattern errors are reported as query
+/// errors.
+unsafe fn count_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+    function: &str,
 ) {
     unsafe {
         let mut writer = VectorWriter::new(result);
@@ -137,7 +269,11 @@ unsafe extern "C" fn count_state_finalize(
 
             match state.finalize_count() {
                 Ok(n) => writer.write_i64(idx, n),
-                Err(_) => writer.set_null(idx),
+                Err(e) => {
+                    let pattern = state.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message(function, pattern, &e));
+                    return;
+                }
             }
         }
     }
@@ -145,55 +281,207 @@ unsafe extern "C" fn count_state_finalize(
 
 // -- Shared update/combine callbacks --
 
-// SAFETY: `input` is a valid DuckDB data chunk with columns (VARCHAR, TIMESTAMP,
-// BOOLEAN...) as registered. `states` points to `row_count` aggregate state pointers.
-// VARCHAR is read via VectorReader::read_str() which handles duckdb_string_t correctly.
-unsafe extern "C" fn sequence_state_update(
-    _info: duckdb_function_info,
+// SAFETY: see `sequence_state_update`.
+unsafe extern "C" fn match_state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { sequence_state_update(info, input, states, "sequence_match", false) }
+}
+
+// SAFETY: see `sequence_state_update`.
+unsafe extern "C" fn match_state_update_with_seq(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { sequence_state_update(info, input, states, "sequence_match", true) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (VARCHAR,
+// VARCHAR, TIMESTAMP, BOOLEAN or INTEGER...) as registered. `states` points
+// to `row_count` aggregate state pointers.
+unsafe extern "C" fn match_state_update_with_order(
+    info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
 ) {
     unsafe {
         let row_count = duckdb_data_chunk_get_size(input) as usize;
-        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
-        // Vector 0: VARCHAR (pattern) — read via VectorReader::read_str()
-        let pattern_reader = VectorReader::new(input, 0);
+        let order_reader = VectorReader::new(input, 1);
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SequenceState>::with_state_mut(*states.add(i)) else {
+                continue;
+            };
+            if state.order.is_some() || !order_reader.is_valid(i) {
+                continue;
+            }
+            let order_str = order_reader.read_str(i);
+            let Some(order) = InputOrder::parse(order_str) else {
+                let message =
+                    choice_message("sequence_match", "order", order_str, &InputOrder::NAMES);
+                report(info, &message);
+                return;
+            };
+            state.order = Some(order);
+        }
 
-        // Vector 1: TIMESTAMP
-        let ts_reader = VectorReader::new(input, 1);
+        update_columns::<SequenceState>(info, input, states, "sequence_match", Some(0), 2, false);
+    }
+}
 
-        // Vectors 2..N: BOOLEAN conditions
-        let cond_readers: Vec<VectorReader> = (2..col_count)
-            .map(|c| VectorReader::new(input, c))
-            .collect();
+// SAFETY: see `sequence_state_update`.
+unsafe extern "C" fn count_state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { sequence_state_update(info, input, states, "sequence_count", false) }
+}
 
+// SAFETY: see `sequence_state_update`.
+unsafe extern "C" fn count_state_update_with_seq(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { sequence_state_update(info, input, states, "sequence_count", true) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL,
+// VARCHAR, TIMESTAMP, BOOLEAN or INTEGER...) as registered. `states` points
+// to `row_count` aggregate state pointers.
+unsafe extern "C" fn count_within_state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let window_reader = VectorReader::new(input, 0);
         for i in 0..row_count {
             let Some(state) = FfiState::<SequenceState>::with_state_mut(*states.add(i)) else {
                 continue;
             };
-
-            // Read pattern from first row (same for all rows in a group)
-            if state.pattern_str.is_none() && pattern_reader.is_valid(i) {
-                let s = pattern_reader.read_str(i);
-                state.set_pattern(s);
-            }
-
-            // Skip NULL timestamps
-            if !ts_reader.is_valid(i) {
+            if state.window_us.is_some() || !window_reader.is_valid(i) {
                 continue;
             }
+            let iv = window_reader.read_interval(i);
+            let Some(window_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                let message =
+                    interval_message("sequence_count_within", iv.months, iv.days, iv.micros);
+                report(info, &message);
+                return;
+            };
+            state.window_us = Some(window_us);
+        }
 
-            let timestamp = ts_reader.read_i64(i);
+        update_columns::<SequenceState>(
+            info,
+            input,
+            states,
+            "sequence_count_within",
+            Some(1),
+            2,
+            false,
+        );
+    }
+}
+
+/// Update callback body shared by every aggregate over [`SequenceState`]
+/// (`sequence_match`, `sequence_count`, `sequence_match_events`,
+/// `sequence_match_spans`, `sequence_duration`); `function` names the caller
+/// in error messages. With `has_seq`, column 2 is a `BIGINT` sequence number
+/// and the conditions start at column 3.
+///
+/// The chunk's events are read column-at-a-time by [`chunk_events`]. `DuckDB`
+/// passes one state pointer per row, and rows of the same group are usually
+/// adjacent, so each run of equal pointers is appended with one
+/// [`SequenceState::update_batch`] call.
+///
+/// # Safety
+///
+/// `input` must be a valid data chunk with columns (VARCHAR, TIMESTAMP,
+/// \[BIGINT,\] BOOLEAN or INTEGER...) as registered, and `states` must point to one
+/// aggregate state pointer per row. Patterns referencing more conditions than
+/// supplied are reported as query errors.
+pub unsafe fn sequence_state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+    function: &'static str,
+    has_seq: bool,
+) {
+    unsafe {
+        update_columns::<SequenceState>(info, input, states, function, Some(0), 1, has_seq);
+    }
+}
 
-            // Pack conditions into u32 bitmask (max 32 conditions from function set)
-            let mut bitmask: u32 = 0;
-            for (c, reader) in cond_readers.iter().enumerate() {
-                if reader.is_valid(i) && reader.read_bool(i) {
-                    bitmask |= 1 << c;
+/// [`sequence_state_update`] with the pattern in column `pattern_col` and the
+/// timestamp in column `ts_col` rather than 0 and 1, for any state holding a
+/// [`SequenceState`]: `sequence_count_within`, whose window comes first, and
+/// the labeled `sequence_match_events`, whose labels follow the pattern. With
+/// no `pattern_col` no pattern is read: `sequence_match_any` keeps its
+/// patterns outside the [`SequenceState`].
+///
+/// # Safety
+///
+/// As for [`sequence_state_update`], with the columns moved accordingly and
+/// `states` initialized as `S`.
+pub(crate) unsafe fn update_columns<S: AggregateState + AsMut<SequenceState>>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+    function: &'static str,
+    pattern_col: Option<usize>,
+    ts_col: usize,
+    has_seq: bool,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+        let cond_start = ts_col + 1 + usize::from(has_seq);
+        // Pattern column: VARCHAR — read via VectorReader::read_str()
+        let pattern_reader = pattern_col.map(|col| VectorReader::new(input, col));
+        let mut patterns = PatternCheck::new(function, col_count - cond_start);
+
+        // Then TIMESTAMP, an optional BIGINT sequence and the conditions
+        let events = if has_seq {
+            chunk_events_with_seq(input, ts_col, ts_col + 1, cond_start, col_count)
+        } else {
+            chunk_events(input, ts_col, cond_start, col_count)
+        };
+
+        if row_count == 0 {
+            return;
+        }
+        let states = std::slice::from_raw_parts(states, row_count);
+        let mut start = 0;
+        for run in states.chunk_by(|a, b| a == b) {
+            let rows = start..start + run.len();
+            start = rows.end;
+            let Some(state) = FfiState::<S>::with_state_mut(run[0]) else {
+                continue;
+            };
+            let state = state.as_mut();
+
+            // Read pattern from the first row that has one (same for all rows in a group)
+            if let (Some(pattern_reader), None) = (&pattern_reader, &state.pattern_str) {
+                if let Some(i) = rows.clone().find(|&i| pattern_reader.is_valid(i)) {
+                    let s = pattern_reader.read_str(i);
+                    if !patterns.check(info, s) {
+                        return;
+                    }
+                    state.set_pattern(s);
                 }
             }
 
-            state.update(Event::new(timestamp, bitmask));
+            if has_seq {
+                state.update_batch_with_seq(events.rows_with_seq(rows));
+            } else {
+                state.update_batch(events.rows(rows));
+            }
         }
     }
 }
@@ -219,9 +507,77 @@ unsafe extern "C" fn sequence_state_combine(
     }
 }
 
+/// Reads a `LIST(VARCHAR)` argument column. [`VectorReader`] reads scalar
+/// columns only, so the list entries and the child vector are read with raw
+/// `libduckdb-sys` calls, as in the `retention(BOOLEAN[])` overload of
+/// [`retention`](super::retention).
+pub(crate) struct VarcharListReader {
+    reader: VectorReader,
+    entries: *const duckdb_list_entry,
+    strings: *mut duckdb_string_t,
+    /// NULL when every element is valid.
+    validity: *mut u64,
+}
+
+impl VarcharListReader {
+    /// Creates a reader for the `LIST(VARCHAR)` column `col` of `input`.
+    ///
+    /// # Safety
+    ///
+    /// `input` must be a valid data chunk whose column `col` is a
+    /// `LIST(VARCHAR)` vector.
+    pub(crate) unsafe fn new(input: duckdb_data_chunk, col: usize) -> Self {
+        unsafe {
+            let vector = duckdb_data_chunk_get_vector(input, col as idx_t);
+            let child = duckdb_list_vector_get_child(vector);
+            Self {
+                reader: VectorReader::new(input, col),
+                entries: duckdb_vector_get_data(vector).cast::<duckdb_list_entry>(),
+                strings: duckdb_vector_get_data(child).cast::<duckdb_string_t>(),
+                validity: duckdb_vector_get_validity(child),
+            }
+        }
+    }
+
+    /// Returns whether the list at `row` is non-`NULL`.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be less than the chunk's row count.
+    pub(crate) unsafe fn is_valid(&self, row: usize) -> bool {
+        unsafe { self.reader.is_valid(row) }
+    }
+
+    /// Copies the elements of the list at `row`, `None` for a `NULL` element.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be less than the chunk's row count and its list non-`NULL`.
+    pub(crate) unsafe fn read(&self, row: usize) -> Vec<Option<Arc<str>>> {
+        unsafe {
+            let entry = *self.entries.add(row);
+            (entry.offset..entry.offset + entry.length)
+                .map(|j| {
+                    let valid =
+                        self.validity.is_null() || duckdb_validity_row_is_valid(self.validity, j);
+                    valid.then(|| {
+                        let string = self.strings.add(j as usize);
+                        let len = duckdb_string_t_length(*string) as usize;
+                        let data = duckdb_string_t_data(string).cast::<u8>();
+                        Arc::from(String::from_utf8_lossy(std::slice::from_raw_parts(
+                            data, len,
+                        )))
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::event::Event;
     use quack_rs::testing::AggregateTestHarness;
 
     #[test]
@@ -289,4 +645,23 @@ mod tests {
         assert_eq!(matched, count > 0);
         assert_eq!(count, 2);
     }
+
+    #[test]
+    fn test_count_within_window_propagates_from_zero() {
+        let mut source = AggregateTestHarness::<SequenceState>::new();
+        source.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.window_us = Some(1_000_000);
+            s.update(Event::new(1_000_000, 0b01));
+            s.update(Event::new(5_000_000, 0b01));
+            s.update(Event::new(5_500_000, 0b10));
+        });
+
+        let mut target = AggregateTestHarness::<SequenceState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = target.finalize();
+        assert_eq!(state.window_us, Some(1_000_000));
+        assert_eq!(state.finalize_count().unwrap(), 1);
+    }
 }
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sequence_count_by` aggregate function.
+//!
+//! Reads its bucket width like `sequence_count_within` reads its window and
+//! the rest of the row through [`update_columns`]. Returns
+//! `LIST(STRUCT(bucket_start TIMESTAMP, count BIGINT))`, written like
+//! `sequence_match_spans` through [`ListVector`] and [`StructVector`] field
+//! writers; the C API has no `MAP` return type.
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::conditions::{CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{interval_message, non_positive_message, pattern_message, report};
+use crate::ffi::sequence::update_columns;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sequence::SequenceCountByState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::{ListVector, StructVector};
+use quack_rs::vector::VectorReader;
+
+impl quack_rs::aggregate::AggregateState for SequenceCountByState {}
+
+/// Registers the `sequence_count_by` function with `DuckDB`.
+///
+/// Signature: `sequence_count_by(INTERVAL, VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> LIST(STRUCT(bucket_start TIMESTAMP, count BIGINT))`
+///
+/// Counts the matches of `sequence_count` per bucket of the INTERVAL width
+/// holding their first matched event (see
+/// [`SequenceCountByState::finalize`]). Empty list if no match.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_count_by(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let element = LogicalType::struct_type(&[
+        ("bucket_start", TypeId::Timestamp),
+        ("count", TypeId::BigInt),
+    ]);
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_count_by")
+        .returns_logical(LogicalType::list_from_logical(&element));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder
+                .param(TypeId::Interval)
+                .param(TypeId::Varchar)
+                .param(TypeId::Timestamp);
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, SequenceCountByState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL,
+// VARCHAR, TIMESTAMP, BOOLEAN or INTEGER...) as registered. `states` points
+// to `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let bucket_reader = VectorReader::new(input, 0);
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SequenceCountByState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+            if state.bucket_us.is_some() || !bucket_reader.is_valid(i) {
+                continue;
+            }
+            let iv = bucket_reader.read_interval(i);
+            let Some(bucket_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                report(
+                    info,
+                    &interval_message("sequence_count_by", iv.months, iv.days, iv.micros),
+                );
+                return;
+            };
+            if bucket_us <= 0 {
+                report(
+                    info,
+                    &non_positive_message("sequence_count_by", "bucket", bucket_us),
+                );
+                return;
+            }
+            state.bucket_us = Some(bucket_us);
+        }
+
+        update_columns::<SequenceCountByState>(
+            info,
+            input,
+            states,
+            "sequence_count_by",
+            Some(1),
+            2,
+            false,
+        );
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SequenceCountByState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SequenceCountByState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(bucket_start TIMESTAMP, count BIGINT)) vector.
+// Empty list on no match; pattern errors are reported as query errors.
+unsafe extern "C" fn state_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceCountByState>::with_state_mut(*source.add(i))
+            else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let buckets = match state.finalize() {
+                Ok(buckets) => buckets,
+                Err(e) => {
+                    let pattern = state.sequence.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_count_by", pattern, &e));
+                    return;
+                }
+            };
+            let bucket_count = buckets.len() as u64;
+
+            // Reserve space in the list child vector before taking field writers,
+            // since reserve may reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + bucket_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut start_writer = StructVector::field_writer(child, 0);
+            let mut count_writer = StructVector::field_writer(child, 1);
+            for (j, &(bucket_start, matches)) in buckets.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                start_writer.write_i64(pos, bucket_start);
+                count_writer.write_i64(pos, matches);
+            }
+
+            ListVector::set_entry(result, idx, list_offset, bucket_count);
+
+            list_offset += bucket_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<SequenceCountByState>::new();
+        source.update(|s| {
+            s.bucket_us = Some(1_000_000);
+            s.sequence.set_pattern("(?1).*(?2)");
+            s.sequence.update(Event::new(2_500_000, 0b01));
+            s.sequence.update(Event::new(2_700_000, 0b10));
+        });
+        let mut target = AggregateTestHarness::<SequenceCountByState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert_eq!(state.bucket_us, Some(1_000_000));
+        assert_eq!(state.finalize().unwrap(), vec![(2_000_000, 1)]);
+    }
+}
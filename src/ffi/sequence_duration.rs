@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sequence_duration` aggregate function.
+//!
+//! Uses [`quack_rs::aggregate::AggregateFunctionSetBuilder`] for function set
+//! registration and [`quack_rs::vector::VectorWriter::write_interval`] for the
+//! `INTERVAL` result.
+
+use crate::ffi::conditions::{CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{overflow_message, pattern_message, report};
+use crate::ffi::sequence::sequence_state_update;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sequence::{DurationError, SequenceState};
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::interval::DuckInterval;
+use quack_rs::types::TypeId;
+use quack_rs::vector::VectorWriter;
+
+// Note: AggregateState for SequenceState is implemented in ffi/sequence.rs.
+
+/// Registers the `sequence_duration` function with `DuckDB`.
+///
+/// Signature: `sequence_duration(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> INTERVAL`
+///
+/// Returns the time between the first and last matched `(?N)` step of the
+/// first full match — the same match `sequence_match_events` reports. NULL if
+/// no match; a duration that overflows the microsecond range, such as from
+/// `'-infinity'` to `'infinity'`, fails the query.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_duration(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder =
+        AggregateFunctionSetBuilder::new("sequence_duration").returns(TypeId::Interval);
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, SequenceState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: see `sequence_state_update`.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { sequence_state_update(info, input, states, "sequence_duration", false) }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SequenceState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SequenceState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB INTERVAL vector. NULL on no match; pattern errors and durations
+// that overflow are reported as query errors.
+unsafe extern "C" fn state_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceState>::with_state_mut(*source.add(i)) else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            match state.finalize_duration() {
+                Ok(Some(micros)) => writer.write_interval(
+                    idx,
+                    DuckInterval {
+                        months: 0,
+                        days: 0,
+                        micros,
+                    },
+                ),
+                Ok(None) => writer.set_null(idx),
+                Err(DurationError::Pattern(e)) => {
+                    let pattern = state.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_duration", pattern, &e));
+                    return;
+                }
+                Err(DurationError::Overflow(e)) => {
+                    report(info, &overflow_message("sequence_duration", &e));
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_sequence_duration_combine_matches_events() {
+        let mut a = AggregateTestHarness::<SequenceState>::new();
+        a.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.update(Event::new(1_000_000, 0b01));
+        });
+
+        let mut b = AggregateTestHarness::<SequenceState>::new();
+        b.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.update(Event::new(4_500_000, 0b10));
+        });
+
+        b.combine(&a, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = b.finalize();
+        assert_eq!(state.finalize_duration().unwrap(), Some(3_500_000));
+        assert_eq!(state.finalize_events().unwrap(), vec![1_000_000, 4_500_000]);
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sequence_match_any` aggregate function.
+//!
+//! Registered like [`sequence_match`](super::sequence), with a `VARCHAR[]`
+//! patterns argument in place of the pattern. The list is read by the shared
+//! [`VarcharListReader`] once per state; the events go through the shared
+//! [`update_columns`] without a pattern column.
+
+use crate::ffi::conditions::{CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::PatternCheck;
+use crate::ffi::sequence::{update_columns, VarcharListReader};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::pattern::cache;
+use crate::pattern::parser::CompiledPattern;
+use crate::sequence::SequenceMatchAnyState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::VectorWriter;
+use std::sync::Arc;
+
+impl quack_rs::aggregate::AggregateState for SequenceMatchAnyState {}
+
+/// The compiled patterns of one list, `None` for a `NULL` element.
+type Patterns = Arc<[Option<Arc<CompiledPattern>>]>;
+
+/// Registers the `sequence_match_any` function with `DuckDB`.
+///
+/// Signature:
+/// - `sequence_match_any(VARCHAR[], TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
+///
+/// Returns the 1-based index of the first pattern in the list that matches,
+/// or `NULL` if none does.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_match_any(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder =
+        AggregateFunctionSetBuilder::new("sequence_match_any").returns(TypeId::Integer);
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder
+                .param_logical(LogicalType::list(TypeId::Varchar))
+                .param(TypeId::Timestamp);
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, SequenceMatchAnyState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (LIST(VARCHAR),
+// TIMESTAMP, BOOLEAN or INTEGER...) as registered. `states` points to
+// `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+        let patterns_reader = VarcharListReader::new(input, 0);
+        let mut check = PatternCheck::new("sequence_match_any", col_count - 2);
+        // The patterns are usually a constant list: states with the same list
+        // as the last one share its compiled patterns.
+        let mut last: Option<(Vec<Option<Arc<str>>>, Patterns)> = None;
+
+        // Patterns are read once per state, from the first row with a
+        // non-NULL list, and each is checked against the condition columns.
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SequenceMatchAnyState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+            if state.patterns.is_some() || !patterns_reader.is_valid(i) {
+                continue;
+            }
+
+            let strings = patterns_reader.read(i);
+            if let Some((last_strings, patterns)) = &last {
+                if *last_strings == strings {
+                    state.patterns = Some(Arc::clone(patterns));
+                    continue;
+                }
+            }
+
+            let mut patterns = Vec::with_capacity(strings.len());
+            for pattern in &strings {
+                let Some(pattern) = pattern else {
+                    patterns.push(None);
+                    continue;
+                };
+                if !check.check(info, pattern) {
+                    return;
+                }
+                // Checked above, so this is a cache hit.
+                let Ok(compiled) = cache::compile(pattern) else {
+                    return;
+                };
+                patterns.push(Some(compiled));
+            }
+            let patterns: Patterns = patterns.into();
+            state.patterns = Some(Arc::clone(&patterns));
+            last = Some((strings, patterns));
+        }
+
+        update_columns::<SequenceMatchAnyState>(
+            info,
+            input,
+            states,
+            "sequence_match_any",
+            None,
+            1,
+            false,
+        );
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SequenceMatchAnyState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SequenceMatchAnyState>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB INTEGER vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceMatchAnyState>::with_state_mut(*source.add(i))
+            else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            match state.finalize() {
+                Some(index) => writer.write_i32(idx, index as i32 + 1),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<SequenceMatchAnyState>::new();
+        source.update(|s| {
+            s.patterns = Some(Arc::from(vec![
+                Some(cache::compile("(?2)").unwrap()),
+                Some(cache::compile("(?1)").unwrap()),
+            ]));
+            s.sequence.update(Event::new(100, 0b01));
+        });
+        let mut target = AggregateTestHarness::<SequenceMatchAnyState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert_eq!(state.finalize(), Some(1));
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sequence_match_debug` aggregate function.
+//!
+//! Registered like [`window_funnel_debug`](super::window_funnel_debug), with
+//! the `sequence_match` arguments and a `STRUCT` of finalize statistics
+//! written through [`quack_rs::vector::complex::StructVector`] field writers.
+//! Events reach the sequence through the shared
+//! [`update_columns`](super::sequence::update_columns).
+
+use crate::ffi::conditions::{chunk_events, CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{pattern_message, report};
+use crate::ffi::sequence::update_columns;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sequence_match_debug::SequenceMatchDebugState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::StructVector;
+use quack_rs::vector::VectorWriter;
+
+impl quack_rs::aggregate::AggregateState for SequenceMatchDebugState {}
+
+/// Registers the `sequence_match_debug` function with `DuckDB`.
+///
+/// Signature: `sequence_match_debug(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...])`
+/// returning `STRUCT(matched BOOLEAN, events_collected BIGINT,
+/// events_stored BIGINT, execution_path VARCHAR, states_expanded BIGINT,
+/// sort_us BIGINT, match_us BIGINT)`: the `sequence_match` result with the
+/// statistics of [`SequenceMatchDebugState::finalize`].
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_match_debug(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_match_debug").returns_logical(
+        LogicalType::struct_type(&[
+            ("matched", TypeId::Boolean),
+            ("events_collected", TypeId::BigInt),
+            ("events_stored", TypeId::BigInt),
+            ("execution_path", TypeId::Varchar),
+            ("states_expanded", TypeId::BigInt),
+            ("sort_us", TypeId::BigInt),
+            ("match_us", TypeId::BigInt),
+        ]),
+    );
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, SequenceMatchDebugState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (VARCHAR,
+// TIMESTAMP, COND×N) as registered. `states` points to `row_count` aggregate
+// state pointers.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        // Count every event before the sequence drops those with no
+        // condition set.
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+        let events = chunk_events(input, 1, 2, col_count);
+        for i in 0..row_count {
+            if events.get(i).is_none() {
+                continue;
+            }
+            if let Some(state) = FfiState::<SequenceMatchDebugState>::with_state_mut(*states.add(i))
+            {
+                state.events_collected += 1;
+            }
+        }
+
+        update_columns::<SequenceMatchDebugState>(
+            info,
+            input,
+            states,
+            "sequence_match_debug",
+            Some(0),
+            1,
+            false,
+        );
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SequenceMatchDebugState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SequenceMatchDebugState>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB STRUCT vector with the seven fields registered and room for
+// `offset + count` elements.
+unsafe extern "C" fn state_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+        let mut matched_writer = StructVector::field_writer(result, 0);
+        let mut collected_writer = StructVector::field_writer(result, 1);
+        let mut stored_writer = StructVector::field_writer(result, 2);
+        let mut path_writer = StructVector::field_writer(result, 3);
+        let mut expanded_writer = StructVector::field_writer(result, 4);
+        let mut sort_writer = StructVector::field_writer(result, 5);
+        let mut match_writer = StructVector::field_writer(result, 6);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceMatchDebugState>::with_state_mut(*source.add(i))
+            else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            let stats = match state.finalize() {
+                Ok(stats) => stats,
+                Err(e) => {
+                    let pattern = state.sequence.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_match_debug", pattern, &e));
+                    return;
+                }
+            };
+            matched_writer.write_bool(idx, stats.matched);
+            collected_writer.write_i64(idx, state.events_collected as i64);
+            stored_writer.write_i64(idx, stats.events_stored as i64);
+            path_writer.write_varchar(idx, stats.path.as_str());
+            expanded_writer.write_i64(idx, stats.states_expanded as i64);
+            sort_writer.write_i64(idx, stats.sort_us as i64);
+            match_writer.write_i64(idx, stats.match_us as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<SequenceMatchDebugState>::new();
+        source.update(|s| {
+            s.sequence.set_pattern("(?1).*(?2)");
+            s.update(Event::new(0, 0b01));
+            s.update(Event::new(1_000_000, 0b10));
+        });
+        let mut target = AggregateTestHarness::<SequenceMatchDebugState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert!(state.finalize().unwrap().matched);
+        assert_eq!(state.events_collected, 2);
+    }
+}
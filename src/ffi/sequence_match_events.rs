@@ -7,31 +7,41 @@
 //! [`returns_logical`][quack_rs::aggregate::AggregateFunctionSetBuilder::returns_logical]
 //! for `LIST(TIMESTAMP)` return type registration.
 //! Uses [`quack_rs::aggregate::FfiState`] for safe state management,
-//! [`quack_rs::vector::VectorReader`] for input, and
+//! the shared [`sequence_state_update`] for input, and
 //! [`quack_rs::vector::complex::ListVector`] + [`quack_rs::vector::VectorWriter`]
 //! for LIST output.
-
-use crate::common::event::Event;
-use crate::sequence::SequenceState;
+//!
+//! The overloads with a `VARCHAR[]` labels argument after the pattern are a
+//! second set under the same name, returning
+//! `LIST(STRUCT(label VARCHAR, ts TIMESTAMP))` over a
+//! [`LabeledSequenceState`]. The labels list is read by the shared
+//! [`VarcharListReader`].
+
+use crate::ffi::conditions::{CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{labels_message, pattern_message, report};
+use crate::ffi::sequence::{sequence_state_update, update_columns, VarcharListReader};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::pattern::cache;
+use crate::sequence::{LabeledSequenceState, SequenceState};
 use libduckdb_sys::*;
 use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
 use quack_rs::types::{LogicalType, TypeId};
-use quack_rs::vector::complex::ListVector;
+use quack_rs::vector::complex::{ListVector, StructVector};
 use quack_rs::vector::VectorReader;
-
-/// Minimum number of boolean condition parameters for sequence functions.
-const MIN_CONDITIONS: usize = 2;
-/// Maximum number of boolean condition parameters for sequence functions.
-const MAX_CONDITIONS: usize = 32;
+use std::sync::Arc;
 
 // Note: AggregateState for SequenceState is implemented in ffi/sequence.rs.
+impl quack_rs::aggregate::AggregateState for LabeledSequenceState {}
 
 /// Registers the `sequence_match_events` function with `DuckDB`.
 ///
-/// Signature: `sequence_match_events(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> LIST(TIMESTAMP)`
+/// Signatures:
+/// - `sequence_match_events(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> LIST(TIMESTAMP)`
+/// - `sequence_match_events(VARCHAR, VARCHAR[], TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> LIST(STRUCT(label VARCHAR, ts TIMESTAMP))`
 ///
 /// Returns an array of timestamps corresponding to each matched `(?N)` step in
-/// the pattern. Empty array if no match.
+/// the pattern, each paired with its label when labels are given. Empty array
+/// if no match.
 ///
 /// # Safety
 ///
@@ -43,66 +53,60 @@ const MAX_CONDITIONS: usize = 32;
 pub unsafe fn register_sequence_match_events(
     con: &impl quack_rs::connection::Registrar,
 ) -> Result<(), quack_rs::error::ExtensionError> {
-    let builder = AggregateFunctionSetBuilder::new("sequence_match_events")
-        .returns_logical(LogicalType::list(TypeId::Timestamp))
-        .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, |n, builder| {
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_match_events")
+        .returns_logical(LogicalType::list(TypeId::Timestamp));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
             let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
             for _ in 0..n {
-                b = b.param(TypeId::Boolean);
+                b = b.param(cond_type);
             }
-            b.state_size(FfiState::<SequenceState>::size_callback)
-                .init(FfiState::<SequenceState>::init_callback)
+            with_lifecycle!(b, SequenceState)
                 .update(state_update)
                 .combine(state_combine)
                 .finalize(state_finalize)
-                .destructor(FfiState::<SequenceState>::destroy_callback)
         });
+    }
+    unsafe {
+        con.register_aggregate_set(builder)?;
+        register_labeled(con)
+    }
+}
+
+/// Registers the `sequence_match_events` set with a `VARCHAR[]` labels
+/// argument after the pattern.
+unsafe fn register_labeled(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let element =
+        LogicalType::struct_type(&[("label", TypeId::Varchar), ("ts", TypeId::Timestamp)]);
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_match_events")
+        .returns_logical(LogicalType::list_from_logical(&element));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder
+                .param(TypeId::Varchar)
+                .param_logical(LogicalType::list(TypeId::Varchar))
+                .param(TypeId::Timestamp);
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, LabeledSequenceState)
+                .update(labeled_state_update)
+                .combine(labeled_state_combine)
+                .finalize(labeled_state_finalize)
+        });
+    }
     unsafe { con.register_aggregate_set(builder) }
 }
 
-// SAFETY: `input` is a valid DuckDB data chunk with columns (VARCHAR, TIMESTAMP,
-// BOOLEAN...) as registered. `states` points to `row_count` aggregate state pointers.
+// SAFETY: see `sequence_state_update`.
 unsafe extern "C" fn state_update(
-    _info: duckdb_function_info,
+    info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
 ) {
-    unsafe {
-        let row_count = duckdb_data_chunk_get_size(input) as usize;
-        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
-
-        let pattern_reader = VectorReader::new(input, 0);
-        let ts_reader = VectorReader::new(input, 1);
-        let cond_readers: Vec<VectorReader> = (2..col_count)
-            .map(|c| VectorReader::new(input, c))
-            .collect();
-
-        for i in 0..row_count {
-            let Some(state) = FfiState::<SequenceState>::with_state_mut(*states.add(i)) else {
-                continue;
-            };
-
-            if state.pattern_str.is_none() && pattern_reader.is_valid(i) {
-                let s = pattern_reader.read_str(i);
-                state.set_pattern(s);
-            }
-
-            if !ts_reader.is_valid(i) {
-                continue;
-            }
-
-            let timestamp = ts_reader.read_i64(i);
-
-            let mut bitmask: u32 = 0;
-            for (c, reader) in cond_readers.iter().enumerate() {
-                if reader.is_valid(i) && reader.read_bool(i) {
-                    bitmask |= 1 << c;
-                }
-            }
-
-            state.update(Event::new(timestamp, bitmask));
-        }
-    }
+    unsafe { sequence_state_update(info, input, states, "sequence_match_events", false) }
 }
 
 // SAFETY: `source` and `target` point to `count` aggregate state pointers.
@@ -128,9 +132,10 @@ unsafe extern "C" fn state_combine(
 
 // SAFETY: `source` points to `count` aggregate state pointers. `result` is a
 // valid DuckDB LIST(TIMESTAMP) vector. Each list entry is populated with the
-// matched condition timestamps. Empty list on no match or pattern error.
+// matched condition timestamps. Empty list on no match; pattern errors are
+// reported as query errors.
 unsafe extern "C" fn state_finalize(
-    _info: duckdb_function_info,
+    info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
     result: duckdb_vector,
     count: idx_t,
@@ -148,7 +153,14 @@ unsafe extern "C" fn state_finalize(
                 continue;
             };
 
-            let timestamps = state.finalize_events().unwrap_or_default();
+            let timestamps = match state.finalize_events() {
+                Ok(timestamps) => timestamps,
+                Err(e) => {
+                    let pattern = state.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_match_events", pattern, &e));
+                    return;
+                }
+            };
             let ts_count = timestamps.len() as u64;
 
             // Reserve space in the list child vector
@@ -169,9 +181,139 @@ unsafe extern "C" fn state_finalize(
     }
 }
 
+// SAFETY: `input` is a valid DuckDB data chunk with columns (VARCHAR,
+// LIST(VARCHAR), TIMESTAMP, BOOLEAN or INTEGER...) as registered. `states`
+// points to `row_count` aggregate state pointers.
+unsafe extern "C" fn labeled_state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let pattern_reader = VectorReader::new(input, 0);
+        let labels_reader = VarcharListReader::new(input, 1);
+
+        // Labels are read once per state, from the first row with a non-NULL
+        // list, and checked against the pattern of that row.
+        for i in 0..row_count {
+            let Some(state) = FfiState::<LabeledSequenceState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+            if state.labels.is_some() || !labels_reader.is_valid(i) {
+                continue;
+            }
+            let labels: Arc<[Option<Arc<str>>]> = labels_reader.read(i).into();
+
+            // An invalid pattern is reported by `update_columns` below.
+            if pattern_reader.is_valid(i) {
+                let pattern = pattern_reader.read_str(i);
+                if let Ok(compiled) = cache::compile(pattern) {
+                    let steps = compiled.condition_steps();
+                    if labels.len() != steps {
+                        let message =
+                            labels_message("sequence_match_events", pattern, labels.len(), steps);
+                        report(info, &message);
+                        return;
+                    }
+                }
+            }
+            state.labels = Some(labels);
+        }
+
+        update_columns::<LabeledSequenceState>(
+            info,
+            input,
+            states,
+            "sequence_match_events",
+            Some(0),
+            2,
+            false,
+        );
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn labeled_state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<LabeledSequenceState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<LabeledSequenceState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(label VARCHAR, ts TIMESTAMP)) vector.
+unsafe extern "C" fn labeled_state_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<LabeledSequenceState>::with_state_mut(*source.add(i))
+            else {
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let steps = match state.finalize() {
+                Ok(steps) => steps,
+                Err(e) => {
+                    let pattern = state.sequence.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_match_events", pattern, &e));
+                    return;
+                }
+            };
+            let step_count = steps.len() as u64;
+
+            // Reserve before taking field writers, since reserve may
+            // reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + step_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut label_writer = StructVector::field_writer(child, 0);
+            let mut ts_writer = StructVector::field_writer(child, 1);
+            for (j, (label, ts)) in steps.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                match label {
+                    Some(label) => label_writer.write_varchar(pos, label),
+                    None => label_writer.set_null(pos),
+                }
+                ts_writer.write_i64(pos, *ts);
+            }
+
+            ListVector::set_entry(result, idx, list_offset, step_count);
+
+            list_offset += step_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::event::Event;
     use quack_rs::testing::AggregateTestHarness;
 
     #[test]
@@ -230,4 +372,25 @@ mod tests {
         let events = state.finalize_events().unwrap();
         assert_eq!(events.len(), 2);
     }
+
+    #[test]
+    fn test_labeled_events_config_propagation() {
+        let mut source = AggregateTestHarness::<LabeledSequenceState>::new();
+        source.update(|s| {
+            s.sequence.set_pattern("(?1).*(?2)");
+            s.labels = Some(Arc::from([Some(Arc::from("view")), None]));
+            s.sequence.update(Event::new(1_000_000, 0b01));
+            s.sequence.update(Event::new(2_000_000, 0b10));
+        });
+
+        let mut target = AggregateTestHarness::<LabeledSequenceState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = target.finalize();
+        let steps = state.finalize().unwrap();
+        assert_eq!(
+            steps,
+            vec![(Some(Arc::from("view")), 1_000_000), (None, 2_000_000)]
+        );
+    }
 }
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sequence_match_spans` aggregate function.
+//!
+//! Uses [`quack_rs::aggregate::AggregateFunctionSetBuilder`] with
+//! [`returns_logical`][quack_rs::aggregate::AggregateFunctionSetBuilder::returns_logical]
+//! for `LIST(STRUCT(start_ts TIMESTAMP, end_ts TIMESTAMP))` return type
+//! registration. Output is written through
+//! [`quack_rs::vector::complex::ListVector`] and
+//! [`quack_rs::vector::complex::StructVector`] field writers on the list child.
+
+use crate::ffi::conditions::{CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{pattern_message, report};
+use crate::ffi::sequence::sequence_state_update;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sequence::SequenceState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::{ListVector, StructVector};
+
+// Note: AggregateState for SequenceState is implemented in ffi/sequence.rs.
+
+/// Registers the `sequence_match_spans` function with `DuckDB`.
+///
+/// Signature: `sequence_match_spans(VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> LIST(STRUCT(start_ts TIMESTAMP, end_ts TIMESTAMP))`
+///
+/// Returns one struct per non-overlapping match — the same matches
+/// `sequence_count` counts — holding the timestamps of the first and last
+/// matched event. Empty list if no match. Intended for `UNNEST`.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_match_spans(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let element = LogicalType::struct_type(&[
+        ("start_ts", TypeId::Timestamp),
+        ("end_ts", TypeId::Timestamp),
+    ]);
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_match_spans")
+        .returns_logical(LogicalType::list_from_logical(&element));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder.param(TypeId::Varchar).param(TypeId::Timestamp);
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, SequenceState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: see `sequence_state_update`.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { sequence_state_update(info, input, states, "sequence_match_spans", false) }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SequenceState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SequenceState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(start_ts TIMESTAMP, end_ts TIMESTAMP)) vector. Empty
+// list on no match; pattern errors are reported as query errors.
+unsafe extern "C" fn state_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceState>::with_state_mut(*source.add(i)) else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let spans = match state.finalize_spans() {
+                Ok(spans) => spans,
+                Err(e) => {
+                    let pattern = state.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_match_spans", pattern, &e));
+                    return;
+                }
+            };
+            let span_count = spans.len() as u64;
+
+            // Reserve space in the list child vector before taking field writers,
+            // since reserve may reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + span_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut start_writer = StructVector::field_writer(child, 0);
+            let mut end_writer = StructVector::field_writer(child, 1);
+            for (j, &(start_ts, end_ts)) in spans.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                start_writer.write_i64(pos, start_ts);
+                end_writer.write_i64(pos, end_ts);
+            }
+
+            ListVector::set_entry(result, idx, list_offset, span_count);
+
+            list_offset += span_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_sequence_spans_combine_matches_count() {
+        let mut a = AggregateTestHarness::<SequenceState>::new();
+        a.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.update(Event::new(1_000_000, 0b01));
+            s.update(Event::new(4_000_000, 0b10));
+        });
+
+        let mut b = AggregateTestHarness::<SequenceState>::new();
+        b.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.update(Event::new(2_000_000, 0b10));
+            s.update(Event::new(3_000_000, 0b01));
+        });
+
+        b.combine(&a, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = b.finalize();
+        let spans = state.finalize_spans().unwrap();
+        assert_eq!(spans, vec![(1_000_000, 2_000_000), (3_000_000, 4_000_000)]);
+        assert_eq!(spans.len() as i64, state.finalize_count().unwrap());
+    }
+}
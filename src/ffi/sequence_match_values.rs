@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sequence_match_values` and `sequence_extract`
+//! aggregate functions.
+//!
+//! Uses [`quack_rs::aggregate::AggregateFunctionSetBuilder`] with
+//! [`returns_logical`][quack_rs::aggregate::AggregateFunctionSetBuilder::returns_logical]
+//! for `LIST(STRUCT(ts TIMESTAMP, value VARCHAR))` return type registration.
+//! Output is written through [`quack_rs::vector::complex::ListVector`] and
+//! [`quack_rs::vector::complex::StructVector`] field writers on the list child.
+//!
+//! `sequence_extract` takes the same arguments into the same
+//! [`SequenceValuesState`] and returns `LIST(STRUCT(name VARCHAR, value
+//! VARCHAR))` instead; the C API has no `MAP` return type, and
+//! `map_from_entries` turns the list into one in SQL.
+
+use crate::ffi::conditions::{chunk_events, CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::error::{pattern_message, report, PatternCheck};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sequence::SequenceValuesState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::{ListVector, StructVector};
+use quack_rs::vector::VectorReader;
+use std::sync::Arc;
+
+/// Number of fixed parameters before the variable boolean conditions.
+///
+/// Layout: VARCHAR (pattern), TIMESTAMP, VARCHAR (value), then BOOLEAN × N.
+const FIXED_PARAMS: usize = 3;
+
+impl quack_rs::aggregate::AggregateState for SequenceValuesState {}
+
+/// Registers the `sequence_match_values` function with `DuckDB`.
+///
+/// Signature: `sequence_match_values(VARCHAR, TIMESTAMP, VARCHAR, BOOLEAN, BOOLEAN [, ...]) -> LIST(STRUCT(ts TIMESTAMP, value VARCHAR))`
+///
+/// Returns one struct per matched `(?N)` step in the pattern, holding the
+/// timestamp and value column of the matched event. Empty list if no match.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_match_values(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let element =
+        LogicalType::struct_type(&[("ts", TypeId::Timestamp), ("value", TypeId::Varchar)]);
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_match_values")
+        .returns_logical(LogicalType::list_from_logical(&element));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder
+                .param(TypeId::Varchar) // pattern
+                .param(TypeId::Timestamp) // timestamp
+                .param(TypeId::Varchar); // value
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, SequenceValuesState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `sequence_extract` function with `DuckDB`.
+///
+/// Signature: `sequence_extract(VARCHAR, TIMESTAMP, VARCHAR, BOOLEAN, BOOLEAN [, ...]) -> LIST(STRUCT(name VARCHAR, value VARCHAR))`
+///
+/// Returns one struct per `(?N:name)` step of the pattern, holding the
+/// capture name and the value column of the matched event (see
+/// [`SequenceValuesState::finalize_captures`]). Empty list if no match.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_extract(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let element =
+        LogicalType::struct_type(&[("name", TypeId::Varchar), ("value", TypeId::Varchar)]);
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_extract")
+        .returns_logical(LogicalType::list_from_logical(&element));
+    for cond_type in CONDITION_TYPES {
+        builder = builder.overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+            let mut b = builder
+                .param(TypeId::Varchar) // pattern
+                .param(TypeId::Timestamp) // timestamp
+                .param(TypeId::Varchar); // value
+            for _ in 0..n {
+                b = b.param(cond_type);
+            }
+            with_lifecycle!(b, SequenceValuesState)
+                .update(extract_update)
+                .combine(state_combine)
+                .finalize(extract_finalize)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: as for `update_values`.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_values(info, input, states, "sequence_match_values") }
+}
+
+// SAFETY: as for `update_values`.
+unsafe extern "C" fn extract_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_values(info, input, states, "sequence_extract") }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (VARCHAR, TIMESTAMP,
+// VARCHAR, BOOLEAN...) as registered. `states` points to `row_count` aggregate
+// state pointers. Pattern errors name `function`.
+unsafe fn update_values(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+    function: &'static str,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+
+        let pattern_reader = VectorReader::new(input, 0);
+        let mut patterns = PatternCheck::new(function, col_count - FIXED_PARAMS);
+        let value_reader = VectorReader::new(input, 2);
+        let events = chunk_events(input, 1, FIXED_PARAMS, col_count);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SequenceValuesState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            if state.pattern_str.is_none() && pattern_reader.is_valid(i) {
+                let s = pattern_reader.read_str(i);
+                if !patterns.check(info, s) {
+                    return;
+                }
+                state.set_pattern(s);
+            }
+
+            let Some(event) = events.get(i) else {
+                continue;
+            };
+            if !event.has_any_condition() {
+                // Skip the value read for rows that cannot match any step
+                continue;
+            }
+
+            let value: Option<Arc<str>> = if value_reader.is_valid(i) {
+                Some(Arc::from(value_reader.read_str(i)))
+            } else {
+                None
+            };
+
+            state.update(event, value);
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SequenceValuesState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SequenceValuesState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(ts TIMESTAMP, value VARCHAR)) vector. Each list
+// entry is populated with the matched (timestamp, value) pairs. Empty list on
+// no match; pattern errors are reported as query errors.
+unsafe extern "C" fn state_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceValuesState>::with_state_mut(*source.add(i))
+            else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let matches = match state.finalize_values() {
+                Ok(matches) => matches,
+                Err(e) => {
+                    let pattern = state.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_match_values", pattern, &e));
+                    return;
+                }
+            };
+            let match_count = matches.len() as u64;
+
+            // Reserve space in the list child vector before taking field writers,
+            // since reserve may reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + match_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut ts_writer = StructVector::field_writer(child, 0);
+            let mut value_writer = StructVector::field_writer(child, 1);
+            for (j, (ts, value)) in matches.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                ts_writer.write_i64(pos, *ts);
+                match value {
+                    Some(v) => value_writer.write_varchar(pos, v),
+                    None => value_writer.set_null(pos),
+                }
+            }
+
+            ListVector::set_entry(result, idx, list_offset, match_count);
+
+            list_offset += match_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(name VARCHAR, value VARCHAR)) vector. Empty list on
+// no match; pattern errors are reported as query errors.
+unsafe extern "C" fn extract_finalize(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceValuesState>::with_state_mut(*source.add(i))
+            else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let captures = match state.finalize_captures() {
+                Ok(captures) => captures,
+                Err(e) => {
+                    let pattern = state.pattern_str.as_deref().unwrap_or("");
+                    report(info, &pattern_message("sequence_extract", pattern, &e));
+                    return;
+                }
+            };
+            let capture_count = captures.len() as u64;
+
+            // Reserve space in the list child vector before taking field writers,
+            // since reserve may reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + capture_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut name_writer = StructVector::field_writer(child, 0);
+            let mut value_writer = StructVector::field_writer(child, 1);
+            for (j, (name, value)) in captures.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                name_writer.write_varchar(pos, name);
+                match value {
+                    Some(v) => value_writer.write_varchar(pos, v),
+                    None => value_writer.set_null(pos),
+                }
+            }
+
+            ListVector::set_entry(result, idx, list_offset, capture_count);
+
+            list_offset += capture_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_sequence_values_combine_value_union() {
+        let mut a = AggregateTestHarness::<SequenceValuesState>::new();
+        a.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.update(Event::new(1_000_000, 0b01), Some(Arc::from("landing")));
+        });
+
+        let mut b = AggregateTestHarness::<SequenceValuesState>::new();
+        b.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.update(Event::new(2_000_000, 0b10), Some(Arc::from("signup")));
+        });
+
+        b.combine(&a, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = b.finalize();
+        let values = state.finalize_values().unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0], (1_000_000, Some(Arc::from("landing"))));
+        assert_eq!(values[1], (2_000_000, Some(Arc::from("signup"))));
+    }
+
+    #[test]
+    fn test_sequence_values_config_propagation() {
+        // Zero-initialized target combine pattern (Session 10 bug).
+        let mut source = AggregateTestHarness::<SequenceValuesState>::new();
+        source.update(|s| {
+            s.set_pattern("(?1).*(?2)");
+            s.update(Event::new(1_000_000, 0b01), Some(Arc::from("a")));
+            s.update(Event::new(2_000_000, 0b10), None);
+        });
+
+        let mut target = AggregateTestHarness::<SequenceValuesState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = target.finalize();
+        assert!(state.pattern_str.is_some());
+        let values = state.finalize_values().unwrap();
+        assert_eq!(
+            values,
+            vec![(1_000_000, Some(Arc::from("a"))), (2_000_000, None)]
+        );
+    }
+}
@@ -8,11 +8,28 @@
 //! and [`quack_rs::vector::VectorReader`] for safe vector reading (including
 //! `read_str()` which replaces the hand-rolled `read_varchar()` helper that
 //! handled the undocumented `duckdb_string_t` 16-byte inline/pointer format).
-
+//!
+//! One function set is registered per value column type (`VARCHAR`, `BIGINT`,
+//! `INTEGER`, `DATE`, `UUID`), each returning its own type. `DuckDB` merges sets
+//! registered under the same name into one overloaded function. The callbacks
+//! are generic over [`NodeValue`], which reads and writes the type's physical
+//! representation.
+//!
+//! `sequence_next_node_hist` shares the update and combine callbacks. It
+//! returns `LIST(STRUCT(key T, value BIGINT))`, the physical layout of
+//! `MAP(T, BIGINT)`, written through [`quack_rs::vector::complex::ListVector`]
+//! and [`quack_rs::vector::complex::StructVector`] field writers;
+//! `map_from_entries` turns it into a `MAP`.
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::conditions::{pack_chunk, ConditionReader, CONDITION_TYPES};
+use crate::ffi::error::{base_message, choice_message, interval_message, report};
+use crate::ffi::state_lifecycle::with_lifecycle;
 use crate::sequence_next_node::{NextNodeEvent, SequenceNextNodeState};
 use libduckdb_sys::*;
 use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
-use quack_rs::types::TypeId;
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::{ListVector, StructVector};
 use quack_rs::vector::{VectorReader, VectorWriter};
 use std::sync::Arc;
 
@@ -23,21 +40,97 @@ const MAX_EVENT_CONDITIONS: usize = 32;
 
 /// Number of fixed parameters before the variable boolean event conditions.
 ///
-/// Layout: VARCHAR (direction), VARCHAR (base), TIMESTAMP, VARCHAR (`event_column`),
-/// BOOLEAN (`base_condition`), then BOOLEAN × N event conditions.
+/// Layout: VARCHAR (direction), VARCHAR (base), [INTERVAL (window),] TIMESTAMP,
+/// value (`event_column`), BOOLEAN (`base_condition`), then BOOLEAN × N event
+/// conditions. The window adds one to this count; `sequence_next_node_hist`
+/// has no base and one less.
 const FIXED_PARAMS: usize = 5;
 
-impl quack_rs::aggregate::AggregateState for SequenceNextNodeState {}
+impl<V: NodeValue> quack_rs::aggregate::AggregateState for SequenceNextNodeState<V> {}
+
+/// A value column type supported by `sequence_next_node`.
+///
+/// Implemented for the physical representation of each registered SQL type;
+/// `INTEGER` and `DATE` share `i32`.
+pub trait NodeValue: Clone + Send + 'static {
+    /// Reads the value at row `idx`.
+    ///
+    /// # Safety
+    ///
+    /// `reader` must read a column whose physical type is `Self`, and `idx`
+    /// must be a valid, non-NULL row.
+    unsafe fn read(reader: &VectorReader, idx: usize) -> Self;
+
+    /// Writes `self` to row `idx` of the result vector.
+    ///
+    /// # Safety
+    ///
+    /// `writer` must write a vector whose physical type is `Self`, and `idx`
+    /// must be within its capacity.
+    unsafe fn write(&self, writer: &mut VectorWriter, idx: usize);
+}
+
+impl NodeValue for Arc<str> {
+    unsafe fn read(reader: &VectorReader, idx: usize) -> Self {
+        // Arc<str> for O(1) clone in combine
+        Self::from(unsafe { reader.read_str(idx) })
+    }
+
+    unsafe fn write(&self, writer: &mut VectorWriter, idx: usize) {
+        // write_varchar handles both inline (≤12 bytes) and pointer storage
+        // formats via duckdb_vector_assign_string_element_len, which accepts a
+        // length parameter — no null terminator or CString conversion needed.
+        unsafe { writer.write_varchar(idx, self) };
+    }
+}
+
+impl NodeValue for i64 {
+    unsafe fn read(reader: &VectorReader, idx: usize) -> Self {
+        unsafe { reader.read_i64(idx) }
+    }
+
+    unsafe fn write(&self, writer: &mut VectorWriter, idx: usize) {
+        unsafe { writer.write_i64(idx, *self) };
+    }
+}
+
+impl NodeValue for i32 {
+    unsafe fn read(reader: &VectorReader, idx: usize) -> Self {
+        unsafe { reader.read_i32(idx) }
+    }
+
+    unsafe fn write(&self, writer: &mut VectorWriter, idx: usize) {
+        unsafe { writer.write_i32(idx, *self) };
+    }
+}
+
+impl NodeValue for i128 {
+    unsafe fn read(reader: &VectorReader, idx: usize) -> Self {
+        unsafe { reader.read_i128(idx) }
+    }
+
+    unsafe fn write(&self, writer: &mut VectorWriter, idx: usize) {
+        unsafe { writer.write_i128(idx, *self) };
+    }
+}
 
 /// Registers the `sequence_next_node` function with `DuckDB`.
 ///
-/// Signature: `sequence_next_node(VARCHAR, VARCHAR, TIMESTAMP, VARCHAR, BOOLEAN, BOOLEAN [, ...]) -> VARCHAR`
+/// Signatures, for `T` in `VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID`:
+///
+/// 1. `sequence_next_node(VARCHAR, VARCHAR, TIMESTAMP, T, BOOLEAN, BOOLEAN [, ...]) -> T`
+/// 2. With window: `sequence_next_node(VARCHAR, VARCHAR, INTERVAL, TIMESTAMP, T, BOOLEAN, BOOLEAN [, ...]) -> T`
 ///
 /// Parameters:
-/// - `direction`: `'forward'` or `'backward'`
+/// - `direction`: `'forward'` or `'backward'`, optionally followed by
+///   `', strict_increase'` (see [`SequenceNextNodeState::strict_increase`])
+///   `', session_gap(D)'` (see [`SequenceNextNodeState::session_gap_us`])
+///   and `', missing_base(M)'` (see [`SequenceNextNodeState::missing_base`])
 /// - `base`: `'head'`, `'tail'`, `'first_match'`, or `'last_match'`
+/// - `window` (optional): maximum gap between the matched chain and the
+///   adjacent event (see [`SequenceNextNodeState::window_us`])
 /// - `timestamp`: Event timestamp column
-/// - `event_column`: Value column (returned as result)
+/// - `event_column`: Value column (returned as result, same type)
 /// - `base_condition`: Boolean condition for the base/anchor event
 /// - `event1, event2, ...`: Sequential event conditions to match
 ///
@@ -51,79 +144,302 @@ impl quack_rs::aggregate::AggregateState for SequenceNextNodeState {}
 pub unsafe fn register_sequence_next_node(
     con: &impl quack_rs::connection::Registrar,
 ) -> Result<(), quack_rs::error::ExtensionError> {
-    let builder = AggregateFunctionSetBuilder::new("sequence_next_node")
-        .returns(TypeId::Varchar)
-        .overloads(MIN_EVENT_CONDITIONS..=MAX_EVENT_CONDITIONS, |n, builder| {
-            let mut b = builder
-                .param(TypeId::Varchar) // direction
-                .param(TypeId::Varchar) // base
-                .param(TypeId::Timestamp) // timestamp
-                .param(TypeId::Varchar) // event_column
-                .param(TypeId::Boolean); // base_condition
-            for _ in 0..n {
-                b = b.param(TypeId::Boolean); // event conditions
-            }
-            b.state_size(FfiState::<SequenceNextNodeState>::size_callback)
-                .init(FfiState::<SequenceNextNodeState>::init_callback)
-                .update(state_update)
-                .combine(state_combine)
-                .finalize(state_finalize)
-                .destructor(FfiState::<SequenceNextNodeState>::destroy_callback)
-        });
+    unsafe {
+        register_for::<Arc<str>>(con, TypeId::Varchar)?;
+        register_for::<i64>(con, TypeId::BigInt)?;
+        register_for::<i32>(con, TypeId::Integer)?;
+        register_for::<i32>(con, TypeId::Date)?;
+        register_for::<i128>(con, TypeId::Uuid)
+    }
+}
+
+/// Registers the `sequence_next_node` set whose value column and return type
+/// is `value_type`, stored as `V`.
+unsafe fn register_for<V: NodeValue>(
+    con: &impl quack_rs::connection::Registrar,
+    value_type: TypeId,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_next_node").returns(value_type);
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            // Group 1: WITHOUT window parameter
+            .overloads(
+                MIN_EVENT_CONDITIONS..=MAX_EVENT_CONDITIONS,
+                move |n, builder| {
+                    let mut b = builder
+                        .param(TypeId::Varchar) // direction
+                        .param(TypeId::Varchar) // base
+                        .param(TypeId::Timestamp) // timestamp
+                        .param(value_type) // event_column
+                        .param(cond_type); // base_condition
+                    for _ in 0..n {
+                        b = b.param(cond_type); // event conditions
+                    }
+                    with_lifecycle!(b, SequenceNextNodeState<V>)
+                        .update(state_update::<V>)
+                        .combine(state_combine::<V>)
+                        .finalize(state_finalize::<V>)
+                },
+            )
+            // Group 2: WITH window parameter after base
+            .overloads(
+                MIN_EVENT_CONDITIONS..=MAX_EVENT_CONDITIONS,
+                move |n, builder| {
+                    let mut b = builder
+                        .param(TypeId::Varchar) // direction
+                        .param(TypeId::Varchar) // base
+                        .param(TypeId::Interval) // window
+                        .param(TypeId::Timestamp) // timestamp
+                        .param(value_type) // event_column
+                        .param(cond_type); // base_condition
+                    for _ in 0..n {
+                        b = b.param(cond_type); // event conditions
+                    }
+                    with_lifecycle!(b, SequenceNextNodeState<V>)
+                        .update(state_update_with_window::<V>)
+                        .combine(state_combine::<V>)
+                        .finalize(state_finalize::<V>)
+                },
+            );
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `sequence_next_node_hist` function with `DuckDB`.
+///
+/// Signatures, for `T` in `VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID`:
+///
+/// 1. `sequence_next_node_hist(VARCHAR, TIMESTAMP, T, BOOLEAN, BOOLEAN [, ...]) -> LIST(STRUCT(key T, value BIGINT))`
+/// 2. With window: `sequence_next_node_hist(VARCHAR, INTERVAL, TIMESTAMP, T, BOOLEAN, BOOLEAN [, ...]) -> LIST(STRUCT(key T, value BIGINT))`
+///
+/// Parameters match `sequence_next_node` without `base`: every event
+/// satisfying `base_condition` starts a chain, and the result counts the
+/// adjacent values of all complete chains (see
+/// [`SequenceNextNodeState::finalize_histogram`]).
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sequence_next_node_hist(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    unsafe {
+        register_hist_for::<Arc<str>>(con, TypeId::Varchar)?;
+        register_hist_for::<i64>(con, TypeId::BigInt)?;
+        register_hist_for::<i32>(con, TypeId::Integer)?;
+        register_hist_for::<i32>(con, TypeId::Date)?;
+        register_hist_for::<i128>(con, TypeId::Uuid)
+    }
+}
+
+/// Registers the `sequence_next_node_hist` set whose value column and key
+/// type is `value_type`, stored as `V`.
+unsafe fn register_hist_for<V: NodeValue + Ord>(
+    con: &impl quack_rs::connection::Registrar,
+    value_type: TypeId,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let entry = LogicalType::struct_type(&[("key", value_type), ("value", TypeId::BigInt)]);
+    let mut builder = AggregateFunctionSetBuilder::new("sequence_next_node_hist")
+        .returns_logical(LogicalType::list_from_logical(&entry));
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            // Group 1: WITHOUT window parameter
+            .overloads(
+                MIN_EVENT_CONDITIONS..=MAX_EVENT_CONDITIONS,
+                move |n, builder| {
+                    let mut b = builder
+                        .param(TypeId::Varchar) // direction
+                        .param(TypeId::Timestamp) // timestamp
+                        .param(value_type) // event_column
+                        .param(cond_type); // base_condition
+                    for _ in 0..n {
+                        b = b.param(cond_type); // event conditions
+                    }
+                    with_lifecycle!(b, SequenceNextNodeState<V>)
+                        .update(hist_update::<V>)
+                        .combine(state_combine::<V>)
+                        .finalize(hist_finalize::<V>)
+                },
+            )
+            // Group 2: WITH window parameter after direction
+            .overloads(
+                MIN_EVENT_CONDITIONS..=MAX_EVENT_CONDITIONS,
+                move |n, builder| {
+                    let mut b = builder
+                        .param(TypeId::Varchar) // direction
+                        .param(TypeId::Interval) // window
+                        .param(TypeId::Timestamp) // timestamp
+                        .param(value_type) // event_column
+                        .param(cond_type); // base_condition
+                    for _ in 0..n {
+                        b = b.param(cond_type); // event conditions
+                    }
+                    with_lifecycle!(b, SequenceNextNodeState<V>)
+                        .update(hist_update_with_window::<V>)
+                        .combine(state_combine::<V>)
+                        .finalize(hist_finalize::<V>)
+                },
+            );
+    }
     unsafe { con.register_aggregate_set(builder) }
 }
 
 // SAFETY: `input` is a valid DuckDB data chunk with columns
-// (VARCHAR, VARCHAR, TIMESTAMP, VARCHAR, BOOLEAN, BOOLEAN...) as registered.
+// (VARCHAR, VARCHAR, TIMESTAMP, V, BOOLEAN, BOOLEAN...) as registered for `V`.
 // `states` points to `row_count` aggregate state pointers.
-unsafe extern "C" fn state_update(
-    _info: duckdb_function_info,
+unsafe extern "C" fn state_update<V: NodeValue>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        update_impl::<V>(info, input, states, "sequence_next_node", true, false);
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns
+// (VARCHAR, VARCHAR, INTERVAL, TIMESTAMP, V, BOOLEAN, BOOLEAN...) as registered
+// for `V`. `states` points to `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update_with_window<V: NodeValue>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        update_impl::<V>(info, input, states, "sequence_next_node", true, true);
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns
+// (VARCHAR, TIMESTAMP, V, BOOLEAN, BOOLEAN...) as registered for `V`.
+// `states` points to `row_count` aggregate state pointers.
+unsafe extern "C" fn hist_update<V: NodeValue>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        update_impl::<V>(info, input, states, "sequence_next_node_hist", false, false);
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns
+// (VARCHAR, INTERVAL, TIMESTAMP, V, BOOLEAN, BOOLEAN...) as registered for `V`.
+// `states` points to `row_count` aggregate state pointers.
+unsafe extern "C" fn hist_update_with_window<V: NodeValue>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        update_impl::<V>(info, input, states, "sequence_next_node_hist", false, true);
+    }
+}
+
+/// Shared update logic for all overload groups of `sequence_next_node` and
+/// `sequence_next_node_hist`.
+///
+/// An unrecognized direction, direction option or base string, or a
+/// month-based window, is reported as a query error through
+/// [`crate::ffi::error`] under `name`. A `NULL` window leaves the search
+/// unlimited.
+///
+/// # Safety
+///
+/// Requires valid `info`, `input` data chunk and `states` aggregate state pointers.
+unsafe fn update_impl<V: NodeValue>(
+    info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
+    name: &str,
+    has_base: bool,
+    has_window: bool,
 ) {
     unsafe {
         let row_count = duckdb_data_chunk_get_size(input) as usize;
         let col_count = duckdb_data_chunk_get_column_count(input) as usize;
-        let num_event_conditions = col_count.saturating_sub(FIXED_PARAMS);
+        let fixed_params = FIXED_PARAMS + usize::from(has_window) - usize::from(!has_base);
+        let num_event_conditions = col_count.saturating_sub(fixed_params);
 
         // Column 0: VARCHAR (direction)
         let direction_reader = VectorReader::new(input, 0);
-        // Column 1: VARCHAR (base)
-        let base_reader = VectorReader::new(input, 1);
-        // Column 2: TIMESTAMP
-        let ts_reader = VectorReader::new(input, 2);
-        // Column 3: VARCHAR (event_column / value)
-        let value_reader = VectorReader::new(input, 3);
-        // Column 4: BOOLEAN (base_condition)
-        let base_cond_reader = VectorReader::new(input, 4);
-
-        // Columns 5..N: BOOLEAN event conditions
-        let event_cond_readers: Vec<VectorReader> = (FIXED_PARAMS..col_count)
-            .map(|c| VectorReader::new(input, c))
-            .collect();
+        // Column 1: VARCHAR (base, only if has_base)
+        let base_reader = if has_base {
+            Some(VectorReader::new(input, 1))
+        } else {
+            None
+        };
+        // Next column: INTERVAL (window, only if has_window)
+        let window_col = 1 + usize::from(has_base);
+        let window_reader = if has_window {
+            Some(VectorReader::new(input, window_col))
+        } else {
+            None
+        };
+        // Then TIMESTAMP, event_column / value (physical type V) and
+        // BOOLEAN or INTEGER base_condition
+        let ts_col = window_col + usize::from(has_window);
+        let ts_reader = VectorReader::new(input, ts_col);
+        let value_reader = VectorReader::new(input, ts_col + 1);
+        let base_cond_reader = ConditionReader::new(input, ts_col + 2);
+
+        // Remaining columns: BOOLEAN or INTEGER event conditions, packed column-at-a-time
+        let event_masks = pack_chunk(input, fixed_params, col_count);
 
         for i in 0..row_count {
-            let Some(state) = FfiState::<SequenceNextNodeState>::with_state_mut(*states.add(i))
+            let Some(state) = FfiState::<SequenceNextNodeState<V>>::with_state_mut(*states.add(i))
             else {
                 continue;
             };
 
-            // Parse direction (once per state)
+            // Parse direction and its options (once per state)
             if state.direction.is_none() && direction_reader.is_valid(i) {
                 let dir_str = direction_reader.read_str(i);
-                if let Some(dir) = SequenceNextNodeState::parse_direction(dir_str) {
-                    state.set_direction(dir);
-                }
+                let options = match SequenceNextNodeState::parse_direction_options(dir_str) {
+                    Ok(parsed) => parsed,
+                    Err((what, token)) => {
+                        let expected: &[&str] = if what == "direction" {
+                            &["forward", "backward"]
+                        } else {
+                            &SequenceNextNodeState::DIRECTION_OPTIONS
+                        };
+                        report(info, &choice_message(name, what, token, expected));
+                        return;
+                    }
+                };
+                state.set_direction_options(options);
             }
 
             // Parse base (once per state)
-            if state.base.is_none() && base_reader.is_valid(i) {
-                let base_str = base_reader.read_str(i);
-                if let Some(base) = SequenceNextNodeState::parse_base(base_str) {
+            if let Some(ref base_reader) = base_reader {
+                if state.base.is_none() && base_reader.is_valid(i) {
+                    let base_str = base_reader.read_str(i);
+                    let Some(base) = SequenceNextNodeState::parse_base(base_str) else {
+                        let expected = ["head", "tail", "first_match", "last_match"];
+                        report(info, &choice_message(name, "base", base_str, &expected));
+                        return;
+                    };
                     state.set_base(base);
                 }
             }
 
+            // Parse window (once per state)
+            if let Some(ref window_reader) = window_reader {
+                if state.window_us.is_none() && window_reader.is_valid(i) {
+                    let iv = window_reader.read_interval(i);
+                    let Some(window_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                        let message = interval_message(name, iv.months, iv.days, iv.micros);
+                        report(info, &message);
+                        return;
+                    };
+                    state.set_window(window_us);
+                }
+            }
+
             // Set num_steps (once per state)
             if state.num_steps == 0 {
                 state.num_steps = num_event_conditions;
@@ -136,23 +452,18 @@ unsafe extern "C" fn state_update(
 
             let timestamp = ts_reader.read_i64(i);
 
-            // Read event_column value (nullable), convert to Arc<str> for O(1) clone
-            let value: Option<Arc<str>> = if value_reader.is_valid(i) {
-                Some(Arc::from(value_reader.read_str(i)))
+            // Read event_column value (nullable)
+            let value = if value_reader.is_valid(i) {
+                Some(V::read(&value_reader, i))
             } else {
                 None
             };
 
             // Read base_condition
-            let base_condition = base_cond_reader.is_valid(i) && base_cond_reader.read_bool(i);
+            let base_condition = base_cond_reader.read(i);
 
-            // Pack event conditions into u32 bitmask
-            let mut bitmask: u32 = 0;
-            for (c, reader) in event_cond_readers.iter().enumerate() {
-                if reader.is_valid(i) && reader.read_bool(i) {
-                    bitmask |= 1 << c;
-                }
-            }
+            // Pack event conditions into u32 bitmask (max 32 event conditions)
+            let bitmask = event_masks[i] as u32;
 
             state.update(NextNodeEvent {
                 timestamp_us: timestamp,
@@ -165,7 +476,7 @@ unsafe extern "C" fn state_update(
 }
 
 // SAFETY: `source` and `target` point to `count` aggregate state pointers.
-unsafe extern "C" fn state_combine(
+unsafe extern "C" fn state_combine<V: NodeValue>(
     _info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
     target: *mut duckdb_aggregate_state,
@@ -173,10 +484,10 @@ unsafe extern "C" fn state_combine(
 ) {
     unsafe {
         for i in 0..count as usize {
-            let Some(src) = FfiState::<SequenceNextNodeState>::with_state(*source.add(i)) else {
+            let Some(src) = FfiState::<SequenceNextNodeState<V>>::with_state(*source.add(i)) else {
                 continue;
             };
-            let Some(tgt) = FfiState::<SequenceNextNodeState>::with_state_mut(*target.add(i))
+            let Some(tgt) = FfiState::<SequenceNextNodeState<V>>::with_state_mut(*target.add(i))
             else {
                 continue;
             };
@@ -187,9 +498,11 @@ unsafe extern "C" fn state_combine(
 }
 
 // SAFETY: `source` points to `count` aggregate state pointers. `result` is a
-// valid DuckDB VARCHAR vector. NULL is set via validity bitmap when no match found.
-unsafe extern "C" fn state_finalize(
-    _info: duckdb_function_info,
+// valid DuckDB vector of the type registered for `V`. NULL is set via validity
+// bitmap when no match found. A group without a base event under
+// `missing_base(error)` is reported as a query error.
+unsafe extern "C" fn state_finalize<V: NodeValue>(
+    info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
     result: duckdb_vector,
     count: idx_t,
@@ -201,28 +514,76 @@ unsafe extern "C" fn state_finalize(
         for i in 0..count as usize {
             let idx = offset as usize + i;
 
-            let Some(state) = FfiState::<SequenceNextNodeState>::with_state_mut(*source.add(i))
+            let Some(state) = FfiState::<SequenceNextNodeState<V>>::with_state_mut(*source.add(i))
             else {
                 writer.set_null(idx);
                 continue;
             };
 
-            match state.finalize() {
-                Some(value) => {
-                    // write_varchar handles both inline (≤12 bytes) and pointer
-                    // storage formats via duckdb_vector_assign_string_element_len,
-                    // which accepts a length parameter — no null terminator or
-                    // CString conversion needed.
-                    writer.write_varchar(idx, &value);
-                }
-                None => {
-                    writer.set_null(idx);
-                }
+            if let Err(e) = state.check_base() {
+                report(info, &base_message("sequence_next_node", &e));
+                return;
+            }
+            match state.finalize_value() {
+                Some(value) => value.write(&mut writer, idx),
+                None => writer.set_null(idx),
             }
         }
     }
 }
 
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(key V, value BIGINT)) vector. Empty list when no
+// chain has a next node. A group without a base event under
+// `missing_base(error)` is reported as a query error.
+unsafe extern "C" fn hist_finalize<V: NodeValue + Ord>(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<SequenceNextNodeState<V>>::with_state_mut(*source.add(i))
+            else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            if let Err(e) = state.check_base() {
+                report(info, &base_message("sequence_next_node_hist", &e));
+                return;
+            }
+            let histogram = state.finalize_histogram();
+            let entry_count = histogram.len() as u64;
+
+            // Reserve space in the list child vector before taking field writers,
+            // since reserve may reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + entry_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut key_writer = StructVector::field_writer(child, 0);
+            let mut count_writer = StructVector::field_writer(child, 1);
+            for (j, (value, n)) in histogram.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                value.write(&mut key_writer, pos);
+                count_writer.write_i64(pos, *n);
+            }
+
+            ListVector::set_entry(result, idx, list_offset, entry_count);
+
+            list_offset += entry_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +614,31 @@ mod tests {
         assert_eq!(state.base, Some(Base::Head));
     }
 
+    #[test]
+    fn test_next_node_combine_window_propagation() {
+        let mut source = AggregateTestHarness::<SequenceNextNodeState>::new();
+        source.update(|s| {
+            s.set_direction(Direction::Forward);
+            s.set_base(Base::Head);
+            s.set_window(1_000_000);
+            s.num_steps = 1;
+            s.update(NextNodeEvent::new(0, Some(Arc::from("A")), true, 0b1));
+            s.update(NextNodeEvent::new(
+                5_000_000,
+                Some(Arc::from("B")),
+                false,
+                0,
+            ));
+        });
+
+        let mut target = AggregateTestHarness::<SequenceNextNodeState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = target.finalize();
+        assert_eq!(state.window_us, Some(1_000_000));
+        assert_eq!(state.finalize(), None);
+    }
+
     #[test]
     fn test_next_node_combine_event_union() {
         let mut a = AggregateTestHarness::<SequenceNextNodeState>::new();
@@ -321,4 +707,47 @@ mod tests {
         let value_with_null = "hello\0world";
         assert_eq!(value_with_null.len(), 11);
     }
+
+    #[test]
+    fn test_next_node_hist_combine() {
+        let mut a = AggregateTestHarness::<SequenceNextNodeState<i64>>::new();
+        a.update(|s| {
+            s.set_direction(Direction::Forward);
+            s.num_steps = 1;
+            s.update(NextNodeEvent::new(1, Some(10), true, 0b1));
+            s.update(NextNodeEvent::new(2, Some(20), false, 0));
+        });
+
+        let mut b = AggregateTestHarness::<SequenceNextNodeState<i64>>::new();
+        b.update(|s| {
+            s.update(NextNodeEvent::new(3, Some(10), true, 0b1));
+            s.update(NextNodeEvent::new(4, Some(20), false, 0));
+            s.update(NextNodeEvent::new(5, Some(10), true, 0b1));
+            s.update(NextNodeEvent::new(6, Some(30), false, 0));
+        });
+
+        b.combine(&a, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = b.finalize();
+        assert_eq!(state.finalize_histogram(), vec![(20, 2), (30, 1)]);
+    }
+
+    #[test]
+    fn test_next_node_typed_combine() {
+        let mut a = AggregateTestHarness::<SequenceNextNodeState<i32>>::new();
+        a.update(|s| {
+            s.set_direction(Direction::Forward);
+            s.set_base(Base::Head);
+            s.num_steps = 1;
+            s.update(NextNodeEvent::new(1_000_000, Some(19_723), true, 0b1));
+        });
+
+        let mut b = AggregateTestHarness::<SequenceNextNodeState<i32>>::new();
+        b.update(|s| s.update(NextNodeEvent::new(2_000_000, Some(19_724), false, 0)));
+
+        b.combine(&a, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = b.finalize();
+        assert_eq!(state.finalize_value(), Some(19_724));
+    }
 }
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sequence_pattern_check` and
+//! `sequence_pattern_normalize` scalar functions.
+//!
+//! Registration uses the raw `libduckdb-sys` scalar function API and, for
+//! `sequence_pattern_check`, a `STRUCT` return type, like
+//! [`selfcheck`](super::selfcheck). Pattern strings are read with
+//! [`quack_rs::vector::VectorReader`] and the check result is written through
+//! [`quack_rs::vector::complex::StructVector`] field writers.
+
+use crate::ffi::conditions::MAX_CONDITIONS;
+use crate::ffi::error::{pattern_message, report_scalar};
+use crate::pattern::parser::{parse_pattern, CompiledPattern, PatternError};
+use libduckdb_sys::*;
+use quack_rs::vector::complex::StructVector;
+use quack_rs::vector::{VectorReader, VectorWriter};
+use std::ffi::CStr;
+
+/// `STRUCT` fields of the result, in order.
+const FIELDS: [(&CStr, DUCKDB_TYPE); 4] = [
+    (c"valid", DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+    (c"error", DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR),
+    (c"num_conditions", DUCKDB_TYPE_DUCKDB_TYPE_INTEGER),
+    (c"has_time_constraints", DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+];
+
+/// Registers the `sequence_pattern_check` function with `DuckDB`.
+///
+/// Signature: `sequence_pattern_check(VARCHAR) -> STRUCT(valid BOOLEAN,
+/// error VARCHAR, num_conditions INTEGER, has_time_constraints BOOLEAN)`
+///
+/// A valid pattern has a `NULL` error; an invalid one has `NULL`
+/// `num_conditions` and `has_time_constraints`. `NULL` input produces `NULL`.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sequence_pattern_check(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, c"sequence_pattern_check".as_ptr());
+
+        let mut varchar = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR);
+        duckdb_scalar_function_add_parameter(func, varchar);
+        duckdb_destroy_logical_type(&mut varchar);
+
+        let mut types: Vec<duckdb_logical_type> = FIELDS
+            .iter()
+            .map(|&(_, type_id)| duckdb_create_logical_type(type_id))
+            .collect();
+        let mut names: Vec<*const std::ffi::c_char> =
+            FIELDS.iter().map(|(name, _)| name.as_ptr()).collect();
+        let ret_type = duckdb_create_struct_type(
+            types.as_mut_ptr(),
+            names.as_mut_ptr(),
+            FIELDS.len() as idx_t,
+        );
+        duckdb_scalar_function_set_return_type(func, ret_type);
+        duckdb_destroy_logical_type(&mut { ret_type });
+        for mut member in types {
+            duckdb_destroy_logical_type(&mut member);
+        }
+
+        duckdb_scalar_function_set_function(func, Some(sequence_pattern_check_function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register sequence_pattern_check function");
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+/// Registers the `sequence_pattern_normalize` function with `DuckDB`.
+///
+/// Signature: `sequence_pattern_normalize(VARCHAR) -> VARCHAR`
+///
+/// Returns the canonical text of the pattern (see the
+/// [`Display`](std::fmt::Display) implementation of [`CompiledPattern`]).
+/// `NULL` input produces `NULL`; a malformed pattern fails the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sequence_pattern_normalize(con: duckdb_connection) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, c"sequence_pattern_normalize".as_ptr());
+
+        let mut varchar = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR);
+        duckdb_scalar_function_add_parameter(func, varchar);
+        duckdb_scalar_function_set_return_type(func, varchar);
+        duckdb_destroy_logical_type(&mut varchar);
+
+        duckdb_scalar_function_set_function(func, Some(sequence_pattern_normalize_function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register sequence_pattern_normalize function");
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+/// Parses `pattern` and checks that it references no more conditions than
+/// the aggregates accept.
+fn check(pattern: &str) -> Result<CompiledPattern, PatternError> {
+    let compiled = parse_pattern(pattern)?;
+    compiled.check_conditions(MAX_CONDITIONS)?;
+    Ok(compiled)
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one VARCHAR column as
+// registered. `output` is a valid STRUCT vector with the four fields of
+// `FIELDS`, sized for the chunk.
+unsafe extern "C" fn sequence_pattern_check_function(
+    _info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let reader = VectorReader::new(input, 0);
+        let mut writer = VectorWriter::new(output);
+        let mut valid_writer = StructVector::field_writer(output, 0);
+        let mut error_writer = StructVector::field_writer(output, 1);
+        let mut conditions_writer = StructVector::field_writer(output, 2);
+        let mut time_writer = StructVector::field_writer(output, 3);
+
+        for row in 0..row_count {
+            if !reader.is_valid(row) {
+                writer.set_null(row);
+                continue;
+            }
+            match check(reader.read_str(row)) {
+                Ok(pattern) => {
+                    valid_writer.write_bool(row, true);
+                    error_writer.set_null(row);
+                    conditions_writer.write_i32(row, pattern.max_condition() as i32);
+                    time_writer.write_bool(row, pattern.has_time_constraints());
+                }
+                Err(error) => {
+                    valid_writer.write_bool(row, false);
+                    error_writer.write_varchar(row, &error.to_string());
+                    conditions_writer.set_null(row);
+                    time_writer.set_null(row);
+                }
+            }
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one VARCHAR column as
+// registered. `output` is a valid VARCHAR vector sized for the chunk.
+unsafe extern "C" fn sequence_pattern_normalize_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let reader = VectorReader::new(input, 0);
+        let mut writer = VectorWriter::new(output);
+
+        for row in 0..row_count {
+            if !reader.is_valid(row) {
+                writer.set_null(row);
+                continue;
+            }
+            let pattern = reader.read_str(row);
+            match parse_pattern(pattern) {
+                Ok(compiled) => writer.write_varchar(row, &compiled.to_string()),
+                Err(error) => {
+                    let message = pattern_message("sequence_pattern_normalize", pattern, &error);
+                    report_scalar(info, &message);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_rejects_conditions_past_the_maximum() {
+        assert_eq!(check("(?1).*(?64)").unwrap().max_condition(), 64);
+        let err = check("(?1).*(?65)").unwrap_err();
+        assert_eq!(err.position, 6);
+        assert!(err.to_string().contains("(?65)"), "{err}");
+        assert!(check("(?1)(?x)").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for `first_event_after_session_start` and
+//! `last_event_before_session_end`.
+//!
+//! Both functions share [`SessionEdgeState`] and the update and combine logic;
+//! only the finalize callback differs. They are registered with
+//! [`quack_rs::aggregate::AggregateFunctionBuilder`] like
+//! [`sessionize_approx`](super::sessionize_approx) and evaluated as window
+//! functions through `DuckDB`'s segment tree, which relies on the O(1)
+//! ordered [`SessionEdgeState::combine`].
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::error::{interval_message, report};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::session_edge::SessionEdgeState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::{VectorReader, VectorWriter};
+use std::sync::Arc;
+
+impl quack_rs::aggregate::AggregateState for SessionEdgeState {}
+
+/// Registers `first_event_after_session_start` and
+/// `last_event_before_session_end` with `DuckDB`.
+///
+/// Signature: `(TIMESTAMP, VARCHAR, INTERVAL) -> VARCHAR`
+///
+/// Parameters:
+/// - `timestamp`: Event timestamp
+/// - `value`: Value to return for the session's first or last event
+/// - `gap`: Inactivity gap that starts a new session
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_session_edges(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    unsafe {
+        con.register_aggregate(builder(
+            "first_event_after_session_start",
+            update_session_start,
+            finalize_session_start,
+        ))?;
+        con.register_aggregate(builder(
+            "last_event_before_session_end",
+            update_session_end,
+            finalize_session_end,
+        ))
+    }
+}
+
+type UpdateFn =
+    unsafe extern "C" fn(duckdb_function_info, duckdb_data_chunk, *mut duckdb_aggregate_state);
+
+type FinalizeFn = unsafe extern "C" fn(
+    duckdb_function_info,
+    *mut duckdb_aggregate_state,
+    duckdb_vector,
+    idx_t,
+    idx_t,
+);
+
+fn builder(name: &str, update: UpdateFn, finalize: FinalizeFn) -> AggregateFunctionBuilder {
+    let b = AggregateFunctionBuilder::new(name)
+        .param(TypeId::Timestamp)
+        .param(TypeId::Varchar)
+        .param(TypeId::Interval)
+        .returns(TypeId::Varchar);
+    with_lifecycle!(b, SessionEdgeState)
+        .update(update)
+        .combine(state_combine)
+        .finalize(finalize)
+}
+
+// SAFETY: Same contract as `update_impl`.
+unsafe extern "C" fn update_session_start(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_impl(info, input, states, "first_event_after_session_start") }
+}
+
+// SAFETY: Same contract as `update_impl`.
+unsafe extern "C" fn update_session_end(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_impl(info, input, states, "last_event_before_session_end") }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns
+// (TIMESTAMP, VARCHAR, INTERVAL) as registered. `states` points to
+// `row_count` aggregate state pointers.
+unsafe fn update_impl(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+    name: &str,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+
+        let ts_reader = VectorReader::new(input, 0);
+        let value_reader = VectorReader::new(input, 1);
+        let gap_reader = VectorReader::new(input, 2);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SessionEdgeState>::with_state_mut(*states.add(i)) else {
+                continue;
+            };
+
+            // Set gap threshold (once per state)
+            if state.sessions.threshold_us == 0 && gap_reader.is_valid(i) {
+                let gap = gap_reader.read_interval(i);
+                let Some(gap_us) = interval_to_micros(gap.months, gap.days, gap.micros) else {
+                    let message = interval_message(name, gap.months, gap.days, gap.micros);
+                    report(info, &message);
+                    return;
+                };
+                state.set_threshold(gap_us);
+            }
+
+            if !ts_reader.is_valid(i) {
+                continue;
+            }
+
+            let value = value_reader
+                .is_valid(i)
+                .then(|| Arc::from(value_reader.read_str(i)));
+            state.update(ts_reader.read_i64(i), value);
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+// `target` holds the preceding segment of the frame.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SessionEdgeState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SessionEdgeState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+/// Writes `edge(state)` for every state; empty states and `NULL` values
+/// produce `NULL`.
+unsafe fn write_edges(
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+    edge: fn(&SessionEdgeState) -> Option<&str>,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<SessionEdgeState>::with_state(*source.add(i)).and_then(edge) {
+                Some(value) => writer.write_varchar(idx, value),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB VARCHAR vector.
+unsafe extern "C" fn finalize_session_start(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        write_edges(
+            source,
+            result,
+            count,
+            offset,
+            SessionEdgeState::session_start_value,
+        );
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB VARCHAR vector.
+unsafe extern "C" fn finalize_session_end(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        write_edges(
+            source,
+            result,
+            count,
+            offset,
+            SessionEdgeState::session_end_value,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    const MIN: i64 = 60_000_000;
+
+    #[test]
+    fn test_session_edge_config_propagation() {
+        // Zero-initialized target combine pattern (Session 10 bug).
+        let mut source = AggregateTestHarness::<SessionEdgeState>::new();
+        source.update(|s| {
+            s.set_threshold(30 * MIN);
+            s.update(0, Some(Arc::from("home")));
+            s.update(100 * MIN, Some(Arc::from("blog")));
+        });
+
+        let mut target = AggregateTestHarness::<SessionEdgeState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.sessions.threshold_us, 30 * MIN);
+        assert_eq!(state.session_start_value(), Some("blog"));
+        assert_eq!(state.session_end_value(), Some("home"));
+    }
+}
@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for `session_number_of_event` and `events_in_session`.
+//!
+//! Registered like the [`session_edge`](super::session_edge) functions: both
+//! names share [`SessionPositionState`] and the update and combine logic, and
+//! only the finalize callback differs.
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::error::{interval_message, report};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::session_position::SessionPositionState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+impl quack_rs::aggregate::AggregateState for SessionPositionState {}
+
+/// Registers `session_number_of_event` and `events_in_session` with `DuckDB`.
+///
+/// Signature: `(TIMESTAMP, INTERVAL) -> BIGINT`
+///
+/// Parameters:
+/// - `timestamp`: Event timestamp
+/// - `gap`: Inactivity gap that starts a new session
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_session_positions(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    unsafe {
+        con.register_aggregate(builder(
+            "session_number_of_event",
+            update_event_number,
+            finalize_event_number,
+        ))?;
+        con.register_aggregate(builder(
+            "events_in_session",
+            update_events_in_session,
+            finalize_events_in_session,
+        ))
+    }
+}
+
+type UpdateFn =
+    unsafe extern "C" fn(duckdb_function_info, duckdb_data_chunk, *mut duckdb_aggregate_state);
+
+type FinalizeFn = unsafe extern "C" fn(
+    duckdb_function_info,
+    *mut duckdb_aggregate_state,
+    duckdb_vector,
+    idx_t,
+    idx_t,
+);
+
+fn builder(name: &str, update: UpdateFn, finalize: FinalizeFn) -> AggregateFunctionBuilder {
+    let b = AggregateFunctionBuilder::new(name)
+        .param(TypeId::Timestamp)
+        .param(TypeId::Interval)
+        .returns(TypeId::BigInt);
+    with_lifecycle!(b, SessionPositionState)
+        .update(update)
+        .combine(state_combine)
+        .finalize(finalize)
+}
+
+// SAFETY: Same contract as `update_impl`.
+unsafe extern "C" fn update_event_number(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_impl(info, input, states, "session_number_of_event") }
+}
+
+// SAFETY: Same contract as `update_impl`.
+unsafe extern "C" fn update_events_in_session(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_impl(info, input, states, "events_in_session") }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns
+// (TIMESTAMP, INTERVAL) as registered. `states` points to `row_count`
+// aggregate state pointers.
+unsafe fn update_impl(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+    name: &str,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+
+        let ts_reader = VectorReader::new(input, 0);
+        let gap_reader = VectorReader::new(input, 1);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SessionPositionState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            // Set gap threshold (once per state)
+            if state.sessions.threshold_us == 0 && gap_reader.is_valid(i) {
+                let gap = gap_reader.read_interval(i);
+                let Some(gap_us) = interval_to_micros(gap.months, gap.days, gap.micros) else {
+                    let message = interval_message(name, gap.months, gap.days, gap.micros);
+                    report(info, &message);
+                    return;
+                };
+                state.set_threshold(gap_us);
+            }
+
+            if !ts_reader.is_valid(i) {
+                continue;
+            }
+            state.update(ts_reader.read_i64(i));
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+// `target` holds the preceding segment of the frame.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SessionPositionState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SessionPositionState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+/// Writes `position(state)` for every state; empty states produce `NULL`.
+unsafe fn write_positions(
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+    position: fn(&SessionPositionState) -> Option<i64>,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<SessionPositionState>::with_state(*source.add(i)).and_then(position) {
+                Some(value) => writer.write_i64(idx, value),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB BIGINT vector.
+unsafe extern "C" fn finalize_event_number(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        write_positions(
+            source,
+            result,
+            count,
+            offset,
+            SessionPositionState::event_number,
+        );
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB BIGINT vector.
+unsafe extern "C" fn finalize_events_in_session(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        write_positions(
+            source,
+            result,
+            count,
+            offset,
+            SessionPositionState::events_to_session_end,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    const MIN: i64 = 60_000_000;
+
+    #[test]
+    fn test_session_position_config_propagation() {
+        let mut source = AggregateTestHarness::<SessionPositionState>::new();
+        source.update(|s| {
+            s.set_threshold(30 * MIN);
+            s.update(0);
+            s.update(10 * MIN);
+            s.update(100 * MIN);
+        });
+
+        let mut target = AggregateTestHarness::<SessionPositionState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.sessions.threshold_us, 30 * MIN);
+        assert_eq!(state.event_number(), Some(1));
+        assert_eq!(state.events_to_session_end(), Some(2));
+    }
+}
@@ -1,9 +1,10 @@
 // SPDX-License-Identifier: MIT
 // Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
 
-//! FFI registration for the `sessionize` aggregate/window function.
+//! FFI registration for the `sessionize`, `sessionize_by` and
+//! `sessionize_info` aggregate/window functions.
 //!
-//! `sessionize` is registered as an aggregate function with window semantics.
+//! All three are registered as aggregate functions with window semantics.
 //! The `DuckDB` public C Extension API does not expose window function registration
 //! hooks (see `quack-rs` documentation: "Known limitations — window functions").
 //! This module intentionally uses raw `libduckdb-sys` calls and is excluded from
@@ -14,12 +15,19 @@
 //! See `LESSONS.md` for context on this decision.
 
 use crate::common::timestamp::interval_to_micros;
+use crate::ffi::error::{interval_message, negative_message, report};
+use crate::ffi::state_lifecycle::BoxedState;
 use crate::sessionize::SessionizeBoundaryState;
 use libduckdb_sys::*;
+use std::ffi::CStr;
 
 /// Registers the `sessionize` function with `DuckDB`.
 ///
-/// Signature: `sessionize(TIMESTAMP, INTERVAL) → BIGINT`
+/// Signatures:
+///
+/// 1. `sessionize(TIMESTAMP, INTERVAL) → BIGINT`
+/// 2. With a maximum session length:
+///    `sessionize(TIMESTAMP, INTERVAL, INTERVAL) → BIGINT`
 ///
 /// Used as a window function:
 /// ```sql
@@ -28,83 +36,310 @@ use libduckdb_sys::*;
 /// FROM events
 /// ```
 ///
+/// The frame must start at the partition start (the default `RANGE` frame or
+/// `ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW`). The C API does not
+/// expose the frame to aggregate callbacks, so bounded frames cannot be
+/// rejected here; see [`crate::sessionize`] for details.
+///
 /// # Safety
 ///
 /// Requires a valid `duckdb_connection` handle.
 pub unsafe fn register_sessionize(con: duckdb_connection) {
     unsafe {
-        let func = duckdb_create_aggregate_function();
-
-        let name = c"sessionize";
-        duckdb_aggregate_function_set_name(func, name.as_ptr());
-
-        // Parameter 0: TIMESTAMP (event timestamp)
-        let ts_type = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP);
-        duckdb_aggregate_function_add_parameter(func, ts_type);
-        duckdb_destroy_logical_type(&mut { ts_type });
-
-        // Parameter 1: INTERVAL (gap threshold)
-        let interval_type = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL);
-        duckdb_aggregate_function_add_parameter(func, interval_type);
-        duckdb_destroy_logical_type(&mut { interval_type });
-
-        // Return type: BIGINT (session ID)
-        let ret_type = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BIGINT);
-        duckdb_aggregate_function_set_return_type(func, ret_type);
-        duckdb_destroy_logical_type(&mut { ret_type });
-
-        // Set callbacks
-        duckdb_aggregate_function_set_functions(
-            func,
-            Some(state_size),
-            Some(state_init),
-            Some(state_update),
-            Some(state_combine),
-            Some(state_finalize),
+        register(
+            con,
+            c"sessionize",
+            Output::SessionId,
+            &[
+                (Extra::None, state_update),
+                (Extra::MaxLength, state_update_capped),
+            ],
+        );
+    }
+}
+
+/// Registers the `sessionize_by` function with `DuckDB`.
+///
+/// Signature: `sessionize_by(TIMESTAMP, INTERVAL, BOOLEAN) → BIGINT`
+///
+/// `sessionize` with an extra session break at every row whose condition is
+/// true (see [`crate::sessionize`]), under the same frame requirements:
+/// ```sql
+/// SELECT sessionize_by(event_time, INTERVAL '30 minutes', event_type = 'login')
+///   OVER (PARTITION BY user_id ORDER BY event_time)
+/// FROM events
+/// ```
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sessionize_by(con: duckdb_connection) {
+    unsafe {
+        register(
+            con,
+            c"sessionize_by",
+            Output::SessionId,
+            &[(Extra::Condition, state_update_by)],
+        )
+    }
+}
+
+/// Registers the `sessionize_info` function with `DuckDB`.
+///
+/// Signatures, each returning `STRUCT(session_id BIGINT, is_start BOOLEAN)`:
+///
+/// 1. `sessionize_info(TIMESTAMP, INTERVAL)`
+/// 2. With a maximum session length: `sessionize_info(TIMESTAMP, INTERVAL, INTERVAL)`
+/// 3. With a boundary condition: `sessionize_info(TIMESTAMP, INTERVAL, BOOLEAN)`
+///
+/// The session ID of `sessionize` (or `sessionize_by` for the condition
+/// overload) and whether the row starts its session, under the same frame
+/// requirements:
+/// ```sql
+/// SELECT sessionize_info(event_time, INTERVAL '30 minutes')
+///   OVER (PARTITION BY user_id ORDER BY event_time)
+/// FROM events
+/// ```
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sessionize_info(con: duckdb_connection) {
+    unsafe {
+        register(
+            con,
+            c"sessionize_info",
+            Output::Info,
+            &[
+                (Extra::None, state_update_info),
+                (Extra::MaxLength, state_update_info_capped),
+                (Extra::Condition, state_update_info_by),
+            ],
         );
+    }
+}
+
+/// Third argument of an overload, after (TIMESTAMP, INTERVAL).
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Extra {
+    /// No third argument.
+    None,
+    /// BOOLEAN boundary condition (`sessionize_by`).
+    Condition,
+    /// INTERVAL maximum session length.
+    MaxLength,
+}
+
+/// Result of a function set.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// BIGINT session ID.
+    SessionId,
+    /// `STRUCT(session_id BIGINT, is_start BOOLEAN)` (`sessionize_info`).
+    Info,
+}
+
+type UpdateFn =
+    unsafe extern "C" fn(duckdb_function_info, duckdb_data_chunk, *mut duckdb_aggregate_state);
+
+type FinalizeFn = unsafe extern "C" fn(
+    duckdb_function_info,
+    *mut duckdb_aggregate_state,
+    duckdb_vector,
+    idx_t,
+    idx_t,
+);
+
+/// Registers `name` as a function set returning `output`, with one overload
+/// per `(extra, update)` pair: parameters (TIMESTAMP, INTERVAL) plus `extra`,
+/// update callback `update`.
+unsafe fn register(
+    con: duckdb_connection,
+    name: &CStr,
+    output: Output,
+    overloads: &[(Extra, UpdateFn)],
+) {
+    unsafe {
+        let set = duckdb_create_aggregate_function_set(name.as_ptr());
+
+        for &(extra, update) in overloads {
+            let func = duckdb_create_aggregate_function();
+            duckdb_aggregate_function_set_name(func, name.as_ptr());
 
-        duckdb_aggregate_function_set_destructor(func, Some(state_destroy));
+            // Parameter 0: TIMESTAMP (event timestamp)
+            let ts_type = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP);
+            duckdb_aggregate_function_add_parameter(func, ts_type);
+            duckdb_destroy_logical_type(&mut { ts_type });
 
-        let result = duckdb_register_aggregate_function(con, func);
+            // Parameter 1: INTERVAL (gap threshold)
+            let interval_type = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL);
+            duckdb_aggregate_function_add_parameter(func, interval_type);
+            duckdb_destroy_logical_type(&mut { interval_type });
+
+            // Parameter 2: BOOLEAN (boundary condition) or INTERVAL (maximum
+            // session length)
+            let extra_type = match extra {
+                Extra::None => None,
+                Extra::Condition => Some(DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+                Extra::MaxLength => Some(DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL),
+            };
+            if let Some(extra_type) = extra_type {
+                let extra_type = duckdb_create_logical_type(extra_type);
+                duckdb_aggregate_function_add_parameter(func, extra_type);
+                duckdb_destroy_logical_type(&mut { extra_type });
+            }
+
+            // Return type: BIGINT (session ID) or STRUCT(session_id BIGINT,
+            // is_start BOOLEAN)
+            let ret_type = match output {
+                Output::SessionId => duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+                Output::Info => info_type(),
+            };
+            duckdb_aggregate_function_set_return_type(func, ret_type);
+            duckdb_destroy_logical_type(&mut { ret_type });
+            let finalize: FinalizeFn = match output {
+                Output::SessionId => state_finalize,
+                Output::Info => state_finalize_info,
+            };
+
+            // Set callbacks
+            duckdb_aggregate_function_set_functions(
+                func,
+                Some(State::size_callback),
+                Some(State::init_callback),
+                Some(update),
+                Some(state_combine),
+                Some(finalize),
+            );
+
+            duckdb_aggregate_function_set_destructor(func, Some(State::destroy_callback));
+
+            duckdb_add_aggregate_function_to_set(set, func);
+            duckdb_destroy_aggregate_function(&mut { func });
+        }
+
+        let result = duckdb_register_aggregate_function_set(con, set);
         if result != DuckDBSuccess {
-            eprintln!("behavioral: failed to register sessionize function");
+            eprintln!(
+                "behavioral: failed to register {} function",
+                name.to_string_lossy()
+            );
         }
 
-        duckdb_destroy_aggregate_function(&mut { func });
+        duckdb_destroy_aggregate_function_set(&mut { set });
+    }
+}
+
+/// Creates the `STRUCT(session_id BIGINT, is_start BOOLEAN)` result type.
+unsafe fn info_type() -> duckdb_logical_type {
+    unsafe {
+        let mut types = [
+            duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+            duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN),
+        ];
+        let mut names = [c"session_id".as_ptr(), c"is_start".as_ptr()];
+        let info =
+            duckdb_create_struct_type(types.as_mut_ptr(), names.as_mut_ptr(), types.len() as idx_t);
+        for member in &mut types {
+            duckdb_destroy_logical_type(member);
+        }
+        info
     }
 }
 
 /// State stored in `DuckDB`'s aggregate state buffer.
 /// Points to a heap-allocated [`SessionizeBoundaryState`].
-#[repr(C)]
-struct FfiState {
-    inner: *mut SessionizeBoundaryState,
+type State = BoxedState<SessionizeBoundaryState>;
+
+// SAFETY: `input` is a valid DuckDB data chunk with the registered column types
+// (TIMESTAMP, INTERVAL). `states` points to `row_count` aggregate state pointers,
+// each initialized by `State::init_callback`.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_rows(info, input, states, "sessionize", Extra::None) }
 }
 
-// SAFETY: Returns the byte size of FfiState for DuckDB's state allocation.
-// Pure computation with no pointer dereferences.
-unsafe extern "C" fn state_size(_info: duckdb_function_info) -> idx_t {
-    std::mem::size_of::<FfiState>() as idx_t
+// SAFETY: Same as `state_update`, with a third INTERVAL column.
+unsafe extern "C" fn state_update_capped(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_rows(info, input, states, "sessionize", Extra::MaxLength) }
+}
+
+// SAFETY: Same as `state_update`, with a third BOOLEAN column.
+unsafe extern "C" fn state_update_by(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_rows(info, input, states, "sessionize_by", Extra::Condition) }
+}
+
+// SAFETY: Same as `state_update`.
+unsafe extern "C" fn state_update_info(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_rows(info, input, states, "sessionize_info", Extra::None) }
+}
+
+// SAFETY: Same as `state_update_capped`.
+unsafe extern "C" fn state_update_info_capped(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_rows(info, input, states, "sessionize_info", Extra::MaxLength) }
+}
+
+// SAFETY: Same as `state_update_by`.
+unsafe extern "C" fn state_update_info_by(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_rows(info, input, states, "sessionize_info", Extra::Condition) }
 }
 
-// SAFETY: `state` is a DuckDB-allocated buffer of at least `state_size()` bytes.
-// We initialize the inner pointer to a heap-allocated SessionizeBoundaryState
-// which will be freed in `state_destroy`.
-unsafe extern "C" fn state_init(_info: duckdb_function_info, state: duckdb_aggregate_state) {
+/// Reads the INTERVAL at `row` of `vector` as (months, days, micros), or
+/// `None` if it is `NULL`.
+///
+/// # Safety
+///
+/// `vector` must be a valid INTERVAL vector with more than `row` rows.
+unsafe fn read_interval(vector: duckdb_vector, row: usize) -> Option<(i32, i32, i64)> {
     unsafe {
-        let ffi_state = &mut *(state as *mut FfiState);
-        ffi_state.inner = Box::into_raw(Box::new(SessionizeBoundaryState::new()));
+        let validity = duckdb_vector_get_validity(vector);
+        if !validity.is_null() && !duckdb_validity_row_is_valid(validity, row as idx_t) {
+            return None;
+        }
+        // { months: i32, days: i32, micros: i64 } = 16 bytes
+        let interval_ptr = (duckdb_vector_get_data(vector) as *const u8).add(row * 16);
+        Some((
+            *(interval_ptr as *const i32),
+            *(interval_ptr.add(4) as *const i32),
+            *(interval_ptr.add(8) as *const i64),
+        ))
     }
 }
 
-// SAFETY: `input` is a valid DuckDB data chunk with the registered column types
-// (TIMESTAMP, INTERVAL). `states` points to `row_count` aggregate state pointers,
-// each initialized by `state_init`. All vector data pointers are valid for
-// `row_count` elements. Validity bitmaps may be null (meaning all rows are valid).
-unsafe extern "C" fn state_update(
-    _info: duckdb_function_info,
+// SAFETY: `input` is a valid DuckDB data chunk with columns (TIMESTAMP,
+// INTERVAL) plus the column of `extra`. `states` points to `row_count`
+// aggregate state pointers, each initialized by `State::init_callback`. All
+// vector data pointers are valid for `row_count` elements. Validity bitmaps
+// may be null (meaning all rows are valid).
+unsafe fn update_rows(
+    info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
+    function: &str,
+    extra: Extra,
 ) {
     unsafe {
         let row_count = duckdb_data_chunk_get_size(input) as usize;
@@ -114,18 +349,29 @@ unsafe extern "C" fn state_update(
         let ts_data = duckdb_vector_get_data(ts_vec) as *const i64;
         let ts_validity = duckdb_vector_get_validity(ts_vec);
 
-        // Vector 1: INTERVAL (months: i32, days: i32, micros: i64)
-        let interval_vec = duckdb_data_chunk_get_vector(input, 1);
-        let interval_data = duckdb_vector_get_data(interval_vec) as *const u8;
-        let interval_validity = duckdb_vector_get_validity(interval_vec);
+        // Vector 1: INTERVAL (gap threshold)
+        let gap_vec = duckdb_data_chunk_get_vector(input, 1);
+
+        // Vector 2: BOOLEAN (boundary condition; NULL counts as false) or
+        // INTERVAL (maximum session length)
+        let extra_vec = if extra == Extra::None {
+            std::ptr::null_mut()
+        } else {
+            duckdb_data_chunk_get_vector(input, 2)
+        };
+        let (cond_data, cond_validity) = if extra == Extra::Condition {
+            (
+                duckdb_vector_get_data(extra_vec) as *const u8,
+                duckdb_vector_get_validity(extra_vec),
+            )
+        } else {
+            (std::ptr::null(), std::ptr::null_mut())
+        };
 
         for i in 0..row_count {
-            let state_ptr = *states.add(i);
-            let ffi_state = &mut *(state_ptr as *mut FfiState);
-            if ffi_state.inner.is_null() {
+            let Some(state) = State::get_mut(*states.add(i)) else {
                 continue;
-            }
-            let state = &mut *ffi_state.inner;
+            };
 
             // NULL timestamps: mark state so finalize emits NULL for this row
             if !ts_validity.is_null() && !duckdb_validity_row_is_valid(ts_validity, i as idx_t) {
@@ -134,30 +380,46 @@ unsafe extern "C" fn state_update(
             }
 
             // Read interval threshold (same for all rows, but read per-row for safety)
-            if !interval_validity.is_null()
-                && !duckdb_validity_row_is_valid(interval_validity, i as idx_t)
-            {
+            let Some((months, days, micros)) = read_interval(gap_vec, i) else {
                 continue;
-            }
-
-            // Parse interval: { months: i32, days: i32, micros: i64 } = 16 bytes
-            let interval_ptr = interval_data.add(i * 16);
-            let months = *(interval_ptr as *const i32);
-            let days = *(interval_ptr.add(4) as *const i32);
-            let micros = *(interval_ptr.add(8) as *const i64);
+            };
+            let Some(threshold_us) = interval_to_micros(months, days, micros) else {
+                report(info, &interval_message(function, months, days, micros));
+                return;
+            };
+            state.threshold_us = threshold_us;
 
-            if let Some(threshold_us) = interval_to_micros(months, days, micros) {
-                state.threshold_us = threshold_us;
+            if extra == Extra::MaxLength {
+                let Some((months, days, micros)) = read_interval(extra_vec, i) else {
+                    continue;
+                };
+                let Some(max_session_us) = interval_to_micros(months, days, micros) else {
+                    report(info, &interval_message(function, months, days, micros));
+                    return;
+                };
+                if max_session_us < 0 {
+                    report(
+                        info,
+                        &negative_message(function, "max_session_length", max_session_us),
+                    );
+                    return;
+                }
+                state.max_session_us = Some(max_session_us);
             }
 
+            let breaks = !cond_data.is_null()
+                && (cond_validity.is_null()
+                    || duckdb_validity_row_is_valid(cond_validity, i as idx_t))
+                && *cond_data.add(i) != 0;
+
             let timestamp = *ts_data.add(i);
-            state.update(timestamp);
+            state.update_with_break(timestamp, breaks);
         }
     }
 }
 
 // SAFETY: `source` and `target` point to `count` aggregate state pointers,
-// each initialized by `state_init`. Null checks guard against uninitialized states.
+// each initialized by `State::init_callback`. Null checks guard against uninitialized states.
 unsafe extern "C" fn state_combine(
     _info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
@@ -166,26 +428,20 @@ unsafe extern "C" fn state_combine(
 ) {
     unsafe {
         for i in 0..count as usize {
-            let src_ptr = *source.add(i);
-            let tgt_ptr = *target.add(i);
-            let src_ffi = &*(src_ptr as *const FfiState);
-            let tgt_ffi = &mut *(tgt_ptr as *mut FfiState);
-
-            if src_ffi.inner.is_null() || tgt_ffi.inner.is_null() {
+            let Some(src_state) = State::get(*source.add(i)) else {
                 continue;
-            }
-
-            let src_state = &*src_ffi.inner;
-            let tgt_state = &*tgt_ffi.inner;
-            let combined = tgt_state.combine(src_state);
-            *tgt_ffi.inner = combined;
+            };
+            let Some(tgt_state) = State::get_mut(*target.add(i)) else {
+                continue;
+            };
+            *tgt_state = tgt_state.combine(src_state);
         }
     }
 }
 
 // SAFETY: `source` points to `count` aggregate state pointers. `result` is a
-// valid DuckDB BIGINT vector with room for `offset + count` elements. Null
-// inner pointers or empty states produce NULL output via validity bitmap.
+// valid DuckDB BIGINT vector with room for `offset + count` elements.
+// Destroyed or empty states produce NULL output via validity bitmap.
 unsafe extern "C" fn state_finalize(
     _info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
@@ -199,33 +455,45 @@ unsafe extern "C" fn state_finalize(
         let validity = duckdb_vector_get_validity(result);
 
         for i in 0..count as usize {
-            let state_ptr = *source.add(i);
-            let ffi_state = &*(state_ptr as *const FfiState);
             let idx = offset as usize + i;
 
-            if ffi_state.inner.is_null()
-                || (*ffi_state.inner).first_ts.is_none()
-                || (*ffi_state.inner).current_row_null
-            {
-                duckdb_validity_set_row_invalid(validity, idx as idx_t);
-            } else {
-                *data.add(idx) = (*ffi_state.inner).finalize();
+            match State::get(*source.add(i)) {
+                Some(state) if state.first_ts.is_some() && !state.current_row_null => {
+                    *data.add(idx) = state.finalize();
+                }
+                _ => duckdb_validity_set_row_invalid(validity, idx as idx_t),
             }
         }
     }
 }
 
-// SAFETY: `state` points to `count` aggregate state pointers. Each inner pointer
-// was allocated by `Box::into_raw` in `state_init`. We reclaim the Box to free
-// heap memory, then null the pointer to prevent double-free.
-unsafe extern "C" fn state_destroy(state: *mut duckdb_aggregate_state, count: idx_t) {
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB STRUCT(session_id BIGINT, is_start BOOLEAN) vector with room
+// for `offset + count` elements. Destroyed or empty states produce NULL
+// output via the struct's validity bitmap.
+unsafe extern "C" fn state_finalize_info(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
     unsafe {
+        let id_data = duckdb_vector_get_data(duckdb_struct_vector_get_child(result, 0)) as *mut i64;
+        let start_data =
+            duckdb_vector_get_data(duckdb_struct_vector_get_child(result, 1)) as *mut bool;
+        duckdb_vector_ensure_validity_writable(result);
+        let validity = duckdb_vector_get_validity(result);
+
         for i in 0..count as usize {
-            let state_ptr = *state.add(i);
-            let ffi_state = &mut *(state_ptr as *mut FfiState);
-            if !ffi_state.inner.is_null() {
-                drop(Box::from_raw(ffi_state.inner));
-                ffi_state.inner = std::ptr::null_mut();
+            let idx = offset as usize + i;
+
+            match State::get(*source.add(i)) {
+                Some(state) if state.first_ts.is_some() && !state.current_row_null => {
+                    *id_data.add(idx) = state.finalize();
+                    *start_data.add(idx) = state.is_session_start();
+                }
+                _ => duckdb_validity_set_row_invalid(validity, idx as idx_t),
             }
         }
     }
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sessionize_approx` aggregate function.
+//!
+//! Uses [`quack_rs::aggregate::AggregateFunctionBuilder`] (single signature),
+//! [`quack_rs::aggregate::FfiState`] for safe state management, and
+//! [`quack_rs::vector::VectorReader`] / [`quack_rs::vector::VectorWriter`]
+//! for vector I/O. Unlike [`sessionize`](super::sessionize) it does not rely
+//! on ordered window frames, so the standard aggregate builder suffices.
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::error::{interval_message, report};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sessionize_approx::SessionizeApproxState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+impl quack_rs::aggregate::AggregateState for SessionizeApproxState {}
+
+/// Registers the `sessionize_approx` function with `DuckDB`.
+///
+/// Signature: `sessionize_approx(TIMESTAMP, INTERVAL, INTERVAL) -> BIGINT`
+///
+/// Parameters:
+/// - `timestamp`: Event timestamp (any arrival order)
+/// - `gap`: Inactivity gap that starts a new session
+/// - `slack`: Maximum tolerated disorder of the input
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sessionize_approx(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let builder = AggregateFunctionBuilder::new("sessionize_approx")
+        .param(TypeId::Timestamp)
+        .param(TypeId::Interval)
+        .param(TypeId::Interval)
+        .returns(TypeId::BigInt);
+    let builder = with_lifecycle!(builder, SessionizeApproxState)
+        .update(state_update)
+        .combine(state_combine)
+        .finalize(state_finalize);
+    unsafe { con.register_aggregate(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns
+// (TIMESTAMP, INTERVAL, INTERVAL) as registered. `states` points to
+// `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+
+        let ts_reader = VectorReader::new(input, 0);
+        let gap_reader = VectorReader::new(input, 1);
+        let slack_reader = VectorReader::new(input, 2);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SessionizeApproxState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            // Set gap and slack (once per state)
+            if !state.params_set && gap_reader.is_valid(i) && slack_reader.is_valid(i) {
+                let gap = gap_reader.read_interval(i);
+                let slack = slack_reader.read_interval(i);
+                let Some(gap_us) = interval_to_micros(gap.months, gap.days, gap.micros) else {
+                    let message =
+                        interval_message("sessionize_approx", gap.months, gap.days, gap.micros);
+                    report(info, &message);
+                    return;
+                };
+                let Some(slack_us) = interval_to_micros(slack.months, slack.days, slack.micros)
+                else {
+                    let message = interval_message(
+                        "sessionize_approx",
+                        slack.months,
+                        slack.days,
+                        slack.micros,
+                    );
+                    report(info, &message);
+                    return;
+                };
+                state.set_params(gap_us, slack_us.max(0));
+            }
+
+            if !ts_reader.is_valid(i) {
+                continue;
+            }
+
+            state.update(ts_reader.read_i64(i));
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SessionizeApproxState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SessionizeApproxState>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB BIGINT vector. NULL is written for states without timestamps.
+unsafe extern "C" fn state_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<SessionizeApproxState>::with_state(*source.add(i)) {
+                Some(state) if state.max_seen.is_some() => {
+                    writer.write_i64(idx, state.finalize());
+                }
+                _ => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    const MIN: i64 = 60_000_000;
+
+    #[test]
+    fn test_sessionize_approx_config_propagation() {
+        // Zero-initialized target combine pattern (Session 10 bug).
+        let mut source = AggregateTestHarness::<SessionizeApproxState>::new();
+        source.update(|s| {
+            s.set_params(30 * MIN, 5 * MIN);
+            s.update(0);
+            s.update(100 * MIN);
+        });
+
+        let mut target = AggregateTestHarness::<SessionizeApproxState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.threshold_us, 30 * MIN);
+        assert_eq!(state.slack_us, 5 * MIN);
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_sessionize_approx_combine_out_of_order_partials() {
+        let mut a = AggregateTestHarness::<SessionizeApproxState>::new();
+        a.update(|s| {
+            s.set_params(30 * MIN, 10 * MIN);
+            s.update(200 * MIN);
+            s.update(10 * MIN);
+        });
+
+        let mut b = AggregateTestHarness::<SessionizeApproxState>::new();
+        b.update(|s| {
+            s.set_params(30 * MIN, 10 * MIN);
+            s.update(0);
+            s.update(205 * MIN);
+        });
+
+        b.combine(&a, |src, tgt| tgt.combine_in_place(src));
+        let state = b.finalize();
+        assert_eq!(state.finalize(), 2);
+    }
+}
@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `sessionize_state` aggregate function and the
+//! `sessionize_merge` and `sessionize_finalize` scalar functions.
+//!
+//! Unlike the `sessionize` window function, `sessionize_state` is a plain
+//! aggregate over unordered rows, registered with
+//! [`quack_rs::aggregate::AggregateFunctionBuilder`] like
+//! [`session_position`](super::session_position). Its
+//! [`SessionizeSummaryState`] finalizes to the `BLOB` of
+//! [`SessionizeBoundaryState::to_blob`]. The two scalar functions are
+//! registered through [`state_export`](super::state_export).
+
+use crate::common::state_blob::BlobError;
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::error::{interval_message, report};
+use crate::ffi::state_export::{
+    finalize_rows, register_finalize, register_merge, write_blob, ExportedState,
+};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::sessionize::{SessionizeBoundaryState, SessionizeSummaryState};
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+impl quack_rs::aggregate::AggregateState for SessionizeSummaryState {}
+
+/// Registers the `sessionize_state` function with `DuckDB`.
+///
+/// Signature: `sessionize_state(TIMESTAMP, INTERVAL) -> BLOB`
+///
+/// Parameters:
+/// - `timestamp`: Event timestamp
+/// - `gap`: Inactivity gap that starts a new session
+///
+/// The result is the exported state of the group's rows in timestamp order
+/// (see [`SessionizeBoundaryState::to_blob`]), to be combined with
+/// `sessionize_merge` and finished with `sessionize_finalize`.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_sessionize_state(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let b = AggregateFunctionBuilder::new("sessionize_state")
+        .param(TypeId::Timestamp)
+        .param(TypeId::Interval)
+        .returns(TypeId::Blob);
+    let builder = with_lifecycle!(b, SessionizeSummaryState)
+        .update(state_update)
+        .combine(state_combine)
+        .finalize(state_finalize_blob);
+    unsafe { con.register_aggregate(builder) }
+}
+
+/// Registers the `sessionize_merge` function with `DuckDB`.
+///
+/// Signature: `sessionize_merge(BLOB, BLOB) -> BLOB`
+///
+/// Returns the state of the rows of both states, which must cover disjoint
+/// time ranges (see [`SessionizeBoundaryState::merge_ranges`]). A `NULL`
+/// argument is an empty state, so the result is the other argument; both
+/// `NULL` produces `NULL`. Overlapping ranges, different gaps and malformed
+/// blobs fail the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sessionize_merge(con: duckdb_connection) {
+    unsafe { register_merge::<SessionizeBoundaryState>(con) }
+}
+
+/// Registers the `sessionize_finalize` function with `DuckDB`.
+///
+/// Signature: `sessionize_finalize(BLOB) -> BIGINT`
+///
+/// Returns the number of sessions of the state's rows, the session ID
+/// `sessionize` gives the last of them, or 0 without rows. `NULL` input
+/// produces `NULL`; a malformed blob fails the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sessionize_finalize(con: duckdb_connection) {
+    unsafe {
+        register_finalize(
+            con,
+            c"sessionize_finalize",
+            duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BIGINT),
+            sessionize_finalize_function,
+        );
+    }
+}
+
+impl ExportedState for SessionizeBoundaryState {
+    const NAME: &'static str = "sessionize";
+
+    fn from_blob(bytes: &[u8]) -> Result<Self, BlobError> {
+        Self::from_blob(bytes)
+    }
+
+    fn to_blob(&self) -> Vec<u8> {
+        self.to_blob()
+    }
+
+    fn merge(self, other: &Self) -> Result<Self, &'static str> {
+        self.merge_ranges(other)
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns
+// (TIMESTAMP, INTERVAL) as registered. `states` points to `row_count`
+// aggregate state pointers.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+
+        let ts_reader = VectorReader::new(input, 0);
+        let gap_reader = VectorReader::new(input, 1);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<SessionizeSummaryState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            // Set gap threshold (once per state)
+            if state.threshold_us.is_none() && gap_reader.is_valid(i) {
+                let gap = gap_reader.read_interval(i);
+                let Some(gap_us) = interval_to_micros(gap.months, gap.days, gap.micros) else {
+                    let message =
+                        interval_message("sessionize_state", gap.months, gap.days, gap.micros);
+                    report(info, &message);
+                    return;
+                };
+                state.threshold_us = Some(gap_us);
+            }
+
+            if !ts_reader.is_valid(i) {
+                continue;
+            }
+            state.update(ts_reader.read_i64(i));
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<SessionizeSummaryState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<SessionizeSummaryState>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB BLOB vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize_blob(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<SessionizeSummaryState>::with_state_mut(*source.add(i)) {
+                Some(state) => write_blob(result, idx, &state.summarize().to_blob()),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one BLOB column as
+// registered. `output` is a valid BIGINT vector sized for the chunk.
+unsafe extern "C" fn sessionize_finalize_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        finalize_rows::<SessionizeBoundaryState>(info, input, output, |writer, row, state| {
+            writer.write_i64(row, state.finalize());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<SessionizeSummaryState>::new();
+        source.update(|s| {
+            s.threshold_us = Some(1_800_000_000);
+            s.update(3_600_000_000);
+            s.update(0);
+        });
+        let mut target = AggregateTestHarness::<SessionizeSummaryState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert_eq!(state.threshold_us, Some(1_800_000_000));
+        assert_eq!(state.summarize().finalize(), 2);
+    }
+}
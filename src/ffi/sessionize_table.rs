@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Registration of the `sessionize_table` table macro.
+//!
+//! The `DuckDB` C Extension API cannot register table functions that take a
+//! relation as input, so `sessionize_table` is a SQL table macro created on the
+//! loading connection with `duckdb_query`. It reads the relation through
+//! `query_table`, partitions and orders it internally and appends a
+//! `session_id` column computed with built-in window functions only — it does
+//! not depend on the [`sessionize`](super::sessionize) window registration.
+//!
+//! Session IDs match `sessionize`: a new session starts when the gap to the
+//! previous timestamp in the partition exceeds `gap`, IDs start at 1 and rows
+//! with a `NULL` timestamp get a `NULL` ID. Rows sharing a timestamp are peers
+//! in the default `RANGE` frame and therefore always share a session ID.
+
+use libduckdb_sys::*;
+
+/// Error raised by the macro for month-based gaps, worded like
+/// [`interval_message`](crate::ffi::error::interval_message).
+const MONTH_GAP_MESSAGE: &str = "sessionize_table: interval with month(s) is not supported (months vary in length); use days, hours, minutes or seconds";
+
+/// `CREATE MACRO` statement defining `sessionize_table`.
+///
+/// `events` is a table or view name (a string, resolved by `query_table`);
+/// `ts_col` and `partition_col` are unqualified column references substituted
+/// into the body. `__sessionize_break` is a helper column removed from the
+/// output.
+fn macro_sql() -> String {
+    format!(
+        "CREATE OR REPLACE MACRO sessionize_table(events, ts_col, gap, partition_col := NULL) AS TABLE
+SELECT * EXCLUDE (__sessionize_break),
+    CASE WHEN ts_col IS NOT NULL THEN
+        1 + COALESCE(SUM(__sessionize_break) OVER (PARTITION BY partition_col ORDER BY ts_col), 0)
+    END AS session_id
+FROM (
+    SELECT *,
+        CASE
+            WHEN datepart('year', gap) <> 0 OR datepart('month', gap) <> 0
+                THEN error('{MONTH_GAP_MESSAGE}')
+            WHEN ts_col - LAG(ts_col) OVER (PARTITION BY partition_col ORDER BY ts_col) > gap
+                THEN 1
+            ELSE 0
+        END AS __sessionize_break
+    FROM query_table(events)
+)"
+    )
+}
+
+/// Registers the `sessionize_table` table macro with `DuckDB`.
+///
+/// Signature: `sessionize_table(events VARCHAR, ts_col, gap INTERVAL, partition_col := NULL)`
+///
+/// Returns every column of `events` plus `session_id BIGINT`:
+/// ```sql
+/// SELECT user_id, event_time, session_id
+/// FROM sessionize_table('events', event_time, INTERVAL '30 minutes', partition_col := user_id)
+/// ```
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_sessionize_table(con: duckdb_connection) {
+    unsafe {
+        let sql = std::ffi::CString::new(macro_sql()).expect("macro SQL contains no NUL bytes");
+        let mut result: duckdb_result = std::mem::zeroed();
+        if duckdb_query(con, sql.as_ptr(), &mut result) != DuckDBSuccess {
+            eprintln!("behavioral: failed to register sessionize_table macro");
+        }
+        duckdb_destroy_result(&mut result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::error::interval_message;
+
+    #[test]
+    fn test_month_gap_message_matches_interval_message() {
+        let aggregate = interval_message("sessionize_table", 1, 0, 0);
+        let (_, suffix) = aggregate.split_once("month(s)").unwrap();
+        assert!(MONTH_GAP_MESSAGE.starts_with("sessionize_table: interval with month(s)"));
+        assert!(MONTH_GAP_MESSAGE.ends_with(suffix));
+        assert!(!MONTH_GAP_MESSAGE.contains('\''));
+        assert!(macro_sql().contains(MONTH_GAP_MESSAGE));
+    }
+}
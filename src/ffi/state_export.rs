@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Shared FFI plumbing for exported aggregate states: the `<name>_merge` and
+//! `<name>_finalize` scalar functions of
+//! [`window_funnel_state`](super::window_funnel_state),
+//! [`retention_state`](super::retention_state) and
+//! [`sessionize_state`](super::sessionize_state).
+//!
+//! A state type implements [`ExportedState`]; the merge callback is generic
+//! over it, and each finalize callback reads its states through
+//! [`finalize_rows`]. The scalar functions are registered with the raw
+//! `libduckdb-sys` scalar function API, like
+//! [`parse_duration`](super::parse_duration). `BLOB` values are read and
+//! written as `duckdb_string_t`, the representation `DuckDB` shares between
+//! `VARCHAR` and `BLOB`.
+
+use crate::common::state_blob::BlobError;
+use crate::ffi::error::report_scalar;
+use libduckdb_sys::*;
+use quack_rs::vector::{VectorReader, VectorWriter};
+use std::ffi::{CStr, CString};
+
+/// An aggregate state that can be exported as a `BLOB` and merged.
+pub(crate) trait ExportedState: Sized {
+    /// Function name prefix of the `_state`, `_merge` and `_finalize`
+    /// functions.
+    const NAME: &'static str;
+
+    /// Imports an exported state.
+    fn from_blob(bytes: &[u8]) -> Result<Self, BlobError>;
+
+    /// Exports the state.
+    fn to_blob(&self) -> Vec<u8>;
+
+    /// Merges `other` into `self`, or returns why the two states cannot be
+    /// merged.
+    fn merge(self, other: &Self) -> Result<Self, &'static str>;
+}
+
+/// Reads a `BLOB` argument column. [`VectorReader::read_str`] expects UTF-8,
+/// so the bytes are read from the `duckdb_string_t` values directly, as
+/// the `LIST(VARCHAR)` elements of
+/// [`VarcharListReader`](super::sequence::VarcharListReader) are.
+struct BlobColumn {
+    reader: VectorReader,
+    strings: *mut duckdb_string_t,
+}
+
+impl BlobColumn {
+    /// Creates a reader for the `BLOB` column `col` of `input`.
+    ///
+    /// # Safety
+    ///
+    /// `input` must be a valid data chunk whose column `col` is a `BLOB`
+    /// vector.
+    unsafe fn new(input: duckdb_data_chunk, col: usize) -> Self {
+        unsafe {
+            let vector = duckdb_data_chunk_get_vector(input, col as idx_t);
+            Self {
+                reader: VectorReader::new(input, col),
+                strings: duckdb_vector_get_data(vector).cast::<duckdb_string_t>(),
+            }
+        }
+    }
+
+    /// Borrows the bytes at `row`, `None` when it is `NULL`.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be less than the chunk's row count.
+    unsafe fn read(&self, row: usize) -> Option<&[u8]> {
+        unsafe {
+            if !self.reader.is_valid(row) {
+                return None;
+            }
+            let string = self.strings.add(row);
+            let len = duckdb_string_t_length(*string) as usize;
+            let data = duckdb_string_t_data(string).cast::<u8>();
+            Some(std::slice::from_raw_parts(data, len))
+        }
+    }
+}
+
+/// Writes `bytes` as the `BLOB` at `row` of `vector`. The bytes are copied.
+///
+/// # Safety
+///
+/// `vector` must be a valid `BLOB` vector with room for `row`.
+pub(crate) unsafe fn write_blob(vector: duckdb_vector, row: usize, bytes: &[u8]) {
+    unsafe {
+        duckdb_vector_assign_string_element_len(
+            vector,
+            row as idx_t,
+            bytes.as_ptr().cast(),
+            bytes.len() as idx_t,
+        );
+    }
+}
+
+/// Imports a state argument of `<S::NAME>_<function>`, reporting a malformed
+/// blob as an error and returning `None`.
+///
+/// # Safety
+///
+/// `info` must be the `duckdb_function_info` passed to the running scalar
+/// function.
+unsafe fn load<S: ExportedState>(
+    info: duckdb_function_info,
+    function: &str,
+    bytes: &[u8],
+) -> Option<S> {
+    match S::from_blob(bytes) {
+        Ok(state) => Some(state),
+        Err(err) => {
+            unsafe { report_scalar(info, &format!("{}_{function}: {err}", S::NAME)) };
+            None
+        }
+    }
+}
+
+/// Registers `<S::NAME>_merge(BLOB, BLOB) -> BLOB` with `DuckDB`.
+///
+/// A `NULL` argument is an empty state, so the result is the other
+/// argument; both `NULL` produces `NULL`. States that [`ExportedState::merge`]
+/// rejects and malformed blobs fail the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub(crate) unsafe fn register_merge<S: ExportedState>(con: duckdb_connection) {
+    let name = CString::new(format!("{}_merge", S::NAME)).unwrap_or_default();
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, name.as_ptr());
+
+        for _ in 0..2 {
+            let mut blob = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BLOB);
+            duckdb_scalar_function_add_parameter(func, blob);
+            duckdb_destroy_logical_type(&mut blob);
+        }
+
+        let mut blob = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BLOB);
+        duckdb_scalar_function_set_return_type(func, blob);
+        duckdb_destroy_logical_type(&mut blob);
+
+        // NULL arguments are empty states, not NULL results
+        duckdb_scalar_function_set_special_handling(func);
+        duckdb_scalar_function_set_function(func, Some(merge_function::<S>));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register {}_merge function", S::NAME);
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+/// Registers `<name>(BLOB) -> return_type` with `DuckDB` and destroys
+/// `return_type`. `NULL` input produces `NULL`.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle and logical type.
+pub(crate) unsafe fn register_finalize(
+    con: duckdb_connection,
+    name: &CStr,
+    mut return_type: duckdb_logical_type,
+    function: unsafe extern "C" fn(duckdb_function_info, duckdb_data_chunk, duckdb_vector),
+) {
+    unsafe {
+        let func = duckdb_create_scalar_function();
+        duckdb_scalar_function_set_name(func, name.as_ptr());
+
+        let mut blob = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BLOB);
+        duckdb_scalar_function_add_parameter(func, blob);
+        duckdb_destroy_logical_type(&mut blob);
+
+        duckdb_scalar_function_set_return_type(func, return_type);
+        duckdb_destroy_logical_type(&mut return_type);
+
+        duckdb_scalar_function_set_function(func, Some(function));
+
+        let result = duckdb_register_scalar_function(con, func);
+        if result != DuckDBSuccess {
+            eprintln!(
+                "behavioral: failed to register {} function",
+                name.to_string_lossy()
+            );
+        }
+
+        duckdb_destroy_scalar_function(&mut { func });
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with two BLOB columns as
+// registered. `output` is a valid BLOB vector sized for the chunk.
+unsafe extern "C" fn merge_function<S: ExportedState>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let left = BlobColumn::new(input, 0);
+        let right = BlobColumn::new(input, 1);
+        let mut writer = VectorWriter::new(output);
+
+        for row in 0..row_count {
+            let merged = match (left.read(row), right.read(row)) {
+                (None, None) => {
+                    writer.set_null(row);
+                    continue;
+                }
+                (Some(bytes), None) | (None, Some(bytes)) => load::<S>(info, "merge", bytes),
+                (Some(left), Some(right)) => {
+                    let (Some(state), Some(other)) = (
+                        load::<S>(info, "merge", left),
+                        load::<S>(info, "merge", right),
+                    ) else {
+                        return;
+                    };
+                    match state.merge(&other) {
+                        Ok(merged) => Some(merged),
+                        Err(message) => {
+                            report_scalar(info, &format!("{}_merge: {message}", S::NAME));
+                            return;
+                        }
+                    }
+                }
+            };
+            let Some(merged) = merged else {
+                return;
+            };
+            write_blob(output, row, &merged.to_blob());
+        }
+    }
+}
+
+/// Imports the states of the single `BLOB` column of `input` and passes each
+/// one to `write` with its row; `NULL` states produce `NULL` through
+/// `writer`. Stops at the first malformed blob, which is reported as an error
+/// of `<S::NAME>_finalize`.
+///
+/// # Safety
+///
+/// `info`, `input` and `output` must be the arguments of the running scalar
+/// function, whose only parameter is a `BLOB`.
+pub(crate) unsafe fn finalize_rows<S: ExportedState>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+    mut write: impl FnMut(&mut VectorWriter, usize, S),
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let states = BlobColumn::new(input, 0);
+        let mut writer = VectorWriter::new(output);
+
+        for row in 0..row_count {
+            let Some(bytes) = states.read(row) else {
+                writer.set_null(row);
+                continue;
+            };
+            let Some(state) = load::<S>(info, "finalize", bytes) else {
+                return;
+            };
+            write(&mut writer, row, state);
+        }
+    }
+}
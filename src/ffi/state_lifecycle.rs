@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Aggregate state lifecycle: allocation, initialization and destruction.
+//!
+//! `DuckDB` allocates a fixed-size buffer per aggregate state, initializes it
+//! with the registered `init` callback and hands every initialized state to
+//! the registered destructor exactly once — after finalize, but also when
+//! the query fails, is cancelled through `duckdb_interrupt`, or hits a
+//! reported error before finalize runs. Every state in this crate owns heap
+//! data (event buffers, pattern caches, strings), so an aggregate registered
+//! without a destructor leaks on every one of those paths.
+//!
+//! Two helpers keep the three callbacks together:
+//!
+//! - [`with_lifecycle!`] sets the size, init and destroy callbacks of
+//!   [`FfiState`](quack_rs::aggregate::FfiState) on a `quack-rs` aggregate
+//!   builder in one step, so a registration cannot set one without the
+//!   others. `FfiState`'s destroy callback drops the state in place.
+//! - [`BoxedState`] provides the same three callbacks for the raw
+//!   `libduckdb-sys` registration of [`sessionize`](super::sessionize), which
+//!   `quack-rs` builders cannot express.
+
+use libduckdb_sys::*;
+
+/// Sets the state size, init and destroy callbacks of
+/// [`FfiState<$state>`](quack_rs::aggregate::FfiState) on an aggregate
+/// builder.
+///
+/// Works on both `AggregateFunctionBuilder` and the overload builders of
+/// `AggregateFunctionSetBuilder`:
+///
+/// ```ignore
+/// with_lifecycle!(builder.param(TypeId::Timestamp), MyState)
+///     .update(state_update)
+///     .combine(state_combine)
+///     .finalize(state_finalize)
+/// ```
+macro_rules! with_lifecycle {
+    ($builder:expr, $state:ty) => {
+        $builder
+            .state_size(quack_rs::aggregate::FfiState::<$state>::size_callback)
+            .init(quack_rs::aggregate::FfiState::<$state>::init_callback)
+            .destructor(quack_rs::aggregate::FfiState::<$state>::destroy_callback)
+    };
+}
+
+pub(crate) use with_lifecycle;
+
+/// Aggregate state buffer holding a pointer to a heap-allocated `S`.
+///
+/// The raw-FFI counterpart of [`FfiState`](quack_rs::aggregate::FfiState):
+/// register [`size_callback`](Self::size_callback),
+/// [`init_callback`](Self::init_callback) and
+/// [`destroy_callback`](Self::destroy_callback) with
+/// `duckdb_aggregate_function_set_functions` and
+/// `duckdb_aggregate_function_set_destructor`, and reach the state through
+/// [`get`](Self::get) and [`get_mut`](Self::get_mut).
+#[repr(C)]
+pub struct BoxedState<S> {
+    inner: *mut S,
+}
+
+impl<S: Default> BoxedState<S> {
+    /// Returns the byte size of the state buffer.
+    ///
+    /// # Safety
+    ///
+    /// Pure computation; safe to call with any `info`.
+    pub unsafe extern "C" fn size_callback(_info: duckdb_function_info) -> idx_t {
+        std::mem::size_of::<Self>() as idx_t
+    }
+
+    /// Allocates a default `S` and stores its pointer in `state`.
+    ///
+    /// # Safety
+    ///
+    /// `state` must point to a buffer of at least
+    /// [`size_callback`](Self::size_callback) bytes.
+    pub unsafe extern "C" fn init_callback(
+        _info: duckdb_function_info,
+        state: duckdb_aggregate_state,
+    ) {
+        unsafe {
+            (state as *mut Self).write(Self {
+                inner: Box::into_raw(Box::default()),
+            });
+        }
+    }
+
+    /// Drops the `S` of each of the `count` states and nulls its pointer, so
+    /// destroying a state twice is a no-op.
+    ///
+    /// # Safety
+    ///
+    /// `states` must point to `count` state pointers, each initialized by
+    /// [`init_callback`](Self::init_callback).
+    pub unsafe extern "C" fn destroy_callback(states: *mut duckdb_aggregate_state, count: idx_t) {
+        unsafe {
+            for i in 0..count as usize {
+                let boxed = &mut *(*states.add(i) as *mut Self);
+                if !boxed.inner.is_null() {
+                    drop(Box::from_raw(boxed.inner));
+                    boxed.inner = std::ptr::null_mut();
+                }
+            }
+        }
+    }
+
+    /// Returns the state behind `state`, or `None` if it was destroyed.
+    ///
+    /// # Safety
+    ///
+    /// `state` must have been initialized by
+    /// [`init_callback`](Self::init_callback), and the returned reference
+    /// must not outlive the callback it was obtained in.
+    pub unsafe fn get<'a>(state: duckdb_aggregate_state) -> Option<&'a S> {
+        unsafe { (*(state as *const Self)).inner.as_ref() }
+    }
+
+    /// Returns the state behind `state` mutably, or `None` if it was
+    /// destroyed.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`get`](Self::get), and no other reference to the state may
+    /// be live.
+    pub unsafe fn get_mut<'a>(state: duckdb_aggregate_state) -> Option<&'a mut S> {
+        unsafe { (*(state as *mut Self)).inner.as_mut() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Default)]
+    struct Tracked {
+        events: Vec<i64>,
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_boxed_state_lifecycle() {
+        let mut buffers: [MaybeUninit<BoxedState<Tracked>>; 3] =
+            std::array::from_fn(|_| MaybeUninit::uninit());
+        let mut states: Vec<duckdb_aggregate_state> =
+            buffers.iter_mut().map(|b| b.as_mut_ptr().cast()).collect();
+        unsafe {
+            assert_eq!(
+                BoxedState::<Tracked>::size_callback(std::ptr::null_mut()) as usize,
+                std::mem::size_of::<*mut Tracked>()
+            );
+            for &state in &states {
+                BoxedState::<Tracked>::init_callback(std::ptr::null_mut(), state);
+            }
+            // States abandoned mid-update, as by an interrupted query, are
+            // dropped with their buffers.
+            BoxedState::<Tracked>::get_mut(states[0])
+                .unwrap()
+                .events
+                .extend(0..1000);
+            assert_eq!(
+                BoxedState::<Tracked>::get(states[0]).unwrap().events.len(),
+                1000
+            );
+
+            let before = DROPS.load(Ordering::SeqCst);
+            BoxedState::<Tracked>::destroy_callback(states.as_mut_ptr(), 3);
+            assert_eq!(DROPS.load(Ordering::SeqCst), before + 3);
+            assert!(BoxedState::<Tracked>::get(states[1]).is_none());
+
+            // A second destroy is a no-op.
+            BoxedState::<Tracked>::destroy_callback(states.as_mut_ptr(), 3);
+            assert_eq!(DROPS.load(Ordering::SeqCst), before + 3);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `strict_condition` scalar function.
+//!
+//! Every aggregate reads a `NULL` condition as `false`. The C extension API
+//! gives aggregates no named parameters and no bind callback, so a
+//! `null_condition => 'error'` option cannot be passed to them; wrapping a
+//! condition argument in `strict_condition` fails the query on a `NULL`
+//! instead, before the aggregate sees it. Registration uses the raw
+//! `libduckdb-sys` scalar function API, like
+//! [`parse_duration`](super::parse_duration), with a `BOOLEAN` and an
+//! `INTEGER` overload like the condition parameters. `NULL` inputs must reach
+//! the callback, so the function is registered with special `NULL` handling.
+
+use crate::ffi::conditions::ConditionKind;
+use crate::ffi::error::report_scalar;
+use libduckdb_sys::*;
+use quack_rs::vector::{VectorReader, VectorWriter};
+
+/// Signature of a scalar function callback.
+type ScalarFunction = unsafe extern "C" fn(duckdb_function_info, duckdb_data_chunk, duckdb_vector);
+
+/// Error raised for a `NULL` condition, with the two explicit alternatives.
+const NULL_CONDITION_MESSAGE: &str = "strict_condition: condition is NULL (use coalesce(cond, false) to count it as false, or FILTER (WHERE cond IS NOT NULL) to skip the event)";
+
+/// Registers the `strict_condition` function with `DuckDB`.
+///
+/// Signatures:
+/// - `strict_condition(BOOLEAN) -> BOOLEAN`
+/// - `strict_condition(INTEGER) -> BOOLEAN`
+///
+/// Returns the condition coerced as the aggregates coerce it (`INTEGER` is
+/// `true` when non-zero) and fails the query on `NULL`.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_strict_condition(con: duckdb_connection) {
+    unsafe {
+        let set = duckdb_create_scalar_function_set(c"strict_condition".as_ptr());
+        let overloads: [(duckdb_type, ScalarFunction); 2] = [
+            (DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN, strict_boolean_function),
+            (DUCKDB_TYPE_DUCKDB_TYPE_INTEGER, strict_integer_function),
+        ];
+        for (param_type, function) in overloads {
+            let func = duckdb_create_scalar_function();
+            duckdb_scalar_function_set_name(func, c"strict_condition".as_ptr());
+
+            let mut param = duckdb_create_logical_type(param_type);
+            duckdb_scalar_function_add_parameter(func, param);
+            duckdb_destroy_logical_type(&mut param);
+
+            let mut boolean = duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN);
+            duckdb_scalar_function_set_return_type(func, boolean);
+            duckdb_destroy_logical_type(&mut boolean);
+
+            duckdb_scalar_function_set_special_handling(func);
+            duckdb_scalar_function_set_function(func, Some(function));
+            duckdb_add_scalar_function_to_set(set, func);
+            duckdb_destroy_scalar_function(&mut { func });
+        }
+
+        let result = duckdb_register_scalar_function_set(con, set);
+        if result != DuckDBSuccess {
+            eprintln!("behavioral: failed to register strict_condition function");
+        }
+
+        duckdb_destroy_scalar_function_set(&mut { set });
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one BOOLEAN column as
+// registered. `output` is a valid BOOLEAN vector sized for the chunk.
+unsafe extern "C" fn strict_boolean_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe { evaluate(info, input, output, ConditionKind::Boolean) }
+}
+
+// SAFETY: as `strict_boolean_function`, with an INTEGER column.
+unsafe extern "C" fn strict_integer_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe { evaluate(info, input, output, ConditionKind::Integer) }
+}
+
+/// Copies the coerced condition of every row of `input` to `output`,
+/// reporting an error at the first `NULL`.
+unsafe fn evaluate(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+    kind: ConditionKind,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let reader = VectorReader::new(input, 0);
+        let mut writer = VectorWriter::new(output);
+
+        for row in 0..row_count {
+            if !reader.is_valid(row) {
+                report_scalar(info, NULL_CONDITION_MESSAGE);
+                return;
+            }
+            let value = match kind {
+                ConditionKind::Boolean => reader.read_bool(row),
+                ConditionKind::Integer => reader.read_i32(row) != 0,
+            };
+            writer.write_bool(row, value);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `first_touch` and `last_touch` aggregate
+//! functions.
+//!
+//! One function set is registered per function and channel column type
+//! (`VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID`), as for
+//! [`transition_matrix`](super::transition_matrix), with [`NodeValue`]
+//! reading and writing the channels. `LAST` selects the function; both share
+//! [`TouchAttributionState`] and the update and combine callbacks.
+
+use crate::common::timestamp::interval_to_micros;
+use crate::ffi::conditions::{ConditionReader, CONDITION_TYPES};
+use crate::ffi::error::{interval_message, negative_message, report};
+use crate::ffi::sequence_next_node::NodeValue;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::touch_attribution::TouchAttributionState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::{VectorReader, VectorWriter};
+use std::sync::Arc;
+
+impl<V: NodeValue + Ord> quack_rs::aggregate::AggregateState for TouchAttributionState<V> {}
+
+/// Registers the `first_touch` and `last_touch` functions with `DuckDB`.
+///
+/// Signatures, for `T` in `VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID` and
+/// `converted` as `BOOLEAN` or `INTEGER`:
+///
+/// 1. `first_touch(TIMESTAMP, T, converted) -> T`
+/// 2. With a lookback: `first_touch(TIMESTAMP, T, converted, INTERVAL) -> T`
+///
+/// and the same for `last_touch`. See
+/// [`TouchAttributionState::finalize_first`] and
+/// [`TouchAttributionState::finalize_last`].
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_touch_attribution(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    unsafe {
+        register_function::<false>(con)?;
+        register_function::<true>(con)
+    }
+}
+
+/// Returns the SQL name of the function selected by `LAST`.
+const fn function_name<const LAST: bool>() -> &'static str {
+    if LAST {
+        "last_touch"
+    } else {
+        "first_touch"
+    }
+}
+
+/// Registers every channel type of the function selected by `LAST`.
+unsafe fn register_function<const LAST: bool>(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    unsafe {
+        register_for::<Arc<str>, LAST>(con, TypeId::Varchar)?;
+        register_for::<i64, LAST>(con, TypeId::BigInt)?;
+        register_for::<i32, LAST>(con, TypeId::Integer)?;
+        register_for::<i32, LAST>(con, TypeId::Date)?;
+        register_for::<i128, LAST>(con, TypeId::Uuid)
+    }
+}
+
+/// Registers the set of the function selected by `LAST` whose channel column
+/// is `channel_type`, stored as `V`.
+unsafe fn register_for<V: NodeValue + Ord, const LAST: bool>(
+    con: &impl quack_rs::connection::Registrar,
+    channel_type: TypeId,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder =
+        AggregateFunctionSetBuilder::new(function_name::<LAST>()).returns(channel_type);
+    for cond_type in CONDITION_TYPES {
+        // (TIMESTAMP, T, converted) and (TIMESTAMP, T, converted, INTERVAL lookback)
+        builder = builder.overloads(3..=4, move |n, builder| {
+            let mut b = builder
+                .param(TypeId::Timestamp) // timestamp
+                .param(channel_type) // channel
+                .param(cond_type); // converted
+            if n == 4 {
+                b = b.param(TypeId::Interval); // lookback
+            }
+            with_lifecycle!(b, TouchAttributionState<V>)
+                .update(state_update::<V, LAST>)
+                .combine(state_combine::<V>)
+                .finalize(state_finalize::<V, LAST>)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (TIMESTAMP, V,
+// converted [, INTERVAL]) as registered for `V`. `states` points to
+// `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update<V: NodeValue + Ord, const LAST: bool>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+
+        let ts_reader = VectorReader::new(input, 0);
+        let channel_reader = VectorReader::new(input, 1);
+        let converted_reader = ConditionReader::new(input, 2);
+        let lookback_reader = (col_count > 3).then(|| VectorReader::new(input, 3));
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<TouchAttributionState<V>>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            if let Some(lookback_reader) = &lookback_reader {
+                if state.lookback_us.is_none() && lookback_reader.is_valid(i) {
+                    let iv = lookback_reader.read_interval(i);
+                    let Some(lookback_us) = interval_to_micros(iv.months, iv.days, iv.micros)
+                    else {
+                        report(
+                            info,
+                            &interval_message(
+                                function_name::<LAST>(),
+                                iv.months,
+                                iv.days,
+                                iv.micros,
+                            ),
+                        );
+                        return;
+                    };
+                    if lookback_us < 0 {
+                        report(
+                            info,
+                            &negative_message(function_name::<LAST>(), "lookback", lookback_us),
+                        );
+                        return;
+                    }
+                    state.lookback_us = Some(lookback_us);
+                }
+            }
+
+            // Rows without a timestamp are neither touchpoints nor conversions
+            if !ts_reader.is_valid(i) {
+                continue;
+            }
+
+            let channel = channel_reader
+                .is_valid(i)
+                .then(|| V::read(&channel_reader, i));
+            state.update(ts_reader.read_i64(i), channel, converted_reader.read(i));
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine<V: NodeValue + Ord>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<TouchAttributionState<V>>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<TouchAttributionState<V>>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB vector of the channel type `V`. NULL when the group has no
+// conversion or no credited touchpoint.
+unsafe extern "C" fn state_finalize<V: NodeValue + Ord, const LAST: bool>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<TouchAttributionState<V>>::with_state(*source.add(i))
+            else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            let channel = if LAST {
+                state.finalize_last()
+            } else {
+                state.finalize_first()
+            };
+            match channel {
+                Some(channel) => channel.write(&mut writer, idx),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<TouchAttributionState<Arc<str>>>::new();
+        source.update(|s| {
+            s.lookback_us = Some(1_000_000);
+            s.update(1_000_000, Some(Arc::from("search")), false);
+            s.update(2_500_000, Some(Arc::from("email")), false);
+            s.update(3_000_000, None, true);
+        });
+        let mut target = AggregateTestHarness::<TouchAttributionState<Arc<str>>>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let state = target.finalize();
+        assert_eq!(state.lookback_us, Some(1_000_000));
+        assert_eq!(state.finalize_first(), Some(Arc::from("email")));
+    }
+}
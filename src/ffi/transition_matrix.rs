@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `transition_matrix` aggregate function.
+//!
+//! One function set is registered per state column type (`VARCHAR`,
+//! `BIGINT`, `INTEGER`, `DATE`, `UUID`), as for
+//! [`group_array_sorted_by_ts`](super::group_array_sorted), with
+//! [`NodeValue`] reading and writing the states. Each set returns
+//! `LIST(STRUCT(from_state T, to_state T, count BIGINT))`, written like
+//! `sequence_next_node_hist` through [`ListVector`] and [`StructVector`]
+//! field writers. The C API has no `MAP` return type; the flat edge list
+//! converts to nested maps in SQL.
+
+use crate::ffi::error::{limit_message, report};
+use crate::ffi::sequence_next_node::NodeValue;
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::transition_matrix::TransitionMatrixState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::{ListVector, StructVector};
+use quack_rs::vector::VectorReader;
+use std::sync::Arc;
+
+impl<V: NodeValue + Ord> quack_rs::aggregate::AggregateState for TransitionMatrixState<V> {}
+
+/// Registers the `transition_matrix` function with `DuckDB`.
+///
+/// Signatures, for `T` in `VARCHAR`, `BIGINT`, `INTEGER`, `DATE`, `UUID`:
+///
+/// 1. `transition_matrix(TIMESTAMP, T) -> LIST(STRUCT(from_state T, to_state T, count BIGINT))`
+/// 2. With a state limit: `transition_matrix(TIMESTAMP, T, BIGINT) -> LIST(STRUCT(...))`
+///
+/// Parameters:
+/// - `timestamp`: Event timestamp, the order of the path
+/// - `state`: State of the event (page, screen, channel, ...)
+/// - `n_limit` (optional): Number of most frequent states kept by value (see
+///   [`TransitionMatrixState::max_states`])
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_transition_matrix(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    unsafe {
+        register_for::<Arc<str>>(con, TypeId::Varchar)?;
+        register_for::<i64>(con, TypeId::BigInt)?;
+        register_for::<i32>(con, TypeId::Integer)?;
+        register_for::<i32>(con, TypeId::Date)?;
+        register_for::<i128>(con, TypeId::Uuid)
+    }
+}
+
+/// Registers the `transition_matrix` set whose state column is
+/// `state_type`, stored as `V`.
+unsafe fn register_for<V: NodeValue + Ord>(
+    con: &impl quack_rs::connection::Registrar,
+    state_type: TypeId,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let entry = LogicalType::struct_type(&[
+        ("from_state", state_type),
+        ("to_state", state_type),
+        ("count", TypeId::BigInt),
+    ]);
+    let builder = AggregateFunctionSetBuilder::new("transition_matrix")
+        .returns_logical(LogicalType::list_from_logical(&entry))
+        // (TIMESTAMP, T) and (TIMESTAMP, T, BIGINT n_limit)
+        .overloads(2..=3, move |n, builder| {
+            let mut b = builder
+                .param(TypeId::Timestamp) // timestamp
+                .param(state_type); // state
+            if n == 3 {
+                b = b.param(TypeId::BigInt); // n_limit
+            }
+            with_lifecycle!(b, TransitionMatrixState<V>)
+                .update(state_update::<V>)
+                .combine(state_combine::<V>)
+                .finalize(state_finalize::<V>)
+        });
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (TIMESTAMP, V
+// [, BIGINT]) as registered for `V`. `states` points to `row_count` aggregate
+// state pointers.
+unsafe extern "C" fn state_update<V: NodeValue + Ord>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+
+        let ts_reader = VectorReader::new(input, 0);
+        let state_reader = VectorReader::new(input, 1);
+        let limit_reader = (col_count > 2).then(|| VectorReader::new(input, 2));
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<TransitionMatrixState<V>>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+
+            if let Some(limit_reader) = &limit_reader {
+                if state.max_states == 0 && limit_reader.is_valid(i) {
+                    let n_limit = limit_reader.read_i64(i);
+                    if n_limit <= 0 {
+                        report(
+                            info,
+                            &limit_message("transition_matrix", "n_limit", n_limit),
+                        );
+                        return;
+                    }
+                    state.max_states = usize::try_from(n_limit).unwrap_or(usize::MAX);
+                }
+            }
+
+            // Rows without a timestamp or a state are not part of the path
+            if !ts_reader.is_valid(i) || !state_reader.is_valid(i) {
+                continue;
+            }
+
+            state.update(ts_reader.read_i64(i), V::read(&state_reader, i));
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine<V: NodeValue + Ord>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<TransitionMatrixState<V>>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<TransitionMatrixState<V>>::with_state_mut(*target.add(i))
+            else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB LIST(STRUCT(from_state V, to_state V, count BIGINT)) vector.
+// Empty list when the group has fewer than two events.
+unsafe extern "C" fn state_finalize<V: NodeValue + Ord>(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut list_offset = ListVector::get_size(result) as u64;
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<TransitionMatrixState<V>>::with_state_mut(*source.add(i))
+            else {
+                // Empty list for null state
+                ListVector::set_entry(result, idx, list_offset, 0);
+                continue;
+            };
+
+            let transitions = state.finalize();
+            let entry_count = transitions.len() as u64;
+
+            // Reserve space in the list child vector before taking field writers,
+            // since reserve may reallocate the child's data buffers.
+            ListVector::reserve(result, (list_offset + entry_count) as usize);
+
+            let child = ListVector::get_child(result);
+            let mut from_writer = StructVector::field_writer(child, 0);
+            let mut to_writer = StructVector::field_writer(child, 1);
+            let mut count_writer = StructVector::field_writer(child, 2);
+            for (j, (from, to, n)) in transitions.iter().enumerate() {
+                let pos = list_offset as usize + j;
+                match from {
+                    Some(from) => from.write(&mut from_writer, pos),
+                    None => from_writer.set_null(pos),
+                }
+                match to {
+                    Some(to) => to.write(&mut to_writer, pos),
+                    None => to_writer.set_null(pos),
+                }
+                count_writer.write_i64(pos, *n);
+            }
+
+            ListVector::set_entry(result, idx, list_offset, entry_count);
+
+            list_offset += entry_count;
+            ListVector::set_size(result, list_offset as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<TransitionMatrixState>::new();
+        source.update(|s| {
+            s.max_states = 1;
+            s.update(2_000_000, Arc::from("product"));
+            s.update(1_000_000, Arc::from("home"));
+            s.update(3_000_000, Arc::from("home"));
+        });
+        let mut target = AggregateTestHarness::<TransitionMatrixState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert_eq!(state.max_states, 1);
+        assert_eq!(
+            state.finalize(),
+            vec![
+                (Some(Arc::from("home")), None, 1),
+                (None, Some(Arc::from("home")), 1)
+            ]
+        );
+    }
+}
@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: MIT
 // Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
 
-//! FFI registration for the `window_funnel` aggregate function.
+//! FFI registration for the `window_funnel` aggregate function and its
+//! `window_funnel_mask`, `window_funnel_flags`, `window_funnel_entry_ts`,
+//! `conversion_rate` and `time_to_convert` variants.
 //!
 //! Uses [`quack_rs::aggregate::AggregateFunctionSetBuilder`] for function set
 //! registration, [`quack_rs::aggregate::FfiState`] for safe state management,
@@ -9,27 +11,48 @@
 
 use crate::common::event::Event;
 use crate::common::timestamp::interval_to_micros;
-use crate::window_funnel::{FunnelMode, WindowFunnelState};
+use crate::ffi::conditions::{
+    chunk_events, chunk_events_with_seq, CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS,
+};
+use crate::ffi::error::{
+    interval_message, min_gaps_message, mode_message, overflow_message, range_message, report,
+};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::window_funnel::{FunnelMode, WindowFunnelState, UNBOUNDED_WINDOW_US};
 use libduckdb_sys::*;
 use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
-use quack_rs::types::TypeId;
+use quack_rs::interval::DuckInterval;
+use quack_rs::types::{LogicalType, TypeId};
 use quack_rs::vector::{VectorReader, VectorWriter};
 
-/// Minimum number of boolean condition parameters for `window_funnel`.
-const MIN_CONDITIONS: usize = 2;
-/// Maximum number of boolean condition parameters for `window_funnel`.
-const MAX_CONDITIONS: usize = 32;
+/// Maximum number of condition parameters for `window_funnel_mask`, whose
+/// `UINTEGER` result has one bit per step, and of steps in the 32-bit
+/// condition bitmask passed to `window_funnel_flags`.
+const MAX_MASK_CONDITIONS: usize = 32;
 
 impl quack_rs::aggregate::AggregateState for WindowFunnelState {}
 
 /// Registers the `window_funnel` function with `DuckDB` as a function set
-/// with overloads for two signatures:
+/// with overloads for six signatures:
 ///
 /// 1. Without mode: `window_funnel(INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
 /// 2. With mode: `window_funnel(INTERVAL, VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
+/// 3. With mode and `dedup_within`:
+///    `window_funnel(INTERVAL, VARCHAR, INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
+/// 4. With sequence number: `window_funnel(INTERVAL, TIMESTAMP, BIGINT, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
+/// 5. With mode and sequence number:
+///    `window_funnel(INTERVAL, VARCHAR, TIMESTAMP, BIGINT, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
+/// 6. With mode and minimum step gaps:
+///    `window_funnel(INTERVAL, VARCHAR, INTERVAL[], TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> INTEGER`
 ///
 /// The VARCHAR parameter accepts a comma-separated list of mode names
-/// (e.g., `'strict_increase, strict_once'`).
+/// (e.g., `'strict_increase, strict_once'`). The second INTERVAL collapses
+/// events with identical conditions within that interval (see
+/// [`WindowFunnelState::dedup_within_us`]); an `INTERVAL[]` in its place sets
+/// the minimum time between consecutive steps (see
+/// [`WindowFunnelState::min_gaps_us`]). The BIGINT orders events with the
+/// same timestamp (see [`WindowFunnelState::seqs`]). Conditions may be
+/// `BOOLEAN` or `INTEGER` (see [`crate::ffi::conditions`]).
 ///
 /// # Safety
 ///
@@ -41,91 +64,534 @@ impl quack_rs::aggregate::AggregateState for WindowFunnelState {}
 pub unsafe fn register_window_funnel(
     con: &impl quack_rs::connection::Registrar,
 ) -> Result<(), quack_rs::error::ExtensionError> {
-    // Register both overload groups under the same function set name.
-    // DuckDB distinguishes them by parameter types.
-    let builder = AggregateFunctionSetBuilder::new("window_funnel")
-        .returns(TypeId::Integer)
-        // Group 1: WITHOUT mode parameter: (INTERVAL, TIMESTAMP, BOOL×N)
-        .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, |n, builder| {
-            let mut b = builder.param(TypeId::Interval).param(TypeId::Timestamp);
-            for _ in 0..n {
-                b = b.param(TypeId::Boolean);
-            }
-            b.state_size(FfiState::<WindowFunnelState>::size_callback)
-                .init(FfiState::<WindowFunnelState>::init_callback)
-                .update(state_update)
+    // Register all overload groups under the same function set name, once per
+    // condition column type. DuckDB distinguishes them by parameter types.
+    let mut builder = AggregateFunctionSetBuilder::new("window_funnel").returns(TypeId::Integer);
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            // Group 1: WITHOUT mode parameter: (INTERVAL, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder.param(TypeId::Interval).param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            })
+            // Group 2: WITH mode parameter: (INTERVAL, VARCHAR, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_mode)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            })
+            // Group 3: WITH mode and dedup_within: (INTERVAL, VARCHAR, INTERVAL, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Interval)
+                    .param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_dedup)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            })
+            // Group 4: WITH sequence number: (INTERVAL, TIMESTAMP, BIGINT, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Timestamp)
+                    .param(TypeId::BigInt);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_seq)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            })
+            // Group 5: WITH mode and sequence number: (INTERVAL, VARCHAR, TIMESTAMP, BIGINT, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp)
+                    .param(TypeId::BigInt);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_mode_and_seq)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            })
+            // Group 6: WITH mode and min gaps: (INTERVAL, VARCHAR, INTERVAL[], TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param_logical(LogicalType::list(TypeId::Interval))
+                    .param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_min_gaps)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `window_funnel_flags` function with `DuckDB` as a function
+/// set with one overload per bitmask type:
+///
+/// `window_funnel_flags(INTERVAL, TIMESTAMP, INTEGER | UINTEGER, INTEGER) -> INTEGER`
+///
+/// A `window_funnel` without modes whose conditions come from one bitmask
+/// per event, with bit `k` set when the event matches step `k + 1`, followed
+/// by the number of steps (see [`step_mask`]). It has its own name because
+/// `window_funnel(INTERVAL, TIMESTAMP, INTEGER, INTEGER)` is the two-step
+/// funnel over `INTEGER` conditions.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_window_funnel_flags(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder =
+        AggregateFunctionSetBuilder::new("window_funnel_flags").returns(TypeId::Integer);
+    for mask_type in [TypeId::Integer, TypeId::UInteger] {
+        // (INTERVAL, TIMESTAMP, INTEGER | UINTEGER mask, INTEGER num_steps)
+        builder = builder.overloads(1..=1, move |_, builder| {
+            let b = builder
+                .param(TypeId::Interval)
+                .param(TypeId::Timestamp)
+                .param(mask_type)
+                .param(TypeId::Integer);
+            with_lifecycle!(b, WindowFunnelState)
+                .update(state_update_with_step_mask)
                 .combine(state_combine)
                 .finalize(state_finalize)
-                .destructor(FfiState::<WindowFunnelState>::destroy_callback)
-        })
-        // Group 2: WITH mode parameter: (INTERVAL, VARCHAR, TIMESTAMP, BOOL×N)
-        .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, |n, builder| {
-            let mut b = builder
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `window_funnel_mask` function with `DuckDB` as a function set
+/// with overloads for two signatures:
+///
+/// 1. Without mode: `window_funnel_mask(INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> UINTEGER`
+/// 2. With mode: `window_funnel_mask(INTERVAL, VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> UINTEGER`
+///
+/// Arguments match `window_funnel`; the result is the bitmask of steps
+/// reached on the best chain (see [`WindowFunnelState::finalize_mask`]).
+/// Up to 32 conditions are accepted.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_window_funnel_mask(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder =
+        AggregateFunctionSetBuilder::new("window_funnel_mask").returns(TypeId::UInteger);
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            // Group 1: WITHOUT mode parameter: (INTERVAL, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_MASK_CONDITIONS, move |n, builder| {
+                let mut b = builder.param(TypeId::Interval).param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update)
+                    .combine(state_combine)
+                    .finalize(state_finalize_mask)
+            })
+            // Group 2: WITH mode parameter: (INTERVAL, VARCHAR, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_MASK_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_mode)
+                    .combine(state_combine)
+                    .finalize(state_finalize_mask)
+            });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `window_funnel_entry_ts` function with `DuckDB` as a
+/// function set with overloads for two signatures:
+///
+/// 1. Without mode: `window_funnel_entry_ts(INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> TIMESTAMP`
+/// 2. With mode: `window_funnel_entry_ts(INTERVAL, VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> TIMESTAMP`
+///
+/// Arguments match `window_funnel`; the result is the timestamp of the entry
+/// event of the best chain (see [`WindowFunnelState::finalize_entry_ts`]), or
+/// `NULL` when no event matched the first condition.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_window_funnel_entry_ts(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder =
+        AggregateFunctionSetBuilder::new("window_funnel_entry_ts").returns(TypeId::Timestamp);
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            // Group 1: WITHOUT mode parameter: (INTERVAL, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder.param(TypeId::Interval).param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update)
+                    .combine(state_combine)
+                    .finalize(state_finalize_entry_ts)
+            })
+            // Group 2: WITH mode parameter: (INTERVAL, VARCHAR, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_mode)
+                    .combine(state_combine)
+                    .finalize(state_finalize_entry_ts)
+            });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `conversion_rate` function with `DuckDB`.
+///
+/// Signature: `conversion_rate(INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN) -> DOUBLE`
+///
+/// A two-step `window_funnel` without modes, finalized by
+/// [`WindowFunnelState::finalize_conversion`]: `1.0` when the target followed
+/// an entry within the window, `0.0` when it did not and `NULL` when there was
+/// no entry. One overload is registered per condition column type.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_conversion_rate(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("conversion_rate").returns(TypeId::Double);
+    for cond_type in CONDITION_TYPES {
+        // (INTERVAL, TIMESTAMP, COND entry, COND target)
+        builder = builder.overloads(2..=2, move |_, builder| {
+            let b = builder
                 .param(TypeId::Interval)
-                .param(TypeId::Varchar)
-                .param(TypeId::Timestamp);
-            for _ in 0..n {
-                b = b.param(TypeId::Boolean);
-            }
-            b.state_size(FfiState::<WindowFunnelState>::size_callback)
-                .init(FfiState::<WindowFunnelState>::init_callback)
-                .update(state_update_with_mode)
+                .param(TypeId::Timestamp)
+                .param(cond_type)
+                .param(cond_type);
+            with_lifecycle!(b, WindowFunnelState)
+                .update(state_update)
                 .combine(state_combine)
-                .finalize(state_finalize)
-                .destructor(FfiState::<WindowFunnelState>::destroy_callback)
+                .finalize(state_finalize_conversion)
+        });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `time_to_convert` function with `DuckDB`.
+///
+/// Signature: `time_to_convert(INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN) -> INTERVAL`
+///
+/// A two-step `window_funnel` without modes, like `conversion_rate`, finalized
+/// by [`WindowFunnelState::finalize_time_to_convert`]: the time from the entry
+/// to the target event of the best chain, or `NULL` when it did not convert.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_time_to_convert(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("time_to_convert").returns(TypeId::Interval);
+    for cond_type in CONDITION_TYPES {
+        // (INTERVAL, TIMESTAMP, COND entry, COND target)
+        builder = builder.overloads(2..=2, move |_, builder| {
+            let b = builder
+                .param(TypeId::Interval)
+                .param(TypeId::Timestamp)
+                .param(cond_type)
+                .param(cond_type);
+            with_lifecycle!(b, WindowFunnelState)
+                .update(state_update)
+                .combine(state_combine)
+                .finalize(state_finalize_time_to_convert)
         });
+    }
     unsafe { con.register_aggregate_set(builder) }
 }
 
 // SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL, TIMESTAMP,
 // BOOLEAN...) as registered. `states` points to `row_count` aggregate state pointers.
-unsafe extern "C" fn state_update(
-    _info: duckdb_function_info,
+pub(crate) unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
 ) {
     // No mode parameter: INTERVAL(0), TIMESTAMP(1), BOOLEAN(2..N)
     unsafe {
-        update_impl(input, states, false);
+        update_impl(info, input, states, false, None, false);
     }
 }
 
 // SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL, VARCHAR,
 // TIMESTAMP, BOOLEAN...) as registered. The VARCHAR at column 1 contains the mode
 // string. `states` points to `row_count` aggregate state pointers.
-unsafe extern "C" fn state_update_with_mode(
-    _info: duckdb_function_info,
+pub(crate) unsafe extern "C" fn state_update_with_mode(
+    info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
 ) {
     // With mode parameter: INTERVAL(0), VARCHAR(1), TIMESTAMP(2), BOOLEAN(3..N)
     unsafe {
-        update_impl(input, states, true);
+        update_impl(info, input, states, true, None, false);
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL, VARCHAR,
+// INTERVAL, TIMESTAMP, BOOLEAN...) as registered. The INTERVAL at column 2 is
+// the dedup_within interval. `states` points to `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update_with_dedup(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    // With mode and dedup: INTERVAL(0), VARCHAR(1), INTERVAL(2), TIMESTAMP(3), BOOLEAN(4..N)
+    unsafe {
+        update_impl(
+            info,
+            input,
+            states,
+            true,
+            Some(ModeArgument::DedupWithin),
+            false,
+        );
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL, VARCHAR,
+// LIST(INTERVAL), TIMESTAMP, BOOLEAN...) as registered. The list at column 2
+// holds the minimum step gaps. `states` points to `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update_with_min_gaps(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    // With mode and min gaps: INTERVAL(0), VARCHAR(1), INTERVAL[](2), TIMESTAMP(3), BOOLEAN(4..N)
+    unsafe {
+        update_impl(
+            info,
+            input,
+            states,
+            true,
+            Some(ModeArgument::MinGaps),
+            false,
+        );
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL,
+// TIMESTAMP, BIGINT, BOOLEAN...) as registered. The BIGINT at column 2 is the
+// sequence number. `states` points to `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update_with_seq(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    // With seq: INTERVAL(0), TIMESTAMP(1), BIGINT(2), BOOLEAN(3..N)
+    unsafe {
+        update_impl(info, input, states, false, None, true);
     }
 }
 
-/// Shared update implementation for both signatures.
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL, VARCHAR,
+// TIMESTAMP, BIGINT, BOOLEAN...) as registered. `states` points to `row_count`
+// aggregate state pointers.
+unsafe extern "C" fn state_update_with_mode_and_seq(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    // With mode and seq: INTERVAL(0), VARCHAR(1), TIMESTAMP(2), BIGINT(3), BOOLEAN(4..N)
+    unsafe {
+        update_impl(info, input, states, true, None, true);
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL,
+// TIMESTAMP, INTEGER or UINTEGER, INTEGER) as registered. `states` points to
+// `row_count` aggregate state pointers.
+unsafe extern "C" fn state_update_with_step_mask(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    // With step mask: INTERVAL(0), TIMESTAMP(1), INTEGER or UINTEGER(2), INTEGER(3)
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+
+        let interval_reader = VectorReader::new(input, 0);
+        let ts_reader = VectorReader::new(input, 1);
+        let mask_reader = VectorReader::new(input, 2);
+        let steps_reader = VectorReader::new(input, 3);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<WindowFunnelState>::with_state_mut(*states.add(i)) else {
+                continue;
+            };
+
+            // Skip NULL timestamps and step counts
+            if !ts_reader.is_valid(i) || !steps_reader.is_valid(i) {
+                continue;
+            }
+
+            let num_steps = steps_reader.read_i32(i);
+            // UINTEGER and INTEGER share their 4-byte layout; reinterpret the bits.
+            let mask = if mask_reader.is_valid(i) {
+                mask_reader.read_i32(i) as u32
+            } else {
+                0
+            };
+            let Some(conditions) = step_mask(mask, num_steps) else {
+                let message = range_message(
+                    "window_funnel_flags",
+                    "num_steps",
+                    i64::from(num_steps),
+                    1,
+                    MAX_MASK_CONDITIONS as i64,
+                );
+                report(info, &message);
+                return;
+            };
+
+            if !configure(
+                info,
+                state,
+                &interval_reader,
+                None,
+                i,
+                "window_funnel_flags",
+            ) {
+                return;
+            }
+
+            state.update(
+                Event::new(ts_reader.read_i64(i), conditions),
+                num_steps as usize,
+            );
+        }
+    }
+}
+
+/// Condition bits of a 32-bit step bitmask for a funnel of `num_steps`
+/// steps: bit `k` is step `k + 1`, and bits at or above `num_steps` are
+/// cleared, so an event matching no step is not stored. `None` when
+/// `num_steps` is outside `1..=32`.
+fn step_mask(mask: u32, num_steps: i32) -> Option<u64> {
+    let steps = u32::try_from(num_steps)
+        .ok()
+        .filter(|steps| (1..=MAX_MASK_CONDITIONS as u32).contains(steps))?;
+    Some(u64::from(mask) & ((1u64 << steps) - 1))
+}
+
+/// Argument between the mode string and the timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModeArgument {
+    /// `INTERVAL` collapsing duplicate events.
+    DedupWithin,
+    /// `INTERVAL[]` of minimum gaps between consecutive steps.
+    MinGaps,
+}
+
+/// Shared update implementation for all signatures.
 ///
-/// When `has_mode` is true, column layout is:
+/// When `mode_arg` is set (implies `has_mode`), column layout is:
+///   \[0\] INTERVAL, \[1\] VARCHAR (mode), \[2\] INTERVAL (dedup) or INTERVAL\[\] (min gaps), \[3\] TIMESTAMP, \[4..N\] BOOLEAN
+/// When only `has_mode` is true, column layout is:
 ///   \[0\] INTERVAL, \[1\] VARCHAR (mode), \[2\] TIMESTAMP, \[3..N\] BOOLEAN
 /// When `has_mode` is false, column layout is:
 ///   \[0\] INTERVAL, \[1\] TIMESTAMP, \[2..N\] BOOLEAN
+/// When `has_seq` is true, a BIGINT sequence number follows the TIMESTAMP and
+/// the conditions start one column later.
+///
+/// An unrecognized mode name, a month-based window, dedup interval or gap, or
+/// a gap list not one shorter than the conditions is reported as a query
+/// error through [`crate::ffi::error`]. A `NULL` dedup interval disables
+/// deduplication, and a `NULL` gap list or gap sets no minimum.
 ///
 /// # Safety
 ///
-/// Requires valid `input` data chunk and `states` aggregate state pointers.
+/// Requires valid `info`, `input` data chunk and `states` aggregate state pointers.
 unsafe fn update_impl(
+    info: duckdb_function_info,
     input: duckdb_data_chunk,
     states: *mut duckdb_aggregate_state,
     has_mode: bool,
+    mode_arg: Option<ModeArgument>,
+    has_seq: bool,
 ) {
     unsafe {
         let row_count = duckdb_data_chunk_get_size(input) as usize;
         let col_count = duckdb_data_chunk_get_column_count(input) as usize;
 
-        // Column indices depend on whether mode parameter is present
-        let ts_col: usize = if has_mode { 2 } else { 1 };
-        let bool_start: usize = if has_mode { 3 } else { 2 };
+        // Column indices depend on whether mode and dedup or gap parameters are present
+        let ts_col: usize = 1 + usize::from(has_mode) + usize::from(mode_arg.is_some());
+        let bool_start: usize = ts_col + 1 + usize::from(has_seq);
         let num_conditions = col_count.saturating_sub(bool_start);
 
         // Vector 0: INTERVAL (window size) — read via VectorReader
@@ -138,13 +604,27 @@ unsafe fn update_impl(
             None
         };
 
-        // TIMESTAMP vector
-        let ts_reader = VectorReader::new(input, ts_col);
+        // Dedup INTERVAL vector (only with ModeArgument::DedupWithin)
+        let dedup_reader = if mode_arg == Some(ModeArgument::DedupWithin) {
+            Some(VectorReader::new(input, 2))
+        } else {
+            None
+        };
 
-        // BOOLEAN condition vectors
-        let cond_readers: Vec<VectorReader> = (bool_start..col_count)
-            .map(|c| VectorReader::new(input, c))
-            .collect();
+        // Min gaps INTERVAL[] vector (only with ModeArgument::MinGaps)
+        let gaps_reader = if mode_arg == Some(ModeArgument::MinGaps) {
+            Some(IntervalListReader::new(input, 2))
+        } else {
+            None
+        };
+
+        // TIMESTAMP, sequence and condition vectors (BOOLEAN or INTEGER), read
+        // column-at-a-time
+        let events = if has_seq {
+            chunk_events_with_seq(input, ts_col, ts_col + 1, bool_start, col_count)
+        } else {
+            chunk_events(input, ts_col, bool_start, col_count)
+        };
 
         for i in 0..row_count {
             let Some(state) = FfiState::<WindowFunnelState>::with_state_mut(*states.add(i)) else {
@@ -152,45 +632,183 @@ unsafe fn update_impl(
             };
 
             // Skip NULL timestamps
-            if !ts_reader.is_valid(i) {
+            let Some(event) = events.get(i) else {
                 continue;
+            };
+
+            if !configure(
+                info,
+                state,
+                &interval_reader,
+                mode_reader.as_ref(),
+                i,
+                "window_funnel",
+            ) {
+                return;
             }
 
-            // Read window size from interval using VectorReader
-            let iv = interval_reader.read_interval(i);
-            if let Some(window_us) = interval_to_micros(iv.months, iv.days, iv.micros) {
-                state.window_size_us = window_us;
+            if let Some(ref dedup_reader) = dedup_reader {
+                if dedup_reader.is_valid(i) {
+                    let iv = dedup_reader.read_interval(i);
+                    let Some(dedup_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                        let message =
+                            interval_message("window_funnel", iv.months, iv.days, iv.micros);
+                        report(info, &message);
+                        return;
+                    };
+                    state.dedup_within_us = dedup_us;
+                }
             }
 
-            // Parse mode string (once per state, from first row that has it)
-            if let Some(ref mode_reader) = mode_reader {
-                if state.mode.is_default() && mode_reader.is_valid(i) {
-                    let s = mode_reader.read_str(i);
-                    if let Ok(mode) = FunnelMode::parse_modes(s) {
-                        state.mode = mode;
+            if let Some(ref gaps_reader) = gaps_reader {
+                if state.min_gaps_us.is_empty() && gaps_reader.is_valid(i) {
+                    let gaps = match gaps_reader.read_micros(i) {
+                        Ok(gaps) => gaps,
+                        Err(iv) => {
+                            let message =
+                                interval_message("window_funnel", iv.months, iv.days, iv.micros);
+                            report(info, &message);
+                            return;
+                        }
+                    };
+                    if gaps.len() + 1 != num_conditions {
+                        let message = min_gaps_message("window_funnel", gaps.len(), num_conditions);
+                        report(info, &message);
+                        return;
                     }
+                    state.min_gaps_us = gaps;
                 }
             }
 
-            let timestamp = ts_reader.read_i64(i);
+            if has_seq {
+                state.update_with_seq(event, events.seq(i), num_conditions);
+            } else {
+                state.update(event, num_conditions);
+            }
+        }
+    }
+}
+
+/// Reads a `LIST(INTERVAL)` argument column as microseconds. [`VectorReader`]
+/// reads scalar columns only, so the list entries and the child vector are
+/// read with raw `libduckdb-sys` calls, like
+/// [`VarcharListReader`](crate::ffi::sequence::VarcharListReader).
+struct IntervalListReader {
+    reader: VectorReader,
+    entries: *const duckdb_list_entry,
+    intervals: *const duckdb_interval,
+    /// NULL when every element is valid.
+    validity: *mut u64,
+}
 
-            // Pack conditions into u32 bitmask (max 32 conditions from function set)
-            let mut bitmask: u32 = 0;
-            for (c, reader) in cond_readers.iter().enumerate() {
-                if reader.is_valid(i) && reader.read_bool(i) {
-                    bitmask |= 1 << c;
-                }
+impl IntervalListReader {
+    /// Creates a reader for the `LIST(INTERVAL)` column `col` of `input`.
+    ///
+    /// # Safety
+    ///
+    /// `input` must be a valid data chunk whose column `col` is a
+    /// `LIST(INTERVAL)` vector.
+    unsafe fn new(input: duckdb_data_chunk, col: usize) -> Self {
+        unsafe {
+            let vector = duckdb_data_chunk_get_vector(input, col as idx_t);
+            let child = duckdb_list_vector_get_child(vector);
+            Self {
+                reader: VectorReader::new(input, col),
+                entries: duckdb_vector_get_data(vector).cast::<duckdb_list_entry>(),
+                intervals: duckdb_vector_get_data(child).cast::<duckdb_interval>(),
+                validity: duckdb_vector_get_validity(child),
             }
+        }
+    }
 
-            state.update(Event::new(timestamp, bitmask), num_conditions);
+    /// Returns whether the list at `row` is non-`NULL`.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be less than the chunk's row count.
+    unsafe fn is_valid(&self, row: usize) -> bool {
+        unsafe { self.reader.is_valid(row) }
+    }
+
+    /// Returns the elements of the list at `row` in microseconds, 0 for a
+    /// `NULL` element, or the first month-based element as an error.
+    ///
+    /// # Safety
+    ///
+    /// `row` must be less than the chunk's row count and its list non-`NULL`.
+    unsafe fn read_micros(&self, row: usize) -> Result<Vec<i64>, duckdb_interval> {
+        unsafe {
+            let entry = *self.entries.add(row);
+            (entry.offset..entry.offset + entry.length)
+                .map(|j| {
+                    if !self.validity.is_null() && !duckdb_validity_row_is_valid(self.validity, j) {
+                        return Ok(0);
+                    }
+                    let iv = *self.intervals.add(j as usize);
+                    interval_to_micros(iv.months, iv.days, iv.micros).ok_or(iv)
+                })
+                .collect()
         }
     }
 }
 
+/// Sets the window of `state` from row `i` of `interval_reader` and, while the
+/// mode is still the default, its mode from row `i` of `mode_reader` (once per
+/// state, from the first row that has one). A `NULL` window is unbounded
+/// ([`UNBOUNDED_WINDOW_US`]). Marks the parameters as set for combine (see
+/// [`WindowFunnelState::params_set`]).
+///
+/// A month-based window or an unknown mode is reported as an error of
+/// `function` and makes this return `false`.
+///
+/// # Safety
+///
+/// `info` must be the `duckdb_function_info` passed to the running callback,
+/// and `i` a row of the chunk both readers read.
+pub(crate) unsafe fn configure(
+    info: duckdb_function_info,
+    state: &mut WindowFunnelState,
+    interval_reader: &VectorReader,
+    mode_reader: Option<&VectorReader>,
+    i: usize,
+    function: &str,
+) -> bool {
+    unsafe {
+        if interval_reader.is_valid(i) {
+            let iv = interval_reader.read_interval(i);
+            let Some(window_us) = interval_to_micros(iv.months, iv.days, iv.micros) else {
+                report(
+                    info,
+                    &interval_message(function, iv.months, iv.days, iv.micros),
+                );
+                return false;
+            };
+            state.window_size_us = window_us;
+        } else {
+            state.window_size_us = UNBOUNDED_WINDOW_US;
+        }
+
+        if let Some(mode_reader) = mode_reader {
+            if state.mode.is_default() && mode_reader.is_valid(i) {
+                let s = mode_reader.read_str(i);
+                match FunnelMode::parse_modes(s) {
+                    Ok(mode) => state.mode = mode,
+                    Err(token) => {
+                        report(info, &mode_message(function, s, &token));
+                        return false;
+                    }
+                }
+            }
+        }
+        state.params_set = true;
+        true
+    }
+}
+
 // SAFETY: `source` and `target` point to `count` aggregate state pointers.
 // combine_in_place propagates window_size_us and mode from source to target
-// when target has defaults (Session 10 bug fix).
-unsafe extern "C" fn state_combine(
+// when target has not set them (Session 10 bug fix).
+pub(crate) unsafe extern "C" fn state_combine(
     _info: duckdb_function_info,
     source: *mut duckdb_aggregate_state,
     target: *mut duckdb_aggregate_state,
@@ -236,9 +854,123 @@ unsafe extern "C" fn state_finalize(
     }
 }
 
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB UINTEGER vector with room for `offset + count` elements. At most
+// 32 conditions are registered, so the mask fits in 32 bits.
+unsafe extern "C" fn state_finalize_mask(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<WindowFunnelState>::with_state_mut(*source.add(i)) else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            let mask = state.finalize_mask();
+            writer.write_u32(idx, mask as u32);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB TIMESTAMP vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize_entry_ts(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<WindowFunnelState>::with_state_mut(*source.add(i))
+                .and_then(WindowFunnelState::finalize_entry_ts)
+            {
+                Some(ts) => writer.write_i64(idx, ts),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB DOUBLE vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize_conversion(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<WindowFunnelState>::with_state_mut(*source.add(i))
+                .and_then(WindowFunnelState::finalize_conversion)
+            {
+                Some(converted) => writer.write_f64(idx, converted),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB INTERVAL vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize_time_to_convert(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let time = FfiState::<WindowFunnelState>::with_state_mut(*source.add(i))
+                .map_or(Ok(None), WindowFunnelState::finalize_time_to_convert);
+            match time {
+                Ok(Some(micros)) => writer.write_interval(
+                    idx,
+                    DuckInterval {
+                        months: 0,
+                        days: 0,
+                        micros,
+                    },
+                ),
+                Ok(None) => writer.set_null(idx),
+                Err(error) => {
+                    report(info, &overflow_message("time_to_convert", &error));
+                    return;
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::event::Event;
     use quack_rs::testing::AggregateTestHarness;
 
     #[test]
@@ -249,6 +981,7 @@ mod tests {
         let mut source = AggregateTestHarness::<WindowFunnelState>::new();
         source.update(|s| {
             s.window_size_us = 3_600_000_000; // 1 hour
+            s.params_set = true;
             s.update(Event::new(1_000_000, 0b01), 2);
         });
 
@@ -261,12 +994,28 @@ mod tests {
         assert_eq!(state.window_size_us, 3_600_000_000);
     }
 
+    #[test]
+    fn test_step_mask_clears_bits_beyond_steps() {
+        assert_eq!(step_mask(0b1011, 3), Some(0b011));
+        assert_eq!(step_mask(0b1000, 3), Some(0));
+        assert_eq!(step_mask(u32::MAX, 32), Some(u64::from(u32::MAX)));
+        assert_eq!(step_mask(0b1, 1), Some(1));
+    }
+
+    #[test]
+    fn test_step_mask_rejects_step_counts_out_of_range() {
+        assert_eq!(step_mask(1, 0), None);
+        assert_eq!(step_mask(1, -1), None);
+        assert_eq!(step_mask(1, 33), None);
+    }
+
     #[test]
     fn test_funnel_combine_mode_propagation() {
         let mut source = AggregateTestHarness::<WindowFunnelState>::new();
         source.update(|s| {
             s.mode = FunnelMode::STRICT_ORDER;
             s.window_size_us = 1_000_000;
+            s.params_set = true;
             s.update(Event::new(1_000_000, 0b01), 2);
         });
 
@@ -277,17 +1026,108 @@ mod tests {
         assert_eq!(state.mode, FunnelMode::STRICT_ORDER);
     }
 
+    #[test]
+    fn test_funnel_combine_dedup_propagation() {
+        let mut source = AggregateTestHarness::<WindowFunnelState>::new();
+        source.update(|s| {
+            s.window_size_us = 1_000_000;
+            s.params_set = true;
+            s.dedup_within_us = 500_000;
+            s.update(Event::new(1_000_000, 0b01), 2);
+        });
+
+        let mut target = AggregateTestHarness::<WindowFunnelState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let state = target.finalize();
+        assert_eq!(state.dedup_within_us, 500_000);
+    }
+
+    #[test]
+    fn test_funnel_mask_combine_propagation() {
+        let mut source = AggregateTestHarness::<WindowFunnelState>::new();
+        source.update(|s| {
+            s.window_size_us = 10_000_000;
+            s.params_set = true;
+            s.update(Event::new(1_000_000, 0b001), 3);
+            s.update(Event::new(2_000_000, 0b010), 3);
+        });
+
+        let mut target = AggregateTestHarness::<WindowFunnelState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = target.finalize();
+        assert_eq!(state.finalize_mask(), 0b011);
+    }
+
+    #[test]
+    fn test_conversion_rate_combine_propagation() {
+        let mut source = AggregateTestHarness::<WindowFunnelState>::new();
+        source.update(|s| {
+            s.window_size_us = 10_000_000;
+            s.params_set = true;
+            s.update(Event::new(1_000_000, 0b01), 2);
+            s.update(Event::new(5_000_000, 0b10), 2);
+        });
+
+        let mut target = AggregateTestHarness::<WindowFunnelState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = target.finalize();
+        assert_eq!(state.finalize_conversion(), Some(1.0));
+    }
+
+    #[test]
+    fn test_time_to_convert_combine_across_segments() {
+        // Entry and target arrive in different segments; the zero-initialized
+        // target state must pick up the window.
+        let mut entry = AggregateTestHarness::<WindowFunnelState>::new();
+        entry.update(|s| {
+            s.window_size_us = 60_000_000;
+            s.params_set = true;
+            s.update(Event::new(1_000_000, 0b01), 2);
+        });
+        let mut target = AggregateTestHarness::<WindowFunnelState>::new();
+        target.update(|s| s.update(Event::new(46_000_000, 0b10), 2));
+
+        target.combine(&entry, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = target.finalize();
+        assert_eq!(state.finalize_time_to_convert(), Ok(Some(45_000_000)));
+    }
+
+    #[test]
+    fn test_entry_ts_combine_across_segments() {
+        // The best chain's entry lives in a different segment than its steps.
+        let mut entries = AggregateTestHarness::<WindowFunnelState>::new();
+        entries.update(|s| {
+            s.window_size_us = 10_000_000;
+            s.params_set = true;
+            s.update(Event::new(1_000_000, 0b01), 2);
+            s.update(Event::new(30_000_000, 0b01), 2);
+        });
+        let mut steps = AggregateTestHarness::<WindowFunnelState>::new();
+        steps.update(|s| s.update(Event::new(35_000_000, 0b10), 2));
+
+        steps.combine(&entries, |src, tgt| tgt.combine_in_place(src));
+
+        let mut state = steps.finalize();
+        assert_eq!(state.finalize_entry_ts(), Some(30_000_000));
+    }
+
     #[test]
     fn test_funnel_combine_events_merged() {
         let mut a = AggregateTestHarness::<WindowFunnelState>::new();
         a.update(|s| {
             s.window_size_us = 10_000_000; // 10 seconds
+            s.params_set = true;
             s.update(Event::new(1_000_000, 0b01), 2); // step 1
         });
 
         let mut b = AggregateTestHarness::<WindowFunnelState>::new();
         b.update(|s| {
             s.window_size_us = 10_000_000;
+            s.params_set = true;
             s.update(Event::new(2_000_000, 0b10), 2); // step 2
         });
 
@@ -310,6 +1150,7 @@ mod tests {
         let mut a1 = AggregateTestHarness::<WindowFunnelState>::new();
         a1.update(|s| {
             s.window_size_us = 10_000_000;
+            s.params_set = true;
             for &e in &events_a {
                 s.update(e, 3);
             }
@@ -317,6 +1158,7 @@ mod tests {
         let mut b1 = AggregateTestHarness::<WindowFunnelState>::new();
         b1.update(|s| {
             s.window_size_us = 10_000_000;
+            s.params_set = true;
             for &e in &events_b {
                 s.update(e, 3);
             }
@@ -325,6 +1167,7 @@ mod tests {
         let mut c1 = AggregateTestHarness::<WindowFunnelState>::new();
         c1.update(|s| {
             s.window_size_us = 10_000_000;
+            s.params_set = true;
             for &e in &events_c {
                 s.update(e, 3);
             }
@@ -337,6 +1180,7 @@ mod tests {
         let mut b2 = AggregateTestHarness::<WindowFunnelState>::new();
         b2.update(|s| {
             s.window_size_us = 10_000_000;
+            s.params_set = true;
             for &e in &events_b {
                 s.update(e, 3);
             }
@@ -344,6 +1188,7 @@ mod tests {
         let mut c2 = AggregateTestHarness::<WindowFunnelState>::new();
         c2.update(|s| {
             s.window_size_us = 10_000_000;
+            s.params_set = true;
             for &e in &events_c {
                 s.update(e, 3);
             }
@@ -352,6 +1197,7 @@ mod tests {
         let mut a2 = AggregateTestHarness::<WindowFunnelState>::new();
         a2.update(|s| {
             s.window_size_us = 10_000_000;
+            s.params_set = true;
             for &e in &events_a {
                 s.update(e, 3);
             }
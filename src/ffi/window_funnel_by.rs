@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `window_funnel_by` aggregate function.
+//!
+//! Registered like [`window_funnel_entry_ts`](super::window_funnel), with a
+//! `VARCHAR` dimension column after the timestamp and a `STRUCT` return type
+//! written through [`quack_rs::vector::complex::StructVector`] field writers.
+//! The window and mode are read by the shared
+//! [`configure`](super::window_funnel::configure).
+
+use crate::ffi::conditions::{chunk_events, CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::ffi::window_funnel::configure;
+use crate::window_funnel_by::WindowFunnelByState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::{LogicalType, TypeId};
+use quack_rs::vector::complex::StructVector;
+use quack_rs::vector::{VectorReader, VectorWriter};
+use std::sync::Arc;
+
+impl quack_rs::aggregate::AggregateState for WindowFunnelByState {}
+
+/// Registers the `window_funnel_by` function with `DuckDB` as a function set
+/// with overloads for two signatures:
+///
+/// 1. Without mode: `window_funnel_by(INTERVAL, TIMESTAMP, VARCHAR, BOOLEAN, BOOLEAN [, ...])`
+/// 2. With mode: `window_funnel_by(INTERVAL, VARCHAR, TIMESTAMP, VARCHAR, BOOLEAN, BOOLEAN [, ...])`
+///
+/// Both return `STRUCT(step INTEGER, entry_dim VARCHAR)`: the
+/// `window_funnel` result and the dimension at the entry event of the best
+/// chain (see [`WindowFunnelByState::finalize`]).
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_window_funnel_by(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("window_funnel_by").returns_logical(
+        LogicalType::struct_type(&[("step", TypeId::Integer), ("entry_dim", TypeId::Varchar)]),
+    );
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            // Group 1: WITHOUT mode parameter: (INTERVAL, TIMESTAMP, VARCHAR, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Timestamp)
+                    .param(TypeId::Varchar);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelByState)
+                    .update(state_update)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            })
+            // Group 2: WITH mode parameter: (INTERVAL, VARCHAR, TIMESTAMP, VARCHAR, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp)
+                    .param(TypeId::Varchar);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelByState)
+                    .update(state_update_with_mode)
+                    .combine(state_combine)
+                    .finalize(state_finalize)
+            });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with columns (INTERVAL,
+// TIMESTAMP, VARCHAR, COND×N) as registered. `states` points to `row_count`
+// aggregate state pointers.
+unsafe extern "C" fn state_update(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_impl(info, input, states, false) }
+}
+
+// SAFETY: as `state_update`, with a VARCHAR mode column after the INTERVAL.
+unsafe extern "C" fn state_update_with_mode(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe { update_impl(info, input, states, true) }
+}
+
+/// Shared update implementation. Column layout:
+///   \[0\] INTERVAL, (\[1\] VARCHAR mode,) TIMESTAMP, VARCHAR dimension, COND×N
+///
+/// # Safety
+///
+/// Requires valid `info`, `input` data chunk and `states` aggregate state pointers.
+unsafe fn update_impl(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+    has_mode: bool,
+) {
+    unsafe {
+        let row_count = duckdb_data_chunk_get_size(input) as usize;
+        let col_count = duckdb_data_chunk_get_column_count(input) as usize;
+
+        let ts_col = 1 + usize::from(has_mode);
+        let dim_col = ts_col + 1;
+        let bool_start = dim_col + 1;
+        let num_conditions = col_count.saturating_sub(bool_start);
+
+        let interval_reader = VectorReader::new(input, 0);
+        let mode_reader = has_mode.then(|| VectorReader::new(input, 1));
+        let dim_reader = VectorReader::new(input, dim_col);
+        let events = chunk_events(input, ts_col, bool_start, col_count);
+
+        for i in 0..row_count {
+            let Some(state) = FfiState::<WindowFunnelByState>::with_state_mut(*states.add(i))
+            else {
+                continue;
+            };
+            let Some(event) = events.get(i) else {
+                continue;
+            };
+            if !configure(
+                info,
+                &mut state.funnel,
+                &interval_reader,
+                mode_reader.as_ref(),
+                i,
+                "window_funnel_by",
+            ) {
+                return;
+            }
+
+            let dim = if dim_reader.is_valid(i) {
+                Some(Arc::from(dim_reader.read_str(i)))
+            } else {
+                None
+            };
+            state.update(event, dim, num_conditions);
+        }
+    }
+}
+
+// SAFETY: `source` and `target` point to `count` aggregate state pointers.
+unsafe extern "C" fn state_combine(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        for i in 0..count as usize {
+            let Some(src) = FfiState::<WindowFunnelByState>::with_state(*source.add(i)) else {
+                continue;
+            };
+            let Some(tgt) = FfiState::<WindowFunnelByState>::with_state_mut(*target.add(i)) else {
+                continue;
+            };
+
+            tgt.combine_in_place(src);
+        }
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB STRUCT(step INTEGER, entry_dim VARCHAR) vector with room for
+// `offset + count` elements.
+unsafe extern "C" fn state_finalize(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+        let mut step_writer = StructVector::field_writer(result, 0);
+        let mut dim_writer = StructVector::field_writer(result, 1);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            let Some(state) = FfiState::<WindowFunnelByState>::with_state_mut(*source.add(i))
+            else {
+                writer.set_null(idx);
+                continue;
+            };
+
+            let (step, dim) = state.finalize();
+            step_writer.write_i32(idx, step as i32);
+            match dim {
+                Some(dim) => dim_writer.write_varchar(idx, &dim),
+                None => dim_writer.set_null(idx),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = AggregateTestHarness::<WindowFunnelByState>::new();
+        source.update(|s| {
+            s.funnel.window_size_us = 3_600_000_000;
+            s.funnel.params_set = true;
+            s.update(Event::new(0, 0b01), Some(Arc::from("ads")), 2);
+            s.update(Event::new(1_000_000, 0b10), None, 2);
+        });
+        let mut target = AggregateTestHarness::<WindowFunnelByState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        assert_eq!(state.finalize(), (2, Some(Arc::from("ads"))));
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! FFI registration for the `window_funnel_state` aggregate function and the
+//! `window_funnel_merge` and `window_funnel_finalize` scalar functions.
+//!
+//! `window_funnel_state` shares the update and combine callbacks of
+//! [`window_funnel`](super::window_funnel) and finalizes to the `BLOB` of
+//! [`WindowFunnelState::to_blob`]. The two scalar functions read such blobs
+//! back with [`WindowFunnelState::from_blob`] and are registered through
+//! [`state_export`](super::state_export).
+
+use crate::common::state_blob::BlobError;
+use crate::ffi::conditions::{CONDITION_TYPES, MAX_CONDITIONS, MIN_CONDITIONS};
+use crate::ffi::state_export::{
+    finalize_rows, register_finalize, register_merge, write_blob, ExportedState,
+};
+use crate::ffi::state_lifecycle::with_lifecycle;
+use crate::ffi::window_funnel::{state_combine, state_update, state_update_with_mode};
+use crate::window_funnel::WindowFunnelState;
+use libduckdb_sys::*;
+use quack_rs::aggregate::{AggregateFunctionSetBuilder, FfiState};
+use quack_rs::types::TypeId;
+use quack_rs::vector::VectorWriter;
+
+/// Registers the `window_funnel_state` function with `DuckDB` as a function
+/// set with overloads for two signatures:
+///
+/// 1. Without mode: `window_funnel_state(INTERVAL, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BLOB`
+/// 2. With mode: `window_funnel_state(INTERVAL, VARCHAR, TIMESTAMP, BOOLEAN, BOOLEAN [, ...]) -> BLOB`
+///
+/// Arguments match `window_funnel`; the result is the exported state (see
+/// [`WindowFunnelState::to_blob`]), to be combined with `window_funnel_merge`
+/// and finished with `window_funnel_finalize`.
+///
+/// # Safety
+///
+/// Requires a valid connection implementing the [`Registrar`](quack_rs::connection::Registrar) trait.
+///
+/// # Errors
+///
+/// Returns an error if function registration fails.
+pub unsafe fn register_window_funnel_state(
+    con: &impl quack_rs::connection::Registrar,
+) -> Result<(), quack_rs::error::ExtensionError> {
+    let mut builder = AggregateFunctionSetBuilder::new("window_funnel_state").returns(TypeId::Blob);
+    for cond_type in CONDITION_TYPES {
+        builder = builder
+            // Group 1: WITHOUT mode parameter: (INTERVAL, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder.param(TypeId::Interval).param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update)
+                    .combine(state_combine)
+                    .finalize(state_finalize_blob)
+            })
+            // Group 2: WITH mode parameter: (INTERVAL, VARCHAR, TIMESTAMP, COND×N)
+            .overloads(MIN_CONDITIONS..=MAX_CONDITIONS, move |n, builder| {
+                let mut b = builder
+                    .param(TypeId::Interval)
+                    .param(TypeId::Varchar)
+                    .param(TypeId::Timestamp);
+                for _ in 0..n {
+                    b = b.param(cond_type);
+                }
+                with_lifecycle!(b, WindowFunnelState)
+                    .update(state_update_with_mode)
+                    .combine(state_combine)
+                    .finalize(state_finalize_blob)
+            });
+    }
+    unsafe { con.register_aggregate_set(builder) }
+}
+
+/// Registers the `window_funnel_merge` function with `DuckDB`.
+///
+/// Signature: `window_funnel_merge(BLOB, BLOB) -> BLOB`
+///
+/// Returns the state holding the events of both states, as if their rows had
+/// been aggregated together. A `NULL` argument is an empty state, so the
+/// result is the other argument; both `NULL` produces `NULL`. States of
+/// different funnels (window, mode, steps) or malformed blobs fail the
+/// query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_window_funnel_merge(con: duckdb_connection) {
+    unsafe { register_merge::<WindowFunnelState>(con) }
+}
+
+/// Registers the `window_funnel_finalize` function with `DuckDB`.
+///
+/// Signature: `window_funnel_finalize(BLOB) -> INTEGER`
+///
+/// Returns the step `window_funnel` would have returned for the rows of the
+/// state. `NULL` input produces `NULL`; a malformed blob fails the query.
+///
+/// # Safety
+///
+/// Requires a valid `duckdb_connection` handle.
+pub unsafe fn register_window_funnel_finalize(con: duckdb_connection) {
+    unsafe {
+        register_finalize(
+            con,
+            c"window_funnel_finalize",
+            duckdb_create_logical_type(DUCKDB_TYPE_DUCKDB_TYPE_INTEGER),
+            window_funnel_finalize_function,
+        );
+    }
+}
+
+impl ExportedState for WindowFunnelState {
+    const NAME: &'static str = "window_funnel";
+
+    fn from_blob(bytes: &[u8]) -> Result<Self, BlobError> {
+        Self::from_blob(bytes)
+    }
+
+    fn to_blob(&self) -> Vec<u8> {
+        self.to_blob()
+    }
+
+    fn merge(mut self, other: &Self) -> Result<Self, &'static str> {
+        if !self.same_funnel(other) {
+            return Err("states of different funnels (window, mode or steps differ)");
+        }
+        self.combine_in_place(other);
+        Ok(self)
+    }
+}
+
+// SAFETY: `source` points to `count` aggregate state pointers. `result` is a
+// valid DuckDB BLOB vector with room for `offset + count` elements.
+unsafe extern "C" fn state_finalize_blob(
+    _info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let mut writer = VectorWriter::new(result);
+
+        for i in 0..count as usize {
+            let idx = offset as usize + i;
+
+            match FfiState::<WindowFunnelState>::with_state(*source.add(i)) {
+                Some(state) => write_blob(result, idx, &state.to_blob()),
+                None => writer.set_null(idx),
+            }
+        }
+    }
+}
+
+// SAFETY: `input` is a valid DuckDB data chunk with one BLOB column as
+// registered. `output` is a valid INTEGER vector sized for the chunk.
+unsafe extern "C" fn window_funnel_finalize_function(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        finalize_rows::<WindowFunnelState>(info, input, output, |writer, row, mut state| {
+            writer.write_i32(row, state.finalize() as i32);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::event::Event;
+    use quack_rs::testing::AggregateTestHarness;
+
+    #[test]
+    fn test_blob_of_combined_state_finalizes_like_window_funnel() {
+        let mut source = AggregateTestHarness::<WindowFunnelState>::new();
+        source.update(|s| {
+            s.window_size_us = 10;
+            s.params_set = true;
+            s.update(Event::new(0, 0b001), 3);
+            s.update(Event::new(5, 0b010), 3);
+        });
+        let mut target = AggregateTestHarness::<WindowFunnelState>::new();
+        target.combine(&source, |src, tgt| tgt.combine_in_place(src));
+        let mut state = target.finalize();
+        let mut restored = WindowFunnelState::from_blob(&state.to_blob()).unwrap();
+        assert_eq!(restored.finalize(), 2);
+        assert_eq!(state.finalize(), 2);
+    }
+}
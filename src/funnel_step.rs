@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `funnel_step` — Per-row funnel step as a window function.
+//!
+//! Takes the same arguments as [`window_funnel`](crate::window_funnel) and,
+//! evaluated over a frame that ends at the current row, returns the funnel
+//! step the current row advanced a chain to: the highest step of any chain in
+//! the frame whose last matched step is the current row, or 0 when the row
+//! does not advance any chain. This annotates every row of a funnel for
+//! debugging, where `window_funnel` only returns one number per group.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id, event_time, event_type,
+//!   funnel_step(INTERVAL '1 hour', event_time,
+//!     event_type = 'view', event_type = 'cart', event_type = 'purchase'
+//!   ) OVER (
+//!     PARTITION BY user_id ORDER BY event_time
+//!     ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW
+//!   ) as step
+//! FROM events
+//! ```
+//!
+//! # Implementation
+//!
+//! The C API does not tell an aggregate which row of the frame is the current
+//! one. As in [`session_edge`](crate::session_edge), `DuckDB` combines the
+//! segments of a frame in order, so [`FunnelStepState`] keeps the last row of
+//! its segment next to a [`WindowFunnelState`] holding the segment's events;
+//! when the frame ends at the current row, that last row is the current row.
+//! Finalize runs [`WindowFunnelState::finalize_step`] for it, which scans the
+//! whole frame: `O(n * k)` per row, where `window_funnel` over the same frame
+//! would cost the same.
+
+use crate::common::event::Event;
+use crate::window_funnel::WindowFunnelState;
+
+/// State for the `funnel_step` window function.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct FunnelStepState {
+    /// Events of this segment, with the window size and number of steps.
+    pub funnel: WindowFunnelState,
+    /// Last row of the segment; `None` when it had a `NULL` timestamp.
+    last: Option<Event>,
+    /// Whether any row has been added to the segment.
+    has_rows: bool,
+}
+
+impl FunnelStepState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            funnel: WindowFunnelState::new(),
+            last: None,
+            has_rows: false,
+        }
+    }
+
+    /// Adds the next row of the segment; `event` is `None` for a row with a
+    /// `NULL` timestamp, which reports step 0 when it is the current row.
+    pub fn update(&mut self, event: Option<Event>, num_conditions: usize) {
+        if let Some(event) = event {
+            self.funnel.update(event, num_conditions);
+        }
+        self.last = event;
+        self.has_rows = true;
+    }
+
+    /// Combines `other` (the following segment) into `self`.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.funnel.combine_in_place(&other.funnel);
+        if other.has_rows {
+            self.last = other.last;
+            self.has_rows = true;
+        }
+    }
+
+    /// Computes the step the segment's last row advanced a chain to, or 0.
+    #[must_use]
+    pub fn finalize(&mut self) -> i64 {
+        self.last.map_or(0, |last| self.funnel.finalize_step(last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600_000_000;
+
+    fn event(ts: i64, conds: &[bool]) -> Event {
+        Event::from_bools(ts, conds)
+    }
+
+    /// Steps of every row of `rows` over a frame ending at that row.
+    fn steps(rows: &[Option<Event>]) -> Vec<i64> {
+        (1..=rows.len())
+            .map(|end| {
+                let mut state = FunnelStepState::new();
+                state.funnel.window_size_us = HOUR;
+                for &row in &rows[..end] {
+                    state.update(row, 3);
+                }
+                state.finalize()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_state() {
+        assert_eq!(FunnelStepState::new().finalize(), 0);
+    }
+
+    #[test]
+    fn test_each_advancing_row_reports_its_step() {
+        let rows = [
+            Some(event(0, &[true, false, false])),
+            Some(event(10, &[false, false, false])),
+            Some(event(20, &[false, true, false])),
+            Some(event(30, &[false, false, true])),
+        ];
+        assert_eq!(steps(&rows), vec![1, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_repeated_step_reports_zero() {
+        let rows = [
+            Some(event(0, &[true, false, false])),
+            Some(event(10, &[false, true, false])),
+            Some(event(20, &[false, true, false])),
+        ];
+        assert_eq!(steps(&rows), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_new_entry_after_window_expires() {
+        let rows = [
+            Some(event(0, &[true, false, false])),
+            Some(event(2 * HOUR, &[true, false, false])),
+            Some(event(2 * HOUR + 10, &[false, true, false])),
+            Some(event(4 * HOUR, &[false, false, true])),
+        ];
+        // The second entry starts its own chain; the last event is outside
+        // the window of both entries.
+        assert_eq!(steps(&rows), vec![1, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_null_timestamp_row_reports_zero() {
+        let rows = [
+            Some(event(0, &[true, false, false])),
+            None,
+            Some(event(10, &[false, true, false])),
+        ];
+        assert_eq!(steps(&rows), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_combine_keeps_last_row_of_following_segment() {
+        let mut left = FunnelStepState::new();
+        left.funnel.window_size_us = HOUR;
+        left.funnel.params_set = true;
+        left.update(Some(event(0, &[true, false, false])), 3);
+        left.update(Some(event(10, &[false, true, false])), 3);
+        let mut right = FunnelStepState::new();
+        right.update(Some(event(20, &[false, false, true])), 3);
+
+        let mut target = FunnelStepState::new();
+        target.combine_in_place(&left);
+        target.combine_in_place(&FunnelStepState::new());
+        assert_eq!(target.clone().finalize(), 2);
+        target.combine_in_place(&right);
+        assert_eq!(target.finalize(), 3);
+    }
+
+    #[test]
+    fn test_same_timestamp_as_earlier_step() {
+        // The current row is treated as the latest of its timestamp.
+        let rows = [
+            Some(event(0, &[true, false, false])),
+            Some(event(5, &[false, true, false])),
+            Some(event(5, &[false, false, true])),
+        ];
+        assert_eq!(steps(&rows), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `group_array_sorted_by_ts` — The group's values in timestamp order.
+//!
+//! Returns the `value` of the group's earliest `max_n` events as a `LIST`,
+//! ordered by timestamp. This is the usual way to export raw paths for
+//! offline analysis, next to the behavioral functions that summarize them.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id, group_array_sorted_by_ts(page, event_time, 100) as path
+//! FROM events
+//! GROUP BY user_id
+//! ```
+//!
+//! # Implementation
+//!
+//! Unlike `list(value ORDER BY ts)`, memory is bounded by `max_n` rather
+//! than by the size of the group. The state buffers at most `2 * max_n`
+//! entries: when the buffer fills, it is sorted and truncated to the
+//! earliest `max_n`, which amortizes the sort to O(log `max_n`) per row.
+//! Combine truncates both sides and merges them with the shared
+//! [`merge_sorted_by`], so partial states never hold more than the result.
+//!
+//! Entries are ordered by timestamp, then by value, so events sharing a
+//! timestamp come out in the same order however the input was partitioned
+//! across threads. Rows with a `NULL` timestamp are ignored; a `NULL` value
+//! is kept and sorts before the other values of its timestamp.
+
+use crate::common::event::{merge_sorted_by, sort_presorted};
+use std::sync::Arc;
+
+/// One buffered event: timestamp in microseconds and value.
+type Entry<V> = (i64, Option<V>);
+
+/// State for the `group_array_sorted_by_ts` aggregate function.
+///
+/// Generic over the value type `V`, defaulting to `Arc<str>`, like
+/// [`SequenceNextNodeState`](crate::sequence_next_node::SequenceNextNodeState).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct GroupArraySortedState<V = Arc<str>> {
+    /// Maximum number of values returned. 0 until set by the first row.
+    pub max_n: usize,
+    /// Buffered entries, at most `2 * max_n` once `max_n` is set.
+    entries: Vec<Entry<V>>,
+}
+
+impl<V> Default for GroupArraySortedState<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> GroupArraySortedState<V> {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_n: 0,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<V: Ord + Clone> GroupArraySortedState<V> {
+    /// Adds an event.
+    pub fn update(&mut self, timestamp_us: i64, value: Option<V>) {
+        self.entries.push((timestamp_us, value));
+        if self.max_n > 0 && self.entries.len() >= self.max_n.saturating_mul(2) {
+            self.compact();
+        }
+    }
+
+    /// Sorts the buffer and truncates it to the earliest `max_n` entries.
+    fn compact(&mut self) {
+        sort_presorted(&mut self.entries);
+        if self.max_n > 0 {
+            self.entries.truncate(self.max_n);
+        }
+    }
+
+    /// Combines `other` into `self`, keeping the earliest `max_n` entries.
+    ///
+    /// Propagates `max_n` into a zero-initialized target.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        if self.max_n == 0 {
+            self.max_n = other.max_n;
+        }
+        if other.entries.is_empty() {
+            return;
+        }
+        self.compact();
+        let mut theirs = other.entries.clone();
+        sort_presorted(&mut theirs);
+        if self.max_n > 0 {
+            theirs.truncate(self.max_n);
+        }
+        self.entries = merge_sorted_by(&self.entries, &theirs, Ord::cmp);
+        if self.max_n > 0 {
+            self.entries.truncate(self.max_n);
+        }
+    }
+
+    /// Returns the values of the earliest `max_n` events in timestamp order.
+    #[must_use]
+    pub fn finalize(&mut self) -> Vec<Option<V>> {
+        self.compact();
+        self.entries
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(max_n: usize, events: &[(i64, &str)]) -> GroupArraySortedState {
+        let mut s = GroupArraySortedState::new();
+        s.max_n = max_n;
+        for &(ts, value) in events {
+            s.update(ts, Some(Arc::from(value)));
+        }
+        s
+    }
+
+    fn values(mut s: GroupArraySortedState) -> Vec<Option<String>> {
+        s.finalize()
+            .into_iter()
+            .map(|v| v.map(|v| v.to_string()))
+            .collect()
+    }
+
+    fn some(values: &[&str]) -> Vec<Option<String>> {
+        values.iter().map(|v| Some((*v).to_string())).collect()
+    }
+
+    #[test]
+    fn test_empty_state() {
+        assert!(GroupArraySortedState::<i64>::new().finalize().is_empty());
+    }
+
+    #[test]
+    fn test_values_in_timestamp_order() {
+        let s = state(10, &[(30, "c"), (10, "a"), (20, "b")]);
+        assert_eq!(values(s), some(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn test_capped_at_max_n_earliest() {
+        let events: Vec<(i64, String)> = (0..100).rev().map(|i| (i, format!("v{i}"))).collect();
+        let mut s = GroupArraySortedState::new();
+        s.max_n = 3;
+        for (ts, value) in &events {
+            s.update(*ts, Some(Arc::from(value.as_str())));
+            assert!(s.entries.len() < 6);
+        }
+        assert_eq!(values(s), some(&["v0", "v1", "v2"]));
+    }
+
+    #[test]
+    fn test_ties_ordered_by_value() {
+        let s = state(10, &[(5, "b"), (5, "a"), (1, "z")]);
+        assert_eq!(values(s), some(&["z", "a", "b"]));
+    }
+
+    #[test]
+    fn test_null_value_is_kept() {
+        let mut s = GroupArraySortedState::<i64>::new();
+        s.max_n = 5;
+        s.update(2, Some(7));
+        s.update(1, None);
+        assert_eq!(s.finalize(), vec![None, Some(7)]);
+    }
+
+    #[test]
+    fn test_combine_matches_sequential_for_every_split() {
+        let events = [(4, "d"), (1, "a"), (6, "f"), (3, "c"), (5, "e"), (2, "b")];
+        let whole = values(state(4, &events));
+        assert_eq!(whole, some(&["a", "b", "c", "d"]));
+        for split in 0..=events.len() {
+            let mut left = state(4, &events[..split]);
+            left.combine_in_place(&state(4, &events[split..]));
+            assert_eq!(values(left), whole, "split {split}");
+        }
+    }
+
+    #[test]
+    fn test_combine_zero_target_propagates_max_n() {
+        let mut target = GroupArraySortedState::new();
+        target.combine_in_place(&state(2, &[(3, "c"), (1, "a"), (2, "b")]));
+        assert_eq!(target.max_n, 2);
+        assert_eq!(values(target), some(&["a", "b"]));
+    }
+}
@@ -10,13 +10,70 @@
 //!
 //! | Function | Type | Description |
 //! |----------|------|-------------|
-//! | `sessionize(ts, gap)` | Window | Assigns session IDs based on inactivity gaps |
-//! | `retention(c1, ..., cN)` | Aggregate | Cohort retention analysis |
+//! | `sessionize(ts, gap [, max_len])` | Window | Assigns session IDs based on inactivity gaps |
+//! | `sessionize_by(ts, gap, cond)` | Window | Session IDs split on gaps and on rows where `cond` is true |
+//! | `sessionize_info(ts, gap [, max_len \| cond])` | Window | Session ID plus whether the row starts its session |
+//! | `sessionize_approx(ts, gap, slack)` | Aggregate | Session count over out-of-order input |
+//! | `sessionize_state(ts, gap)` | Aggregate | Session state as a `BLOB`, to merge and finalize later |
+//! | `sessionize_table(events, ts, gap)` | Table macro | Input rows plus a `session_id` column |
+//! | `first_event_after_session_start(ts, val, gap)` | Window | Value of the first event in the row's session |
+//! | `last_event_before_session_end(ts, val, gap)` | Window | Value of the last event in the row's session |
+//! | `session_number_of_event(ts, gap)` | Window | Position of the row in its session |
+//! | `events_in_session(ts, gap)` | Window | Events from the row to the end of its session |
+//! | `event_gap_stats(ts)` | Aggregate | Min/max/median gap between consecutive timestamps |
+//! | `retention(c1, ..., cN)` or `retention([c1, ..., cN])` | Aggregate | Cohort retention analysis |
+//! | `retention_counts(c1, ..., cN)` | Aggregate | Per-condition retained counts |
+//! | `retention_rate(c1, ..., cN)` | Aggregate | Retained counts divided by the anchor count |
+//! | `retention_with_validity(c1, ..., cN)` | Aggregate | Retention result with each condition's non-`NULL` row count |
+//! | `retention_by_period(base_ts, ts, period, n)` | Aggregate | Retention over `n` periods after `base_ts` |
+//! | `retention_state(c1, ..., cN)` | Aggregate | Retention state as a `BLOB`, to merge and finalize later |
 //! | `window_funnel(window, ts, c1, ..., cN)` | Aggregate | Conversion funnel analysis |
+//! | `window_funnel_mask(window, ts, c1, ..., cN)` | Aggregate | Bitmask of funnel steps reached |
+//! | `window_funnel_flags(window, ts, mask, num_steps)` | Aggregate | Funnel step over a per-event condition bitmask |
+//! | `window_funnel_entry_ts(window, ts, c1, ..., cN)` | Aggregate | Entry timestamp of the best funnel chain |
+//! | `window_funnel_by(window, ts, dim, c1, ..., cN)` | Aggregate | Funnel step and the dimension at the entry event |
+//! | `window_funnel_debug(window, ts, c1, ..., cN)` | Aggregate | Funnel step with the statistics of its search |
+//! | `window_funnel_state(window, ts, c1, ..., cN)` | Aggregate | Funnel state as a `BLOB`, to merge and finalize later |
+//! | `conversion_rate(window, ts, entry, target)` | Aggregate | 1.0 / 0.0 / NULL two-step conversion |
+//! | `time_to_convert(window, ts, entry, target)` | Aggregate | Entry-to-target time of a two-step conversion |
+//! | `funnel_step(window, ts, c1, ..., cN)` | Window | Funnel step each row advanced a chain to |
 //! | `sequence_match(pattern, ts, c1, ..., cN)` | Aggregate | Pattern matching over event sequences |
 //! | `sequence_count(pattern, ts, c1, ..., cN)` | Aggregate | Counts pattern matches in event sequences |
-//! | `sequence_match_events(pattern, ts, c1, ..., cN)` | Aggregate | Returns matched step timestamps |
-//! | `sequence_next_node(dir, base, ts, val, bc, e1, ..., eN)` | Aggregate | Next event after pattern match |
+//! | `sequence_count_within(window, pattern, ts, c1, ..., cN)` | Aggregate | Counts matches spanning at most the window |
+//! | `sequence_count_by(bucket, pattern, ts, c1, ..., cN)` | Aggregate | Counts matches per time bucket of their first event |
+//! | `sequence_match_debug(pattern, ts, c1, ..., cN)` | Aggregate | Pattern match with the statistics of its finalize |
+//! | `sequence_match_any(patterns, ts, c1, ..., cN)` | Aggregate | Index of the first matching pattern |
+//! | `sequence_match_events(pattern[, labels], ts, c1, ..., cN)` | Aggregate | Returns matched step timestamps, optionally labeled |
+//! | `sequence_match_spans(pattern, ts, c1, ..., cN)` | Aggregate | Returns start/end timestamps of each counted match |
+//! | `sequence_match_values(pattern, ts, val, c1, ..., cN)` | Aggregate | Returns matched step timestamps and values |
+//! | `sequence_extract(pattern, ts, val, c1, ..., cN)` | Aggregate | Returns the values of named `(?N:name)` steps |
+//! | `sequence_duration(pattern, ts, c1, ..., cN)` | Aggregate | Returns elapsed time of the first match |
+//! | `sequence_next_node(dir, base[, window], ts, val, bc, e1, ..., eN)` | Aggregate | Next event after pattern match |
+//! | `sequence_next_node_hist(dir[, window], ts, val, bc, e1, ..., eN)` | Aggregate | Next event counts over all matches |
+//! | `group_array_sorted_by_ts(val, ts, max_n)` | Aggregate | Earliest `max_n` values in timestamp order |
+//! | `transition_matrix(ts, state[, n_limit])` | Aggregate | Counts of transitions between consecutive states |
+//! | `markov_attribution([path,] ts, channel, converted)` | Aggregate | Removal-effect attribution of conversions to channels |
+//! | `path_count_sketch([path,] ts, value, depth)` | Aggregate | Approximate distinct paths over the first `depth` events |
+//! | `first_touch(ts, channel, converted [, lookback])` | Aggregate | Channel of the first touchpoint before the conversion |
+//! | `last_touch(ts, channel, converted [, lookback])` | Aggregate | Channel of the last touchpoint before the conversion |
+//! | `window_funnel_from_list(window[, mode], events)` | Scalar | `window_funnel` over an event list |
+//! | `sequence_match_from_list(pattern, events)` | Scalar | `sequence_match` over an event list |
+//! | `window_funnel_merge(state1, state2)` | Scalar | Merges two `window_funnel_state` states |
+//! | `window_funnel_finalize(state)` | Scalar | `window_funnel` step of a `window_funnel_state` state |
+//! | `retention_merge(state1, state2)` | Scalar | Merges two `retention_state` states |
+//! | `retention_finalize(state)` | Scalar | `retention` result of a `retention_state` state |
+//! | `sessionize_merge(state1, state2)` | Scalar | Merges two `sessionize_state` states of disjoint time ranges |
+//! | `sessionize_finalize(state)` | Scalar | Session count of a `sessionize_state` state |
+//! | `behavioral_selfcheck()` | Scalar | Runs the internal consistency suite |
+//! | `parse_duration(text)` | Scalar | Parses a duration such as `'1h30m'` into an interval |
+//! | `strict_condition(cond)` | Scalar | Fails the query on a `NULL` condition instead of reading it as false |
+//! | `cohort_of(ts, anchor, period)` | Scalar | Index of the period after `anchor` that `ts` falls in |
+//! | `sequence_pattern_check(pattern)` | Scalar | Validates a pattern string |
+//! | `sequence_pattern_normalize(pattern)` | Scalar | Canonical text of a pattern |
+//! | `behavioral_explain(pattern)` | Table | Pattern steps and execution path |
+//! | `behavioral_demo_events(n_users, seed)` | Table | Deterministic synthetic clickstream events |
+//! | `behavioral_version()` | Scalar | Extension version and build commit |
+//! | `behavioral_functions()` | Table | Lists functions, signatures and modes |
 //!
 //! ## Installation
 //!
@@ -24,15 +81,70 @@
 //! INSTALL behavioral FROM community;
 //! LOAD behavioral;
 //! ```
+//!
+//! ## Rust Library
+//!
+//! The SQL functions are thin FFI wrappers over the states in the modules
+//! below, which depend on nothing but `std`. Without the default `ffi`
+//! feature the crate builds as a plain library of those states, with no
+//! `DuckDB` dependency:
+//!
+//! ```toml
+//! [dependencies]
+//! duckdb-behavioral = { version = "0.5", default-features = false }
+//! ```
+//!
+//! The entry points are [`window_funnel::WindowFunnelState`],
+//! [`sequence::SequenceState`], [`sessionize::SessionizeBoundaryState`],
+//! [`retention::RetentionState`] and the pattern language in
+//! [`pattern::parser`] and [`pattern::executor`]. Feed a state the rows of a
+//! group with `update`, merge partial states with `combine`, and call a
+//! `finalize` method for the value the SQL function returns: the extension
+//! runs exactly these calls. Timestamps are microseconds since the epoch,
+//! and [`common::event::Event`] packs one row's conditions into a bitmask.
+//!
+//! ```
+//! use behavioral::common::event::Event;
+//! use behavioral::window_funnel::WindowFunnelState;
+//!
+//! // window_funnel(INTERVAL '1 hour', ts, view, cart, purchase)
+//! let mut state = WindowFunnelState::new();
+//! state.window_size_us = 3_600_000_000;
+//! state.params_set = true;
+//! state.update(Event::new(0, 0b001), 3);
+//! state.update(Event::new(900_000_000, 0b010), 3);
+//! state.update(Event::new(7_200_000_000, 0b100), 3);
+//! assert_eq!(state.finalize(), 2);
+//! ```
+//!
+//! The public items of these modules follow semantic versioning like the
+//! SQL functions; the `ffi` module is private.
 
+pub mod catalog;
 pub mod common;
+pub mod demo_events;
+pub mod event_gap_stats;
+pub mod funnel_step;
+pub mod group_array_sorted;
+pub mod markov_attribution;
+pub mod path_count_sketch;
 pub mod pattern;
 pub mod retention;
+pub mod selfcheck;
 pub mod sequence;
+pub mod sequence_match_debug;
 pub mod sequence_next_node;
+pub mod session_edge;
+pub mod session_position;
 pub mod sessionize;
+pub mod sessionize_approx;
+pub mod touch_attribution;
+pub mod transition_matrix;
 pub mod window_funnel;
+pub mod window_funnel_by;
+pub mod window_funnel_debug;
 
+#[cfg(feature = "ffi")]
 mod ffi;
 
 // Extension entry point generated by `quack_rs::entry_point_v2!`.
@@ -44,6 +156,11 @@ mod ffi;
 //
 // The closure receives a `&Connection` implementing the `Registrar` trait,
 // providing a version-agnostic API for registering all extension components.
+//
+// DuckDB-WASM loads the `wasm32-unknown-emscripten` build through the same
+// symbol, exported from the extension's WebAssembly module, so the entry
+// point has no platform-specific variant.
+#[cfg(feature = "ffi")]
 quack_rs::entry_point_v2!(behavioral_init_c_api, |con| {
     // Safety: `con` is a valid Connection provided by the entry_point_v2!
     // macro via duckdb_connect. The macro's closure runs in an unsafe context.
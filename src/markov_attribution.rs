@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `markov_attribution` — Removal-effect attribution over channel paths.
+//!
+//! Orders each path's touchpoints by timestamp and splits it into journeys:
+//! a journey starts at a virtual `start` state, visits one channel per
+//! touchpoint and ends in `conversion` after a touchpoint flagged as
+//! converted, or in `null` when the path ends without one. The transition
+//! counts of all journeys define a first-order absorbing Markov chain (the
+//! counts of [`transition_matrix`](crate::transition_matrix) plus the three
+//! virtual states).
+//!
+//! The removal effect of a channel is the relative drop of the chain's
+//! conversion probability from `start` when every transition into the channel
+//! is redirected to `null`. Observed conversions are then distributed over the
+//! channels in proportion to their removal effects.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! -- Over all users: one path per user_id
+//! SELECT unnest(markov_attribution(user_id, event_time, channel, purchased))
+//! FROM touchpoints
+//! ```
+//!
+//! # Implementation
+//!
+//! The state collects `(path, timestamp, channel, converted)` touchpoints;
+//! combine concatenates them. Finalize sorts them with the shared presorted
+//! check ([`sort_presorted`]), counts transitions between the `k` channels
+//! and `start`, and solves the absorption probabilities once for the full
+//! chain and once per removed channel by Gaussian elimination: O(k^4) for
+//! `k` distinct channels, independent of the number of touchpoints.
+
+use crate::common::event::sort_presorted;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// One touchpoint: `(path, timestamp, channel, converted)`.
+type Touchpoint<K> = (K, i64, Arc<str>, bool);
+
+/// Attribution result for one channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelAttribution {
+    /// Channel name.
+    pub channel: Arc<str>,
+    /// Relative drop of the conversion probability when the channel is
+    /// removed, in `[0, 1]`.
+    pub removal_effect: f64,
+    /// Share of the observed conversions attributed to the channel.
+    pub conversions: f64,
+}
+
+/// State for the `markov_attribution` aggregate function.
+///
+/// Generic over the path key `K`. The overload without a path column uses
+/// `()`, so the whole group is one path.
+#[derive(Debug, Clone)]
+pub struct MarkovAttributionState<K = ()> {
+    /// Collected touchpoints. Sorted in finalize.
+    touchpoints: Vec<Touchpoint<K>>,
+}
+
+impl<K> Default for MarkovAttributionState<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> MarkovAttributionState<K> {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            touchpoints: Vec::new(),
+        }
+    }
+
+    /// Adds a touchpoint of path `path`.
+    pub fn update(&mut self, path: K, timestamp_us: i64, channel: Arc<str>, converted: bool) {
+        self.touchpoints
+            .push((path, timestamp_us, channel, converted));
+    }
+}
+
+impl<K: Ord + Clone> MarkovAttributionState<K> {
+    /// Combines `other` into `self` by appending its touchpoints.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.touchpoints.extend(other.touchpoints.iter().cloned());
+    }
+
+    /// Returns the attribution of every channel, ordered by channel name.
+    ///
+    /// Touchpoints sharing a timestamp within a path are ordered by channel,
+    /// then non-converting first, so the result does not depend on input
+    /// order. Removal effects and conversions are 0 when nothing converted.
+    #[must_use]
+    pub fn finalize(&mut self) -> Vec<ChannelAttribution> {
+        sort_presorted(&mut self.touchpoints);
+
+        let mut index: BTreeMap<&str, usize> = BTreeMap::new();
+        for (_, _, channel, _) in &self.touchpoints {
+            index.insert(channel, 0);
+        }
+        for (i, slot) in index.values_mut().enumerate() {
+            *slot = i;
+        }
+        let k = index.len();
+
+        let mut chain = Chain::new(k);
+        let mut conversions = 0u64;
+        let mut prev = chain.start();
+        for (i, (path, _, channel, converted)) in self.touchpoints.iter().enumerate() {
+            if i > 0 && self.touchpoints[i - 1].0 != *path {
+                chain.end(prev, false);
+                prev = chain.start();
+            }
+            let state = index[channel.as_ref()];
+            chain.add(prev, state);
+            prev = state;
+            if *converted {
+                chain.end(prev, true);
+                conversions += 1;
+                prev = chain.start();
+            }
+        }
+        chain.end(prev, false);
+
+        let base = chain.conversion_probability(None);
+        let effects: Vec<f64> = (0..k)
+            .map(|c| {
+                if base > 0.0 {
+                    (1.0 - chain.conversion_probability(Some(c)) / base).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let total_effect: f64 = effects.iter().sum();
+
+        index
+            .keys()
+            .zip(effects)
+            .map(|(channel, removal_effect)| ChannelAttribution {
+                channel: Arc::from(*channel),
+                removal_effect,
+                conversions: if total_effect > 0.0 {
+                    conversions as f64 * removal_effect / total_effect
+                } else {
+                    0.0
+                },
+            })
+            .collect()
+    }
+}
+
+/// Transition counts of the absorbing chain. Transient states are the `k`
+/// channels (`0..k`) and `start` (`k`); `conversion` and `null` are absorbing
+/// and only counted per source state.
+struct Chain {
+    /// `transient[i][j]`: transitions from transient state `i` to `j`.
+    transient: Vec<Vec<u64>>,
+    /// Transitions from each transient state to `conversion`.
+    converted: Vec<u64>,
+    /// Transitions from each transient state to `null`.
+    dropped: Vec<u64>,
+}
+
+impl Chain {
+    fn new(channels: usize) -> Self {
+        let n = channels + 1;
+        Self {
+            transient: vec![vec![0; n]; n],
+            converted: vec![0; n],
+            dropped: vec![0; n],
+        }
+    }
+
+    fn start(&self) -> usize {
+        self.converted.len() - 1
+    }
+
+    fn add(&mut self, from: usize, to: usize) {
+        self.transient[from][to] += 1;
+    }
+
+    /// Ends the journey at `from`; a journey still at `start` is empty.
+    fn end(&mut self, from: usize, converted: bool) {
+        if from == self.start() {
+            return;
+        }
+        if converted {
+            self.converted[from] += 1;
+        } else {
+            self.dropped[from] += 1;
+        }
+    }
+
+    /// Probability of reaching `conversion` from `start`, with every
+    /// transition into channel `removed` redirected to `null`.
+    ///
+    /// Solves `x = P x + p_conversion` over the transient states, with
+    /// `x = 0` for the removed channel and for states never left.
+    fn conversion_probability(&self, removed: Option<usize>) -> f64 {
+        let n = self.converted.len();
+        // Augmented system (I - P) x = p_conversion, one row per state.
+        let mut rows: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                let mut row = vec![0.0; n + 1];
+                row[i] = 1.0;
+                let out: u64 =
+                    self.transient[i].iter().sum::<u64>() + self.converted[i] + self.dropped[i];
+                if out == 0 || removed == Some(i) {
+                    return row;
+                }
+                let out = out as f64;
+                for (j, &count) in self.transient[i].iter().enumerate() {
+                    row[j] -= count as f64 / out;
+                }
+                row[n] = self.converted[i] as f64 / out;
+                row
+            })
+            .collect();
+
+        // Gaussian elimination with partial pivoting. Every state that is
+        // left reaches `conversion` or `null` (each journey ends in one), so
+        // the system is non-singular.
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|&a, &b| rows[a][col].abs().total_cmp(&rows[b][col].abs()))
+                .unwrap_or(col);
+            rows.swap(col, pivot);
+            let p = rows[col][col];
+            if p.abs() < f64::EPSILON {
+                continue;
+            }
+            let pivot_row = rows[col].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r == col || row[col] == 0.0 {
+                    continue;
+                }
+                let factor = row[col] / p;
+                for (value, pivot_value) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+        let start = n - 1;
+        let p = rows[start][start];
+        if p.abs() < f64::EPSILON {
+            0.0
+        } else {
+            rows[start][n] / p
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribution(state: &mut MarkovAttributionState<i64>) -> Vec<(String, f64, f64)> {
+        state
+            .finalize()
+            .into_iter()
+            .map(|a| (a.channel.to_string(), a.removal_effect, a.conversions))
+            .collect()
+    }
+
+    fn state(touchpoints: &[(i64, i64, &str, bool)]) -> MarkovAttributionState<i64> {
+        let mut s = MarkovAttributionState::new();
+        for &(path, ts, channel, converted) in touchpoints {
+            s.update(path, ts, Arc::from(channel), converted);
+        }
+        s
+    }
+
+    fn assert_close(actual: &[(String, f64, f64)], expected: &[(&str, f64, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert_eq!(a.0, e.0);
+            assert!((a.1 - e.1).abs() < 1e-9, "{actual:?}");
+            assert!((a.2 - e.2).abs() < 1e-9, "{actual:?}");
+        }
+    }
+
+    #[test]
+    fn test_textbook_example() {
+        // Journeys: c1 -> c2 -> conversion, c1 -> null, c2 -> c3 -> conversion.
+        // Base: 2/3 * P(c1) + 1/3 * P(c2) = 2/3 * 1/2 + 1/3 * 1 = 2/3.
+        // Without c1: 1/3, effect 1/2. Without c2: 0, effect 1.
+        // Without c3: P(c2) = 1/2, P(c1) = 1/4, 1/3, effect 1/2.
+        let mut s = state(&[
+            (1, 1, "c1", false),
+            (1, 2, "c2", true),
+            (2, 1, "c1", false),
+            (3, 1, "c2", false),
+            (3, 2, "c3", true),
+        ]);
+        assert_close(
+            &attribution(&mut s),
+            &[("c1", 0.5, 0.5), ("c2", 1.0, 1.0), ("c3", 0.5, 0.5)],
+        );
+    }
+
+    #[test]
+    fn test_single_channel_gets_every_conversion() {
+        let mut s = state(&[(1, 1, "email", true), (2, 1, "email", false)]);
+        assert_close(&attribution(&mut s), &[("email", 1.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_no_conversions() {
+        let mut s = state(&[(1, 1, "a", false), (1, 2, "b", false)]);
+        assert_close(&attribution(&mut s), &[("a", 0.0, 0.0), ("b", 0.0, 0.0)]);
+        assert!(MarkovAttributionState::<()>::new().finalize().is_empty());
+    }
+
+    #[test]
+    fn test_conversion_restarts_journey() {
+        // One path, two journeys: a -> conversion, then b -> conversion.
+        // Each channel alone carries half the chain.
+        let mut s = state(&[(1, 1, "a", true), (1, 2, "b", true)]);
+        assert_close(&attribution(&mut s), &[("a", 0.5, 1.0), ("b", 0.5, 1.0)]);
+    }
+
+    #[test]
+    fn test_cycles_converge() {
+        // a -> b -> a -> b -> conversion exercises a cycle between channels.
+        let mut s = state(&[
+            (1, 1, "a", false),
+            (1, 2, "b", false),
+            (1, 3, "a", false),
+            (1, 4, "b", true),
+            (2, 1, "a", false),
+        ]);
+        let result = attribution(&mut s);
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|r| (r.1 - 1.0).abs() < 1e-9));
+        assert!((result.iter().map(|r| r.2).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_combine_matches_sequential_for_every_split() {
+        let touchpoints = [
+            (2, 3, "c3", true),
+            (1, 1, "c1", false),
+            (3, 1, "c1", false),
+            (2, 1, "c2", false),
+            (1, 2, "c2", true),
+        ];
+        let whole = attribution(&mut state(&touchpoints));
+        for split in 0..=touchpoints.len() {
+            let mut left = state(&touchpoints[..split]);
+            left.combine_in_place(&state(&touchpoints[split..]));
+            assert_eq!(attribution(&mut left), whole, "split {split}");
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `path_count_sketch` — Approximate number of distinct path prefixes.
+//!
+//! A path is the sequence of event values of one path key (typically a
+//! user) in timestamp order. The aggregate returns an estimate of how many
+//! distinct prefixes of length `depth` the group's paths have, which bounds
+//! the output of flow analyses such as
+//! [`sequence_next_node`](crate::sequence_next_node) or
+//! [`transition_matrix`](crate::transition_matrix) before they run. Paths
+//! with fewer than `depth` events count with all their events.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! -- Distinct three-page landing paths over all users
+//! SELECT path_count_sketch(user_id, event_time, page, 3)
+//! FROM events
+//! ```
+//!
+//! Without a path column, as in `markov_attribution`, the whole group is one
+//! path.
+//!
+//! # Implementation
+//!
+//! Values are hashed on arrival. The state keeps, per path key, the `depth`
+//! earliest `(timestamp, value hash)` pairs, so its size is O(paths x depth)
+//! whatever the number of events, and combine merges the prefixes of keys
+//! present in both states. Finalize hashes each prefix and counts the prefix
+//! hashes in a [`HyperLogLog`] sketch, in fixed memory however many of them
+//! are distinct.
+
+use crate::common::sketch::{hash_sequence, hash_str, HyperLogLog};
+use std::collections::BTreeMap;
+
+/// State for the `path_count_sketch` aggregate function.
+///
+/// Generic over the path key `K`. The overload without a path column uses
+/// `()`, so the whole group is one path.
+#[derive(Debug, Clone)]
+pub struct PathCountSketchState<K = ()> {
+    /// Number of leading events kept per path; 0 until the first update.
+    pub depth: usize,
+    /// Per path, its earliest `(timestamp, value hash)` pairs in order, at
+    /// most `depth` of them.
+    prefixes: BTreeMap<K, Vec<(i64, u64)>>,
+}
+
+impl<K> Default for PathCountSketchState<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> PathCountSketchState<K> {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            depth: 0,
+            prefixes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Ord + Clone> PathCountSketchState<K> {
+    /// Adds the event with value `value` at `timestamp_us` to path `path`.
+    /// `depth` must have been set.
+    pub fn update(&mut self, path: K, timestamp_us: i64, value: &str) {
+        let depth = self.depth;
+        insert_event(
+            self.prefixes.entry(path).or_default(),
+            (timestamp_us, hash_str(value)),
+            depth,
+        );
+    }
+
+    /// Combines `other` into `self`, keeping the `depth` earliest events of
+    /// every path. A zero-initialized target takes the depth of `other`.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        if self.depth == 0 {
+            self.depth = other.depth;
+        }
+        let depth = self.depth;
+        for (path, events) in &other.prefixes {
+            let prefix = self.prefixes.entry(path.clone()).or_default();
+            for &event in events {
+                insert_event(prefix, event, depth);
+            }
+        }
+    }
+
+    /// Estimated number of distinct path prefixes, 0 without events.
+    #[must_use]
+    pub fn finalize(&self) -> i64 {
+        let mut sketch = HyperLogLog::new();
+        for prefix in self.prefixes.values() {
+            sketch.insert(hash_sequence(prefix.iter().map(|&(_, hash)| hash)));
+        }
+        sketch.estimate().round() as i64
+    }
+}
+
+/// Inserts `event` into the sorted `prefix` and drops events past `depth`.
+///
+/// Events sharing a timestamp are ordered by value hash, so the prefix does
+/// not depend on input order.
+fn insert_event(prefix: &mut Vec<(i64, u64)>, event: (i64, u64), depth: usize) {
+    if prefix.len() >= depth && prefix.last().is_some_and(|&last| event >= last) {
+        return;
+    }
+    let at = prefix.partition_point(|&kept| kept <= event);
+    prefix.insert(at, event);
+    prefix.truncate(depth);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(depth: usize, events: &[(i64, i64, &str)]) -> PathCountSketchState<i64> {
+        let mut s = PathCountSketchState::new();
+        s.depth = depth;
+        for &(path, ts, value) in events {
+            s.update(path, ts, value);
+        }
+        s
+    }
+
+    /// Four users; users 1 and 2 share the landing path home > search.
+    const EVENTS: [(i64, i64, &str); 9] = [
+        (1, 0, "home"),
+        (1, 1, "search"),
+        (1, 2, "cart"),
+        (2, 5, "home"),
+        (2, 6, "search"),
+        (2, 7, "blog"),
+        (3, 0, "home"),
+        (3, 1, "blog"),
+        (4, 0, "pricing"),
+    ];
+
+    #[test]
+    fn test_empty_state() {
+        let mut s = PathCountSketchState::<i64>::new();
+        s.depth = 3;
+        assert_eq!(s.finalize(), 0);
+    }
+
+    #[test]
+    fn test_counts_distinct_prefixes_of_depth() {
+        assert_eq!(state(1, &EVENTS).finalize(), 2);
+        assert_eq!(state(2, &EVENTS).finalize(), 3);
+        assert_eq!(state(3, &EVENTS).finalize(), 4);
+    }
+
+    #[test]
+    fn test_prefix_follows_timestamps_not_input_order() {
+        let mut reversed = EVENTS;
+        reversed.reverse();
+        assert_eq!(state(2, &reversed).finalize(), 3);
+    }
+
+    #[test]
+    fn test_ties_do_not_depend_on_input_order() {
+        let a = state(
+            1,
+            &[
+                (1, 0, "home"),
+                (1, 0, "blog"),
+                (2, 0, "blog"),
+                (2, 0, "home"),
+            ],
+        );
+        assert_eq!(a.finalize(), 1);
+    }
+
+    #[test]
+    fn test_short_path_differs_from_its_extension() {
+        let s = state(3, &[(1, 0, "home"), (2, 0, "home"), (2, 1, "home")]);
+        assert_eq!(s.finalize(), 2);
+    }
+
+    #[test]
+    fn test_without_path_key_group_is_one_path() {
+        let mut s = PathCountSketchState::<()>::new();
+        s.depth = 2;
+        for (ts, value) in [(2, "cart"), (0, "home"), (1, "search")] {
+            s.update((), ts, value);
+        }
+        assert_eq!(s.finalize(), 1);
+        assert_eq!(s.prefixes[&()].len(), 2);
+    }
+
+    #[test]
+    fn test_combine_matches_single_state_for_every_split() {
+        let whole = state(2, &EVENTS);
+        for split in 0..=EVENTS.len() {
+            let mut left = state(2, &EVENTS[..split]);
+            left.combine_in_place(&state(2, &EVENTS[split..]));
+            assert_eq!(left.prefixes, whole.prefixes, "split {split}");
+        }
+    }
+
+    #[test]
+    fn test_combine_zero_target_propagates_depth() {
+        let mut target = PathCountSketchState::<i64>::new();
+        target.combine_in_place(&state(2, &EVENTS));
+        assert_eq!(target.depth, 2);
+        target.combine_in_place(&state(2, &[(1, -1, "ad")]));
+        assert_eq!(target.finalize(), 4);
+    }
+
+    #[test]
+    fn test_many_paths_within_error() {
+        let mut s = PathCountSketchState::<i64>::new();
+        s.depth = 2;
+        for user in 0..20_000 {
+            s.update(user, 0, "home");
+            s.update(user, 1, &format!("page{}", user % 5_000));
+        }
+        let estimate = s.finalize();
+        assert!((estimate - 5_000).abs() <= 250, "{estimate}");
+    }
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Process-wide cache of compiled patterns.
+//!
+//! Every group of a `sequence_*` aggregate finalizes its own state, and each
+//! state used to parse the pattern string itself: with 10M groups and one
+//! constant pattern, the same string was parsed 10M times. [`compile`] keeps
+//! the most recently used [`CACHE_CAPACITY`] patterns behind a mutex and hands
+//! out shared [`Arc`]s, so each distinct pattern is parsed once per process
+//! while it stays in use.
+//!
+//! Only successful compilations are cached. An invalid pattern fails the query
+//! on its first occurrence, so there is nothing to gain from remembering it.
+//! The lock is held for a lookup only; parsing happens outside it, so two
+//! threads may occasionally parse the same new pattern concurrently, and the
+//! first insert wins.
+
+use crate::pattern::parser::{parse_pattern, CompiledPattern, PatternError};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Number of distinct patterns kept. Queries use a handful of constant
+/// patterns, so this only matters for pattern columns with many values.
+pub const CACHE_CAPACITY: usize = 64;
+
+/// Compiled patterns, least recently used first.
+static CACHE: Mutex<Vec<(String, Arc<CompiledPattern>)>> = Mutex::new(Vec::new());
+
+/// Returns the compiled form of `pattern`, parsing it only on a cache miss.
+///
+/// # Errors
+///
+/// Returns `PatternError` if the pattern string is invalid.
+pub fn compile(pattern: &str) -> Result<Arc<CompiledPattern>, PatternError> {
+    if let Some(compiled) = lookup(pattern) {
+        return Ok(compiled);
+    }
+    let compiled = Arc::new(parse_pattern(pattern)?);
+    let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    let compiled = touch(&mut cache, pattern).unwrap_or_else(|| {
+        if cache.len() == CACHE_CAPACITY {
+            cache.remove(0);
+        }
+        cache.push((pattern.to_string(), Arc::clone(&compiled)));
+        compiled
+    });
+    drop(cache);
+    Ok(compiled)
+}
+
+/// Returns the cached compilation of `pattern`, marking it most recently used.
+fn lookup(pattern: &str) -> Option<Arc<CompiledPattern>> {
+    let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    let compiled = touch(&mut cache, pattern);
+    drop(cache);
+    compiled
+}
+
+/// Moves the entry for `pattern` to the most recently used end of `cache`
+/// and returns its compilation.
+fn touch(
+    cache: &mut Vec<(String, Arc<CompiledPattern>)>,
+    pattern: &str,
+) -> Option<Arc<CompiledPattern>> {
+    let index = cache.iter().position(|(text, _)| text == pattern)?;
+    let entry = cache.remove(index);
+    let compiled = Arc::clone(&entry.1);
+    cache.push(entry);
+    Some(compiled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(pattern: &str) -> bool {
+        CACHE
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(text, _)| text == pattern)
+    }
+
+    #[test]
+    fn test_compile_returns_shared_pattern() {
+        let first = compile("(?1)(?t<=7)(?2).*(?3)").unwrap();
+        let second = compile("(?1)(?t<=7)(?2).*(?3)").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(
+            first.steps,
+            parse_pattern("(?1)(?t<=7)(?2).*(?3)").unwrap().steps
+        );
+    }
+
+    #[test]
+    fn test_compile_does_not_cache_errors() {
+        let err = compile("(?1)(?t=>9)").unwrap_err();
+        assert_eq!(err, parse_pattern("(?1)(?t=>9)").unwrap_err());
+        assert!(!cached("(?1)(?t=>9)"));
+    }
+
+    #[test]
+    fn test_least_recently_used_pattern_is_evicted() {
+        // Other tests share the cache, so only the order of this test's own
+        // patterns is asserted.
+        let kept = compile("(?9).(?8)").unwrap();
+        compile("(?7).(?6)").unwrap();
+        for n in 0..CACHE_CAPACITY - 1 {
+            compile(&format!("(?1)(?t<={n}us)(?2)")).unwrap();
+            compile("(?9).(?8)").unwrap();
+        }
+        assert!(cached("(?9).(?8)"));
+        assert!(!cached("(?7).(?6)"));
+        assert!(CACHE.lock().unwrap().len() <= CACHE_CAPACITY);
+        assert!(Arc::ptr_eq(&kept, &compile("(?9).(?8)").unwrap()));
+    }
+}
@@ -5,15 +5,41 @@
 //!
 //! Executes compiled patterns against sorted event streams using a
 //! non-deterministic finite automaton (NFA) with backtracking for `.*` steps.
+//!
+//! Backtracking is memoized: the outcome of an NFA state depends only on its
+//! event position, pattern step and last matched timestamp, so a state that
+//! has been fully explored without a match is never expanded again (see
+//! [`FailedStates`]). Execution is polynomial for every pattern — patterns
+//! such as `(?1).*.*.*.(?2)` cannot blow up, and are never cut short.
+//!
+//! # Anchors
+//!
+//! A pattern anchored with `^` is only tried from the first event, and one
+//! anchored with `$` only completes when its last step consumed the last
+//! event, so either anchor allows at most one match. Anchored patterns always
+//! use the NFA, where the end check is one comparison on completion.
+//!
+//! # Match Windows
+//!
+//! [`execute_pattern_within`] additionally bounds the duration of each match:
+//! the events consumed by `(?N)`, `(?!N)` and `.` steps must lie within
+//! `window_us` of the first of them. NFA states then carry the first matched
+//! timestamp, which the memo keys on, and a state whose next match would
+//! exceed the window dies like a failed condition. Windowed execution always
+//! uses the NFA: the fast paths' greedy scans would keep an early first step
+//! that a later one could replace.
+//!
+//! A `(?T...)` constraint checks the same first matched timestamp inside the
+//! pattern: `(?1).*(?2).*(?T<=3600)(?3)` is `(?1).*(?2).*(?3)` under a one-hour
+//! window ending at the `(?3)` event. NFA states always carry the first
+//! timestamp; the memo keys on it only ahead of a `(?T...)` step or under a
+//! window.
 
 use crate::common::event::Event;
-use crate::common::timestamp::MICROS_PER_SECOND;
+use crate::common::event_run::RunLengthBuffer;
+use crate::common::timestamp::{elapsed_exceeds, elapsed_wide, MICROS_PER_SECOND};
 use crate::pattern::parser::{CompiledPattern, PatternStep};
-
-/// Maximum number of active NFA states before aborting execution.
-/// Prevents pathological patterns (e.g., `.*.*.*.*`) from consuming
-/// unbounded memory.
-const MAX_NFA_STATES: usize = 10_000;
+use std::collections::HashSet;
 
 /// Result of executing a pattern against an event stream.
 #[derive(Debug, Clone)]
@@ -23,6 +49,9 @@ pub struct MatchResult {
     pub matched: bool,
     /// Number of non-overlapping full matches found.
     pub count: usize,
+    /// NFA states expanded, each at most once thanks to the memo; 0 when a
+    /// fast path ran.
+    pub states_expanded: usize,
 }
 
 /// Executes a compiled pattern against a sorted event stream.
@@ -53,21 +82,162 @@ pub fn execute_pattern(
         return MatchResult {
             matched: false,
             count: 0,
+            states_expanded: 0,
+        };
+    }
+
+    execute_pattern_with(pattern, events, count_all, None, &mut |_, _| {})
+}
+
+/// Executes a compiled pattern like [`execute_pattern`], counting only
+/// matches whose first and last matched events are at most `window_us`
+/// apart.
+///
+/// `None` places no bound and is identical to [`execute_pattern`]. Events
+/// must be sorted by timestamp (ascending) before calling.
+#[must_use]
+pub fn execute_pattern_within(
+    pattern: &CompiledPattern,
+    events: &[Event],
+    count_all: bool,
+    window_us: Option<i64>,
+) -> MatchResult {
+    if events.is_empty() || pattern.steps.is_empty() {
+        return MatchResult {
+            matched: false,
+            count: 0,
+            states_expanded: 0,
         };
     }
 
+    execute_pattern_with(pattern, events, count_all, window_us, &mut |_, _| {})
+}
+
+/// Inclusive `(first, last)` event indices of one match.
+pub type MatchSpan = (usize, usize);
+
+/// Executes a compiled pattern and returns the span of every non-overlapping
+/// match.
+///
+/// Uses the same executor as `sequence_count`, so the number of spans always
+/// equals [`execute_pattern`]'s `count` with `count_all = true`. A span runs
+/// from the first event a step other than `.*` or `(?!N)*` consumed to the
+/// last, so a leading `.*` does not move the start; a match that consumed no
+/// such event starts and ends where the search resumed. Events must be sorted
+/// by timestamp (ascending) before calling.
+#[must_use]
+pub fn execute_pattern_spans(pattern: &CompiledPattern, events: &[Event]) -> Vec<MatchSpan> {
+    let mut spans = Vec::new();
+    if events.is_empty() || pattern.steps.is_empty() {
+        return spans;
+    }
+    execute_pattern_with(pattern, events, true, None, &mut |first, last| {
+        spans.push((first, last.max(first)));
+    });
+    spans
+}
+
+/// Dispatches to a fast path or the NFA, calling `on_match(first, last)` for
+/// each match found. `execute_pattern` passes a no-op, which monomorphizes
+/// away.
+fn execute_pattern_with(
+    pattern: &CompiledPattern,
+    events: &[Event],
+    count_all: bool,
+    window_us: Option<i64>,
+    on_match: &mut impl FnMut(usize, usize),
+) -> MatchResult {
+    if window_us.is_some() {
+        return execute_pattern_nfa(pattern, events, count_all, window_us, on_match);
+    }
+
     // Try fast paths for common pattern shapes before falling back to NFA.
     match classify_pattern(pattern) {
         PatternShape::AdjacentConditions(ref conds) => {
-            return fast_adjacent(events, conds, count_all);
+            return fast_adjacent(events, conds, count_all, on_match);
         }
         PatternShape::WildcardSeparated(ref conds) => {
-            return fast_wildcard(events, conds, count_all);
+            return fast_wildcard(events, conds, count_all, on_match);
         }
         PatternShape::Complex => {} // Fall through to NFA
     }
 
-    execute_pattern_nfa(pattern, events, count_all)
+    execute_pattern_nfa(pattern, events, count_all, None, on_match)
+}
+
+/// Executes a compiled pattern against run-length encoded events.
+///
+/// Runs must be sorted (see [`RunLengthBuffer::sort`]). Each run is expanded
+/// to at most [`run_expansion_cap`] events before matching, so memory stays
+/// proportional to the number of runs rather than events.
+#[must_use]
+pub fn execute_pattern_runs(
+    pattern: &CompiledPattern,
+    runs: &RunLengthBuffer,
+    count_all: bool,
+) -> MatchResult {
+    let events = runs.expand(run_expansion_cap(pattern, count_all));
+    execute_pattern(pattern, &events, count_all)
+}
+
+/// Number of events per run needed to evaluate `pattern` over run-length
+/// encoded input.
+///
+/// A single match consumes at most one event per `(?N)`, `(?!N)` or `.` step,
+/// and `.*` and `(?!N)*` may match zero events, so for existence matching any run longer than that
+/// can be shortened without changing the result. Counting non-overlapping
+/// matches depends on the full run length, so `count_all` disables the cap.
+#[must_use]
+pub fn run_expansion_cap(pattern: &CompiledPattern, count_all: bool) -> usize {
+    if count_all {
+        return usize::MAX;
+    }
+    pattern
+        .steps
+        .iter()
+        .filter(|step| {
+            matches!(
+                step,
+                PatternStep::Condition(_) | PatternStep::NotCondition(_) | PatternStep::OneEvent
+            )
+        })
+        .count()
+}
+
+/// Execution strategy chosen for a pattern without a match window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionPath {
+    /// Adjacent conditions only, e.g. `(?1)(?2)(?3)`: sliding-window scan.
+    Adjacent,
+    /// Conditions separated by `.*`, e.g. `(?1).*(?2)`: single forward scan.
+    Wildcard,
+    /// Everything else: the memoized NFA.
+    Nfa,
+}
+
+impl ExecutionPath {
+    /// Returns the lowercase name shown by `behavioral_explain`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Adjacent => "adjacent",
+            Self::Wildcard => "wildcard",
+            Self::Nfa => "nfa",
+        }
+    }
+}
+
+/// Returns the path [`execute_pattern`] takes for `pattern`.
+///
+/// Windowed execution ([`execute_pattern_within`] with a window) always
+/// takes [`ExecutionPath::Nfa`].
+#[must_use]
+pub fn execution_path(pattern: &CompiledPattern) -> ExecutionPath {
+    match classify_pattern(pattern) {
+        PatternShape::AdjacentConditions(_) => ExecutionPath::Adjacent,
+        PatternShape::WildcardSeparated(_) => ExecutionPath::Wildcard,
+        PatternShape::Complex => ExecutionPath::Nfa,
+    }
 }
 
 /// Pattern shape classification for fast-path dispatch.
@@ -76,7 +246,8 @@ enum PatternShape {
     AdjacentConditions(Vec<usize>),
     /// Conditions separated by `.*` — greedy forward scan.
     WildcardSeparated(Vec<usize>),
-    /// Requires full NFA (time constraints, `.`, mixed shapes).
+    /// Requires full NFA (time constraints, `.`, negated conditions,
+    /// anchors).
     Complex,
 }
 
@@ -84,22 +255,41 @@ enum PatternShape {
 ///
 /// Returns `AdjacentConditions` if all steps are `Condition` (no wildcards).
 /// Returns `WildcardSeparated` if the pattern alternates `Condition` and
-/// `AnyEvents` steps (e.g., `(?1).*(?2).*(?3)`).
+/// `AnyEvents` steps (e.g., `(?1).*(?2).*(?3)`), with at least one `.*`
+/// between every two conditions.
 /// Returns `Complex` for patterns with time constraints, `.` (`OneEvent`),
-/// or mixed structures.
+/// negated conditions, anchors, or mixed structures.
 fn classify_pattern(pattern: &CompiledPattern) -> PatternShape {
+    if pattern.anchored_start || pattern.anchored_end {
+        return PatternShape::Complex;
+    }
     let mut conditions = Vec::new();
     let mut has_any_events = false;
     let mut has_only_conditions = true;
+    // Two conditions with no `.*` between them must match adjacent events,
+    // which the wildcard scan does not check.
+    let mut has_adjacent_pair = false;
+    let mut previous_was_condition = false;
 
     for step in &pattern.steps {
         match step {
-            PatternStep::Condition(idx) => conditions.push(*idx),
+            PatternStep::Condition(idx) => {
+                has_adjacent_pair |= previous_was_condition;
+                previous_was_condition = true;
+                conditions.push(*idx);
+            }
             PatternStep::AnyEvents => {
                 has_any_events = true;
                 has_only_conditions = false;
+                previous_was_condition = false;
             }
-            PatternStep::OneEvent | PatternStep::TimeConstraint(_, _) => {
+            PatternStep::OneEvent
+            | PatternStep::NotCondition(_)
+            | PatternStep::AnyNotCondition(_)
+            | PatternStep::TimeConstraint(_, _)
+            | PatternStep::TimeConstraintMicros(_, _)
+            | PatternStep::StartTimeConstraint(_, _)
+            | PatternStep::StartTimeConstraintMicros(_, _) => {
                 return PatternShape::Complex;
             }
         }
@@ -114,9 +304,9 @@ fn classify_pattern(pattern: &CompiledPattern) -> PatternShape {
     }
 
     // Has AnyEvents — check if it's the standard wildcard-separated form.
-    // Accept any mix of Condition and AnyEvents (consecutive AnyEvents is
-    // just .*.* which matches any number of events, same as .*).
-    if has_any_events {
+    // Consecutive AnyEvents is just .*.* which matches any number of events,
+    // same as .*; a mix such as (?1)(?2).*(?3) needs the NFA.
+    if has_any_events && !has_adjacent_pair {
         return PatternShape::WildcardSeparated(conditions);
     }
 
@@ -127,12 +317,18 @@ fn classify_pattern(pattern: &CompiledPattern) -> PatternShape {
 ///
 /// Scans with a sliding window of `k` events, checking each window for a
 /// consecutive match of all conditions. O(n) time, O(1) space.
-fn fast_adjacent(events: &[Event], conditions: &[usize], count_all: bool) -> MatchResult {
+fn fast_adjacent(
+    events: &[Event],
+    conditions: &[usize],
+    count_all: bool,
+    on_match: &mut impl FnMut(usize, usize),
+) -> MatchResult {
     let k = conditions.len();
     if events.len() < k {
         return MatchResult {
             matched: false,
             count: 0,
+            states_expanded: 0,
         };
     }
 
@@ -149,10 +345,12 @@ fn fast_adjacent(events: &[Event], conditions: &[usize], count_all: bool) -> Mat
         }
         if matched {
             total += 1;
+            on_match(i, i + k - 1);
             if !count_all {
                 return MatchResult {
                     matched: true,
                     count: 1,
+                    states_expanded: 0,
                 };
             }
             i += k; // Non-overlapping: advance past the match
@@ -162,6 +360,7 @@ fn fast_adjacent(events: &[Event], conditions: &[usize], count_all: bool) -> Mat
     MatchResult {
         matched: total > 0,
         count: total,
+        states_expanded: 0,
     }
 }
 
@@ -170,20 +369,31 @@ fn fast_adjacent(events: &[Event], conditions: &[usize], count_all: bool) -> Mat
 /// Single-pass linear scan: maintains a step counter and advances through
 /// conditions as matching events are found. O(n) time, O(1) space.
 /// Equivalent to lazy NFA matching for this pattern shape.
-fn fast_wildcard(events: &[Event], conditions: &[usize], count_all: bool) -> MatchResult {
+fn fast_wildcard(
+    events: &[Event],
+    conditions: &[usize],
+    count_all: bool,
+    on_match: &mut impl FnMut(usize, usize),
+) -> MatchResult {
     let k = conditions.len();
     let mut total = 0;
     let mut step = 0;
+    let mut first = 0;
 
-    for event in events {
+    for (i, event) in events.iter().enumerate() {
         if event.condition(conditions[step]) {
+            if step == 0 {
+                first = i;
+            }
             step += 1;
             if step >= k {
                 total += 1;
+                on_match(first, i);
                 if !count_all {
                     return MatchResult {
                         matched: true,
                         count: 1,
+                        states_expanded: 0,
                     };
                 }
                 step = 0; // Reset for next non-overlapping match
@@ -194,17 +404,21 @@ fn fast_wildcard(events: &[Event], conditions: &[usize], count_all: bool) -> Mat
     MatchResult {
         matched: total > 0,
         count: total,
+        states_expanded: 0,
     }
 }
 
 /// Full NFA-based pattern execution for complex patterns.
 ///
 /// Used when the pattern contains time constraints, `.` (`OneEvent`),
-/// or other structures that cannot be handled by the fast paths.
+/// negated conditions, or other structures that cannot be handled by the
+/// fast paths, and for every pattern when a match window is given.
 fn execute_pattern_nfa(
     pattern: &CompiledPattern,
     events: &[Event],
     count_all: bool,
+    window_us: Option<i64>,
+    on_match: &mut impl FnMut(usize, usize),
 ) -> MatchResult {
     let mut total_matches = 0;
     let mut search_start = 0;
@@ -214,73 +428,116 @@ fn execute_pattern_nfa(
     // cleared (retaining capacity) at the start of each try_match_from call.
     let mut states = Vec::with_capacity(pattern.steps.len() * 2);
 
+    let mut failed = FailedStates::new(pattern, events.len(), window_us.is_some());
+    let entry = pattern.entry_condition();
+
     while search_start < events.len() {
-        if let Some(match_end) = try_match_from(pattern, events, search_start, &mut states) {
+        // A pattern starting with `(?N)` can only match from an event where
+        // condition N holds: skip the start positions before the next one
+        if let Some(cond) = entry {
+            match next_entry(events, search_start, cond) {
+                Some(next) if next == search_start || !pattern.anchored_start => {
+                    search_start = next;
+                }
+                _ => break,
+            }
+        }
+        if let Some((match_start, match_end)) = try_match_from(
+            pattern,
+            events,
+            search_start,
+            window_us,
+            &mut states,
+            &mut failed,
+        ) {
             total_matches += 1;
+            on_match(match_start.unwrap_or(search_start), match_end);
             if !count_all {
                 return MatchResult {
                     matched: true,
                     count: 1,
+                    states_expanded: failed.expanded,
                 };
             }
-            // For non-overlapping count, advance past this match
-            search_start = match_end + 1;
+            // For non-overlapping count, advance past this match, and at
+            // least one event past a match that consumed none (e.g. `.*`)
+            search_start = match_end.max(search_start) + 1;
+            failed.forget_event(search_start);
         } else {
             search_start += 1;
         }
+        if pattern.anchored_start {
+            // `^`: only the match starting at the first event counts
+            break;
+        }
     }
 
     MatchResult {
         matched: total_matches > 0,
         count: total_matches,
+        states_expanded: failed.expanded,
     }
 }
 
 /// Tries to match the full pattern starting from the given event index.
 ///
-/// Returns `Some(end_index)` if a full match is found (the index of the last
-/// matched event), or `None` if no match is possible from this starting position.
+/// Returns `Some((first_index, end_index))` if a full match is found: the
+/// index of the first event matched by a consuming step (`None` if the match
+/// matched none, e.g. `.*`) and that of the last consumed event. Returns
+/// `None` if no match is possible from this starting position.
 ///
 /// The `states` Vec is pre-allocated by the caller and reused across calls
 /// to avoid per-position heap allocation (see `execute_pattern` for rationale).
+/// `failed` carries the states known not to match across calls.
 fn try_match_from(
     pattern: &CompiledPattern,
     events: &[Event],
     start: usize,
+    window_us: Option<i64>,
     states: &mut Vec<NfaState>,
-) -> Option<usize> {
+    failed: &mut FailedStates,
+) -> Option<(Option<usize>, usize)> {
     states.clear();
     states.push(NfaState {
         event_idx: start,
         step_idx: 0,
         last_match_ts: None,
+        first_match_ts: None,
+        first_match_idx: None,
     });
 
-    let mut iterations = 0;
-
     while let Some(state) = states.pop() {
-        iterations += 1;
-        if iterations > MAX_NFA_STATES {
-            // Prevent runaway matching on pathological patterns
-            return None;
+        if !failed.insert(
+            state.event_idx,
+            state.step_idx,
+            state.last_match_ts,
+            state.first_match_ts,
+        ) {
+            // Already expanded: its subtree holds no match
+            continue;
         }
 
         // Successfully matched all steps
         if state.step_idx >= pattern.steps.len() {
+            if pattern.anchored_end && state.event_idx < events.len() {
+                // `$`: events remain after the match
+                continue;
+            }
             // Return the index of the last consumed event (one before current)
-            return Some(if state.event_idx > 0 {
+            let end = if state.event_idx > 0 {
                 state.event_idx - 1
             } else {
                 0
-            });
+            };
+            return Some((state.first_match_idx, end));
         }
 
         // No more events to consume
         if state.event_idx >= events.len() {
             // Time constraints and AnyEvents can still succeed at the end
             match &pattern.steps[state.step_idx] {
-                PatternStep::AnyEvents => {
-                    // .* can match zero events, advance to next step
+                PatternStep::AnyEvents | PatternStep::AnyNotCondition(_) => {
+                    // .* and (?!N)* can match zero events, advance to next step
                     states.push(NfaState {
                         step_idx: state.step_idx + 1,
                         ..state
@@ -297,11 +554,7 @@ fn try_match_from(
             PatternStep::Condition(cond_idx) => {
                 if event.condition(*cond_idx) {
                     // Condition matched, advance both event and step
-                    states.push(NfaState {
-                        event_idx: state.event_idx + 1,
-                        step_idx: state.step_idx + 1,
-                        last_match_ts: Some(event.timestamp_us),
-                    });
+                    states.extend(state.matched(event.timestamp_us, window_us));
                 }
                 // If condition doesn't match, this state dies (no push)
             }
@@ -322,26 +575,39 @@ fn try_match_from(
             }
             PatternStep::OneEvent => {
                 // . matches exactly one event
+                states.extend(state.matched(event.timestamp_us, window_us));
+            }
+            PatternStep::NotCondition(cond_idx) => {
+                // (?!N) matches exactly one event where condition N is false
+                if !event.condition(*cond_idx) {
+                    states.extend(state.matched(event.timestamp_us, window_us));
+                }
+            }
+            PatternStep::AnyNotCondition(cond_idx) => {
+                // Like .*, but an event where condition N is true cannot be
+                // consumed: the only way forward is to advance the step.
+                if !event.condition(*cond_idx) {
+                    states.push(NfaState {
+                        event_idx: state.event_idx + 1,
+                        ..state
+                    });
+                }
                 states.push(NfaState {
-                    event_idx: state.event_idx + 1,
                     step_idx: state.step_idx + 1,
-                    last_match_ts: Some(event.timestamp_us),
+                    ..state
                 });
             }
-            PatternStep::TimeConstraint(op, threshold_seconds) => {
+            step @ (PatternStep::TimeConstraint(..)
+            | PatternStep::TimeConstraintMicros(..)
+            | PatternStep::StartTimeConstraint(..)
+            | PatternStep::StartTimeConstraintMicros(..)) => {
                 // Time constraint doesn't consume an event, just checks timing
-                if let Some(prev_ts) = state.last_match_ts {
-                    let elapsed_us = event.timestamp_us - prev_ts;
-                    let elapsed_seconds = elapsed_us / MICROS_PER_SECOND;
-                    if op.evaluate(elapsed_seconds, *threshold_seconds) {
-                        // Time constraint satisfied, advance step
-                        states.push(NfaState {
-                            step_idx: state.step_idx + 1,
-                            ..state
-                        });
-                    }
-                } else {
-                    // No previous match timestamp; time constraint is vacuously true
+                if time_constraint_allows(
+                    step,
+                    state.last_match_ts,
+                    state.first_match_ts,
+                    event.timestamp_us,
+                ) {
                     states.push(NfaState {
                         step_idx: state.step_idx + 1,
                         ..state
@@ -361,35 +627,76 @@ fn try_match_from(
 /// matches, `None` if no match is found. Events must be sorted by
 /// timestamp (ascending) before calling.
 pub fn execute_pattern_events(pattern: &CompiledPattern, events: &[Event]) -> Option<Vec<i64>> {
+    execute_pattern_event_indices(pattern, events)
+        .map(|indices| indices.iter().map(|&i| events[i].timestamp_us).collect())
+}
+
+/// Executes a compiled pattern and returns the indices of matched events.
+///
+/// Same matching semantics as [`execute_pattern_events`], but returns the
+/// position in `events` of each event matched by a `(?N)` condition step.
+/// Callers holding per-event payloads in a parallel vector can use the
+/// indices to recover those payloads without a join-back.
+pub fn execute_pattern_event_indices(
+    pattern: &CompiledPattern,
+    events: &[Event],
+) -> Option<Vec<usize>> {
     if events.is_empty() || pattern.steps.is_empty() {
         return None;
     }
 
-    try_match_from_with_timestamps(pattern, events, 0, events.len())
+    try_match_from_with_indices(pattern, events, 0, events.len())
 }
 
 /// Tries to match the full pattern starting from position range `[start, end)`,
-/// collecting timestamps for each `(?N)` condition step.
-fn try_match_from_with_timestamps(
+/// collecting event indices for each `(?N)` condition step.
+fn try_match_from_with_indices(
     pattern: &CompiledPattern,
     events: &[Event],
     search_start: usize,
     search_end: usize,
-) -> Option<Vec<i64>> {
-    for start in search_start..search_end {
-        if let Some(timestamps) = try_match_collecting(pattern, events, start) {
-            return Some(timestamps);
+) -> Option<Vec<usize>> {
+    let mut failed = FailedStates::new(pattern, events.len(), false);
+    let search_end = if pattern.anchored_start {
+        search_end.min(search_start + 1)
+    } else {
+        search_end
+    };
+    let entry = pattern.entry_condition();
+    let mut start = search_start;
+    while start < search_end {
+        if let Some(cond) = entry {
+            match next_entry(events, start, cond) {
+                Some(next) if next < search_end => start = next,
+                _ => return None,
+            }
         }
+        if let Some(indices) = try_match_collecting(pattern, events, start, &mut failed) {
+            return Some(indices);
+        }
+        start += 1;
     }
     None
 }
 
-/// Tries to match from a specific start position, collecting condition timestamps.
+/// Returns the index of the first event at or after `from` where condition
+/// `cond` holds, the next position a pattern with that entry condition can
+/// start matching from.
+#[inline]
+fn next_entry(events: &[Event], from: usize, cond: usize) -> Option<usize> {
+    events[from..]
+        .iter()
+        .position(|event| event.condition(cond))
+        .map(|offset| from + offset)
+}
+
+/// Tries to match from a specific start position, collecting condition event indices.
 fn try_match_collecting(
     pattern: &CompiledPattern,
     events: &[Event],
     start: usize,
-) -> Option<Vec<i64>> {
+    failed: &mut FailedStates,
+) -> Option<Vec<usize>> {
     // Count how many Condition steps are in the pattern
     let num_conditions = pattern
         .steps
@@ -397,31 +704,39 @@ fn try_match_collecting(
         .filter(|s| matches!(s, PatternStep::Condition(_)))
         .count();
 
-    let mut states: Vec<NfaStateWithTimestamps> = vec![NfaStateWithTimestamps {
+    let mut states: Vec<NfaStateWithIndices> = vec![NfaStateWithIndices {
         event_idx: start,
         step_idx: 0,
         last_match_ts: None,
+        first_match_ts: None,
         collected: Vec::with_capacity(num_conditions),
     }];
 
-    let mut iterations = 0;
-
     while let Some(state) = states.pop() {
-        iterations += 1;
-        if iterations > MAX_NFA_STATES {
-            return None;
+        // Whether a state can finish a match does not depend on the indices
+        // collected on the way there.
+        if !failed.insert(
+            state.event_idx,
+            state.step_idx,
+            state.last_match_ts,
+            state.first_match_ts,
+        ) {
+            continue;
         }
 
         // Successfully matched all steps
         if state.step_idx >= pattern.steps.len() {
+            if pattern.anchored_end && state.event_idx < events.len() {
+                continue;
+            }
             return Some(state.collected);
         }
 
         // No more events to consume
         if state.event_idx >= events.len() {
             match &pattern.steps[state.step_idx] {
-                PatternStep::AnyEvents => {
-                    states.push(NfaStateWithTimestamps {
+                PatternStep::AnyEvents | PatternStep::AnyNotCondition(_) => {
+                    states.push(NfaStateWithIndices {
                         step_idx: state.step_idx + 1,
                         ..state
                     });
@@ -436,48 +751,50 @@ fn try_match_collecting(
         match &pattern.steps[state.step_idx] {
             PatternStep::Condition(cond_idx) => {
                 if event.condition(*cond_idx) {
-                    let mut new_collected = state.collected.clone();
-                    new_collected.push(event.timestamp_us);
-                    states.push(NfaStateWithTimestamps {
-                        event_idx: state.event_idx + 1,
-                        step_idx: state.step_idx + 1,
-                        last_match_ts: Some(event.timestamp_us),
-                        collected: new_collected,
-                    });
+                    states.push(state.matched(event.timestamp_us, true));
                 }
             }
             PatternStep::AnyEvents => {
                 // Consume event (stay in same step) — pushed first (lower priority)
-                states.push(NfaStateWithTimestamps {
+                states.push(NfaStateWithIndices {
                     event_idx: state.event_idx + 1,
                     ..state.clone()
                 });
                 // Advance step (lazy) — pushed last (higher priority)
-                states.push(NfaStateWithTimestamps {
+                states.push(NfaStateWithIndices {
                     step_idx: state.step_idx + 1,
                     ..state
                 });
             }
-            PatternStep::OneEvent => {
-                states.push(NfaStateWithTimestamps {
-                    event_idx: state.event_idx + 1,
+            step @ (PatternStep::OneEvent | PatternStep::NotCondition(_)) => {
+                // `.` consumes any event, `(?!N)` one where condition N is false
+                if !matches!(step, PatternStep::NotCondition(c) if event.condition(*c)) {
+                    states.push(state.matched(event.timestamp_us, false));
+                }
+            }
+            PatternStep::AnyNotCondition(cond_idx) => {
+                if !event.condition(*cond_idx) {
+                    states.push(NfaStateWithIndices {
+                        event_idx: state.event_idx + 1,
+                        ..state.clone()
+                    });
+                }
+                states.push(NfaStateWithIndices {
                     step_idx: state.step_idx + 1,
-                    last_match_ts: Some(event.timestamp_us),
-                    collected: state.collected,
+                    ..state
                 });
             }
-            PatternStep::TimeConstraint(op, threshold_seconds) => {
-                if let Some(prev_ts) = state.last_match_ts {
-                    let elapsed_us = event.timestamp_us - prev_ts;
-                    let elapsed_seconds = elapsed_us / MICROS_PER_SECOND;
-                    if op.evaluate(elapsed_seconds, *threshold_seconds) {
-                        states.push(NfaStateWithTimestamps {
-                            step_idx: state.step_idx + 1,
-                            ..state
-                        });
-                    }
-                } else {
-                    states.push(NfaStateWithTimestamps {
+            step @ (PatternStep::TimeConstraint(..)
+            | PatternStep::TimeConstraintMicros(..)
+            | PatternStep::StartTimeConstraint(..)
+            | PatternStep::StartTimeConstraintMicros(..)) => {
+                if time_constraint_allows(
+                    step,
+                    state.last_match_ts,
+                    state.first_match_ts,
+                    event.timestamp_us,
+                ) {
+                    states.push(NfaStateWithIndices {
                         step_idx: state.step_idx + 1,
                         ..state
                     });
@@ -489,22 +806,182 @@ fn try_match_collecting(
     None
 }
 
-/// NFA state that also collects matched condition timestamps.
+/// Evaluates a time constraint step before an event at `ts`, measuring from
+/// the last matched timestamp for `(?t...)` and the first for `(?T...)`.
+/// Without a matched event the constraint is vacuously true.
+#[inline]
+fn time_constraint_allows(
+    step: &PatternStep,
+    last_match_ts: Option<i64>,
+    first_match_ts: Option<i64>,
+    ts: i64,
+) -> bool {
+    let since = if step.is_start_time_constraint() {
+        first_match_ts
+    } else {
+        last_match_ts
+    };
+    since.is_none_or(|since_ts| time_constraint_holds(step, since_ts, ts))
+}
+
+/// Evaluates a time constraint step against the time elapsed from `since_ts`
+/// to `ts`: the previous match for `(?t...)`, the first for `(?T...)`.
+///
+/// [`PatternStep::TimeConstraint`] and [`PatternStep::StartTimeConstraint`]
+/// truncate elapsed time to whole seconds (`ClickHouse` semantics); the
+/// `Micros` variants compare exactly in microseconds. All compare in `i128`,
+/// so timestamps far apart (e.g. `'infinity'`) cannot overflow into a
+/// negative elapsed time.
+#[inline]
+fn time_constraint_holds(step: &PatternStep, since_ts: i64, ts: i64) -> bool {
+    let elapsed_us = elapsed_wide(since_ts, ts);
+    match *step {
+        PatternStep::TimeConstraint(op, threshold_seconds)
+        | PatternStep::StartTimeConstraint(op, threshold_seconds) => op.evaluate(
+            elapsed_us / i128::from(MICROS_PER_SECOND),
+            i128::from(threshold_seconds),
+        ),
+        PatternStep::TimeConstraintMicros(op, threshold_us)
+        | PatternStep::StartTimeConstraintMicros(op, threshold_us) => {
+            op.evaluate(elapsed_us, i128::from(threshold_us))
+        }
+        _ => true,
+    }
+}
+
+/// NFA states that have been expanded, shared across starting positions.
+///
+/// Every transition consumes an event or advances a step, so the state graph
+/// is acyclic and a depth-first search only pops a state a second time after
+/// its first expansion has been fully explored without reaching a match.
+/// Skipping it is then exact, and bounds the work of a search by the number of
+/// distinct states instead of the number of paths.
+///
+/// A state is `(event_idx, step_idx, last_match_ts, first_match_ts)`. The
+/// last timestamp only matters while a `(?t...)` constraint lies ahead, and
+/// the first while a `(?T...)` constraint lies ahead or under a match window,
+/// so other states are keyed by position alone in a dense bitset of
+/// `(events + 1) * (steps + 1)` bits; states before a time constraint, and all
+/// states under a window, go to a hash set.
+struct FailedStates {
+    /// Row width of `bits`: `pattern.steps.len() + 1`.
+    width: usize,
+    /// Bit `event_idx * width + step_idx` for states whose timestamps are irrelevant.
+    bits: Vec<u64>,
+    /// States whose future depends on the first or last matched timestamp.
+    timed: HashSet<(usize, usize, Option<i64>, Option<i64>)>,
+    /// `timed_steps[step_idx]` is true when a `(?t...)` constraint follows
+    /// `step_idx`.
+    timed_steps: Vec<bool>,
+    /// `start_timed_steps[step_idx]` is true when a `(?T...)` constraint
+    /// follows `step_idx`.
+    start_timed_steps: Vec<bool>,
+    /// Whether a match window applies, making every state depend on its
+    /// first matched timestamp.
+    windowed: bool,
+    /// Number of states recorded, i.e. expanded, so far.
+    expanded: usize,
+}
+
+impl FailedStates {
+    fn new(pattern: &CompiledPattern, num_events: usize, windowed: bool) -> Self {
+        let width = pattern.steps.len() + 1;
+        let mut timed_steps = vec![false; width];
+        let mut start_timed_steps = vec![false; width];
+        for (i, step) in pattern.steps.iter().enumerate().rev() {
+            timed_steps[i] = timed_steps[i + 1]
+                || (step.is_time_constraint() && !step.is_start_time_constraint());
+            start_timed_steps[i] = start_timed_steps[i + 1] || step.is_start_time_constraint();
+        }
+        Self {
+            width,
+            bits: vec![0; ((num_events + 1) * width).div_ceil(64)],
+            timed: HashSet::new(),
+            timed_steps,
+            start_timed_steps,
+            windowed,
+            expanded: 0,
+        }
+    }
+
+    /// Records a state as expanded; returns false if it already was.
+    fn insert(
+        &mut self,
+        event_idx: usize,
+        step_idx: usize,
+        last_match_ts: Option<i64>,
+        first_match_ts: Option<i64>,
+    ) -> bool {
+        let last_matters = self.timed_steps[step_idx];
+        let first_matters = self.start_timed_steps[step_idx] || self.windowed;
+        let new = if last_matters || first_matters {
+            self.timed.insert((
+                event_idx,
+                step_idx,
+                last_match_ts.filter(|_| last_matters),
+                first_match_ts.filter(|_| first_matters),
+            ))
+        } else {
+            let bit = event_idx * self.width + step_idx;
+            let (word, mask) = (bit / 64, 1u64 << (bit % 64));
+            let new = self.bits[word] & mask == 0;
+            self.bits[word] |= mask;
+            new
+        };
+        self.expanded += usize::from(new);
+        new
+    }
+
+    /// Forgets the states at `event_idx` after a match ending just before it.
+    ///
+    /// States on the matching path are recorded without having failed. Only
+    /// those at the event after the match are reachable from the next search.
+    fn forget_event(&mut self, event_idx: usize) {
+        for step_idx in 0..self.width {
+            let bit = event_idx * self.width + step_idx;
+            if let Some(word) = self.bits.get_mut(bit / 64) {
+                *word &= !(1u64 << (bit % 64));
+            }
+        }
+        self.timed.retain(|&(event, _, _, _)| event > event_idx);
+    }
+}
+
+/// NFA state that also collects the indices of matched condition events.
 #[derive(Debug, Clone)]
-struct NfaStateWithTimestamps {
+struct NfaStateWithIndices {
     /// Current position in the event stream.
     event_idx: usize,
     /// Current position in the pattern steps.
     step_idx: usize,
-    /// Timestamp of the last matched event (for time constraints).
+    /// Timestamp of the last matched event (for `(?t...)` constraints).
     last_match_ts: Option<i64>,
-    /// Collected timestamps for each matched `(?N)` condition step.
-    collected: Vec<i64>,
+    /// Timestamp of the first matched event (for `(?T...)` constraints).
+    first_match_ts: Option<i64>,
+    /// Collected event indices for each matched `(?N)` condition step.
+    collected: Vec<usize>,
+}
+
+impl NfaStateWithIndices {
+    /// The state after the current step matched the current event, at
+    /// `timestamp_us`; `collect` records the event's index (`(?N)` steps).
+    fn matched(mut self, timestamp_us: i64, collect: bool) -> Self {
+        if collect {
+            self.collected.push(self.event_idx);
+        }
+        Self {
+            event_idx: self.event_idx + 1,
+            step_idx: self.step_idx + 1,
+            last_match_ts: Some(timestamp_us),
+            first_match_ts: self.first_match_ts.or(Some(timestamp_us)),
+            collected: self.collected,
+        }
+    }
 }
 
 /// State of a single NFA thread.
 ///
-/// At 24 bytes with `Copy` semantics, NFA states are stack-allocated
+/// At 48 bytes with `Copy` semantics, NFA states are stack-allocated
 /// and avoid heap cloning overhead during backtracking exploration.
 #[derive(Debug, Clone, Copy)]
 struct NfaState {
@@ -512,8 +989,33 @@ struct NfaState {
     event_idx: usize,
     /// Current position in the pattern steps.
     step_idx: usize,
-    /// Timestamp of the last matched event (for time constraints).
+    /// Timestamp of the last matched event (for `(?t...)` constraints).
     last_match_ts: Option<i64>,
+    /// Timestamp of the first matched event (for `(?T...)` constraints and
+    /// match windows).
+    first_match_ts: Option<i64>,
+    /// Index of the first matched event, where the match's span starts. Not
+    /// part of the [`FailedStates`] key: it does not change whether the
+    /// subtree matches.
+    first_match_idx: Option<usize>,
+}
+
+impl NfaState {
+    /// The state after the current step matched the current event, at
+    /// `timestamp_us`; `None` if the match would span more than `window_us`.
+    fn matched(self, timestamp_us: i64, window_us: Option<i64>) -> Option<Self> {
+        let first = self.first_match_ts.unwrap_or(timestamp_us);
+        if window_us.is_some_and(|window| elapsed_exceeds(first, timestamp_us, window)) {
+            return None;
+        }
+        Some(Self {
+            event_idx: self.event_idx + 1,
+            step_idx: self.step_idx + 1,
+            last_match_ts: Some(timestamp_us),
+            first_match_ts: Some(first),
+            first_match_idx: Some(self.first_match_idx.unwrap_or(self.event_idx)),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -527,6 +1029,193 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn test_time_constraints_at_extreme_timestamps() {
+        let events = make_events(&[(-i64::MAX, &[true, false]), (i64::MAX, &[false, true])]);
+        let matches = |p: &str| execute_pattern(&parse_pattern(p).unwrap(), &events, false).matched;
+        assert!(!matches("(?1)(?t<=60)(?2)"));
+        assert!(matches("(?1)(?t>=60)(?2)"));
+        assert!(!matches("(?1)(?t<1h)(?2)"));
+        let pattern = parse_pattern("(?1).*(?2)").unwrap();
+        assert!(!execute_pattern_within(&pattern, &events, false, Some(i64::MAX)).matched);
+    }
+
+    #[test]
+    fn test_anchors() {
+        // signup, signup, purchase, view
+        let events = make_events(&[
+            (1, &[true, false]),
+            (2, &[true, false]),
+            (3, &[false, true]),
+            (4, &[false, false]),
+        ]);
+        let matches = |p: &str| execute_pattern(&parse_pattern(p).unwrap(), &events, true).count;
+        assert_eq!(matches("^(?1)"), 1);
+        assert_eq!(matches("^(?2)"), 0);
+        assert_eq!(matches("^(?1)(?2)"), 0);
+        assert_eq!(matches("^.*(?1)(?2)"), 1);
+        assert_eq!(matches("(?1)$"), 0);
+        assert_eq!(matches("(?2)$"), 0);
+        assert_eq!(matches("(?2).*$"), 1);
+        assert_eq!(matches("(?2).$"), 1);
+        assert_eq!(matches("^(?1).*(?2).*$"), 1);
+        assert_eq!(matches("^(?1).*(?2)$"), 0);
+        // Unanchored, each signup starts a match; anchored, only one counts
+        assert_eq!(matches("(?1).*"), 2);
+        assert_eq!(matches("^(?1).*"), 1);
+        assert_eq!(matches(".$"), 1);
+
+        let pattern = parse_pattern("(?1)(?2).$").unwrap();
+        assert_eq!(execute_pattern_spans(&pattern, &events), vec![(1, 3)]);
+        assert_eq!(execute_pattern_events(&pattern, &events), Some(vec![2, 3]));
+        let pattern = parse_pattern("^(?1)(?2)").unwrap();
+        assert_eq!(execute_pattern_events(&pattern, &events), None);
+        // Windows apply on top of anchors
+        let pattern = parse_pattern("^(?1).*(?2)").unwrap();
+        assert_eq!(
+            execute_pattern_within(&pattern, &events, true, Some(2)).count,
+            1
+        );
+        assert_eq!(
+            execute_pattern_within(&pattern, &events, true, Some(1)).count,
+            0
+        );
+    }
+
+    #[test]
+    fn test_start_time_constraint() {
+        // signup at 0s, view at 50s, purchase at 90s
+        let events = make_events(&[
+            (0, &[true, false, false]),
+            (50_000_000, &[false, true, false]),
+            (90_000_000, &[false, false, true]),
+        ]);
+        let matches = |p: &str| execute_pattern(&parse_pattern(p).unwrap(), &events, false).matched;
+        // Each gap is under a minute, the whole match is not
+        assert!(matches("(?1)(?t<=60)(?2)(?t<=60)(?3)"));
+        assert!(!matches("(?1)(?2)(?T<=60)(?3)"));
+        assert!(matches("(?1)(?2)(?T<=90)(?3)"));
+        assert!(matches("(?1)(?T<60s)(?2)(?T>=90s)(?3)"));
+        assert!(!matches("(?1)(?T>60)(?2)"));
+        // Vacuously true before the first match
+        assert!(matches("(?T<=1)(?1)"));
+        // Matches the same events as the match window
+        let pattern = parse_pattern("(?1).*(?3)").unwrap();
+        for window_s in [60, 90] {
+            assert_eq!(
+                matches(&format!("(?1).*(?T<={window_s})(?3)")),
+                execute_pattern_within(&pattern, &events, false, Some(window_s * 1_000_000))
+                    .matched
+            );
+        }
+        let pattern = parse_pattern("(?1).*(?T<=60)(?2)").unwrap();
+        assert_eq!(
+            execute_pattern_events(&pattern, &events),
+            Some(vec![0, 50_000_000])
+        );
+        let pattern = parse_pattern("(?1).*(?T<=60)(?3)").unwrap();
+        assert_eq!(execute_pattern_events(&pattern, &events), None);
+    }
+
+    #[test]
+    fn test_start_time_constraint_retries_later_start() {
+        // A late first step fits where the early one does not: the memo must
+        // key on the first matched timestamp.
+        let events = make_events(&[
+            (0, &[true, false]),
+            (100_000_000, &[true, false]),
+            (130_000_000, &[false, true]),
+        ]);
+        let pattern = parse_pattern("(?1).*(?T<=60)(?2)").unwrap();
+        assert_eq!(execute_pattern(&pattern, &events, true).count, 1);
+        assert_eq!(execute_pattern_spans(&pattern, &events), vec![(1, 2)]);
+        let pattern = parse_pattern("(?1).*(?1).*(?T<=60)(?2)").unwrap();
+        assert!(!execute_pattern(&pattern, &events, false).matched);
+    }
+
+    #[test]
+    fn test_any_not_condition_blocks_between_steps() {
+        let pattern = parse_pattern("(?1)(?!3)*(?2)").unwrap();
+        // view, other, purchase: no refund in between
+        let clean = make_events(&[
+            (1, &[true, false, false]),
+            (2, &[false, false, false]),
+            (3, &[false, true, false]),
+        ]);
+        assert!(execute_pattern(&pattern, &clean, false).matched);
+        // view, refund, purchase
+        let refunded = make_events(&[
+            (1, &[true, false, false]),
+            (2, &[false, false, true]),
+            (3, &[false, true, false]),
+        ]);
+        assert!(!execute_pattern(&pattern, &refunded, false).matched);
+        // A later view restarts the search after the refund.
+        let retried = make_events(&[
+            (1, &[true, false, false]),
+            (2, &[false, false, true]),
+            (3, &[true, false, false]),
+            (4, &[false, true, false]),
+        ]);
+        assert!(execute_pattern(&pattern, &retried, false).matched);
+        assert_eq!(
+            execute_pattern_event_indices(&pattern, &retried),
+            Some(vec![2, 3])
+        );
+    }
+
+    #[test]
+    fn test_any_not_condition_matches_zero_events() {
+        let pattern = parse_pattern("(?1)(?!3)*(?2)").unwrap();
+        let events = make_events(&[(1, &[true, false, false]), (2, &[false, true, false])]);
+        assert!(execute_pattern(&pattern, &events, false).matched);
+        let trailing = parse_pattern("(?1)(?!2)*").unwrap();
+        assert!(execute_pattern(&trailing, &events[..1], false).matched);
+    }
+
+    #[test]
+    fn test_not_condition_consumes_one_event() {
+        let pattern = parse_pattern("(?1)(?!2)(?3)").unwrap();
+        let events = make_events(&[
+            (1, &[true, false, false]),
+            (2, &[true, false, false]),
+            (3, &[false, false, true]),
+        ]);
+        assert!(execute_pattern(&pattern, &events, false).matched);
+        let blocked = make_events(&[
+            (1, &[true, false, false]),
+            (2, &[false, true, false]),
+            (3, &[false, false, true]),
+        ]);
+        assert!(!execute_pattern(&pattern, &blocked, false).matched);
+        // No intermediate event at all
+        assert!(!execute_pattern(&pattern, &[events[0], events[2]], false).matched);
+    }
+
+    #[test]
+    fn test_not_condition_counts_and_time_constraints() {
+        let pattern = parse_pattern("(?1)(?!3)*(?t<=10)(?2)").unwrap();
+        let events = make_events(&[
+            (0, &[true, false, false]),
+            (5_000_000, &[false, true, false]),
+            (20_000_000, &[true, false, false]),
+            (21_000_000, &[false, false, true]),
+            (22_000_000, &[false, true, false]),
+            (30_000_000, &[true, false, false]),
+            (35_000_000, &[false, true, false]),
+        ]);
+        assert_eq!(execute_pattern(&pattern, &events, true).count, 2);
+    }
+
+    #[test]
+    fn test_negated_steps_use_nfa() {
+        let pattern = parse_pattern("(?1)(?!2)*(?3)").unwrap();
+        assert!(matches!(classify_pattern(&pattern), PatternShape::Complex));
+        assert_eq!(run_expansion_cap(&pattern, false), 2);
+        let single = parse_pattern("(?1)(?!2)(?3)").unwrap();
+        assert_eq!(run_expansion_cap(&single, false), 3);
+    }
+
     #[test]
     fn test_simple_match() {
         let pattern = parse_pattern("(?1)(?2)").unwrap();
@@ -620,6 +1309,81 @@ mod tests {
         assert_eq!(result.count, 2);
     }
 
+    #[test]
+    fn test_within_window_prefers_later_start() {
+        let pattern = parse_pattern("(?1).*(?2)").unwrap();
+        let events = make_events(&[
+            (0, &[true, false]),
+            (500, &[true, false]),
+            (550, &[false, true]),
+            (1000, &[true, false]),
+            (2000, &[false, true]),
+        ]);
+        assert_eq!(execute_pattern(&pattern, &events, true).count, 2);
+        // The match from 0 is too long; the one from 500 fits.
+        let result = execute_pattern_within(&pattern, &events, true, Some(100));
+        assert!(result.matched);
+        assert_eq!(result.count, 1);
+        assert!(execute_pattern_within(&pattern, &events, false, Some(100)).matched);
+        assert!(!execute_pattern_within(&pattern, &events, false, Some(10)).matched);
+    }
+
+    #[test]
+    fn test_within_window_is_inclusive_and_spans_all_matched_steps() {
+        let pattern = parse_pattern("(?1).(?2)").unwrap();
+        let events = make_events(&[
+            (0, &[true, false]),
+            (60, &[false, false]),
+            (100, &[false, true]),
+        ]);
+        assert_eq!(
+            execute_pattern_within(&pattern, &events, true, Some(100)).count,
+            1
+        );
+        assert_eq!(
+            execute_pattern_within(&pattern, &events, true, Some(99)).count,
+            0
+        );
+    }
+
+    #[test]
+    fn test_within_window_leading_wildcard_memo() {
+        // States reached from different starts carry different first
+        // timestamps; the memo must not share their outcomes.
+        let pattern = parse_pattern(".*(?1).*(?2)").unwrap();
+        let events = make_events(&[
+            (0, &[true, false]),
+            (50, &[true, false]),
+            (200, &[false, true]),
+        ]);
+        assert_eq!(
+            execute_pattern_within(&pattern, &events, true, Some(160)).count,
+            1
+        );
+        assert_eq!(
+            execute_pattern_within(&pattern, &events, true, Some(140)).count,
+            0
+        );
+    }
+
+    #[test]
+    fn test_within_without_window_matches_execute_pattern() {
+        let events: Vec<Event> = (0..30)
+            .map(|i| Event::from_bools(i * 7, &[i % 3 == 0, i % 4 == 1]))
+            .collect();
+        for pattern in ["(?1).*(?2)", "(?1)(?2)", "(?1).(?2)", "(?1)(?t<=5)(?2)"] {
+            let pattern = parse_pattern(pattern).unwrap();
+            for count_all in [false, true] {
+                let expected = execute_pattern(&pattern, &events, count_all);
+                let within = execute_pattern_within(&pattern, &events, count_all, None);
+                assert_eq!(within.count, expected.count);
+                // A window wider than the stream changes nothing.
+                let wide = execute_pattern_within(&pattern, &events, count_all, Some(i64::MAX));
+                assert_eq!(wide.count, expected.count);
+            }
+        }
+    }
+
     #[test]
     fn test_empty_events() {
         let pattern = parse_pattern("(?1)").unwrap();
@@ -685,10 +1449,32 @@ mod tests {
         assert!(result.matched);
     }
 
+    #[test]
+    fn test_time_constraint_ms_is_not_truncated() {
+        let pattern = parse_pattern("(?1)(?t<=1500ms)(?2)").unwrap();
+        let within = make_events(&[(0, &[true, false]), (1_200_000, &[false, true])]);
+        let beyond = make_events(&[(0, &[true, false]), (1_800_000, &[false, true])]);
+        assert!(execute_pattern(&pattern, &within, false).matched);
+        assert!(!execute_pattern(&pattern, &beyond, false).matched);
+
+        // Without a unit, 1.8s truncates to 1s and satisfies (?t<=1)
+        let seconds = parse_pattern("(?1)(?t<=1)(?2)").unwrap();
+        assert!(execute_pattern(&seconds, &beyond, false).matched);
+    }
+
+    #[test]
+    fn test_time_constraint_minutes_and_hours() {
+        let events = make_events(&[(0, &[true, false]), (90 * 60_000_000, &[false, true])]);
+        let hours = parse_pattern("(?1)(?t>1h)(?2)").unwrap();
+        let minutes = parse_pattern("(?1)(?t<2m)(?2)").unwrap();
+        assert!(execute_pattern(&hours, &events, false).matched);
+        assert!(!execute_pattern(&minutes, &events, false).matched);
+    }
+
     #[test]
     fn test_max_nfa_states_limit() {
-        // A pathological pattern with multiple .* can cause state explosion.
-        // The executor should abort after MAX_NFA_STATES iterations and return no match.
+        // A pattern with multiple .* has exponentially many paths; with
+        // memoized states the search stays polynomial and reports no match.
         let pattern = parse_pattern("(?1).*.*.*.*(?2)").unwrap();
         // Many events that don't match (?2) force extensive backtracking
         let mut event_data: Vec<(i64, &[bool])> = Vec::new();
@@ -699,15 +1485,75 @@ mod tests {
             event_data.push((i, &conds_mid));
         }
         let events = make_events(&event_data);
-        // Should not hang; returns no match after hitting the state limit
+        // Should not hang; there is no (?2) event to match
         let result = execute_pattern(&pattern, &events, false);
         assert!(!result.matched);
     }
 
+    /// One `(?1)` event, `gap` unmatched events, then one `(?2)` event.
+    fn pathological_events(gap: i64) -> Vec<Event> {
+        let mut events = vec![Event::from_bools(0, &[true, false])];
+        events.extend((1..=gap).map(|i| Event::from_bools(i, &[false, false])));
+        events.push(Event::from_bools(gap + 1, &[false, true]));
+        events
+    }
+
+    #[test]
+    fn test_complex_pattern_with_many_paths_still_matches() {
+        // `.` forces the NFA; the number of ways to split 200 events among the
+        // wildcards is far beyond any fixed iteration budget.
+        let pattern = parse_pattern("(?1).*.*.*.(?2)").unwrap();
+        let events = pathological_events(200);
+        let result = execute_pattern(&pattern, &events, true);
+        assert!(result.matched);
+        assert_eq!(result.count, 1);
+        assert_eq!(execute_pattern_spans(&pattern, &events), vec![(0, 201)]);
+        assert_eq!(
+            execute_pattern_event_indices(&pattern, &events),
+            Some(vec![0, 201])
+        );
+    }
+
+    #[test]
+    fn test_memoized_states_respect_time_constraints() {
+        // The same (event, step) state is reached with different last-match
+        // timestamps; only the later (?1) is within 50us of the (?2).
+        let pattern = parse_pattern("(?1).*.*(?t<=50us)(?2)").unwrap();
+        let mut events = pathological_events(200);
+        events[160] = Event::from_bools(160, &[true, false]);
+        assert!(execute_pattern(&pattern, &events, false).matched);
+        assert_eq!(
+            execute_pattern_event_indices(&pattern, &events),
+            Some(vec![160, 201])
+        );
+        events[160] = Event::from_bools(160, &[false, false]);
+        assert!(!execute_pattern(&pattern, &events, false).matched);
+    }
+
+    #[test]
+    fn test_memoized_count_after_match_reuses_search() {
+        // Back-to-back matches: states on a matching path must not be mistaken
+        // for failures by the next search.
+        let pattern = parse_pattern("(?1).*.(?2).*").unwrap();
+        let mut events = Vec::new();
+        for i in 0..50 {
+            events.push(Event::from_bools(4 * i, &[true, false]));
+            events.push(Event::from_bools(4 * i + 1, &[false, false]));
+            events.push(Event::from_bools(4 * i + 2, &[false, true]));
+        }
+        assert_eq!(execute_pattern(&pattern, &events, true).count, 50);
+    }
+
     #[test]
     fn test_empty_pattern_steps() {
         // A pattern with no steps should not match anything
-        let pattern = CompiledPattern { steps: vec![] };
+        let pattern = CompiledPattern {
+            steps: vec![],
+            positions: vec![],
+            anchored_start: false,
+            anchored_end: false,
+            captures: vec![],
+        };
         let events = make_events(&[(100, &[true])]);
         let result = execute_pattern(&pattern, &events, false);
         assert!(!result.matched);
@@ -887,6 +1733,16 @@ mod tests {
         assert!(result.matched);
     }
 
+    #[test]
+    fn test_count_empty_match_advances() {
+        // A match consuming no event must not restart at the same event.
+        let events = make_events(&[(0, &[true]), (1_000_000, &[false]), (2_000_000, &[true])]);
+        for pattern in ["(?t<=2)", ".*", "(?!1)*"] {
+            let pattern = parse_pattern(pattern).unwrap();
+            assert_eq!(execute_pattern(&pattern, &events, true).count, 3);
+        }
+    }
+
     #[test]
     fn test_match_end_index_for_non_overlapping_count() {
         // Kills mutant: altering match_end return value logic.
@@ -1075,6 +1931,22 @@ mod tests {
         assert!(result.matched);
     }
 
+    #[test]
+    fn test_classify_adjacent_pair_with_wildcard_is_complex() {
+        // (?1)(?2) must stay adjacent even though a .* follows.
+        let pattern = parse_pattern("(?1)(?2).*(?3)").unwrap();
+        assert_eq!(execution_path(&pattern), ExecutionPath::Nfa);
+        let events = make_events(&[
+            (100, &[true, false, false]),
+            (200, &[false, false, true]),
+            (300, &[false, true, false]),
+            (400, &[false, false, true]),
+        ]);
+        assert!(!execute_pattern(&pattern, &events, false).matched);
+        let pattern = parse_pattern(".*(?1).*(?2).*").unwrap();
+        assert_eq!(execution_path(&pattern), ExecutionPath::Wildcard);
+    }
+
     #[test]
     fn test_classify_one_event_is_complex() {
         // Patterns with `.` (OneEvent) must use the NFA.
@@ -1144,8 +2016,8 @@ mod tests {
 
     #[test]
     fn test_events_nfa_state_limit() {
-        // Pathological pattern with multiple .* should hit MAX_NFA_STATES
-        // and return None during event collection (same as execute_pattern).
+        // Pathological pattern with multiple .* and no (?2) event returns
+        // None during event collection (same as execute_pattern).
         let pattern = parse_pattern("(?1).*.*.*.*(?2)").unwrap();
         let mut event_data: Vec<(i64, &[bool])> = Vec::new();
         let conds_start: [bool; 2] = [true, false];
@@ -1162,7 +2034,13 @@ mod tests {
     #[test]
     fn test_events_empty_pattern() {
         // Empty pattern steps should return None.
-        let pattern = CompiledPattern { steps: vec![] };
+        let pattern = CompiledPattern {
+            steps: vec![],
+            positions: vec![],
+            anchored_start: false,
+            anchored_end: false,
+            captures: vec![],
+        };
         let events = make_events(&[(100, &[true])]);
         let result = execute_pattern_events(&pattern, &events);
         assert_eq!(result, None);
@@ -1229,4 +2107,214 @@ mod tests {
         let result = execute_pattern_events(&pattern, &events);
         assert_eq!(result, Some(vec![100, 200]));
     }
+
+    // --- execute_pattern_event_indices tests ---
+
+    #[test]
+    fn test_event_indices_skip_unmatched_events() {
+        let pattern = parse_pattern("(?1).*(?2)").unwrap();
+        let events = make_events(&[
+            (100, &[false, false]),
+            (200, &[true, false]),
+            (300, &[false, false]),
+            (400, &[false, true]),
+        ]);
+        let result = execute_pattern_event_indices(&pattern, &events);
+        assert_eq!(result, Some(vec![1, 3]));
+    }
+
+    #[test]
+    fn test_event_indices_no_match() {
+        let pattern = parse_pattern("(?1)(?2)").unwrap();
+        let events = make_events(&[(100, &[false, true]), (200, &[true, false])]);
+        assert!(execute_pattern_event_indices(&pattern, &events).is_none());
+    }
+
+    #[test]
+    fn test_event_indices_agree_with_timestamps() {
+        let pattern = parse_pattern("(?1).(?2).*(?3)").unwrap();
+        let events = make_events(&[
+            (10, &[true, false, false]),
+            (20, &[false, false, false]),
+            (30, &[false, true, false]),
+            (40, &[false, false, false]),
+            (50, &[false, false, true]),
+        ]);
+        let indices = execute_pattern_event_indices(&pattern, &events).unwrap();
+        let timestamps = execute_pattern_events(&pattern, &events).unwrap();
+        let mapped: Vec<i64> = indices.iter().map(|&i| events[i].timestamp_us).collect();
+        assert_eq!(mapped, timestamps);
+        assert_eq!(indices, vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn test_run_expansion_cap_counts_event_steps() {
+        let pattern = parse_pattern("(?1).*(?t<=5)(?2).(?3)").unwrap();
+        assert_eq!(run_expansion_cap(&pattern, false), 4);
+        assert_eq!(run_expansion_cap(&pattern, true), usize::MAX);
+    }
+
+    #[test]
+    fn test_execute_pattern_runs_adjacent_within_run() {
+        let mut runs = RunLengthBuffer::new();
+        for ts in 0..500 {
+            runs.push(Event::from_bools(ts, &[true, false]));
+        }
+        runs.push(Event::from_bools(500, &[false, true]));
+        let three = parse_pattern("(?1)(?1)(?1)(?2)").unwrap();
+        let gap = parse_pattern("(?2).(?1)").unwrap();
+        assert!(execute_pattern_runs(&three, &runs, false).matched);
+        assert!(!execute_pattern_runs(&gap, &runs, false).matched);
+        assert_eq!(
+            execute_pattern_runs(&parse_pattern("(?1)").unwrap(), &runs, true).count,
+            500
+        );
+    }
+
+    #[test]
+    fn test_spans_match_count_on_every_path() {
+        let events = make_events(&[
+            (100, &[true, false]),
+            (200, &[false, true]),
+            (300, &[false, false]),
+            (400, &[true, false]),
+            (500, &[false, false]),
+            (600, &[false, true]),
+        ]);
+        // Adjacent fast path, wildcard fast path and NFA respectively
+        for (pat, expected) in [
+            ("(?1)(?2)", vec![(0, 1)]),
+            ("(?1).*(?2)", vec![(0, 1), (3, 5)]),
+            ("(?1).(?2)", vec![(3, 5)]),
+        ] {
+            let pattern = parse_pattern(pat).unwrap();
+            let spans = execute_pattern_spans(&pattern, &events);
+            assert_eq!(spans, expected, "{pat}");
+            assert_eq!(spans.len(), execute_pattern(&pattern, &events, true).count);
+        }
+    }
+
+    #[test]
+    fn test_leading_wildcard_spans_start_at_first_matched_event() {
+        let events = make_events(&[
+            (0, &[false, false]),
+            (10, &[true, false]),
+            (20, &[false, true]),
+        ]);
+        // Wildcard fast path, then the NFA through `(?1)(?2)`, `.` and a
+        // time constraint: the leading `.*` never moves the start
+        for pat in [
+            ".*(?1).*(?2)",
+            ".*(?1)(?2)",
+            ".*.(?2)",
+            ".*(?1)(?t<=10)(?2)",
+        ] {
+            let pattern = parse_pattern(pat).unwrap();
+            assert_eq!(
+                execute_pattern_spans(&pattern, &events),
+                vec![(1, 2)],
+                "{pat}"
+            );
+        }
+
+        // A match that consumes no event is empty at the search start
+        let pattern = parse_pattern(".*").unwrap();
+        assert_eq!(execute_pattern_spans(&pattern, &events)[0], (0, 0));
+    }
+
+    #[test]
+    fn test_spans_empty_input() {
+        let pattern = parse_pattern("(?1)").unwrap();
+        assert!(execute_pattern_spans(&pattern, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_entry_condition_skips_start_positions() {
+        // One entry event among 1000: only its start position is tried
+        let mut events: Vec<Event> = (0..1000).map(|ts| Event::new(ts, 0b100)).collect();
+        events[900] = Event::new(900, 0b001);
+        events[902] = Event::new(902, 0b010);
+        let pattern = parse_pattern("(?1).(?2)").unwrap();
+        let result = execute_pattern(&pattern, &events, true);
+        assert_eq!(result.count, 1);
+        assert!(result.states_expanded <= 4, "{}", result.states_expanded);
+        assert_eq!(execute_pattern_spans(&pattern, &events), vec![(900, 902)]);
+        assert_eq!(
+            execute_pattern_events(&pattern, &events),
+            Some(vec![900, 902])
+        );
+    }
+
+    #[test]
+    fn test_entry_condition_after_time_constraints() {
+        let pattern = parse_pattern("(?t<=5)(?1)(?t<=5)(?2)").unwrap();
+        assert_eq!(pattern.entry_condition(), Some(0));
+        let events = make_events(&[
+            (0, &[false, true]),
+            (1_000_000, &[true, false]),
+            (2_000_000, &[false, true]),
+        ]);
+        assert!(execute_pattern(&pattern, &events, false).matched);
+
+        for pat in ["(?!1)(?2)", ".*(?1)", ".(?1)", "(?!1)*(?2)"] {
+            assert_eq!(parse_pattern(pat).unwrap().entry_condition(), None, "{pat}");
+        }
+    }
+
+    #[test]
+    fn test_entry_condition_with_anchored_start() {
+        let events = make_events(&[
+            (0, &[false, false]),
+            (1, &[true, false]),
+            (2, &[false, true]),
+        ]);
+        let anchored = parse_pattern("^(?1)(?t<=1)(?2)").unwrap();
+        let unanchored = parse_pattern("(?1)(?t<=1)(?2)").unwrap();
+        assert!(!execute_pattern(&anchored, &events, false).matched);
+        assert!(execute_pattern_events(&anchored, &events).is_none());
+        assert!(execute_pattern(&unanchored, &events, false).matched);
+        assert_eq!(
+            execute_pattern_events(&unanchored, &events),
+            Some(vec![1, 2])
+        );
+        assert!(execute_pattern(&anchored, &events[1..], false).matched);
+    }
+
+    #[test]
+    fn test_entry_condition_counts_match_every_start() {
+        // Non-overlapping counts agree with trying every start position
+        let events: Vec<Event> = (0..300)
+            .map(|i| {
+                let mask = u64::from(i % 7 == 3) | u64::from(i % 5 == 0) << 1;
+                Event::new(i * 1_000_000, mask)
+            })
+            .collect();
+        for pat in [
+            "(?1).(?2)",
+            "(?1)(?t<=4)(?2)",
+            "(?1).*(?2).",
+            "(?1)(?!1)*(?2)",
+        ] {
+            let pattern = parse_pattern(pat).unwrap();
+            let mut states = Vec::new();
+            let mut failed = FailedStates::new(&pattern, events.len(), false);
+            let (mut start, mut expected) = (0, 0);
+            while start < events.len() {
+                match try_match_from(&pattern, &events, start, None, &mut states, &mut failed) {
+                    Some((_, end)) => {
+                        expected += 1;
+                        start = end.max(start) + 1;
+                        failed.forget_event(start);
+                    }
+                    None => start += 1,
+                }
+            }
+            assert!(expected > 0, "{pat}");
+            assert_eq!(
+                execute_pattern(&pattern, &events, true).count,
+                expected,
+                "{pat}"
+            );
+        }
+    }
 }
@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Step-by-step plan of a compiled pattern, for `behavioral_explain`.
+//!
+//! [`explain`] parses a pattern and describes each [`PatternStep`] together
+//! with the [`ExecutionPath`] the executor would take, so a user can see
+//! which steps keep a pattern off the adjacent and wildcard fast paths.
+
+use crate::pattern::executor::{execution_path, ExecutionPath};
+use crate::pattern::parser::{parse_pattern, PatternError, PatternStep};
+
+/// Description of one pattern step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepPlan {
+    /// 1-based step number.
+    pub step: usize,
+    /// Byte offset of the step in the pattern.
+    pub position: usize,
+    /// Text of the step, e.g. `(?t<=3600)`.
+    pub text: String,
+    /// Step type: `condition`, `any_events`, `one_event`, `not_condition`,
+    /// `any_not_condition`, `time_constraint` or `start_time_constraint`.
+    pub kind: &'static str,
+    /// 1-based condition referenced by `(?N)`, `(?!N)` and `(?!N)*` steps.
+    pub condition: Option<usize>,
+    /// Comparison of a time constraint, e.g. `<= 3600s` or `< 500ms` as
+    /// `< 500000us`.
+    pub time_constraint: Option<String>,
+    /// Whether the step alone forces the NFA. Anchors and patterns without
+    /// conditions take the NFA without any such step.
+    pub forces_nfa: bool,
+}
+
+/// Parses `pattern` and returns its execution path and one [`StepPlan`] per
+/// step.
+///
+/// # Errors
+///
+/// Returns [`PatternError`] if the pattern is malformed.
+pub fn explain(pattern: &str) -> Result<(ExecutionPath, Vec<StepPlan>), PatternError> {
+    let compiled = parse_pattern(pattern)?;
+    let path = execution_path(&compiled);
+    let steps = compiled
+        .steps
+        .iter()
+        .zip(&compiled.positions)
+        .enumerate()
+        .map(|(i, (step, &position))| {
+            let end = compiled
+                .positions
+                .get(i + 1)
+                .copied()
+                .unwrap_or(pattern.len());
+            let mut text = pattern[position..end].trim_end();
+            if i + 1 == compiled.steps.len() && compiled.anchored_end {
+                text = text.trim_end_matches('$').trim_end();
+            }
+            StepPlan {
+                step: i + 1,
+                position,
+                text: text.to_string(),
+                kind: kind(step),
+                condition: step.condition_index().map(|idx| idx + 1),
+                time_constraint: time_constraint(step),
+                forces_nfa: !matches!(step, PatternStep::Condition(_) | PatternStep::AnyEvents),
+            }
+        })
+        .collect();
+    Ok((path, steps))
+}
+
+/// Returns the step type name of `step`.
+const fn kind(step: &PatternStep) -> &'static str {
+    match step {
+        PatternStep::Condition(_) => "condition",
+        PatternStep::AnyEvents => "any_events",
+        PatternStep::OneEvent => "one_event",
+        PatternStep::NotCondition(_) => "not_condition",
+        PatternStep::AnyNotCondition(_) => "any_not_condition",
+        PatternStep::TimeConstraint(_, _) | PatternStep::TimeConstraintMicros(_, _) => {
+            "time_constraint"
+        }
+        PatternStep::StartTimeConstraint(_, _) | PatternStep::StartTimeConstraintMicros(_, _) => {
+            "start_time_constraint"
+        }
+    }
+}
+
+/// Renders the comparison of a time constraint step: whole seconds for
+/// `(?t<=N)` and `(?T<=N)`, microseconds for a constraint written with a
+/// unit.
+fn time_constraint(step: &PatternStep) -> Option<String> {
+    match *step {
+        PatternStep::TimeConstraint(op, seconds)
+        | PatternStep::StartTimeConstraint(op, seconds) => {
+            Some(format!("{} {seconds}s", op.symbol()))
+        }
+        PatternStep::TimeConstraintMicros(op, micros)
+        | PatternStep::StartTimeConstraintMicros(op, micros) => {
+            Some(format!("{} {micros}us", op.symbol()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_fast_paths() {
+        let (path, steps) = explain("(?1)(?2)").unwrap();
+        assert_eq!(path, ExecutionPath::Adjacent);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[1].text, "(?2)");
+        assert_eq!(steps[1].condition, Some(2));
+
+        let (path, steps) = explain("(?1) .* (?3)").unwrap();
+        assert_eq!(path, ExecutionPath::Wildcard);
+        let kinds: Vec<_> = steps.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, ["condition", "any_events", "condition"]);
+        let texts: Vec<_> = steps.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["(?1)", ".*", "(?3)"]);
+        assert!(steps.iter().all(|s| !s.forces_nfa));
+    }
+
+    #[test]
+    fn test_explain_nfa_steps() {
+        let (path, steps) = explain("^(?1)(?t<=3600).(?!2)(?t>5m)(?3)$").unwrap();
+        assert_eq!(path, ExecutionPath::Nfa);
+        assert_eq!(steps[0].position, 1);
+        assert_eq!(steps[1].time_constraint.as_deref(), Some("<= 3600s"));
+        assert_eq!(steps[2].kind, "one_event");
+        assert_eq!(steps[3].kind, "not_condition");
+        assert_eq!(steps[3].condition, Some(2));
+        assert_eq!(steps[4].time_constraint.as_deref(), Some("> 300000000us"));
+        assert_eq!(steps[5].text, "(?3)");
+        let forcing: Vec<_> = steps.iter().map(|s| s.forces_nfa).collect();
+        assert_eq!(forcing, [false, true, true, true, true, false]);
+
+        // Anchors force the NFA without a forcing step.
+        let (path, steps) = explain("^(?1)(?2)").unwrap();
+        assert_eq!(path, ExecutionPath::Nfa);
+        assert!(steps.iter().all(|s| !s.forces_nfa));
+
+        let (_, steps) = explain("(?1).*(?T<=1h)(?2)").unwrap();
+        assert_eq!(steps[2].kind, "start_time_constraint");
+        assert_eq!(steps[2].time_constraint.as_deref(), Some("<= 3600000000us"));
+        assert!(steps[2].forces_nfa);
+
+        assert!(explain("(?1)(?x)").is_err());
+    }
+}
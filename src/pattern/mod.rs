@@ -18,7 +18,11 @@
 //! (?t<N)    — Time constraint: less than N seconds since previous match
 //! (?t==N)   — Time constraint: exactly N seconds since previous match
 //! (?t!=N)   — Time constraint: not exactly N seconds since previous match
+//! (?T<=N)   — Time constraint: at most N seconds since the first match
+//!             (all six operators, as for (?t...))
 //! ```
 
+pub mod cache;
 pub mod executor;
+pub mod explain;
 pub mod parser;
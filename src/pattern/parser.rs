@@ -5,7 +5,46 @@
 //!
 //! Parses patterns like `(?1).*(?2)(?t>=3600)(?3)` into a structured AST
 //! that can be executed by the NFA engine.
+//!
+//! Negated conditions match events where a condition is false: `(?!N)`
+//! matches one such event and `(?!N)*` zero or more, so `(?1)(?!3)*(?2)` reads
+//! "view, then purchase, with no refund in between".
+//!
+//! Anchors tie a pattern to the ends of the group's event stream: with a
+//! leading `^` the first step must match the first event, and with a trailing
+//! `$` the last step must match the last event, so `^(?1).*(?2)$` reads "the
+//! first event was a signup and the last a purchase". They are flags of the
+//! [`CompiledPattern`], not steps, and are rejected anywhere else.
+//!
+//! Time constraints accept an optional unit suffix: `(?t<=1500ms)`,
+//! `(?t<=2m)`, `(?t<=1h)`. Without a suffix the value is in seconds and
+//! elapsed time is truncated to whole seconds (`ClickHouse` semantics). With a
+//! suffix the comparison is exact in microseconds.
+//!
+//! A lower-case `(?t...)` constraint measures time since the previous matched
+//! event. An upper-case `(?T...)` constraint, with the same operators and
+//! units, measures time since the first event of the match, so
+//! `(?1).*(?2).*(?T<=1h)(?3)` requires the whole match to fit in an hour, like
+//! the `window_funnel` window.
+//!
+//! A `(?N)` step can name the event it matches, as a regex capture group
+//! does: `(?1:landing).*(?2:checkout)`. Names are letters, digits and `_`,
+//! not starting with a digit, and unique within the pattern. They do not
+//! change what matches; `sequence_extract` returns a value of the event
+//! matched by each named step.
+//!
+//! Whitespace may separate steps and the parts of a step, as generated SQL
+//! often inserts it: `( ?1 ) . * (?t >= 90 m)` reads as `(?1).*(?t>=90m)`.
+//! Operators, numbers and units stay unbroken. Units are case-insensitive,
+//! `(?t<=100MS)`, but `t` is not: `(?T...)` measures from the match start.
+//! The [`Display`](fmt::Display) form of a [`CompiledPattern`] is its
+//! canonical text, returned by `sequence_pattern_normalize`.
+//!
+//! Errors carry the byte offset of the problem, the text of the step it
+//! occurred in and, for common mistakes such as `(?t=>5)`, `(?1` or
+//! `(?t<=2d)`, a corrected step: `did you mean '(?t>=5)'?`.
 
+use crate::common::timestamp::time_unit_micros;
 use std::fmt;
 
 /// A single step in a compiled pattern.
@@ -17,9 +56,62 @@ pub enum PatternStep {
     AnyEvents,
     /// Match exactly one event (any conditions). Corresponds to `.`.
     OneEvent,
+    /// Match exactly one event where condition N (0-indexed internally) is
+    /// false. Corresponds to `(?!N)`.
+    NotCondition(usize),
+    /// Match zero or more events where condition N (0-indexed internally) is
+    /// false. Corresponds to `(?!N)*`.
+    AnyNotCondition(usize),
     /// Time constraint relative to the previous matched event.
     /// The duration is in seconds (matching `ClickHouse` semantics).
     TimeConstraint(TimeOp, i64),
+    /// Time constraint with an explicit unit suffix (`us`, `ms`, `s`, `m`, `h`).
+    /// The duration is in microseconds and compared exactly, without the
+    /// whole-second truncation applied to [`TimeConstraint`](Self::TimeConstraint).
+    TimeConstraintMicros(TimeOp, i64),
+    /// Time constraint relative to the first matched event of the match,
+    /// `(?T...)`. The duration is in seconds, truncated like
+    /// [`TimeConstraint`](Self::TimeConstraint).
+    StartTimeConstraint(TimeOp, i64),
+    /// `(?T...)` with an explicit unit suffix: microseconds, compared
+    /// exactly like [`TimeConstraintMicros`](Self::TimeConstraintMicros).
+    StartTimeConstraintMicros(TimeOp, i64),
+}
+
+impl PatternStep {
+    /// Returns the 0-indexed condition referenced by a `(?N)`, `(?!N)` or
+    /// `(?!N)*` step.
+    #[must_use]
+    pub const fn condition_index(&self) -> Option<usize> {
+        match *self {
+            Self::Condition(idx) | Self::NotCondition(idx) | Self::AnyNotCondition(idx) => {
+                Some(idx)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true for a `(?t...)` or `(?T...)` time constraint step.
+    #[must_use]
+    pub const fn is_time_constraint(&self) -> bool {
+        matches!(
+            self,
+            Self::TimeConstraint(_, _)
+                | Self::TimeConstraintMicros(_, _)
+                | Self::StartTimeConstraint(_, _)
+                | Self::StartTimeConstraintMicros(_, _)
+        )
+    }
+
+    /// Returns true for a `(?T...)` time constraint step, measured from the
+    /// first event of the match.
+    #[must_use]
+    pub const fn is_start_time_constraint(&self) -> bool {
+        matches!(
+            self,
+            Self::StartTimeConstraint(_, _) | Self::StartTimeConstraintMicros(_, _)
+        )
+    }
 }
 
 /// Comparison operator for time constraints.
@@ -40,16 +132,36 @@ pub enum TimeOp {
 }
 
 impl TimeOp {
-    /// Evaluates the time constraint: `elapsed_seconds <op> threshold`.
+    /// Evaluates the time constraint: `elapsed <op> threshold`.
+    ///
+    /// Both values must be in the same unit (seconds for
+    /// [`PatternStep::TimeConstraint`] and
+    /// [`PatternStep::StartTimeConstraint`], microseconds for the `Micros`
+    /// variants). The executor passes `i128`
+    /// values so that elapsed times between distant timestamps do not
+    /// overflow.
+    #[must_use]
+    pub fn evaluate<T: Ord + Copy>(self, elapsed: T, threshold: T) -> bool {
+        match self {
+            Self::Gte => elapsed >= threshold,
+            Self::Lte => elapsed <= threshold,
+            Self::Gt => elapsed > threshold,
+            Self::Lt => elapsed < threshold,
+            Self::Eq => elapsed == threshold,
+            Self::Ne => elapsed != threshold,
+        }
+    }
+
+    /// Returns the operator as written in patterns.
     #[must_use]
-    pub const fn evaluate(self, elapsed_seconds: i64, threshold: i64) -> bool {
+    pub const fn symbol(self) -> &'static str {
         match self {
-            Self::Gte => elapsed_seconds >= threshold,
-            Self::Lte => elapsed_seconds <= threshold,
-            Self::Gt => elapsed_seconds > threshold,
-            Self::Lt => elapsed_seconds < threshold,
-            Self::Eq => elapsed_seconds == threshold,
-            Self::Ne => elapsed_seconds != threshold,
+            Self::Gte => ">=",
+            Self::Lte => "<=",
+            Self::Gt => ">",
+            Self::Lt => "<",
+            Self::Eq => "==",
+            Self::Ne => "!=",
         }
     }
 }
@@ -60,6 +172,171 @@ impl TimeOp {
 pub struct CompiledPattern {
     /// Ordered steps that events must match.
     pub steps: Vec<PatternStep>,
+    /// Byte offset in the input of each step, parallel to `steps`.
+    pub positions: Vec<usize>,
+    /// Whether the pattern starts with `^`: a match must start at the first
+    /// event.
+    pub anchored_start: bool,
+    /// Whether the pattern ends with `$`: a match must end at the last event.
+    pub anchored_end: bool,
+    /// Capture name of each step, parallel to `steps`. `Some` only for
+    /// `(?N:name)` steps.
+    pub captures: Vec<Option<String>>,
+}
+
+impl CompiledPattern {
+    /// Returns the highest 1-based condition index referenced by a `(?N)`,
+    /// `(?!N)` or `(?!N)*` step, or 0 if the pattern has no condition steps.
+    #[must_use]
+    pub fn max_condition(&self) -> usize {
+        self.steps
+            .iter()
+            .filter_map(PatternStep::condition_index)
+            .map(|idx| idx + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of `(?N)` steps, which is the number of timestamps
+    /// a match of `sequence_match_events` returns.
+    #[must_use]
+    pub fn condition_steps(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step, PatternStep::Condition(_)))
+            .count()
+    }
+
+    /// Returns the bitmask of the conditions of the `(?N)` steps, in the bit
+    /// layout of [`Event::conditions`](crate::common::event::Event::conditions).
+    ///
+    /// Every match consumes an event for each of these steps, so a group of
+    /// events in which one of these conditions never holds cannot match.
+    /// Conditions beyond bit 63 are left out, which only weakens that test.
+    #[must_use]
+    pub fn required_conditions(&self) -> u64 {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                PatternStep::Condition(idx) => 1u64.checked_shl(u32::try_from(*idx).ok()?),
+                _ => None,
+            })
+            .fold(0, |mask, bit| mask | bit)
+    }
+
+    /// Returns the 0-indexed condition of the first `(?N)` step if no other
+    /// event-consuming step precedes it.
+    ///
+    /// Time constraints before the first matched event are vacuously true,
+    /// so a match of such a pattern can only start at an event where this
+    /// condition holds. The executors skip every other start position.
+    #[must_use]
+    pub fn entry_condition(&self) -> Option<usize> {
+        match self.steps.iter().find(|step| !step.is_time_constraint()) {
+            Some(PatternStep::Condition(idx)) => Some(*idx),
+            _ => None,
+        }
+    }
+
+    /// Returns the capture name of each `(?N)` step, in the order of the
+    /// timestamps `sequence_match_events` returns. `None` for unnamed steps.
+    #[must_use]
+    pub fn condition_captures(&self) -> Vec<Option<&str>> {
+        self.steps
+            .iter()
+            .zip(&self.captures)
+            .filter(|(step, _)| matches!(step, PatternStep::Condition(_)))
+            .map(|(_, name)| name.as_deref())
+            .collect()
+    }
+
+    /// Returns true if the pattern has a `(?t...)` or `(?T...)` time
+    /// constraint step.
+    #[must_use]
+    pub fn has_time_constraints(&self) -> bool {
+        self.steps.iter().any(PatternStep::is_time_constraint)
+    }
+
+    /// Checks that every `(?N)`, `(?!N)` and `(?!N)*` step refers to one of
+    /// the `supplied` condition arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PatternError`] positioned at the first step with
+    /// `N > supplied`. Such a step could never be evaluated.
+    pub fn check_conditions(&self, supplied: usize) -> Result<(), PatternError> {
+        for (step, &position) in self.steps.iter().zip(&self.positions) {
+            if let Some(idx) = step.condition_index() {
+                if idx >= supplied {
+                    return Err(PatternError::new(
+                        format!(
+                            "unknown condition (?{}), only {supplied} conditions supplied",
+                            idx + 1
+                        ),
+                        position,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats the pattern in canonical form: no whitespace, lower-case units and
+/// a time constraint written with a unit in the largest unit that divides it
+/// exactly, so `^ (?1:a) . * (?t <= 90 M) (?2)` prints as
+/// `^(?1:a).*(?t<=90m)(?2)`. Parsing the text gives back an equal pattern.
+impl fmt::Display for CompiledPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.anchored_start {
+            f.write_str("^")?;
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            match *step {
+                PatternStep::Condition(idx) => {
+                    match self.captures.get(i).and_then(Option::as_ref) {
+                        Some(name) => write!(f, "(?{}:{name})", idx + 1)?,
+                        None => write!(f, "(?{})", idx + 1)?,
+                    }
+                }
+                PatternStep::AnyEvents => f.write_str(".*")?,
+                PatternStep::OneEvent => f.write_str(".")?,
+                PatternStep::NotCondition(idx) => write!(f, "(?!{})", idx + 1)?,
+                PatternStep::AnyNotCondition(idx) => write!(f, "(?!{})*", idx + 1)?,
+                PatternStep::TimeConstraint(op, seconds) => {
+                    write!(f, "(?t{}{seconds})", op.symbol())?;
+                }
+                PatternStep::StartTimeConstraint(op, seconds) => {
+                    write!(f, "(?T{}{seconds})", op.symbol())?;
+                }
+                PatternStep::TimeConstraintMicros(op, micros) => {
+                    write!(f, "(?t{}{})", op.symbol(), with_unit(micros))?;
+                }
+                PatternStep::StartTimeConstraintMicros(op, micros) => {
+                    write!(f, "(?T{}{})", op.symbol(), with_unit(micros))?;
+                }
+            }
+        }
+        if self.anchored_end {
+            f.write_str("$")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `micros` in the largest time unit that divides it exactly, or in
+/// seconds when it is zero.
+fn with_unit(micros: i64) -> String {
+    if micros == 0 {
+        return "0s".to_string();
+    }
+    ["h", "m", "s", "ms", "us"]
+        .into_iter()
+        .find_map(|unit| {
+            let per = time_unit_micros(unit)?;
+            (micros % per == 0).then(|| format!("{}{unit}", micros / per))
+        })
+        .unwrap_or_else(|| format!("{micros}us"))
 }
 
 /// Error returned when pattern parsing fails.
@@ -70,6 +347,24 @@ pub struct PatternError {
     pub message: String,
     /// Position in the input string where the error occurred.
     pub position: usize,
+    /// Text of the step the error occurred in, e.g. `(?t=>5)`. Empty when the
+    /// error is not tied to the text of one step.
+    pub token: String,
+    /// Corrected text for `token`, when the mistake is a common one.
+    pub suggestion: Option<String>,
+}
+
+impl PatternError {
+    /// Creates an error at `position` without a token or suggestion.
+    #[must_use]
+    pub fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+            token: String::new(),
+            suggestion: None,
+        }
+    }
 }
 
 impl fmt::Display for PatternError {
@@ -78,7 +373,14 @@ impl fmt::Display for PatternError {
             f,
             "pattern error at position {}: {}",
             self.position, self.message
-        )
+        )?;
+        if !self.token.is_empty() {
+            write!(f, " in '{}'", self.token)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; did you mean '{suggestion}'?")?;
+        }
+        Ok(())
     }
 }
 
@@ -100,19 +402,99 @@ impl std::error::Error for PatternError {}
 /// ```
 pub fn parse_pattern(input: &str) -> Result<CompiledPattern, PatternError> {
     let mut parser = Parser::new(input);
-    let steps = parser.parse()?;
+    let (steps, positions) = parser.parse().map_err(|error| {
+        let token = parser.step_text();
+        PatternError {
+            suggestion: suggest(&token),
+            token,
+            ..error
+        }
+    })?;
     if steps.is_empty() {
-        return Err(PatternError {
-            message: "empty pattern".to_string(),
-            position: 0,
-        });
+        return Err(PatternError::new("empty pattern", 0));
     }
-    Ok(CompiledPattern { steps })
+    Ok(CompiledPattern {
+        steps,
+        positions,
+        anchored_start: parser.anchored_start,
+        anchored_end: parser.anchored_end,
+        captures: parser.captures,
+    })
+}
+
+/// Returns a corrected version of a step that failed to parse, trying common
+/// mistakes in turn: whitespace inside the step, reversed or single-character
+/// operators (`=>`, `=<`, `<>`, `=`), a missing `)` or `?`,
+/// condition `0`, a bare `*` and spelled-out or day time units.
+fn suggest(token: &str) -> Option<String> {
+    let compact: String = token.chars().filter(|c| !c.is_whitespace()).collect();
+    let candidates = [
+        Some(compact.clone()),
+        Some(compact.replacen("=>", ">=", 1)),
+        Some(compact.replacen("=<", "<=", 1)),
+        Some(compact.replacen("<>", "!=", 1)),
+        (compact.starts_with("(?t=") && !compact.starts_with("(?t=="))
+            .then(|| compact.replacen("(?t=", "(?t==", 1)),
+        Some(format!("{compact})")),
+        (!compact.starts_with("(?")).then(|| compact.replacen('(', "(?", 1)),
+        (compact == "(?0)").then(|| "(?1)".to_string()),
+        (compact == "*").then(|| ".*".to_string()),
+        fix_time_unit(&compact),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|candidate| candidate != token && is_single_step(candidate))
+}
+
+/// Rewrites the unit of a time constraint such as `(?t<=5sec)` or
+/// `(?T<=2d)` to a supported one (`(?t<=5s)`, `(?T<=48h)`).
+fn fix_time_unit(step: &str) -> Option<String> {
+    let prefix = if step.starts_with("(?T") {
+        "(?T"
+    } else {
+        "(?t"
+    };
+    let body = step.strip_prefix(prefix)?.strip_suffix(')')?;
+    let (head, unit) = body.split_at(body.find(|c: char| c.is_ascii_alphabetic())?);
+    let (op, value) = head.split_at(head.find(|c: char| c.is_ascii_digit())?);
+    let lower = unit.to_ascii_lowercase();
+    let (value, unit) = match lower.as_str() {
+        "d" | "day" | "days" => (value.parse::<u64>().ok()?.checked_mul(24)?.to_string(), "h"),
+        "sec" | "secs" | "second" | "seconds" => (value.to_string(), "s"),
+        "min" | "mins" | "minute" | "minutes" => (value.to_string(), "m"),
+        "hr" | "hrs" | "hour" | "hours" => (value.to_string(), "h"),
+        "msec" | "msecs" | "milli" | "millis" | "millisecond" | "milliseconds" => {
+            (value.to_string(), "ms")
+        }
+        "usec" | "usecs" | "micro" | "micros" | "microsecond" | "microseconds" => {
+            (value.to_string(), "us")
+        }
+        other => (value.to_string(), time_unit_micros(other).map(|_| other)?),
+    };
+    Some(format!("{prefix}{op}{value}{unit})"))
+}
+
+/// Returns true if `text` parses as exactly one step.
+fn is_single_step(text: &str) -> bool {
+    Parser::new(text)
+        .parse()
+        .is_ok_and(|(steps, _)| steps.len() == 1)
 }
 
 struct Parser<'a> {
     input: &'a [u8],
     pos: usize,
+    /// Offset where the step being parsed starts.
+    step_start: usize,
+    /// Whether a leading `^` was parsed.
+    anchored_start: bool,
+    /// Whether a trailing `$` was parsed.
+    anchored_end: bool,
+    /// Capture name of each parsed step, parallel to the steps.
+    captures: Vec<Option<String>>,
+    /// Capture name of the step being parsed.
+    capture: Option<String>,
 }
 
 impl<'a> Parser<'a> {
@@ -120,78 +502,238 @@ impl<'a> Parser<'a> {
         Self {
             input: input.as_bytes(),
             pos: 0,
+            step_start: 0,
+            anchored_start: false,
+            anchored_end: false,
+            captures: Vec::new(),
+            capture: None,
         }
     }
 
-    fn parse(&mut self) -> Result<Vec<PatternStep>, PatternError> {
+    /// Returns the text of the step being parsed: a group up to its `)` (or
+    /// up to the next `(` when unclosed), otherwise up to the next step or
+    /// whitespace.
+    fn step_text(&self) -> String {
+        let rest = &self.input[self.step_start.min(self.input.len())..];
+        let Some((&first, tail)) = rest.split_first() else {
+            return String::new();
+        };
+        let len = if first == b'(' {
+            tail.iter()
+                .position(|&c| c == b')' || c == b'(')
+                .map_or(rest.len(), |i| if tail[i] == b')' { i + 2 } else { i + 1 })
+        } else {
+            tail.iter()
+                .position(|&c| c == b'(' || c == b'.' || c.is_ascii_whitespace())
+                .map_or(rest.len(), |i| i + 1)
+        };
+        String::from_utf8_lossy(&rest[..len]).into_owned()
+    }
+
+    fn parse(&mut self) -> Result<(Vec<PatternStep>, Vec<usize>), PatternError> {
         let mut steps = Vec::new();
+        let mut positions = Vec::new();
         while self.pos < self.input.len() {
             self.skip_whitespace();
             if self.pos >= self.input.len() {
                 break;
             }
+            self.step_start = self.pos;
+            if matches!(self.peek(), Some(b'^' | b'$')) {
+                self.parse_anchor(steps.is_empty())?;
+                continue;
+            }
+            positions.push(self.pos);
             let step = self.parse_step()?;
+            let capture = self.capture.take();
+            if let Some(name) = &capture {
+                if self.captures.iter().flatten().any(|other| other == name) {
+                    return Err(PatternError::new(
+                        format!("duplicate capture name '{name}'"),
+                        self.step_start,
+                    ));
+                }
+            }
             steps.push(step);
+            self.captures.push(capture);
         }
-        Ok(steps)
+        Ok((steps, positions))
     }
 
     fn parse_step(&mut self) -> Result<PatternStep, PatternError> {
         match self.peek() {
             Some(b'(') => self.parse_group(),
             Some(b'.') => self.parse_dot(),
-            Some(c) => Err(PatternError {
-                message: format!("unexpected character '{}'", char::from(c)),
-                position: self.pos,
-            }),
-            None => Err(PatternError {
-                message: "unexpected end of pattern".to_string(),
-                position: self.pos,
-            }),
+            Some(c) => Err(PatternError::new(
+                format!("unexpected character '{}'", char::from(c)),
+                self.pos,
+            )),
+            None => Err(PatternError::new("unexpected end of pattern", self.pos)),
+        }
+    }
+
+    /// Parses `^`, allowed only before the first step, or `$`, allowed only
+    /// after the last.
+    fn parse_anchor(&mut self, at_start: bool) -> Result<(), PatternError> {
+        let start = self.pos;
+        if self.peek() == Some(b'^') {
+            if !at_start || self.anchored_start {
+                return Err(PatternError::new(
+                    "'^' is only allowed at the start of the pattern",
+                    start,
+                ));
+            }
+            self.advance();
+            self.anchored_start = true;
+            return Ok(());
         }
+        self.expect(b'$')?;
+        self.skip_whitespace();
+        if self.pos < self.input.len() {
+            return Err(PatternError::new(
+                "'$' is only allowed at the end of the pattern",
+                start,
+            ));
+        }
+        self.anchored_end = true;
+        Ok(())
     }
 
     fn parse_group(&mut self) -> Result<PatternStep, PatternError> {
         self.expect(b'(')?;
+        self.skip_whitespace();
         self.expect(b'?')?;
+        self.skip_whitespace();
 
         match self.peek() {
-            Some(b't') => self.parse_time_constraint(),
+            Some(b't' | b'T') => self.parse_time_constraint(),
+            Some(b'!') => self.parse_not_condition(),
             Some(c) if c.is_ascii_digit() => self.parse_condition(),
-            Some(c) => Err(PatternError {
-                message: format!("expected digit or 't' after '(?', got '{}'", char::from(c)),
-                position: self.pos,
-            }),
-            None => Err(PatternError {
-                message: "unexpected end of pattern after '(?'".to_string(),
-                position: self.pos,
-            }),
+            Some(c) => Err(PatternError::new(
+                format!(
+                    "expected digit, '!', 't' or 'T' after '(?', got '{}'",
+                    char::from(c)
+                ),
+                self.pos,
+            )),
+            None => Err(PatternError::new(
+                "unexpected end of pattern after '(?'",
+                self.pos,
+            )),
         }
     }
 
     fn parse_condition(&mut self) -> Result<PatternStep, PatternError> {
+        self.parse_condition_index(true).map(PatternStep::Condition)
+    }
+
+    /// Parses `!N)` and an optional `*` following the `(?` of a negated step.
+    fn parse_not_condition(&mut self) -> Result<PatternStep, PatternError> {
+        self.expect(b'!')?;
+        self.skip_whitespace();
+        let idx = self.parse_condition_index(false)?;
+        if self.accept(b'*') {
+            Ok(PatternStep::AnyNotCondition(idx))
+        } else {
+            Ok(PatternStep::NotCondition(idx))
+        }
+    }
+
+    /// Parses `N)` and returns the 0-indexed condition. With `capture`, also
+    /// accepts `N:name)`, keeping the name in `self.capture`.
+    fn parse_condition_index(&mut self, capture: bool) -> Result<usize, PatternError> {
         let start = self.pos;
         let num = self.parse_number()?;
+        self.skip_whitespace();
+        if capture && self.peek() == Some(b':') {
+            self.advance();
+            self.skip_whitespace();
+            self.capture = Some(self.parse_capture_name()?);
+            self.skip_whitespace();
+        }
         self.expect(b')')?;
         if num == 0 {
-            return Err(PatternError {
-                message: "condition index must be >= 1 (1-indexed)".to_string(),
-                position: start,
-            });
+            return Err(PatternError::new(
+                "condition index must be >= 1 (1-indexed)",
+                start,
+            ));
         }
         // Convert from 1-indexed (user-facing) to 0-indexed (internal)
-        Ok(PatternStep::Condition(num - 1))
+        Ok(num - 1)
+    }
+
+    /// Parses the name of a `(?N:name)` capture.
+    fn parse_capture_name(&mut self) -> Result<String, PatternError> {
+        let start = self.pos;
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == b'_')
+        {
+            return Err(PatternError::new(
+                "expected capture name (letters, digits and '_') after ':'",
+                start,
+            ));
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.advance();
+        }
+        Ok(String::from_utf8_lossy(&self.input[start..self.pos]).into_owned())
     }
 
+    /// Parses `t` or `T`, an operator, a value and an optional unit.
     fn parse_time_constraint(&mut self) -> Result<PatternStep, PatternError> {
-        self.expect(b't')?;
-        let op = self.parse_time_op()?;
-        let seconds = self.parse_number()? as i64;
+        let letter = self.peek().unwrap_or(b't');
+        self.advance();
+        let from_start = letter == b'T';
+        self.skip_whitespace();
+        let op = self.parse_time_op(letter)?;
+        self.skip_whitespace();
+        let start = self.pos;
+        let value = self.parse_number()? as i64;
+        self.skip_whitespace();
+        let step = match (self.parse_time_unit()?, from_start) {
+            (None, false) => PatternStep::TimeConstraint(op, value),
+            (None, true) => PatternStep::StartTimeConstraint(op, value),
+            (Some(micros_per_unit), from_start) => {
+                let micros = value.checked_mul(micros_per_unit).ok_or_else(|| {
+                    PatternError::new("time constraint overflows microsecond range", start)
+                })?;
+                if from_start {
+                    PatternStep::StartTimeConstraintMicros(op, micros)
+                } else {
+                    PatternStep::TimeConstraintMicros(op, micros)
+                }
+            }
+        };
+        self.skip_whitespace();
         self.expect(b')')?;
-        Ok(PatternStep::TimeConstraint(op, seconds))
+        Ok(step)
+    }
+
+    /// Parses an optional time unit suffix in any case, returning
+    /// microseconds per unit.
+    fn parse_time_unit(&mut self) -> Result<Option<i64>, PatternError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.advance();
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()
+            .and_then(|unit| time_unit_micros(&unit.to_ascii_lowercase()))
+            .map(Some)
+            .ok_or_else(|| {
+                PatternError::new("unknown time unit (expected us, ms, s, m, or h)", start)
+            })
     }
 
-    fn parse_time_op(&mut self) -> Result<TimeOp, PatternError> {
+    /// Parses the operator after `(?t` or `(?T` (`letter`).
+    fn parse_time_op(&mut self, letter: u8) -> Result<TimeOp, PatternError> {
         match (self.peek(), self.peek_at(1)) {
             (Some(b'>'), Some(b'=')) => {
                 self.advance();
@@ -221,18 +763,19 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(TimeOp::Lt)
             }
-            _ => Err(PatternError {
-                message: "expected comparison operator (>=, <=, >, <, ==, !=) after '(?t'"
-                    .to_string(),
-                position: self.pos,
-            }),
+            _ => Err(PatternError::new(
+                format!(
+                    "expected comparison operator (>=, <=, >, <, ==, !=) after '(?{}'",
+                    char::from(letter)
+                ),
+                self.pos,
+            )),
         }
     }
 
     fn parse_dot(&mut self) -> Result<PatternStep, PatternError> {
         self.expect(b'.')?;
-        if self.peek() == Some(b'*') {
-            self.advance();
+        if self.accept(b'*') {
             Ok(PatternStep::AnyEvents)
         } else {
             Ok(PatternStep::OneEvent)
@@ -248,10 +791,7 @@ impl<'a> Parser<'a> {
                 num = num
                     .checked_mul(10)
                     .and_then(|n| n.checked_add((c - b'0') as usize))
-                    .ok_or_else(|| PatternError {
-                        message: "number overflow in pattern".to_string(),
-                        position: start,
-                    })?;
+                    .ok_or_else(|| PatternError::new("number overflow in pattern", start))?;
                 digits += 1;
                 self.advance();
             } else {
@@ -259,10 +799,7 @@ impl<'a> Parser<'a> {
             }
         }
         if digits == 0 {
-            return Err(PatternError {
-                message: "expected number".to_string(),
-                position: self.pos,
-            });
+            return Err(PatternError::new("expected number", self.pos));
         }
         Ok(num)
     }
@@ -277,6 +814,21 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Consumes `c`, and any whitespace before it, if it is the next
+    /// non-whitespace character. A `*` can only follow `.` or `(?!N)`, so
+    /// `. *` reads as `.*` rather than as a stray `*`.
+    fn accept(&mut self, c: u8) -> bool {
+        let start = self.pos;
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.advance();
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
     fn peek(&self) -> Option<u8> {
         self.input.get(self.pos).copied()
     }
@@ -295,18 +847,18 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(())
             }
-            Some(c) => Err(PatternError {
-                message: format!(
+            Some(c) => Err(PatternError::new(
+                format!(
                     "expected '{}', got '{}'",
                     char::from(expected),
                     char::from(c)
                 ),
-                position: self.pos,
-            }),
-            None => Err(PatternError {
-                message: format!("expected '{}', got end of pattern", char::from(expected)),
-                position: self.pos,
-            }),
+                self.pos,
+            )),
+            None => Err(PatternError::new(
+                format!("expected '{}', got end of pattern", char::from(expected)),
+                self.pos,
+            )),
         }
     }
 }
@@ -389,6 +941,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_time_constraint_units() {
+        for (unit, micros) in &[
+            ("us", 1_500),
+            ("ms", 1_500_000),
+            ("s", 1_500_000_000),
+            ("m", 90_000_000_000),
+            ("h", 5_400_000_000_000),
+        ] {
+            let pat = format!("(?1)(?t<=1500{unit})(?2)");
+            let p = parse_pattern(&pat).unwrap();
+            assert_eq!(
+                p.steps[1],
+                PatternStep::TimeConstraintMicros(TimeOp::Lte, *micros),
+                "failed for unit {unit}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_time_constraint_without_unit_stays_seconds() {
+        let p = parse_pattern("(?1)(?t<=2)(?2)").unwrap();
+        assert_eq!(p.steps[1], PatternStep::TimeConstraint(TimeOp::Lte, 2));
+    }
+
+    #[test]
+    fn test_time_constraint_unknown_unit() {
+        let err = parse_pattern("(?1)(?t<=2d)(?2)").unwrap_err();
+        assert!(err.message.contains("unknown time unit"));
+        assert_eq!(err.position, 10);
+    }
+
+    #[test]
+    fn test_time_constraint_unit_overflow() {
+        let err = parse_pattern("(?1)(?t<=9999999999999h)(?2)").unwrap_err();
+        assert!(err.message.contains("overflow"));
+    }
+
     #[test]
     fn test_complex_pattern() {
         let p = parse_pattern("(?1).*(?2).*(?3)(?4)").unwrap();
@@ -419,6 +1009,56 @@ mod tests {
         assert_eq!(p.steps.len(), 3);
     }
 
+    #[test]
+    fn test_whitespace_inside_steps() {
+        let spaced =
+            parse_pattern("^ ( ?1 : start ) . * ( ?! 3 ) * . (?t >= 5 m) (?T < 2 H) (?2) $")
+                .unwrap();
+        let compact = parse_pattern("^(?1:start).*(?!3)*.(?t>=5m)(?T<2h)(?2)$").unwrap();
+        assert_eq!(spaced.steps, compact.steps);
+        assert_eq!(spaced.captures, compact.captures);
+        assert!(spaced.anchored_start && spaced.anchored_end);
+        // Operators, numbers and units are not split
+        assert!(parse_pattern("(?t> =5)").is_err());
+        assert!(parse_pattern("(?1 0)").is_err());
+        assert!(parse_pattern("(?t<=5 m s)").is_err());
+    }
+
+    #[test]
+    fn test_time_units_ignore_case() {
+        let p = parse_pattern("(?1)(?t<=100MS)(?T<=2H)(?2)").unwrap();
+        assert_eq!(
+            p.steps[1],
+            PatternStep::TimeConstraintMicros(TimeOp::Lte, 100_000)
+        );
+        assert_eq!(
+            p.steps[2],
+            PatternStep::StartTimeConstraintMicros(TimeOp::Lte, 7_200_000_000)
+        );
+    }
+
+    #[test]
+    fn test_display_is_canonical() {
+        let cases = [
+            (" (?1) .* (?2) ", "(?1).*(?2)"),
+            (
+                "^ ( ?1 : a ) . * (?t <= 90 M) (?2)",
+                "^(?1:a).*(?t<=90m)(?2)",
+            ),
+            ("(?1)(?t>=3600)(?2)", "(?1)(?t>=3600)(?2)"),
+            ("(?1)(?T!=5400s)(?2)$", "(?1)(?T!=90m)(?2)$"),
+            (
+                "(?1)(?t<1500MS)(?t>0ms)(?!2)*.(?!3)",
+                "(?1)(?t<1500ms)(?t>0s)(?!2)*.(?!3)",
+            ),
+        ];
+        for (pattern, canonical) in cases {
+            let text = parse_pattern(pattern).unwrap().to_string();
+            assert_eq!(text, canonical, "{pattern}");
+            assert_eq!(parse_pattern(&text).unwrap().to_string(), text);
+        }
+    }
+
     #[test]
     fn test_invalid_character() {
         let err = parse_pattern("(?1)x(?2)").unwrap_err();
@@ -494,7 +1134,7 @@ mod tests {
     #[test]
     fn test_invalid_after_question_mark() {
         let err = parse_pattern("(?x)").unwrap_err();
-        assert!(err.message.contains("expected digit or 't'"));
+        assert!(err.message.contains("expected digit, '!', 't' or 'T'"));
     }
 
     #[test]
@@ -509,21 +1149,257 @@ mod tests {
         assert!(err.message.contains("expected number"));
     }
 
+    #[test]
+    fn test_max_condition() {
+        assert_eq!(parse_pattern("(?1).*(?3)(?2)").unwrap().max_condition(), 3);
+        assert_eq!(parse_pattern(".*").unwrap().max_condition(), 0);
+    }
+
+    #[test]
+    fn test_condition_steps() {
+        let pattern = parse_pattern("(?1).*(?t<=5)(?2)(?!3)(?1)").unwrap();
+        assert_eq!(pattern.condition_steps(), 3);
+        assert_eq!(parse_pattern(".*").unwrap().condition_steps(), 0);
+    }
+
+    #[test]
+    fn test_required_conditions() {
+        let pattern = parse_pattern("(?1).*(?t<=5)(?3)(?!2)(?!4)*(?1)").unwrap();
+        assert_eq!(pattern.required_conditions(), 0b101);
+        assert_eq!(
+            parse_pattern("(?64)").unwrap().required_conditions(),
+            1 << 63
+        );
+        assert_eq!(parse_pattern("(?65).*").unwrap().required_conditions(), 0);
+    }
+
+    #[test]
+    fn test_has_time_constraints() {
+        assert!(!parse_pattern("(?1).*(?2)").unwrap().has_time_constraints());
+        assert!(parse_pattern("(?1)(?t<=5)(?2)")
+            .unwrap()
+            .has_time_constraints());
+        assert!(parse_pattern("(?1)(?t>90m)(?2)")
+            .unwrap()
+            .has_time_constraints());
+        assert!(parse_pattern("(?1).*(?T<=1h)(?2)")
+            .unwrap()
+            .has_time_constraints());
+    }
+
+    #[test]
+    fn test_start_time_constraint() {
+        let p = parse_pattern("(?1).*(?T<=3600)(?2)(?T>90m)(?3)").unwrap();
+        assert_eq!(
+            p.steps[2],
+            PatternStep::StartTimeConstraint(TimeOp::Lte, 3600)
+        );
+        assert_eq!(
+            p.steps[4],
+            PatternStep::StartTimeConstraintMicros(TimeOp::Gt, 5_400_000_000)
+        );
+        assert!(p.steps[2].is_start_time_constraint());
+        assert!(!PatternStep::TimeConstraint(TimeOp::Lte, 1).is_start_time_constraint());
+        let err = parse_pattern("(?1)(?T=>5)").unwrap_err();
+        assert!(err.message.contains("after '(?T'"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_check_conditions_reports_offset() {
+        let pattern = parse_pattern("(?1) .*(?9)").unwrap();
+        assert_eq!(pattern.positions, vec![0, 5, 7]);
+        assert!(pattern.check_conditions(9).is_ok());
+        let err = pattern.check_conditions(4).unwrap_err();
+        assert_eq!(err.position, 7);
+        assert_eq!(
+            err.message,
+            "unknown condition (?9), only 4 conditions supplied"
+        );
+    }
+
+    #[test]
+    fn test_not_condition() {
+        let p = parse_pattern("(?1)(?!3)*(?2)(?!2)").unwrap();
+        assert_eq!(
+            p.steps,
+            vec![
+                PatternStep::Condition(0),
+                PatternStep::AnyNotCondition(2),
+                PatternStep::Condition(1),
+                PatternStep::NotCondition(1),
+            ]
+        );
+        assert_eq!(p.max_condition(), 3);
+    }
+
+    #[test]
+    fn test_not_condition_errors() {
+        assert!(parse_pattern("(?!0)").is_err());
+        assert!(parse_pattern("(?!)").is_err());
+        assert!(parse_pattern("(?!1").is_err());
+        assert!(parse_pattern("(?!t>5)").is_err());
+        // A star only follows a negated condition.
+        assert!(parse_pattern("(?1)*").is_err());
+    }
+
+    #[test]
+    fn test_check_conditions_covers_negated_steps() {
+        let pattern = parse_pattern("(?1)(?!4)*(?2)").unwrap();
+        let err = pattern.check_conditions(3).unwrap_err();
+        assert_eq!(err.position, 4);
+    }
+
+    #[test]
+    fn test_anchors() {
+        let p = parse_pattern("^(?1).*(?2)$").unwrap();
+        assert!(p.anchored_start && p.anchored_end);
+        assert_eq!(
+            p.steps,
+            vec![
+                PatternStep::Condition(0),
+                PatternStep::AnyEvents,
+                PatternStep::Condition(1),
+            ]
+        );
+        assert_eq!(p.positions, vec![1, 5, 7]);
+
+        let p = parse_pattern(" ^ (?1) ").unwrap();
+        assert!(p.anchored_start && !p.anchored_end);
+        let p = parse_pattern("(?1) $ ").unwrap();
+        assert!(!p.anchored_start && p.anchored_end);
+        assert!(!parse_pattern("(?1)").unwrap().anchored_start);
+    }
+
+    #[test]
+    fn test_captures() {
+        let p = parse_pattern("(?1:landing).*(?3)(?2:checkout_2)").unwrap();
+        assert_eq!(
+            p.steps,
+            vec![
+                PatternStep::Condition(0),
+                PatternStep::AnyEvents,
+                PatternStep::Condition(2),
+                PatternStep::Condition(1),
+            ]
+        );
+        assert_eq!(p.positions, vec![0, 12, 14, 18]);
+        assert_eq!(
+            p.condition_captures(),
+            vec![Some("landing"), None, Some("checkout_2")]
+        );
+        assert!(parse_pattern("(?1)")
+            .unwrap()
+            .condition_captures()
+            .iter()
+            .all(Option::is_none));
+    }
+
+    #[test]
+    fn test_capture_errors() {
+        for (pattern, position, message) in [
+            ("(?1:)", 4, "expected capture name"),
+            ("(?1:2x)", 4, "expected capture name"),
+            ("(?1:a-b)", 5, "expected ')'"),
+            ("(?1:a)(?2:a)", 6, "duplicate capture name 'a'"),
+            ("(?!1:a)", 4, "expected ')'"),
+        ] {
+            let err = parse_pattern(pattern).unwrap_err();
+            assert_eq!(err.position, position, "{pattern}");
+            assert!(err.message.starts_with(message), "{pattern}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_misplaced_anchors_rejected() {
+        for (pattern, position, anchor) in [
+            ("(?1)^(?2)", 4, '^'),
+            ("^^(?1)", 1, '^'),
+            ("(?1)$(?2)", 4, '$'),
+            ("(?1)$$", 4, '$'),
+        ] {
+            let err = parse_pattern(pattern).unwrap_err();
+            assert_eq!(err.position, position, "{pattern}");
+            assert!(err.message.starts_with(&format!("'{anchor}'")), "{pattern}");
+        }
+        assert_eq!(parse_pattern("^$").unwrap_err().message, "empty pattern");
+    }
+
     #[test]
     fn test_pattern_error_display() {
-        let err = PatternError {
-            message: "test error".to_string(),
-            position: 5,
-        };
+        let err = PatternError::new("test error", 5);
         assert_eq!(err.to_string(), "pattern error at position 5: test error");
+        let err = parse_pattern("(?1)(?t=>5)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pattern error at position 7: expected comparison operator (>=, <=, >, <, ==, !=) after '(?t' in '(?t=>5)'; did you mean '(?t>=5)'?"
+        );
+    }
+
+    #[test]
+    fn test_error_token_is_failing_step() {
+        let token = |pattern: &str| parse_pattern(pattern).unwrap_err().token;
+        assert_eq!(token("(?1)(?x)(?2)"), "(?x)");
+        assert_eq!(token("(?1)x(?2)"), "x");
+        assert_eq!(token("(?1)(?2"), "(?2");
+        assert_eq!(token("(?1(?2)"), "(?1");
+        assert_eq!(token("(?1) *.*"), "*");
+        assert_eq!(token(""), "");
+        // Errors found after parsing carry no token.
+        let err = parse_pattern("(?5)")
+            .unwrap()
+            .check_conditions(2)
+            .unwrap_err();
+        assert_eq!(err.token, "");
+        assert_eq!(err.suggestion, None);
+    }
+
+    #[test]
+    fn test_error_suggestions() {
+        let suggestion = |pattern: &str| parse_pattern(pattern).unwrap_err().suggestion;
+        let cases = [
+            ("(?t=>5)", "(?t>=5)"),
+            ("(?t=<5)", "(?t<=5)"),
+            ("(?t<>5)", "(?t!=5)"),
+            ("(?t=5)", "(?t==5)"),
+            ("(?T=>5)", "(?T>=5)"),
+            ("(?T<=2d)", "(?T<=48h)"),
+            ("(?t> =5)", "(?t>=5)"),
+            ("(?1 0)", "(?10)"),
+            ("(?1", "(?1)"),
+            ("(?t<=30s", "(?t<=30s)"),
+            ("(1)", "(?1)"),
+            ("(?0)", "(?1)"),
+            ("*", ".*"),
+            ("(?t<=2d)", "(?t<=48h)"),
+            ("(?t<=5sec)", "(?t<=5s)"),
+            ("(?t<=10 minutes)", "(?t<=10m)"),
+            ("(?t<=1hr)", "(?t<=1h)"),
+            ("(?t<=100 Millis)", "(?t<=100ms)"),
+        ];
+        for (step, expected) in cases {
+            let pattern = format!("(?1).*{step}");
+            assert_eq!(suggestion(&pattern).as_deref(), Some(expected), "{pattern}");
+            assert!(parse_pattern(&format!("(?1).*{expected}")).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_no_suggestion_without_a_likely_fix() {
+        for pattern in [
+            "(?x)",
+            "(?1)(?t>=)",
+            "(?t<=2fortnights)",
+            "(?1)x",
+            "(?99999999999999999999999)",
+        ] {
+            let err = parse_pattern(pattern).unwrap_err();
+            assert_eq!(err.suggestion, None, "{pattern}");
+        }
     }
 
     #[test]
     fn test_pattern_error_is_std_error() {
-        let err = PatternError {
-            message: "test".to_string(),
-            position: 0,
-        };
+        let err = PatternError::new("test", 0);
         // Ensure PatternError implements std::error::Error
         let _: &dyn std::error::Error = &err;
     }
@@ -22,9 +22,41 @@
 //! FROM user_activity
 //! GROUP BY user_id, cohort_month
 //! ```
+//!
+//! `retention(conditions)` takes the conditions as one `BOOLEAN[]` instead,
+//! for condition sets built as a list expression. A list is not bound by the
+//! 64 arguments of the variadic form: conditions past the 64th are kept in
+//! [`RetentionState::wide_conditions_met`].
+//!
+//! `retention_counts` takes the same conditions and returns a `BIGINT[]` of
+//! per-condition counts: the sum of the `retention` arrays of every row taken
+//! on its own. Fed one row per user, it counts retained users per cohort
+//! without an `UNNEST` + `SUM` over per-user arrays. `retention_rate` divides
+//! those counts by the anchor count, returning the `DOUBLE[]` share of anchor
+//! rows retained per condition.
+//!
+//! `retention_with_validity` returns the `retention` array together with,
+//! per condition, the number of rows where the condition was non-`NULL`.
+//! Every other function reads a `NULL` condition as false, so a condition
+//! whose events were not instrumented on some days looks like a drop in
+//! retention; a count below the group's row count shows the gap.
+//!
+//! `retention_by_period(base_ts, ts, period, n)` derives the conditions from
+//! timestamps: condition `k` is true for a row whose `ts` falls in
+//! `[base_ts + k * period, base_ts + (k + 1) * period)`. It returns the same
+//! `BOOLEAN[]` as `retention` over those `n` conditions, without writing them
+//! out one by one.
+//!
+//! The scalar `cohort_of(ts, anchor, period)` returns the same period index
+//! for a single timestamp, [`cohort_index`], negative before the anchor, for
+//! assigning cohorts and periods in SQL.
+
+use crate::common::state_blob::{BlobError, BlobReader, BlobWriter};
+use crate::common::timestamp::{elapsed_wide, TimestampOverflow};
 
-/// Maximum number of conditions supported by retention.
-pub const MAX_CONDITIONS: usize = 32;
+/// Number of conditions held in [`RetentionState::conditions_met`]; further
+/// conditions are held in [`RetentionState::wide_conditions_met`].
+pub const INLINE_CONDITIONS: usize = 64;
 
 /// State for the retention aggregate function.
 ///
@@ -36,8 +68,12 @@ pub const MAX_CONDITIONS: usize = 32;
 pub struct RetentionState {
     /// Bitmask of conditions that were true for at least one row.
     /// Bit `i` is set if condition `i` was true for some row.
-    pub conditions_met: u32,
-    /// Number of conditions (set during first update).
+    pub conditions_met: u64,
+    /// Bitmask of conditions past the first 64, 64 per word: bit `j` of word
+    /// `w` is condition `64 * (w + 1) + j`. Empty unless a row had more than
+    /// [`INLINE_CONDITIONS`] conditions.
+    pub wide_conditions_met: Vec<u64>,
+    /// Number of conditions (the longest row seen).
     pub num_conditions: usize,
 }
 
@@ -47,6 +83,7 @@ impl RetentionState {
     pub const fn new() -> Self {
         Self {
             conditions_met: 0,
+            wide_conditions_met: Vec::new(),
             num_conditions: 0,
         }
     }
@@ -54,15 +91,50 @@ impl RetentionState {
     /// Updates the state with a row of condition values.
     ///
     /// Each condition is OR'd into the bitmask: if condition `i` is true
-    /// for this row, bit `i` is set.
+    /// for this row, bit `i` is set. Rows may differ in length (list input);
+    /// missing trailing conditions count as false.
     #[inline]
     pub fn update(&mut self, conditions: &[bool]) {
-        self.num_conditions = conditions.len();
-        for (i, &cond) in conditions.iter().enumerate() {
-            if cond && i < MAX_CONDITIONS {
+        self.num_conditions = self.num_conditions.max(conditions.len());
+        let (inline, wide) = conditions.split_at(conditions.len().min(INLINE_CONDITIONS));
+        for (i, &cond) in inline.iter().enumerate() {
+            if cond {
                 self.conditions_met |= 1 << i;
             }
         }
+        for (i, &cond) in wide.iter().enumerate() {
+            if cond {
+                self.mark(INLINE_CONDITIONS + i);
+            }
+        }
+    }
+
+    /// Records condition `i` as true, without changing `num_conditions`.
+    #[inline]
+    pub fn mark(&mut self, i: usize) {
+        if i < INLINE_CONDITIONS {
+            self.conditions_met |= 1 << i;
+            return;
+        }
+        let wide = i - INLINE_CONDITIONS;
+        let word = wide / INLINE_CONDITIONS;
+        if self.wide_conditions_met.len() <= word {
+            self.wide_conditions_met.resize(word + 1, 0);
+        }
+        self.wide_conditions_met[word] |= 1 << (wide % INLINE_CONDITIONS);
+    }
+
+    /// Returns true if condition `i` was true for some row.
+    #[must_use]
+    #[inline]
+    pub fn condition_met(&self, i: usize) -> bool {
+        if i < INLINE_CONDITIONS {
+            return self.conditions_met & (1 << i) != 0;
+        }
+        let wide = i - INLINE_CONDITIONS;
+        self.wide_conditions_met
+            .get(wide / INLINE_CONDITIONS)
+            .is_some_and(|word| word & (1 << (wide % INLINE_CONDITIONS)) != 0)
     }
 
     /// Combines two states by OR-ing their bitmasks.
@@ -72,12 +144,63 @@ impl RetentionState {
     #[must_use]
     #[inline]
     pub fn combine(&self, other: &Self) -> Self {
+        let (longer, shorter) = if self.wide_conditions_met.len() >= other.wide_conditions_met.len()
+        {
+            (&self.wide_conditions_met, &other.wide_conditions_met)
+        } else {
+            (&other.wide_conditions_met, &self.wide_conditions_met)
+        };
+        let mut wide_conditions_met = longer.clone();
+        for (word, &bits) in wide_conditions_met.iter_mut().zip(shorter) {
+            *word |= bits;
+        }
         Self {
             conditions_met: self.conditions_met | other.conditions_met,
+            wide_conditions_met,
             num_conditions: self.num_conditions.max(other.num_conditions),
         }
     }
 
+    /// Tag of the exported state format: `RTS` and format version 1.
+    pub const BLOB_TAG: [u8; 4] = *b"RTS\x01";
+
+    /// Exports the state as the bytes returned by `retention_state`: the
+    /// number of conditions, then one 64-bit word per 64 conditions (at
+    /// least one). See [`crate::common::state_blob`].
+    #[must_use]
+    pub fn to_blob(&self) -> Vec<u8> {
+        let words = self.num_conditions.div_ceil(INLINE_CONDITIONS).max(1);
+        let mut w = BlobWriter::new(Self::BLOB_TAG);
+        w.len(self.num_conditions);
+        w.u64(self.conditions_met);
+        for word in 1..words {
+            w.u64(self.wide_conditions_met.get(word - 1).copied().unwrap_or(0));
+        }
+        w.finish()
+    }
+
+    /// Imports a state exported by [`to_blob`](Self::to_blob).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BlobError`] when `bytes` is not a `retention` state of
+    /// this format version.
+    pub fn from_blob(bytes: &[u8]) -> Result<Self, BlobError> {
+        let mut r = BlobReader::new(bytes, Self::BLOB_TAG)?;
+        let num_conditions = usize::try_from(r.u64()?).map_err(|_| BlobError::Truncated)?;
+        let words = num_conditions.div_ceil(INLINE_CONDITIONS).max(1);
+        let conditions_met = r.u64()?;
+        // Every word is read before it is stored, so a corrupt condition
+        // count cannot allocate more than the blob holds.
+        let wide_conditions_met = (1..words).map(|_| r.u64()).collect::<Result<Vec<_>, _>>()?;
+        r.finish()?;
+        Ok(Self {
+            conditions_met,
+            wide_conditions_met,
+            num_conditions,
+        })
+    }
+
     /// Produces the final retention result.
     ///
     /// Returns a `Vec<bool>` of length `num_conditions` where:
@@ -90,18 +213,7 @@ impl RetentionState {
     pub fn finalize(&self) -> Vec<bool> {
         let anchor_met = self.conditions_met & 1 != 0;
         (0..self.num_conditions)
-            .map(|i| {
-                if !anchor_met {
-                    false
-                } else if i == 0 {
-                    true
-                } else if i >= MAX_CONDITIONS {
-                    // Conditions beyond u32 capacity are always false
-                    false
-                } else {
-                    self.conditions_met & (1 << i) != 0
-                }
-            })
+            .map(|i| anchor_met && (i == 0 || self.condition_met(i)))
             .collect()
     }
 }
@@ -112,6 +224,231 @@ impl Default for RetentionState {
     }
 }
 
+/// State for the `retention_counts` aggregate function.
+///
+/// Each row contributes its own `retention` result: the anchor count when
+/// condition 0 is true, and the count of condition `i` when both condition 0
+/// and condition `i` are true on that row.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RetentionCountsState {
+    /// Per-condition counts; `counts[0]` is the number of anchor rows.
+    pub counts: Vec<i64>,
+}
+
+impl RetentionCountsState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { counts: Vec::new() }
+    }
+
+    /// Adds one row of condition values.
+    #[inline]
+    pub fn update(&mut self, conditions: &[bool]) {
+        if self.counts.len() < conditions.len() {
+            self.counts.resize(conditions.len(), 0);
+        }
+        if conditions.first() != Some(&true) {
+            return;
+        }
+        for (count, &cond) in self.counts.iter_mut().zip(conditions) {
+            *count += i64::from(cond);
+        }
+    }
+
+    /// Combines two states by adding their counts element-wise.
+    #[must_use]
+    pub fn combine(&self, other: &Self) -> Self {
+        let mut combined = self.clone();
+        combined.combine_in_place(other);
+        combined
+    }
+
+    /// Adds the counts of `other` into `self`.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, &add) in self.counts.iter_mut().zip(&other.counts) {
+            *count += add;
+        }
+    }
+
+    /// Returns the per-condition counts, one per condition.
+    #[must_use]
+    pub fn finalize(&self) -> Vec<i64> {
+        self.counts.clone()
+    }
+
+    /// Returns each count divided by the anchor count (`retention_rate`), so
+    /// element 0 is `1.0`. `None` when no row had the anchor condition.
+    #[must_use]
+    pub fn finalize_rates(&self) -> Option<Vec<f64>> {
+        let anchor = *self.counts.first().filter(|&&anchor| anchor > 0)?;
+        Some(
+            self.counts
+                .iter()
+                .map(|&count| count as f64 / anchor as f64)
+                .collect(),
+        )
+    }
+}
+
+/// State for the `retention_with_validity` aggregate function.
+///
+/// The [`RetentionState`] of the rows with `NULL` conditions read as false,
+/// plus the number of rows where each condition was non-`NULL`.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RetentionValidityState {
+    /// Retention over the conditions, `NULL` counting as false.
+    pub retention: RetentionState,
+    /// Per-condition number of rows where the condition was non-`NULL`.
+    pub non_null_counts: Vec<i64>,
+}
+
+impl RetentionValidityState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            retention: RetentionState::new(),
+            non_null_counts: Vec::new(),
+        }
+    }
+
+    /// Adds one row of condition values, `None` for a `NULL` condition.
+    #[inline]
+    pub fn update(&mut self, conditions: &[Option<bool>]) {
+        if self.non_null_counts.len() < conditions.len() {
+            self.non_null_counts.resize(conditions.len(), 0);
+        }
+        let retention = &mut self.retention;
+        retention.num_conditions = retention.num_conditions.max(conditions.len());
+        for (i, (count, &cond)) in self.non_null_counts.iter_mut().zip(conditions).enumerate() {
+            if let Some(value) = cond {
+                *count += 1;
+                if value {
+                    retention.mark(i);
+                }
+            }
+        }
+    }
+
+    /// Combines `other` into `self`: the retention bitmasks are OR'd and the
+    /// counts added element-wise.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.retention = self.retention.combine(&other.retention);
+        if self.non_null_counts.len() < other.non_null_counts.len() {
+            self.non_null_counts.resize(other.non_null_counts.len(), 0);
+        }
+        for (count, &add) in self.non_null_counts.iter_mut().zip(&other.non_null_counts) {
+            *count += add;
+        }
+    }
+
+    /// Returns the `retention` result and the non-`NULL` count of each
+    /// condition, both one element per condition.
+    #[must_use]
+    pub fn finalize(&self) -> (Vec<bool>, Vec<i64>) {
+        (self.retention.finalize(), self.non_null_counts.clone())
+    }
+}
+
+/// State for the `retention_by_period` aggregate function.
+///
+/// A [`RetentionState`] whose condition `k` is "the event falls in period
+/// `k` after its base timestamp". Only the periods seen are stored, so the
+/// state stays a bitmask however many rows the group has.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RetentionByPeriodState {
+    /// Period length in microseconds. 0 until set by the first row.
+    pub period_us: i64,
+    /// Number of periods returned. 0 until set by the first row.
+    pub num_periods: usize,
+    /// Periods with at least one event.
+    pub periods: RetentionState,
+}
+
+impl RetentionByPeriodState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            period_us: 0,
+            num_periods: 0,
+            periods: RetentionState::new(),
+        }
+    }
+
+    /// Records the event at `ts_us` relative to the base timestamp `base_us`.
+    ///
+    /// Events before the base timestamp or after the last period are ignored.
+    #[inline]
+    pub fn update(&mut self, base_us: i64, ts_us: i64) {
+        if let Some(k) = period_index(base_us, ts_us, self.period_us, self.num_periods) {
+            self.periods.mark(k);
+        }
+    }
+
+    /// Combines `other` into `self`.
+    ///
+    /// Propagates the period and period count into a zero-initialized target.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        if self.period_us == 0 {
+            self.period_us = other.period_us;
+        }
+        if self.num_periods == 0 {
+            self.num_periods = other.num_periods;
+        }
+        self.periods = self.periods.combine(&other.periods);
+    }
+
+    /// Returns one element per period, with `retention` semantics: element
+    /// `k` is true if the group had an event in period 0 and one in period
+    /// `k`.
+    #[must_use]
+    pub fn finalize(&self) -> Vec<bool> {
+        let mut periods = self.periods.clone();
+        periods.num_conditions = self.num_periods;
+        periods.finalize()
+    }
+}
+
+/// Returns the index of the period of length `period_us` that `ts_us` falls
+/// in, counting from `base_us`.
+///
+/// `None` if `ts_us` is before `base_us` or at or after period
+/// `num_periods`, or if `period_us` is not positive. Computed in `i128`, so it is exact for timestamps far apart.
+#[must_use]
+#[inline]
+pub fn period_index(base_us: i64, ts_us: i64, period_us: i64, num_periods: usize) -> Option<usize> {
+    if period_us <= 0 {
+        return None;
+    }
+    let k = usize::try_from(cohort_index(base_us, ts_us, period_us).ok()?).ok()?;
+    (k < num_periods).then_some(k)
+}
+
+/// Returns the index of the period of length `period_us` that `ts_us` falls
+/// in, counting from `anchor_us`.
+///
+/// This is `floor((ts_us - anchor_us) / period_us)`, negative for timestamps
+/// before the anchor. `period_us` must be positive.
+///
+/// # Errors
+///
+/// Returns [`TimestampOverflow`] when the index does not fit in `i64`, which
+/// takes periods of a microsecond between timestamps far apart.
+#[inline]
+pub fn cohort_index(anchor_us: i64, ts_us: i64, period_us: i64) -> Result<i64, TimestampOverflow> {
+    debug_assert!(period_us > 0, "period must be positive");
+    let k = elapsed_wide(anchor_us, ts_us).div_euclid(i128::from(period_us));
+    i64::try_from(k).map_err(|_| TimestampOverflow)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,31 +592,107 @@ mod tests {
     }
 
     #[test]
-    fn test_conditions_beyond_32_silently_ignored() {
-        // Conditions beyond MAX_CONDITIONS (32) are silently ignored because
-        // the bitmask is u32. This test documents the behavior.
+    fn test_validity_counts_non_null_rows() {
+        let mut state = RetentionValidityState::new();
+        state.update(&[Some(true), None, Some(false)]);
+        state.update(&[Some(false), None, Some(true)]);
+        state.update(&[None, None]);
+        // NULLs read as false for the retention result
+        assert_eq!(state.finalize(), (vec![true, false, true], vec![2, 0, 2]));
+
+        let mut empty = RetentionValidityState::new();
+        assert_eq!(empty.finalize(), (vec![], vec![]));
+        empty.update(&[None, None]);
+        assert_eq!(empty.finalize(), (vec![false, false], vec![0, 0]));
+    }
+
+    #[test]
+    fn test_validity_combine_matches_single_state() {
+        let rows: [&[Option<bool>]; 4] = [
+            &[Some(true), Some(false)],
+            &[None, Some(true), None],
+            &[Some(false), None, Some(true)],
+            &[None],
+        ];
+        let mut single = RetentionValidityState::new();
+        for row in rows {
+            single.update(row);
+        }
+
+        let mut target = RetentionValidityState::new();
+        for row in rows.iter().rev() {
+            let mut part = RetentionValidityState::new();
+            part.update(row);
+            target.combine_in_place(&part);
+        }
+        assert_eq!(target.finalize(), single.finalize());
+        assert_eq!(single.finalize(), (vec![true, true, true], vec![2, 2, 1]));
+    }
+
+    #[test]
+    fn test_conditions_beyond_64_widen_state() {
+        // Conditions past the inline u64 (list input) go to wide_conditions_met.
         let mut state = RetentionState::new();
-        let mut conds = vec![false; 33];
+        let mut conds = vec![false; 200];
         conds[0] = true; // anchor
-        conds[32] = true; // beyond u32 capacity
+        conds[64] = true; // first wide bit
+        conds[199] = true; // word 2, bit 7
         state.update(&conds);
+        assert_eq!(state.wide_conditions_met, vec![1, 0, 1 << 7]);
         let result = state.finalize();
-        assert!(result[0]); // anchor is met
-                            // Condition 32 is silently ignored (bit 32 doesn't fit in u32)
-        assert!(!result[32]);
+        assert_eq!(result.len(), 200);
+        assert!(result[0]);
+        assert!(result[64]);
+        assert!(result[199]);
+        assert!(!result[65]);
+        assert!(!result[128]);
+    }
+
+    #[test]
+    fn test_rows_of_different_lengths() {
+        // List input: rows may have different lengths; the longest sets the
+        // result length and missing trailing conditions are false.
+        let mut state = RetentionState::new();
+        state.update(&[true, false, false, true]);
+        state.update(&[false, true]);
+        assert_eq!(state.num_conditions, 4);
+        assert_eq!(state.finalize(), vec![true, true, false, true]);
+    }
+
+    #[test]
+    fn test_combine_wide_states() {
+        let mut a = RetentionState::new();
+        let mut conds = vec![false; 70];
+        conds[0] = true;
+        conds[66] = true;
+        a.update(&conds);
+        let mut b = RetentionState::new();
+        let mut conds = vec![false; 140];
+        conds[130] = true;
+        b.update(&conds);
+
+        let ab = a.combine(&b);
+        assert_eq!(ab.num_conditions, 140);
+        assert_eq!(ab.wide_conditions_met, vec![1 << 2, 1 << 2]);
+        assert_eq!(ab.finalize(), b.combine(&a).finalize());
+        let result = ab.finalize();
+        assert!(result[66] && result[130]);
+        assert_eq!(result.iter().filter(|&&met| met).count(), 3);
     }
 
     #[test]
     fn test_conditions_at_max_boundary() {
-        // Condition 31 (the last one in u32) should work
+        // Condition 63 (the last one in u64) should work
         let mut state = RetentionState::new();
-        let mut conds = vec![false; 32];
+        let mut conds = vec![false; 64];
         conds[0] = true;
         conds[31] = true;
+        conds[63] = true;
         state.update(&conds);
         let result = state.finalize();
         assert!(result[0]);
         assert!(result[31]);
+        assert!(result[63]);
     }
 
     #[test]
@@ -368,32 +781,33 @@ mod tests {
     }
 
     #[test]
-    fn test_finalize_i_at_max_conditions_boundary() {
-        // Kills mutant: replace `i >= MAX_CONDITIONS` with `i > MAX_CONDITIONS`.
-        // Condition at exactly index 32 should be false (i >= 32 → true → false).
+    fn test_finalize_i_at_inline_conditions_boundary() {
+        // Kills mutant: replace `i < INLINE_CONDITIONS` with `i <= INLINE_CONDITIONS`.
+        // Condition 63 is the last inline bit, condition 64 the first wide bit.
         let mut state = RetentionState::new();
-        let mut conds = vec![false; 33];
+        let mut conds = vec![false; 66];
         conds[0] = true;
-        conds[31] = true; // last valid u32 bit
-        conds[32] = true; // at MAX_CONDITIONS boundary
+        conds[63] = true; // last inline bit
+        conds[65] = true; // wide word 0, bit 1
         state.update(&conds);
         let result = state.finalize();
-        assert!(result[31]); // within u32 capacity
-        assert!(!result[32]); // at boundary → false
+        assert!(result[63]);
+        assert!(!result[64]);
+        assert!(result[65]);
     }
 
     #[test]
-    fn test_update_respects_max_conditions_guard() {
-        // Kills mutant: remove `i < MAX_CONDITIONS` check in update.
-        // Setting bit 32 in a u32 would cause 1 << 32 = 1 (wraps around on some platforms).
+    fn test_update_respects_inline_conditions_guard() {
+        // Kills mutant: remove the INLINE_CONDITIONS split in update.
+        // Setting bit 64 in a u64 would cause 1 << 64 = 1 (wraps around on some platforms).
         let mut state = RetentionState::new();
-        let mut conds = vec![false; 33];
+        let mut conds = vec![false; 65];
         conds[0] = true;
-        conds[32] = true;
+        conds[64] = true;
         state.update(&conds);
-        // Bit 32 must NOT wrap around to set bit 0 again
-        // conditions_met should be 1 (only bit 0), not 1 | (1 << 32)
+        // Bit 64 must NOT wrap around to set bit 0 again; it is wide bit 0
         assert_eq!(state.conditions_met, 1);
+        assert_eq!(state.wide_conditions_met, vec![1]);
     }
 
     // --- Session 11: DuckDB zero-initialized target combine tests ---
@@ -436,6 +850,212 @@ mod tests {
         assert_eq!(combined.num_conditions, 5);
         assert_eq!(combined.conditions_met, 0b10001);
     }
+
+    // --- retention_counts ---
+
+    #[test]
+    fn test_counts_empty_state() {
+        assert!(RetentionCountsState::new().finalize().is_empty());
+    }
+
+    #[test]
+    fn test_counts_sum_per_row_retention() {
+        let rows: [&[bool]; 4] = [
+            &[true, true, false],
+            &[true, false, true],
+            &[false, true, true],
+            &[true, true, true],
+        ];
+        let mut state = RetentionCountsState::new();
+        let mut expected = vec![0; 3];
+        for row in rows {
+            state.update(row);
+            let mut single = RetentionState::new();
+            single.update(row);
+            for (e, v) in expected.iter_mut().zip(single.finalize()) {
+                *e += i64::from(v);
+            }
+        }
+        assert_eq!(state.finalize(), vec![3, 2, 2]);
+        assert_eq!(state.finalize(), expected);
+    }
+
+    #[test]
+    fn test_counts_rows_without_anchor_only_set_length() {
+        let mut state = RetentionCountsState::new();
+        state.update(&[false, true, true]);
+        assert_eq!(state.finalize(), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_rates_divide_by_anchor_count() {
+        let mut state = RetentionCountsState::new();
+        assert_eq!(state.finalize_rates(), None);
+        state.update(&[false, true, true]);
+        assert_eq!(state.finalize_rates(), None);
+        for row in [
+            [true, true, false],
+            [true, false, false],
+            [true, true, true],
+            [true, false, true],
+        ] {
+            state.update(&row);
+        }
+        assert_eq!(state.finalize_rates(), Some(vec![1.0, 0.5, 0.5]));
+    }
+
+    #[test]
+    fn test_counts_combine_zero_target() {
+        let mut source = RetentionCountsState::new();
+        source.update(&[true, false, true]);
+        source.update(&[true, true, true]);
+
+        let mut target = RetentionCountsState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.finalize(), vec![2, 1, 2]);
+        assert_eq!(source.combine(&source).finalize(), vec![4, 2, 4]);
+    }
+
+    #[test]
+    fn test_counts_combine_matches_single_state() {
+        let rows: Vec<Vec<bool>> = (0..20u32)
+            .map(|i| vec![i % 2 == 0, i % 3 == 0, i % 5 == 0])
+            .collect();
+        let mut whole = RetentionCountsState::new();
+        let mut left = RetentionCountsState::new();
+        let mut right = RetentionCountsState::new();
+        for (i, row) in rows.iter().enumerate() {
+            whole.update(row);
+            if i < 7 {
+                left.update(row);
+            } else {
+                right.update(row);
+            }
+        }
+        assert_eq!(left.combine(&right).finalize(), whole.finalize());
+        assert_eq!(right.combine(&left).finalize(), whole.finalize());
+        assert_eq!(whole.finalize(), vec![10, 4, 2]);
+    }
+
+    const DAY: i64 = 86_400_000_000;
+
+    fn by_period(num_periods: usize, events: &[(i64, i64)]) -> RetentionByPeriodState {
+        let mut state = RetentionByPeriodState::new();
+        state.period_us = DAY;
+        state.num_periods = num_periods;
+        for &(base, ts) in events {
+            state.update(base, ts);
+        }
+        state
+    }
+
+    #[test]
+    fn test_period_index_bounds() {
+        assert_eq!(period_index(0, 0, DAY, 3), Some(0));
+        assert_eq!(period_index(0, DAY - 1, DAY, 3), Some(0));
+        assert_eq!(period_index(0, DAY, DAY, 3), Some(1));
+        assert_eq!(period_index(0, 3 * DAY - 1, DAY, 3), Some(2));
+        assert_eq!(period_index(0, 3 * DAY, DAY, 3), None);
+        assert_eq!(period_index(0, -1, DAY, 3), None);
+        assert_eq!(period_index(0, 0, 0, 3), None);
+        assert_eq!(period_index(i64::MIN, i64::MAX, DAY, 3), None);
+        assert_eq!(period_index(i64::MIN, i64::MAX, i64::MAX, 3), Some(2));
+    }
+
+    #[test]
+    fn test_cohort_index_floors_before_anchor() {
+        assert_eq!(cohort_index(0, 0, DAY), Ok(0));
+        assert_eq!(cohort_index(0, DAY - 1, DAY), Ok(0));
+        assert_eq!(cohort_index(0, 3 * DAY, DAY), Ok(3));
+        assert_eq!(cohort_index(0, -1, DAY), Ok(-1));
+        assert_eq!(cohort_index(0, -DAY, DAY), Ok(-1));
+        assert_eq!(cohort_index(0, -DAY - 1, DAY), Ok(-2));
+        assert_eq!(cohort_index(i64::MAX, i64::MIN, i64::MAX), Ok(-3));
+        assert_eq!(cohort_index(i64::MIN, i64::MAX, 1), Err(TimestampOverflow));
+    }
+
+    #[test]
+    fn test_by_period_matches_retention() {
+        // Active on days 0, 2 and 5 of a 4-day window.
+        let state = by_period(4, &[(0, 2 * DAY + 5), (0, 10), (0, 5 * DAY)]);
+        assert_eq!(state.finalize(), vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn test_by_period_requires_period_zero() {
+        let state = by_period(3, &[(0, DAY), (0, 2 * DAY)]);
+        assert_eq!(state.finalize(), vec![false, false, false]);
+    }
+
+    #[test]
+    fn test_by_period_wide() {
+        let state = by_period(100, &[(0, 0), (0, 99 * DAY)]);
+        let result = state.finalize();
+        assert_eq!(result.len(), 100);
+        assert!(result[0] && result[99]);
+        assert_eq!(result.iter().filter(|&&met| met).count(), 2);
+    }
+
+    #[test]
+    fn test_by_period_combine_zero_target() {
+        let source = by_period(3, &[(0, 0), (0, 2 * DAY)]);
+        let mut target = RetentionByPeriodState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.period_us, DAY);
+        assert_eq!(target.num_periods, 3);
+        assert_eq!(target.finalize(), vec![true, false, true]);
+    }
+
+    // --- Exported states ---
+
+    #[test]
+    fn test_blob_round_trip() {
+        let empty = RetentionState::new();
+        let restored = RetentionState::from_blob(&empty.to_blob()).unwrap();
+        assert_eq!(restored.num_conditions, 0);
+        assert!(restored.finalize().is_empty());
+
+        let mut conds = vec![false; 130];
+        conds[0] = true;
+        conds[70] = true;
+        let mut state = RetentionState::new();
+        state.update(&conds);
+        let restored = RetentionState::from_blob(&state.to_blob()).unwrap();
+        assert_eq!(restored.finalize(), state.finalize());
+        assert_eq!(restored.num_conditions, 130);
+    }
+
+    #[test]
+    fn test_blob_merge_matches_combine() {
+        let mut a = RetentionState::new();
+        a.update(&[true, false, false]);
+        let mut b = RetentionState::new();
+        b.update(&[false, false, true]);
+        let a = RetentionState::from_blob(&a.to_blob()).unwrap();
+        let b = RetentionState::from_blob(&b.to_blob()).unwrap();
+        assert_eq!(a.combine(&b).finalize(), vec![true, false, true]);
+    }
+
+    #[test]
+    fn test_blob_rejects_malformed_states() {
+        let mut state = RetentionState::new();
+        state.update(&[true; 100]);
+        let bytes = state.to_blob();
+        assert_eq!(
+            RetentionState::from_blob(&bytes[..bytes.len() - 1]).unwrap_err(),
+            BlobError::Truncated
+        );
+        assert_eq!(
+            RetentionState::from_blob(b"WFS\x01").unwrap_err(),
+            BlobError::Tag
+        );
+
+        // A huge condition count without the words to back it
+        let mut huge = RetentionState::BLOB_TAG.to_vec();
+        huge.extend_from_slice(&u64::MAX.to_le_bytes());
+        huge.extend_from_slice(&1u64.to_le_bytes());
+        assert!(RetentionState::from_blob(&huge).is_err());
+    }
 }
 
 #[cfg(test)]
@@ -555,15 +1175,15 @@ mod proptests {
         }
 
         #[test]
-        fn condition_31_preserved_through_combine(
+        fn condition_63_preserved_through_combine(
             val_a in prop::bool::ANY,
             val_b in prop::bool::ANY,
         ) {
-            // Test that bit 31 (the highest valid condition) survives combine
-            let mut conds_a = vec![true; 32]; // anchor=true so bit 31 is visible
-            conds_a[31] = val_a;
-            let mut conds_b = vec![true; 32];
-            conds_b[31] = val_b;
+            // Test that bit 63 (the highest valid condition) survives combine
+            let mut conds_a = vec![true; 64]; // anchor=true so bit 63 is visible
+            conds_a[63] = val_a;
+            let mut conds_b = vec![true; 64];
+            conds_b[63] = val_b;
 
             let mut a = RetentionState::new();
             a.update(&conds_a);
@@ -572,8 +1192,8 @@ mod proptests {
 
             let combined = a.combine(&b);
             let result = combined.finalize();
-            // Bit 31 should be true if either a or b had it true (OR semantics)
-            prop_assert_eq!(result[31], val_a || val_b);
+            // Bit 63 should be true if either a or b had it true (OR semantics)
+            prop_assert_eq!(result[63], val_a || val_b);
         }
     }
 }
@@ -0,0 +1,339 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `behavioral_selfcheck` — Internal consistency suite for the loaded binary.
+//!
+//! Runs three small suites against the compiled extension and reports
+//! pass/fail plus wall-clock time for each, so a deployment can verify the
+//! binary on a new `DuckDB` release before trusting query results.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT behavioral_selfcheck();
+//! -- {'passed': true, 'parser_passed': true, 'parser_us': 41, ...}
+//! ```
+//!
+//! # Suites
+//!
+//! - **Parser**: every pattern in a fixed corpus is parsed, rendered back to
+//!   text and re-parsed to the same steps; malformed patterns are rejected.
+//! - **Funnel**: `window_funnel` over generated event streams stays within
+//!   `0..=N`, is independent of insertion order and partitioning, and never
+//!   decreases when the window grows.
+//! - **Combine**: `window_funnel`, `sequence_count`, `sessionize` and
+//!   `retention` states built from three generated segments give the same
+//!   result for `(a + b) + c`, `a + (b + c)` and a single state.
+//!
+//! Data is generated from fixed seeds, so every run checks the same inputs.
+//! The suites take well under a millisecond on current hardware.
+
+use crate::common::event::Event;
+use crate::common::stopwatch::Stopwatch;
+use crate::pattern::parser::{parse_pattern, PatternStep, TimeOp};
+use crate::retention::RetentionState;
+use crate::sequence::SequenceState;
+use crate::sessionize::SessionizeBoundaryState;
+use crate::window_funnel::WindowFunnelState;
+
+/// Patterns that must survive a parse → render → parse round-trip.
+const ROUND_TRIP_PATTERNS: [&str; 11] = [
+    "(?1)",
+    "(?1)(?2)",
+    "(?1).*(?2).*(?3)",
+    "(?1).(?2)",
+    "(?1)(?t>=3600)(?2)",
+    "(?1)(?t<=1500ms)(?2)(?t!=2h)(?3)",
+    ".*(?12).*",
+    "(?3)(?t<5)(?1)(?t==0us)(?2)",
+    "(?1)(?!3)*(?2)",
+    "(?2)(?!1).(?!4)(?3)",
+    "(?1).*(?T<=3600)(?2)(?T>90m)(?3)",
+];
+
+/// Patterns that must be rejected.
+const INVALID_PATTERNS: [&str; 6] = ["", "(?0)", "(?1", "(?1)x(?2)", "(?t>=)", "(?1)(?t<=2d)"];
+
+/// Number of conditions in generated events.
+const NUM_CONDITIONS: usize = 3;
+
+/// `window_funnel` window for generated streams (10 minutes).
+const WINDOW_US: i64 = 600_000_000;
+
+/// Number of generated event streams per suite.
+const STREAMS: u64 = 16;
+
+/// Outcome of one suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuiteResult {
+    /// True if every check in the suite held.
+    pub passed: bool,
+    /// Wall-clock time spent in the suite, in microseconds.
+    pub elapsed_us: i64,
+}
+
+/// Outcome of all suites, returned by [`run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfCheckReport {
+    /// Pattern parser round-trips.
+    pub parser: SuiteResult,
+    /// `window_funnel` invariants.
+    pub funnel: SuiteResult,
+    /// Combine associativity across state types.
+    pub combine: SuiteResult,
+}
+
+impl SelfCheckReport {
+    /// Returns true if every suite passed.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        self.parser.passed && self.funnel.passed && self.combine.passed
+    }
+
+    /// Returns the total time spent in all suites, in microseconds.
+    #[must_use]
+    pub const fn total_us(&self) -> i64 {
+        self.parser.elapsed_us + self.funnel.elapsed_us + self.combine.elapsed_us
+    }
+}
+
+/// Runs all suites.
+#[must_use]
+pub fn run() -> SelfCheckReport {
+    SelfCheckReport {
+        parser: timed(check_parser),
+        funnel: timed(check_funnel),
+        combine: timed(check_combine),
+    }
+}
+
+fn timed(suite: fn() -> bool) -> SuiteResult {
+    let stopwatch = Stopwatch::start();
+    let passed = suite();
+    SuiteResult {
+        passed,
+        elapsed_us: i64::try_from(stopwatch.elapsed_us()).unwrap_or(i64::MAX),
+    }
+}
+
+/// Renders compiled steps back to pattern text.
+fn render(steps: &[PatternStep]) -> String {
+    let op = |op: TimeOp| match op {
+        TimeOp::Gte => ">=",
+        TimeOp::Lte => "<=",
+        TimeOp::Gt => ">",
+        TimeOp::Lt => "<",
+        TimeOp::Eq => "==",
+        TimeOp::Ne => "!=",
+    };
+    steps
+        .iter()
+        .map(|step| match step {
+            PatternStep::Condition(idx) => format!("(?{})", idx + 1),
+            PatternStep::AnyEvents => ".*".to_string(),
+            PatternStep::OneEvent => ".".to_string(),
+            PatternStep::NotCondition(idx) => format!("(?!{})", idx + 1),
+            PatternStep::AnyNotCondition(idx) => format!("(?!{})*", idx + 1),
+            PatternStep::TimeConstraint(o, secs) => format!("(?t{}{secs})", op(*o)),
+            PatternStep::TimeConstraintMicros(o, us) => format!("(?t{}{us}us)", op(*o)),
+            PatternStep::StartTimeConstraint(o, secs) => format!("(?T{}{secs})", op(*o)),
+            PatternStep::StartTimeConstraintMicros(o, us) => format!("(?T{}{us}us)", op(*o)),
+        })
+        .collect()
+}
+
+fn check_parser() -> bool {
+    let round_trips = ROUND_TRIP_PATTERNS.iter().all(|text| {
+        parse_pattern(text).is_ok_and(|pattern| {
+            parse_pattern(&render(&pattern.steps)).is_ok_and(|again| again.steps == pattern.steps)
+        })
+    });
+    round_trips
+        && INVALID_PATTERNS
+            .iter()
+            .all(|text| parse_pattern(text).is_err())
+}
+
+/// Deterministic xorshift generator; no external RNG dependency.
+struct Rng(u64);
+
+impl Rng {
+    const fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Generates a stream of 64 events with strictly increasing timestamps over
+/// roughly two hours and random condition masks (including all-false events).
+/// Distinct timestamps keep results independent of tie order.
+fn generate(seed: u64) -> Vec<Event> {
+    let mut rng = Rng::new(seed);
+    let mut ts = 0;
+    (0..64)
+        .map(|_| {
+            ts += 1 + (rng.next() % 240_000_000) as i64;
+            // Each event satisfies at most one condition, each with
+            // probability 1/6, so funnels of every length occur.
+            let bit = rng.next() % 6;
+            let mask = if bit < NUM_CONDITIONS as u64 {
+                1 << bit
+            } else {
+                0
+            };
+            Event::new(ts, mask)
+        })
+        .collect()
+}
+
+fn funnel(events: &[Event], window_us: i64) -> WindowFunnelState {
+    let mut state = WindowFunnelState::new();
+    state.window_size_us = window_us;
+    for &event in events {
+        state.update(event, NUM_CONDITIONS);
+    }
+    state
+}
+
+fn check_funnel() -> bool {
+    (0..STREAMS).all(|seed| {
+        let events = generate(seed);
+        let result = funnel(&events, WINDOW_US).finalize();
+
+        let reversed: Vec<Event> = events.iter().rev().copied().collect();
+        let (left, right) = events.split_at(events.len() / 3);
+        let mut split = funnel(right, WINDOW_US);
+        split.combine_in_place(&funnel(left, WINDOW_US));
+
+        (0..=NUM_CONDITIONS as i64).contains(&result)
+            && funnel(&reversed, WINDOW_US).finalize() == result
+            && split.finalize() == result
+            && funnel(&events, WINDOW_US * 2).finalize() >= result
+    })
+}
+
+fn check_combine() -> bool {
+    (0..STREAMS).all(|seed| {
+        let events = generate(seed);
+        let third = events.len() / 3;
+        let (a, rest) = events.split_at(third);
+        let (b, c) = rest.split_at(third);
+        funnel_associative(a, b, c)
+            && sequence_associative(a, b, c)
+            && sessionize_associative(a, b, c)
+            && retention_associative(a, b, c)
+    })
+}
+
+fn funnel_associative(a: &[Event], b: &[Event], c: &[Event]) -> bool {
+    let (sa, sb, sc) = (
+        funnel(a, WINDOW_US),
+        funnel(b, WINDOW_US),
+        funnel(c, WINDOW_US),
+    );
+    let mut left = sa.combine(&sb).combine(&sc);
+    let mut right = sa.combine(&sb.combine(&sc));
+    let whole = funnel(&[a, b, c].concat(), WINDOW_US).finalize();
+    left.finalize() == whole && right.finalize() == whole
+}
+
+fn sequence(events: &[Event]) -> SequenceState {
+    let mut state = SequenceState::new();
+    state.set_pattern("(?1).*(?2)(?t<=600)(?3)");
+    for &event in events {
+        state.update(event);
+    }
+    state
+}
+
+fn sequence_associative(a: &[Event], b: &[Event], c: &[Event]) -> bool {
+    let (sa, sb, sc) = (sequence(a), sequence(b), sequence(c));
+    let left = sa.combine(&sb).combine(&sc).finalize_count();
+    let right = sa.combine(&sb.combine(&sc)).finalize_count();
+    let whole = sequence(&[a, b, c].concat()).finalize_count();
+    whole.is_ok() && left == whole && right == whole
+}
+
+fn sessionize(events: &[Event]) -> SessionizeBoundaryState {
+    let mut state = SessionizeBoundaryState::new();
+    state.threshold_us = 300_000_000;
+    for event in events {
+        state.update(event.timestamp_us);
+    }
+    state
+}
+
+fn sessionize_associative(a: &[Event], b: &[Event], c: &[Event]) -> bool {
+    let (sa, sb, sc) = (sessionize(a), sessionize(b), sessionize(c));
+    let whole = sessionize(&[a, b, c].concat()).finalize();
+    sa.combine(&sb).combine(&sc).finalize() == whole
+        && sa.combine(&sb.combine(&sc)).finalize() == whole
+}
+
+fn retention(events: &[Event]) -> RetentionState {
+    let mut state = RetentionState::new();
+    for event in events {
+        let conditions: Vec<bool> = (0..NUM_CONDITIONS).map(|i| event.condition(i)).collect();
+        state.update(&conditions);
+    }
+    state
+}
+
+fn retention_associative(a: &[Event], b: &[Event], c: &[Event]) -> bool {
+    let (ra, rb, rc) = (retention(a), retention(b), retention(c));
+    let whole = retention(&[a, b, c].concat()).finalize();
+    ra.combine(&rb).combine(&rc).finalize() == whole
+        && ra.combine(&rb.combine(&rc)).finalize() == whole
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selfcheck_passes() {
+        let report = run();
+        assert!(report.parser.passed);
+        assert!(report.funnel.passed);
+        assert!(report.combine.passed);
+        assert!(report.passed());
+        assert!(report.total_us() >= 0);
+    }
+
+    #[test]
+    fn test_render_round_trips_every_step_kind() {
+        let pattern = parse_pattern("(?2).*.(?t>=5)(?t<100ms)(?1)(?!3)(?!1)*").unwrap();
+        assert_eq!(
+            render(&pattern.steps),
+            "(?2).*.(?t>=5)(?t<100000us)(?1)(?!3)(?!1)*"
+        );
+    }
+
+    #[test]
+    fn test_generate_is_deterministic_and_sorted() {
+        let events = generate(7);
+        assert_eq!(events, generate(7));
+        assert!(events
+            .windows(2)
+            .all(|w| w[0].timestamp_us < w[1].timestamp_us));
+        assert!(events.iter().any(Event::has_any_condition));
+    }
+
+    #[test]
+    fn test_combine_suite_detects_broken_state() {
+        // A funnel that only sees one partition cannot equal the whole stream
+        // for every seed, so the comparison in the suite is meaningful.
+        let differs = (0..STREAMS).any(|seed| {
+            let events = generate(seed);
+            let (a, _) = events.split_at(events.len() / 3);
+            funnel(a, WINDOW_US).finalize() != funnel(&events, WINDOW_US).finalize()
+        });
+        assert!(differs);
+    }
+}
@@ -27,10 +27,180 @@
 //! FROM events
 //! GROUP BY user_id
 //! ```
+//!
+//! # Run-length encoding
+//!
+//! [`SequenceState::enable_run_length`] switches a state to a
+//! [`RunLengthBuffer`], which stores runs of consecutive events with the same
+//! condition mask as `(mask, first_ts, last_ts, count)`. For telemetry-style
+//! data with long runs of identical events this cuts state memory by one to
+//! two orders of magnitude. Match results are unchanged for patterns without
+//! time constraints when input arrives in timestamp order; time constraints
+//! only see the first and last timestamp of each run.
+//!
+//! # Sequence numbers
+//!
+//! `sequence_match` and `sequence_count` accept an optional `BIGINT`
+//! sequence-number column after the timestamp that orders events sharing a
+//! timestamp. The numbers are kept in [`SequenceState::seqs`], parallel to the
+//! events, and only used for sorting.
+//!
+//! # Match windows
+//!
+//! `sequence_count_within(window, pattern, ts, ...)` counts only matches whose
+//! first and last matched events lie within `window` of each other. The
+//! window is kept in [`SequenceState::window_us`] and enforced by the NFA
+//! executor (see [`execute_pattern_within`]), not by filtering the matches
+//! `sequence_count` finds: a match that is too long gives way to a shorter
+//! one starting later.
+//!
+//! # Input order
+//!
+//! Events are sorted by timestamp in finalize, after an O(n) scan that skips
+//! the sort when they already arrive in order. `sequence_match(pattern,
+//! order, ts, ...)` sets [`SequenceState::order`]:
+//! [`InputOrder::AssumeSorted`] trusts the caller and skips both the scan
+//! and the sort, and [`InputOrder::VerifySorted`] fails the query on
+//! out-of-order events (see [`SequenceState::verify_order`]) instead of
+//! sorting them.
+//!
+//! Before any sorting, finalize ORs the condition masks of the group's
+//! events: if a condition of a `(?N)` step never occurs, the pattern cannot
+//! match and the group skips both the sort and the executor. Sparse funnels,
+//! where most groups never reach the later steps, mostly end here.
+//!
+//! # Time buckets
+//!
+//! `sequence_count_by(bucket, pattern, ts, ...)` splits the matches
+//! `sequence_count` counts by the time bucket of their first matched event,
+//! in one pass over the events ([`SequenceCountByState`]). Buckets are
+//! aligned to the Unix epoch, like `time_bucket` with a `1970-01-01` origin.
+
+use crate::common::event::{
+    sort_events, sort_events_by_seq, CompressedEventBuffer, Event, COMPRESSION_THRESHOLD,
+};
+use crate::common::event_buffer::EventBuffer;
+use crate::common::event_run::RunLengthBuffer;
+use crate::common::stopwatch::Stopwatch;
+use crate::common::timestamp::{elapsed_micros, TimestampOverflow};
+use crate::pattern::cache;
+use crate::pattern::executor::{
+    execute_pattern_event_indices, execute_pattern_events, execute_pattern_runs,
+    execute_pattern_spans, execute_pattern_within, execution_path, run_expansion_cap,
+    ExecutionPath, MatchResult,
+};
+use crate::pattern::parser::{CompiledPattern, PatternError};
+use std::sync::Arc;
+
+/// Returns the compiled form of `pattern_str`, filling `slot` from the
+/// process-wide [`cache`] on first use.
+fn compiled(
+    slot: &mut Option<Arc<CompiledPattern>>,
+    pattern_str: Option<&str>,
+) -> Result<Arc<CompiledPattern>, PatternError> {
+    if let Some(pattern) = slot {
+        return Ok(Arc::clone(pattern));
+    }
+    let pattern = cache::compile(pattern_str.unwrap_or(""))?;
+    *slot = Some(Arc::clone(&pattern));
+    Ok(pattern)
+}
+
+/// How finalize treats the order of the collected events, set by the `order`
+/// argument of `sequence_match`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputOrder {
+    /// Sort the events, skipping the sort when a scan finds them in order.
+    #[default]
+    Sort,
+    /// Trust that the events arrive in timestamp order: no scan, no sort.
+    /// Out-of-order events give unspecified (but memory-safe) results.
+    AssumeSorted,
+    /// Check the order and fail instead of sorting out-of-order events.
+    VerifySorted,
+}
 
-use crate::common::event::{sort_events, Event};
-use crate::pattern::executor::{execute_pattern, execute_pattern_events, MatchResult};
-use crate::pattern::parser::{parse_pattern, CompiledPattern, PatternError};
+impl InputOrder {
+    /// The accepted `order` strings, in enum order.
+    pub const NAMES: [&'static str; 3] = ["sort", "assume_sorted", "verify_sorted"];
+
+    /// Parses an `order` string, ignoring case and surrounding whitespace.
+    ///
+    /// Returns `None` for unrecognized strings.
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("sort") => Some(Self::Sort),
+            s if s.eq_ignore_ascii_case("assume_sorted") => Some(Self::AssumeSorted),
+            s if s.eq_ignore_ascii_case("verify_sorted") => Some(Self::VerifySorted),
+            _ => None,
+        }
+    }
+}
+
+/// Error of [`SequenceState::verify_order`]: an event at `timestamp_us`
+/// arrived after one at `previous_us`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsortedEvents {
+    /// Timestamp of the earlier-arriving event.
+    pub previous_us: i64,
+    /// Timestamp of the event that arrived after it.
+    pub timestamp_us: i64,
+}
+
+impl std::fmt::Display for UnsortedEvents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "events are not in timestamp order with 'verify_sorted': an event at {} microseconds arrived after one at {}",
+            self.timestamp_us, self.previous_us
+        )
+    }
+}
+
+impl std::error::Error for UnsortedEvents {}
+
+/// Error of [`SequenceState::finalize_duration`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DurationError {
+    /// The pattern string is invalid.
+    Pattern(PatternError),
+    /// The matched steps are too far apart for `i64` microseconds, such as
+    /// `'-infinity'` and `'infinity'`.
+    Overflow(TimestampOverflow),
+}
+
+impl From<PatternError> for DurationError {
+    fn from(error: PatternError) -> Self {
+        Self::Pattern(error)
+    }
+}
+
+impl From<TimestampOverflow> for DurationError {
+    fn from(error: TimestampOverflow) -> Self {
+        Self::Overflow(error)
+    }
+}
+
+/// Statistics of a `sequence_match` finalize returned by
+/// [`SequenceState::finalize_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceStats {
+    /// Whether the pattern matched, as [`SequenceState::finalize_match`]
+    /// returns.
+    pub matched: bool,
+    /// Events kept for matching: those with at least one condition set.
+    pub events_stored: usize,
+    /// Executor path the pattern took.
+    pub path: ExecutionPath,
+    /// NFA states expanded ([`MatchResult::states_expanded`]); 0 on the fast
+    /// paths.
+    pub states_expanded: usize,
+    /// Wall-clock microseconds spent decompressing and sorting the events.
+    pub sort_us: u64,
+    /// Wall-clock microseconds spent compiling and executing the pattern.
+    pub match_us: u64,
+}
 
 /// State for `sequence_match` and `sequence_count` aggregate functions.
 ///
@@ -40,11 +210,25 @@ use crate::pattern::parser::{parse_pattern, CompiledPattern, PatternError};
 #[non_exhaustive]
 pub struct SequenceState {
     /// Collected events (timestamp + conditions). Sorted in finalize.
-    pub events: Vec<Event>,
+    pub events: EventBuffer,
+    /// Events compressed once `events` reached [`COMPRESSION_THRESHOLD`];
+    /// they precede `events` and are moved back into it in finalize.
+    pub compressed: CompressedEventBuffer,
+    /// Run-length encoded events. When set, new events are collected here
+    /// instead of `events`.
+    pub runs: Option<RunLengthBuffer>,
     /// Pattern string (parsed on first use in finalize).
     pub pattern_str: Option<String>,
-    /// Cached compiled pattern (populated during finalize).
-    compiled_pattern: Option<CompiledPattern>,
+    /// Compiled pattern shared through [`cache`] (populated during finalize).
+    compiled_pattern: Option<Arc<CompiledPattern>>,
+    /// Sequence number of each entry in `events` (same index) when a
+    /// sequence-number column is given; empty otherwise.
+    pub seqs: Vec<i64>,
+    /// Maximum time in microseconds from the first to the last matched event
+    /// of a match (`sequence_count_within`); `None` places no bound.
+    pub window_us: Option<i64>,
+    /// How finalize treats the event order; `None` is [`InputOrder::Sort`].
+    pub order: Option<InputOrder>,
 }
 
 impl SequenceState {
@@ -52,12 +236,35 @@ impl SequenceState {
     #[must_use]
     pub const fn new() -> Self {
         Self {
-            events: Vec::new(),
+            events: EventBuffer::new(),
+            compressed: CompressedEventBuffer::new(),
+            runs: None,
             pattern_str: None,
             compiled_pattern: None,
+            seqs: Vec::new(),
+            window_us: None,
+            order: None,
         }
     }
 
+    /// Switches the state to run-length encoded event storage.
+    ///
+    /// Events collected so far are moved into the run buffer, in sequence
+    /// order when sequence numbers are set; runs do not keep them. Idempotent.
+    pub fn enable_run_length(&mut self) {
+        if self.runs.is_some() {
+            return;
+        }
+        self.sort_events();
+        self.seqs = Vec::new();
+        let mut runs = RunLengthBuffer::new();
+        for event in self.events.iter() {
+            runs.push(*event);
+        }
+        self.events = EventBuffer::new();
+        self.runs = Some(runs);
+    }
+
     /// Sets the pattern string (called once during the first update).
     pub fn set_pattern(&mut self, pattern: &str) {
         if self.pattern_str.is_none() {
@@ -71,8 +278,82 @@ impl SequenceState {
     /// as events with all-false conditions cannot match any `(?N)` step.
     pub fn update(&mut self, event: Event) {
         if event.has_any_condition() {
-            self.events.push(event);
+            if let Some(runs) = &mut self.runs {
+                runs.push(event);
+            } else {
+                self.events.push(event);
+                self.compress_if_full();
+            }
+        }
+    }
+
+    /// Adds an event with its sequence number, which orders it among events
+    /// with the same timestamp. With run-length encoding the number is
+    /// dropped.
+    ///
+    /// A state takes either only sequenced or only plain events, so that
+    /// `seqs` stays parallel to `events`.
+    pub fn update_with_seq(&mut self, event: Event, seq: i64) {
+        if event.has_any_condition() {
+            if let Some(runs) = &mut self.runs {
+                runs.push(event);
+            } else {
+                self.events.push(event);
+                self.seqs.push(seq);
+                self.compress_if_full();
+            }
+        }
+    }
+
+    /// Adds a batch of events with their sequence numbers, as
+    /// [`update_with_seq`](Self::update_with_seq) per event.
+    pub fn update_batch_with_seq(&mut self, events: impl IntoIterator<Item = (Event, i64)>) {
+        let events = events
+            .into_iter()
+            .filter(|(event, _)| event.has_any_condition());
+        match &mut self.runs {
+            Some(runs) => events.for_each(|(event, _)| runs.push(event)),
+            None => events.for_each(|(event, seq)| {
+                self.events.push(event);
+                self.seqs.push(seq);
+                self.compress_if_full();
+            }),
+        }
+    }
+
+    /// Adds a batch of events, e.g. the rows of one data chunk that map to
+    /// this state. Equivalent to calling [`update`](Self::update) per event,
+    /// with the storage choice made once for the whole batch.
+    pub fn update_batch(&mut self, events: impl IntoIterator<Item = Event>) {
+        let events = events.into_iter().filter(Event::has_any_condition);
+        match &mut self.runs {
+            Some(runs) => events.for_each(|event| runs.push(event)),
+            None => events.for_each(|event| {
+                self.events.push(event);
+                self.compress_if_full();
+            }),
+        }
+    }
+
+    /// Moves the buffered events into [`compressed`](Self::compressed) once
+    /// there are [`COMPRESSION_THRESHOLD`] of them.
+    fn compress_if_full(&mut self) {
+        if self.events.len() >= COMPRESSION_THRESHOLD {
+            self.compressed.push_block(&self.events);
+            self.events = EventBuffer::new();
+        }
+    }
+
+    /// Moves the compressed events back in front of the buffered ones.
+    fn decompress(&mut self) {
+        if self.compressed.is_empty() {
+            return;
         }
+        let mut events = Vec::with_capacity(self.compressed.len() + self.events.len());
+        self.compressed.decompress_into(&mut events);
+        events.extend_from_slice(&self.events);
+        self.events = EventBuffer::from(events);
+        self.compressed = CompressedEventBuffer::new();
     }
 
     /// Combines two states by concatenating their event lists, returning a new state.
@@ -81,52 +362,207 @@ impl SequenceState {
     /// `execute()` sorts them before pattern matching.
     #[must_use]
     pub fn combine(&self, other: &Self) -> Self {
-        let mut events = Vec::with_capacity(self.events.len() + other.events.len());
+        if self.runs.is_some()
+            || other.runs.is_some()
+            || !self.compressed.is_empty()
+            || !other.compressed.is_empty()
+        {
+            let mut combined = self.clone();
+            combined.combine_in_place(other);
+            return combined;
+        }
+        let mut events = EventBuffer::with_capacity(self.events.len() + other.events.len());
         events.extend_from_slice(&self.events);
         events.extend_from_slice(&other.events);
+        let mut seqs = Vec::with_capacity(self.seqs.len() + other.seqs.len());
+        seqs.extend_from_slice(&self.seqs);
+        seqs.extend_from_slice(&other.seqs);
         Self {
             events,
+            compressed: CompressedEventBuffer::new(),
+            runs: None,
             pattern_str: self
                 .pattern_str
                 .clone()
                 .or_else(|| other.pattern_str.clone()),
-            compiled_pattern: None, // Will be recompiled in finalize
+            compiled_pattern: None, // Fetched from the pattern cache in finalize
+            seqs,
+            window_us: self.window_us.or(other.window_us),
+            order: self.order.or(other.order),
         }
     }
 
     /// Combines another state into `self` in-place by appending its events.
     ///
     /// This is the preferred combine method for sequential (left-fold) chains.
-    /// By extending `self.events` in-place, Vec's doubling growth strategy
+    /// By extending `self.events` in-place, the buffer's doubling growth strategy
     /// provides O(N) amortized total copies for a chain of N single-event
     /// combines, compared to O(N²) when allocating a new Vec per combine.
     ///
     /// The compiled pattern is preserved when `self` already has one, avoiding
     /// redundant recompilation in finalize. The pattern string is invariant
     /// within a single query, so `self.compiled_pattern` remains valid.
+    ///
+    /// Run-length encoding propagates: if `other` uses runs, `self` is
+    /// switched to runs as well (e.g. a zero-initialized combine target).
+    /// Compressed events of `other` are appended without decompressing; the
+    /// buffered events of `self` are compressed first to keep input order.
     pub fn combine_in_place(&mut self, other: &Self) {
-        self.events.extend_from_slice(&other.events);
+        if other.runs.is_some() {
+            self.enable_run_length();
+        }
+        if let Some(runs) = &mut self.runs {
+            if let Some(other_runs) = &other.runs {
+                runs.extend_from_runs(other_runs.runs());
+            }
+            let mut decompressed = Vec::new();
+            other.compressed.decompress_into(&mut decompressed);
+            for event in decompressed.iter().chain(other.events.iter()) {
+                runs.push(*event);
+            }
+        } else {
+            if !other.compressed.is_empty() {
+                self.compressed.push_block(&self.events);
+                self.events = EventBuffer::new();
+                self.compressed.extend_from(&other.compressed);
+            }
+            self.events.extend_from_slice(&other.events);
+            self.seqs.extend_from_slice(&other.seqs);
+            self.compress_if_full();
+        }
         if self.pattern_str.is_none() {
             self.pattern_str.clone_from(&other.pattern_str);
             // Pattern string changed, invalidate cached compilation
             self.compiled_pattern = None;
         }
+        if self.window_us.is_none() {
+            self.window_us = other.window_us;
+        }
+        if self.order.is_none() {
+            self.order = other.order;
+        }
+    }
+
+    /// Sorts events (or runs) by timestamp, ordering ties by sequence number
+    /// when `seqs` is set. Decompresses compressed events first. Nothing is
+    /// sorted with [`InputOrder::AssumeSorted`].
+    fn sort_events(&mut self) {
+        self.decompress();
+        if self.order == Some(InputOrder::AssumeSorted) {
+            return;
+        }
+        if let Some(runs) = &mut self.runs {
+            runs.sort();
+        } else if self.seqs.is_empty() {
+            sort_events(&mut self.events);
+        } else {
+            sort_events_by_seq(&mut self.events, &mut self.seqs);
+        }
+    }
+
+    /// Checks the event order for [`InputOrder::VerifySorted`]; with any
+    /// other order this always succeeds. Called before finalize, whose sort
+    /// then finds the events in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnsortedEvents`] for the first event, in arrival order, that
+    /// is earlier than the one before it (or, with sequence numbers, equally
+    /// timed with a lower number).
+    pub fn verify_order(&mut self) -> Result<(), UnsortedEvents> {
+        if self.order != Some(InputOrder::VerifySorted) {
+            return Ok(());
+        }
+        self.decompress();
+        let first_unsorted = if let Some(runs) = &self.runs {
+            runs.runs()
+                .windows(2)
+                .find(|w| w[1].first_ts < w[0].last_ts)
+                .map(|w| (w[0].last_ts, w[1].first_ts))
+        } else {
+            let key = |i: usize| (self.events[i].timestamp_us, self.seqs.get(i).copied());
+            (1..self.events.len())
+                .find(|&i| key(i) < key(i - 1))
+                .map(|i| (self.events[i - 1].timestamp_us, self.events[i].timestamp_us))
+        };
+        first_unsorted.map_or(Ok(()), |(previous_us, timestamp_us)| {
+            Err(UnsortedEvents {
+                previous_us,
+                timestamp_us,
+            })
+        })
+    }
+
+    /// Returns the OR of the condition masks of the collected events.
+    fn conditions_present(&self) -> u64 {
+        let buffered = self
+            .events
+            .iter()
+            .fold(self.compressed.union_conditions(), |mask, event| {
+                mask | event.conditions
+            });
+        self.runs.as_ref().map_or(buffered, |runs| {
+            runs.runs()
+                .iter()
+                .fold(buffered, |mask, run| mask | run.conditions)
+        })
+    }
+
+    /// Returns true if a `(?N)` step of `pattern` refers to a condition that
+    /// holds for none of the collected events, so that no match exists.
+    ///
+    /// This is an O(n) scan of the condition masks, checked before the sort:
+    /// in sparse funnels most groups never reach the later steps and skip
+    /// sorting and matching entirely.
+    fn cannot_match(&self, pattern: &CompiledPattern) -> bool {
+        pattern.required_conditions() & !self.conditions_present() != 0
     }
 
     /// Compiles the pattern and executes it against the sorted event stream.
     fn execute(&mut self, count_all: bool) -> Result<MatchResult, PatternError> {
-        sort_events(&mut self.events);
+        let pattern = compiled(&mut self.compiled_pattern, self.pattern_str.as_deref())?;
+        Ok(self.execute_compiled(&pattern, count_all))
+    }
+
+    /// Executes `pattern` against the sorted event stream. Sorting is skipped
+    /// when the events are already in order, so running several patterns
+    /// sorts once, and when a condition the pattern requires never occurs.
+    fn execute_compiled(&mut self, pattern: &CompiledPattern, count_all: bool) -> MatchResult {
+        if self.cannot_match(pattern) {
+            return MatchResult {
+                matched: false,
+                count: 0,
+                states_expanded: 0,
+            };
+        }
+        self.sort_events();
 
-        if self.compiled_pattern.is_none() {
-            let pattern_str = self.pattern_str.as_deref().unwrap_or("");
-            self.compiled_pattern = Some(parse_pattern(pattern_str)?);
+        match (&self.runs, self.window_us) {
+            (Some(runs), None) => execute_pattern_runs(pattern, runs, count_all),
+            (Some(runs), window_us) => {
+                let events = runs.expand(run_expansion_cap(pattern, count_all));
+                execute_pattern_within(pattern, &events, count_all, window_us)
+            }
+            (None, window_us) => {
+                execute_pattern_within(pattern, &self.events, count_all, window_us)
+            }
         }
+    }
 
-        let pattern = self
-            .compiled_pattern
-            .as_ref()
-            .expect("compiled_pattern was set on the line above");
-        Ok(execute_pattern(pattern, &self.events, count_all))
+    /// Executes `sequence_match_any` — returns the index of the first of
+    /// `patterns` that matches, or `None` if none does. `None` entries (NULL
+    /// patterns) never match.
+    ///
+    /// The events are collected and sorted once for all patterns.
+    pub fn finalize_match_any(
+        &mut self,
+        patterns: &[Option<Arc<CompiledPattern>>],
+    ) -> Option<usize> {
+        patterns.iter().position(|pattern| {
+            pattern
+                .as_deref()
+                .is_some_and(|pattern| self.execute_compiled(pattern, false).matched)
+        })
     }
 
     /// Executes `sequence_match` — returns true if the pattern matches.
@@ -138,8 +574,45 @@ impl SequenceState {
         Ok(self.execute(false)?.matched)
     }
 
+    /// Executes `sequence_match` and reports where its finalize spent its
+    /// work (`sequence_match_debug`), see [`SequenceStats`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `PatternError` if the pattern string is invalid.
+    pub fn finalize_stats(&mut self) -> Result<SequenceStats, PatternError> {
+        let pattern = compiled(&mut self.compiled_pattern, self.pattern_str.as_deref())?;
+        let stopwatch = Stopwatch::start();
+        if !self.cannot_match(&pattern) {
+            self.sort_events();
+        }
+        let sort_us = stopwatch.elapsed_us();
+        let result = self.execute_compiled(&pattern, false);
+        let match_us = stopwatch.elapsed_us().saturating_sub(sort_us);
+        Ok(SequenceStats {
+            matched: result.matched,
+            events_stored: self
+                .runs
+                .as_ref()
+                .map_or(self.events.len() + self.compressed.len(), |runs| {
+                    runs.event_count() as usize
+                }),
+            path: if self.window_us.is_some() {
+                ExecutionPath::Nfa
+            } else {
+                execution_path(&pattern)
+            },
+            states_expanded: result.states_expanded,
+            sort_us,
+            match_us,
+        })
+    }
+
     /// Executes `sequence_count` — returns the number of non-overlapping matches.
     ///
+    /// With [`window_us`](Self::window_us) set, this is `sequence_count_within`:
+    /// only matches spanning at most the window are counted.
+    ///
     /// # Errors
     ///
     /// Returns `PatternError` if the pattern string is invalid.
@@ -156,18 +629,64 @@ impl SequenceState {
     ///
     /// Returns `PatternError` if the pattern string is invalid.
     pub fn finalize_events(&mut self) -> Result<Vec<i64>, PatternError> {
-        sort_events(&mut self.events);
+        let pattern = compiled(&mut self.compiled_pattern, self.pattern_str.as_deref())?;
+        if self.cannot_match(&pattern) {
+            return Ok(Vec::new());
+        }
+        self.sort_events();
 
-        if self.compiled_pattern.is_none() {
-            let pattern_str = self.pattern_str.as_deref().unwrap_or("");
-            self.compiled_pattern = Some(parse_pattern(pattern_str)?);
+        if let Some(runs) = &self.runs {
+            let events = runs.expand(run_expansion_cap(&pattern, false));
+            return Ok(execute_pattern_events(&pattern, &events).unwrap_or_default());
         }
+        Ok(execute_pattern_events(&pattern, &self.events).unwrap_or_default())
+    }
 
-        let pattern = self
-            .compiled_pattern
-            .as_ref()
-            .expect("compiled_pattern was set on the line above");
-        Ok(execute_pattern_events(pattern, &self.events).unwrap_or_default())
+    /// Executes `sequence_duration` — returns the elapsed microseconds between
+    /// the first and last matched `(?N)` step of the first full match.
+    ///
+    /// Returns `None` if the pattern does not match.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DurationError::Pattern`] if the pattern string is invalid and
+    /// [`DurationError::Overflow`] when the duration does not fit in `i64`.
+    pub fn finalize_duration(&mut self) -> Result<Option<i64>, DurationError> {
+        let timestamps = self.finalize_events()?;
+        match (timestamps.first(), timestamps.last()) {
+            (Some(&first), Some(&last)) => Ok(Some(elapsed_micros(first, last)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Executes `sequence_match_spans` — returns `(start_ts, end_ts)` of every
+    /// non-overlapping match counted by `sequence_count`.
+    ///
+    /// Returns an empty vector if the pattern does not match. With run-length
+    /// encoding, events inside a run report the run's last timestamp.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PatternError` if the pattern string is invalid.
+    pub fn finalize_spans(&mut self) -> Result<Vec<(i64, i64)>, PatternError> {
+        let pattern = compiled(&mut self.compiled_pattern, self.pattern_str.as_deref())?;
+        if self.cannot_match(&pattern) {
+            return Ok(Vec::new());
+        }
+        self.sort_events();
+
+        let expanded;
+        let events: &[Event] = match &self.runs {
+            Some(runs) => {
+                expanded = runs.expand(run_expansion_cap(&pattern, true));
+                &expanded
+            }
+            None => &self.events,
+        };
+        Ok(execute_pattern_spans(&pattern, events)
+            .into_iter()
+            .map(|(first, last)| (events[first].timestamp_us, events[last].timestamp_us))
+            .collect())
     }
 }
 
@@ -177,6 +696,323 @@ impl Default for SequenceState {
     }
 }
 
+impl AsMut<Self> for SequenceState {
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+/// A matched `(?N)` step returned by `sequence_match_values`: the event
+/// timestamp and its value (`None` for NULL).
+pub type MatchedValue = (i64, Option<Arc<str>>);
+
+/// A named step returned by `sequence_extract`: the capture name and the
+/// value of the event it matched (`None` for NULL).
+pub type CapturedValue = (Arc<str>, Option<Arc<str>>);
+
+/// State for the `sequence_match_values` and `sequence_extract` aggregate
+/// functions.
+///
+/// Like [`SequenceState`], but carries a string value alongside each event so
+/// that finalize can return the value of every matched `(?N)` step together
+/// with its timestamp. Values are stored in a vector parallel to `events`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct SequenceValuesState {
+    /// Collected events (timestamp + conditions). Sorted in finalize.
+    pub events: Vec<Event>,
+    /// Value for each entry in `events` (same index). `None` for NULL values.
+    pub values: Vec<Option<Arc<str>>>,
+    /// Pattern string (parsed on first use in finalize).
+    pub pattern_str: Option<String>,
+    /// Compiled pattern shared through [`cache`] (populated during finalize).
+    compiled_pattern: Option<Arc<CompiledPattern>>,
+}
+
+impl SequenceValuesState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            values: Vec::new(),
+            pattern_str: None,
+            compiled_pattern: None,
+        }
+    }
+
+    /// Sets the pattern string (called once during the first update).
+    pub fn set_pattern(&mut self, pattern: &str) {
+        if self.pattern_str.is_none() {
+            self.pattern_str = Some(pattern.to_string());
+        }
+    }
+
+    /// Adds an event and its value to the state.
+    ///
+    /// Only events where at least one condition is true are stored,
+    /// as events with all-false conditions cannot match any `(?N)` step.
+    pub fn update(&mut self, event: Event, value: Option<Arc<str>>) {
+        if event.has_any_condition() {
+            self.events.push(event);
+            self.values.push(value);
+        }
+    }
+
+    /// Combines another state into `self` in-place by appending its events
+    /// and values.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.events.extend_from_slice(&other.events);
+        self.values.extend_from_slice(&other.values);
+        if self.pattern_str.is_none() {
+            self.pattern_str.clone_from(&other.pattern_str);
+            self.compiled_pattern = None;
+        }
+    }
+
+    /// Sorts events by timestamp, applying the same permutation to `values`.
+    ///
+    /// Skips the sort entirely when events are already in order, which is
+    /// the common case for `ORDER BY`-fed input.
+    fn sort_entries(&mut self) {
+        if self
+            .events
+            .windows(2)
+            .all(|w| w[0].timestamp_us <= w[1].timestamp_us)
+        {
+            return;
+        }
+        let mut order: Vec<usize> = (0..self.events.len()).collect();
+        order.sort_by_key(|&i| self.events[i].timestamp_us);
+        self.events = order.iter().map(|&i| self.events[i]).collect();
+        self.values = order.iter().map(|&i| self.values[i].clone()).collect();
+    }
+
+    /// Executes `sequence_match_values` — returns `(timestamp, value)` pairs
+    /// for each matched `(?N)` step.
+    ///
+    /// If the pattern does not match, returns an empty vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PatternError` if the pattern string is invalid.
+    pub fn finalize_values(&mut self) -> Result<Vec<MatchedValue>, PatternError> {
+        self.sort_entries();
+
+        let pattern = compiled(&mut self.compiled_pattern, self.pattern_str.as_deref())?;
+        let indices = execute_pattern_event_indices(&pattern, &self.events).unwrap_or_default();
+        Ok(indices
+            .into_iter()
+            .map(|i| (self.events[i].timestamp_us, self.values[i].clone()))
+            .collect())
+    }
+}
+
+impl SequenceValuesState {
+    /// Executes `sequence_extract` — returns `(name, value)` pairs for each
+    /// `(?N:name)` step of the first match, in pattern order.
+    ///
+    /// Unnamed steps are skipped. If the pattern does not match, returns an
+    /// empty vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PatternError` if the pattern string is invalid.
+    pub fn finalize_captures(&mut self) -> Result<Vec<CapturedValue>, PatternError> {
+        self.sort_entries();
+
+        let pattern = compiled(&mut self.compiled_pattern, self.pattern_str.as_deref())?;
+        let indices = execute_pattern_event_indices(&pattern, &self.events).unwrap_or_default();
+        Ok(pattern
+            .condition_captures()
+            .into_iter()
+            .zip(indices)
+            .filter_map(|(name, i)| Some((Arc::from(name?), self.values[i].clone())))
+            .collect())
+    }
+}
+
+impl Default for SequenceValuesState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A matched `(?N)` step returned by the labeled `sequence_match_events`:
+/// the step's label (`None` for a NULL label) and the event timestamp.
+pub type LabeledStep = (Option<Arc<str>>, i64);
+
+/// State for `sequence_match_events` with a `labels` argument.
+///
+/// A [`SequenceState`] plus the step labels, read once from the first row
+/// that has them, like the pattern.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct LabeledSequenceState {
+    /// Events and pattern, as for the unlabeled function.
+    pub sequence: SequenceState,
+    /// Label of each `(?N)` step, in pattern order. `None` until set.
+    pub labels: Option<Arc<[Option<Arc<str>>]>>,
+}
+
+impl LabeledSequenceState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sequence: SequenceState::new(),
+            labels: None,
+        }
+    }
+
+    /// Combines another state into `self` in-place, taking its labels when
+    /// `self` has none.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.sequence.combine_in_place(&other.sequence);
+        if self.labels.is_none() {
+            self.labels.clone_from(&other.labels);
+        }
+    }
+
+    /// Executes the labeled `sequence_match_events` — returns the label and
+    /// timestamp of each matched `(?N)` step, the `k`-th step taking the
+    /// `k`-th label.
+    ///
+    /// If the pattern does not match, returns an empty vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PatternError` if the pattern string is invalid.
+    pub fn finalize(&mut self) -> Result<Vec<LabeledStep>, PatternError> {
+        let timestamps = self.sequence.finalize_events()?;
+        let labels = self.labels.as_deref().unwrap_or_default();
+        Ok(timestamps
+            .into_iter()
+            .enumerate()
+            .map(|(k, ts)| (labels.get(k).cloned().flatten(), ts))
+            .collect())
+    }
+}
+
+/// State for the `sequence_match_any` aggregate function.
+///
+/// One [`SequenceState`] holds the events for every pattern; the patterns are
+/// compiled once, in update, and shared between states through an `Arc`.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SequenceMatchAnyState {
+    /// Events; the pattern of the inner state is unused.
+    pub sequence: SequenceState,
+    /// The compiled patterns, in list order, `None` for a NULL element.
+    /// `None` until set.
+    pub patterns: Option<Arc<[Option<Arc<CompiledPattern>>]>>,
+}
+
+impl SequenceMatchAnyState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sequence: SequenceState::new(),
+            patterns: None,
+        }
+    }
+
+    /// Combines another state into `self` in-place, taking its patterns when
+    /// `self` has none.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.sequence.combine_in_place(&other.sequence);
+        if self.patterns.is_none() {
+            self.patterns.clone_from(&other.patterns);
+        }
+    }
+
+    /// Returns the 0-based index of the first matching pattern, or `None` if
+    /// no pattern matches or none were set.
+    pub fn finalize(&mut self) -> Option<usize> {
+        let patterns = self.patterns.clone()?;
+        self.sequence.finalize_match_any(&patterns)
+    }
+}
+
+/// Match count of one time bucket returned by `sequence_count_by`:
+/// `(bucket_start_us, count)`.
+pub type BucketCount = (i64, i64);
+
+/// State for the `sequence_count_by` aggregate function.
+///
+/// A [`SequenceState`] plus the bucket width, set from the first row like the
+/// window of `sequence_count_within`.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SequenceCountByState {
+    /// Events and pattern, as for `sequence_count`.
+    pub sequence: SequenceState,
+    /// Bucket width in microseconds, always positive. `None` until set.
+    pub bucket_us: Option<i64>,
+}
+
+impl SequenceCountByState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sequence: SequenceState::new(),
+            bucket_us: None,
+        }
+    }
+
+    /// Combines another state into `self` in-place, taking its bucket width
+    /// when `self` has none.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.sequence.combine_in_place(&other.sequence);
+        if self.bucket_us.is_none() {
+            self.bucket_us = other.bucket_us;
+        }
+    }
+
+    /// Executes `sequence_count_by` — returns the number of matches per
+    /// bucket of their first matched event, for non-empty buckets in time
+    /// order. The counts sum to `sequence_count`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PatternError` if the pattern string is invalid.
+    pub fn finalize(&mut self) -> Result<Vec<BucketCount>, PatternError> {
+        let Some(bucket_us) = self.bucket_us else {
+            return Ok(Vec::new());
+        };
+        let mut buckets: Vec<BucketCount> = Vec::new();
+        // Spans come in start order, so equal buckets are adjacent.
+        for (start, _) in self.sequence.finalize_spans()? {
+            let bucket = start.saturating_sub(start.rem_euclid(bucket_us));
+            match buckets.last_mut() {
+                Some((last, count)) if *last == bucket => *count += 1,
+                _ => buckets.push((bucket, 1)),
+            }
+        }
+        Ok(buckets)
+    }
+}
+
+impl AsMut<SequenceState> for SequenceCountByState {
+    fn as_mut(&mut self) -> &mut SequenceState {
+        &mut self.sequence
+    }
+}
+
+impl AsMut<SequenceState> for SequenceMatchAnyState {
+    fn as_mut(&mut self) -> &mut SequenceState {
+        &mut self.sequence
+    }
+}
+
+impl AsMut<SequenceState> for LabeledSequenceState {
+    fn as_mut(&mut self) -> &mut SequenceState {
+        &mut self.sequence
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,6 +1021,183 @@ mod tests {
         Event::from_bools(ts, conds)
     }
 
+    #[test]
+    fn test_update_batch_matches_update() {
+        let events = [
+            make_event(300, &[false, true]),
+            make_event(100, &[true, false]),
+            make_event(200, &[false, false]),
+            make_event(400, &[false, true]),
+        ];
+        for run_length in [false, true] {
+            let mut single = SequenceState::new();
+            let mut batch = SequenceState::new();
+            if run_length {
+                single.enable_run_length();
+                batch.enable_run_length();
+            }
+            for event in events {
+                single.update(event);
+            }
+            batch.update_batch(events);
+            assert_eq!(batch.events.len(), single.events.len());
+            single.set_pattern("(?1).*(?2)");
+            batch.set_pattern("(?1).*(?2)");
+            assert_eq!(batch.finalize_count(), single.finalize_count());
+        }
+    }
+
+    #[test]
+    fn test_seq_orders_same_timestamp_events() {
+        // Same timestamp; arrival order says (?2) first, seq says (?1) first.
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1)(?2)");
+        state.update_with_seq(make_event(100, &[false, true]), 8);
+        state.update_with_seq(make_event(100, &[true, false]), 7);
+        state.update_with_seq(make_event(100, &[false, false]), 9);
+        assert_eq!(state.seqs, vec![8, 7]);
+        assert!(state.finalize_match().unwrap());
+        assert_eq!(state.finalize_events().unwrap(), vec![100, 100]);
+    }
+
+    #[test]
+    fn test_update_batch_with_seq_matches_update_with_seq() {
+        let events = [
+            (make_event(100, &[false, true]), 2),
+            (make_event(100, &[true, false]), 1),
+            (make_event(50, &[false, false]), 0),
+        ];
+        let mut single = SequenceState::new();
+        for (event, seq) in events {
+            single.update_with_seq(event, seq);
+        }
+        let mut batch = SequenceState::new();
+        batch.update_batch_with_seq(events);
+        assert_eq!(batch.seqs, single.seqs);
+        assert_eq!(batch.events.len(), single.events.len());
+    }
+
+    #[test]
+    fn test_seq_combine_and_run_length() {
+        let mut a = SequenceState::new();
+        a.set_pattern("(?1)(?2)");
+        a.update_with_seq(make_event(100, &[false, true]), 2);
+        let mut b = SequenceState::new();
+        b.update_with_seq(make_event(100, &[true, false]), 1);
+
+        let mut combined = a.combine(&b);
+        assert_eq!(combined.seqs, vec![2, 1]);
+        let mut target = SequenceState::new();
+        target.combine_in_place(&a);
+        target.combine_in_place(&b);
+        assert_eq!(target.seqs, combined.seqs);
+        assert!(combined.finalize_match().unwrap());
+
+        // Enabling run-length sorts by seq once, then drops the numbers.
+        target.enable_run_length();
+        assert!(target.seqs.is_empty());
+        assert!(target.finalize_match().unwrap());
+    }
+
+    #[test]
+    fn test_missing_condition_skips_sort_and_match() {
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        for (ts, mask) in [(300, 0b001), (100, 0b100), (200, 0b001)] {
+            state.update(Event::new(ts, mask));
+        }
+        assert!(!state.finalize_match().unwrap());
+        assert_eq!(state.finalize_count().unwrap(), 0);
+        assert!(state.finalize_events().unwrap().is_empty());
+        assert!(state.finalize_spans().unwrap().is_empty());
+        let debug = state.finalize_stats().unwrap();
+        assert!(!debug.matched);
+        assert_eq!(debug.events_stored, 3);
+        // Condition 2 never occurs: the events were never sorted
+        assert_eq!(state.events[0].timestamp_us, 300);
+
+        // Negated steps require nothing
+        state.pattern_str = Some("(?1)(?!2)".to_string());
+        state.compiled_pattern = None;
+        assert!(state.finalize_match().unwrap());
+        assert_eq!(state.events[0].timestamp_us, 100);
+    }
+
+    #[test]
+    fn test_missing_condition_sees_compressed_and_run_events() {
+        let events: Vec<Event> = (0..=COMPRESSION_THRESHOLD)
+            .map(|i| Event::new(i as i64, if i == 5 { 0b10 } else { 0b01 }))
+            .collect();
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update_batch(events.iter().copied());
+        assert_eq!(state.compressed.len(), COMPRESSION_THRESHOLD);
+        assert!(state.clone().finalize_match().unwrap());
+        state.pattern_str = Some("(?1).*(?3)".to_string());
+        let debug = state.finalize_stats().unwrap();
+        assert!(!debug.matched);
+        assert_eq!(debug.events_stored, events.len());
+
+        let mut runs = SequenceState::new();
+        runs.set_pattern("(?1).*(?2)");
+        runs.enable_run_length();
+        runs.update_batch(events.iter().copied());
+        assert!(runs.finalize_match().unwrap());
+    }
+
+    #[test]
+    fn test_compressed_events_match_uncompressed() {
+        let mut x = 0x2545_F491_4F6C_DD1D_u64;
+        let events: Vec<(Event, i64)> = (0..2 * COMPRESSION_THRESHOLD + 100)
+            .map(|i| {
+                x = x.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                // Pairs of events share a timestamp; seq reverses their order
+                let ts = (i / 2) as i64 * 1_000_000;
+                (Event::new(ts, 1 << ((x >> 40) % 3)), -(i as i64))
+            })
+            .collect();
+        let fresh = || {
+            let mut s = SequenceState::new();
+            s.set_pattern("(?1)(?2)(?3)");
+            s
+        };
+        let mut reference = fresh();
+        reference.events = events.iter().map(|&(e, _)| e).collect();
+        reference.seqs = events.iter().map(|&(_, seq)| seq).collect();
+
+        let mut state = fresh();
+        state.update_batch_with_seq(events.iter().copied());
+        assert_eq!(state.compressed.len(), 2 * COMPRESSION_THRESHOLD);
+
+        let (left, right) = events.split_at(COMPRESSION_THRESHOLD + 7);
+        let (mut a, mut b) = (fresh(), fresh());
+        a.update_batch_with_seq(left.iter().copied());
+        b.update_batch_with_seq(right.iter().copied());
+        let mut target = SequenceState::new();
+        target.combine_in_place(&a);
+        target.combine_in_place(&b);
+
+        let expected = reference.finalize_count().unwrap();
+        assert!(expected > 0);
+        for s in [&mut state, &mut a.combine(&b), &mut target] {
+            assert_eq!(s.finalize_count().unwrap(), expected);
+            assert!(s.compressed.is_empty());
+            assert_eq!(s.seqs, reference.seqs);
+        }
+
+        // A run-length target takes the compressed events of its source
+        let mut runs = fresh();
+        runs.enable_run_length();
+        runs.combine_in_place(&b);
+        let mut plain = fresh();
+        plain.update_batch(right.iter().map(|&(e, _)| e));
+        plain.enable_run_length();
+        assert_eq!(
+            runs.finalize_count().unwrap(),
+            plain.finalize_count().unwrap()
+        );
+    }
+
     #[test]
     fn test_empty_state() {
         let mut state = SequenceState::new();
@@ -232,6 +1245,35 @@ mod tests {
         assert_eq!(state.finalize_count().unwrap(), 2);
     }
 
+    #[test]
+    fn test_count_within_window() {
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update(make_event(0, &[true, false]));
+        state.update(make_event(500, &[false, true]));
+        state.update(make_event(1000, &[true, false]));
+        state.update(make_event(1100, &[false, true]));
+        assert_eq!(state.finalize_count().unwrap(), 2);
+        state.window_us = Some(200);
+        assert_eq!(state.finalize_count().unwrap(), 1);
+        state.window_us = Some(0);
+        assert_eq!(state.finalize_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_window_propagates_through_combine() {
+        let mut source = SequenceState::new();
+        source.set_pattern("(?1).*(?2)");
+        source.window_us = Some(100);
+        source.update(make_event(0, &[true, false]));
+        source.update(make_event(500, &[false, true]));
+        let mut target = SequenceState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.window_us, Some(100));
+        assert_eq!(target.finalize_count().unwrap(), 0);
+        assert_eq!(SequenceState::new().combine(&source).window_us, Some(100));
+    }
+
     #[test]
     fn test_time_constraint() {
         let mut state = SequenceState::new();
@@ -291,6 +1333,46 @@ mod tests {
         assert!(state.finalize_match().unwrap());
     }
 
+    #[test]
+    fn test_assume_sorted_skips_sort() {
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1)(?2)");
+        state.order = Some(InputOrder::AssumeSorted);
+        state.update(make_event(200, &[false, true]));
+        state.update(make_event(100, &[true, false]));
+        // Arrival order is trusted: (?2) before (?1) does not match.
+        assert!(!state.finalize_match().unwrap());
+        assert_eq!(state.events[0].timestamp_us, 200);
+    }
+
+    #[test]
+    fn test_verify_order_reports_first_unsorted_event() {
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1)(?2)");
+        state.update(make_event(300, &[true, false]));
+        state.update(make_event(100, &[false, true]));
+        // Without verify_sorted the order is not checked.
+        assert_eq!(state.verify_order(), Ok(()));
+
+        let mut target = SequenceState::new();
+        state.order = Some(InputOrder::VerifySorted);
+        target.combine_in_place(&state);
+        assert_eq!(
+            target.verify_order(),
+            Err(UnsortedEvents {
+                previous_us: 300,
+                timestamp_us: 100
+            })
+        );
+
+        let mut sorted = SequenceState::new();
+        sorted.order = InputOrder::parse(" Verify_Sorted ");
+        sorted.update(make_event(100, &[true, false]));
+        sorted.update(make_event(100, &[false, true]));
+        assert_eq!(sorted.verify_order(), Ok(()));
+        assert_eq!(InputOrder::parse("sorted"), None);
+    }
+
     #[test]
     fn test_non_overlapping_count() {
         let mut state = SequenceState::new();
@@ -760,6 +1842,399 @@ mod tests {
         let events = target.finalize_events().unwrap();
         assert_eq!(events, vec![100, 200, 300]);
     }
+
+    // --- Run-length encoding tests ---
+
+    /// 1000 heartbeats (condition 1), a purchase (condition 2), 1000 heartbeats.
+    fn heartbeat_state(run_length: bool) -> SequenceState {
+        let mut state = SequenceState::new();
+        if run_length {
+            state.enable_run_length();
+        }
+        for ts in 0..1_000 {
+            state.update(make_event(ts, &[true, false]));
+        }
+        state.update(make_event(1_000, &[false, true]));
+        for ts in 1_001..2_001 {
+            state.update(make_event(ts, &[true, false]));
+        }
+        state
+    }
+
+    #[test]
+    fn test_run_length_collapses_runs() {
+        let state = heartbeat_state(true);
+        assert!(state.events.is_empty());
+        let runs = state.runs.as_ref().unwrap();
+        assert_eq!(runs.runs().len(), 3);
+        assert_eq!(runs.event_count(), 2_001);
+    }
+
+    #[test]
+    fn test_run_length_match_agrees_with_plain() {
+        for pattern in [
+            "(?1)(?2)(?1)",
+            "(?1)(?1)(?1)(?2)",
+            "(?2).*(?1)(?1)",
+            "(?1).(?2)",
+            "(?2)(?2)",
+            "(?1).*(?2).*(?1)",
+        ] {
+            let mut plain = heartbeat_state(false);
+            let mut rle = heartbeat_state(true);
+            plain.set_pattern(pattern);
+            rle.set_pattern(pattern);
+            assert_eq!(
+                rle.finalize_match().unwrap(),
+                plain.finalize_match().unwrap(),
+                "pattern {pattern}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_length_count_uses_full_runs() {
+        let mut plain = heartbeat_state(false);
+        let mut rle = heartbeat_state(true);
+        plain.set_pattern("(?1)");
+        rle.set_pattern("(?1)");
+        assert_eq!(rle.finalize_count().unwrap(), 2_000);
+        assert_eq!(plain.finalize_count().unwrap(), 2_000);
+    }
+
+    #[test]
+    fn test_finalize_spans() {
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update(make_event(400, &[false, true]));
+        state.update(make_event(100, &[true, false]));
+        state.update(make_event(200, &[false, true]));
+        state.update(make_event(300, &[true, false]));
+        assert_eq!(
+            state.finalize_spans().unwrap(),
+            vec![(100, 200), (300, 400)]
+        );
+        assert_eq!(state.finalize_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_finalize_duration() {
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2).*(?3)");
+        state.update(make_event(500, &[false, false, true]));
+        state.update(make_event(100, &[true, false, false]));
+        state.update(make_event(250, &[false, true, false]));
+        state.update(make_event(900, &[false, false, true]));
+        assert_eq!(state.finalize_duration().unwrap(), Some(400));
+
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update(make_event(100, &[false, true]));
+        assert_eq!(state.finalize_duration().unwrap(), None);
+    }
+
+    #[test]
+    fn test_finalize_duration_infinity_overflows() {
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update(make_event(i64::MIN, &[true, false]));
+        state.update(make_event(i64::MAX, &[false, true]));
+        assert_eq!(
+            state.finalize_duration(),
+            Err(DurationError::Overflow(TimestampOverflow))
+        );
+
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update(make_event(-1, &[true, false]));
+        state.update(make_event(i64::MAX, &[false, true]));
+        assert_eq!(
+            state.finalize_duration(),
+            Err(DurationError::Overflow(TimestampOverflow))
+        );
+
+        let mut state = SequenceState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update(make_event(0, &[true, false]));
+        state.update(make_event(i64::MAX, &[false, true]));
+        assert_eq!(state.finalize_duration().unwrap(), Some(i64::MAX));
+    }
+
+    #[test]
+    fn test_run_length_spans_use_full_runs() {
+        let mut plain = heartbeat_state(false);
+        let mut rle = heartbeat_state(true);
+        plain.set_pattern("(?1)");
+        rle.set_pattern("(?1)");
+        let spans = rle.finalize_spans().unwrap();
+        assert_eq!(spans.len(), plain.finalize_spans().unwrap().len());
+        // Run interiors only keep the run's edge timestamps
+        assert_eq!(spans[0], (0, 0));
+        assert_eq!(spans[1], (999, 999));
+    }
+
+    #[test]
+    fn test_run_length_time_constraint_uses_run_edges() {
+        // Last heartbeat before the purchase is at ts=999
+        let mut state = heartbeat_state(true);
+        state.set_pattern("(?1)(?t<=1)(?2)");
+        assert!(state.finalize_match().unwrap());
+    }
+
+    #[test]
+    fn test_run_length_enable_moves_existing_events() {
+        let mut state = SequenceState::new();
+        state.update(make_event(200, &[false, true]));
+        state.update(make_event(100, &[true, false]));
+        state.enable_run_length();
+        assert!(state.events.is_empty());
+        assert_eq!(state.runs.as_ref().unwrap().event_count(), 2);
+        state.set_pattern("(?1)(?2)");
+        assert!(state.finalize_match().unwrap());
+    }
+
+    #[test]
+    fn test_run_length_combine_into_zero_target() {
+        let mut source = heartbeat_state(true);
+        source.set_pattern("(?1)(?2)");
+        let mut target = SequenceState::new();
+        target.combine_in_place(&source);
+        assert!(target.runs.is_some());
+        assert!(target.finalize_match().unwrap());
+    }
+
+    #[test]
+    fn test_run_length_combine_out_of_order_partials() {
+        let mut late = SequenceState::new();
+        late.enable_run_length();
+        late.update(make_event(300, &[false, true]));
+        let mut early = SequenceState::new();
+        early.enable_run_length();
+        for ts in [100, 200] {
+            early.update(make_event(ts, &[true, false]));
+        }
+        let mut combined = late.combine(&early);
+        combined.set_pattern("(?1)(?1)(?2)");
+        assert!(combined.finalize_match().unwrap());
+        assert_eq!(combined.finalize_events().unwrap(), vec![100, 200, 300]);
+    }
+
+    // --- SequenceValuesState tests ---
+
+    #[allow(clippy::unnecessary_wraps)]
+    fn val(s: &str) -> Option<Arc<str>> {
+        Some(Arc::from(s))
+    }
+
+    #[test]
+    fn test_values_simple_match() {
+        let mut state = SequenceValuesState::new();
+        state.set_pattern("(?1).*(?2)");
+        state.update(make_event(100, &[true, false]), val("home"));
+        state.update(make_event(200, &[false, false]), val("ignored"));
+        state.update(make_event(300, &[false, true]), val("checkout"));
+        let result = state.finalize_values().unwrap();
+        assert_eq!(result, vec![(100, val("home")), (300, val("checkout"))]);
+    }
+
+    #[test]
+    fn test_values_no_match_is_empty() {
+        let mut state = SequenceValuesState::new();
+        state.set_pattern("(?1)(?2)");
+        state.update(make_event(100, &[false, true]), val("b"));
+        state.update(make_event(200, &[true, false]), val("a"));
+        assert!(state.finalize_values().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_values_follow_unsorted_events() {
+        let mut state = SequenceValuesState::new();
+        state.set_pattern("(?1)(?2)(?3)");
+        state.update(make_event(300, &[false, false, true]), val("c"));
+        state.update(make_event(100, &[true, false, false]), val("a"));
+        state.update(make_event(200, &[false, true, false]), val("b"));
+        let result = state.finalize_values().unwrap();
+        assert_eq!(
+            result,
+            vec![(100, val("a")), (200, val("b")), (300, val("c"))]
+        );
+    }
+
+    #[test]
+    fn test_values_null_value_preserved() {
+        let mut state = SequenceValuesState::new();
+        state.set_pattern("(?1)(?2)");
+        state.update(make_event(100, &[true, false]), None);
+        state.update(make_event(200, &[false, true]), val("x"));
+        let result = state.finalize_values().unwrap();
+        assert_eq!(result, vec![(100, None), (200, val("x"))]);
+    }
+
+    #[test]
+    fn test_values_combine_propagates_pattern() {
+        let mut source = SequenceValuesState::new();
+        source.set_pattern("(?1).*(?2)");
+        source.update(make_event(200, &[false, true]), val("end"));
+        source.update(make_event(100, &[true, false]), val("start"));
+
+        let mut target = SequenceValuesState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.pattern_str.as_deref(), Some("(?1).*(?2)"));
+        let result = target.finalize_values().unwrap();
+        assert_eq!(result, vec![(100, val("start")), (200, val("end"))]);
+    }
+
+    #[test]
+    fn test_values_invalid_pattern() {
+        let mut state = SequenceValuesState::new();
+        state.set_pattern("(?");
+        state.update(make_event(100, &[true]), val("a"));
+        assert!(state.finalize_values().is_err());
+    }
+
+    #[test]
+    fn test_captures_named_steps_only() {
+        let mut state = SequenceValuesState::new();
+        state.set_pattern("(?1:landing).*(?2).*(?3:checkout)");
+        state.update(make_event(300, &[false, false, true]), val("/pay"));
+        state.update(make_event(100, &[true, false, false]), val("/home"));
+        state.update(make_event(200, &[false, true, false]), None);
+        let result = state.finalize_captures().unwrap();
+        assert_eq!(
+            result,
+            vec![
+                (Arc::from("landing"), val("/home")),
+                (Arc::from("checkout"), val("/pay")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_captures_no_match_is_empty() {
+        let mut state = SequenceValuesState::new();
+        state.set_pattern("(?1:a)(?2:b)");
+        state.update(make_event(100, &[true, false]), val("x"));
+        assert!(state.finalize_captures().unwrap().is_empty());
+    }
+
+    fn compile_all(patterns: &[Option<&str>]) -> Arc<[Option<Arc<CompiledPattern>>]> {
+        patterns
+            .iter()
+            .map(|p| p.map(|p| cache::compile(p).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_match_any_returns_first_matching_pattern() {
+        let mut state = SequenceMatchAnyState::new();
+        state.patterns = Some(compile_all(&[
+            Some("(?3)"),
+            None,
+            Some("(?1)(?2)"),
+            Some("(?1).*(?2)"),
+        ]));
+        // Unsorted input: sorting once must serve every pattern.
+        state
+            .sequence
+            .update(make_event(300, &[false, true, false]));
+        state
+            .sequence
+            .update(make_event(100, &[true, false, false]));
+        state
+            .sequence
+            .update(make_event(200, &[false, false, false]));
+        assert_eq!(state.finalize(), Some(2));
+    }
+
+    #[test]
+    fn test_match_any_none_and_combine() {
+        let mut source = SequenceMatchAnyState::new();
+        source.patterns = Some(compile_all(&[Some("(?2)(?1)")]));
+        source.sequence.update(make_event(100, &[true, false]));
+        let mut other = SequenceMatchAnyState::new();
+        other.sequence.update(make_event(200, &[false, true]));
+
+        let mut target = SequenceMatchAnyState::new();
+        target.combine_in_place(&source);
+        target.combine_in_place(&other);
+        assert_eq!(target.finalize(), None);
+        assert_eq!(SequenceMatchAnyState::new().finalize(), None);
+    }
+
+    #[test]
+    fn test_labeled_steps_take_labels_in_order() {
+        let mut state = LabeledSequenceState::new();
+        state.sequence.set_pattern("(?1).*(?2)(?1)");
+        state.labels = Some(Arc::from([val("view"), None, val("return")]));
+        state.sequence.update(make_event(100, &[true, false]));
+        state.sequence.update(make_event(200, &[false, true]));
+        state.sequence.update(make_event(300, &[true, false]));
+        let result = state.finalize().unwrap();
+        assert_eq!(
+            result,
+            vec![(val("view"), 100), (None, 200), (val("return"), 300)]
+        );
+    }
+
+    #[test]
+    fn test_labeled_combine_propagates_labels() {
+        let mut source = LabeledSequenceState::new();
+        source.sequence.set_pattern("(?1)(?2)");
+        source.labels = Some(Arc::from([val("a"), val("b")]));
+        source.sequence.update(make_event(100, &[true, false]));
+        let mut other = LabeledSequenceState::new();
+        other.sequence.update(make_event(200, &[false, true]));
+
+        let mut target = LabeledSequenceState::new();
+        target.combine_in_place(&source);
+        target.combine_in_place(&other);
+        let result = target.finalize().unwrap();
+        assert_eq!(result, vec![(val("a"), 100), (val("b"), 200)]);
+    }
+
+    #[test]
+    fn test_count_by_buckets_matches_by_first_event() {
+        let mut state = SequenceCountByState::new();
+        state.bucket_us = Some(1_000);
+        state.sequence.set_pattern("(?1).*(?2)");
+        // Matches start at 100, 900, 1_500 and -200; the one starting at
+        // 900 ends in the next bucket but counts in its first.
+        for (ts, conds) in [
+            (100, [true, false]),
+            (200, [false, true]),
+            (900, [true, false]),
+            (1_100, [false, true]),
+            (1_500, [true, false]),
+            (1_600, [false, true]),
+            (-200, [true, false]),
+            (-100, [false, true]),
+        ] {
+            state.sequence.update(make_event(ts, &conds));
+        }
+        assert_eq!(
+            state.finalize().unwrap(),
+            vec![(-1_000, 1), (0, 2), (1_000, 1)]
+        );
+        assert_eq!(state.sequence.finalize_count().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_count_by_combine_propagates_bucket() {
+        let mut source = SequenceCountByState::new();
+        source.bucket_us = Some(60);
+        source.sequence.set_pattern("(?1)");
+        source.sequence.update(make_event(10, &[true]));
+        let mut other = SequenceCountByState::new();
+        other.sequence.update(make_event(70, &[true]));
+        other.sequence.update(make_event(80, &[true]));
+
+        let mut target = SequenceCountByState::new();
+        assert!(target.clone().finalize().unwrap().is_empty());
+        target.combine_in_place(&source);
+        target.combine_in_place(&other);
+        assert_eq!(target.bucket_us, Some(60));
+        assert_eq!(target.finalize().unwrap(), vec![(0, 1), (60, 2)]);
+    }
 }
 
 #[cfg(test)]
@@ -775,7 +2250,7 @@ mod proptests {
             let mut state = SequenceState::new();
             state.set_pattern("(?1)(?2)");
             for i in 0..num_events {
-                let bitmask = 1u32 << (i % 2);
+                let bitmask = 1u64 << (i % 2);
                 state.update(Event::from_bools(i as i64, &[bitmask & 1 != 0, bitmask & 2 != 0]));
             }
             let count = state.finalize_count().unwrap();
@@ -793,7 +2268,7 @@ mod proptests {
             count_state.set_pattern("(?1)(?2)");
 
             for i in 0..num_events {
-                let bitmask = 1u32 << (i % 2);
+                let bitmask = 1u64 << (i % 2);
                 let event = Event::new(i as i64, bitmask);
                 match_state.update(event);
                 count_state.update(event);
@@ -816,7 +2291,7 @@ mod proptests {
             let mut state = SequenceState::new();
             state.set_pattern("(?1)");
             for i in 0..num_events {
-                state.update(Event::new(i as i64, 0u32)); // all conditions false
+                state.update(Event::new(i as i64, 0u64)); // all conditions false
             }
             // All-false events are filtered by update(), so no match possible
             prop_assert!(!state.finalize_match().unwrap());
@@ -830,32 +2305,32 @@ mod proptests {
             let mut a = SequenceState::new();
             a.set_pattern("(?1)(?2)");
             for i in 0..n_a {
-                a.update(Event::new(i as i64, 1u32));
+                a.update(Event::new(i as i64, 1u64));
             }
 
             let mut b = SequenceState::new();
             b.set_pattern("(?1)(?2)");
             for i in 0..n_b {
-                b.update(Event::new((n_a + i) as i64, 2u32));
+                b.update(Event::new((n_a + i) as i64, 2u64));
             }
 
             let combined = a.combine(&b);
             prop_assert_eq!(combined.events.len(), n_a + n_b);
         }
 
-        // --- 32-condition property tests ---
+        // --- 64-condition property tests ---
 
         #[test]
         fn high_condition_indices_match(
-            cond_idx in 8..=31usize,
+            cond_idx in 8..=63usize,
             num_events in 2..=20usize,
         ) {
-            // Pattern (?N) where N is a high condition index (9-32, 1-indexed)
+            // Pattern (?N) where N is a high condition index (9-64, 1-indexed)
             let pattern = format!("(?{})", cond_idx + 1);
             let mut state = SequenceState::new();
             state.set_pattern(&pattern);
             for i in 0..num_events {
-                let bitmask = 1u32 << cond_idx;
+                let bitmask = 1u64 << cond_idx;
                 state.update(Event::new(i as i64, bitmask));
             }
             // Should match since all events satisfy the condition
@@ -865,7 +2340,7 @@ mod proptests {
 
         #[test]
         fn high_condition_pair_match(
-            cond_a in 8..=30usize,
+            cond_a in 8..=62usize,
         ) {
             // Two consecutive high-index conditions: (?A)(?B)
             let cond_b = cond_a + 1;
@@ -874,24 +2349,24 @@ mod proptests {
             state.set_pattern(&pattern);
 
             // First event satisfies condition A
-            state.update(Event::new(100, 1u32 << cond_a));
+            state.update(Event::new(100, 1u64 << cond_a));
             // Second event satisfies condition B
-            state.update(Event::new(200, 1u32 << cond_b));
+            state.update(Event::new(200, 1u64 << cond_b));
 
             let matched = state.finalize_match().unwrap();
             prop_assert!(matched);
         }
 
         #[test]
-        fn condition_32_boundary_no_match(
+        fn condition_64_boundary_no_match(
             num_events in 1..=10usize,
         ) {
-            // (?32) requires condition index 31 (0-indexed). Events with only
+            // (?64) requires condition index 63 (0-indexed). Events with only
             // condition 0 set should NOT match.
             let mut state = SequenceState::new();
-            state.set_pattern("(?32)");
+            state.set_pattern("(?64)");
             for i in 0..num_events {
-                state.update(Event::new(i as i64, 1u32)); // only bit 0
+                state.update(Event::new(i as i64, 1u64)); // only bit 0
             }
             let matched = state.finalize_match().unwrap();
             prop_assert!(!matched);
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `sequence_match_debug` — Pattern match plus where finalize spent its work.
+//!
+//! Computes `sequence_match` and returns, per group, how many events arrived
+//! and were kept, which executor path the pattern took, how many NFA states
+//! it expanded and how long the sort and the match took. `DuckDB`'s
+//! `EXPLAIN ANALYZE` only times the aggregate operator as a whole: the C API
+//! offers aggregates no hook to add their own metrics to the profiler, so
+//! this is the way to see inside one group.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id,
+//!   sequence_match_debug('(?1).*(?t<=3600)(?2)', event_time,
+//!     event_type = 'view', event_type = 'purchase') AS dbg
+//! FROM events
+//! GROUP BY user_id
+//! ORDER BY dbg.match_us DESC
+//! LIMIT 10
+//! ```
+
+use crate::common::event::Event;
+use crate::pattern::parser::PatternError;
+use crate::sequence::{SequenceState, SequenceStats};
+
+/// State for the `sequence_match_debug` aggregate function.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct SequenceMatchDebugState {
+    /// The pattern match over all events.
+    pub sequence: SequenceState,
+    /// Events received by update, including those with no condition set,
+    /// which the sequence does not store.
+    pub events_collected: u64,
+}
+
+impl Default for SequenceMatchDebugState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequenceMatchDebugState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sequence: SequenceState::new(),
+            events_collected: 0,
+        }
+    }
+
+    /// Adds an event.
+    pub fn update(&mut self, event: Event) {
+        self.events_collected += 1;
+        self.sequence.update(event);
+    }
+
+    /// Merges `other` into `self`, propagating the pattern to a
+    /// zero-initialized target as [`SequenceState::combine_in_place`] does.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.sequence.combine_in_place(&other.sequence);
+        self.events_collected += other.events_collected;
+    }
+
+    /// Computes the match with the statistics of its finalize (see
+    /// [`SequenceState::finalize_stats`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PatternError` if the pattern string is invalid.
+    pub fn finalize(&mut self) -> Result<SequenceStats, PatternError> {
+        self.sequence.finalize_stats()
+    }
+}
+
+impl AsMut<SequenceState> for SequenceMatchDebugState {
+    fn as_mut(&mut self) -> &mut SequenceState {
+        &mut self.sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::executor::ExecutionPath;
+
+    #[test]
+    fn test_collected_counts_unstored_events_across_combine() {
+        let mut a = SequenceMatchDebugState::new();
+        a.sequence.set_pattern("(?1).*(?2)");
+        a.update(Event::new(0, 0b01));
+        a.update(Event::new(1, 0b00));
+        let mut b = SequenceMatchDebugState::new();
+        b.update(Event::new(2, 0b10));
+
+        let mut target = SequenceMatchDebugState::new();
+        target.combine_in_place(&a);
+        target.combine_in_place(&b);
+        assert_eq!(target.events_collected, 3);
+        let stats = target.finalize().unwrap();
+        assert!(stats.matched);
+        assert_eq!(stats.events_stored, 2);
+        assert_eq!(stats.path, ExecutionPath::Wildcard);
+        assert_eq!(stats.states_expanded, 0);
+    }
+
+    #[test]
+    fn test_nfa_reports_expanded_states() {
+        let mut state = SequenceMatchDebugState::new();
+        state.sequence.set_pattern("(?1).(?2)");
+        for (ts, mask) in [(0, 0b01), (1, 0b01), (2, 0b01), (3, 0b10)] {
+            state.update(Event::new(ts, mask));
+        }
+        let result = state.finalize().unwrap();
+        assert!(result.matched);
+        assert_eq!(result.path, ExecutionPath::Nfa);
+        assert!(result.states_expanded > 0);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let mut state = SequenceMatchDebugState::new();
+        state.sequence.set_pattern("(?1)(?x)");
+        state.update(Event::new(0, 0b01));
+        assert!(state.finalize().is_err());
+    }
+}
@@ -26,12 +26,49 @@
 //! Uses simple sequential matching (not NFA/regex patterns). Events are sorted
 //! by timestamp and scanned in the specified direction to find a chain matching
 //! event1 → event2 → ... → eventN. The value of the adjacent event is returned.
+//! The state records where its events fall out of order as they arrive and as
+//! partial states are combined, so the sort merges the sorted runs instead of
+//! sorting groups of millions of events from scratch.
 //!
 //! - **Forward**: Returns the value of the event immediately after the last
 //!   matched event.
 //! - **Backward**: Returns the value of the event immediately before the
 //!   earliest matched event.
 //!
+//! An optional window ([`SequenceNextNodeState::window_us`]) limits the gap
+//! between the matched chain and the adjacent event; an adjacent event further
+//! away is treated as missing.
+//!
+//! # Events Sharing a Timestamp
+//!
+//! By default, consecutive chain steps and the adjacent event may share a
+//! timestamp, as in `ClickHouse`. Trackers that batch several events into one
+//! millisecond then produce chains whose order is an accident of sorting. The
+//! `strict_increase` option, given after the direction (`'forward,
+//! strict_increase'`), requires each chain step to be strictly later than the
+//! previous one (earlier, backward) and skips events sharing the timestamp of
+//! the chain's edge when looking for the adjacent event (see
+//! [`SequenceNextNodeState::strict_increase`]).
+//!
+//! # Sessions
+//!
+//! The `session_gap(D)` direction option (`'forward, session_gap(30m)'`, `D`
+//! a [`parse_duration`] duration) splits the events into sessions with the
+//! rule of `sessionize`: a gap of more than `D` between consecutive events
+//! starts a new session. The chain and the adjacent event must then lie in
+//! the session of the chain's first step, so a next page from a later visit
+//! is treated as missing (see [`SequenceNextNodeState::session_gap_us`]).
+//!
+//! # Groups Without a Base Event
+//!
+//! A group in which no event satisfies the base condition returns `NULL`,
+//! like a group without a match. The `missing_base(M)` direction option
+//! (`'forward, missing_base(first)'`) changes that for queries ported from
+//! systems that behave differently: `null` keeps the default, `first`
+//! returns the value of the group's earliest event, and `error` fails the
+//! query (see [`MissingBase`]). Groups with a base event but no match are
+//! not affected.
+//!
 //! # `Arc<str>` Value Storage (Session 9)
 //!
 //! Event values use `Arc<str>` (atomically reference-counted immutable string)
@@ -43,7 +80,17 @@
 //! - **Combine**: cloning events in `combine_in_place` is O(1) per event
 //! - **Sort**: swapping 32-byte elements vs 40-byte elements
 //! - **Cache utilization**: 2 events per cache line vs 1.6 previously
-
+//!
+//! # Typed values
+//!
+//! [`NextNodeEvent`] and [`SequenceNextNodeState`] are generic over the value
+//! type `V`, defaulting to `Arc<str>`. Non-VARCHAR value columns (`BIGINT`,
+//! `INTEGER`, `DATE`, `UUID`) are stored in their physical representation
+//! (`i64`, `i32`, `i128`) and returned as the same SQL type, so no cast to
+//! VARCHAR and back is needed. The matching logic is identical for all `V`.
+
+use crate::common::event::merge_sorted_into;
+use crate::common::timestamp::{elapsed_exceeds, parse_duration};
 use std::sync::Arc;
 
 /// Direction of traversal for sequence matching.
@@ -57,6 +104,55 @@ pub enum Direction {
     Backward,
 }
 
+/// A direction with the options given after it, as parsed by
+/// [`SequenceNextNodeState::parse_direction_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectionOptions {
+    /// The direction.
+    pub direction: Direction,
+    /// Whether `strict_increase` was given.
+    pub strict_increase: bool,
+    /// The gap of a `session_gap(D)` option in microseconds, if given.
+    pub session_gap_us: Option<i64>,
+    /// The behavior of a `missing_base(M)` option, [`MissingBase::Null`] if
+    /// not given.
+    pub missing_base: MissingBase,
+}
+
+/// Result of a group in which no event satisfies the base condition, set by
+/// the `missing_base(M)` direction option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingBase {
+    /// `missing_base(null)`: `NULL`, the default.
+    #[default]
+    Null,
+    /// `missing_base(first)`: the value of the group's earliest event.
+    First,
+    /// `missing_base(error)`: fail the query
+    /// (see [`SequenceNextNodeState::check_base`]).
+    Error,
+}
+
+/// Error of [`SequenceNextNodeState::check_base`]: no event of the group
+/// satisfies the base condition under `missing_base(error)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoBaseEvent {
+    /// Number of events in the group.
+    pub events: usize,
+}
+
+impl std::fmt::Display for NoBaseEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "none of the group's {} events satisfies the base condition with 'missing_base(error)'",
+            self.events
+        )
+    }
+}
+
+impl std::error::Error for NoBaseEvent {}
+
 /// Base position for starting the sequence match.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Base {
@@ -70,40 +166,36 @@ pub enum Base {
     LastMatch,
 }
 
-/// A single timestamped event with a string value for `sequence_next_node`.
+/// A single timestamped event with a value for `sequence_next_node`.
 ///
 /// Uses `Arc<str>` instead of `String` for O(1) clone semantics. This reduces
 /// per-event struct size from 40 bytes to 32 bytes and eliminates deep string
 /// copying in combine operations (reference count increment instead of heap
 /// allocation + memcpy).
 ///
-/// Unlike [`crate::common::event::Event`] (which is `Copy` with a `u32` bitmask),
-/// this struct stores a reference-counted string value that may be returned as
-/// the function result.
+/// Unlike [`crate::common::event::Event`] (which is `Copy` with a `u64` bitmask),
+/// this struct stores a value (by default a reference-counted string) that may
+/// be returned as the function result.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
-pub struct NextNodeEvent {
+pub struct NextNodeEvent<V = Arc<str>> {
     /// Timestamp in microseconds since Unix epoch.
     pub timestamp_us: i64,
     /// The event column value (candidate return value). Uses `Arc<str>` for
     /// O(1) clone — critical for combine performance in `DuckDB`'s segment tree.
-    pub value: Option<Arc<str>>,
+    pub value: Option<V>,
     /// Whether the base condition is satisfied for this event.
     pub base_condition: bool,
     /// Bitmask of which sequential event conditions this event satisfies.
     /// Bit `i` is set if this event matches event `i+1` (1-indexed) in the chain.
+    /// Kept at `u32` (32 event conditions) so the struct stays at 32 bytes.
     pub conditions: u32,
 }
 
-impl NextNodeEvent {
+impl<V> NextNodeEvent<V> {
     /// Creates a new event with the given timestamp, value, base condition, and conditions bitmask.
     #[must_use]
-    pub fn new(
-        timestamp_us: i64,
-        value: Option<Arc<str>>,
-        base_condition: bool,
-        conditions: u32,
-    ) -> Self {
+    pub fn new(timestamp_us: i64, value: Option<V>, base_condition: bool, conditions: u32) -> Self {
         Self {
             timestamp_us,
             value,
@@ -120,40 +212,62 @@ impl NextNodeEvent {
 /// after a completed sequence match.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
-pub struct SequenceNextNodeState {
-    /// Collected events. Sorted by timestamp in finalize.
-    pub events: Vec<NextNodeEvent>,
+pub struct SequenceNextNodeState<V = Arc<str>> {
+    /// Collected events. Sorted by timestamp within each run started at
+    /// `run_starts`, and as a whole after finalize.
+    pub events: Vec<NextNodeEvent<V>>,
+    /// Indices in `events` at which an event is earlier than the one before
+    /// it, i.e. where a new timestamp-sorted run starts. Kept by `update` and
+    /// combine so that finalize merges the runs instead of sorting all
+    /// events; empty when the events are in order.
+    run_starts: Vec<usize>,
     /// Direction of traversal (forward or backward).
     pub direction: Option<Direction>,
     /// Base position for matching.
     pub base: Option<Base>,
     /// Number of event condition steps in the sequence.
     pub num_steps: usize,
+    /// Maximum gap in microseconds between the matched chain and the adjacent
+    /// event. An adjacent event further away than this is ignored, as if the
+    /// chain had no adjacent event. `None` means unlimited.
+    pub window_us: Option<i64>,
+    /// Whether chain steps must have strictly increasing timestamps (strictly
+    /// decreasing, backward), and the adjacent event a timestamp different
+    /// from the chain's edge. Set by the `strict_increase` direction option.
+    pub strict_increase: bool,
+    /// Largest gap in microseconds between consecutive events of a session,
+    /// set by the `session_gap(D)` direction option. A longer gap starts a
+    /// new session, and the chain and its adjacent event must lie in one
+    /// session. `None` treats all events as one session.
+    pub session_gap_us: Option<i64>,
+    /// Result of a group without a base event, set by the `missing_base(M)`
+    /// direction option.
+    pub missing_base: MissingBase,
 }
 
 impl SequenceNextNodeState {
+    /// Options accepted after the direction.
+    pub const DIRECTION_OPTIONS: [&'static str; 5] = [
+        "strict_increase",
+        "session_gap(D)",
+        "missing_base(null)",
+        "missing_base(first)",
+        "missing_base(error)",
+    ];
+
     /// Creates a new empty state.
     #[must_use]
     pub const fn new() -> Self {
         Self {
             events: Vec::new(),
+            run_starts: Vec::new(),
             direction: None,
             base: None,
             num_steps: 0,
-        }
-    }
-
-    /// Sets the direction parameter.
-    pub fn set_direction(&mut self, direction: Direction) {
-        if self.direction.is_none() {
-            self.direction = Some(direction);
-        }
-    }
-
-    /// Sets the base parameter.
-    pub fn set_base(&mut self, base: Base) {
-        if self.base.is_none() {
-            self.base = Some(base);
+            window_us: None,
+            strict_increase: false,
+            session_gap_us: None,
+            missing_base: MissingBase::Null,
         }
     }
 
@@ -169,6 +283,46 @@ impl SequenceNextNodeState {
         }
     }
 
+    /// Parses a direction string followed by optional comma-separated
+    /// options, e.g. `"forward, strict_increase, session_gap(30m)"` or
+    /// `"backward, missing_base(error)"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `("direction", token)` for an unrecognized direction and
+    /// `("option", token)` for an option not in [`Self::DIRECTION_OPTIONS`],
+    /// including a `session_gap` whose duration does not parse.
+    pub fn parse_direction_options(s: &str) -> Result<DirectionOptions, (&'static str, &str)> {
+        let mut tokens = s.split(',');
+        let first = tokens.next().unwrap_or_default().trim();
+        let mut options = DirectionOptions {
+            direction: Self::parse_direction(first).ok_or(("direction", first))?,
+            strict_increase: false,
+            session_gap_us: None,
+            missing_base: MissingBase::Null,
+        };
+        for token in tokens.map(str::trim).filter(|t| !t.is_empty()) {
+            if token.eq_ignore_ascii_case("strict_increase") {
+                options.strict_increase = true;
+                continue;
+            }
+            if let Some(missing_base) = parse_missing_base(token) {
+                options.missing_base = missing_base;
+                continue;
+            }
+            let gap = token
+                .get(..12)
+                .filter(|name| name.eq_ignore_ascii_case("session_gap("))
+                .and_then(|_| token[12..].strip_suffix(')'))
+                .and_then(|duration| parse_duration(duration.trim()).ok());
+            match gap {
+                Some(gap_us) => options.session_gap_us = Some(gap_us),
+                None => return Err(("option", token)),
+            }
+        }
+        Ok(options)
+    }
+
     /// Parses a base string.
     ///
     /// Returns `None` for unrecognized base strings.
@@ -183,11 +337,56 @@ impl SequenceNextNodeState {
         }
     }
 
+    /// Executes the sequence matching and returns the next node's value.
+    ///
+    /// Returns `None` if no match is found or no adjacent event exists.
+    pub fn finalize(&mut self) -> Option<String> {
+        self.finalize_value().map(|value| String::from(&*value))
+    }
+}
+
+impl<V: Clone> SequenceNextNodeState<V> {
+    /// Sets the direction parameter.
+    pub fn set_direction(&mut self, direction: Direction) {
+        if self.direction.is_none() {
+            self.direction = Some(direction);
+        }
+    }
+
+    /// Sets the base parameter.
+    pub fn set_base(&mut self, base: Base) {
+        if self.base.is_none() {
+            self.base = Some(base);
+        }
+    }
+
+    /// Applies the options parsed from the direction string.
+    pub fn set_direction_options(&mut self, options: DirectionOptions) {
+        self.set_direction(options.direction);
+        self.strict_increase = options.strict_increase;
+        self.session_gap_us = options.session_gap_us;
+        self.missing_base = options.missing_base;
+    }
+
+    /// Sets the window for the adjacent event (see [`Self::window_us`]).
+    pub const fn set_window(&mut self, window_us: i64) {
+        if self.window_us.is_none() {
+            self.window_us = Some(window_us);
+        }
+    }
+
     /// Adds an event to the state.
     ///
     /// All events are stored regardless of conditions because any event could
     /// be the "next node" whose value is returned.
-    pub fn update(&mut self, event: NextNodeEvent) {
+    pub fn update(&mut self, event: NextNodeEvent<V>) {
+        if self
+            .events
+            .last()
+            .is_some_and(|last| event.timestamp_us < last.timestamp_us)
+        {
+            self.run_starts.push(self.events.len());
+        }
         self.events.push(event);
     }
 
@@ -196,9 +395,12 @@ impl SequenceNextNodeState {
     pub fn combine(&self, other: &Self) -> Self {
         let mut events = Vec::with_capacity(self.events.len() + other.events.len());
         events.extend(self.events.iter().cloned());
+        let mut run_starts = self.run_starts.clone();
+        append_runs(&mut run_starts, &events, other);
         events.extend(other.events.iter().cloned());
         Self {
             events,
+            run_starts,
             direction: self.direction.or(other.direction),
             base: self.base.or(other.base),
             num_steps: if self.num_steps > 0 {
@@ -206,6 +408,14 @@ impl SequenceNextNodeState {
             } else {
                 other.num_steps
             },
+            window_us: self.window_us.or(other.window_us),
+            strict_increase: self.strict_increase || other.strict_increase,
+            session_gap_us: self.session_gap_us.or(other.session_gap_us),
+            missing_base: if self.missing_base == MissingBase::Null {
+                other.missing_base
+            } else {
+                self.missing_base
+            },
         }
     }
 
@@ -213,7 +423,11 @@ impl SequenceNextNodeState {
     ///
     /// Preferred for sequential (left-fold) chains. Uses Vec's doubling growth
     /// strategy for O(N) amortized total copies.
+    ///
+    /// The sorted runs of both states are kept, so a combine of sorted
+    /// segments costs a merge, not a sort, in finalize.
     pub fn combine_in_place(&mut self, other: &Self) {
+        append_runs(&mut self.run_starts, &self.events, other);
         self.events.extend(other.events.iter().cloned());
         if self.direction.is_none() {
             self.direction = other.direction;
@@ -224,18 +438,55 @@ impl SequenceNextNodeState {
         if self.num_steps == 0 {
             self.num_steps = other.num_steps;
         }
+        if self.window_us.is_none() {
+            self.window_us = other.window_us;
+        }
+        self.strict_increase |= other.strict_increase;
+        if self.session_gap_us.is_none() {
+            self.session_gap_us = other.session_gap_us;
+        }
+        if self.missing_base == MissingBase::Null {
+            self.missing_base = other.missing_base;
+        }
+    }
+
+    /// Fails under `missing_base(error)` when the group has events but none
+    /// satisfies the base condition. Finalize then returns `NULL` like
+    /// `missing_base(null)`, so the extension checks this first.
+    pub fn check_base(&self) -> Result<(), NoBaseEvent> {
+        if self.missing_base == MissingBase::Error && !self.has_base_event() {
+            return Err(NoBaseEvent {
+                events: self.events.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns true if the group is empty or an event satisfies the base
+    /// condition.
+    fn has_base_event(&self) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e.base_condition)
+    }
+
+    /// Returns true if finalize returns the earliest event's value: under
+    /// `missing_base(first)`, when no event satisfies the base condition.
+    fn returns_first(&self) -> bool {
+        self.missing_base == MissingBase::First && !self.has_base_event()
     }
 
     /// Executes the sequence matching and returns the next node's value.
     ///
     /// Returns `None` if no match is found or no adjacent event exists.
-    pub fn finalize(&mut self) -> Option<String> {
+    pub fn finalize_value(&mut self) -> Option<V> {
         if self.events.is_empty() || self.num_steps == 0 {
             return None;
         }
 
         // Sort events by timestamp
         self.sort_events();
+        if self.returns_first() {
+            return self.events[0].value.clone();
+        }
 
         let direction = self.direction.unwrap_or(Direction::Forward);
         let base = self.base.unwrap_or(Base::FirstMatch);
@@ -244,22 +495,62 @@ impl SequenceNextNodeState {
             Direction::Forward => self.match_forward(base),
             Direction::Backward => self.match_backward(base),
         }
+        .cloned()
     }
 
-    /// Sorts events by timestamp (ascending) with presorted detection.
+    /// Sorts events by timestamp (ascending).
+    ///
+    /// Merges the sorted runs recorded by update and combine pairwise, in
+    /// `O(n log k)` for `k` runs, as `DuckDB` combines a group from a few
+    /// large segments, each usually in order. Runs shorter than
+    /// [`MIN_MERGED_RUN_LEN`] on average mean unordered input, which a sort
+    /// handles faster. Both are stable: events sharing a timestamp keep their
+    /// combine order.
     fn sort_events(&mut self) {
-        if self
-            .events
-            .windows(2)
-            .all(|w| w[0].timestamp_us <= w[1].timestamp_us)
-        {
+        let len = self.events.len();
+        // Events removed directly from `events` leave stale run starts.
+        self.run_starts.retain(|&start| start < len);
+        if self.run_starts.is_empty() {
             return;
         }
-        self.events.sort_unstable_by_key(|e| e.timestamp_us);
+        if len < (self.run_starts.len() + 1) * MIN_MERGED_RUN_LEN {
+            self.events.sort_by_key(|e| e.timestamp_us);
+            self.run_starts.clear();
+            return;
+        }
+
+        let mut bounds: Vec<usize> = std::iter::once(0)
+            .chain(self.run_starts.drain(..))
+            .chain(std::iter::once(len))
+            .collect();
+        let mut events = std::mem::take(&mut self.events);
+        while bounds.len() > 2 {
+            let mut merged = Vec::with_capacity(len);
+            let mut merged_bounds = Vec::with_capacity(bounds.len() / 2 + 1);
+            merged_bounds.push(0);
+            for runs in bounds.windows(3).step_by(2) {
+                merge_sorted_into(
+                    &events[runs[0]..runs[1]],
+                    &events[runs[1]..runs[2]],
+                    &mut merged,
+                    |a, b| a.timestamp_us.cmp(&b.timestamp_us),
+                );
+                merged_bounds.push(merged.len());
+            }
+            // An odd run out is carried to the next pass.
+            let paired_end = bounds[2 * (merged_bounds.len() - 1)];
+            if paired_end < len {
+                merged.extend_from_slice(&events[paired_end..]);
+                merged_bounds.push(len);
+            }
+            bounds = merged_bounds;
+            events = merged;
+        }
+        self.events = events;
     }
 
     /// Forward matching: find sequential event1→event2→...→eventN, return next event's value.
-    fn match_forward(&self, base: Base) -> Option<String> {
+    fn match_forward(&self, base: Base) -> Option<&V> {
         let n = self.events.len();
 
         match base {
@@ -297,14 +588,48 @@ impl SequenceNextNodeState {
         }
     }
 
+    /// Returns true if an adjacent event at `adjacent_us` lies within the
+    /// window of the matched event at `matched_us`.
+    fn within_window(&self, matched_us: i64, adjacent_us: i64) -> bool {
+        self.window_us.is_none_or(|window| {
+            u64::try_from(window).is_ok_and(|window| adjacent_us.abs_diff(matched_us) <= window)
+        })
+    }
+
+    /// Returns true if an event at `later_us` may follow one at `earlier_us`
+    /// in time order: always, unless [`Self::strict_increase`] requires the
+    /// timestamps to differ.
+    const fn advances(&self, earlier_us: i64, later_us: i64) -> bool {
+        !self.strict_increase || later_us > earlier_us
+    }
+
+    /// Returns true if event `pos` starts a new session: more than
+    /// [`Self::session_gap_us`] after the event before it, as in `sessionize`.
+    fn starts_session(&self, pos: usize) -> bool {
+        pos > 0
+            && self.session_gap_us.is_some_and(|gap| {
+                elapsed_exceeds(
+                    self.events[pos - 1].timestamp_us,
+                    self.events[pos].timestamp_us,
+                    gap,
+                )
+            })
+    }
+
     /// Try to match the full sequence forward starting from `start`.
     ///
-    /// Returns the value of the event immediately after the last matched event.
-    fn try_match_forward_from(&self, start: usize, n: usize) -> Option<String> {
+    /// Returns the value of the event immediately after the last matched event
+    /// (the first strictly later one under [`Self::strict_increase`]), or
+    /// `None` if it lies outside the window. Only events of the session of
+    /// `start` are considered.
+    fn try_match_forward_from(&self, start: usize, n: usize) -> Option<&V> {
         // Check event1 (step 0) at start position
         if self.events[start].conditions & 1 == 0 {
             return None;
         }
+        let n = ((start + 1)..n)
+            .find(|&pos| self.starts_session(pos))
+            .unwrap_or(n);
 
         let mut last_matched = start;
         let mut step = 1;
@@ -313,7 +638,12 @@ impl SequenceNextNodeState {
             if step >= self.num_steps {
                 break;
             }
-            if (self.events[pos].conditions >> step) & 1 != 0 {
+            if (self.events[pos].conditions >> step) & 1 != 0
+                && self.advances(
+                    self.events[last_matched].timestamp_us,
+                    self.events[pos].timestamp_us,
+                )
+            {
                 last_matched = pos;
                 step += 1;
             }
@@ -321,9 +651,11 @@ impl SequenceNextNodeState {
 
         if step == self.num_steps {
             // Full match! Return next event's value
-            let next_idx = last_matched + 1;
-            if next_idx < n {
-                self.events[next_idx].value.as_deref().map(String::from)
+            let matched_us = self.events[last_matched].timestamp_us;
+            let next_idx = ((last_matched + 1)..n)
+                .find(|&pos| self.advances(matched_us, self.events[pos].timestamp_us))?;
+            if self.within_window(matched_us, self.events[next_idx].timestamp_us) {
+                self.events[next_idx].value.as_ref()
             } else {
                 None
             }
@@ -337,7 +669,7 @@ impl SequenceNextNodeState {
     /// Matches event1 at the starting position (later timestamp), then event2
     /// at an earlier position, etc. Returns the value of the event immediately
     /// before the earliest matched event.
-    fn match_backward(&self, base: Base) -> Option<String> {
+    fn match_backward(&self, base: Base) -> Option<&V> {
         let n = self.events.len();
 
         match base {
@@ -380,22 +712,34 @@ impl SequenceNextNodeState {
     /// Try to match the full sequence backward starting from `start`.
     ///
     /// event1 is matched at `start`, event2 at an earlier position, etc.
-    /// Returns the value of the event immediately before the earliest matched.
-    fn try_match_backward_from(&self, start: usize) -> Option<String> {
+    /// Returns the value of the event immediately before the earliest matched
+    /// (the last strictly earlier one under [`Self::strict_increase`]), or
+    /// `None` if it lies outside the window. Only events of the session of
+    /// `start` are considered.
+    fn try_match_backward_from(&self, start: usize) -> Option<&V> {
         // Check event1 (step 0) at start position
         if self.events[start].conditions & 1 == 0 {
             return None;
         }
+        let session_start = (1..=start)
+            .rev()
+            .find(|&pos| self.starts_session(pos))
+            .unwrap_or(0);
 
         let mut earliest_matched = start;
         let mut step = 1;
 
-        if start > 0 {
-            for pos in (0..start).rev() {
+        if start > session_start {
+            for pos in (session_start..start).rev() {
                 if step >= self.num_steps {
                     break;
                 }
-                if (self.events[pos].conditions >> step) & 1 != 0 {
+                if (self.events[pos].conditions >> step) & 1 != 0
+                    && self.advances(
+                        self.events[pos].timestamp_us,
+                        self.events[earliest_matched].timestamp_us,
+                    )
+                {
                     earliest_matched = pos;
                     step += 1;
                 }
@@ -404,11 +748,12 @@ impl SequenceNextNodeState {
 
         if step == self.num_steps {
             // Full match! Return the event before the earliest matched position
-            if earliest_matched > 0 {
-                self.events[earliest_matched - 1]
-                    .value
-                    .as_deref()
-                    .map(String::from)
+            let matched_us = self.events[earliest_matched].timestamp_us;
+            let prev_idx = (session_start..earliest_matched)
+                .rev()
+                .find(|&pos| self.advances(self.events[pos].timestamp_us, matched_us))?;
+            if self.within_window(matched_us, self.events[prev_idx].timestamp_us) {
+                self.events[prev_idx].value.as_ref()
             } else {
                 None
             }
@@ -418,9 +763,101 @@ impl SequenceNextNodeState {
     }
 }
 
-impl Default for SequenceNextNodeState {
+impl<V: Clone + Ord> SequenceNextNodeState<V> {
+    /// Counts the adjacent values of every complete match, one per event
+    /// satisfying `base_condition`, for `sequence_next_node_hist`.
+    ///
+    /// `base` is ignored: every start is tried. Matches whose adjacent event
+    /// is missing, outside the window or has a `NULL` value are not counted.
+    /// Returns `(value, count)` pairs ordered by count descending, then value.
+    /// Under `missing_base(first)`, a group without a base event counts the
+    /// value of its earliest event once.
+    pub fn finalize_histogram(&mut self) -> Vec<(V, i64)> {
+        if self.events.is_empty() || self.num_steps == 0 {
+            return Vec::new();
+        }
+        self.sort_events();
+        if self.returns_first() {
+            return self.events[0]
+                .value
+                .iter()
+                .map(|v| (v.clone(), 1))
+                .collect();
+        }
+
+        let direction = self.direction.unwrap_or(Direction::Forward);
+        let mut values: Vec<V> = (0..self.events.len())
+            .filter(|&start| self.events[start].base_condition)
+            .filter_map(|start| match direction {
+                Direction::Forward => self.try_match_forward_from(start, self.events.len()),
+                Direction::Backward => self.try_match_backward_from(start),
+            })
+            .cloned()
+            .collect();
+        values.sort_unstable();
+
+        let mut counts: Vec<(V, i64)> = Vec::new();
+        for value in values {
+            match counts.last_mut() {
+                Some((last, count)) if *last == value => *count += 1,
+                _ => counts.push((value, 1)),
+            }
+        }
+        // Stable sort keeps equal counts in value order.
+        counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        counts
+    }
+}
+
+/// Parses a `missing_base(M)` direction option, ASCII case-insensitive.
+fn parse_missing_base(token: &str) -> Option<MissingBase> {
+    let mode = token
+        .get(..13)
+        .filter(|name| name.eq_ignore_ascii_case("missing_base("))
+        .and_then(|_| token[13..].strip_suffix(')'))?
+        .trim();
+    match mode {
+        m if m.eq_ignore_ascii_case("null") => Some(MissingBase::Null),
+        m if m.eq_ignore_ascii_case("first") => Some(MissingBase::First),
+        m if m.eq_ignore_ascii_case("error") => Some(MissingBase::Error),
+        _ => None,
+    }
+}
+
+/// Average run length below which finalize sorts the events instead of
+/// merging their runs (see [`SequenceNextNodeState::sort_events`]).
+const MIN_MERGED_RUN_LEN: usize = 8;
+
+/// Appends to `run_starts` the runs of `other`'s events, about to be
+/// appended to `events`: one starting at the seam when `other` begins
+/// earlier than `events` ends, then `other`'s own.
+fn append_runs<V>(
+    run_starts: &mut Vec<usize>,
+    events: &[NextNodeEvent<V>],
+    other: &SequenceNextNodeState<V>,
+) {
+    let offset = events.len();
+    if let (Some(last), Some(first)) = (events.last(), other.events.first()) {
+        if first.timestamp_us < last.timestamp_us {
+            run_starts.push(offset);
+        }
+    }
+    run_starts.extend(other.run_starts.iter().map(|&start| offset + start));
+}
+
+impl<V> Default for SequenceNextNodeState<V> {
     fn default() -> Self {
-        Self::new()
+        Self {
+            events: Vec::new(),
+            run_starts: Vec::new(),
+            direction: None,
+            base: None,
+            num_steps: 0,
+            window_us: None,
+            strict_increase: false,
+            session_gap_us: None,
+            missing_base: MissingBase::Null,
+        }
     }
 }
 
@@ -888,6 +1325,71 @@ mod tests {
         assert_eq!(a.finalize(), Some("C".to_string()));
     }
 
+    #[test]
+    fn test_update_and_combine_record_run_starts() {
+        let mut a = SequenceNextNodeState::new();
+        for ts in [5, 3, 4] {
+            a.update(make_event(ts, "A", false, &[false]));
+        }
+        assert_eq!(a.run_starts, vec![1]);
+
+        let mut b = SequenceNextNodeState::new();
+        for ts in [1, 2, 0] {
+            b.update(make_event(ts, "B", false, &[false]));
+        }
+        let combined = a.combine(&b);
+        a.combine_in_place(&b);
+        assert_eq!(a.run_starts, vec![1, 3, 5]);
+        assert_eq!(combined.run_starts, a.run_starts);
+
+        // No run starts at a seam that is in order.
+        let mut target = SequenceNextNodeState::new();
+        target.combine_in_place(&b);
+        let mut later = SequenceNextNodeState::new();
+        later.update(make_event(7, "C", false, &[false]));
+        target.combine_in_place(&later);
+        assert_eq!(target.run_starts, vec![2]);
+    }
+
+    #[test]
+    fn test_sort_merges_interleaved_segments() {
+        // Four sorted segments of 16 events with interleaved timestamps; the
+        // last two share theirs, so ties show the combine order.
+        let mut target = SequenceNextNodeState::new();
+        for (segment, offset) in [("a", 0), ("b", 1), ("c", 2), ("d", 2)] {
+            let mut state = SequenceNextNodeState::new();
+            for i in 0..16 {
+                state.update(make_event(i * 3 + offset, segment, false, &[false]));
+            }
+            target.combine_in_place(&state);
+        }
+        assert_eq!(target.run_starts.len(), 3);
+
+        target.sort_events();
+        assert!(target.run_starts.is_empty());
+        assert!(target
+            .events
+            .windows(2)
+            .all(|w| w[0].timestamp_us <= w[1].timestamp_us));
+        let values: Vec<&str> = target.events[..4]
+            .iter()
+            .map(|e| e.value.as_deref().unwrap())
+            .collect();
+        assert_eq!(values, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_sort_short_runs() {
+        let mut state = SequenceNextNodeState::new();
+        for ts in [4, 3, 2, 1, 0] {
+            state.update(make_event(ts, "A", false, &[false]));
+        }
+        assert_eq!(state.run_starts.len(), 4);
+        state.sort_events();
+        let timestamps: Vec<i64> = state.events.iter().map(|e| e.timestamp_us).collect();
+        assert_eq!(timestamps, [0, 1, 2, 3, 4]);
+    }
+
     #[test]
     fn test_combine_preserves_direction_and_base() {
         let mut a = SequenceNextNodeState::new();
@@ -1118,6 +1620,27 @@ mod tests {
     fn test_next_node_event_size() {
         // Verify NextNodeEvent with Arc<str> is 32 bytes (down from 40 with String)
         assert_eq!(std::mem::size_of::<NextNodeEvent>(), 32);
+        // Integer values fit in the same footprint
+        assert_eq!(std::mem::size_of::<NextNodeEvent<i64>>(), 32);
+    }
+
+    #[test]
+    fn test_typed_values_match_like_strings() {
+        let mut typed = SequenceNextNodeState::<i64>::default();
+        typed.set_direction(Direction::Forward);
+        typed.set_base(Base::FirstMatch);
+        typed.num_steps = 2;
+        typed.update(NextNodeEvent::new(30, Some(300), false, 0b00));
+        typed.update(NextNodeEvent::new(10, Some(100), true, 0b01));
+        typed.update(NextNodeEvent::new(20, Some(200), false, 0b10));
+        assert_eq!(typed.finalize_value(), Some(300));
+
+        let mut backward = SequenceNextNodeState::<i128>::default();
+        backward.set_direction(Direction::Backward);
+        backward.num_steps = 1;
+        backward.update(NextNodeEvent::new(10, None, false, 0));
+        backward.update(NextNodeEvent::new(20, Some(i128::MAX), true, 0b1));
+        assert_eq!(backward.finalize_value(), None);
     }
 
     #[test]
@@ -1462,6 +1985,376 @@ mod tests {
         // backward → event before pos 1 = A
         assert_eq!(state.finalize(), Some("A".to_string()));
     }
+
+    // --- Window ---
+
+    #[test]
+    fn test_window_ignores_distant_next_node() {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Forward);
+        state.base = Some(Base::Head);
+        state.num_steps = 2;
+        state.set_window(10);
+        state.update(make_event(0, "A", true, &[true, false]));
+        state.update(make_event(5, "B", false, &[false, true]));
+        state.update(make_event(16, "C", false, &[false, false]));
+        // C is 11us after the last matched event B.
+        assert_eq!(state.finalize(), None);
+
+        state.window_us = Some(11);
+        assert_eq!(state.finalize(), Some("C".to_string()));
+    }
+
+    #[test]
+    fn test_window_measured_from_chain_edge() {
+        // The window applies to the gap next to the chain, not to its span.
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Forward);
+        state.base = Some(Base::Head);
+        state.num_steps = 2;
+        state.set_window(10);
+        state.update(make_event(0, "A", true, &[true, false]));
+        state.update(make_event(100, "B", false, &[false, true]));
+        state.update(make_event(105, "C", false, &[false, false]));
+        assert_eq!(state.finalize(), Some("C".to_string()));
+    }
+
+    #[test]
+    fn test_window_backward() {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Backward);
+        state.base = Some(Base::Tail);
+        state.num_steps = 1;
+        state.set_window(10);
+        state.update(make_event(0, "A", false, &[false]));
+        state.update(make_event(20, "B", true, &[true]));
+        assert_eq!(state.finalize(), None);
+
+        state.update(make_event(15, "C", false, &[false]));
+        assert_eq!(state.finalize(), Some("C".to_string()));
+    }
+
+    #[test]
+    fn test_window_first_match_moves_to_next_chain() {
+        // A chain whose next node is out of window does not count as a match.
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Forward);
+        state.base = Some(Base::FirstMatch);
+        state.num_steps = 1;
+        state.set_window(10);
+        state.update(make_event(0, "A", true, &[true]));
+        state.update(make_event(50, "B", true, &[true]));
+        state.update(make_event(55, "C", false, &[false]));
+        assert_eq!(state.finalize(), Some("C".to_string()));
+    }
+
+    // --- Histogram ---
+
+    fn histogram(state: &mut SequenceNextNodeState) -> Vec<(String, i64)> {
+        state
+            .finalize_histogram()
+            .into_iter()
+            .map(|(value, count)| (value.to_string(), count))
+            .collect()
+    }
+
+    #[test]
+    fn test_histogram_counts_every_base_match() {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Forward);
+        state.base = Some(Base::Head); // ignored
+        state.num_steps = 1;
+        for (ts, page) in ["home", "cart", "home", "search", "home", "cart", "home"]
+            .iter()
+            .enumerate()
+        {
+            state.update(make_event(
+                ts as i64,
+                page,
+                *page == "home",
+                &[*page == "home"],
+            ));
+        }
+        // The last home has no next event.
+        assert_eq!(
+            histogram(&mut state),
+            vec![("cart".to_string(), 2), ("search".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_histogram_backward_and_window() {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Backward);
+        state.num_steps = 1;
+        state.update(make_event(0, "ad", false, &[false]));
+        state.update(make_event(10, "buy", true, &[true]));
+        state.update(make_event(20, "email", false, &[false]));
+        state.update(make_event(100, "buy", true, &[true]));
+        assert_eq!(
+            histogram(&mut state),
+            vec![("ad".to_string(), 1), ("email".to_string(), 1)]
+        );
+
+        state.set_window(50);
+        assert_eq!(histogram(&mut state), vec![("ad".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_histogram_skips_null_values_and_empty_state() {
+        let mut state = SequenceNextNodeState::new();
+        assert!(state.finalize_histogram().is_empty());
+
+        state.num_steps = 1;
+        state.update(make_event(0, "A", true, &[true]));
+        state.update(make_null_event(1, true, &[true]));
+        state.update(make_event(2, "B", false, &[false]));
+        assert_eq!(histogram(&mut state), vec![("B".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_window_set_once_and_propagated_by_combine() {
+        let mut source = SequenceNextNodeState::new();
+        source.set_window(10);
+        source.set_window(20);
+        assert_eq!(source.window_us, Some(10));
+
+        let mut target = SequenceNextNodeState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.window_us, Some(10));
+        assert_eq!(
+            SequenceNextNodeState::new().combine(&source).window_us,
+            Some(10)
+        );
+    }
+
+    // --- strict_increase ---
+
+    #[test]
+    fn test_parse_direction_options() {
+        let options = |direction, strict_increase, session_gap_us| DirectionOptions {
+            direction,
+            strict_increase,
+            session_gap_us,
+            missing_base: MissingBase::Null,
+        };
+        assert_eq!(
+            SequenceNextNodeState::parse_direction_options("forward"),
+            Ok(options(Direction::Forward, false, None))
+        );
+        assert_eq!(
+            SequenceNextNodeState::parse_direction_options(" Backward , STRICT_INCREASE,"),
+            Ok(options(Direction::Backward, true, None))
+        );
+        assert_eq!(
+            SequenceNextNodeState::parse_direction_options("forward, Session_Gap( 1h30m )"),
+            Ok(options(Direction::Forward, false, Some(5_400_000_000)))
+        );
+        assert_eq!(
+            SequenceNextNodeState::parse_direction_options("forward, session_gap(30)"),
+            Err(("option", "session_gap(30)"))
+        );
+        assert_eq!(
+            SequenceNextNodeState::parse_direction_options("sideways, strict_increase"),
+            Err(("direction", "sideways"))
+        );
+        assert_eq!(
+            SequenceNextNodeState::parse_direction_options("forward, strict"),
+            Err(("option", "strict"))
+        );
+    }
+
+    // --- missing_base ---
+
+    #[test]
+    fn test_parse_missing_base() {
+        let parsed = |s| {
+            SequenceNextNodeState::parse_direction_options(s).map(|options| options.missing_base)
+        };
+        assert_eq!(parsed("forward"), Ok(MissingBase::Null));
+        assert_eq!(parsed("forward, missing_base(null)"), Ok(MissingBase::Null));
+        assert_eq!(
+            parsed("backward, strict_increase, Missing_Base( FIRST )"),
+            Ok(MissingBase::First)
+        );
+        assert_eq!(
+            parsed("forward,missing_base(error)"),
+            Ok(MissingBase::Error)
+        );
+        assert_eq!(
+            parsed("forward, missing_base(last)"),
+            Err(("option", "missing_base(last)"))
+        );
+        assert_eq!(
+            parsed("forward, missing_base"),
+            Err(("option", "missing_base"))
+        );
+    }
+
+    /// A group of three events, none satisfying the base condition, arriving
+    /// out of order.
+    fn without_base(missing_base: MissingBase) -> SequenceNextNodeState {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Forward);
+        state.base = Some(Base::FirstMatch);
+        state.num_steps = 1;
+        state.missing_base = missing_base;
+        state.update(make_event(20, "Product", false, &[true]));
+        state.update(make_event(10, "Home", false, &[true]));
+        state.update(make_event(30, "Cart", false, &[false]));
+        state
+    }
+
+    #[test]
+    fn test_missing_base_modes() {
+        assert_eq!(without_base(MissingBase::Null).finalize(), None);
+        assert_eq!(
+            without_base(MissingBase::First).finalize(),
+            Some("Home".to_string())
+        );
+        assert_eq!(
+            without_base(MissingBase::First).finalize_histogram(),
+            vec![(Arc::from("Home"), 1)]
+        );
+
+        let error = without_base(MissingBase::Error);
+        assert_eq!(error.check_base(), Err(NoBaseEvent { events: 3 }));
+        assert!(without_base(MissingBase::Null).check_base().is_ok());
+        assert!(SequenceNextNodeState::new().check_base().is_ok());
+    }
+
+    #[test]
+    fn test_missing_base_ignored_with_base_event() {
+        // A base event without a match still returns NULL.
+        let mut state = without_base(MissingBase::First);
+        state.update(make_event(40, "Checkout", true, &[false]));
+        assert!(state.check_base().is_ok());
+        assert_eq!(state.clone().finalize(), None);
+
+        state.missing_base = MissingBase::Error;
+        assert!(state.check_base().is_ok());
+
+        // The earliest event's value may itself be NULL.
+        let mut null_first = without_base(MissingBase::First);
+        null_first.update(make_null_event(0, false, &[false]));
+        assert_eq!(null_first.clone().finalize(), None);
+        assert!(null_first.finalize_histogram().is_empty());
+    }
+
+    #[test]
+    fn test_missing_base_propagated_by_combine() {
+        let source = without_base(MissingBase::Error);
+        let mut target = SequenceNextNodeState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.missing_base, MissingBase::Error);
+        assert_eq!(
+            SequenceNextNodeState::new().combine(&source).missing_base,
+            MissingBase::Error
+        );
+    }
+
+    /// Events batched in pairs: A and B share timestamp 10, C and D share 20.
+    fn batched(strict_increase: bool, direction: Direction) -> SequenceNextNodeState {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(direction);
+        state.base = Some(Base::FirstMatch);
+        state.num_steps = 2;
+        state.strict_increase = strict_increase;
+        state.update(make_event(10, "A", true, &[true, false]));
+        state.update(make_event(10, "B", true, &[false, true]));
+        state.update(make_event(20, "C", true, &[false, true]));
+        state.update(make_event(20, "D", false, &[false, false]));
+        state.update(make_event(30, "E", false, &[false, false]));
+        state
+    }
+
+    #[test]
+    fn test_strict_increase_forward() {
+        // Default: B shares A's timestamp and completes the chain; C follows.
+        assert_eq!(
+            batched(false, Direction::Forward).finalize(),
+            Some("C".to_string())
+        );
+        // Strict: B cannot follow A, so C completes the chain and D, sharing
+        // C's timestamp, is skipped for E.
+        assert_eq!(
+            batched(true, Direction::Forward).finalize(),
+            Some("E".to_string())
+        );
+    }
+
+    #[test]
+    fn test_strict_increase_backward() {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Backward);
+        state.base = Some(Base::Tail);
+        state.num_steps = 2;
+        state.update(make_event(0, "A", false, &[false, false]));
+        state.update(make_event(10, "X", false, &[false, false]));
+        state.update(make_event(10, "B", false, &[false, true]));
+        state.update(make_event(20, "C", false, &[false, true]));
+        state.update(make_event(20, "D", true, &[true, false]));
+        assert_eq!(state.clone().finalize(), Some("B".to_string()));
+
+        // Strict: C cannot precede D; B does, and X, sharing B's timestamp,
+        // is skipped for A.
+        state.strict_increase = true;
+        assert_eq!(state.finalize(), Some("A".to_string()));
+    }
+
+    #[test]
+    fn test_strict_increase_no_later_event() {
+        let mut state = batched(true, Direction::Forward);
+        state.events.pop();
+        assert_eq!(state.finalize(), None);
+    }
+
+    #[test]
+    fn test_session_gap_limits_chain_and_next_node() {
+        let mut state = SequenceNextNodeState::new();
+        state.direction = Some(Direction::Forward);
+        state.base = Some(Base::FirstMatch);
+        state.num_steps = 2;
+        state.session_gap_us = Some(10);
+        // Session 1: Home at 0, Product at 10 ends the session; the next
+        // visit starts at 30.
+        state.update(make_event(0, "Home", true, &[true, false]));
+        state.update(make_event(10, "Product", false, &[false, true]));
+        state.update(make_event(30, "Cart", false, &[false, false]));
+        assert_eq!(state.clone().finalize(), None);
+
+        // A chain split across sessions does not match either.
+        state.events[1] = make_event(21, "Product", false, &[false, true]);
+        assert_eq!(state.clone().finalize(), None);
+
+        state.session_gap_us = None;
+        assert_eq!(state.clone().finalize(), Some("Cart".to_string()));
+
+        // Backward: the previous node must be in the chain's session too.
+        let mut backward = SequenceNextNodeState::new();
+        backward.direction = Some(Direction::Backward);
+        backward.base = Some(Base::Tail);
+        backward.num_steps = 1;
+        backward.session_gap_us = Some(10);
+        backward.update(make_event(0, "Search", false, &[false]));
+        backward.update(make_event(15, "Home", true, &[true]));
+        assert_eq!(backward.clone().finalize(), None);
+        backward.update(make_event(5, "Ad", false, &[false]));
+        assert_eq!(backward.finalize(), Some("Ad".to_string()));
+    }
+
+    #[test]
+    fn test_strict_increase_propagated_by_combine() {
+        let source = batched(true, Direction::Forward);
+        let mut target = SequenceNextNodeState::new();
+        target.combine_in_place(&source);
+        assert!(target.strict_increase);
+        assert!(
+            SequenceNextNodeState::new()
+                .combine(&source)
+                .strict_increase
+        );
+    }
 }
 
 #[cfg(test)]
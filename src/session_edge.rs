@@ -0,0 +1,310 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `first_event_after_session_start` / `last_event_before_session_end` —
+//! Session-edge values as window functions.
+//!
+//! Both functions take `(timestamp, value, gap)` and split the partition into
+//! sessions exactly like [`sessionize`](crate::sessionize). They return the
+//! `value` of the first (respectively last) event of the current row's
+//! session, e.g. the landing page or exit page of every row's session.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id, event_time,
+//!   first_event_after_session_start(event_time, page, INTERVAL '30 minutes') OVER (
+//!     PARTITION BY user_id ORDER BY event_time
+//!   ) as landing_page,
+//!   last_event_before_session_end(event_time, page, INTERVAL '30 minutes') OVER (
+//!     PARTITION BY user_id ORDER BY event_time
+//!     ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING
+//!   ) as exit_page
+//! FROM events
+//! ```
+//!
+//! `first_event_after_session_start` reads the frame up to the current row,
+//! so it uses the default frame. `last_event_before_session_end` reads the
+//! frame from the current row onwards and needs
+//! `ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING`.
+//!
+//! # Implementation
+//!
+//! [`SessionEdgeState`] wraps a [`SessionizeBoundaryState`] and adds value
+//! slots for the edges of a segment: the first and last values, the first
+//! value of its last session and the last value of its first session.
+//! Combining two adjacent segments stays O(1): if the right segment contains
+//! a boundary, or the gap between the segments opens one, the current session
+//! of the combined segment starts in the right segment; otherwise it
+//! continues from the left. The first session is resolved symmetrically, so
+//! the function evaluates through `DuckDB`'s segment tree in `O(n log n)`.
+//!
+//! Rows with a `NULL` timestamp are ignored. A `NULL` value is kept and
+//! returned like any other value.
+
+use crate::common::timestamp::elapsed_exceeds;
+use crate::sessionize::SessionizeBoundaryState;
+use std::sync::Arc;
+
+/// State for the session-edge window functions.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SessionEdgeState {
+    /// Session boundaries over the timestamps of this segment.
+    pub sessions: SessionizeBoundaryState,
+    /// Value of the earliest event in this segment.
+    first_value: Option<Arc<str>>,
+    /// Value of the latest event in this segment.
+    last_value: Option<Arc<str>>,
+    /// Value of the first event of the last session in this segment.
+    last_session_first: Option<Arc<str>>,
+    /// Value of the last event of the first session in this segment.
+    first_session_last: Option<Arc<str>>,
+}
+
+impl SessionEdgeState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sessions: SessionizeBoundaryState::new(),
+            first_value: None,
+            last_value: None,
+            last_session_first: None,
+            first_session_last: None,
+        }
+    }
+
+    /// Sets the gap threshold (called on the first update).
+    pub const fn set_threshold(&mut self, threshold_us: i64) {
+        self.sessions.threshold_us = threshold_us;
+    }
+
+    /// Returns true if no event has been added to this segment.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.sessions.first_ts.is_none()
+    }
+
+    /// Adds the next event of the segment. Events must arrive in timestamp order.
+    pub fn update(&mut self, timestamp_us: i64, value: Option<Arc<str>>) {
+        match self.sessions.last_ts {
+            None => {
+                self.first_value.clone_from(&value);
+                self.last_session_first.clone_from(&value);
+                self.first_session_last.clone_from(&value);
+            }
+            Some(prev) => {
+                if elapsed_exceeds(prev, timestamp_us, self.sessions.threshold_us) {
+                    self.last_session_first.clone_from(&value);
+                } else if self.sessions.boundaries == 0 {
+                    self.first_session_last.clone_from(&value);
+                }
+            }
+        }
+        self.last_value = value;
+        self.sessions.update(timestamp_us);
+    }
+
+    /// Combines two states representing adjacent ordered segments, `self`
+    /// preceding `other`.
+    #[must_use]
+    pub fn combine(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            return other.clone();
+        }
+        if other.is_empty() {
+            return self.clone();
+        }
+        let cross_boundary = match (self.sessions.last_ts, other.sessions.first_ts) {
+            (Some(last), Some(first)) => elapsed_exceeds(last, first, self.sessions.threshold_us),
+            _ => false,
+        };
+        let last_session_first = if other.sessions.boundaries > 0 {
+            &other.last_session_first
+        } else if cross_boundary {
+            &other.first_value
+        } else {
+            &self.last_session_first
+        };
+        let first_session_last = if self.sessions.boundaries > 0 {
+            &self.first_session_last
+        } else if cross_boundary {
+            &self.last_value
+        } else {
+            &other.first_session_last
+        };
+        Self {
+            sessions: self.sessions.combine(&other.sessions),
+            first_value: self.first_value.clone(),
+            last_value: other.last_value.clone(),
+            last_session_first: last_session_first.clone(),
+            first_session_last: first_session_last.clone(),
+        }
+    }
+
+    /// Combines `other` (the following segment) into `self`.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        *self = self.combine(other);
+    }
+
+    /// Value of the first event of the last session, i.e. the start of the
+    /// current row's session when the frame ends at the current row.
+    #[must_use]
+    pub fn session_start_value(&self) -> Option<&str> {
+        self.last_session_first.as_deref()
+    }
+
+    /// Value of the last event of the first session, i.e. the end of the
+    /// current row's session when the frame starts at the current row.
+    #[must_use]
+    pub fn session_end_value(&self) -> Option<&str> {
+        self.first_session_last.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: i64 = 60_000_000;
+
+    fn state(events: &[(i64, &str)]) -> SessionEdgeState {
+        let mut s = SessionEdgeState::new();
+        s.set_threshold(30 * MIN);
+        for &(ts, value) in events {
+            s.update(ts * MIN, Some(Arc::from(value)));
+        }
+        s
+    }
+
+    const EVENTS: [(i64, &str); 6] = [
+        (0, "home"),
+        (10, "search"),
+        (20, "cart"),
+        (90, "home"),
+        (100, "checkout"),
+        (200, "blog"),
+    ];
+
+    #[test]
+    fn test_empty_state() {
+        let s = SessionEdgeState::new();
+        assert!(s.is_empty());
+        assert_eq!(s.session_start_value(), None);
+        assert_eq!(s.session_end_value(), None);
+    }
+
+    #[test]
+    fn test_single_session() {
+        let s = state(&EVENTS[..3]);
+        assert_eq!(s.session_start_value(), Some("home"));
+        assert_eq!(s.session_end_value(), Some("cart"));
+    }
+
+    #[test]
+    fn test_running_session_start() {
+        let expected = ["home", "home", "home", "home", "home", "blog"];
+        for (i, want) in expected.iter().enumerate() {
+            let s = state(&EVENTS[..=i]);
+            assert_eq!(s.session_start_value(), Some(*want), "row {i}");
+        }
+        // Row 4 (checkout) belongs to the session that started at row 3.
+        let s = state(&EVENTS[..5]);
+        assert_eq!(s.sessions.finalize(), 2);
+    }
+
+    #[test]
+    fn test_running_session_end() {
+        let expected = ["cart", "cart", "cart", "checkout", "checkout", "blog"];
+        for (i, want) in expected.iter().enumerate() {
+            let s = state(&EVENTS[i..]);
+            assert_eq!(s.session_end_value(), Some(*want), "row {i}");
+        }
+    }
+
+    #[test]
+    fn test_gap_exactly_at_threshold_continues_session() {
+        let s = state(&[(0, "a"), (30, "b"), (60, "c")]);
+        assert_eq!(s.session_start_value(), Some("a"));
+        assert_eq!(s.session_end_value(), Some("c"));
+    }
+
+    #[test]
+    fn test_infinite_timestamps_split_sessions() {
+        let events = [(i64::MIN, "a"), (0, "b"), (i64::MAX, "c")];
+        let mut s = SessionEdgeState::new();
+        s.set_threshold(30 * MIN);
+        for &(ts, value) in &events {
+            s.update(ts, Some(Arc::from(value)));
+        }
+        assert_eq!(s.sessions.finalize(), 3);
+        assert_eq!(s.session_start_value(), Some("c"));
+
+        let mut first = SessionEdgeState::new();
+        first.set_threshold(30 * MIN);
+        first.update(i64::MIN, Some(Arc::from("a")));
+        let mut last = SessionEdgeState::new();
+        last.set_threshold(30 * MIN);
+        last.update(i64::MAX, Some(Arc::from("c")));
+        let combined = first.combine(&last);
+        assert_eq!(combined.sessions.finalize(), 2);
+        assert_eq!(combined.session_start_value(), Some("c"));
+        assert_eq!(combined.session_end_value(), Some("a"));
+    }
+
+    #[test]
+    fn test_null_value_is_returned() {
+        let mut s = SessionEdgeState::new();
+        s.set_threshold(30 * MIN);
+        s.update(0, None);
+        s.update(10 * MIN, Some(Arc::from("b")));
+        assert!(!s.is_empty());
+        assert_eq!(s.session_start_value(), None);
+        assert_eq!(s.session_end_value(), Some("b"));
+    }
+
+    #[test]
+    fn test_combine_matches_sequential_for_every_split() {
+        let whole = state(&EVENTS);
+        for split in 0..=EVENTS.len() {
+            let combined = state(&EVENTS[..split]).combine(&state(&EVENTS[split..]));
+            assert_eq!(combined.session_start_value(), whole.session_start_value());
+            assert_eq!(combined.session_end_value(), whole.session_end_value());
+            assert_eq!(combined.sessions.finalize(), whole.sessions.finalize());
+        }
+    }
+
+    #[test]
+    fn test_combine_cross_boundary() {
+        let left = state(&EVENTS[..3]);
+        let right = state(&EVENTS[3..5]);
+        let combined = left.combine(&right);
+        assert_eq!(combined.session_start_value(), Some("home"));
+        assert_eq!(combined.session_end_value(), Some("cart"));
+        assert_eq!(combined.sessions.finalize(), 2);
+    }
+
+    #[test]
+    fn test_combine_is_associative() {
+        let (a, b, c) = (
+            state(&EVENTS[..2]),
+            state(&EVENTS[2..4]),
+            state(&EVENTS[4..]),
+        );
+        let left = a.combine(&b).combine(&c);
+        let right = a.combine(&b.combine(&c));
+        assert_eq!(left.session_start_value(), right.session_start_value());
+        assert_eq!(left.session_end_value(), right.session_end_value());
+        assert_eq!(left.session_start_value(), Some("blog"));
+        assert_eq!(left.session_end_value(), Some("cart"));
+    }
+
+    #[test]
+    fn test_combine_zero_target_propagates_threshold() {
+        let mut target = SessionEdgeState::new();
+        target.combine_in_place(&state(&EVENTS));
+        assert_eq!(target.sessions.threshold_us, 30 * MIN);
+        assert_eq!(target.session_start_value(), Some("blog"));
+    }
+}
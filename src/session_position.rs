@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `session_number_of_event` / `events_in_session` — Position of a row within
+//! its session as window functions.
+//!
+//! Both functions take `(timestamp, gap)` and split the partition into
+//! sessions exactly like [`sessionize`](crate::sessionize).
+//! `session_number_of_event` returns the 1-based ordinal of the current row
+//! within its session; `events_in_session` returns the number of events from
+//! the current row to the end of its session, the current row included.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id, event_time,
+//!   session_number_of_event(event_time, INTERVAL '30 minutes') OVER (
+//!     PARTITION BY user_id ORDER BY event_time
+//!   ) as event_number,
+//!   events_in_session(event_time, INTERVAL '30 minutes') OVER (
+//!     PARTITION BY user_id ORDER BY event_time
+//!     ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING
+//!   ) as events_left
+//! FROM events
+//! ```
+//!
+//! Like the [`session_edge`](crate::session_edge) functions, each reads one
+//! side of the current row: `session_number_of_event` the default frame,
+//! `events_in_session` the frame `ROWS BETWEEN CURRENT ROW AND UNBOUNDED
+//! FOLLOWING`. A window aggregate only sees its frame, not which row of it is
+//! current, so no single frame yields the size of the current row's session;
+//! it is `event_number + events_left - 1`.
+//!
+//! # Implementation
+//!
+//! [`SessionPositionState`] wraps a [`SessionizeBoundaryState`] and counts
+//! the events of the segment, of its first session and of its last session.
+//! Combining two adjacent segments stays O(1), resolved like the value slots
+//! of [`SessionEdgeState`](crate::session_edge::SessionEdgeState): the last
+//! session of the combined segment extends into the left segment only when
+//! the right segment has no boundary and the gap between them opens none.
+//!
+//! Rows with a `NULL` timestamp are ignored.
+
+use crate::common::timestamp::elapsed_exceeds;
+use crate::sessionize::SessionizeBoundaryState;
+
+/// State for the session-position window functions.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SessionPositionState {
+    /// Session boundaries over the timestamps of this segment.
+    pub sessions: SessionizeBoundaryState,
+    /// Events in this segment.
+    events: i64,
+    /// Events of the first session in this segment.
+    first_session_events: i64,
+    /// Events of the last session in this segment.
+    last_session_events: i64,
+}
+
+impl SessionPositionState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sessions: SessionizeBoundaryState::new(),
+            events: 0,
+            first_session_events: 0,
+            last_session_events: 0,
+        }
+    }
+
+    /// Sets the gap threshold (called on the first update).
+    pub const fn set_threshold(&mut self, threshold_us: i64) {
+        self.sessions.threshold_us = threshold_us;
+    }
+
+    /// Returns true if no event has been added to this segment.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.events == 0
+    }
+
+    /// Adds the next event of the segment. Events must arrive in timestamp order.
+    pub fn update(&mut self, timestamp_us: i64) {
+        let starts_session = self
+            .sessions
+            .last_ts
+            .is_none_or(|prev| elapsed_exceeds(prev, timestamp_us, self.sessions.threshold_us));
+        if starts_session {
+            self.last_session_events = 1;
+        } else {
+            self.last_session_events += 1;
+        }
+        if self.sessions.boundaries == 0 && (self.events == 0 || !starts_session) {
+            self.first_session_events += 1;
+        }
+        self.events += 1;
+        self.sessions.update(timestamp_us);
+    }
+
+    /// Combines two states representing adjacent ordered segments, `self`
+    /// preceding `other`.
+    #[must_use]
+    pub fn combine(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            return other.clone();
+        }
+        if other.is_empty() {
+            return self.clone();
+        }
+        let cross_boundary = match (self.sessions.last_ts, other.sessions.first_ts) {
+            (Some(last), Some(first)) => elapsed_exceeds(last, first, self.sessions.threshold_us),
+            _ => false,
+        };
+        let last_session_events = if other.sessions.boundaries > 0 || cross_boundary {
+            other.last_session_events
+        } else {
+            self.last_session_events + other.events
+        };
+        let first_session_events = if self.sessions.boundaries > 0 || cross_boundary {
+            self.first_session_events
+        } else {
+            self.events + other.first_session_events
+        };
+        Self {
+            sessions: self.sessions.combine(&other.sessions),
+            events: self.events + other.events,
+            first_session_events,
+            last_session_events,
+        }
+    }
+
+    /// Combines `other` (the following segment) into `self`.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        *self = self.combine(other);
+    }
+
+    /// Events of the last session, i.e. the ordinal of the current row in
+    /// its session when the frame ends at the current row. `None` without
+    /// events.
+    #[must_use]
+    pub const fn event_number(&self) -> Option<i64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.last_session_events)
+        }
+    }
+
+    /// Events of the first session, i.e. the events from the current row to
+    /// the end of its session when the frame starts at the current row.
+    /// `None` without events.
+    #[must_use]
+    pub const fn events_to_session_end(&self) -> Option<i64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.first_session_events)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: i64 = 60_000_000;
+
+    fn state(minutes: &[i64]) -> SessionPositionState {
+        let mut s = SessionPositionState::new();
+        s.set_threshold(30 * MIN);
+        for &ts in minutes {
+            s.update(ts * MIN);
+        }
+        s
+    }
+
+    /// Sessions of 3, 2 and 1 events.
+    const EVENTS: [i64; 6] = [0, 10, 20, 90, 100, 200];
+
+    #[test]
+    fn test_empty_state() {
+        let s = SessionPositionState::new();
+        assert!(s.is_empty());
+        assert_eq!(s.event_number(), None);
+        assert_eq!(s.events_to_session_end(), None);
+    }
+
+    #[test]
+    fn test_running_event_number() {
+        let expected = [1, 2, 3, 1, 2, 1];
+        for (i, want) in expected.iter().enumerate() {
+            let s = state(&EVENTS[..=i]);
+            assert_eq!(s.event_number(), Some(*want), "row {i}");
+        }
+    }
+
+    #[test]
+    fn test_running_events_to_session_end() {
+        let expected = [3, 2, 1, 2, 1, 1];
+        for (i, want) in expected.iter().enumerate() {
+            let s = state(&EVENTS[i..]);
+            assert_eq!(s.events_to_session_end(), Some(*want), "row {i}");
+        }
+    }
+
+    #[test]
+    fn test_gap_exactly_at_threshold_continues_session() {
+        let s = state(&[0, 30, 60]);
+        assert_eq!(s.event_number(), Some(3));
+        assert_eq!(s.events_to_session_end(), Some(3));
+    }
+
+    #[test]
+    fn test_combine_matches_sequential_for_every_split() {
+        for len in 0..=EVENTS.len() {
+            for start in 0..=len {
+                let events = &EVENTS[start..len];
+                let whole = state(events);
+                for split in 0..=events.len() {
+                    let combined = state(&events[..split]).combine(&state(&events[split..]));
+                    assert_eq!(combined.event_number(), whole.event_number());
+                    assert_eq!(
+                        combined.events_to_session_end(),
+                        whole.events_to_session_end()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_combine_is_associative() {
+        let (a, b, c) = (
+            state(&EVENTS[..2]),
+            state(&EVENTS[2..4]),
+            state(&EVENTS[4..]),
+        );
+        let left = a.combine(&b).combine(&c);
+        let right = a.combine(&b.combine(&c));
+        assert_eq!(left.event_number(), right.event_number());
+        assert_eq!(left.events_to_session_end(), right.events_to_session_end());
+        assert_eq!(left.event_number(), Some(1));
+        assert_eq!(left.events_to_session_end(), Some(3));
+    }
+
+    #[test]
+    fn test_combine_zero_target_propagates_threshold() {
+        let mut target = SessionPositionState::new();
+        target.combine_in_place(&state(&EVENTS[..5]));
+        assert_eq!(target.sessions.threshold_us, 30 * MIN);
+        assert_eq!(target.event_number(), Some(2));
+    }
+}
@@ -29,6 +29,80 @@
 //! The `combine` operation is O(1) — it merges two adjacent segments by checking
 //! if the gap between `left.last_ts` and `right.first_ts` exceeds the threshold.
 //! This enables O(n log n) windowed evaluation via segment trees.
+//!
+//! # Frame Requirements
+//!
+//! The session ID is the number of sessions between the start of the frame and
+//! the current row, so the frame must start at the beginning of the partition.
+//! Both `ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW` and the default
+//! `RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW` (implied by `ORDER BY`)
+//! produce correct IDs: a `RANGE` frame additionally contains the current row's
+//! peers, but peers share its timestamp and a zero gap never adds a boundary.
+//!
+//! Frames with a bounded start (`ROWS BETWEEN 10 PRECEDING ...`,
+//! `RANGE BETWEEN INTERVAL 30 MINUTES PRECEDING ...`) yield the number of
+//! sessions *within the frame*, not a session ID. The `DuckDB` C API does not
+//! expose the frame specification to aggregate callbacks, so this cannot be
+//! detected or rejected at bind time.
+//!
+//! # Condition Breaks
+//!
+//! `sessionize_by(ts, gap, boundary_cond)` also starts a new session at every
+//! row whose `boundary_cond` is true, whatever the gap — the session
+//! definitions of web analytics tools, which break on explicit events such as
+//! a login or a campaign change. The row with the true condition is the first
+//! row of its session. Within a segment a condition break is counted like a
+//! gap break; across segments, [`SessionizeBoundaryState::first_breaks`]
+//! records whether the right segment starts with one, so combine stays O(1).
+//!
+//! # Maximum Session Length
+//!
+//! `sessionize(ts, gap, max_session_length)` also starts a new session at a
+//! row that would make the current session longer than `max_session_length`,
+//! measured from the session's first row, as GA4 does. Unlike a gap, whether
+//! a row breaks on length depends on where its session started, which may be
+//! arbitrarily far to the left, so the boundary count of a segment is no
+//! longer a number but a function of the session start entering it.
+//!
+//! Gap and condition breaks ("hard" breaks) do not depend on the past, so only
+//! the rows before a segment's first hard break are affected. Of those, the
+//! first to break on length is the first row more than `max_session_length`
+//! after the incoming session start, which is never later than the first row
+//! more than `max_session_length` after the segment's first row. A segment
+//! therefore keeps, for each of those few candidate rows, the number of
+//! length breaks and the open session start that follow from a break at that
+//! row, plus the same two numbers from its first hard break to its end.
+//! Entering with a given session start is a binary search over the
+//! candidates, and combine maps the left segment's candidates through the
+//! right segment, so it costs O(k log k) for the k rows within
+//! `max_session_length` of the segment start instead of O(1).
+//!
+//! # Session Starts
+//!
+//! `sessionize_info` returns the session ID together with whether the current
+//! row starts its session, so boundaries need no `LAG` pass. The current row
+//! starts its session when the frame's last session starts at its timestamp.
+//! A segment records the timestamp of its last gap or condition break
+//! ([`SessionizeBoundaryState::last_break_ts`]), which combine takes from the
+//! right segment, from the cross-segment boundary, or from the left segment,
+//! in that order; with a maximum session length the open session start
+//! already kept by the length-break summary is used instead. Whether a row
+//! *ends* its session depends on the next row, which a frame ending at the
+//! current row does not contain.
+//!
+//! # Exported States
+//!
+//! `sessionize_state(ts, gap)` is a plain aggregate returning the boundary
+//! state of its group as a `BLOB`, for rolling up stored partial states.
+//! Aggregate input is unordered, so [`SessionizeSummaryState`] collects the
+//! timestamps and sorts them once. `sessionize_merge` combines two exported
+//! states with [`SessionizeBoundaryState::merge_ranges`], which only accepts
+//! states over disjoint time ranges (such as one per day), since the rows of
+//! overlapping ranges would interleave. `sessionize_finalize` returns the
+//! number of sessions: the session ID `sessionize` gives the last row.
+
+use crate::common::state_blob::{BlobError, BlobReader, BlobWriter};
+use crate::common::timestamp::elapsed_exceeds;
 
 /// Simple session counter — counts sessions within a single segment.
 ///
@@ -76,7 +150,7 @@ impl SessionizeState {
                 self.session_count = 1;
             }
             Some(prev) => {
-                if timestamp_us - prev > self.threshold_us {
+                if elapsed_exceeds(prev, timestamp_us, self.threshold_us) {
                     self.session_count += 1;
                 }
                 if timestamp_us > prev {
@@ -105,7 +179,7 @@ impl SessionizeState {
             (_, None) => self.clone(),
             (Some(_), Some(other_first)) => {
                 let boundary = self.last_ts.map_or(0, |self_last| {
-                    i64::from(other_first - self_last > self.threshold_us)
+                    i64::from(elapsed_exceeds(self_last, other_first, self.threshold_us))
                 });
 
                 Self {
@@ -272,6 +346,19 @@ pub struct SessionizeBoundaryState {
     /// Whether the rightmost row in this segment had a `NULL` timestamp.
     /// Used by the FFI finalize to emit `NULL` for `NULL`-timestamp rows.
     pub current_row_null: bool,
+    /// Whether the first row of this segment starts a new session by
+    /// condition (`sessionize_by`). Adds a boundary when this segment is
+    /// combined to the right of a non-empty one.
+    pub first_breaks: bool,
+    /// Maximum session length in microseconds, or `None` for no limit.
+    pub max_session_us: Option<i64>,
+    /// Timestamp of the last row after the first that starts a session by a
+    /// gap or condition break, if any. Gives the start of the last session
+    /// (`sessionize_info`) without a maximum session length.
+    pub last_break_ts: Option<i64>,
+    /// Length-break summary of the segment; empty unless `max_session_us`
+    /// is set.
+    cap: CapSummary,
 }
 
 impl SessionizeBoundaryState {
@@ -284,6 +371,10 @@ impl SessionizeBoundaryState {
             boundaries: 0,
             threshold_us: 0,
             current_row_null: false,
+            first_breaks: false,
+            max_session_us: None,
+            last_break_ts: None,
+            cap: CapSummary::new(),
         }
     }
 
@@ -300,15 +391,35 @@ impl SessionizeBoundaryState {
     /// Updates the state with a single non-`NULL` timestamp.
     #[inline]
     pub fn update(&mut self, timestamp_us: i64) {
+        self.update_with_break(timestamp_us, false);
+    }
+
+    /// Updates the state with a single non-`NULL` timestamp whose row starts
+    /// a new session if `breaks` is true, regardless of the gap.
+    #[inline]
+    pub fn update_with_break(&mut self, timestamp_us: i64, breaks: bool) {
+        if let Some(max_session_us) = self.max_session_us {
+            let mut row = Self::new();
+            row.threshold_us = self.threshold_us;
+            row.max_session_us = Some(max_session_us);
+            row.first_ts = Some(timestamp_us);
+            row.last_ts = Some(timestamp_us);
+            row.first_breaks = breaks;
+            row.cap = CapSummary::row(timestamp_us);
+            *self = self.combine(&row);
+            return;
+        }
         self.current_row_null = false;
         match self.last_ts {
             None => {
                 self.first_ts = Some(timestamp_us);
                 self.last_ts = Some(timestamp_us);
+                self.first_breaks = breaks;
             }
             Some(prev) => {
-                if timestamp_us - prev > self.threshold_us {
+                if breaks || elapsed_exceeds(prev, timestamp_us, self.threshold_us) {
                     self.boundaries += 1;
+                    self.last_break_ts = Some(timestamp_us);
                 }
                 if timestamp_us > prev {
                     self.last_ts = Some(timestamp_us);
@@ -324,7 +435,10 @@ impl SessionizeBoundaryState {
 
     /// Combines two states representing adjacent ordered segments.
     ///
-    /// O(1) operation: only checks the cross-segment boundary.
+    /// O(1) operation: only checks the cross-segment boundary, which is a
+    /// gap exceeding the threshold or a condition break on `other`'s first row.
+    /// With a maximum session length it is O(k log k) instead, see the
+    /// [module documentation](self#maximum-session-length).
     ///
     /// The `current_row_null` flag always propagates from `other` (the right/later
     /// segment), because in `ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW` the
@@ -340,17 +454,34 @@ impl SessionizeBoundaryState {
                 result.current_row_null = other.current_row_null;
                 result
             }
-            (Some(_), Some(other_first)) => {
-                let cross_boundary = self.last_ts.map_or(0, |self_last| {
-                    i64::from(other_first - self_last > self.threshold_us)
+            (Some(first), Some(other_first)) => {
+                let hard_break = self.last_ts.is_some_and(|self_last| {
+                    other.first_breaks || elapsed_exceeds(self_last, other_first, self.threshold_us)
+                });
+                let max_session_us = self.max_session_us.or(other.max_session_us);
+
+                let cap = max_session_us.map_or_else(CapSummary::new, |max| {
+                    self.cap
+                        .combine(&other.cap, first, other_first, hard_break, max)
                 });
+                let boundaries = max_session_us.map_or_else(
+                    || self.boundaries + other.boundaries + i64::from(hard_break),
+                    |max| cap.eval(first, max).0,
+                );
 
                 Self {
                     first_ts: self.first_ts,
                     last_ts: other.last_ts.or(self.last_ts),
-                    boundaries: self.boundaries + other.boundaries + cross_boundary,
+                    boundaries,
                     threshold_us: self.threshold_us,
                     current_row_null: other.current_row_null,
+                    first_breaks: self.first_breaks,
+                    max_session_us,
+                    last_break_ts: other
+                        .last_break_ts
+                        .or_else(|| hard_break.then_some(other_first))
+                        .or(self.last_break_ts),
+                    cap,
                 }
             }
         }
@@ -365,6 +496,26 @@ impl SessionizeBoundaryState {
             0
         }
     }
+
+    /// Returns the timestamp of the first row of the last session, or `None`
+    /// for empty data.
+    #[must_use]
+    pub fn session_start(&self) -> Option<i64> {
+        let first = self.first_ts?;
+        Some(self.max_session_us.map_or_else(
+            || self.last_break_ts.unwrap_or(first),
+            |max| self.cap.eval(first, max).1,
+        ))
+    }
+
+    /// Returns whether the last row starts its session: whether the last
+    /// session starts at `last_ts`. Rows sharing the timestamp of a session's
+    /// first row all start it.
+    #[must_use]
+    pub fn is_session_start(&self) -> bool {
+        self.session_start()
+            .is_some_and(|start| Some(start) == self.last_ts)
+    }
 }
 
 impl Default for SessionizeBoundaryState {
@@ -373,6 +524,268 @@ impl Default for SessionizeBoundaryState {
     }
 }
 
+/// Exported states: `sessionize_state`, `sessionize_merge` and
+/// `sessionize_finalize`.
+impl SessionizeBoundaryState {
+    /// Tag of the exported state format: `SES` and format version 1.
+    pub const BLOB_TAG: [u8; 4] = *b"SES\x01";
+
+    /// Exports the state as the bytes returned by `sessionize_state`: the
+    /// gap threshold, the time range, the boundaries and the last break.
+    /// States with a maximum session length are not exported. See
+    /// [`crate::common::state_blob`].
+    #[must_use]
+    pub fn to_blob(&self) -> Vec<u8> {
+        let mut w = BlobWriter::new(Self::BLOB_TAG);
+        w.u8(u8::from(self.first_ts.is_some()) | (u8::from(self.last_break_ts.is_some()) << 1));
+        w.i64(self.threshold_us);
+        w.i64(self.first_ts.unwrap_or(0));
+        w.i64(self.last_ts.unwrap_or(0));
+        w.i64(self.boundaries);
+        w.i64(self.last_break_ts.unwrap_or(0));
+        w.finish()
+    }
+
+    /// Imports a state exported by [`to_blob`](Self::to_blob).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BlobError`] when `bytes` is not a `sessionize` state of
+    /// this format version, or holds one that no input could produce.
+    pub fn from_blob(bytes: &[u8]) -> Result<Self, BlobError> {
+        let mut r = BlobReader::new(bytes, Self::BLOB_TAG)?;
+        let flags = r.u8()?;
+        let threshold_us = r.i64()?;
+        let (first, last, boundaries, last_break) = (r.i64()?, r.i64()?, r.i64()?, r.i64()?);
+        r.finish()?;
+
+        let mut state = Self::new();
+        state.threshold_us = threshold_us;
+        match flags {
+            0 => return Ok(state),
+            1 | 3 => {}
+            _ => return Err(BlobError::Invalid("flags")),
+        }
+        if first > last {
+            return Err(BlobError::Invalid("time range"));
+        }
+        if boundaries < 0 {
+            return Err(BlobError::Invalid("boundary count"));
+        }
+        state.first_ts = Some(first);
+        state.last_ts = Some(last);
+        state.boundaries = boundaries;
+        state.last_break_ts = (flags & 2 != 0).then_some(last_break);
+        Ok(state)
+    }
+
+    /// Merges two exported states whose time ranges do not overlap, in
+    /// either order: the earlier one is combined with the later one. An
+    /// empty state merges with any state.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message when the states have different gap thresholds or
+    /// overlapping time ranges, whose rows would interleave.
+    pub fn merge_ranges(&self, other: &Self) -> Result<Self, &'static str> {
+        let (Some(first), Some(other_first)) = (self.first_ts, other.first_ts) else {
+            return Ok(if self.first_ts.is_some() { self } else { other }.clone());
+        };
+        if self.threshold_us != other.threshold_us {
+            return Err("states with different gaps");
+        }
+        let (earlier, later) = if first <= other_first {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        if later.first_ts < earlier.last_ts {
+            return Err("states with overlapping time ranges");
+        }
+        Ok(earlier.combine(later))
+    }
+}
+
+/// State for the `sessionize_state` aggregate function.
+///
+/// An aggregate receives its rows in no particular order, while
+/// [`SessionizeBoundaryState`] needs them in timestamp order, so the
+/// timestamps are collected and sorted into a boundary state in
+/// [`summarize`](Self::summarize).
+#[derive(Debug, Clone, Default)]
+pub struct SessionizeSummaryState {
+    /// Gap threshold in microseconds; `None` until the first update.
+    pub threshold_us: Option<i64>,
+    /// Timestamps received, in input order.
+    timestamps: Vec<i64>,
+}
+
+impl SessionizeSummaryState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            threshold_us: None,
+            timestamps: Vec::new(),
+        }
+    }
+
+    /// Adds a non-`NULL` timestamp.
+    pub fn update(&mut self, timestamp_us: i64) {
+        self.timestamps.push(timestamp_us);
+    }
+
+    /// Appends the timestamps of `other`. A zero-initialized target takes
+    /// the threshold of `other`.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.threshold_us = self.threshold_us.or(other.threshold_us);
+        self.timestamps.extend_from_slice(&other.timestamps);
+    }
+
+    /// Returns the boundary state of the timestamps in order, the state
+    /// `sessionize` reaches at the group's last row.
+    #[must_use]
+    pub fn summarize(&mut self) -> SessionizeBoundaryState {
+        self.timestamps.sort_unstable();
+        let mut state = SessionizeBoundaryState::new();
+        state.threshold_us = self.threshold_us.unwrap_or(0);
+        for &ts in &self.timestamps {
+            state.update(ts);
+        }
+        state
+    }
+}
+
+/// A row that may be the first length break of a segment, with the outcome
+/// of a session starting at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CapCandidate {
+    /// Timestamp of the row.
+    ts: i64,
+    /// Length breaks from this row (counting its own) to the segment's first
+    /// hard break or end.
+    breaks: i64,
+    /// Start of the session open at the segment's first hard break or end.
+    end_start: i64,
+}
+
+/// Length-break summary of a segment under a maximum session length.
+///
+/// Functions of the incoming session start `start`, which is never after the
+/// segment's first row. See the
+/// [module documentation](self#maximum-session-length).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CapSummary {
+    /// Candidate rows before the first hard break, in timestamp order: those
+    /// at most the maximum length after the segment's first row, plus the
+    /// first row after that.
+    head: Vec<CapCandidate>,
+    /// Boundaries and open session start from the first hard break
+    /// (counting it) to the end of the segment, if there is a hard break.
+    tail: Option<(i64, i64)>,
+}
+
+impl CapSummary {
+    const fn new() -> Self {
+        Self {
+            head: Vec::new(),
+            tail: None,
+        }
+    }
+
+    /// Summary of a single row.
+    fn row(ts: i64) -> Self {
+        Self {
+            head: vec![CapCandidate {
+                ts,
+                breaks: 1,
+                end_start: ts,
+            }],
+            tail: None,
+        }
+    }
+
+    /// Length breaks and open session start at the end of the rows before
+    /// the first hard break, entering with a session started at `start`.
+    fn eval_head(&self, start: i64, max: i64) -> (i64, i64) {
+        let limit = start.saturating_add(max);
+        let first_break = self.head.partition_point(|c| c.ts <= limit);
+        self.head
+            .get(first_break)
+            .map_or((0, start), |c| (c.breaks, c.end_start))
+    }
+
+    /// Boundaries and open session start at the end of the segment, entering
+    /// with a session started at `start`.
+    fn eval(&self, start: i64, max: i64) -> (i64, i64) {
+        let (breaks, end_start) = self.eval_head(start, max);
+        match self.tail {
+            Some((tail_breaks, tail_start)) => (breaks + tail_breaks, tail_start),
+            None => (breaks, end_start),
+        }
+    }
+
+    /// Summary of `self` followed by `other`, whose first rows are at
+    /// `first` and `other_first`. `hard_break` is whether `other`'s first row
+    /// is a gap or condition break.
+    fn combine(
+        &self,
+        other: &Self,
+        first: i64,
+        other_first: i64,
+        hard_break: bool,
+        max: i64,
+    ) -> Self {
+        // Outcome of entering `other` with a session started at `start`.
+        let through_other = |start: i64| {
+            if hard_break {
+                let (breaks, end_start) = other.eval(other_first, max);
+                (breaks + 1, end_start)
+            } else {
+                other.eval(start, max)
+            }
+        };
+
+        match self.tail {
+            Some((breaks, end_start)) => {
+                let (other_breaks, other_start) = through_other(end_start);
+                Self {
+                    head: self.head.clone(),
+                    tail: Some((breaks + other_breaks, other_start)),
+                }
+            }
+            None if hard_break => Self {
+                head: self.head.clone(),
+                tail: Some(through_other(first)),
+            },
+            None => {
+                let limit = first.saturating_add(max);
+                let mut head: Vec<CapCandidate> = self
+                    .head
+                    .iter()
+                    .map(|c| {
+                        let (breaks, end_start) = other.eval_head(c.end_start, max);
+                        CapCandidate {
+                            ts: c.ts,
+                            breaks: c.breaks + breaks,
+                            end_start,
+                        }
+                    })
+                    .collect();
+                if head.last().is_none_or(|c| c.ts <= limit) {
+                    head.extend_from_slice(&other.head);
+                }
+                let keep = head.partition_point(|c| c.ts <= limit) + 1;
+                head.truncate(keep);
+                Self {
+                    head,
+                    tail: other.tail,
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod boundary_tests {
     use super::*;
@@ -409,6 +822,23 @@ mod boundary_tests {
         assert_eq!(state.finalize(), 2);
     }
 
+    #[test]
+    fn test_extreme_timestamps_do_not_wrap() {
+        // -infinity to infinity wraps to a negative gap with raw subtraction
+        let mut state = SessionizeBoundaryState::new();
+        state.threshold_us = 1_800_000_000;
+        state.update(-i64::MAX);
+        state.update(i64::MAX);
+        assert_eq!(state.finalize(), 2);
+
+        let mut left = SessionizeBoundaryState::new();
+        left.threshold_us = 1_800_000_000;
+        let mut right = left.clone();
+        left.update(-i64::MAX);
+        right.update(i64::MAX);
+        assert_eq!(left.combine(&right).finalize(), 2);
+    }
+
     #[test]
     fn test_three_sessions() {
         let mut state = SessionizeBoundaryState::new();
@@ -937,6 +1367,397 @@ mod boundary_tests {
         let state = SessionizeBoundaryState::new();
         assert!(!state.current_row_null);
     }
+
+    // --- Frame semantics ---
+    // RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW extends the ROWS frame
+    // with the current row's peers (rows sharing its timestamp).
+
+    #[test]
+    fn test_range_frame_peers_match_rows_frame() {
+        let leaf = |ts: i64| {
+            let mut s = SessionizeBoundaryState::new();
+            s.threshold_us = 1_800_000_000; // 30 min
+            s.update(ts);
+            s
+        };
+        let rows_frame = leaf(0).combine(&leaf(3_600_000_000));
+        // Two more rows at the current row's timestamp
+        let range_frame = rows_frame
+            .combine(&leaf(3_600_000_000))
+            .combine(&leaf(3_600_000_000));
+        assert_eq!(rows_frame.finalize(), 2);
+        assert_eq!(range_frame.finalize(), rows_frame.finalize());
+    }
+
+    #[test]
+    fn test_bounded_frame_counts_sessions_within_frame() {
+        // A frame starting after the first session boundary cannot see the
+        // earlier session, so the result is not the session ID.
+        let leaf = |ts: i64| {
+            let mut s = SessionizeBoundaryState::new();
+            s.threshold_us = 1_800_000_000; // 30 min
+            s.update(ts);
+            s
+        };
+        let full = leaf(0)
+            .combine(&leaf(3_600_000_000))
+            .combine(&leaf(3_700_000_000));
+        let bounded = leaf(3_600_000_000).combine(&leaf(3_700_000_000));
+        assert_eq!(full.finalize(), 2);
+        assert_eq!(bounded.finalize(), 1);
+    }
+
+    // --- Condition breaks (sessionize_by) ---
+
+    #[test]
+    fn test_condition_break_starts_new_session() {
+        let mut state = SessionizeBoundaryState::new();
+        state.threshold_us = 1_800_000_000; // 30 min
+        state.update_with_break(0, false);
+        state.update_with_break(60_000_000, true); // 1 min gap, login
+        assert_eq!(state.finalize(), 2);
+        state.update_with_break(120_000_000, false);
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_condition_break_and_gap_count_once() {
+        let mut state = SessionizeBoundaryState::new();
+        state.threshold_us = 60_000_000; // 1 min
+        state.update_with_break(0, false);
+        state.update_with_break(3_600_000_000, true);
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_condition_break_on_first_row_is_not_a_boundary() {
+        let mut state = SessionizeBoundaryState::new();
+        state.threshold_us = 60_000_000;
+        state.update_with_break(0, true);
+        assert_eq!(state.finalize(), 1);
+        assert!(state.first_breaks);
+    }
+
+    #[test]
+    fn test_combine_condition_break_at_segment_start() {
+        let leaf = |ts: i64, breaks: bool| {
+            let mut s = SessionizeBoundaryState::new();
+            s.threshold_us = 1_800_000_000; // 30 min
+            s.update_with_break(ts, breaks);
+            s
+        };
+        let combined = leaf(0, false)
+            .combine(&leaf(60_000_000, true))
+            .combine(&leaf(120_000_000, false));
+        assert_eq!(combined.finalize(), 2);
+        assert!(!combined.first_breaks);
+
+        // A break on the first row of the frame adds nothing.
+        let frame = leaf(0, true).combine(&leaf(60_000_000, false));
+        assert_eq!(frame.finalize(), 1);
+        assert!(frame.first_breaks);
+    }
+
+    #[test]
+    fn test_combine_condition_break_zero_target() {
+        let mut source = SessionizeBoundaryState::new();
+        source.threshold_us = 1_800_000_000;
+        source.update_with_break(60_000_000, true);
+        let target = SessionizeBoundaryState::new().combine(&source);
+        assert!(target.first_breaks);
+
+        let mut left = SessionizeBoundaryState::new();
+        left.threshold_us = 1_800_000_000;
+        left.update(0);
+        assert_eq!(left.combine(&target).finalize(), 2);
+    }
+
+    // --- Maximum session length ---
+
+    /// Session count of `events` computed row by row: a row starts a session
+    /// on a condition break, a gap over `gap`, or more than `max` after the
+    /// session's first row.
+    fn capped_reference(events: &[(i64, bool)], gap: i64, max: i64) -> i64 {
+        let mut sessions = 0;
+        let mut start = 0;
+        let mut prev: Option<i64> = None;
+        for &(ts, breaks) in events {
+            if prev.is_none_or(|p| breaks || ts - p > gap || ts - start > max) {
+                sessions += 1;
+                start = ts;
+            }
+            prev = Some(ts);
+        }
+        sessions
+    }
+
+    fn capped(events: &[(i64, bool)], gap: i64, max: i64) -> SessionizeBoundaryState {
+        let mut s = SessionizeBoundaryState::new();
+        s.threshold_us = gap;
+        s.max_session_us = Some(max);
+        for &(ts, breaks) in events {
+            s.update_with_break(ts, breaks);
+        }
+        s
+    }
+
+    /// Combines single-row leaves in halves, like a segment tree.
+    fn capped_tree(events: &[(i64, bool)], gap: i64, max: i64) -> SessionizeBoundaryState {
+        if events.len() <= 1 {
+            return capped(events, gap, max);
+        }
+        let (left, right) = events.split_at(events.len() / 2);
+        capped_tree(left, gap, max).combine(&capped_tree(right, gap, max))
+    }
+
+    /// Sorted pseudo-random events: gaps of 0 to 49 and a condition break on
+    /// roughly one row in eight.
+    fn capped_events(seed: u64, len: usize) -> Vec<(i64, bool)> {
+        let mut x = seed;
+        let mut ts = 0;
+        (0..len)
+            .map(|_| {
+                x = x
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                ts += ((x >> 33) % 50) as i64;
+                (ts, x >> 61 == 0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_max_session_length_breaks_long_session() {
+        // A row every 10 minutes, 30 minute gap, 1 hour maximum length
+        let events: Vec<(i64, bool)> = (0..=12).map(|i| (i * 600_000_000, false)).collect();
+        let (gap, max) = (1_800_000_000, 3_600_000_000);
+        // 0:00 to 1:00 is exactly the maximum; 1:10 starts session 2
+        assert_eq!(capped(&events[..7], gap, max).finalize(), 1);
+        assert_eq!(capped(&events[..8], gap, max).finalize(), 2);
+        assert_eq!(capped(&events, gap, max).finalize(), 2);
+        assert_eq!(capped_tree(&events, gap, max).finalize(), 2);
+    }
+
+    #[test]
+    fn test_max_session_length_restarts_after_gap() {
+        let events = [
+            (0, false),
+            (50, false),
+            (200, false),
+            (240, false),
+            (260, false),
+        ];
+        // Gap break at 200 restarts the length; 260 is more than 50 after it
+        let s = capped(&events, 100, 50);
+        assert_eq!(s.finalize(), 3);
+        assert_eq!(s.finalize(), capped_reference(&events, 100, 50));
+    }
+
+    #[test]
+    fn test_max_session_length_combine_matches_reference() {
+        for seed in 0..16 {
+            let events = capped_events(seed, 60);
+            for (gap, max) in [(30, 60), (45, 100), (49, 20), (10, 0)] {
+                for end in 1..=events.len() {
+                    let prefix = &events[..end];
+                    let expected = capped_reference(prefix, gap, max);
+                    assert_eq!(capped(prefix, gap, max).finalize(), expected);
+                    assert_eq!(capped_tree(prefix, gap, max).finalize(), expected);
+                }
+                let expected = capped_reference(&events, gap, max);
+                for split in 0..=events.len() {
+                    let (left, right) = events.split_at(split);
+                    let combined = capped(left, gap, max).combine(&capped(right, gap, max));
+                    assert_eq!(combined.finalize(), expected, "seed {seed} split {split}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_session_length_zero_target_propagates() {
+        let events = [(0, false), (40, false), (80, false)];
+        let source = capped(&events, 100, 50);
+        let target = SessionizeBoundaryState::new().combine(&source);
+        assert_eq!(target.max_session_us, Some(50));
+        assert_eq!(target.finalize(), 2);
+        let extended = target.combine(&capped(&[(120, false)], 100, 50));
+        assert_eq!(extended.finalize(), 2);
+        assert_eq!(
+            extended
+                .combine(&capped(&[(140, false)], 100, 50))
+                .finalize(),
+            3
+        );
+    }
+
+    // --- Session starts ---
+
+    /// Segment tree over single-row leaves of `events`, with an optional
+    /// maximum session length.
+    fn leaf_tree(events: &[(i64, bool)], gap: i64, max: Option<i64>) -> SessionizeBoundaryState {
+        if events.len() <= 1 {
+            let mut s = SessionizeBoundaryState::new();
+            s.threshold_us = gap;
+            s.max_session_us = max;
+            for &(ts, breaks) in events {
+                s.update_with_break(ts, breaks);
+            }
+            return s;
+        }
+        let (left, right) = events.split_at(events.len() / 2);
+        leaf_tree(left, gap, max).combine(&leaf_tree(right, gap, max))
+    }
+
+    #[test]
+    fn test_session_start_tracks_last_session() {
+        let mut s = SessionizeBoundaryState::new();
+        s.threshold_us = 100;
+        assert_eq!(s.session_start(), None);
+        assert!(!s.is_session_start());
+        s.update(10);
+        assert!(s.is_session_start());
+        s.update(50);
+        assert_eq!(s.session_start(), Some(10));
+        assert!(!s.is_session_start());
+        s.update(200); // gap break
+        assert!(s.is_session_start());
+        s.update_with_break(210, true);
+        assert_eq!(s.session_start(), Some(210));
+        s.update(220);
+        assert_eq!(s.session_start(), Some(210));
+        assert!(!s.is_session_start());
+    }
+
+    #[test]
+    fn test_session_start_combine_matches_row_by_row() {
+        for seed in 0..16 {
+            // Strictly increasing timestamps, so a row starts a session
+            // exactly when the session ID changes.
+            let events: Vec<(i64, bool)> = capped_events(seed, 40)
+                .into_iter()
+                .enumerate()
+                .map(|(i, (ts, breaks))| (ts + i as i64, breaks))
+                .collect();
+            for max in [None, Some(60), Some(0)] {
+                for end in 1..=events.len() {
+                    let frame = leaf_tree(&events[..end], 30, max);
+                    let previous = leaf_tree(&events[..end - 1], 30, max);
+                    assert_eq!(
+                        frame.is_session_start(),
+                        frame.finalize() != previous.finalize(),
+                        "seed {seed} max {max:?} end {end}"
+                    );
+                }
+            }
+        }
+    }
+
+    // --- Exported states ---
+
+    const MIN: i64 = 60_000_000;
+
+    fn summary(gap: i64, timestamps: &[i64]) -> SessionizeBoundaryState {
+        let mut state = SessionizeSummaryState::new();
+        state.threshold_us = Some(gap);
+        for &ts in timestamps {
+            state.update(ts);
+        }
+        state.summarize()
+    }
+
+    #[test]
+    fn test_summary_ignores_input_order() {
+        let sorted = summary(30 * MIN, &[0, 10 * MIN, 60 * MIN, 70 * MIN]);
+        let shuffled = summary(30 * MIN, &[70 * MIN, 0, 60 * MIN, 10 * MIN]);
+        assert_eq!(sorted.finalize(), 2);
+        assert_eq!(shuffled.finalize(), 2);
+        assert_eq!(shuffled.last_break_ts, Some(60 * MIN));
+    }
+
+    #[test]
+    fn test_summary_combine_zero_target_propagates_threshold() {
+        let mut source = SessionizeSummaryState::new();
+        source.threshold_us = Some(30 * MIN);
+        source.update(0);
+        source.update(60 * MIN);
+        let mut target = SessionizeSummaryState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.threshold_us, Some(30 * MIN));
+        assert_eq!(target.summarize().finalize(), 2);
+    }
+
+    #[test]
+    fn test_blob_round_trip() {
+        let state = summary(30 * MIN, &[0, 60 * MIN, 70 * MIN]);
+        let restored = SessionizeBoundaryState::from_blob(&state.to_blob()).unwrap();
+        assert_eq!(restored.first_ts, Some(0));
+        assert_eq!(restored.last_ts, Some(70 * MIN));
+        assert_eq!(restored.threshold_us, 30 * MIN);
+        assert_eq!(restored.last_break_ts, Some(60 * MIN));
+        assert_eq!(restored.finalize(), 2);
+
+        let empty = SessionizeBoundaryState::new();
+        let restored = SessionizeBoundaryState::from_blob(&empty.to_blob()).unwrap();
+        assert_eq!(restored.finalize(), 0);
+    }
+
+    #[test]
+    fn test_merge_ranges_matches_whole_range_in_either_order() {
+        let events = [0, 10 * MIN, 35 * MIN, 100 * MIN, 110 * MIN];
+        let whole = summary(30 * MIN, &events);
+        for split in 0..=events.len() {
+            let left = summary(30 * MIN, &events[..split]);
+            let right = summary(30 * MIN, &events[split..]);
+            for merged in [left.merge_ranges(&right), right.merge_ranges(&left)] {
+                let merged = merged.unwrap();
+                assert_eq!(merged.finalize(), whole.finalize(), "split {split}");
+                assert_eq!(merged.session_start(), whole.session_start());
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_ranges_session_spans_the_split() {
+        // Two days whose sessions join across midnight
+        let day1 = summary(30 * MIN, &[0, 1430 * MIN]);
+        let day2 = summary(30 * MIN, &[1450 * MIN, 1500 * MIN]);
+        assert_eq!(day1.merge_ranges(&day2).unwrap().finalize(), 3);
+    }
+
+    #[test]
+    fn test_merge_ranges_rejects_overlaps_and_different_gaps() {
+        let a = summary(30 * MIN, &[0, 60 * MIN]);
+        let b = summary(30 * MIN, &[30 * MIN, 90 * MIN]);
+        assert!(a.merge_ranges(&b).is_err());
+        let c = summary(10 * MIN, &[120 * MIN]);
+        assert!(a.merge_ranges(&c).is_err());
+        // Touching ranges are not overlapping
+        let d = summary(30 * MIN, &[60 * MIN, 200 * MIN]);
+        assert_eq!(a.merge_ranges(&d).unwrap().finalize(), 3);
+    }
+
+    #[test]
+    fn test_blob_rejects_malformed_states() {
+        let state = summary(30 * MIN, &[0, 60 * MIN]);
+        let bytes = state.to_blob();
+        let mut bad_flags = bytes.clone();
+        bad_flags[4] = 4;
+        assert_eq!(
+            SessionizeBoundaryState::from_blob(&bad_flags).unwrap_err(),
+            BlobError::Invalid("flags")
+        );
+        let mut reversed = bytes.clone();
+        reversed[13..21].copy_from_slice(&i64::MAX.to_le_bytes());
+        assert_eq!(
+            SessionizeBoundaryState::from_blob(&reversed).unwrap_err(),
+            BlobError::Invalid("time range")
+        );
+        assert_eq!(
+            SessionizeBoundaryState::from_blob(&bytes[..20]).unwrap_err(),
+            BlobError::Truncated
+        );
+    }
 }
 
 #[cfg(test)]
@@ -968,6 +1789,29 @@ mod proptests {
             prop_assert_eq!(ab_c.finalize(), a_bc.finalize());
         }
 
+        #[test]
+        fn combine_with_breaks_matches_sequential(
+            events in proptest::collection::vec((0i64..10_000, any::<bool>()), 1..40),
+            split in 0usize..40,
+            threshold in 0i64..5_000,
+        ) {
+            let mut sorted = events;
+            sorted.sort_by_key(|&(ts, _)| ts);
+            let split = split.min(sorted.len());
+            let fold = |part: &[(i64, bool)]| {
+                let mut s = SessionizeBoundaryState::new();
+                s.threshold_us = threshold;
+                for &(ts, breaks) in part {
+                    s.update_with_break(ts, breaks);
+                }
+                s
+            };
+            let whole = fold(&sorted);
+            let halves = fold(&sorted[..split]).combine(&fold(&sorted[split..]));
+            prop_assert_eq!(halves.finalize(), whole.finalize());
+            prop_assert_eq!(halves.first_breaks, whole.first_breaks);
+        }
+
         #[test]
         fn combine_with_empty_is_identity(
             ts in 0i64..1_000_000_000,
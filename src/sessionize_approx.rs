@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `sessionize_approx` — Sessionization of out-of-order input without a sort.
+//!
+//! [`sessionize`](crate::sessionize) requires rows ordered by timestamp. For
+//! late-arriving data (e.g. Kafka-style ingestion) a full `ORDER BY` can be
+//! the most expensive part of the query. `sessionize_approx` instead accepts
+//! rows in arrival order and tolerates disorder up to a `slack` interval.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! -- Number of sessions per user, input in arrival order
+//! SELECT user_id,
+//!   sessionize_approx(event_time, INTERVAL '30 minutes', INTERVAL '5 minutes')
+//! FROM events
+//! GROUP BY user_id
+//!
+//! -- Running session ID in arrival order
+//! SELECT user_id, event_time,
+//!   sessionize_approx(event_time, INTERVAL '30 minutes', INTERVAL '5 minutes') OVER (
+//!     PARTITION BY user_id ORDER BY ingest_offset
+//!   ) as session_id
+//! FROM events
+//! ```
+//!
+//! # Implementation
+//!
+//! Incoming timestamps are pushed onto a min-heap reorder buffer. The
+//! watermark is `max_seen - slack`: every buffered timestamp older than the
+//! watermark can no longer be preceded by an on-time event, so it is popped
+//! in order and fed to a [`SessionizeBoundaryState`]. The buffer therefore
+//! holds only the events of the most recent `slack` interval.
+//!
+//! An event that arrives already behind the emitted timestamps (disorder
+//! greater than `slack`) is *late*. Late events are still counted as part of
+//! the data but can never open a new session; they are tallied in
+//! [`SessionizeApproxState::late_events`]. When the input disorder stays
+//! within `slack`, the result is identical to `sessionize` over sorted input.
+
+use crate::sessionize::SessionizeBoundaryState;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// State for the `sessionize_approx` aggregate function.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct SessionizeApproxState {
+    /// Gap threshold in microseconds.
+    pub threshold_us: i64,
+    /// Maximum tolerated disorder in microseconds.
+    pub slack_us: i64,
+    /// Whether [`set_params`](Self::set_params) was called. Combine copies
+    /// the gap and slack into a state that has not set them, so a zero gap
+    /// and slack given by the caller are kept.
+    pub params_set: bool,
+    /// Largest timestamp seen so far. Drives the watermark.
+    pub max_seen: Option<i64>,
+    /// Number of events that arrived behind the watermark and were
+    /// processed out of order.
+    pub late_events: u64,
+    /// Reorder buffer of timestamps not yet behind the watermark.
+    buffer: BinaryHeap<Reverse<i64>>,
+    /// Session boundaries over the timestamps already flushed in order.
+    flushed: SessionizeBoundaryState,
+}
+
+impl SessionizeApproxState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            threshold_us: 0,
+            slack_us: 0,
+            params_set: false,
+            max_seen: None,
+            late_events: 0,
+            buffer: BinaryHeap::new(),
+            flushed: SessionizeBoundaryState::new(),
+        }
+    }
+
+    /// Sets the gap threshold and slack (called on the first update).
+    pub const fn set_params(&mut self, threshold_us: i64, slack_us: i64) {
+        self.threshold_us = threshold_us;
+        self.slack_us = slack_us;
+        self.params_set = true;
+        self.flushed.threshold_us = threshold_us;
+    }
+
+    /// Number of timestamps currently held in the reorder buffer.
+    #[must_use]
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Adds a timestamp, flushing every buffered timestamp behind the watermark.
+    pub fn update(&mut self, timestamp_us: i64) {
+        self.max_seen = Some(self.max_seen.map_or(timestamp_us, |m| m.max(timestamp_us)));
+        self.buffer.push(Reverse(timestamp_us));
+        self.flush_to_watermark();
+    }
+
+    /// Pops buffered timestamps older than `max_seen - slack` in ascending order.
+    fn flush_to_watermark(&mut self) {
+        let Some(max_seen) = self.max_seen else {
+            return;
+        };
+        let watermark = max_seen.saturating_sub(self.slack_us);
+        while let Some(&Reverse(ts)) = self.buffer.peek() {
+            if ts >= watermark {
+                break;
+            }
+            self.buffer.pop();
+            self.emit(ts);
+        }
+    }
+
+    /// Feeds one timestamp, in watermark order, to the boundary tracker.
+    fn emit(&mut self, timestamp_us: i64) {
+        if self.flushed.last_ts.is_some_and(|last| timestamp_us < last) {
+            self.late_events += 1;
+        }
+        self.flushed.update(timestamp_us);
+    }
+
+    /// Combines another state into `self`.
+    ///
+    /// The flushed summaries are merged in time order (left = earlier first
+    /// timestamp) with [`SessionizeBoundaryState::combine`]. Before merging,
+    /// the left side's buffered timestamps that precede the right side's
+    /// flushed range are flushed into the left summary, so they land in
+    /// order. Remaining buffered timestamps from both sides are re-fed
+    /// through the reorder buffer, which tolerates disorder across partial
+    /// states up to `slack` just like disorder within one. If the flushed
+    /// ranges overlap, the input disorder exceeded `slack` and the result is
+    /// approximate.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        if !self.params_set && other.params_set {
+            self.set_params(other.threshold_us, other.slack_us);
+        }
+        self.late_events += other.late_events;
+
+        let mut other_flushed = other.flushed.clone();
+        let mut other_buffer = other.buffer.clone();
+
+        if let Some(other_first) = other_flushed.first_ts {
+            // An empty flushed summary sorts first: its buffered timestamps
+            // may still precede the other side's flushed range.
+            self.flushed.threshold_us = self.threshold_us;
+            let self_is_left = self.flushed.first_ts.is_none_or(|f| f <= other_first);
+            let (left, left_buffer, right) = if self_is_left {
+                (&mut self.flushed, &mut self.buffer, &other_flushed)
+            } else {
+                (&mut other_flushed, &mut other_buffer, &self.flushed)
+            };
+            let bound = right.first_ts.unwrap_or(i64::MAX);
+            while let Some(&Reverse(ts)) = left_buffer.peek() {
+                if ts >= bound {
+                    break;
+                }
+                left_buffer.pop();
+                left.update(ts);
+            }
+            let mut merged = left.combine(right);
+            merged.current_row_null = false;
+            self.flushed = merged;
+        }
+        self.flushed.threshold_us = self.threshold_us;
+
+        if let Some(other_max) = other.max_seen {
+            self.max_seen = Some(self.max_seen.map_or(other_max, |m| m.max(other_max)));
+        }
+        self.buffer.extend(other_buffer);
+        self.flush_to_watermark();
+    }
+
+    /// Returns the number of sessions over all timestamps seen, including
+    /// those still buffered. Returns 0 for an empty state.
+    ///
+    /// Does not mutate the state, so it is safe to call repeatedly on the
+    /// same state (as `DuckDB` does for window frames).
+    #[must_use]
+    pub fn finalize(&self) -> i64 {
+        let mut flushed = self.flushed.clone();
+        // `into_sorted_vec` on a heap of `Reverse` yields descending timestamps
+        for Reverse(ts) in self.buffer.clone().into_sorted_vec().into_iter().rev() {
+            flushed.update(ts);
+        }
+        flushed.finalize()
+    }
+}
+
+impl Default for SessionizeApproxState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: i64 = 60_000_000;
+
+    fn state(gap_min: i64, slack_min: i64) -> SessionizeApproxState {
+        let mut s = SessionizeApproxState::new();
+        s.set_params(gap_min * MIN, slack_min * MIN);
+        s
+    }
+
+    fn sorted_sessions(timestamps: &[i64], gap_us: i64) -> i64 {
+        let mut sorted = timestamps.to_vec();
+        sorted.sort_unstable();
+        let mut exact = SessionizeBoundaryState::new();
+        exact.threshold_us = gap_us;
+        for ts in sorted {
+            exact.update(ts);
+        }
+        exact.finalize()
+    }
+
+    #[test]
+    fn test_empty_state() {
+        assert_eq!(SessionizeApproxState::new().finalize(), 0);
+    }
+
+    #[test]
+    fn test_in_order_matches_sessionize() {
+        let mut s = state(30, 5);
+        for ts in [0, 10, 20, 90, 100, 200] {
+            s.update(ts * MIN);
+        }
+        assert_eq!(s.finalize(), 3);
+        assert_eq!(s.late_events, 0);
+    }
+
+    #[test]
+    fn test_disorder_within_slack_is_exact() {
+        let arrival = [0, 20, 10, 95, 90, 100, 210, 200];
+        let mut s = state(30, 15);
+        for ts in arrival {
+            s.update(ts * MIN);
+        }
+        let expected = sorted_sessions(&arrival.map(|t| t * MIN), 30 * MIN);
+        assert_eq!(s.finalize(), expected);
+        assert_eq!(s.late_events, 0);
+    }
+
+    #[test]
+    fn test_disorder_beyond_slack_counts_late() {
+        // After 200 arrives the watermark is 195, so 0 and 100 are flushed.
+        // 60 then arrives behind the flushed 100: too late to open its own
+        // session.
+        let mut s = state(30, 5);
+        for ts in [0, 100, 200, 60] {
+            s.update(ts * MIN);
+        }
+        assert_eq!(s.late_events, 1);
+        assert_eq!(s.finalize(), 3); // exact answer would be 4
+    }
+
+    #[test]
+    fn test_buffer_bounded_by_slack() {
+        let mut s = state(30, 5);
+        for ts in 0..1_000 {
+            s.update(ts * MIN);
+        }
+        // Only timestamps within the last 5 minutes stay buffered
+        assert!(s.buffered() <= 6);
+        assert_eq!(s.finalize(), 1);
+    }
+
+    #[test]
+    fn test_finalize_is_idempotent() {
+        let mut s = state(30, 60);
+        for ts in [100, 0, 50] {
+            s.update(ts * MIN);
+        }
+        // 0 is flushed (watermark 40); 50 and 100 stay buffered
+        assert_eq!(s.buffered(), 2);
+        assert_eq!(s.finalize(), 3);
+        assert_eq!(s.finalize(), 3);
+        assert_eq!(s.buffered(), 2);
+    }
+
+    #[test]
+    fn test_combine_disjoint_partials() {
+        let mut a = state(30, 5);
+        for ts in [0, 10, 20] {
+            a.update(ts * MIN);
+        }
+        let mut b = state(30, 5);
+        for ts in [100, 110] {
+            b.update(ts * MIN);
+        }
+        let mut ab = a.clone();
+        ab.combine_in_place(&b);
+        let mut ba = b.clone();
+        ba.combine_in_place(&a);
+        assert_eq!(ab.finalize(), 2);
+        assert_eq!(ba.finalize(), 2);
+        assert_eq!(ab.late_events, 0);
+        assert_eq!(ba.late_events, 0);
+    }
+
+    #[test]
+    fn test_combine_interleaved_buffers_within_slack() {
+        // Each partial keeps its events buffered (slack covers everything),
+        // so combine reorders the union exactly.
+        let mut a = state(30, 1_000);
+        let mut b = state(30, 1_000);
+        for ts in [0, 100, 200] {
+            a.update(ts * MIN);
+        }
+        for ts in [20, 120, 300] {
+            b.update(ts * MIN);
+        }
+        a.combine_in_place(&b);
+        assert_eq!(a.finalize(), 4);
+        assert_eq!(a.late_events, 0);
+    }
+
+    #[test]
+    fn test_combine_into_zero_initialized_target() {
+        let mut source = state(30, 5);
+        for ts in [0, 100] {
+            source.update(ts * MIN);
+        }
+        let mut target = SessionizeApproxState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.threshold_us, 30 * MIN);
+        assert_eq!(target.slack_us, 5 * MIN);
+        assert_eq!(target.finalize(), 2);
+    }
+
+    #[test]
+    fn test_combine_keeps_explicit_zero_gap_and_slack() {
+        let mut target = state(0, 0);
+        target.update(0);
+        let mut source = state(30, 5);
+        source.update(10 * MIN);
+        target.combine_in_place(&source);
+        assert_eq!((target.threshold_us, target.slack_us), (0, 0));
+        assert_eq!(target.finalize(), 2);
+    }
+}
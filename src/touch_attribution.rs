@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `first_touch` / `last_touch` — Single-touch attribution.
+//!
+//! Both functions take `(timestamp, channel, converted [, lookback])` and
+//! credit the group's first conversion, the earliest row whose `converted`
+//! condition is true, to one touchpoint: `first_touch` returns the channel of
+//! the earliest touchpoint at or before the conversion, `last_touch` the
+//! channel of the latest. With a `lookback` interval, only touchpoints at most
+//! that long before the conversion count.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id,
+//!   first_touch(event_time, channel, event_type = 'purchase') as first_channel,
+//!   last_touch(event_time, channel, event_type = 'purchase',
+//!              INTERVAL '7 days') as last_channel_7d
+//! FROM events
+//! GROUP BY user_id
+//! ```
+//!
+//! Every row with a non-`NULL` channel is a touchpoint, including the
+//! converting row itself; a converting row without a channel only marks the
+//! conversion. The result is `NULL` when the group has no conversion or no
+//! touchpoint within the lookback. Touchpoints sharing a timestamp are
+//! ordered by channel, so the result does not depend on input order.
+//!
+//! # Implementation
+//!
+//! The state keeps the touchpoints and only the earliest conversion
+//! timestamp, since later conversions never change the result. Combine
+//! appends the touchpoints and takes the earlier conversion. Finalize is one
+//! O(n) pass over the touchpoints without sorting: the minimum (or maximum)
+//! `(timestamp, channel)` inside `[conversion - lookback, conversion]`.
+
+/// State for the `first_touch` and `last_touch` aggregate functions.
+///
+/// Generic over the channel type `V`, the physical type of the channel
+/// column, as for [`sequence_next_node`](crate::sequence_next_node).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TouchAttributionState<V> {
+    /// Collected `(timestamp, channel)` touchpoints, in input order.
+    touchpoints: Vec<(i64, V)>,
+    /// Timestamp of the earliest conversion seen so far.
+    pub conversion_us: Option<i64>,
+    /// Lookback window in microseconds (`None` without a `lookback`
+    /// argument: every earlier touchpoint counts).
+    pub lookback_us: Option<i64>,
+}
+
+impl<V> Default for TouchAttributionState<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> TouchAttributionState<V> {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            touchpoints: Vec::new(),
+            conversion_us: None,
+            lookback_us: None,
+        }
+    }
+
+    /// Adds a row: a touchpoint when it has a `channel`, a conversion when
+    /// `converted` is true, or both.
+    pub fn update(&mut self, timestamp_us: i64, channel: Option<V>, converted: bool) {
+        if let Some(channel) = channel {
+            self.touchpoints.push((timestamp_us, channel));
+        }
+        if converted {
+            self.conversion_us = Some(
+                self.conversion_us
+                    .map_or(timestamp_us, |conv| conv.min(timestamp_us)),
+            );
+        }
+    }
+}
+
+impl<V: Ord + Clone> TouchAttributionState<V> {
+    /// Combines `other` into `self` by appending its touchpoints and keeping
+    /// the earlier conversion.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.touchpoints.extend(other.touchpoints.iter().cloned());
+        self.conversion_us = match (self.conversion_us, other.conversion_us) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if self.lookback_us.is_none() {
+            self.lookback_us = other.lookback_us;
+        }
+    }
+
+    /// Returns the channel of the earliest touchpoint credited with the
+    /// first conversion, or `None` (see the [module docs](self)).
+    #[must_use]
+    pub fn finalize_first(&self) -> Option<V> {
+        self.credited().min().map(|(_, channel)| channel.clone())
+    }
+
+    /// Returns the channel of the latest touchpoint credited with the first
+    /// conversion, or `None` (see the [module docs](self)).
+    #[must_use]
+    pub fn finalize_last(&self) -> Option<V> {
+        self.credited().max().map(|(_, channel)| channel.clone())
+    }
+
+    /// Returns the touchpoints inside `[conversion - lookback, conversion]`.
+    fn credited(&self) -> impl Iterator<Item = &(i64, V)> {
+        let conversion = self.conversion_us;
+        let earliest = match (conversion, self.lookback_us) {
+            (Some(conv), Some(lookback)) => conv.saturating_sub(lookback),
+            _ => i64::MIN,
+        };
+        self.touchpoints
+            .iter()
+            .filter(move |(ts, _)| conversion.is_some_and(|conv| *ts <= conv) && *ts >= earliest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn state(rows: &[(i64, Option<&str>, bool)]) -> TouchAttributionState<Arc<str>> {
+        let mut state = TouchAttributionState::new();
+        for &(ts, channel, converted) in rows {
+            state.update(ts, channel.map(Arc::from), converted);
+        }
+        state
+    }
+
+    #[test]
+    fn test_first_and_last_touch_before_conversion() {
+        let state = state(&[
+            (300, Some("email"), false),
+            (100, Some("search"), false),
+            (400, None, true),
+            (500, Some("social"), false),
+        ]);
+        assert_eq!(state.finalize_first(), Some(Arc::from("search")));
+        assert_eq!(state.finalize_last(), Some(Arc::from("email")));
+    }
+
+    #[test]
+    fn test_converting_row_is_a_touchpoint() {
+        let state = state(&[(100, Some("search"), false), (200, Some("direct"), true)]);
+        assert_eq!(state.finalize_last(), Some(Arc::from("direct")));
+    }
+
+    #[test]
+    fn test_first_conversion_is_credited() {
+        let state = state(&[
+            (100, Some("search"), false),
+            (200, None, true),
+            (300, Some("email"), false),
+            (400, None, true),
+        ]);
+        assert_eq!(state.finalize_last(), Some(Arc::from("search")));
+    }
+
+    #[test]
+    fn test_no_conversion_is_none() {
+        let state = state(&[(100, Some("search"), false)]);
+        assert_eq!(state.finalize_first(), None);
+        assert_eq!(state.finalize_last(), None);
+    }
+
+    #[test]
+    fn test_lookback_excludes_older_touchpoints() {
+        let mut state = state(&[
+            (100, Some("search"), false),
+            (800, Some("email"), false),
+            (1_000, None, true),
+        ]);
+        state.lookback_us = Some(200);
+        assert_eq!(state.finalize_first(), Some(Arc::from("email")));
+        state.lookback_us = Some(100);
+        assert_eq!(state.finalize_first(), None);
+    }
+
+    #[test]
+    fn test_ties_ordered_by_channel() {
+        let state = state(&[
+            (100, Some("search"), false),
+            (100, Some("email"), false),
+            (200, None, true),
+        ]);
+        assert_eq!(state.finalize_first(), Some(Arc::from("email")));
+        assert_eq!(state.finalize_last(), Some(Arc::from("search")));
+    }
+
+    #[test]
+    fn test_combine_keeps_earlier_conversion() {
+        let mut a = state(&[(100, Some("search"), false), (500, None, true)]);
+        let mut b = state(&[(300, Some("email"), false), (200, None, true)]);
+        b.lookback_us = Some(1_000);
+        a.combine_in_place(&b);
+        assert_eq!(a.conversion_us, Some(200));
+        assert_eq!(a.lookback_us, Some(1_000));
+        assert_eq!(a.finalize_last(), Some(Arc::from("search")));
+    }
+}
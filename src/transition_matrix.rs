@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `transition_matrix` — First-order transition counts between states.
+//!
+//! Orders a group's events by timestamp and counts every pair of consecutive
+//! states `(from, to)`, the sufficient statistics of a first-order Markov
+//! chain over the group's path. Self-transitions are counted like any other.
+//! This is the building block for Markov-chain attribution and flow models:
+//! transition probabilities are the counts divided by the row sums.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT t.from_state, t.to_state, sum(t.count) AS transitions
+//! FROM (
+//!   SELECT unnest(transition_matrix(event_time, page, 20)) AS t
+//!   FROM events
+//!   GROUP BY user_id
+//! )
+//! GROUP BY ALL
+//! ```
+//!
+//! # Implementation
+//!
+//! The state collects `(timestamp, state)` pairs; combine concatenates them.
+//! Finalize sorts them with the shared presorted check ([`sort_presorted`]),
+//! ordering events that share a timestamp by state so the result does not
+//! depend on input order, and counts pairs in a [`BTreeMap`].
+//!
+//! With a limit of `n` states, only the `n` most frequent states of the group
+//! keep their value; the others are collapsed into one `NULL` state, so
+//! transitions into and out of the long tail are still counted.
+
+use crate::common::event::sort_presorted;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// One transition count: `(from_state, to_state, count)`, `None` for a state
+/// collapsed by the limit.
+pub type Transition<V> = (Option<V>, Option<V>, i64);
+
+/// State for the `transition_matrix` aggregate function.
+///
+/// Generic over the state type `V`, defaulting to `Arc<str>`, like
+/// [`SequenceNextNodeState`](crate::sequence_next_node::SequenceNextNodeState).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TransitionMatrixState<V = Arc<str>> {
+    /// Number of distinct states kept by value; 0 keeps all of them.
+    pub max_states: usize,
+    /// Collected `(timestamp, state)` pairs. Sorted in finalize.
+    events: Vec<(i64, V)>,
+}
+
+impl<V> Default for TransitionMatrixState<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> TransitionMatrixState<V> {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_states: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds an event.
+    pub fn update(&mut self, timestamp_us: i64, state: V) {
+        self.events.push((timestamp_us, state));
+    }
+}
+
+impl<V: Ord + Clone> TransitionMatrixState<V> {
+    /// Combines `other` into `self` by appending its events.
+    ///
+    /// Propagates `max_states` into a zero-initialized target.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        if self.max_states == 0 {
+            self.max_states = other.max_states;
+        }
+        self.events.extend(other.events.iter().cloned());
+    }
+
+    /// Returns the transition counts ordered by `from_state`, then
+    /// `to_state`, with the collapsed `NULL` state last.
+    #[must_use]
+    pub fn finalize(&mut self) -> Vec<Transition<V>> {
+        sort_presorted(&mut self.events);
+        let kept = self.kept_states();
+        let label = |state: &V| {
+            kept.as_ref()
+                .is_none_or(|kept| kept.binary_search(state).is_ok())
+                .then(|| state.clone())
+        };
+
+        let mut counts: BTreeMap<(Option<V>, Option<V>), i64> = BTreeMap::new();
+        for pair in self.events.windows(2) {
+            *counts
+                .entry((label(&pair[0].1), label(&pair[1].1)))
+                .or_insert(0) += 1;
+        }
+
+        let mut transitions: Vec<Transition<V>> = counts
+            .into_iter()
+            .map(|((from, to), count)| (from, to, count))
+            .collect();
+        transitions.sort_by(|a, b| {
+            nulls_last(a.0.as_ref(), b.0.as_ref())
+                .then_with(|| nulls_last(a.1.as_ref(), b.1.as_ref()))
+        });
+        transitions
+    }
+
+    /// Returns the `max_states` most frequent states, ties broken by value,
+    /// in value order; `None` when every state is kept.
+    fn kept_states(&self) -> Option<Vec<V>> {
+        if self.max_states == 0 {
+            return None;
+        }
+        let mut frequency: BTreeMap<&V, u64> = BTreeMap::new();
+        for (_, state) in &self.events {
+            *frequency.entry(state).or_insert(0) += 1;
+        }
+        if frequency.len() <= self.max_states {
+            return None;
+        }
+        let mut by_frequency: Vec<(&V, u64)> = frequency.into_iter().collect();
+        // Stable sort keeps equal frequencies in value order.
+        by_frequency.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        let mut kept: Vec<V> = by_frequency
+            .into_iter()
+            .take(self.max_states)
+            .map(|(state, _)| state.clone())
+            .collect();
+        kept.sort_unstable();
+        Some(kept)
+    }
+}
+
+/// Orders values with `None` after every `Some`, as `NULLS LAST`.
+fn nulls_last<V: Ord>(a: Option<&V>, b: Option<&V>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(max_states: usize, events: &[(i64, &str)]) -> TransitionMatrixState {
+        let mut s = TransitionMatrixState::new();
+        s.max_states = max_states;
+        for &(ts, value) in events {
+            s.update(ts, Arc::from(value));
+        }
+        s
+    }
+
+    fn transitions(mut s: TransitionMatrixState) -> Vec<(Option<String>, Option<String>, i64)> {
+        s.finalize()
+            .into_iter()
+            .map(|(from, to, n)| (from.map(|v| v.to_string()), to.map(|v| v.to_string()), n))
+            .collect()
+    }
+
+    fn t(from: Option<&str>, to: Option<&str>, n: i64) -> (Option<String>, Option<String>, i64) {
+        (from.map(String::from), to.map(String::from), n)
+    }
+
+    #[test]
+    fn test_counts_consecutive_pairs_in_timestamp_order() {
+        let s = state(
+            0,
+            &[
+                (3, "cart"),
+                (1, "home"),
+                (2, "product"),
+                (4, "home"),
+                (5, "home"),
+            ],
+        );
+        assert_eq!(
+            transitions(s),
+            vec![
+                t(Some("cart"), Some("home"), 1),
+                t(Some("home"), Some("home"), 1),
+                t(Some("home"), Some("product"), 1),
+                t(Some("product"), Some("cart"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_and_single_event() {
+        assert!(TransitionMatrixState::<i64>::new().finalize().is_empty());
+        assert!(transitions(state(0, &[(1, "home")])).is_empty());
+    }
+
+    #[test]
+    fn test_limit_collapses_rare_states_into_null() {
+        // home x3, product x2, cart x1, help x1: with 2 states, cart and
+        // help become NULL and the NULL state sorts last.
+        let s = state(
+            2,
+            &[
+                (1, "home"),
+                (2, "help"),
+                (3, "home"),
+                (4, "product"),
+                (5, "cart"),
+                (6, "home"),
+                (7, "product"),
+            ],
+        );
+        assert_eq!(
+            transitions(s),
+            vec![
+                t(Some("home"), Some("product"), 2),
+                t(Some("home"), None, 1),
+                t(Some("product"), None, 1),
+                t(None, Some("home"), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combine_matches_sequential_for_every_split() {
+        let events = [(4, "d"), (1, "a"), (6, "a"), (3, "c"), (5, "b"), (2, "b")];
+        let whole = transitions(state(0, &events));
+        for split in 0..=events.len() {
+            let mut left = TransitionMatrixState::new();
+            left.combine_in_place(&state(0, &events[..split]));
+            left.combine_in_place(&state(0, &events[split..]));
+            assert_eq!(transitions(left), whole, "split {split}");
+        }
+    }
+
+    #[test]
+    fn test_combine_zero_target_propagates_limit() {
+        let mut target = TransitionMatrixState::new();
+        target.combine_in_place(&state(1, &[(1, "a"), (2, "b"), (3, "a")]));
+        assert_eq!(target.max_states, 1);
+        assert_eq!(
+            transitions(target),
+            vec![t(Some("a"), None, 1), t(None, Some("a"), 1)]
+        );
+    }
+}
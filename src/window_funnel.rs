@@ -5,7 +5,8 @@
 //!
 //! Searches for the longest chain of events `cond1 -> cond2 -> ... -> condN`
 //! where each subsequent event occurs within `window_size` of the **first**
-//! event in the chain. Returns an integer 0..N indicating the max step reached.
+//! event in the chain (or of the previous matched step with `'strict_time'`).
+//! Returns an integer 0..N indicating the max step reached.
 //!
 //! This matches `ClickHouse` `windowFunnel()` semantics.
 //!
@@ -45,11 +46,82 @@
 //!   funnel by at most one step, even if it satisfies multiple conditions.
 //! - **Allow Reentry** (0x20, SQL: `'allow_reentry'`): If the entry condition
 //!   fires again mid-chain, the funnel resets from that new entry point.
+//! - **Entry Advances** (0x40, SQL: `'entry_advances'`): _Extension mode_.
+//!   The entry event may advance the funnel past step 1 when it also satisfies
+//!   the following conditions.
+//! - **Strict Order Zero** (0x80, SQL: `'strict_order_zero'`): Like
+//!   `strict_order`, but a violation breaks the whole funnel and the result
+//!   is 0, for bit-identical results with `ClickHouse` workloads.
+//! - **Strict Time** (0x100, SQL: `'strict_time'`): _Extension mode_. The
+//!   window applies between consecutive matched steps instead of from the
+//!   entry event, as in Amplitude-style funnels.
+//! - **Event Dedup** (0x200, SQL: `'strict_dedup'`): _Extension mode_.
+//!   Events identical to an earlier event, with the same timestamp and the
+//!   same condition bitmask, are dropped before the scan. Unlike
+//!   `'strict_deduplication'`, `ClickHouse`'s alias of `'strict'`, it
+//!   removes duplicate ingestion rather than constraining the chain.
+//! - **Skip** (SQL: `'skip(K)'`): _Extension mode_. An event satisfying a
+//!   later step may advance the funnel to it, skipping at most `K` steps in
+//!   total. The result is the furthest step reached; the skipped steps are the
+//!   zero bits below it in [`WindowFunnelState::finalize_mask`].
+//! - **Tie Break** (SQL: `'tie_break(input)'`, `'tie_break(condition)'`):
+//!   _Extension mode_. Orders events with identical timestamps before the
+//!   scan, which is otherwise unspecified; see [`TieBreak`].
+//!
+//! # Unbounded Window
+//!
+//! A `NULL` window (SQL: `NULL::INTERVAL`) removes the time bound: the FFI
+//! stores it as [`UNBOUNDED_WINDOW_US`], and every later event can extend a
+//! chain. A window of 0 keeps its meaning of "same timestamp only". Because
+//! the sentinel is non-zero, it propagates through combine like any set
+//! window.
+//!
+//! # Duplicate Events
+//!
+//! An optional `dedup_within` interval (SQL: third argument after the mode
+//! string) collapses repeated events before the scan: an event is dropped when
+//! an event with the identical condition bitmask was kept at most
+//! `dedup_within` earlier. This removes bot-generated bursts without a
+//! pre-aggregating CTE. The `'strict_dedup'` mode drops exact duplicates
+//! only, the events a row ingested twice produces, and applies first.
+//!
+//! # Minimum Step Gaps
+//!
+//! An optional list of minimum gaps (SQL: an `INTERVAL[]` after the mode
+//! string) bounds the time between consecutive steps from below: an event
+//! arriving sooner than its step's gap after the previous matched step does
+//! not advance the chain, so an add-to-cart followed by a purchase within
+//! seconds can be ignored as bot behavior. See
+//! [`WindowFunnelState::min_gaps_us`].
+//!
+//! # Sequence Numbers
+//!
+//! An optional `BIGINT` sequence-number column after the timestamp breaks
+//! timestamp ties: events are ordered by `(timestamp, seq)`. It takes
+//! precedence over the `tie_break(...)` mode. The numbers are stored in
+//! [`WindowFunnelState::seqs`], parallel to the events.
+
+use crate::common::event::{
+    sort_events, sort_events_by_seq, CompressedEventBuffer, Event, COMPRESSION_THRESHOLD,
+};
+use crate::common::event_buffer::EventBuffer;
+use crate::common::state_blob::{BlobError, BlobReader, BlobWriter};
+use crate::common::timestamp::{elapsed_exceeds, elapsed_micros, elapsed_wide, TimestampOverflow};
+use std::collections::HashMap;
+
+/// Window size of an unbounded window, set for a `NULL` window argument.
+///
+/// Events at any distance from the window start are within it, including
+/// the `infinity` timestamps whose distance exceeds `i64` microseconds.
+pub const UNBOUNDED_WINDOW_US: i64 = i64::MAX;
 
-use crate::common::event::{sort_events, Event};
+/// Number of events from which finalize scans chains through
+/// [`StepPositions`] instead of walking every event after each entry.
+/// Smaller groups scan faster than the index builds.
+const INDEXED_SCAN_MIN_EVENTS: usize = 64;
 
-/// Funnel matching mode as a bitmask, controlling how strictly the event
-/// sequence is enforced.
+/// Funnel matching mode: a bitmask of flags controlling how strictly the
+/// event sequence is enforced, plus the parameter of the `skip(K)` mode.
 ///
 /// Modes are combinable: multiple modes can be active simultaneously. Each
 /// mode adds an independent constraint on top of the default greedy scan.
@@ -64,23 +136,35 @@ use crate::common::event::{sort_events, Event};
 /// Bit 3 (0x08): STRICT_INCREASE    (ClickHouse: 'strict_increase')
 /// Bit 4 (0x10): STRICT_ONCE        (ClickHouse: 'strict_once')
 /// Bit 5 (0x20): ALLOW_REENTRY      (ClickHouse: 'allow_reentry')
+/// Bit 6 (0x40): ENTRY_ADVANCES     (Extension: 'entry_advances')
+/// Bit 7 (0x80): STRICT_ORDER_ZERO  (ClickHouse parity: 'strict_order_zero')
+/// Bit 8 (0x100): STRICT_TIME       (Extension: 'strict_time')
+/// Bit 9 (0x200): EVENT_DEDUP       (Extension: 'strict_dedup')
 /// ```
+///
+/// `skip(K)` is not a flag: it sets [`max_skips`](Self::max_skips), the number
+/// of funnel steps a chain may skip. Neither is `tie_break(...)`, which sets
+/// the [`TieBreak`] order of same-timestamp events.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-pub struct FunnelMode(u8);
+pub struct FunnelMode {
+    bits: u16,
+    max_skips: u8,
+    tie_break: TieBreak,
+}
 
 impl FunnelMode {
     /// Default mode: no constraints beyond the basic greedy scan.
-    pub const DEFAULT: Self = Self(0);
+    pub const DEFAULT: Self = Self::from_bits(0);
 
     /// If the previously-matched condition fires again before the next
     /// condition is matched, the chain breaks. This prevents backwards
     /// movement in the funnel. Corresponds to `ClickHouse`'s `'strict'` and
     /// `'strict_deduplication'` modes (which are aliases in `ClickHouse`).
-    pub const STRICT: Self = Self(0x01);
+    pub const STRICT: Self = Self::from_bits(0x01);
 
     /// Events must satisfy conditions in exact sequential order. No out-of-order
     /// conditions allowed between matched steps.
-    pub const STRICT_ORDER: Self = Self(0x02);
+    pub const STRICT_ORDER: Self = Self::from_bits(0x02);
 
     /// **Extension mode** (not in `ClickHouse`). Events with identical
     /// timestamps as the previously-matched step are skipped for the
@@ -91,47 +175,155 @@ impl FunnelMode {
     /// maps `'strict_deduplication'` to `STRICT` to match `ClickHouse`
     /// semantics. This timestamp-based dedup mode is available via the
     /// `'timestamp_dedup'` SQL string.
-    pub const STRICT_DEDUPLICATION: Self = Self(0x04);
+    pub const STRICT_DEDUPLICATION: Self = Self::from_bits(0x04);
 
     /// Requires strictly increasing timestamps between matched funnel steps.
     /// Same-timestamp events cannot advance the funnel.
-    pub const STRICT_INCREASE: Self = Self(0x08);
+    pub const STRICT_INCREASE: Self = Self::from_bits(0x08);
 
     /// Each event can advance the funnel by at most one step.
-    pub const STRICT_ONCE: Self = Self(0x10);
+    pub const STRICT_ONCE: Self = Self::from_bits(0x10);
 
     /// If the entry condition fires again mid-chain, the funnel resets
     /// from that new entry point.
-    pub const ALLOW_REENTRY: Self = Self(0x20);
+    pub const ALLOW_REENTRY: Self = Self::from_bits(0x20);
+
+    /// **Extension mode** (not in `ClickHouse`). The entry event may advance
+    /// the funnel through every consecutive condition it satisfies, instead
+    /// of counting only as step 1. For example, an event satisfying both
+    /// cond1 and cond2 starts the funnel at step 2. Use SQL string
+    /// `'entry_advances'`. Has no effect when combined with `STRICT_ONCE`.
+    pub const ENTRY_ADVANCES: Self = Self::from_bits(0x40);
+
+    /// Like [`STRICT_ORDER`](Self::STRICT_ORDER), but an out-of-order event
+    /// breaks the whole funnel: the result is 0 and no further entry points
+    /// are tried. Matches `ClickHouse`'s `strict_order` result for migrated
+    /// workloads. Use SQL string `'strict_order_zero'`.
+    pub const STRICT_ORDER_ZERO: Self = Self::from_bits(0x80);
+
+    /// **Extension mode** (not in `ClickHouse`). Each step must occur within
+    /// the window of the previous matched step rather than of the entry
+    /// event, so a funnel can span more than one window overall. Matches how
+    /// Amplitude-style funnels define conversion windows. Use SQL string
+    /// `'strict_time'`.
+    pub const STRICT_TIME: Self = Self::from_bits(0x100);
+
+    /// **Extension mode** (not in `ClickHouse`). Before the scan, each event
+    /// with the same timestamp and the same condition bitmask as an earlier
+    /// event is dropped, so rows ingested twice count once. Other modes then
+    /// see one event, e.g. `STRICT` is not broken by a repeated step. Use SQL
+    /// string `'strict_dedup'`, not to be confused with
+    /// `'strict_deduplication'`, an alias of [`STRICT`](Self::STRICT).
+    pub const EVENT_DEDUP: Self = Self::from_bits(0x200);
+
+    /// Every SQL mode string accepted by [`parse_mode_str`](Self::parse_mode_str),
+    /// in bit order. Used in error messages for unrecognized modes.
+    pub const MODE_NAMES: [&'static str; 11] = [
+        "strict",
+        "strict_deduplication",
+        "strict_order",
+        "timestamp_dedup",
+        "strict_increase",
+        "strict_once",
+        "allow_reentry",
+        "entry_advances",
+        "strict_order_zero",
+        "strict_time",
+        "strict_dedup",
+    ];
+
+    /// Largest `K` accepted by `skip(K)`: a chain can skip at most every
+    /// step but the entry of a 64-step funnel.
+    pub const MAX_SKIPS: u8 = 63;
 
     /// Creates a `FunnelMode` from a raw bitmask.
     #[must_use]
-    pub const fn from_bits(bits: u8) -> Self {
-        Self(bits)
+    pub const fn from_bits(bits: u16) -> Self {
+        Self {
+            bits,
+            max_skips: 0,
+            tie_break: TieBreak::Unspecified,
+        }
+    }
+
+    /// Creates the `skip(K)` mode: no flags, at most `max_skips` skipped steps
+    /// (clamped to [`MAX_SKIPS`](Self::MAX_SKIPS)).
+    #[must_use]
+    pub const fn skip(max_skips: u8) -> Self {
+        let max_skips = if max_skips > Self::MAX_SKIPS {
+            Self::MAX_SKIPS
+        } else {
+            max_skips
+        };
+        Self {
+            bits: 0,
+            max_skips,
+            tie_break: TieBreak::Unspecified,
+        }
+    }
+
+    /// Creates the `tie_break(...)` mode: no flags, same-timestamp events
+    /// ordered by `tie_break`.
+    #[must_use]
+    pub const fn tie_break(tie_break: TieBreak) -> Self {
+        Self {
+            bits: 0,
+            max_skips: 0,
+            tie_break,
+        }
     }
 
     /// Returns the raw bitmask value.
     #[must_use]
-    pub const fn bits(self) -> u8 {
-        self.0
+    pub const fn bits(self) -> u16 {
+        self.bits
+    }
+
+    /// Returns the number of steps a chain may skip (`K` of `skip(K)`).
+    #[must_use]
+    pub const fn max_skips(self) -> u8 {
+        self.max_skips
+    }
+
+    /// Returns the order of events with identical timestamps.
+    #[must_use]
+    pub const fn tie_break_order(self) -> TieBreak {
+        self.tie_break
     }
 
     /// Returns true if the given flag is set.
     #[must_use]
     pub const fn has(self, flag: Self) -> bool {
-        self.0 & flag.0 == flag.0
+        self.bits & flag.bits == flag.bits
     }
 
-    /// Returns a new mode with the given flag set.
+    /// Returns a new mode with the given flag set. Combining two `skip(K)`
+    /// modes keeps the larger `K`; a `tie_break(...)` in `flag` replaces the
+    /// one in `self`.
     #[must_use]
     pub const fn with(self, flag: Self) -> Self {
-        Self(self.0 | flag.0)
+        let max_skips = if flag.max_skips > self.max_skips {
+            flag.max_skips
+        } else {
+            self.max_skips
+        };
+        let tie_break = if matches!(flag.tie_break, TieBreak::Unspecified) {
+            self.tie_break
+        } else {
+            flag.tie_break
+        };
+        Self {
+            bits: self.bits | flag.bits,
+            max_skips,
+            tie_break,
+        }
     }
 
-    /// Returns true if this is the default mode (no flags set).
+    /// Returns true if this is the default mode (no flags set, no skips, no
+    /// tie break).
     #[must_use]
     pub const fn is_default(self) -> bool {
-        self.0 == 0
+        self.bits == 0 && self.max_skips == 0 && matches!(self.tie_break, TieBreak::Unspecified)
     }
 
     /// Parses a mode string into a single flag bit.
@@ -142,9 +334,25 @@ impl FunnelMode {
     /// `'timestamp_dedup'` maps to [`STRICT_DEDUPLICATION`](Self::STRICT_DEDUPLICATION),
     /// an extension mode not present in `ClickHouse`.
     ///
+    /// `'skip(K)'` maps to [`skip(K)`](Self::skip) for `K` in
+    /// `0..=63` ([`MAX_SKIPS`](Self::MAX_SKIPS)), and `'tie_break(T)'` to
+    /// [`tie_break`](Self::tie_break) for `T` a [`TieBreak::NAMES`] entry.
+    ///
     /// Returns `None` for unrecognized mode strings.
     #[must_use]
     pub fn parse_mode_str(s: &str) -> Option<Self> {
+        if let Some(arg) = s
+            .strip_prefix("tie_break(")
+            .and_then(|r| r.strip_suffix(')'))
+        {
+            return TieBreak::parse(arg.trim()).map(Self::tie_break);
+        }
+        if let Some(arg) = s.strip_prefix("skip(").and_then(|r| r.strip_suffix(')')) {
+            return match arg.trim().parse::<u8>() {
+                Ok(k) if k <= Self::MAX_SKIPS => Some(Self::skip(k)),
+                _ => None,
+            };
+        }
         match s {
             "strict" | "strict_deduplication" => Some(Self::STRICT),
             "strict_order" => Some(Self::STRICT_ORDER),
@@ -152,6 +360,10 @@ impl FunnelMode {
             "strict_increase" => Some(Self::STRICT_INCREASE),
             "strict_once" => Some(Self::STRICT_ONCE),
             "allow_reentry" => Some(Self::ALLOW_REENTRY),
+            "entry_advances" => Some(Self::ENTRY_ADVANCES),
+            "strict_order_zero" => Some(Self::STRICT_ORDER_ZERO),
+            "strict_time" => Some(Self::STRICT_TIME),
+            "strict_dedup" => Some(Self::EVENT_DEDUP),
             _ => None,
         }
     }
@@ -195,6 +407,10 @@ impl std::fmt::Display for FunnelMode {
             (Self::STRICT_INCREASE, "strict_increase"),
             (Self::STRICT_ONCE, "strict_once"),
             (Self::ALLOW_REENTRY, "allow_reentry"),
+            (Self::ENTRY_ADVANCES, "entry_advances"),
+            (Self::STRICT_ORDER_ZERO, "strict_order_zero"),
+            (Self::STRICT_TIME, "strict_time"),
+            (Self::EVENT_DEDUP, "strict_dedup"),
         ];
         for (flag, name) in flags {
             if self.has(flag) {
@@ -205,10 +421,102 @@ impl std::fmt::Display for FunnelMode {
                 first = false;
             }
         }
+        if self.max_skips > 0 {
+            if !first {
+                write!(f, "+")?;
+            }
+            write!(f, "skip({})", self.max_skips)?;
+            first = false;
+        }
+        if !matches!(self.tie_break, TieBreak::Unspecified) {
+            if !first {
+                write!(f, "+")?;
+            }
+            write!(f, "tie_break({})", self.tie_break.name())?;
+        }
         Ok(())
     }
 }
 
+/// Order of events with identical timestamps, set by the `tie_break(...)`
+/// mode.
+///
+/// Events are sorted by timestamp before the scan. Without a tie break the
+/// sort is unstable and same-timestamp events end up in whatever order the
+/// combine of parallel segments left them, so a funnel whose result depends
+/// on that order can differ between runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// Unspecified order (unstable sort). The default, and the fastest.
+    #[default]
+    Unspecified,
+    /// Order in which rows reached the aggregate (stable sort). Only
+    /// deterministic when that order is, e.g. for single-threaded input.
+    Input,
+    /// Lowest satisfied condition first, i.e. in funnel order; events
+    /// satisfying the same lowest condition are ordered by their whole
+    /// bitmask. A total order, so the result does not depend on the input
+    /// order. SQL: `'tie_break(condition)'` or its alias
+    /// `'tie_break(argument)'`, since conditions are numbered by argument
+    /// position.
+    Condition,
+}
+
+impl TieBreak {
+    /// Every name accepted by [`parse`](Self::parse).
+    pub const NAMES: [&'static str; 3] = ["input", "condition", "argument"];
+
+    /// Parses a tie break name; `'argument'` is an alias of `'condition'`.
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "input" => Some(Self::Input),
+            "condition" | "argument" => Some(Self::Condition),
+            _ => None,
+        }
+    }
+
+    /// Code of this tie break in an exported state.
+    const fn code(self) -> u8 {
+        match self {
+            Self::Unspecified => 0,
+            Self::Input => 1,
+            Self::Condition => 2,
+        }
+    }
+
+    /// Inverse of [`code`](Self::code).
+    const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Unspecified),
+            1 => Some(Self::Input),
+            2 => Some(Self::Condition),
+            _ => None,
+        }
+    }
+
+    /// Returns the SQL name of this tie break.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Unspecified => "unspecified",
+            Self::Input => "input",
+            Self::Condition => "condition",
+        }
+    }
+
+    /// Sorts `events` by timestamp, ordering ties by this tie break.
+    pub fn sort(self, events: &mut [Event]) {
+        match self {
+            Self::Unspecified => sort_events(events),
+            Self::Input => events.sort_by_key(|e| e.timestamp_us),
+            Self::Condition => events.sort_unstable_by_key(|e| {
+                (e.timestamp_us, e.conditions.trailing_zeros(), e.conditions)
+            }),
+        }
+    }
+}
+
 /// State for the `window_funnel` aggregate function.
 ///
 /// Collects timestamped events during `update`, then processes them in `finalize`
@@ -217,13 +525,30 @@ impl std::fmt::Display for FunnelMode {
 #[non_exhaustive]
 pub struct WindowFunnelState {
     /// Collected events (timestamp + conditions bitmask). Sorted in finalize.
-    pub events: Vec<Event>,
+    pub events: EventBuffer,
+    /// Events compressed once `events` reached [`COMPRESSION_THRESHOLD`];
+    /// they precede `events` and are moved back into it in finalize.
+    pub compressed: CompressedEventBuffer,
     /// Window size in microseconds.
     pub window_size_us: i64,
     /// Number of funnel steps (conditions).
     pub num_conditions: usize,
     /// Funnel mode (combinable bitmask).
     pub mode: FunnelMode,
+    /// Duplicate-collapsing interval in microseconds; 0 disables it.
+    pub dedup_within_us: i64,
+    /// Minimum time in microseconds between consecutive steps: entry `k` is
+    /// the least time from the event matching condition `k` to the one
+    /// matching condition `k + 1` (0-based). Empty when no gaps are given.
+    pub min_gaps_us: Vec<i64>,
+    /// Sequence number of each entry in `events` (same index) when a
+    /// sequence-number column is given; empty otherwise.
+    pub seqs: Vec<i64>,
+    /// Whether the window, mode, dedup interval and minimum gaps were set
+    /// from the function arguments. Combine copies them into a state that
+    /// has not set them, so a zero window or the default mode given by the
+    /// caller is never mistaken for a fresh `DuckDB` target.
+    pub params_set: bool,
 }
 
 impl WindowFunnelState {
@@ -231,10 +556,15 @@ impl WindowFunnelState {
     #[must_use]
     pub const fn new() -> Self {
         Self {
-            events: Vec::new(),
+            events: EventBuffer::new(),
+            compressed: CompressedEventBuffer::new(),
             window_size_us: 0,
             num_conditions: 0,
             mode: FunnelMode::DEFAULT,
+            dedup_within_us: 0,
+            min_gaps_us: Vec::new(),
+            seqs: Vec::new(),
+            params_set: false,
         }
     }
 
@@ -250,78 +580,439 @@ impl WindowFunnelState {
         self.num_conditions = num_conditions;
         if event.has_any_condition() {
             self.events.push(event);
+            self.compress_if_full();
+        }
+    }
+
+    /// Adds an event with its sequence number, which orders it among events
+    /// with the same timestamp.
+    ///
+    /// A state takes either only this or only [`update`](Self::update), so
+    /// that `seqs` stays parallel to `events`.
+    pub fn update_with_seq(&mut self, event: Event, seq: i64, num_conditions: usize) {
+        self.num_conditions = num_conditions;
+        if event.has_any_condition() {
+            self.events.push(event);
+            self.seqs.push(seq);
+            self.compress_if_full();
+        }
+    }
+
+    /// Adds a complete list of events, for `window_funnel_from_list`, whose
+    /// events carry condition bitmasks but no step count.
+    ///
+    /// The number of steps becomes one past the highest condition set in any
+    /// event. Steps beyond it match no event and are never reached, so any
+    /// larger count gives the same funnel level.
+    pub fn update_list(&mut self, events: impl IntoIterator<Item = Event>) {
+        let mut seen = 0u64;
+        for event in events {
+            seen |= event.conditions;
+            self.update(event, self.num_conditions);
+        }
+        let steps = (u64::BITS - seen.leading_zeros()) as usize;
+        self.num_conditions = self.num_conditions.max(steps);
+    }
+
+    /// Moves the buffered events into [`compressed`](Self::compressed) once
+    /// there are [`COMPRESSION_THRESHOLD`] of them.
+    fn compress_if_full(&mut self) {
+        if self.events.len() >= COMPRESSION_THRESHOLD {
+            self.compressed.push_block(&self.events);
+            self.events = EventBuffer::new();
         }
     }
 
+    /// Moves the compressed events back in front of the buffered ones.
+    fn decompress(&mut self) {
+        if self.compressed.is_empty() {
+            return;
+        }
+        let mut events = Vec::with_capacity(self.compressed.len() + self.events.len());
+        self.compressed.decompress_into(&mut events);
+        events.extend_from_slice(&self.events);
+        self.events = EventBuffer::from(events);
+        self.compressed = CompressedEventBuffer::new();
+    }
+
     /// Combines two states by concatenating their event lists, returning a new state.
     ///
     /// Events do not need to be in sorted order during combine because
     /// `finalize()` sorts them before scanning.
     #[must_use]
     pub fn combine(&self, other: &Self) -> Self {
-        let mut events = Vec::with_capacity(self.events.len() + other.events.len());
+        if !self.compressed.is_empty() || !other.compressed.is_empty() {
+            let mut combined = self.clone();
+            combined.combine_in_place(other);
+            return combined;
+        }
+        let mut events = EventBuffer::with_capacity(self.events.len() + other.events.len());
         events.extend_from_slice(&self.events);
         events.extend_from_slice(&other.events);
-        // Propagate window_size and mode from whichever state has them set,
-        // matching combine_in_place behavior for DuckDB's zero-initialized targets.
-        let window_size_us = if self.window_size_us != 0 {
-            self.window_size_us
-        } else {
-            other.window_size_us
-        };
-        let mode = if self.mode.is_default() {
-            other.mode
+        // Take the parameters from whichever state has them set, matching
+        // combine_in_place behavior for DuckDB's zero-initialized targets.
+        let params = if self.params_set || !other.params_set {
+            self
         } else {
-            self.mode
+            other
         };
+        let mut seqs = Vec::with_capacity(self.seqs.len() + other.seqs.len());
+        seqs.extend_from_slice(&self.seqs);
+        seqs.extend_from_slice(&other.seqs);
         Self {
             events,
-            window_size_us,
+            compressed: CompressedEventBuffer::new(),
+            window_size_us: params.window_size_us,
             num_conditions: self.num_conditions.max(other.num_conditions),
-            mode,
+            mode: params.mode,
+            dedup_within_us: params.dedup_within_us,
+            min_gaps_us: params.min_gaps_us.clone(),
+            seqs,
+            params_set: params.params_set,
         }
     }
 
     /// Combines another state into `self` in-place by appending its events.
     ///
     /// This is the preferred combine method for sequential (left-fold) chains.
-    /// By extending `self.events` in-place, Vec's doubling growth strategy
+    /// By extending `self.events` in-place, the buffer's doubling growth strategy
     /// provides O(N) amortized total copies for a chain of N single-event
     /// combines, compared to O(N²) when allocating a new Vec per combine.
+    ///
+    /// Compressed events of `other` are appended without decompressing; the
+    /// buffered events of `self` are compressed first to keep input order.
     pub fn combine_in_place(&mut self, other: &Self) {
+        if !other.compressed.is_empty() {
+            self.compressed.push_block(&self.events);
+            self.events = EventBuffer::new();
+            self.compressed.extend_from(&other.compressed);
+        }
         self.events.extend_from_slice(&other.events);
+        self.seqs.extend_from_slice(&other.seqs);
+        self.compress_if_full();
         self.num_conditions = self.num_conditions.max(other.num_conditions);
-        // Propagate window_size and mode from whichever state has them set.
+        // Propagate the parameters from whichever state has them set.
         // DuckDB's segment tree creates fresh (zero-initialized) target states
         // and combines source states into them, so these fields must be propagated.
-        if self.window_size_us == 0 && other.window_size_us != 0 {
+        if !self.params_set && other.params_set {
             self.window_size_us = other.window_size_us;
-        }
-        if self.mode.is_default() && !other.mode.is_default() {
             self.mode = other.mode;
+            self.dedup_within_us = other.dedup_within_us;
+            self.min_gaps_us.clone_from(&other.min_gaps_us);
+            self.params_set = true;
         }
     }
 
+    /// Tag of the exported state format: `WFS` and format version 1.
+    pub const BLOB_TAG: [u8; 4] = *b"WFS\x01";
+
+    /// Exports the state as the bytes returned by `window_funnel_state`:
+    /// the parameters, then every event (compressed or not) in input order,
+    /// then the sequence numbers. See [`crate::common::state_blob`].
+    #[must_use]
+    pub fn to_blob(&self) -> Vec<u8> {
+        let mut events = Vec::with_capacity(self.compressed.len() + self.events.len());
+        self.compressed.decompress_into(&mut events);
+        events.extend_from_slice(&self.events);
+
+        let mut w = BlobWriter::new(Self::BLOB_TAG);
+        w.u8(u8::from(self.params_set));
+        w.i64(self.window_size_us);
+        w.u8(self.num_conditions as u8);
+        w.u16(self.mode.bits());
+        w.u8(self.mode.max_skips());
+        w.u8(self.mode.tie_break_order().code());
+        w.i64(self.dedup_within_us);
+        w.len(self.min_gaps_us.len());
+        for &gap in &self.min_gaps_us {
+            w.i64(gap);
+        }
+        w.len(events.len());
+        for event in &events {
+            w.i64(event.timestamp_us);
+            w.u64(event.conditions);
+        }
+        w.len(self.seqs.len());
+        for &seq in &self.seqs {
+            w.i64(seq);
+        }
+        w.finish()
+    }
+
+    /// Imports a state exported by [`to_blob`](Self::to_blob).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BlobError`] when `bytes` is not a `window_funnel` state of
+    /// this format version, or holds one that no update could produce.
+    pub fn from_blob(bytes: &[u8]) -> Result<Self, BlobError> {
+        let mut r = BlobReader::new(bytes, Self::BLOB_TAG)?;
+        let params_set = match r.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(BlobError::Invalid("parameter flag")),
+        };
+        let window_size_us = r.i64()?;
+        let num_conditions = usize::from(r.u8()?);
+        if num_conditions > 64 {
+            return Err(BlobError::Invalid("step count"));
+        }
+        let bits = r.u16()?;
+        let max_skips = r.u8()?;
+        if max_skips > FunnelMode::MAX_SKIPS {
+            return Err(BlobError::Invalid("skip count"));
+        }
+        let tie_break = TieBreak::from_code(r.u8()?).ok_or(BlobError::Invalid("tie break"))?;
+        let mode = FunnelMode::from_bits(bits)
+            .with(FunnelMode::skip(max_skips))
+            .with(FunnelMode::tie_break(tie_break));
+        let dedup_within_us = r.i64()?;
+        let min_gaps_us = (0..r.len(8)?)
+            .map(|_| r.i64())
+            .collect::<Result<Vec<_>, _>>()?;
+        let events = (0..r.len(16)?)
+            .map(|_| Ok(Event::new(r.i64()?, r.u64()?)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let seqs = (0..r.len(8)?)
+            .map(|_| r.i64())
+            .collect::<Result<Vec<_>, _>>()?;
+        r.finish()?;
+        if !seqs.is_empty() && seqs.len() != events.len() {
+            return Err(BlobError::Invalid("sequence numbers"));
+        }
+
+        let mut state = Self {
+            window_size_us,
+            num_conditions,
+            mode,
+            dedup_within_us,
+            min_gaps_us,
+            seqs,
+            params_set,
+            ..Self::new()
+        };
+        for chunk in events.chunks(COMPRESSION_THRESHOLD) {
+            state.events.extend_from_slice(chunk);
+            state.compress_if_full();
+        }
+        Ok(state)
+    }
+
+    /// Returns true if `self` and `other` can be merged: states with events
+    /// and set parameters must agree on the window, mode, dedup interval,
+    /// minimum gaps and number of steps, and either both or neither carry
+    /// sequence numbers.
+    #[must_use]
+    pub fn same_funnel(&self, other: &Self) -> bool {
+        let params_match = !self.params_set
+            || !other.params_set
+            || (self.window_size_us == other.window_size_us
+                && self.mode == other.mode
+                && self.dedup_within_us == other.dedup_within_us
+                && self.min_gaps_us == other.min_gaps_us);
+        let steps_match = self.num_conditions == 0
+            || other.num_conditions == 0
+            || self.num_conditions == other.num_conditions;
+        let has_events = |s: &Self| !s.events.is_empty() || !s.compressed.is_empty();
+        let seqs_match = !has_events(self)
+            || !has_events(other)
+            || self.seqs.is_empty() == other.seqs.is_empty();
+        params_match && steps_match && seqs_match
+    }
+
     /// Computes the maximum funnel step reached.
     ///
+    /// This is the furthest step of the best chain found by
+    /// [`finalize_mask`](Self::finalize_mask). Without `skip(K)` every step
+    /// up to it was matched.
+    #[must_use]
+    pub fn finalize(&mut self) -> i64 {
+        furthest_step(self.finalize_mask())
+    }
+
+    /// Computes whether the funnel converted, for `conversion_rate`.
+    ///
+    /// Returns `None` when no event matched the entry condition, `Some(1.0)`
+    /// when some entry reached the last step within the window and
+    /// `Some(0.0)` otherwise, so that `AVG` over groups is the conversion
+    /// rate of the groups that entered the funnel.
+    #[must_use]
+    pub fn finalize_conversion(&mut self) -> Option<f64> {
+        match self.finalize() {
+            0 => None,
+            steps if steps == self.num_conditions as i64 => Some(1.0),
+            _ => Some(0.0),
+        }
+    }
+
+    /// Computes the furthest step reached and the number of steps skipped on
+    /// the way to it. The skip count is always 0 without `skip(K)`.
+    #[must_use]
+    pub fn finalize_with_skips(&mut self) -> (i64, i64) {
+        let mask = self.finalize_mask();
+        let steps = furthest_step(mask);
+        (steps, steps - i64::from(mask.count_ones()))
+    }
+
+    /// Computes the furthest step reached and the entry event of the best
+    /// chain, for `window_funnel_by`.
+    ///
+    /// The event is `None` exactly when
+    /// [`finalize_entry_ts`](Self::finalize_entry_ts) is.
+    #[must_use]
+    pub fn finalize_with_entry(&mut self) -> (i64, Option<Event>) {
+        match self.best_chain() {
+            Some(chain) => (
+                furthest_step(chain.mask),
+                Some(self.events[chain.entry_idx]),
+            ),
+            None => (0, None),
+        }
+    }
+
+    /// Computes the timestamp of the entry event of the best chain, for
+    /// `window_funnel_entry_ts`.
+    ///
+    /// This is the chain whose steps [`finalize_mask`](Self::finalize_mask)
+    /// reports. Returns `None` when no event matched the entry condition, or
+    /// when a `strict_order_zero` violation broke the funnel.
+    #[must_use]
+    pub fn finalize_entry_ts(&mut self) -> Option<i64> {
+        let chain = self.best_chain()?;
+        Some(self.events[chain.entry_idx].timestamp_us)
+    }
+
+    /// Computes the time from the entry event to the last step of the best
+    /// chain in microseconds, for `time_to_convert`.
+    ///
+    /// Returns `None` unless the best chain reached the last step, i.e. when
+    /// [`finalize_conversion`](Self::finalize_conversion) is not `Some(1.0)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimestampOverflow`] when the time does not fit in `i64`
+    /// microseconds. Only possible with `strict_time`, which bounds each step
+    /// rather than the whole chain by the window.
+    pub fn finalize_time_to_convert(&mut self) -> Result<Option<i64>, TimestampOverflow> {
+        let Some(chain) = self.best_chain() else {
+            return Ok(None);
+        };
+        if furthest_step(chain.mask) != self.num_conditions as i64 {
+            return Ok(None);
+        }
+        let entry_ts = self.events[chain.entry_idx].timestamp_us;
+        elapsed_micros(entry_ts, self.events[chain.last_step_idx].timestamp_us).map(Some)
+    }
+
+    /// Computes the furthest step that `last` advances a chain to, for
+    /// `funnel_step`: the highest step of any chain whose last matched step is
+    /// `last`, or 0 when `last` does not advance any chain.
+    ///
+    /// `last` must be one of the collected events and is treated as the
+    /// latest of them, after every other event with the same timestamp. A
+    /// chain that already matched an earlier event for `last`'s step does not
+    /// count, so a repeat of a step reports 0.
+    #[must_use]
+    pub fn finalize_step(&mut self, last: Event) -> i64 {
+        if !last.has_any_condition() || self.num_conditions == 0 {
+            return 0;
+        }
+        self.decompress();
+        let mut removed = false;
+        self.events.retain(|event| {
+            let keep = removed || *event != last;
+            removed |= !keep;
+            keep
+        });
+        self.mode.tie_break_order().sort(&mut self.events);
+        self.events.push(last);
+        let last_idx = self.events.len() - 1;
+        (0..self.events.len())
+            .filter(|&i| self.events[i].condition(0))
+            .filter_map(|i| self.scan_funnel(i, self.events[i].timestamp_us))
+            .filter(|&(_, idx)| idx == last_idx)
+            .map(|(reached, _)| furthest_step(reached))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Computes the furthest step reached with statistics of the search, for
+    /// `window_funnel_debug`.
+    ///
+    /// The step and entry timestamp are those of
+    /// [`finalize`](Self::finalize) and
+    /// [`finalize_entry_ts`](Self::finalize_entry_ts).
+    #[must_use]
+    pub fn finalize_stats(&mut self) -> FunnelStats {
+        let (chain, chains_tried) = self.search_chains(false);
+        FunnelStats {
+            step: chain.map_or(0, |chain| furthest_step(chain.mask)),
+            events_scanned: self.events.len(),
+            chains_tried,
+            best_entry_ts: chain.map(|chain| self.events[chain.entry_idx].timestamp_us),
+        }
+    }
+
+    /// Computes the bitmask of funnel steps reached on the best chain.
+    ///
+    /// Bit `k` is set when condition `k + 1` was matched. The best chain is
+    /// the one reaching the furthest step; ties go to the chain with fewer
+    /// skipped steps, then to the earliest entry.
+    #[must_use]
+    pub fn finalize_mask(&mut self) -> u64 {
+        // Only the mask is returned, so dominated entries need not be scanned.
+        self.search_chains(true).0.map_or(0, |chain| chain.mask)
+    }
+
+    /// Finds the best chain: its step bitmask, the index of its entry event
+    /// in the sorted (and deduplicated) events and the index of its last step's
+    /// event.
+    ///
     /// Algorithm:
-    /// 1. Sort events by timestamp
+    /// 1. Sort events by timestamp, ordering ties per [`TieBreak`] (and
+    ///    drop identical events with [`FunnelMode::EVENT_DEDUP`], then
+    ///    collapse duplicates if `dedup_within_us` is set)
     /// 2. For each event matching condition 0 (funnel entry):
     ///    a. Greedily scan forward within the window
     ///    b. Try to match conditions 1, 2, ..., N in order
-    ///    c. Track the maximum step reached
-    /// 3. Return the global maximum across all entry points
+    ///    c. Keep the chain with the most steps reached
+    /// 3. Return the best chain across all entry points
     ///
     /// Time complexity: O(n * k) where n = events, k = conditions.
-    /// In practice, much faster due to early termination.
-    #[must_use]
-    pub fn finalize(&mut self) -> i64 {
+    /// In practice, much faster due to early termination. Groups of at least
+    /// [`INDEXED_SCAN_MIN_EVENTS`] events scan from each entry through
+    /// [`StepPositions`] where the mode allows, visiting only the events that
+    /// can match the next step.
+    fn best_chain(&mut self) -> Option<Chain> {
+        self.search_chains(false).0
+    }
+
+    /// [`best_chain`](Self::best_chain), also returning the number of entry
+    /// events a chain was scanned from.
+    ///
+    /// With `prune`, entries dominated by a later one are skipped when
+    /// [`can_prune_entries`](Self::can_prune_entries) allows it. The best
+    /// chain then reaches the same steps, but may start at a later entry.
+    fn search_chains(&mut self, prune: bool) -> (Option<Chain>, usize) {
+        self.decompress();
         if self.events.is_empty() || self.num_conditions == 0 {
-            return 0;
+            return (None, 0);
         }
 
-        sort_events(&mut self.events);
-        let mut max_step: i64 = 0;
+        self.sort_events();
+        if self.mode.has(FunnelMode::EVENT_DEDUP) {
+            self.dedup_identical();
+        }
+        if self.dedup_within_us > 0 {
+            self.dedup_events();
+        }
+        let mut best: Option<Chain> = None;
+        let mut tried = 0;
+        let prune = prune && self.can_prune_entries();
+        // First events after the current entry matching condition 1 and
+        // condition 0. Both only move forward, so pruning costs O(n) in total.
+        let (mut next_step, mut next_entry) = (0, 0);
+        let positions = self.step_positions();
 
         for i in 0..self.events.len() {
             // Only start from events matching condition 0
@@ -329,17 +1020,158 @@ impl WindowFunnelState {
                 continue;
             }
 
+            // An entry followed by another entry before any candidate for
+            // step 2 is dominated: every chain from it also fits in the
+            // later entry's window, which ends no earlier.
+            if prune {
+                next_step = self.next_with_condition(next_step.max(i + 1), 1);
+                next_entry = self.next_with_condition(next_entry.max(i + 1), 0);
+                if next_entry < next_step {
+                    continue;
+                }
+            }
+
+            tried += 1;
             let entry_ts = self.events[i].timestamp_us;
-            let step = self.scan_funnel(i, entry_ts);
-            max_step = max_step.max(step);
+            // STRICT_ORDER_ZERO violation breaks the whole funnel
+            let scanned = positions.as_ref().map_or_else(
+                || self.scan_funnel(i, entry_ts),
+                |positions| Some(self.scan_indexed(positions, i, entry_ts)),
+            );
+            let Some((reached, last_step_idx)) = scanned else {
+                return (None, tried);
+            };
+            let best_mask = best.map_or(0, |chain| chain.mask);
+            if (furthest_step(reached), reached.count_ones())
+                > (furthest_step(best_mask), best_mask.count_ones())
+            {
+                best = Some(Chain {
+                    mask: reached,
+                    entry_idx: i,
+                    last_step_idx,
+                });
+            }
 
             // Early termination: can't do better than matching all conditions
-            if max_step == self.num_conditions as i64 {
+            if reached.count_ones() as usize == self.num_conditions {
                 break;
             }
         }
 
-        max_step
+        (best, tried)
+    }
+
+    /// Returns whether entries can be pruned by dominance: only in the plain
+    /// default scan, where a chain's steps are bounded by the entry's window
+    /// alone. Modes, `skip(K)` and minimum gaps all depend on where the chain
+    /// entered or on the events between its steps; `EVENT_DEDUP` applies
+    /// before the scan.
+    fn can_prune_entries(&self) -> bool {
+        self.mode.bits() & !FunnelMode::EVENT_DEDUP.bits() == 0
+            && self.mode.max_skips() == 0
+            && self.min_gaps_us.is_empty()
+            && self.num_conditions >= 2
+    }
+
+    /// Builds the [`StepPositions`] of the sorted events when
+    /// [`scan_indexed`](Self::scan_indexed) can replace
+    /// [`scan_funnel`](Self::scan_funnel): the group is large enough, and no
+    /// mode or `skip(K)` reacts to events that do not match the next step.
+    fn step_positions(&self) -> Option<StepPositions> {
+        let flags = FunnelMode::STRICT
+            .with(FunnelMode::STRICT_ORDER)
+            .with(FunnelMode::STRICT_ORDER_ZERO)
+            .with(FunnelMode::ALLOW_REENTRY);
+        if self.events.len() < INDEXED_SCAN_MIN_EVENTS
+            || self.mode.bits() & flags.bits() != 0
+            || self.mode.max_skips() > 0
+        {
+            return None;
+        }
+        Some(StepPositions::new(&self.events, self.num_conditions))
+    }
+
+    /// Returns the index of the first event at or after `from` satisfying
+    /// `condition`, or the number of events if there is none.
+    fn next_with_condition(&self, from: usize, condition: usize) -> usize {
+        self.events[from.min(self.events.len())..]
+            .iter()
+            .position(|event| event.condition(condition))
+            .map_or(self.events.len(), |offset| from + offset)
+    }
+
+    /// Sorts events by timestamp, ordering ties by sequence number when
+    /// `seqs` is set and per the [`TieBreak`] mode otherwise.
+    fn sort_events(&mut self) {
+        if self.seqs.is_empty() {
+            self.mode.tie_break_order().sort(&mut self.events);
+        } else {
+            sort_events_by_seq(&mut self.events, &mut self.seqs);
+        }
+    }
+
+    /// Drops each event whose condition bitmask matches an event kept at most
+    /// `dedup_within_us` earlier, along with its sequence number. Events must
+    /// be sorted; the result is idempotent, so repeated finalize calls are
+    /// unaffected.
+    fn dedup_events(&mut self) {
+        let within = self.dedup_within_us;
+        let mut last_kept: HashMap<u64, i64> = HashMap::new();
+        let keep: Vec<bool> = self
+            .events
+            .iter()
+            .map(|event| match last_kept.get(&event.conditions) {
+                Some(&kept_ts) if !elapsed_exceeds(kept_ts, event.timestamp_us, within) => false,
+                _ => {
+                    last_kept.insert(event.conditions, event.timestamp_us);
+                    true
+                }
+            })
+            .collect();
+        self.retain_events(&keep);
+    }
+
+    /// Drops each event with the same timestamp and condition bitmask as an
+    /// earlier event, along with its sequence number, for
+    /// [`FunnelMode::EVENT_DEDUP`]. Events must be sorted. The positions of
+    /// each run of equal timestamps are sorted by bitmask, making duplicates
+    /// adjacent, and the first of each keeps its place in the tie order.
+    fn dedup_identical(&mut self) {
+        let mut keep = vec![true; self.events.len()];
+        let mut run: Vec<usize> = Vec::new();
+        let mut start = 0;
+        for end in 1..=self.events.len() {
+            if end < self.events.len()
+                && self.events[end].timestamp_us == self.events[start].timestamp_us
+            {
+                continue;
+            }
+            if end - start > 1 {
+                run.clear();
+                run.extend(start..end);
+                run.sort_unstable_by_key(|&i| (self.events[i].conditions, i));
+                for pair in run.windows(2) {
+                    if self.events[pair[0]].conditions == self.events[pair[1]].conditions {
+                        keep[pair[1]] = false;
+                    }
+                }
+            }
+            start = end;
+        }
+        if keep.contains(&false) {
+            self.retain_events(&keep);
+        }
+    }
+
+    /// Keeps the events, and their sequence numbers, whose entry in `keep`
+    /// is true.
+    fn retain_events(&mut self, keep: &[bool]) {
+        let mut kept = keep.iter().copied();
+        self.events.retain(|_| kept.next().unwrap_or(true));
+        if !self.seqs.is_empty() {
+            let mut kept = keep.iter().copied();
+            self.seqs.retain(|_| kept.next().unwrap_or(true));
+        }
     }
 
     /// Scans forward from an entry point trying to match funnel steps.
@@ -348,23 +1180,49 @@ impl WindowFunnelState {
     /// are evaluated in order: `STRICT`, `STRICT_ORDER`, `STRICT_DEDUPLICATION`,
     /// `STRICT_INCREASE`. If any constraint fails, the event is handled per
     /// that constraint's semantics (break, return, continue, or skip).
-    fn scan_funnel(&self, start_idx: usize, entry_ts: i64) -> i64 {
-        let mut current_step: usize = 1; // Already matched step 0
+    ///
+    /// Returns the bitmask of steps reached and the index of the last matched
+    /// step's event, or `None` when a `STRICT_ORDER_ZERO` violation breaks the
+    /// funnel.
+    fn scan_funnel(&self, start_idx: usize, entry_ts: i64) -> Option<(u64, usize)> {
+        let max_skips = usize::from(self.mode.max_skips());
+        let mut current_step = self.entry_step(&self.events[start_idx]);
+        let mut reached = steps_mask(current_step);
+        let mut skipped = 0;
         let mut prev_matched_ts = entry_ts;
+        let mut prev_matched_idx = start_idx;
+        if current_step >= self.num_conditions {
+            return Some((reached, prev_matched_idx));
+        }
 
         for j in (start_idx + 1)..self.events.len() {
             let event = &self.events[j];
 
-            // Check window: event must be within window_size of the ENTRY event
-            if event.timestamp_us - entry_ts > self.window_size_us {
+            // Check window: event must be within window_size of the ENTRY event,
+            // or of the previous matched step with STRICT_TIME. Events are sorted
+            // and no later event can match, so either way the scan ends here.
+            let window_start = if self.mode.has(FunnelMode::STRICT_TIME) {
+                prev_matched_ts
+            } else {
+                entry_ts
+            };
+            if self.window_size_us != UNBOUNDED_WINDOW_US
+                && elapsed_exceeds(window_start, event.timestamp_us, self.window_size_us)
+            {
                 break;
             }
 
             // --- Mode: ALLOW_REENTRY ---
             // If entry condition fires again mid-chain, reset the funnel
             if self.mode.has(FunnelMode::ALLOW_REENTRY) && current_step > 1 && event.condition(0) {
-                current_step = 1;
+                current_step = self.entry_step(event);
+                reached = steps_mask(current_step);
+                skipped = 0;
                 prev_matched_ts = event.timestamp_us;
+                prev_matched_idx = j;
+                if current_step >= self.num_conditions {
+                    return Some((reached, prev_matched_idx));
+                }
                 // Continue scanning from this new entry; don't also try to
                 // match the next step on this same event
                 continue;
@@ -379,8 +1237,10 @@ impl WindowFunnelState {
                 break;
             }
 
-            // --- Mode: STRICT_ORDER ---
-            if self.mode.has(FunnelMode::STRICT_ORDER) {
+            // --- Mode: STRICT_ORDER / STRICT_ORDER_ZERO ---
+            if self.mode.has(FunnelMode::STRICT_ORDER)
+                || self.mode.has(FunnelMode::STRICT_ORDER_ZERO)
+            {
                 let mut earlier_fired = false;
                 for k in 0..current_step {
                     if event.condition(k) {
@@ -389,37 +1249,56 @@ impl WindowFunnelState {
                     }
                 }
                 if earlier_fired {
-                    return current_step as i64;
+                    if self.mode.has(FunnelMode::STRICT_ORDER_ZERO) {
+                        return None;
+                    }
+                    return Some((reached, prev_matched_idx));
                 }
             }
 
+            // Step this event would match: the next expected one or, with
+            // SKIP(K), the first later step within the remaining skip budget.
+            let Some(target) = self.next_match(event, current_step, max_skips - skipped) else {
+                continue;
+            };
+
             // --- Mode: STRICT_DEDUPLICATION ---
             if self.mode.has(FunnelMode::STRICT_DEDUPLICATION)
                 && event.timestamp_us == prev_matched_ts
-                && event.condition(current_step)
             {
                 continue;
             }
 
             // --- Mode: STRICT_INCREASE ---
-            if self.mode.has(FunnelMode::STRICT_INCREASE)
-                && event.condition(current_step)
-                && event.timestamp_us <= prev_matched_ts
-            {
+            if self.mode.has(FunnelMode::STRICT_INCREASE) && event.timestamp_us <= prev_matched_ts {
+                continue;
+            }
+
+            // --- Minimum step gap ---
+            if !self.min_gap_met(target, prev_matched_ts, event.timestamp_us) {
                 continue;
             }
 
+            // --- Mode: SKIP(K) ---
+            skipped += target - current_step;
+            current_step = target;
+
             // Check if this event matches the next expected condition.
             // In default mode, a single event can advance multiple steps
             // (e.g., an event satisfying both cond2 and cond3 advances 2 steps).
-            // STRICT_ONCE limits this to at most 1 step per event.
-            while event.condition(current_step) {
+            // STRICT_ONCE limits this to at most 1 step per event, and a
+            // minimum gap on the following step stops it.
+            while event.condition(current_step)
+                && self.min_gap_met(current_step, prev_matched_ts, event.timestamp_us)
+            {
+                reached |= 1 << current_step;
                 current_step += 1;
                 prev_matched_ts = event.timestamp_us;
+                prev_matched_idx = j;
 
                 // Matched all conditions
                 if current_step >= self.num_conditions {
-                    return self.num_conditions as i64;
+                    return Some((reached, prev_matched_idx));
                 }
 
                 // --- Mode: STRICT_ONCE ---
@@ -430,28 +1309,213 @@ impl WindowFunnelState {
             }
         }
 
-        current_step as i64
+        Some((reached, prev_matched_idx))
     }
-}
 
-impl Default for WindowFunnelState {
-    fn default() -> Self {
-        Self::new()
+    /// [`scan_funnel`](Self::scan_funnel) visiting only the events that
+    /// satisfy the next step's condition, found in `positions`.
+    ///
+    /// Only valid when [`step_positions`](Self::step_positions) built
+    /// `positions`: the modes left skip an event that does not match the next
+    /// step, and the window bound holds for every event before the first
+    /// candidate outside it, so jumping between candidates finds the same
+    /// chain. Each step costs a binary search instead of a walk over the
+    /// events in between, so many entries no longer rescan their windows.
+    fn scan_indexed(
+        &self,
+        positions: &StepPositions,
+        start_idx: usize,
+        entry_ts: i64,
+    ) -> (u64, usize) {
+        let mut current_step = self.entry_step(&self.events[start_idx]);
+        let mut reached = steps_mask(current_step);
+        let mut prev_matched_ts = entry_ts;
+        let mut prev_matched_idx = start_idx;
+
+        while current_step < self.num_conditions {
+            let candidates = positions.of(current_step);
+            let mut cursor = candidates.partition_point(|&j| j <= prev_matched_idx);
+            let event = loop {
+                let Some(&j) = candidates.get(cursor) else {
+                    return (reached, prev_matched_idx);
+                };
+                cursor += 1;
+                let event = &self.events[j];
+                let window_start = if self.mode.has(FunnelMode::STRICT_TIME) {
+                    prev_matched_ts
+                } else {
+                    entry_ts
+                };
+                if self.window_size_us != UNBOUNDED_WINDOW_US
+                    && elapsed_exceeds(window_start, event.timestamp_us, self.window_size_us)
+                {
+                    return (reached, prev_matched_idx);
+                }
+                if self.mode.has(FunnelMode::STRICT_DEDUPLICATION)
+                    && event.timestamp_us == prev_matched_ts
+                {
+                    continue;
+                }
+                if self.mode.has(FunnelMode::STRICT_INCREASE)
+                    && event.timestamp_us <= prev_matched_ts
+                {
+                    continue;
+                }
+                if self.min_gap_met(current_step, prev_matched_ts, event.timestamp_us) {
+                    prev_matched_idx = j;
+                    break event;
+                }
+            };
+
+            // As in scan_funnel, one event may complete several steps
+            while current_step < self.num_conditions
+                && event.condition(current_step)
+                && self.min_gap_met(current_step, prev_matched_ts, event.timestamp_us)
+            {
+                reached |= 1 << current_step;
+                current_step += 1;
+                prev_matched_ts = event.timestamp_us;
+                if self.mode.has(FunnelMode::STRICT_ONCE) {
+                    break;
+                }
+            }
+        }
+
+        (reached, prev_matched_idx)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Returns the step `event` can match next: `current_step` if it satisfies
+    /// that condition, otherwise the first later step it satisfies at most
+    /// `budget` steps ahead.
+    fn next_match(&self, event: &Event, current_step: usize, budget: usize) -> Option<usize> {
+        let last = (current_step + budget).min(self.num_conditions - 1);
+        (current_step..=last).find(|&step| event.condition(step))
+    }
 
-    fn make_event(ts: i64, conds: &[bool]) -> Event {
-        Event::from_bools(ts, conds)
+    /// Returns whether an event at `to` may match condition `step` after the
+    /// previous step matched at `from`: at least the step's minimum gap
+    /// must have passed.
+    fn min_gap_met(&self, step: usize, from: i64, to: i64) -> bool {
+        self.min_gaps_us
+            .get(step - 1)
+            .is_none_or(|&gap| elapsed_wide(from, to) >= i128::from(gap))
     }
 
-    #[test]
-    fn test_empty_state() {
-        let mut state = WindowFunnelState::new();
-        assert_eq!(state.finalize(), 0);
+    /// Returns the funnel step reached by an entry event (one satisfying
+    /// condition 0).
+    ///
+    /// Without `ENTRY_ADVANCES` the entry event only ever completes step 1.
+    /// With it, the entry event also completes each following condition it
+    /// satisfies, stopping at the first one it does not or whose minimum gap
+    /// is positive.
+    fn entry_step(&self, entry: &Event) -> usize {
+        let mut step = 1;
+        let ts = entry.timestamp_us;
+        if self.mode.has(FunnelMode::ENTRY_ADVANCES) && !self.mode.has(FunnelMode::STRICT_ONCE) {
+            while step < self.num_conditions
+                && entry.condition(step)
+                && self.min_gap_met(step, ts, ts)
+            {
+                step += 1;
+            }
+        }
+        step
+    }
+}
+
+/// Statistics of a funnel search returned by
+/// [`WindowFunnelState::finalize_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunnelStats {
+    /// Furthest step reached, as [`WindowFunnelState::finalize`] returns.
+    pub step: i64,
+    /// Events the scan ran over: those with at least one condition set,
+    /// after `dedup_within`.
+    pub events_scanned: usize,
+    /// Entry events a chain was scanned from. Fewer than the entry events
+    /// when a chain reaches every step, or a `strict_order_zero` violation
+    /// ends the search.
+    pub chains_tried: usize,
+    /// Timestamp of the best chain's entry event; `None` when `step` is 0.
+    pub best_entry_ts: Option<i64>,
+}
+
+/// Positions in the sorted events of the events satisfying each funnel
+/// condition, in ascending order, for
+/// [`WindowFunnelState::scan_indexed`].
+///
+/// Built once per finalize in O(n + set condition bits), so that scanning
+/// from every entry costs O(k log n) per chain rather than a walk over the
+/// entry's window.
+#[derive(Debug)]
+struct StepPositions {
+    positions: Vec<Vec<usize>>,
+}
+
+impl StepPositions {
+    /// Indexes conditions `0..num_conditions` of `events`.
+    fn new(events: &[Event], num_conditions: usize) -> Self {
+        let mut positions = vec![Vec::new(); num_conditions];
+        let relevant = steps_mask(num_conditions);
+        for (j, event) in events.iter().enumerate() {
+            let mut bits = event.conditions & relevant;
+            while bits != 0 {
+                positions[bits.trailing_zeros() as usize].push(j);
+                bits &= bits - 1;
+            }
+        }
+        Self { positions }
+    }
+
+    /// Positions of the events satisfying condition `step`.
+    fn of(&self, step: usize) -> &[usize] {
+        &self.positions[step]
+    }
+}
+
+/// A chain found by `best_chain`.
+#[derive(Debug, Clone, Copy)]
+struct Chain {
+    /// Steps reached, one bit per condition.
+    mask: u64,
+    /// Index of the entry event in the sorted events.
+    entry_idx: usize,
+    /// Index of the last matched step's event in the sorted events.
+    last_step_idx: usize,
+}
+
+/// Furthest step set in a reached-steps bitmask (0 for an empty mask).
+const fn furthest_step(mask: u64) -> i64 {
+    64 - mask.leading_zeros() as i64
+}
+
+/// Bitmask with the lowest `steps` bits set (steps 1 through `steps` reached).
+const fn steps_mask(steps: usize) -> u64 {
+    if steps >= 64 {
+        u64::MAX
+    } else {
+        (1 << steps) - 1
+    }
+}
+
+impl Default for WindowFunnelState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_event(ts: i64, conds: &[bool]) -> Event {
+        Event::from_bools(ts, conds)
+    }
+
+    #[test]
+    fn test_empty_state() {
+        let mut state = WindowFunnelState::new();
+        assert_eq!(state.finalize(), 0);
     }
 
     #[test]
@@ -713,6 +1777,29 @@ mod tests {
         assert_eq!(state.finalize(), 1);
     }
 
+    #[test]
+    fn test_unbounded_window() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = UNBOUNDED_WINDOW_US;
+        state.update(make_event(-i64::MAX, &[true, false, false]), 3);
+        state.update(make_event(0, &[false, true, false]), 3);
+        state.update(make_event(i64::MAX, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_unbounded_window_propagates_in_combine() {
+        let mut source = WindowFunnelState::new();
+        source.window_size_us = UNBOUNDED_WINDOW_US;
+        source.params_set = true;
+        source.update(make_event(0, &[true, false]), 2);
+        source.update(make_event(400 * 86_400_000_000, &[false, true]), 2);
+        let mut target = WindowFunnelState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.window_size_us, UNBOUNDED_WINDOW_US);
+        assert_eq!(target.finalize(), 2);
+    }
+
     #[test]
     fn test_combine_empty_states() {
         let a = WindowFunnelState::new();
@@ -794,6 +1881,26 @@ mod tests {
         assert_eq!(a.events.len(), 1);
     }
 
+    #[test]
+    fn test_combine_small_states_stay_inline() {
+        let mut a = WindowFunnelState::new();
+        a.update(make_event(0, &[true, false]), 2);
+        let mut b = WindowFunnelState::new();
+        b.update(make_event(1, &[false, true]), 2);
+
+        a.combine_in_place(&b);
+        assert!(a.events.is_inline());
+
+        // Growing past the inline capacity promotes without losing events
+        for ts in 2..8 {
+            a.update(make_event(ts, &[false, true]), 2);
+        }
+        assert!(!a.events.is_inline());
+        assert_eq!(a.events.len(), 8);
+        a.window_size_us = 100;
+        assert_eq!(a.finalize(), 2);
+    }
+
     // --- Mutation testing coverage: finalize edge cases ---
 
     #[test]
@@ -927,201 +2034,823 @@ mod tests {
         assert_eq!(state.finalize(), 3);
     }
 
-    // --- FunnelMode bitmask tests ---
-
     #[test]
-    fn test_funnel_mode_default_is_zero() {
-        assert_eq!(FunnelMode::DEFAULT.bits(), 0);
-        assert!(FunnelMode::DEFAULT.is_default());
+    fn test_finalize_mask_partial_funnel() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(10_000_000, &[false, true, false]), 3);
+        assert_eq!(state.finalize_mask(), 0b011);
+        assert_eq!(state.finalize(), 2);
     }
 
     #[test]
-    fn test_funnel_mode_individual_flags() {
-        assert_eq!(FunnelMode::STRICT.bits(), 0x01);
-        assert_eq!(FunnelMode::STRICT_ORDER.bits(), 0x02);
-        assert_eq!(FunnelMode::STRICT_DEDUPLICATION.bits(), 0x04);
-        assert_eq!(FunnelMode::STRICT_INCREASE.bits(), 0x08);
-        assert_eq!(FunnelMode::STRICT_ONCE.bits(), 0x10);
-        assert_eq!(FunnelMode::ALLOW_REENTRY.bits(), 0x20);
+    fn test_finalize_mask_empty_and_complete() {
+        let mut state = WindowFunnelState::new();
+        assert_eq!(state.finalize_mask(), 0);
+        state.window_size_us = 60_000_000;
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(1_000_000, &[false, true]), 2);
+        assert_eq!(state.finalize_mask(), 0b11);
     }
 
     #[test]
-    fn test_funnel_mode_combinable() {
-        let mode = FunnelMode::STRICT.with(FunnelMode::STRICT_INCREASE);
-        assert!(mode.has(FunnelMode::STRICT));
-        assert!(mode.has(FunnelMode::STRICT_INCREASE));
-        assert!(!mode.has(FunnelMode::STRICT_ORDER));
-        assert!(!mode.is_default());
+    fn test_finalize_mask_keeps_best_chain() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        // Later entry reaches step 3; earlier one only step 1.
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(200_000_000, &[true, false, false]), 3);
+        state.update(make_event(210_000_000, &[false, true, false]), 3);
+        state.update(make_event(220_000_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize_mask(), 0b111);
     }
 
     #[test]
-    fn test_funnel_mode_has_self() {
-        // Each flag should contain itself
-        let flags = [
-            FunnelMode::STRICT,
-            FunnelMode::STRICT_ORDER,
-            FunnelMode::STRICT_DEDUPLICATION,
-            FunnelMode::STRICT_INCREASE,
-            FunnelMode::STRICT_ONCE,
-            FunnelMode::ALLOW_REENTRY,
-        ];
-        for flag in flags {
-            assert!(flag.has(flag));
+    fn test_finalize_mask_prunes_dominated_entries() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        // Ten entries a second apart, then steps 2 and 3: only the last
+        // entry needs a scan, and it reaches what every other entry would.
+        for i in 0..10 {
+            state.update(make_event(i * 1_000_000, &[true, false, false]), 3);
         }
+        state.update(make_event(30_000_000, &[false, true, false]), 3);
+        state.update(make_event(65_000_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize_mask(), 0b111);
+        // The unpruned search still reports the earliest complete chain,
+        // after scanning from six entries.
+        assert_eq!(state.finalize_entry_ts(), Some(5_000_000));
+        assert_eq!(state.finalize_stats().chains_tried, 6);
     }
 
     #[test]
-    fn test_funnel_mode_from_bits_roundtrip() {
-        let mode = FunnelMode::from_bits(0x13);
-        assert!(mode.has(FunnelMode::STRICT));
-        assert!(mode.has(FunnelMode::STRICT_ORDER));
-        assert!(mode.has(FunnelMode::STRICT_ONCE));
-        assert!(!mode.has(FunnelMode::STRICT_DEDUPLICATION));
-        assert_eq!(mode.bits(), 0x13);
+    fn test_finalize_mask_pruning_matches_full_search() {
+        let mut x: u64 = 7;
+        for round in 0..200 {
+            let mut state = WindowFunnelState::new();
+            state.window_size_us = 20;
+            let mut ts = 0;
+            for _ in 0..60 {
+                x = x
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                ts += ((x >> 33) % 6) as i64;
+                state.update(Event::new(ts, (x >> 40) & 0b1111), 4);
+            }
+            let full = state.clone().best_chain().map_or(0, |chain| chain.mask);
+            assert_eq!(state.finalize_mask(), full, "round {round}");
+        }
     }
 
     #[test]
-    fn test_funnel_mode_parse_mode_str() {
-        assert_eq!(
-            FunnelMode::parse_mode_str("strict"),
-            Some(FunnelMode::STRICT)
-        );
-        assert_eq!(
-            FunnelMode::parse_mode_str("strict_order"),
-            Some(FunnelMode::STRICT_ORDER)
-        );
-        // strict_deduplication is a ClickHouse alias for strict
-        assert_eq!(
-            FunnelMode::parse_mode_str("strict_deduplication"),
-            Some(FunnelMode::STRICT)
-        );
-        // timestamp_dedup is our extension mode
-        assert_eq!(
-            FunnelMode::parse_mode_str("timestamp_dedup"),
-            Some(FunnelMode::STRICT_DEDUPLICATION)
-        );
-        assert_eq!(
-            FunnelMode::parse_mode_str("strict_increase"),
-            Some(FunnelMode::STRICT_INCREASE)
-        );
-        assert_eq!(
-            FunnelMode::parse_mode_str("strict_once"),
-            Some(FunnelMode::STRICT_ONCE)
-        );
-        assert_eq!(
-            FunnelMode::parse_mode_str("allow_reentry"),
-            Some(FunnelMode::ALLOW_REENTRY)
-        );
-        assert_eq!(FunnelMode::parse_mode_str("unknown"), None);
-        assert_eq!(FunnelMode::parse_mode_str(""), None);
+    fn test_indexed_scan_matches_linear_scan() {
+        let modes = [
+            FunnelMode::DEFAULT,
+            FunnelMode::STRICT_DEDUPLICATION,
+            FunnelMode::STRICT_INCREASE,
+            FunnelMode::STRICT_ONCE.with(FunnelMode::ENTRY_ADVANCES),
+            FunnelMode::STRICT_TIME,
+            FunnelMode::ENTRY_ADVANCES.with(FunnelMode::STRICT_INCREASE),
+        ];
+        let mut x: u64 = 11;
+        for round in 0..300 {
+            let mut state = WindowFunnelState::new();
+            state.window_size_us = if round % 10 == 0 {
+                UNBOUNDED_WINDOW_US
+            } else {
+                25
+            };
+            state.mode = modes[round % modes.len()];
+            if round % 4 == 1 {
+                state.min_gaps_us = vec![0, 3, 0];
+            }
+            let mut ts = 0;
+            for _ in 0..INDEXED_SCAN_MIN_EVENTS + 40 {
+                x = x
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                ts += ((x >> 33) % 4) as i64;
+                state.update(Event::new(ts, (x >> 40) & 0b1_1111), 4);
+            }
+            state.sort_events();
+            let positions = state.step_positions().unwrap();
+            for i in (0..state.events.len()).filter(|&i| state.events[i].condition(0)) {
+                let entry_ts = state.events[i].timestamp_us;
+                assert_eq!(
+                    Some(state.scan_indexed(&positions, i, entry_ts)),
+                    state.scan_funnel(i, entry_ts),
+                    "round {round}, entry {i}"
+                );
+            }
+        }
     }
 
     #[test]
-    fn test_funnel_mode_display() {
-        assert_eq!(FunnelMode::DEFAULT.to_string(), "default");
-        assert_eq!(FunnelMode::STRICT.to_string(), "strict");
-        assert_eq!(
-            FunnelMode::STRICT
-                .with(FunnelMode::STRICT_INCREASE)
-                .to_string(),
-            "strict+strict_increase"
-        );
+    fn test_indexed_scan_only_for_large_groups_and_local_modes() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        for i in 0..INDEXED_SCAN_MIN_EVENTS as i64 {
+            state.update(make_event(i, &[true, i % 5 == 0, i == 63]), 3);
+        }
+        assert!(state.step_positions().is_some());
+        for mode in [
+            FunnelMode::STRICT,
+            FunnelMode::STRICT_ORDER,
+            FunnelMode::STRICT_ORDER_ZERO,
+            FunnelMode::ALLOW_REENTRY,
+            FunnelMode::skip(1),
+        ] {
+            state.mode = mode;
+            assert!(state.step_positions().is_none(), "{mode}");
+        }
+        state.mode = FunnelMode::DEFAULT;
+        assert_eq!(state.finalize(), 3);
+
+        let mut small = WindowFunnelState::new();
+        for i in 1..INDEXED_SCAN_MIN_EVENTS as i64 {
+            small.update(make_event(i, &[true, true]), 2);
+        }
+        assert!(small.step_positions().is_none());
     }
 
     #[test]
-    fn test_funnel_mode_with_is_commutative() {
-        let a = FunnelMode::STRICT.with(FunnelMode::STRICT_ORDER);
-        let b = FunnelMode::STRICT_ORDER.with(FunnelMode::STRICT);
-        assert_eq!(a, b);
+    fn test_indexed_scan_many_entries() {
+        // Every event re-enters; only the last few reach the later steps
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 100;
+        for i in 0..10_000 {
+            state.update(Event::new(i, 0b001), 3);
+        }
+        state.update(Event::new(10_000, 0b010), 3);
+        state.update(Event::new(10_001, 0b100), 3);
+        let debug = state.clone().finalize_stats();
+        assert_eq!(debug.step, 3);
+        assert_eq!(debug.best_entry_ts, Some(9_901));
+        assert_eq!(debug.chains_tried, 9_902);
+        assert_eq!(state.finalize_mask(), 0b111);
     }
 
-    // --- parse_modes tests ---
-
     #[test]
-    fn test_parse_modes_empty_string() {
-        assert_eq!(FunnelMode::parse_modes("").unwrap(), FunnelMode::DEFAULT);
+    fn test_finalize_mask_sixty_four_steps() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.update(Event::new(0, u64::MAX), 64);
+        state.mode = FunnelMode::ENTRY_ADVANCES;
+        assert_eq!(state.finalize_mask(), u64::MAX);
+        assert_eq!(state.finalize(), 64);
     }
 
     #[test]
-    fn test_parse_modes_whitespace_only() {
-        assert_eq!(FunnelMode::parse_modes("  ").unwrap(), FunnelMode::DEFAULT);
+    fn test_finalize_mask_strict_order_zero_violation() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::STRICT_ORDER_ZERO;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1_000_000, &[false, true, false]), 3);
+        state.update(make_event(2_000_000, &[true, false, false]), 3);
+        assert_eq!(state.finalize_mask(), 0);
     }
 
     #[test]
-    fn test_parse_modes_single() {
-        assert_eq!(
-            FunnelMode::parse_modes("strict").unwrap(),
-            FunnelMode::STRICT
-        );
+    fn test_finalize_conversion() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        assert_eq!(state.finalize_conversion(), None);
+        // Target without an entry: not in the denominator
+        state.update(make_event(0, &[false, true]), 2);
+        assert_eq!(state.finalize_conversion(), None);
+        state.update(make_event(10_000_000, &[true, false]), 2);
+        assert_eq!(state.finalize_conversion(), Some(0.0));
+        state.update(make_event(20_000_000, &[false, true]), 2);
+        assert_eq!(state.finalize_conversion(), Some(1.0));
     }
 
     #[test]
-    fn test_parse_modes_two_comma_separated() {
-        let mode = FunnelMode::parse_modes("strict_increase, strict_once").unwrap();
-        assert!(mode.has(FunnelMode::STRICT_INCREASE));
-        assert!(mode.has(FunnelMode::STRICT_ONCE));
-        assert!(!mode.has(FunnelMode::STRICT));
+    fn test_finalize_conversion_outside_window() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(61_000_000, &[false, true]), 2);
+        assert_eq!(state.finalize_conversion(), Some(0.0));
+        // A later entry within the window of the target converts
+        state.update(make_event(30_000_000, &[true, false]), 2);
+        assert_eq!(state.finalize_conversion(), Some(1.0));
     }
 
     #[test]
-    fn test_parse_modes_no_whitespace() {
-        let mode = FunnelMode::parse_modes("strict,strict_order").unwrap();
-        assert!(mode.has(FunnelMode::STRICT));
-        assert!(mode.has(FunnelMode::STRICT_ORDER));
+    fn test_finalize_entry_ts_of_best_chain() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        assert_eq!(state.finalize_entry_ts(), None);
+        state.update(make_event(0, &[false, true, false]), 3);
+        assert_eq!(state.finalize_entry_ts(), None);
+
+        // Entry at 10s reaches step 2; entry at 100s reaches step 3.
+        state.update(make_event(10_000_000, &[true, false, false]), 3);
+        state.update(make_event(20_000_000, &[false, true, false]), 3);
+        state.update(make_event(100_000_000, &[true, false, false]), 3);
+        state.update(make_event(110_000_000, &[false, true, false]), 3);
+        assert_eq!(state.finalize_entry_ts(), Some(10_000_000));
+        state.update(make_event(120_000_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize_entry_ts(), Some(100_000_000));
+        assert_eq!(state.finalize(), 3);
     }
 
     #[test]
-    fn test_parse_modes_extra_whitespace() {
-        let mode = FunnelMode::parse_modes("  strict_increase ,  strict_once  ").unwrap();
-        assert!(mode.has(FunnelMode::STRICT_INCREASE));
-        assert!(mode.has(FunnelMode::STRICT_ONCE));
+    fn test_finalize_entry_ts_ties_go_to_earliest_entry() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(5_000_000, &[true, false]), 2);
+        assert_eq!(state.finalize_entry_ts(), Some(0));
     }
 
     #[test]
-    fn test_parse_modes_all_clickhouse_modes() {
-        // ClickHouse-compatible modes: strict_deduplication is an alias for strict
-        let mode = FunnelMode::parse_modes(
-            "strict, strict_order, strict_deduplication, strict_increase, strict_once, allow_reentry",
-        )
-        .unwrap();
-        assert!(mode.has(FunnelMode::STRICT)); // both 'strict' and 'strict_deduplication' set this
-        assert!(mode.has(FunnelMode::STRICT_ORDER));
-        assert!(!mode.has(FunnelMode::STRICT_DEDUPLICATION)); // not set by ClickHouse mode names
-        assert!(mode.has(FunnelMode::STRICT_INCREASE));
-        assert!(mode.has(FunnelMode::STRICT_ONCE));
-        assert!(mode.has(FunnelMode::ALLOW_REENTRY));
+    fn test_finalize_entry_ts_strict_order_zero_violation() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::STRICT_ORDER_ZERO;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1_000_000, &[false, true, false]), 3);
+        state.update(make_event(2_000_000, &[true, false, false]), 3);
+        assert_eq!(state.finalize(), 0);
+        assert_eq!(state.finalize_entry_ts(), None);
     }
 
     #[test]
-    fn test_parse_modes_all_modes_including_extensions() {
-        // All modes including our extension mode
-        let mode = FunnelMode::parse_modes(
-            "strict, strict_order, timestamp_dedup, strict_increase, strict_once, allow_reentry",
-        )
-        .unwrap();
-        assert!(mode.has(FunnelMode::STRICT));
-        assert!(mode.has(FunnelMode::STRICT_ORDER));
-        assert!(mode.has(FunnelMode::STRICT_DEDUPLICATION));
-        assert!(mode.has(FunnelMode::STRICT_INCREASE));
-        assert!(mode.has(FunnelMode::STRICT_ONCE));
-        assert!(mode.has(FunnelMode::ALLOW_REENTRY));
+    fn test_finalize_time_to_convert() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        assert_eq!(state.finalize_time_to_convert(), Ok(None));
+        state.update(make_event(10_000_000, &[true, false]), 2);
+        assert_eq!(state.finalize_time_to_convert(), Ok(None));
+        // Greedy: the first target after the entry ends the chain
+        state.update(make_event(25_000_000, &[false, true]), 2);
+        state.update(make_event(40_000_000, &[false, true]), 2);
+        assert_eq!(state.finalize_time_to_convert(), Ok(Some(15_000_000)));
+        assert_eq!(state.finalize_conversion(), Some(1.0));
     }
 
     #[test]
-    fn test_parse_modes_invalid_returns_err() {
-        let err = FunnelMode::parse_modes("strict, invalid_mode").unwrap_err();
-        assert_eq!(err, "invalid_mode");
+    fn test_finalize_time_to_convert_uses_best_chain() {
+        // The first entry times out; the converting chain starts at 100s.
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(10_000_000, &[false, true, false]), 3);
+        state.update(make_event(100_000_000, &[true, false, false]), 3);
+        state.update(make_event(101_000_000, &[false, true, false]), 3);
+        state.update(make_event(130_000_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize_time_to_convert(), Ok(Some(30_000_000)));
     }
 
     #[test]
-    fn test_parse_modes_trailing_comma() {
-        // Trailing comma produces an empty token which is skipped
-        let mode = FunnelMode::parse_modes("strict,").unwrap();
-        assert_eq!(mode, FunnelMode::STRICT);
+    fn test_finalize_time_to_convert_same_event() {
+        // An entry event satisfying both conditions converts in zero time
+        // with entry_advances.
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::ENTRY_ADVANCES;
+        state.update(make_event(5_000_000, &[true, true]), 2);
+        assert_eq!(state.finalize_time_to_convert(), Ok(Some(0)));
     }
 
     #[test]
-    fn test_parse_modes_duplicate_mode() {
+    fn test_extreme_timestamps_do_not_wrap() {
+        // -infinity to infinity wraps to a negative gap with raw subtraction
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.update(make_event(-i64::MAX, &[true, false]), 2);
+        state.update(make_event(i64::MAX, &[false, true]), 2);
+        assert_eq!(state.finalize(), 1);
+
+        // strict_time bounds each step, so the whole chain can overflow
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = i64::MAX;
+        state.mode = FunnelMode::STRICT_TIME;
+        state.update(make_event(-i64::MAX, &[true, false, false]), 3);
+        state.update(make_event(0, &[false, true, false]), 3);
+        state.update(make_event(i64::MAX, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+        assert_eq!(state.finalize_time_to_convert(), Err(TimestampOverflow));
+    }
+
+    // --- skip(K) tests ---
+
+    fn skip_state(k: u8) -> WindowFunnelState {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::skip(k);
+        state.params_set = true;
+        state
+    }
+
+    #[test]
+    fn test_skip_credits_later_step() {
+        let mut state = skip_state(1);
+        state.update(make_event(0, &[true, false, false, false]), 4);
+        state.update(make_event(1_000_000, &[false, false, true, false]), 4);
+        state.update(make_event(2_000_000, &[false, false, false, true]), 4);
+        assert_eq!(state.finalize_mask(), 0b1101);
+        assert_eq!(state.finalize(), 4);
+        assert_eq!(state.finalize_with_skips(), (4, 1));
+    }
+
+    #[test]
+    fn test_skip_budget_is_total_not_per_step() {
+        let mut state = skip_state(1);
+        state.update(make_event(0, &[true, false, false, false, false]), 5);
+        state.update(
+            make_event(1_000_000, &[false, false, true, false, false]),
+            5,
+        );
+        state.update(
+            make_event(2_000_000, &[false, false, false, false, true]),
+            5,
+        );
+        // Reaching step 5 would need a second skip.
+        assert_eq!(state.finalize_with_skips(), (3, 1));
+    }
+
+    #[test]
+    fn test_skip_zero_is_default() {
+        let mut state = skip_state(0);
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1_000_000, &[false, false, true]), 3);
+        assert!(state.mode.is_default());
+        assert_eq!(state.finalize_with_skips(), (1, 0));
+    }
+
+    #[test]
+    fn test_skip_prefers_next_step_over_skipping() {
+        let mut state = skip_state(2);
+        // Event satisfies both step 2 and step 3: it advances through both
+        // without skipping.
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1_000_000, &[false, true, true]), 3);
+        assert_eq!(state.finalize_with_skips(), (3, 0));
+    }
+
+    #[test]
+    fn test_skip_tie_prefers_fewer_skips() {
+        let mut state = skip_state(1);
+        // First entry reaches step 3 by skipping step 2.
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1_000_000, &[false, false, true]), 3);
+        // Second entry reaches step 3 without skipping.
+        state.update(make_event(100_000_000, &[true, false, false]), 3);
+        state.update(make_event(101_000_000, &[false, true, false]), 3);
+        state.update(make_event(102_000_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize_mask(), 0b111);
+        assert_eq!(state.finalize_with_skips(), (3, 0));
+    }
+
+    #[test]
+    fn test_skip_respects_window() {
+        let mut state = skip_state(1);
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(120_000_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize_with_skips(), (1, 0));
+    }
+
+    #[test]
+    fn test_skip_combines_with_strict_increase() {
+        let mut state = skip_state(1);
+        state.mode = state.mode.with(FunnelMode::STRICT_INCREASE);
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(0, &[false, false, true]), 3);
+        assert_eq!(state.finalize_with_skips(), (1, 0));
+    }
+
+    #[test]
+    fn test_parse_skip_mode() {
+        assert_eq!(
+            FunnelMode::parse_mode_str("skip(2)"),
+            Some(FunnelMode::skip(2))
+        );
+        assert_eq!(
+            FunnelMode::parse_mode_str("skip( 3 )"),
+            Some(FunnelMode::skip(3))
+        );
+        assert_eq!(FunnelMode::parse_mode_str("skip(64)"), None);
+        assert_eq!(FunnelMode::parse_mode_str("skip(-1)"), None);
+        assert_eq!(FunnelMode::parse_mode_str("skip()"), None);
+        assert_eq!(FunnelMode::parse_mode_str("skip"), None);
+        let mode = FunnelMode::parse_modes("strict_once, skip(2)").unwrap();
+        assert!(mode.has(FunnelMode::STRICT_ONCE));
+        assert_eq!(mode.max_skips(), 2);
+        assert_eq!(mode.to_string(), "strict_once+skip(2)");
+        assert_eq!(FunnelMode::skip(1).to_string(), "skip(1)");
+        assert_eq!(FunnelMode::skip(200).max_skips(), FunnelMode::MAX_SKIPS);
+    }
+
+    #[test]
+    fn test_combine_propagates_skip_mode() {
+        let mut source = skip_state(2);
+        source.update(make_event(0, &[true, false, false]), 3);
+        let mut target = WindowFunnelState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.mode.max_skips(), 2);
+    }
+
+    fn tie_break_state(tie_break: TieBreak, events: &[Event]) -> WindowFunnelState {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::tie_break(tie_break);
+        state.params_set = true;
+        for &event in events {
+            state.update(event, 3);
+        }
+        state
+    }
+
+    #[test]
+    fn test_tie_break_condition_ignores_input_order() {
+        let entry = make_event(0, &[true, false, false]);
+        let second = make_event(0, &[false, true, false]);
+        let third = make_event(10, &[false, false, true]);
+        for events in [[entry, second, third], [second, entry, third]] {
+            let mut state = tie_break_state(TieBreak::Condition, &events);
+            assert_eq!(state.finalize(), 3, "{events:?}");
+        }
+    }
+
+    #[test]
+    fn test_tie_break_input_keeps_input_order() {
+        let entry = make_event(0, &[true, false, false]);
+        let second = make_event(0, &[false, true, false]);
+        let third = make_event(10, &[false, false, true]);
+        let mut state = tie_break_state(TieBreak::Input, &[third, entry, second]);
+        assert_eq!(state.finalize(), 3);
+        // The second step arrived before the entry, so it cannot follow it.
+        let mut state = tie_break_state(TieBreak::Input, &[third, second, entry]);
+        assert_eq!(state.finalize(), 1);
+    }
+
+    #[test]
+    fn test_tie_break_condition_orders_by_lowest_condition() {
+        let mut events = [
+            make_event(5, &[false, false, true]),
+            make_event(5, &[false, true, true]),
+            make_event(0, &[false, true, false]),
+            make_event(5, &[true, false, false]),
+        ];
+        TieBreak::Condition.sort(&mut events);
+        assert_eq!(
+            events.map(|e| (e.timestamp_us, e.conditions)),
+            [(0, 0b010), (5, 0b001), (5, 0b110), (5, 0b100)]
+        );
+    }
+
+    #[test]
+    fn test_seq_orders_same_timestamp_events() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        // Arrive out of order; seq puts the entry before the second step.
+        state.update_with_seq(make_event(0, &[false, true, false]), 2, 3);
+        state.update_with_seq(make_event(0, &[true, false, false]), 1, 3);
+        state.update_with_seq(make_event(0, &[false, false, false]), 0, 3);
+        state.update_with_seq(make_event(10, &[false, false, true]), 3, 3);
+        assert_eq!(state.seqs.len(), state.events.len());
+        assert_eq!(state.finalize(), 3);
+        assert_eq!(state.seqs, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_seq_takes_precedence_over_tie_break() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::tie_break(TieBreak::Condition);
+        state.update_with_seq(make_event(0, &[true, false, false]), 2, 3);
+        state.update_with_seq(make_event(0, &[false, true, false]), 1, 3);
+        assert_eq!(state.finalize(), 1);
+    }
+
+    #[test]
+    fn test_seq_combine_and_dedup_stay_parallel() {
+        let mut a = WindowFunnelState::new();
+        a.window_size_us = 3_600_000_000;
+        a.dedup_within_us = 5;
+        a.update_with_seq(make_event(0, &[true, false]), 0, 2);
+        a.update_with_seq(make_event(3, &[true, false]), 2, 2);
+        let mut b = WindowFunnelState::new();
+        b.update_with_seq(make_event(1, &[false, true]), 1, 2);
+
+        let mut combined = a.combine(&b);
+        assert_eq!(combined.seqs, vec![0, 2, 1]);
+        let mut target = WindowFunnelState::new();
+        target.combine_in_place(&a);
+        target.combine_in_place(&b);
+        assert_eq!(target.seqs, combined.seqs);
+
+        assert_eq!(combined.finalize(), 2);
+        // The repeated entry at ts 3 was collapsed together with its seq.
+        assert_eq!(combined.events.len(), 2);
+        assert_eq!(combined.seqs, vec![0, 1]);
+    }
+
+    /// Events with random gaps and sparse later steps, enough to be
+    /// compressed twice.
+    fn large_stream() -> Vec<Event> {
+        let mut x = 0x2545_F491_4F6C_DD1D_u64;
+        let mut ts = 0;
+        (0..2 * COMPRESSION_THRESHOLD + 100)
+            .map(|_| {
+                x = x.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                ts += (x >> 60) as i64 * 1_000_000;
+                Event::new(ts, 1 << ((x >> 40) % 4).min((x >> 45) % 4))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compressed_events_match_uncompressed() {
+        let events = large_stream();
+        let fresh = || {
+            let mut s = WindowFunnelState::new();
+            s.window_size_us = 60_000_000;
+            s.mode = FunnelMode::tie_break(TieBreak::Input);
+            s.params_set = true;
+            s
+        };
+        let mut reference = fresh();
+        reference.num_conditions = 4;
+        reference.events = EventBuffer::from(events.clone());
+
+        let mut state = fresh();
+        for &event in &events {
+            state.update(event, 4);
+        }
+        assert_eq!(state.compressed.len(), 2 * COMPRESSION_THRESHOLD);
+        // Irregular second-resolution gaps still compress to under half of 16 bytes
+        assert!(state.compressed.encoded_bytes() < state.compressed.len() * 8);
+
+        // Compressed partial states combine in input order
+        let (left, right) = events.split_at(COMPRESSION_THRESHOLD + 7);
+        let (mut a, mut b) = (fresh(), fresh());
+        for &event in left {
+            a.update(event, 4);
+        }
+        for &event in right {
+            b.update(event, 4);
+        }
+        let mut combined = a.combine(&b);
+        let mut target = WindowFunnelState::new();
+        target.combine_in_place(&a);
+        target.combine_in_place(&b);
+
+        let expected = reference.clone().finalize_mask();
+        assert_ne!(expected, 0);
+        for s in [&mut state, &mut combined, &mut target] {
+            assert_eq!(s.clone().finalize_mask(), expected);
+            assert_eq!(
+                s.finalize_time_to_convert(),
+                reference.finalize_time_to_convert()
+            );
+            assert!(s.compressed.is_empty());
+            assert_eq!(*s.events, *reference.events);
+        }
+    }
+
+    #[test]
+    fn test_parse_tie_break_mode() {
+        assert_eq!(
+            FunnelMode::parse_mode_str("tie_break(input)"),
+            Some(FunnelMode::tie_break(TieBreak::Input))
+        );
+        assert_eq!(
+            FunnelMode::parse_mode_str("tie_break( argument )"),
+            Some(FunnelMode::tie_break(TieBreak::Condition))
+        );
+        assert_eq!(FunnelMode::parse_mode_str("tie_break(random)"), None);
+        assert_eq!(FunnelMode::parse_mode_str("tie_break"), None);
+        for name in TieBreak::NAMES {
+            assert!(TieBreak::parse(name).is_some(), "{name}");
+        }
+        let mode = FunnelMode::parse_modes("strict, skip(1), tie_break(condition)").unwrap();
+        assert!(mode.has(FunnelMode::STRICT));
+        assert_eq!(mode.tie_break_order(), TieBreak::Condition);
+        assert!(!mode.is_default());
+        assert_eq!(mode.to_string(), "strict+skip(1)+tie_break(condition)");
+        assert_eq!(
+            FunnelMode::tie_break(TieBreak::Input).to_string(),
+            "tie_break(input)"
+        );
+    }
+
+    #[test]
+    fn test_combine_propagates_tie_break_mode() {
+        let source = tie_break_state(TieBreak::Condition, &[make_event(0, &[true, false, false])]);
+        let mut target = WindowFunnelState::new();
+        target.combine_in_place(&source);
+        assert_eq!(target.mode.tie_break_order(), TieBreak::Condition);
+        let combined = WindowFunnelState::new().combine(&source);
+        assert_eq!(combined.mode.tie_break_order(), TieBreak::Condition);
+    }
+
+    // --- FunnelMode bitmask tests ---
+
+    #[test]
+    fn test_funnel_mode_default_is_zero() {
+        assert_eq!(FunnelMode::DEFAULT.bits(), 0);
+        assert!(FunnelMode::DEFAULT.is_default());
+    }
+
+    #[test]
+    fn test_funnel_mode_individual_flags() {
+        assert_eq!(FunnelMode::STRICT.bits(), 0x01);
+        assert_eq!(FunnelMode::STRICT_ORDER.bits(), 0x02);
+        assert_eq!(FunnelMode::STRICT_DEDUPLICATION.bits(), 0x04);
+        assert_eq!(FunnelMode::STRICT_INCREASE.bits(), 0x08);
+        assert_eq!(FunnelMode::STRICT_ONCE.bits(), 0x10);
+        assert_eq!(FunnelMode::ALLOW_REENTRY.bits(), 0x20);
+    }
+
+    #[test]
+    fn test_funnel_mode_combinable() {
+        let mode = FunnelMode::STRICT.with(FunnelMode::STRICT_INCREASE);
+        assert!(mode.has(FunnelMode::STRICT));
+        assert!(mode.has(FunnelMode::STRICT_INCREASE));
+        assert!(!mode.has(FunnelMode::STRICT_ORDER));
+        assert!(!mode.is_default());
+    }
+
+    #[test]
+    fn test_funnel_mode_has_self() {
+        // Each flag should contain itself
+        let flags = [
+            FunnelMode::STRICT,
+            FunnelMode::STRICT_ORDER,
+            FunnelMode::STRICT_DEDUPLICATION,
+            FunnelMode::STRICT_INCREASE,
+            FunnelMode::STRICT_ONCE,
+            FunnelMode::ALLOW_REENTRY,
+        ];
+        for flag in flags {
+            assert!(flag.has(flag));
+        }
+    }
+
+    #[test]
+    fn test_funnel_mode_from_bits_roundtrip() {
+        let mode = FunnelMode::from_bits(0x13);
+        assert!(mode.has(FunnelMode::STRICT));
+        assert!(mode.has(FunnelMode::STRICT_ORDER));
+        assert!(mode.has(FunnelMode::STRICT_ONCE));
+        assert!(!mode.has(FunnelMode::STRICT_DEDUPLICATION));
+        assert_eq!(mode.bits(), 0x13);
+    }
+
+    #[test]
+    fn test_funnel_mode_parse_mode_str() {
+        assert_eq!(
+            FunnelMode::parse_mode_str("strict"),
+            Some(FunnelMode::STRICT)
+        );
+        assert_eq!(
+            FunnelMode::parse_mode_str("strict_order"),
+            Some(FunnelMode::STRICT_ORDER)
+        );
+        // strict_deduplication is a ClickHouse alias for strict
+        assert_eq!(
+            FunnelMode::parse_mode_str("strict_deduplication"),
+            Some(FunnelMode::STRICT)
+        );
+        // timestamp_dedup is our extension mode
+        assert_eq!(
+            FunnelMode::parse_mode_str("timestamp_dedup"),
+            Some(FunnelMode::STRICT_DEDUPLICATION)
+        );
+        assert_eq!(
+            FunnelMode::parse_mode_str("strict_increase"),
+            Some(FunnelMode::STRICT_INCREASE)
+        );
+        assert_eq!(
+            FunnelMode::parse_mode_str("strict_once"),
+            Some(FunnelMode::STRICT_ONCE)
+        );
+        assert_eq!(
+            FunnelMode::parse_mode_str("allow_reentry"),
+            Some(FunnelMode::ALLOW_REENTRY)
+        );
+        assert_eq!(FunnelMode::parse_mode_str("unknown"), None);
+        assert_eq!(FunnelMode::parse_mode_str(""), None);
+    }
+
+    #[test]
+    fn test_funnel_mode_display() {
+        assert_eq!(FunnelMode::DEFAULT.to_string(), "default");
+        assert_eq!(FunnelMode::STRICT.to_string(), "strict");
+        assert_eq!(
+            FunnelMode::STRICT
+                .with(FunnelMode::STRICT_INCREASE)
+                .to_string(),
+            "strict+strict_increase"
+        );
+    }
+
+    #[test]
+    fn test_funnel_mode_with_is_commutative() {
+        let a = FunnelMode::STRICT.with(FunnelMode::STRICT_ORDER);
+        let b = FunnelMode::STRICT_ORDER.with(FunnelMode::STRICT);
+        assert_eq!(a, b);
+    }
+
+    // --- parse_modes tests ---
+
+    #[test]
+    fn test_parse_modes_empty_string() {
+        assert_eq!(FunnelMode::parse_modes("").unwrap(), FunnelMode::DEFAULT);
+    }
+
+    #[test]
+    fn test_parse_modes_whitespace_only() {
+        assert_eq!(FunnelMode::parse_modes("  ").unwrap(), FunnelMode::DEFAULT);
+    }
+
+    #[test]
+    fn test_parse_modes_single() {
+        assert_eq!(
+            FunnelMode::parse_modes("strict").unwrap(),
+            FunnelMode::STRICT
+        );
+    }
+
+    #[test]
+    fn test_parse_modes_two_comma_separated() {
+        let mode = FunnelMode::parse_modes("strict_increase, strict_once").unwrap();
+        assert!(mode.has(FunnelMode::STRICT_INCREASE));
+        assert!(mode.has(FunnelMode::STRICT_ONCE));
+        assert!(!mode.has(FunnelMode::STRICT));
+    }
+
+    #[test]
+    fn test_parse_modes_no_whitespace() {
+        let mode = FunnelMode::parse_modes("strict,strict_order").unwrap();
+        assert!(mode.has(FunnelMode::STRICT));
+        assert!(mode.has(FunnelMode::STRICT_ORDER));
+    }
+
+    #[test]
+    fn test_parse_modes_extra_whitespace() {
+        let mode = FunnelMode::parse_modes("  strict_increase ,  strict_once  ").unwrap();
+        assert!(mode.has(FunnelMode::STRICT_INCREASE));
+        assert!(mode.has(FunnelMode::STRICT_ONCE));
+    }
+
+    #[test]
+    fn test_parse_modes_all_clickhouse_modes() {
+        // ClickHouse-compatible modes: strict_deduplication is an alias for strict
+        let mode = FunnelMode::parse_modes(
+            "strict, strict_order, strict_deduplication, strict_increase, strict_once, allow_reentry",
+        )
+        .unwrap();
+        assert!(mode.has(FunnelMode::STRICT)); // both 'strict' and 'strict_deduplication' set this
+        assert!(mode.has(FunnelMode::STRICT_ORDER));
+        assert!(!mode.has(FunnelMode::STRICT_DEDUPLICATION)); // not set by ClickHouse mode names
+        assert!(mode.has(FunnelMode::STRICT_INCREASE));
+        assert!(mode.has(FunnelMode::STRICT_ONCE));
+        assert!(mode.has(FunnelMode::ALLOW_REENTRY));
+    }
+
+    #[test]
+    fn test_parse_modes_all_modes_including_extensions() {
+        // All modes including our extension mode
+        let mode = FunnelMode::parse_modes(
+            "strict, strict_order, timestamp_dedup, strict_increase, strict_once, allow_reentry",
+        )
+        .unwrap();
+        assert!(mode.has(FunnelMode::STRICT));
+        assert!(mode.has(FunnelMode::STRICT_ORDER));
+        assert!(mode.has(FunnelMode::STRICT_DEDUPLICATION));
+        assert!(mode.has(FunnelMode::STRICT_INCREASE));
+        assert!(mode.has(FunnelMode::STRICT_ONCE));
+        assert!(mode.has(FunnelMode::ALLOW_REENTRY));
+    }
+
+    #[test]
+    fn test_parse_modes_invalid_returns_err() {
+        let err = FunnelMode::parse_modes("strict, invalid_mode").unwrap_err();
+        assert_eq!(err, "invalid_mode");
+    }
+
+    #[test]
+    fn test_mode_names_all_parse() {
+        for name in FunnelMode::MODE_NAMES {
+            assert!(FunnelMode::parse_mode_str(name).is_some(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_parse_modes_trailing_comma() {
+        // Trailing comma produces an empty token which is skipped
+        let mode = FunnelMode::parse_modes("strict,").unwrap();
+        assert_eq!(mode, FunnelMode::STRICT);
+    }
+
+    #[test]
+    fn test_parse_modes_duplicate_mode() {
         // Duplicate mode is idempotent (OR of same bit)
         let mode = FunnelMode::parse_modes("strict, strict").unwrap();
         assert_eq!(mode, FunnelMode::STRICT);
@@ -1130,192 +2859,613 @@ mod tests {
     // --- strict_increase mode tests ---
 
     #[test]
-    fn test_strict_increase_same_timestamp_stops_funnel() {
+    fn test_strict_increase_same_timestamp_stops_funnel() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_INCREASE;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(0, &[false, true, false]), 3); // same ts → skipped
+        assert_eq!(state.finalize(), 1);
+    }
+
+    #[test]
+    fn test_strict_increase_increasing_timestamps_ok() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_INCREASE;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1, &[false, true, false]), 3); // 1 > 0
+        state.update(make_event(2, &[false, false, true]), 3); // 2 > 1
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_strict_increase_mixed_same_and_increasing() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_INCREASE;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1000, &[false, true, false]), 3); // increasing, matches
+        state.update(make_event(1000, &[false, false, true]), 3); // same ts → skipped
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_strict_increase_skips_then_matches_later() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_INCREASE;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(0, &[false, true, false]), 3); // same ts → skipped
+        state.update(make_event(1000, &[false, true, false]), 3); // increasing → matches
+        state.update(make_event(2000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_strict_increase_all_same_timestamp_entry_only() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_INCREASE;
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(0, &[false, true]), 2);
+        assert_eq!(state.finalize(), 1);
+    }
+
+    #[test]
+    fn test_strict_increase_by_one_microsecond() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_INCREASE;
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(1, &[false, true]), 2); // 1us increase
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_strict_increase_empty() {
+        let mut state = WindowFunnelState::new();
+        state.mode = FunnelMode::STRICT_INCREASE;
+        assert_eq!(state.finalize(), 0);
+    }
+
+    // --- strict_once mode tests ---
+
+    #[test]
+    fn test_strict_once_multi_condition_event_advances_only_one() {
+        // Without strict_once, an event matching cond1 AND cond2 can advance 2 steps.
+        // With strict_once, it advances only 1 step per event.
+        let mut state_default = WindowFunnelState::new();
+        state_default.window_size_us = 3_600_000_000;
+        state_default.update(make_event(0, &[true, false, false]), 3);
+        state_default.update(make_event(1000, &[false, true, true]), 3); // cond1+cond2
+                                                                         // Default: matches step 1 (cond[1]), then step 2 (cond[2]) on same event
+        let default_result = state_default.finalize();
+
+        let mut state_once = WindowFunnelState::new();
+        state_once.window_size_us = 3_600_000_000;
+        state_once.mode = FunnelMode::STRICT_ONCE;
+        state_once.update(make_event(0, &[true, false, false]), 3);
+        state_once.update(make_event(1000, &[false, true, true]), 3); // cond1+cond2
+        let once_result = state_once.finalize();
+
+        // strict_once should prevent advancing more than 1 step per event
+        assert!(once_result <= default_result);
+        assert_eq!(once_result, 2); // step 0 (entry) + step 1 (from event at 1000)
+    }
+
+    #[test]
+    fn test_strict_once_sequential_single_conditions() {
+        // When each event satisfies only one condition, strict_once has no effect
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_ONCE;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1000, &[false, true, false]), 3);
+        state.update(make_event(2000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_strict_once_triple_condition_event() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::STRICT_ONCE;
+        state.update(make_event(0, &[true, true, true, true]), 4); // all conditions on entry
+        state.update(make_event(1000, &[false, true, true, true]), 4);
+        // Entry matches step 0. Next event: strict_once means only step 1 advances.
+        // Need another event for step 2 and 3.
+        state.update(make_event(2000, &[false, false, true, true]), 4);
+        state.update(make_event(3000, &[false, false, false, true]), 4);
+        assert_eq!(state.finalize(), 4);
+    }
+
+    #[test]
+    fn test_strict_once_empty() {
+        let mut state = WindowFunnelState::new();
+        state.mode = FunnelMode::STRICT_ONCE;
+        assert_eq!(state.finalize(), 0);
+    }
+
+    // --- allow_reentry mode tests ---
+
+    #[test]
+    fn test_allow_reentry_longer_chain_from_reentry() {
+        // Reentry should find a longer chain
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ALLOW_REENTRY;
+        state.update(make_event(0, &[true, false, false]), 3); // entry 1
+        state.update(make_event(1000, &[false, true, false]), 3); // step 1
+                                                                  // Entry fires again: reset chain
+        state.update(make_event(2000, &[true, false, false]), 3); // reentry
+        state.update(make_event(3000, &[false, true, false]), 3); // step 1 (from reentry)
+        state.update(make_event(4000, &[false, false, true]), 3); // step 2 (from reentry)
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_allow_reentry_no_second_entry() {
+        // Without reentry trigger, behaves like default
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ALLOW_REENTRY;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1000, &[false, true, false]), 3);
+        state.update(make_event(2000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_allow_reentry_multiple_reentries() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ALLOW_REENTRY;
+        state.update(make_event(0, &[true, false]), 2); // entry 1
+        state.update(make_event(1000, &[true, false]), 2); // reentry 1
+        state.update(make_event(2000, &[true, false]), 2); // reentry 2
+        state.update(make_event(3000, &[false, true]), 2); // step 1
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_allow_reentry_resets_from_correct_point() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 50_000; // 50us window
+        state.mode = FunnelMode::ALLOW_REENTRY;
+        // First entry: window expires before step 1
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(100_000, &[true, false]), 2); // reentry at 100ms
+        state.update(make_event(120_000, &[false, true]), 2); // 20us after reentry, in window
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_allow_reentry_empty() {
+        let mut state = WindowFunnelState::new();
+        state.mode = FunnelMode::ALLOW_REENTRY;
+        assert_eq!(state.finalize(), 0);
+    }
+
+    // --- entry_advances mode tests ---
+
+    #[test]
+    fn test_entry_advances_multi_condition_entry() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ENTRY_ADVANCES;
+        state.update(make_event(0, &[true, true, false]), 3); // entry satisfies cond1 + cond2
+        state.update(make_event(1000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_entry_advances_off_by_default() {
+        // Default: the entry event only counts as step 1
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.update(make_event(0, &[true, true, false]), 3);
+        state.update(make_event(1000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 1);
+    }
+
+    #[test]
+    fn test_entry_advances_full_funnel_on_single_event() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ENTRY_ADVANCES;
+        state.update(make_event(0, &[true, true, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_entry_advances_stops_at_gap() {
+        // cond3 on the entry event does not count without cond2
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ENTRY_ADVANCES;
+        state.update(make_event(0, &[true, false, true]), 3);
+        state.update(make_event(1000, &[false, true, false]), 3);
+        assert_eq!(state.finalize(), 2);
+    }
+
+    #[test]
+    fn test_entry_advances_ignored_with_strict_once() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ENTRY_ADVANCES.with(FunnelMode::STRICT_ONCE);
+        state.update(make_event(0, &[true, true, false]), 3);
+        state.update(make_event(1000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 1);
+    }
+
+    #[test]
+    fn test_entry_advances_applies_on_reentry() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = FunnelMode::ENTRY_ADVANCES.with(FunnelMode::ALLOW_REENTRY);
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1000, &[false, true, false]), 3);
+        state.update(make_event(2000, &[true, true, false]), 3); // reentry at step 2
+        state.update(make_event(3000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_entry_advances_parse_and_display() {
+        let mode = FunnelMode::parse_modes("strict_order, entry_advances").unwrap();
+        assert!(mode.has(FunnelMode::ENTRY_ADVANCES));
+        assert_eq!(mode.to_string(), "strict_order+entry_advances");
+    }
+
+    // --- strict_order_zero mode tests ---
+
+    #[test]
+    fn test_strict_order_zero_success_matches_strict_order() {
         let mut state = WindowFunnelState::new();
         state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_INCREASE;
+        state.mode = FunnelMode::STRICT_ORDER_ZERO;
         state.update(make_event(0, &[true, false, false]), 3);
-        state.update(make_event(0, &[false, true, false]), 3); // same ts → skipped
-        assert_eq!(state.finalize(), 1);
+        state.update(make_event(1_000, &[false, true, false]), 3);
+        state.update(make_event(2_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
     }
 
     #[test]
-    fn test_strict_increase_increasing_timestamps_ok() {
+    fn test_strict_order_zero_violation_returns_zero() {
+        // Same input as test_strict_order_earlier_condition_breaks_chain,
+        // where strict_order returns 2 from the second entry point.
         let mut state = WindowFunnelState::new();
         state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_INCREASE;
+        state.mode = FunnelMode::STRICT_ORDER_ZERO;
         state.update(make_event(0, &[true, false, false]), 3);
-        state.update(make_event(1, &[false, true, false]), 3); // 1 > 0
-        state.update(make_event(2, &[false, false, true]), 3); // 2 > 1
-        assert_eq!(state.finalize(), 3);
+        state.update(make_event(1_000, &[true, false, false]), 3);
+        state.update(make_event(2_000, &[false, true, false]), 3);
+        assert_eq!(state.finalize(), 0);
     }
 
     #[test]
-    fn test_strict_increase_mixed_same_and_increasing() {
+    fn test_strict_order_zero_violation_after_progress() {
         let mut state = WindowFunnelState::new();
         state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_INCREASE;
+        state.mode = FunnelMode::STRICT_ORDER_ZERO;
         state.update(make_event(0, &[true, false, false]), 3);
-        state.update(make_event(1000, &[false, true, false]), 3); // increasing, matches
-        state.update(make_event(1000, &[false, false, true]), 3); // same ts → skipped
-        assert_eq!(state.finalize(), 2);
+        state.update(make_event(1_000, &[false, true, false]), 3);
+        state.update(make_event(2_000, &[true, false, false]), 3); // violation at step 2
+        state.update(make_event(3_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 0);
     }
 
     #[test]
-    fn test_strict_increase_skips_then_matches_later() {
+    fn test_strict_order_zero_no_violation_partial() {
         let mut state = WindowFunnelState::new();
         state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_INCREASE;
+        state.mode = FunnelMode::STRICT_ORDER_ZERO;
         state.update(make_event(0, &[true, false, false]), 3);
-        state.update(make_event(0, &[false, true, false]), 3); // same ts → skipped
-        state.update(make_event(1000, &[false, true, false]), 3); // increasing → matches
-        state.update(make_event(2000, &[false, false, true]), 3);
-        assert_eq!(state.finalize(), 3);
+        state.update(make_event(1_000, &[false, true, false]), 3);
+        assert_eq!(state.finalize(), 2);
     }
 
     #[test]
-    fn test_strict_increase_all_same_timestamp_entry_only() {
+    fn test_strict_order_zero_parse_and_display() {
+        assert_eq!(FunnelMode::STRICT_ORDER_ZERO.bits(), 0x80);
+        let mode = FunnelMode::parse_modes("strict_order_zero").unwrap();
+        assert_eq!(mode, FunnelMode::STRICT_ORDER_ZERO);
+        assert_eq!(mode.to_string(), "strict_order_zero");
+    }
+
+    #[test]
+    fn test_48_step_funnel() {
+        // Funnels longer than 32 steps use the upper half of the u64 mask.
         let mut state = WindowFunnelState::new();
         state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_INCREASE;
-        state.update(make_event(0, &[true, false]), 2);
-        state.update(make_event(0, &[false, true]), 2);
-        assert_eq!(state.finalize(), 1);
+        for i in 0..48 {
+            state.update(Event::new(i * 1_000, 1 << i), 48);
+        }
+        assert_eq!(state.finalize(), 48);
+
+        // Without step 41 the funnel stops after 40 steps.
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        for i in (0..48).filter(|&i| i != 40) {
+            state.update(Event::new(i * 1_000, 1 << i), 48);
+        }
+        assert_eq!(state.finalize(), 40);
     }
 
+    // --- strict_time mode tests ---
+
     #[test]
-    fn test_strict_increase_by_one_microsecond() {
+    fn test_strict_time_window_between_steps() {
+        // Each step is 40s after the previous one: the whole chain spans 80s,
+        // more than the 1-minute window from entry.
         let mut state = WindowFunnelState::new();
-        state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_INCREASE;
-        state.update(make_event(0, &[true, false]), 2);
-        state.update(make_event(1, &[false, true]), 2); // 1us increase
-        assert_eq!(state.finalize(), 2);
+        state.window_size_us = 60_000_000;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(40_000_000, &[false, true, false]), 3);
+        state.update(make_event(80_000_000, &[false, false, true]), 3);
+        assert_eq!(state.clone().finalize(), 2);
+
+        state.mode = FunnelMode::STRICT_TIME;
+        assert_eq!(state.finalize(), 3);
     }
 
     #[test]
-    fn test_strict_increase_empty() {
+    fn test_strict_time_gap_breaks_chain() {
         let mut state = WindowFunnelState::new();
-        state.mode = FunnelMode::STRICT_INCREASE;
-        assert_eq!(state.finalize(), 0);
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::STRICT_TIME;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(30_000_000, &[false, true, false]), 3);
+        state.update(make_event(100_000_000, &[false, false, true]), 3); // 70s after step 2
+        assert_eq!(state.finalize(), 2);
     }
 
-    // --- strict_once mode tests ---
+    #[test]
+    fn test_strict_time_unmatched_events_do_not_extend_window() {
+        // The window is measured from the last matched step, not the last event.
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::STRICT_TIME;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(50_000_000, &[false, false, true]), 3); // out of order
+        state.update(make_event(70_000_000, &[false, true, false]), 3); // 70s after entry
+        assert_eq!(state.finalize(), 1);
+    }
 
     #[test]
-    fn test_strict_once_multi_condition_event_advances_only_one() {
-        // Without strict_once, an event matching cond1 AND cond2 can advance 2 steps.
-        // With strict_once, it advances only 1 step per event.
-        let mut state_default = WindowFunnelState::new();
-        state_default.window_size_us = 3_600_000_000;
-        state_default.update(make_event(0, &[true, false, false]), 3);
-        state_default.update(make_event(1000, &[false, true, true]), 3); // cond1+cond2
-                                                                         // Default: matches step 1 (cond[1]), then step 2 (cond[2]) on same event
-        let default_result = state_default.finalize();
+    fn test_strict_time_parse_and_display() {
+        assert_eq!(FunnelMode::STRICT_TIME.bits(), 0x100);
+        let mode = FunnelMode::parse_modes("strict_time, strict_order").unwrap();
+        assert_eq!(mode, FunnelMode::STRICT_ORDER.with(FunnelMode::STRICT_TIME));
+        assert_eq!(mode.to_string(), "strict_order+strict_time");
+    }
 
-        let mut state_once = WindowFunnelState::new();
-        state_once.window_size_us = 3_600_000_000;
-        state_once.mode = FunnelMode::STRICT_ONCE;
-        state_once.update(make_event(0, &[true, false, false]), 3);
-        state_once.update(make_event(1000, &[false, true, true]), 3); // cond1+cond2
-        let once_result = state_once.finalize();
+    // --- strict_dedup tests ---
 
-        // strict_once should prevent advancing more than 1 step per event
-        assert!(once_result <= default_result);
-        assert_eq!(once_result, 2); // step 0 (entry) + step 1 (from event at 1000)
+    #[test]
+    fn test_strict_dedup_mode_parses() {
+        assert_eq!(
+            FunnelMode::parse_mode_str("strict_dedup"),
+            Some(FunnelMode::EVENT_DEDUP)
+        );
+        assert_eq!(FunnelMode::EVENT_DEDUP.bits(), 0x200);
+        // Not the ClickHouse alias of strict
+        assert_ne!(
+            FunnelMode::parse_mode_str("strict_dedup"),
+            FunnelMode::parse_mode_str("strict_deduplication")
+        );
+        let mode = FunnelMode::parse_modes("strict, strict_dedup").unwrap();
+        assert_eq!(mode.to_string(), "strict+strict_dedup");
     }
 
     #[test]
-    fn test_strict_once_sequential_single_conditions() {
-        // When each event satisfies only one condition, strict_once has no effect
+    fn test_strict_dedup_removes_ingested_duplicates() {
+        // The step-2 row was ingested twice: strict breaks on the copy.
         let mut state = WindowFunnelState::new();
-        state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_ONCE;
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::STRICT;
         state.update(make_event(0, &[true, false, false]), 3);
-        state.update(make_event(1000, &[false, true, false]), 3);
-        state.update(make_event(2000, &[false, false, true]), 3);
+        state.update(make_event(1_000_000, &[false, true, false]), 3);
+        state.update(make_event(1_000_000, &[false, true, false]), 3);
+        state.update(make_event(2_000_000, &[false, false, true]), 3);
+        assert_eq!(state.clone().finalize(), 2);
+
+        state.mode = FunnelMode::STRICT.with(FunnelMode::EVENT_DEDUP);
         assert_eq!(state.finalize(), 3);
+        assert_eq!(state.events.len(), 3);
     }
 
     #[test]
-    fn test_strict_once_triple_condition_event() {
+    fn test_strict_dedup_needs_same_timestamp_and_mask() {
         let mut state = WindowFunnelState::new();
-        state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::STRICT_ONCE;
-        state.update(make_event(0, &[true, true, true, true]), 4); // all conditions on entry
-        state.update(make_event(1000, &[false, true, true, true]), 4);
-        // Entry matches step 0. Next event: strict_once means only step 1 advances.
-        // Need another event for step 2 and 3.
-        state.update(make_event(2000, &[false, false, true, true]), 4);
-        state.update(make_event(3000, &[false, false, false, true]), 4);
-        assert_eq!(state.finalize(), 4);
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::EVENT_DEDUP;
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(0, &[true, true]), 2); // different mask: kept
+        state.update(make_event(1, &[true, false]), 2); // later: kept
+        state.update(make_event(0, &[true, false]), 2); // duplicate: dropped
+        assert_eq!(state.finalize(), 2);
+        let events: Vec<(i64, u64)> = state
+            .events
+            .iter()
+            .map(|e| (e.timestamp_us, e.conditions))
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert!(events.contains(&(0, 0b01)) && events.contains(&(0, 0b11)));
     }
 
     #[test]
-    fn test_strict_once_empty() {
+    fn test_strict_dedup_keeps_tie_order_and_seqs() {
+        // Input order A, B, A' at one timestamp: A' goes, A stays first.
         let mut state = WindowFunnelState::new();
-        state.mode = FunnelMode::STRICT_ONCE;
-        assert_eq!(state.finalize(), 0);
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::EVENT_DEDUP.with(FunnelMode::tie_break(TieBreak::Input));
+        state.update(make_event(5, &[false, true]), 2);
+        state.update(make_event(5, &[true, false]), 2);
+        state.update(make_event(5, &[false, true]), 2);
+        assert_eq!(state.finalize(), 1);
+        let masks: Vec<u64> = state.events.iter().map(|e| e.conditions).collect();
+        assert_eq!(masks, vec![0b10, 0b01]);
+
+        let mut seqs = WindowFunnelState::new();
+        seqs.window_size_us = 60_000_000;
+        seqs.mode = FunnelMode::EVENT_DEDUP;
+        seqs.update_with_seq(make_event(5, &[true, false]), 3, 2);
+        seqs.update_with_seq(make_event(5, &[true, false]), 1, 2);
+        seqs.update_with_seq(make_event(5, &[false, true]), 2, 2);
+        assert_eq!(seqs.finalize(), 2);
+        assert_eq!(seqs.seqs, vec![1, 2]);
     }
 
-    // --- allow_reentry mode tests ---
+    // --- dedup_within tests ---
 
     #[test]
-    fn test_allow_reentry_longer_chain_from_reentry() {
-        // Reentry should find a longer chain
+    fn test_dedup_within_removes_strict_break() {
+        // A duplicated step-2 event breaks the chain in strict mode.
         let mut state = WindowFunnelState::new();
-        state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::ALLOW_REENTRY;
-        state.update(make_event(0, &[true, false, false]), 3); // entry 1
-        state.update(make_event(1000, &[false, true, false]), 3); // step 1
-                                                                  // Entry fires again: reset chain
-        state.update(make_event(2000, &[true, false, false]), 3); // reentry
-        state.update(make_event(3000, &[false, true, false]), 3); // step 1 (from reentry)
-        state.update(make_event(4000, &[false, false, true]), 3); // step 2 (from reentry)
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::STRICT;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1_000_000, &[false, true, false]), 3);
+        state.update(make_event(2_000_000, &[false, true, false]), 3); // duplicate
+        state.update(make_event(3_000_000, &[false, false, true]), 3);
+        assert_eq!(state.clone().finalize(), 2);
+
+        state.dedup_within_us = 5_000_000;
         assert_eq!(state.finalize(), 3);
     }
 
     #[test]
-    fn test_allow_reentry_no_second_entry() {
-        // Without reentry trigger, behaves like default
+    fn test_dedup_within_is_inclusive_and_per_bitmask() {
         let mut state = WindowFunnelState::new();
-        state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::ALLOW_REENTRY;
-        state.update(make_event(0, &[true, false, false]), 3);
-        state.update(make_event(1000, &[false, true, false]), 3);
-        state.update(make_event(2000, &[false, false, true]), 3);
-        assert_eq!(state.finalize(), 3);
+        state.window_size_us = 60_000_000;
+        state.dedup_within_us = 1_000_000;
+        state.update(make_event(0, &[true, false]), 2);
+        state.update(make_event(500_000, &[false, true]), 2); // different mask: kept
+        state.update(make_event(1_000_000, &[true, false]), 2); // exactly 1s: dropped
+        state.update(make_event(2_500_000, &[true, false]), 2); // 2.5s after kept: kept
+        assert_eq!(state.finalize(), 2);
+        let ts: Vec<i64> = state.events.iter().map(|e| e.timestamp_us).collect();
+        assert_eq!(ts, vec![0, 500_000, 2_500_000]);
     }
 
     #[test]
-    fn test_allow_reentry_multiple_reentries() {
+    fn test_dedup_within_measures_from_kept_event() {
+        // A burst longer than the interval keeps one event per interval.
         let mut state = WindowFunnelState::new();
-        state.window_size_us = 3_600_000_000;
-        state.mode = FunnelMode::ALLOW_REENTRY;
-        state.update(make_event(0, &[true, false]), 2); // entry 1
-        state.update(make_event(1000, &[true, false]), 2); // reentry 1
-        state.update(make_event(2000, &[true, false]), 2); // reentry 2
-        state.update(make_event(3000, &[false, true]), 2); // step 1
-        assert_eq!(state.finalize(), 2);
+        state.window_size_us = 60_000_000;
+        state.dedup_within_us = 1_000_000;
+        for i in 0..5 {
+            state.update(make_event(i * 600_000, &[true, false]), 2);
+        }
+        assert_eq!(state.finalize(), 1);
+        let ts: Vec<i64> = state.events.iter().map(|e| e.timestamp_us).collect();
+        assert_eq!(ts, vec![0, 1_200_000, 2_400_000]);
     }
 
     #[test]
-    fn test_allow_reentry_resets_from_correct_point() {
+    fn test_combine_in_place_zero_target_propagates_dedup_within() {
+        let mut target = WindowFunnelState::new();
+        let mut source = WindowFunnelState::new();
+        source.dedup_within_us = 1_000_000;
+        source.params_set = true;
+        target.combine_in_place(&source);
+        assert_eq!(target.dedup_within_us, 1_000_000);
+        assert_eq!(
+            WindowFunnelState::new().combine(&source).dedup_within_us,
+            1_000_000
+        );
+    }
+
+    // --- finalize_stats tests ---
+
+    #[test]
+    fn test_finalize_stats_counts_search() {
         let mut state = WindowFunnelState::new();
-        state.window_size_us = 50_000; // 50us window
-        state.mode = FunnelMode::ALLOW_REENTRY;
-        // First entry: window expires before step 1
+        state.window_size_us = 60_000_000;
+        state.dedup_within_us = 1_000_000;
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(500_000, &[true, false, false]), 3); // deduplicated
+        state.update(make_event(2_000_000, &[false, false, false]), 3); // not stored
+        state.update(make_event(90_000_000, &[true, false, false]), 3);
+        state.update(make_event(91_000_000, &[false, true, false]), 3);
+        let result = state.finalize_stats();
+        assert_eq!(
+            result,
+            FunnelStats {
+                step: 2,
+                events_scanned: 3,
+                chains_tried: 2,
+                best_entry_ts: Some(90_000_000),
+            }
+        );
+        assert_eq!(state.finalize(), result.step);
+    }
+
+    #[test]
+    fn test_finalize_stats_stops_at_complete_chain_and_empty() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
         state.update(make_event(0, &[true, false]), 2);
-        state.update(make_event(100_000, &[true, false]), 2); // reentry at 100ms
-        state.update(make_event(120_000, &[false, true]), 2); // 20us after reentry, in window
-        assert_eq!(state.finalize(), 2);
+        state.update(make_event(1, &[false, true]), 2);
+        state.update(make_event(2, &[true, false]), 2);
+        assert_eq!(state.finalize_stats().chains_tried, 1);
+
+        let result = WindowFunnelState::new().finalize_stats();
+        assert_eq!(
+            (result.step, result.chains_tried, result.best_entry_ts),
+            (0, 0, None)
+        );
     }
 
+    // --- min_gaps tests ---
+
     #[test]
-    fn test_allow_reentry_empty() {
+    fn test_min_gap_skips_too_early_step() {
+        // A purchase 2s after add_to_cart is ignored; the one 10s after counts.
         let mut state = WindowFunnelState::new();
-        state.mode = FunnelMode::ALLOW_REENTRY;
-        assert_eq!(state.finalize(), 0);
+        state.window_size_us = 60_000_000;
+        state.min_gaps_us = vec![0, 5_000_000];
+        state.update(make_event(0, &[true, false, false]), 3);
+        state.update(make_event(1_000_000, &[false, true, false]), 3);
+        state.update(make_event(3_000_000, &[false, false, true]), 3);
+        assert_eq!(state.clone().finalize(), 2);
+
+        state.update(make_event(11_000_000, &[false, false, true]), 3);
+        assert_eq!(state.finalize(), 3);
+    }
+
+    #[test]
+    fn test_min_gap_is_inclusive_and_stops_multi_step_event() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.min_gaps_us = vec![1_000_000, 1_000_000];
+        state.update(make_event(0, &[true, false, false]), 3);
+        // Exactly 1s later: step 2 matches, step 3 on the same event does not.
+        state.update(make_event(1_000_000, &[false, true, true]), 3);
+        assert_eq!(state.finalize(), 2);
+
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 60_000_000;
+        state.mode = FunnelMode::ENTRY_ADVANCES;
+        state.min_gaps_us = vec![1, 0];
+        state.update(make_event(0, &[true, true, false]), 3);
+        assert_eq!(state.finalize(), 1);
+    }
+
+    #[test]
+    fn test_combine_in_place_zero_target_propagates_min_gaps() {
+        let mut target = WindowFunnelState::new();
+        let mut source = WindowFunnelState::new();
+        source.min_gaps_us = vec![0, 5_000_000];
+        source.params_set = true;
+        target.combine_in_place(&source);
+        assert_eq!(target.min_gaps_us, vec![0, 5_000_000]);
+        assert_eq!(
+            WindowFunnelState::new().combine(&source).min_gaps_us,
+            vec![0, 5_000_000]
+        );
     }
 
     // --- Combined mode tests ---
@@ -1405,6 +3555,7 @@ mod tests {
         let mut target = WindowFunnelState::new(); // zero-initialized
         let mut source = WindowFunnelState::new();
         source.window_size_us = 3_600_000_000;
+        source.params_set = true;
         source.update(make_event(0, &[true, false]), 2);
         source.update(make_event(1_000_000, &[false, true]), 2);
 
@@ -1419,6 +3570,7 @@ mod tests {
         let mut source = WindowFunnelState::new();
         source.window_size_us = 3_600_000_000;
         source.mode = FunnelMode::STRICT_INCREASE;
+        source.params_set = true;
         source.update(make_event(0, &[true, false]), 2);
         source.update(make_event(1000, &[false, true]), 2);
 
@@ -1446,10 +3598,12 @@ mod tests {
         let mut s1 = WindowFunnelState::new();
         s1.window_size_us = 3_600_000_000;
         s1.mode = FunnelMode::STRICT;
+        s1.params_set = true;
         s1.update(make_event(0, &[true, false, false]), 3);
 
         let mut s2 = WindowFunnelState::new();
         s2.window_size_us = 3_600_000_000;
+        s2.params_set = true;
         s2.update(make_event(1000, &[false, true, false]), 3);
         s2.update(make_event(2000, &[false, false, true]), 3);
 
@@ -1465,14 +3619,40 @@ mod tests {
         // If target already has window_size, it should NOT be overwritten
         let mut target = WindowFunnelState::new();
         target.window_size_us = 1_000_000; // 1 second
+        target.params_set = true;
         let mut source = WindowFunnelState::new();
         source.window_size_us = 3_600_000_000; // 1 hour
+        source.params_set = true;
 
         target.combine_in_place(&source);
         // Target's window_size should be preserved (first-write-wins)
         assert_eq!(target.window_size_us, 1_000_000);
     }
 
+    #[test]
+    fn test_combine_keeps_explicit_zero_window_and_default_mode() {
+        // A zero window and the default mode set from the arguments are
+        // values, not "unset": combine must not replace them.
+        let mut target = WindowFunnelState::new();
+        target.params_set = true;
+        target.update(make_event(0, &[true, false]), 2);
+        let mut source = WindowFunnelState::new();
+        source.window_size_us = 3_600_000_000;
+        source.mode = FunnelMode::STRICT;
+        source.dedup_within_us = 1_000_000;
+        source.params_set = true;
+        source.update(make_event(1_000, &[false, true]), 2);
+
+        let mut combined = target.combine(&source);
+        target.combine_in_place(&source);
+        for state in [&mut target, &mut combined] {
+            assert_eq!(state.window_size_us, 0);
+            assert!(state.mode.is_default());
+            assert_eq!(state.dedup_within_us, 0);
+            assert_eq!(state.finalize(), 1);
+        }
+    }
+
     // ── Coverage gap tests: mode combination edge cases ──
 
     #[test]
@@ -1531,6 +3711,147 @@ mod tests {
         state.update(make_event(300, &[false, false, true]), 3);
         assert_eq!(state.finalize(), 3);
     }
+
+    #[test]
+    fn test_update_list_infers_steps_from_masks() {
+        let events = [
+            Event::new(0, 0b001),
+            Event::new(1_000_000, 0b010),
+            Event::new(2_000_000, 0b100),
+            Event::new(3_000_000, 0),
+        ];
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.update_list(events);
+        assert_eq!(state.num_conditions, 3);
+        assert_eq!(state.finalize(), 3);
+
+        // The same funnel with more declared steps reaches the same level.
+        let mut rows = WindowFunnelState::new();
+        rows.window_size_us = 3_600_000_000;
+        for event in events {
+            rows.update(event, 5);
+        }
+        assert_eq!(rows.finalize(), 3);
+
+        let mut empty = WindowFunnelState::new();
+        empty.update_list([Event::new(0, 0)]);
+        assert_eq!(empty.num_conditions, 0);
+        assert_eq!(empty.finalize(), 0);
+    }
+
+    fn blob_state(mode: FunnelMode, events: &[(i64, u64)]) -> WindowFunnelState {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 3_600_000_000;
+        state.mode = mode;
+        state.params_set = true;
+        for &(ts, mask) in events {
+            state.update(Event::new(ts, mask), 3);
+        }
+        state
+    }
+
+    #[test]
+    fn test_blob_round_trip_keeps_parameters_and_result() {
+        let mode = FunnelMode::STRICT_ORDER
+            .with(FunnelMode::skip(1))
+            .with(FunnelMode::tie_break(TieBreak::Condition));
+        let mut state = blob_state(mode, &[(0, 0b001), (2, 0b100), (1, 0b010)]);
+        state.dedup_within_us = 5;
+        state.min_gaps_us = vec![1, 0];
+
+        let mut restored = WindowFunnelState::from_blob(&state.to_blob()).unwrap();
+        assert_eq!(restored.mode, mode);
+        assert_eq!(restored.window_size_us, 3_600_000_000);
+        assert_eq!(restored.dedup_within_us, 5);
+        assert_eq!(restored.min_gaps_us, vec![1, 0]);
+        assert_eq!(restored.num_conditions, 3);
+        assert!(restored.params_set);
+        assert_eq!(restored.finalize(), state.finalize());
+    }
+
+    #[test]
+    fn test_blob_round_trip_keeps_sequence_numbers() {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = 10;
+        state.params_set = true;
+        state.update_with_seq(Event::new(0, 0b10), 2, 2);
+        state.update_with_seq(Event::new(0, 0b01), 1, 2);
+        let mut restored = WindowFunnelState::from_blob(&state.to_blob()).unwrap();
+        assert_eq!(restored.seqs, vec![2, 1]);
+        assert_eq!(restored.finalize(), 2);
+    }
+
+    #[test]
+    fn test_blob_round_trip_of_compressed_events() {
+        let events: Vec<(i64, u64)> = (0..COMPRESSION_THRESHOLD as i64 + 10)
+            .map(|i| (i, 1 << (i % 3)))
+            .collect();
+        let state = blob_state(FunnelMode::DEFAULT, &events);
+        assert!(!state.compressed.is_empty());
+        let restored = WindowFunnelState::from_blob(&state.to_blob()).unwrap();
+        assert_eq!(restored.compressed.len(), COMPRESSION_THRESHOLD);
+        assert_eq!(restored.events.len(), 10);
+        assert_eq!(restored.to_blob(), state.to_blob());
+    }
+
+    #[test]
+    fn test_blob_of_empty_state_merges_into_any() {
+        let empty = WindowFunnelState::from_blob(&WindowFunnelState::new().to_blob()).unwrap();
+        let mut state = blob_state(FunnelMode::DEFAULT, &[(0, 0b001), (1, 0b010)]);
+        assert!(empty.same_funnel(&state));
+        let mut merged = empty;
+        merged.combine_in_place(&state);
+        assert_eq!(merged.finalize(), state.finalize());
+    }
+
+    #[test]
+    fn test_blob_merge_matches_combine() {
+        let left = blob_state(FunnelMode::DEFAULT, &[(0, 0b001), (5, 0b100)]);
+        let right = blob_state(FunnelMode::DEFAULT, &[(1, 0b010)]);
+        let mut merged = WindowFunnelState::from_blob(&left.to_blob()).unwrap();
+        merged.combine_in_place(&WindowFunnelState::from_blob(&right.to_blob()).unwrap());
+        assert_eq!(merged.finalize(), 3);
+    }
+
+    #[test]
+    fn test_same_funnel_rejects_different_parameters() {
+        let a = blob_state(FunnelMode::DEFAULT, &[(0, 1)]);
+        let b = blob_state(FunnelMode::STRICT, &[(0, 1)]);
+        assert!(!a.same_funnel(&b));
+        let mut c = blob_state(FunnelMode::DEFAULT, &[(0, 1)]);
+        c.window_size_us = 1;
+        assert!(!a.same_funnel(&c));
+        let mut d = blob_state(FunnelMode::DEFAULT, &[]);
+        d.update(Event::new(0, 1), 4);
+        assert!(!a.same_funnel(&d));
+        assert!(a.same_funnel(&a.clone()));
+    }
+
+    #[test]
+    fn test_from_blob_rejects_malformed_states() {
+        let blob = blob_state(FunnelMode::DEFAULT, &[(0, 1)]).to_blob();
+        assert_eq!(
+            WindowFunnelState::from_blob(&blob[..blob.len() - 1]).unwrap_err(),
+            BlobError::Truncated
+        );
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        assert_eq!(
+            WindowFunnelState::from_blob(&trailing).unwrap_err(),
+            BlobError::TrailingBytes
+        );
+        assert_eq!(
+            WindowFunnelState::from_blob(b"not a state").unwrap_err(),
+            BlobError::Tag
+        );
+        let mut bad_flag = blob;
+        bad_flag[4] = 2;
+        assert!(matches!(
+            WindowFunnelState::from_blob(&bad_flag),
+            Err(BlobError::Invalid(_))
+        ));
+    }
 }
 
 #[cfg(test)]
@@ -1548,7 +3869,7 @@ mod proptests {
             let mut state = WindowFunnelState::new();
             state.window_size_us = i64::MAX;
             for i in 0..num_events {
-                let bitmask = 1u32 << (i % num_conditions);
+                let bitmask = 1u64 << (i % num_conditions);
                 state.update(Event::new(i as i64, bitmask), num_conditions);
             }
             let result = state.finalize();
@@ -1573,13 +3894,13 @@ mod proptests {
             let mut a = WindowFunnelState::new();
             a.window_size_us = 3_600_000_000;
             for i in 0..n_a {
-                a.update(Event::new(i as i64, 1u32), 2);
+                a.update(Event::new(i as i64, 1u64), 2);
             }
 
             let mut b = WindowFunnelState::new();
             b.window_size_us = 3_600_000_000;
             for i in 0..n_b {
-                b.update(Event::new((n_a + i) as i64, 2u32), 2);
+                b.update(Event::new((n_a + i) as i64, 2u64), 2);
             }
 
             let combined = a.combine(&b);
@@ -1595,24 +3916,24 @@ mod proptests {
             let mut state = WindowFunnelState::new();
             state.window_size_us = window_us;
             for i in 0..num_conditions {
-                let bitmask = 1u32 << i;
+                let bitmask = 1u64 << i;
                 state.update(Event::new(i as i64, bitmask), num_conditions);
             }
             let result = state.finalize();
             prop_assert_eq!(result, num_conditions as i64);
         }
 
-        // --- 32-condition property tests ---
+        // --- 64-condition property tests ---
 
         #[test]
         fn finalize_bounded_wide_conditions(
             num_events in 1..=50usize,
-            num_conditions in 9..=32usize,
+            num_conditions in 9..=64usize,
         ) {
             let mut state = WindowFunnelState::new();
             state.window_size_us = i64::MAX;
             for i in 0..num_events {
-                let bitmask = 1u32 << (i % num_conditions);
+                let bitmask = 1u64 << (i % num_conditions);
                 state.update(Event::new(i as i64, bitmask), num_conditions);
             }
             let result = state.finalize();
@@ -1622,13 +3943,13 @@ mod proptests {
 
         #[test]
         fn complete_funnel_wide_conditions(
-            num_conditions in 9..=32usize,
+            num_conditions in 9..=64usize,
         ) {
             // A complete sequence of conditions 0..n should reach n steps
             let mut state = WindowFunnelState::new();
             state.window_size_us = i64::MAX;
             for i in 0..num_conditions {
-                let bitmask = 1u32 << i;
+                let bitmask = 1u64 << i;
                 state.update(Event::new(i as i64, bitmask), num_conditions);
             }
             let result = state.finalize();
@@ -1645,19 +3966,19 @@ mod proptests {
         fn combine_preserves_events_wide(
             n_a in 0..=20usize,
             n_b in 0..=20usize,
-            num_conditions in 9..=32usize,
+            num_conditions in 9..=64usize,
         ) {
             let mut a = WindowFunnelState::new();
             a.window_size_us = i64::MAX;
             for i in 0..n_a {
-                let bitmask = 1u32 << (i % num_conditions);
+                let bitmask = 1u64 << (i % num_conditions);
                 a.update(Event::new(i as i64, bitmask), num_conditions);
             }
 
             let mut b = WindowFunnelState::new();
             b.window_size_us = i64::MAX;
             for i in 0..n_b {
-                let bitmask = 1u32 << ((n_a + i) % num_conditions);
+                let bitmask = 1u64 << ((n_a + i) % num_conditions);
                 b.update(Event::new((n_a + i) as i64, bitmask), num_conditions);
             }
 
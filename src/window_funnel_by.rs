@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `window_funnel_by` — Funnel step plus a dimension of the entry event.
+//!
+//! Computes `window_funnel` and also returns the value of a dimension column
+//! (an acquisition channel, a landing page) at the entry event of the best
+//! chain, so funnels can be segmented by that dimension in one pass instead
+//! of joining back to the entry event.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT f.entry_dim AS utm_source, f.step, COUNT(*) AS users
+//! FROM (
+//!   SELECT window_funnel_by(INTERVAL '1 day', event_time, utm_source,
+//!     event_type = 'visit', event_type = 'signup', event_type = 'purchase') AS f
+//!   FROM events
+//!   GROUP BY user_id
+//! )
+//! GROUP BY ALL
+//! ```
+//!
+//! # Implementation
+//!
+//! Events go to a [`WindowFunnelState`] as for `window_funnel`. Only entry
+//! events can be the entry of a chain, so the dimension is kept for the
+//! events matching the first condition and looked up once the best chain is
+//! known. Entry events with the same timestamp and conditions are
+//! indistinguishable to the funnel; their smallest non-`NULL` value is
+//! returned, so the result does not depend on how the input was partitioned.
+
+use crate::common::event::Event;
+use crate::window_funnel::WindowFunnelState;
+use std::sync::Arc;
+
+/// State for the `window_funnel_by` aggregate function.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WindowFunnelByState {
+    /// The funnel over all events.
+    pub funnel: WindowFunnelState,
+    /// Each event matching the first condition with its dimension value.
+    pub entries: Vec<(Event, Option<Arc<str>>)>,
+}
+
+impl Default for WindowFunnelByState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WindowFunnelByState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            funnel: WindowFunnelState::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds an event with its dimension value. `num_conditions` is as for
+    /// [`WindowFunnelState::update`].
+    pub fn update(&mut self, event: Event, dim: Option<Arc<str>>, num_conditions: usize) {
+        if event.condition(0) {
+            self.entries.push((event, dim));
+        }
+        self.funnel.update(event, num_conditions);
+    }
+
+    /// Merges `other` into `self`, propagating the funnel configuration to a
+    /// zero-initialized target as [`WindowFunnelState::combine_in_place`]
+    /// does.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.funnel.combine_in_place(&other.funnel);
+        self.entries.extend_from_slice(&other.entries);
+    }
+
+    /// Computes the furthest step reached and the dimension value of the
+    /// best chain's entry event.
+    ///
+    /// The value is `None` when no chain was found (step 0) or when every
+    /// entry event at the chain's entry had a `NULL` value.
+    #[must_use]
+    pub fn finalize(&mut self) -> (i64, Option<Arc<str>>) {
+        let (step, entry) = self.funnel.finalize_with_entry();
+        let dim = entry.and_then(|entry| {
+            self.entries
+                .iter()
+                .filter(|(event, _)| *event == entry)
+                .filter_map(|(_, dim)| dim.as_ref())
+                .min()
+                .cloned()
+        });
+        (step, dim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600_000_000;
+
+    fn hourly() -> WindowFunnelByState {
+        let mut state = WindowFunnelByState::new();
+        state.funnel.window_size_us = HOUR;
+        state.funnel.params_set = true;
+        state
+    }
+
+    #[test]
+    fn test_dimension_of_best_chain_entry() {
+        let mut state = hourly();
+        // An early entry that goes nowhere, then the entry of the best chain.
+        state.update(Event::new(0, 0b001), Some(Arc::from("ads")), 3);
+        state.update(Event::new(2 * HOUR, 0b001), Some(Arc::from("email")), 3);
+        state.update(Event::new(2 * HOUR + 1, 0b010), Some(Arc::from("ads")), 3);
+        state.update(Event::new(2 * HOUR + 2, 0b100), None, 3);
+        assert_eq!(state.finalize(), (3, Some(Arc::from("email"))));
+    }
+
+    #[test]
+    fn test_no_entry_and_null_dimension() {
+        let mut state = hourly();
+        state.update(Event::new(0, 0b010), Some(Arc::from("ads")), 2);
+        assert_eq!(state.finalize(), (0, None));
+
+        let mut state = hourly();
+        state.update(Event::new(0, 0b001), None, 2);
+        assert_eq!(state.finalize(), (1, None));
+    }
+
+    #[test]
+    fn test_tied_entries_take_smallest_value_across_combine() {
+        let mut a = hourly();
+        a.update(Event::new(0, 0b001), Some(Arc::from("search")), 2);
+        let mut b = WindowFunnelByState::new();
+        b.update(Event::new(0, 0b001), Some(Arc::from("ads")), 2);
+        b.update(Event::new(0, 0b001), None, 2);
+        b.update(Event::new(10, 0b010), None, 2);
+
+        let mut target = WindowFunnelByState::new();
+        target.combine_in_place(&a);
+        target.combine_in_place(&b);
+        assert_eq!(target.funnel.window_size_us, HOUR);
+        assert_eq!(target.finalize(), (2, Some(Arc::from("ads"))));
+    }
+}
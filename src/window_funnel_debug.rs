@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! `window_funnel_debug` — Funnel step plus what the aggregate saw.
+//!
+//! Computes `window_funnel` and returns statistics of the finalize next to
+//! the step: how many events arrived, how many the scan ran over, how many
+//! chains were tried and where the best one started. When a funnel returns a
+//! surprising step, this shows whether events were filtered, deduplicated or
+//! simply never formed a chain, without exporting the raw events.
+//!
+//! # SQL Usage
+//!
+//! ```sql
+//! SELECT user_id,
+//!   window_funnel_debug(INTERVAL '1 hour', 'strict', event_time,
+//!     event_type = 'view', event_type = 'cart', event_type = 'purchase') AS dbg
+//! FROM events
+//! WHERE user_id = 42
+//! GROUP BY user_id
+//! ```
+
+use crate::common::event::Event;
+use crate::window_funnel::{FunnelStats, WindowFunnelState};
+
+/// State for the `window_funnel_debug` aggregate function.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WindowFunnelDebugState {
+    /// The funnel over all events.
+    pub funnel: WindowFunnelState,
+    /// Events received by update, including those with no condition set,
+    /// which the funnel does not store.
+    pub events_collected: u64,
+}
+
+impl Default for WindowFunnelDebugState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WindowFunnelDebugState {
+    /// Creates a new empty state.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            funnel: WindowFunnelState::new(),
+            events_collected: 0,
+        }
+    }
+
+    /// Adds an event. `num_conditions` is as for
+    /// [`WindowFunnelState::update`].
+    pub fn update(&mut self, event: Event, num_conditions: usize) {
+        self.events_collected += 1;
+        self.funnel.update(event, num_conditions);
+    }
+
+    /// Merges `other` into `self`, propagating the funnel configuration to a
+    /// zero-initialized target as [`WindowFunnelState::combine_in_place`]
+    /// does.
+    pub fn combine_in_place(&mut self, other: &Self) {
+        self.funnel.combine_in_place(&other.funnel);
+        self.events_collected += other.events_collected;
+    }
+
+    /// Computes the funnel step with the statistics of its search (see
+    /// [`WindowFunnelState::finalize_stats`]).
+    #[must_use]
+    pub fn finalize(&mut self) -> FunnelStats {
+        self.funnel.finalize_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collected_counts_unstored_events_across_combine() {
+        let mut a = WindowFunnelDebugState::new();
+        a.funnel.window_size_us = 3_600_000_000;
+        a.funnel.params_set = true;
+        a.update(Event::new(0, 0b01), 2);
+        a.update(Event::new(1, 0b00), 2);
+        let mut b = WindowFunnelDebugState::new();
+        b.update(Event::new(2, 0b10), 2);
+
+        let mut target = WindowFunnelDebugState::new();
+        target.combine_in_place(&a);
+        target.combine_in_place(&b);
+        assert_eq!(target.events_collected, 3);
+        let stats = target.finalize();
+        assert_eq!(
+            (stats.step, stats.events_scanned, stats.best_entry_ts),
+            (2, 2, Some(0))
+        );
+    }
+}
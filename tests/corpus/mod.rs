@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Regression corpus of anonymized journeys and their expected outputs.
+//!
+//! A corpus is a pair of files in this directory:
+//!
+//! - `<name>.bjny` — journeys in the compact binary format described in
+//!   `README.md`: per journey, a condition count and a list of
+//!   `(timestamp delta, condition mask)` pairs. No user identifiers, event
+//!   names or absolute times are stored.
+//! - `<name>.expected` — tab-separated expectations, one per line:
+//!   `journey  function  argument  expected`.
+//!
+//! [`decode`] and [`encode`] are exact inverses; [`parse_expectations`] reads
+//! the expectation file. The replay itself lives in `tests/corpus_replay.rs`.
+
+use std::path::{Path, PathBuf};
+
+/// File magic at the start of every `.bjny` file.
+pub const MAGIC: &[u8; 4] = b"BJNY";
+/// Current format version.
+pub const VERSION: u8 = 1;
+
+/// One anonymized journey: the events of a single group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Journey {
+    /// Number of conditions (1..=32) the masks were recorded against.
+    pub num_conditions: usize,
+    /// Events as `(timestamp_us, condition_mask)`, in recorded order.
+    pub events: Vec<(i64, u32)>,
+}
+
+/// One expected output for one journey.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expectation {
+    /// 1-based line number in the `.expected` file, for failure messages.
+    pub line: usize,
+    /// Index of the journey in the `.bjny` file.
+    pub journey: usize,
+    /// Function name, e.g. `window_funnel`.
+    pub function: String,
+    /// Function-specific argument (window and modes, pattern, gap).
+    pub argument: String,
+    /// Expected output, formatted as `DuckDB` prints it.
+    pub expected: String,
+}
+
+/// Returns the directory holding the corpus files.
+pub fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("corpus")
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or_else(|| format!("truncated varint at offset {pos}"))?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(format!(
+        "varint longer than 10 bytes ending at offset {pos}"
+    ))
+}
+
+const fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+const fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Encodes journeys into the `.bjny` format.
+pub fn encode(journeys: &[Journey]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    write_varint(&mut out, journeys.len() as u64);
+    for journey in journeys {
+        out.push(journey.num_conditions as u8);
+        write_varint(&mut out, journey.events.len() as u64);
+        let mut prev = 0i64;
+        for &(ts, mask) in &journey.events {
+            write_varint(&mut out, zigzag(ts.wrapping_sub(prev)));
+            write_varint(&mut out, u64::from(mask));
+            prev = ts;
+        }
+    }
+    out
+}
+
+/// Decodes a `.bjny` file, rejecting bad magic, unknown versions, condition
+/// counts outside 1..=32, masks with bits beyond the condition count, and
+/// trailing bytes.
+pub fn decode(bytes: &[u8]) -> Result<Vec<Journey>, String> {
+    if bytes.len() < 5 || &bytes[..4] != MAGIC {
+        return Err("missing BJNY magic".to_string());
+    }
+    if bytes[4] != VERSION {
+        return Err(format!("unsupported version {}", bytes[4]));
+    }
+    let mut pos = 5;
+    let count = read_varint(bytes, &mut pos)? as usize;
+    let mut journeys = Vec::with_capacity(count);
+    for j in 0..count {
+        let num_conditions = *bytes
+            .get(pos)
+            .ok_or_else(|| format!("journey {j}: truncated header"))?
+            as usize;
+        pos += 1;
+        if !(1..=32).contains(&num_conditions) {
+            return Err(format!("journey {j}: {num_conditions} conditions"));
+        }
+        let n = read_varint(bytes, &mut pos)? as usize;
+        let mut events = Vec::with_capacity(n);
+        let mut prev = 0i64;
+        for _ in 0..n {
+            let ts = prev.wrapping_add(unzigzag(read_varint(bytes, &mut pos)?));
+            let mask = u32::try_from(read_varint(bytes, &mut pos)?)
+                .map_err(|_| format!("journey {j}: mask exceeds 32 bits"))?;
+            if num_conditions < 32 && mask >> num_conditions != 0 {
+                return Err(format!(
+                    "journey {j}: mask {mask:#x} exceeds {num_conditions} conditions"
+                ));
+            }
+            events.push((ts, mask));
+            prev = ts;
+        }
+        journeys.push(Journey {
+            num_conditions,
+            events,
+        });
+    }
+    if pos != bytes.len() {
+        return Err(format!("{} trailing bytes", bytes.len() - pos));
+    }
+    Ok(journeys)
+}
+
+/// Parses a `.expected` file. Blank lines and lines starting with `#` are
+/// skipped.
+pub fn parse_expectations(text: &str) -> Result<Vec<Expectation>, String> {
+    let mut out = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = i + 1;
+        if raw.trim().is_empty() || raw.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = raw.split('\t').collect();
+        let [journey, function, argument, expected] = fields[..] else {
+            return Err(format!("line {line}: expected 4 tab-separated fields"));
+        };
+        let journey = journey
+            .parse()
+            .map_err(|_| format!("line {line}: bad journey index {journey:?}"))?;
+        out.push(Expectation {
+            line,
+            journey,
+            function: function.to_string(),
+            argument: argument.to_string(),
+            expected: expected.to_string(),
+        });
+    }
+    Ok(out)
+}
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Replays the regression corpus in `tests/corpus/` through every function
+//! state and compares against the recorded expected outputs.
+//!
+//! Each expectation is evaluated twice: once with all events in a single
+//! state, and once with the journey split into small partial states that are
+//! combined, as `DuckDB` does in parallel plans. Both must match.
+
+mod corpus;
+
+use behavioral::common::event::Event;
+use behavioral::retention::RetentionState;
+use behavioral::sequence::SequenceState;
+use behavioral::sessionize::SessionizeBoundaryState;
+use behavioral::window_funnel::{FunnelMode, WindowFunnelState};
+use corpus::{Expectation, Journey};
+
+/// Partial-state size for the combine replay. Small and odd so that chunk
+/// borders fall inside runs, sessions and funnel windows.
+const CHUNK: usize = 7;
+
+fn format_list<T: std::fmt::Display>(items: &[T]) -> String {
+    let parts: Vec<String> = items.iter().map(ToString::to_string).collect();
+    format!("[{}]", parts.join(", "))
+}
+
+fn chunks(journey: &Journey, chunked: bool) -> Vec<&[(i64, u32)]> {
+    if chunked {
+        journey.events.chunks(CHUNK).collect()
+    } else {
+        vec![&journey.events[..]]
+    }
+}
+
+fn replay_sessionize(journey: &Journey, gap: &str, chunked: bool) -> Result<String, String> {
+    let threshold_us: i64 = gap.parse().map_err(|_| format!("bad gap {gap:?}"))?;
+    // sessionize is evaluated over ORDER BY ts; segments must be adjacent.
+    let mut sorted = journey.clone();
+    sorted.events.sort_by_key(|&(ts, _)| ts);
+    let mut total = SessionizeBoundaryState::new();
+    total.threshold_us = threshold_us;
+    for part in chunks(&sorted, chunked) {
+        let mut state = SessionizeBoundaryState::new();
+        state.threshold_us = threshold_us;
+        for &(ts, _) in part {
+            state.update(ts);
+        }
+        total = total.combine(&state);
+    }
+    Ok(total.finalize().to_string())
+}
+
+fn replay_retention(journey: &Journey, chunked: bool) -> String {
+    let mut total = RetentionState::new();
+    for part in chunks(journey, chunked) {
+        let mut state = RetentionState::new();
+        for &(_, mask) in part {
+            let conditions: Vec<bool> = (0..journey.num_conditions)
+                .map(|c| mask & (1 << c) != 0)
+                .collect();
+            state.update(&conditions);
+        }
+        total = total.combine(&state);
+    }
+    format_list(&total.finalize())
+}
+
+fn replay_window_funnel(journey: &Journey, arg: &str, chunked: bool) -> Result<String, String> {
+    let (window, modes) = arg.split_once(' ').unwrap_or((arg, ""));
+    let window_size_us: i64 = window
+        .parse()
+        .map_err(|_| format!("bad window {window:?}"))?;
+    let mode = if modes.is_empty() {
+        FunnelMode::DEFAULT
+    } else {
+        FunnelMode::parse_modes(modes)?
+    };
+    let mut total = WindowFunnelState::new();
+    for part in chunks(journey, chunked) {
+        let mut state = WindowFunnelState::new();
+        state.window_size_us = window_size_us;
+        state.mode = mode;
+        state.params_set = true;
+        for &(ts, mask) in part {
+            state.update(Event::new(ts, u64::from(mask)), journey.num_conditions);
+        }
+        total.combine_in_place(&state);
+    }
+    Ok(total.finalize().to_string())
+}
+
+fn replay_sequence(
+    journey: &Journey,
+    function: &str,
+    pattern: &str,
+    chunked: bool,
+) -> Result<String, String> {
+    let mut total = SequenceState::new();
+    for part in chunks(journey, chunked) {
+        let mut state = SequenceState::new();
+        state.set_pattern(pattern);
+        for &(ts, mask) in part {
+            state.update(Event::new(ts, u64::from(mask)));
+        }
+        total.combine_in_place(&state);
+    }
+    let result = match function {
+        "sequence_match" => total.finalize_match().map(|m| m.to_string()),
+        "sequence_count" => total.finalize_count().map(|n| n.to_string()),
+        "sequence_match_events" => total.finalize_events().map(|ts| format_list(&ts)),
+        _ => unreachable!("dispatched by replay()"),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+fn replay(expectation: &Expectation, journey: &Journey, chunked: bool) -> Result<String, String> {
+    let arg = expectation.argument.as_str();
+    match expectation.function.as_str() {
+        "sessionize" => replay_sessionize(journey, arg, chunked),
+        "retention" => Ok(replay_retention(journey, chunked)),
+        "window_funnel" => replay_window_funnel(journey, arg, chunked),
+        f @ ("sequence_match" | "sequence_count" | "sequence_match_events") => {
+            replay_sequence(journey, f, arg, chunked)
+        }
+        other => Err(format!("unknown function {other:?}")),
+    }
+}
+
+fn load(name: &str) -> (Vec<Journey>, Vec<Expectation>) {
+    let dir = corpus::corpus_dir();
+    let bytes = std::fs::read(dir.join(format!("{name}.bjny"))).expect("read .bjny");
+    let journeys = corpus::decode(&bytes).expect("decode .bjny");
+    let text =
+        std::fs::read_to_string(dir.join(format!("{name}.expected"))).expect("read .expected");
+    let expectations = corpus::parse_expectations(&text).expect("parse .expected");
+    (journeys, expectations)
+}
+
+fn replay_corpus(name: &str) {
+    let (journeys, expectations) = load(name);
+    assert!(!expectations.is_empty(), "{name}: no expectations");
+    let mut failures = Vec::new();
+    for e in &expectations {
+        let Some(journey) = journeys.get(e.journey) else {
+            failures.push(format!("line {}: no journey {}", e.line, e.journey));
+            continue;
+        };
+        for chunked in [false, true] {
+            match replay(e, journey, chunked) {
+                Ok(actual) if actual == e.expected => {}
+                Ok(actual) => failures.push(format!(
+                    "line {}: {}({}) on journey {} (chunked={chunked}): expected {}, got {actual}",
+                    e.line, e.function, e.argument, e.journey, e.expected
+                )),
+                Err(err) => failures.push(format!("line {}: {err}", e.line)),
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{name}: {} mismatches\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_corpus_journeys_replay() {
+    replay_corpus("journeys");
+}
+
+#[test]
+fn test_corpus_every_journey_has_expectations() {
+    let (journeys, expectations) = load("journeys");
+    for j in 0..journeys.len() {
+        assert!(
+            expectations.iter().any(|e| e.journey == j),
+            "journey {j} has no expectations"
+        );
+    }
+}
+
+#[test]
+fn test_corpus_encode_decode_roundtrip() {
+    let (journeys, _) = load("journeys");
+    let bytes = corpus::encode(&journeys);
+    assert_eq!(corpus::decode(&bytes).unwrap(), journeys);
+}
+
+#[test]
+fn test_corpus_decode_rejects_malformed() {
+    let journey = Journey {
+        num_conditions: 2,
+        events: vec![(0, 0b01), (-5, 0b10), (i64::MAX, 0)],
+    };
+    let bytes = corpus::encode(std::slice::from_ref(&journey));
+    assert_eq!(corpus::decode(&bytes).unwrap(), vec![journey]);
+
+    assert!(corpus::decode(b"XJNY\x01\x00").is_err());
+    assert!(corpus::decode(b"BJNY\x02\x00").is_err());
+    assert!(corpus::decode(&bytes[..bytes.len() - 1]).is_err());
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(corpus::decode(&trailing).is_err());
+    // Mask bit 2 set on a 2-condition journey
+    let wide = Journey {
+        num_conditions: 2,
+        events: vec![(0, 0b100)],
+    };
+    assert!(corpus::decode(&corpus::encode(&[wide])).is_err());
+}
@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Differential tests: random event streams and patterns run through the
+//! optimized states and through the naive implementations in
+//! `tests/reference/`, which must agree.
+//!
+//! States are built from small partial states combined the way `DuckDB`'s
+//! segment tree does (see [`tree_combine`]), so every property also covers
+//! the combine. Patterns are random concatenations of pattern tokens, which
+//! reach the adjacent and wildcard fast paths as well as the NFA.
+
+mod reference;
+
+use behavioral::common::event::Event;
+use behavioral::pattern::executor::{execute_pattern, execute_pattern_within};
+use behavioral::pattern::parser::parse_pattern;
+use behavioral::sequence::SequenceState;
+use behavioral::sessionize::SessionizeBoundaryState;
+use behavioral::window_funnel::{FunnelMode, TieBreak, WindowFunnelState};
+use proptest::prelude::*;
+use reference::{FunnelOptions, RefEvent};
+
+/// Pattern tokens over three conditions, with second and millisecond time
+/// constraints that the half-second event spacing can satisfy either way.
+const TOKENS: &[&str] = &[
+    "(?1)",
+    "(?2)",
+    "(?3)",
+    ".",
+    ".*",
+    "(?!1)",
+    "(?!2)*",
+    "(?t<=2)",
+    "(?t>1)",
+    "(?t<=1500ms)",
+    "(?T<=4)",
+    "(?t==0)",
+];
+
+/// Random patterns, occasionally anchored.
+fn pattern() -> impl Strategy<Value = String> {
+    (
+        prop::bool::weighted(0.15),
+        prop::collection::vec(prop::sample::select(TOKENS), 1..6),
+        prop::bool::weighted(0.15),
+    )
+        .prop_map(|(start, tokens, end)| {
+            format!(
+                "{}{}{}",
+                if start { "^" } else { "" },
+                tokens.concat(),
+                if end { "$" } else { "" }
+            )
+        })
+}
+
+/// Events half a second to two seconds apart (`distinct`), or with ties when
+/// not, over three conditions.
+fn event_stream(
+    distinct: bool,
+    len: std::ops::Range<usize>,
+) -> impl Strategy<Value = Vec<RefEvent>> {
+    let min_gap = i64::from(distinct);
+    prop::collection::vec((min_gap..=4, 0u64..8), len).prop_map(|steps| {
+        let mut ts = 0;
+        steps
+            .into_iter()
+            .map(|(gap, mask)| {
+                ts += gap * 500_000;
+                (ts, mask)
+            })
+            .collect()
+    })
+}
+
+/// Folds `leaves` like `DuckDB`'s segment tree: every inner node is a fresh
+/// zero-initialized state into which both children are combined.
+fn tree_combine<S: Default>(leaves: &[S], combine: fn(&mut S, &S)) -> S {
+    let mut node = S::default();
+    match leaves {
+        [] => {}
+        [leaf] => combine(&mut node, leaf),
+        _ => {
+            let (left, right) = leaves.split_at(leaves.len() / 2);
+            combine(&mut node, &tree_combine(left, combine));
+            combine(&mut node, &tree_combine(right, combine));
+        }
+    }
+    node
+}
+
+fn to_events(events: &[RefEvent]) -> Vec<Event> {
+    events
+        .iter()
+        .map(|&(ts, mask)| Event::new(ts, mask))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn sequence_matches_reference(
+        events in event_stream(true, 1..12).prop_shuffle(),
+        pattern in pattern(),
+        window_s in prop::option::weighted(0.25, 0i64..4),
+        chunk in 1usize..5,
+    ) {
+        let Ok(compiled) = parse_pattern(&pattern) else {
+            return Ok(());
+        };
+        let window_us = window_s.map(|s| s * 1_000_000);
+        let leaves: Vec<SequenceState> = events
+            .chunks(chunk)
+            .map(|part| {
+                let mut state = SequenceState::new();
+                state.set_pattern(&pattern);
+                state.window_us = window_us;
+                for &(ts, mask) in part {
+                    state.update(Event::new(ts, mask));
+                }
+                state
+            })
+            .collect();
+        let mut total = tree_combine(&leaves, SequenceState::combine_in_place);
+
+        // The state drops events with no condition set.
+        let mut stored: Vec<RefEvent> = events.into_iter().filter(|e| e.1 != 0).collect();
+        stored.sort_unstable();
+        let expected = reference::sequence(&compiled, &stored, window_us);
+        prop_assert_eq!(total.clone().finalize_match().unwrap(), expected.matched);
+        prop_assert_eq!(total.finalize_count().unwrap(), expected.count as i64);
+    }
+
+    #[test]
+    fn fast_paths_match_nfa_and_reference(
+        events in event_stream(false, 1..12),
+        pattern in pattern(),
+    ) {
+        let Ok(compiled) = parse_pattern(&pattern) else {
+            return Ok(());
+        };
+        // A window always takes the NFA; i64::MAX bounds nothing.
+        let stream = to_events(&events);
+        for count_all in [false, true] {
+            let fast = execute_pattern(&compiled, &stream, count_all);
+            let nfa = execute_pattern_within(&compiled, &stream, count_all, Some(i64::MAX));
+            prop_assert_eq!(fast.matched, nfa.matched);
+            prop_assert_eq!(fast.count, nfa.count);
+        }
+        let expected = reference::sequence(&compiled, &events, None);
+        prop_assert_eq!(execute_pattern(&compiled, &stream, true).count, expected.count);
+    }
+
+    #[test]
+    fn window_funnel_matches_reference(
+        events in event_stream(false, 0..12).prop_shuffle(),
+        num_conditions in 1usize..=3,
+        window_s in 0i64..5,
+        strict_once in any::<bool>(),
+        entry_advances in any::<bool>(),
+        chunk in 1usize..5,
+    ) {
+        let options = FunnelOptions { strict_once, entry_advances };
+        // tie_break(condition) makes the order of tied events deterministic.
+        let mut mode = FunnelMode::tie_break(TieBreak::Condition);
+        if strict_once {
+            mode = mode.with(FunnelMode::STRICT_ONCE);
+        }
+        if entry_advances {
+            mode = mode.with(FunnelMode::ENTRY_ADVANCES);
+        }
+        let condition_bits = (1u64 << num_conditions) - 1;
+        let mut events: Vec<RefEvent> = events
+            .into_iter()
+            .map(|(ts, mask)| (ts, mask & condition_bits))
+            .collect();
+        let leaves: Vec<WindowFunnelState> = events
+            .chunks(chunk)
+            .map(|part| {
+                let mut state = WindowFunnelState::new();
+                state.window_size_us = window_s * 1_000_000;
+                state.mode = mode;
+                state.params_set = true;
+                for &(ts, mask) in part {
+                    state.update(Event::new(ts, mask), num_conditions);
+                }
+                state
+            })
+            .collect();
+        let mut total = tree_combine(&leaves, WindowFunnelState::combine_in_place);
+
+        reference::sort_by_condition(&mut events);
+        let expected =
+            reference::window_funnel(&events, num_conditions, window_s * 1_000_000, options);
+        prop_assert_eq!(total.finalize(), expected as i64);
+    }
+
+    #[test]
+    fn sessionize_matches_reference(
+        events in event_stream(false, 0..16),
+        gap_s in 0i64..3,
+        chunk in 1usize..5,
+    ) {
+        // sessionize runs over ORDER BY ts: segments are adjacent and sorted.
+        let timestamps: Vec<i64> = events.iter().map(|&(ts, _)| ts).collect();
+        let leaves: Vec<SessionizeBoundaryState> = timestamps
+            .chunks(chunk)
+            .map(|part| {
+                let mut state = SessionizeBoundaryState::new();
+                state.threshold_us = gap_s * 1_000_000;
+                for &ts in part {
+                    state.update(ts);
+                }
+                state
+            })
+            .collect();
+        let total = tree_combine(&leaves, |node, child| *node = node.combine(child));
+        prop_assert_eq!(
+            total.finalize(),
+            reference::session_count(&timestamps, gap_s * 1_000_000)
+        );
+    }
+}
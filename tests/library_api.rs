@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! The library API without the `ffi` feature: the states driven directly,
+//! on the data of the SQL tests in `test/sql/`, must return the results
+//! those tests expect from the SQL functions.
+//!
+//! Run with `cargo test --no-default-features --test library_api` to check
+//! that nothing here needs `DuckDB`.
+
+use behavioral::common::event::Event;
+use behavioral::pattern::executor::execute_pattern;
+use behavioral::pattern::parser::parse_pattern;
+use behavioral::retention::RetentionState;
+use behavioral::sequence::SequenceState;
+use behavioral::sessionize::{SessionizeBoundaryState, SessionizeSummaryState};
+use behavioral::window_funnel::WindowFunnelState;
+
+const MINUTE: i64 = 60_000_000;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// A funnel state with the parameters the first update of `window_funnel`
+/// sets.
+fn funnel(window_us: i64) -> WindowFunnelState {
+    let mut state = WindowFunnelState::new();
+    state.window_size_us = window_us;
+    state.params_set = true;
+    state
+}
+
+#[test]
+fn window_funnel_matches_sql() {
+    // test/sql/window_funnel_state.test: view, cart, purchase per user
+    let users: [&[(i64, u64)]; 3] = [
+        &[
+            (DAY - 10 * MINUTE, 0b001),
+            (DAY + 5 * MINUTE, 0b010),
+            (DAY + 10 * MINUTE, 0b100),
+        ],
+        &[(0, 0b001), (5 * MINUTE, 0b010)],
+        &[(0, 0b001), (DAY + 5 * HOUR, 0b010)],
+    ];
+    let steps: Vec<i64> = users
+        .iter()
+        .map(|events| {
+            let mut state = funnel(HOUR);
+            for &(ts, mask) in *events {
+                state.update(Event::new(ts, mask), 3);
+            }
+            state.finalize()
+        })
+        .collect();
+    assert_eq!(steps, [3, 2, 1]);
+}
+
+#[test]
+fn window_funnel_partial_states_combine() {
+    // One state per day, as window_funnel_merge combines them
+    let mut first_day = funnel(HOUR);
+    first_day.update(Event::new(DAY - 10 * MINUTE, 0b001), 3);
+    let mut second_day = funnel(HOUR);
+    second_day.update(Event::new(DAY + 5 * MINUTE, 0b010), 3);
+    second_day.update(Event::new(DAY + 10 * MINUTE, 0b100), 3);
+
+    assert_eq!(second_day.clone().finalize(), 0);
+    let mut merged = WindowFunnelState::from_blob(&first_day.to_blob()).unwrap();
+    merged.combine_in_place(&WindowFunnelState::from_blob(&second_day.to_blob()).unwrap());
+    assert_eq!(merged.finalize(), 3);
+}
+
+#[test]
+fn sequence_match_and_count() {
+    let events = [
+        Event::new(0, 0b01),
+        Event::new(MINUTE, 0b10),
+        Event::new(2 * MINUTE, 0b01),
+        Event::new(3 * MINUTE, 0b10),
+    ];
+
+    let mut state = SequenceState::new();
+    state.set_pattern("(?1).*(?2)");
+    for event in events {
+        state.update(event);
+    }
+    assert!(state.clone().finalize_match().unwrap());
+    assert_eq!(state.finalize_count().unwrap(), 2);
+
+    // The parser and executor are usable on their own
+    let pattern = parse_pattern("(?1)(?t<=30)(?2)").unwrap();
+    assert!(!execute_pattern(&pattern, &events, false).matched);
+    assert!(parse_pattern("(?1").is_err());
+}
+
+#[test]
+fn sessionize_matches_sql() {
+    // test/sql/sessionize_state.test, user 1: a session across midnight
+    let timestamps = [
+        10 * HOUR,
+        DAY - 10 * MINUTE,
+        DAY + 10 * MINUTE,
+        DAY + 9 * HOUR,
+    ];
+    let mut ordered = SessionizeBoundaryState::new();
+    ordered.threshold_us = 30 * MINUTE;
+    for ts in timestamps {
+        ordered.update(ts);
+    }
+    assert_eq!(ordered.finalize(), 3);
+
+    // Unordered per-day states, merged like sessionize_merge
+    let day_state = |range: &[i64]| {
+        let mut summary = SessionizeSummaryState::new();
+        summary.threshold_us = Some(30 * MINUTE);
+        for &ts in range.iter().rev() {
+            summary.update(ts);
+        }
+        summary.summarize()
+    };
+    let (first, second) = (day_state(&timestamps[..2]), day_state(&timestamps[2..]));
+    assert_eq!(first.finalize() + second.finalize(), 4);
+    assert_eq!(second.merge_ranges(&first).unwrap().finalize(), 3);
+}
+
+#[test]
+fn retention_matches_sql() {
+    // test/sql/retention_state.test: days 2024-01-01, 01-02 and 01-08
+    let users: [&[[bool; 3]]; 3] = [
+        &[
+            [true, false, false],
+            [false, true, false],
+            [false, false, true],
+        ],
+        &[[true, false, false], [false, false, true]],
+        &[[false, true, false], [false, false, true]],
+    ];
+    let results: Vec<Vec<bool>> = users
+        .iter()
+        .map(|rows| {
+            let states = rows.iter().map(|row| {
+                let mut state = RetentionState::new();
+                state.update(row);
+                state
+            });
+            states
+                .fold(RetentionState::new(), |acc, state| acc.combine(&state))
+                .finalize()
+        })
+        .collect();
+    assert_eq!(
+        results,
+        [
+            vec![true, true, true],
+            vec![true, false, true],
+            vec![false, false, false]
+        ]
+    );
+}
@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2026 Tom F. (https://github.com/tomtom215/duckdb-behavioral)
+
+//! Naive reference implementations of the funnel, sequence and sessionize
+//! semantics, for the differential suite in `tests/differential.rs`.
+//!
+//! Each function restates the documented semantics as directly as possible,
+//! with no fast paths, memoization, pruning or partial states: funnels try
+//! every chain, patterns backtrack without a memo, sessions compare every
+//! consecutive pair. They are exponential or quadratic and only meant for the
+//! small inputs `proptest` generates.
+//!
+//! Events are `(timestamp_us, condition_mask)` pairs, as in the corpus, and
+//! are sorted here; inputs with tied timestamps must fix their order through
+//! the caller's tie break (see [`sort_by_condition`]).
+
+use behavioral::pattern::parser::{CompiledPattern, PatternStep};
+
+/// One event: timestamp in microseconds and a condition bitmask.
+pub type RefEvent = (i64, u64);
+
+const MICROS_PER_SECOND: i128 = 1_000_000;
+
+fn condition(event: RefEvent, index: usize) -> bool {
+    event.1 & (1 << index) != 0
+}
+
+/// Sorts events by timestamp, then by lowest satisfied condition and mask:
+/// the order of `window_funnel`'s `tie_break(condition)` mode.
+pub fn sort_by_condition(events: &mut [RefEvent]) {
+    events.sort_by_key(|&(ts, mask)| (ts, mask.trailing_zeros(), mask));
+}
+
+// -- sessionize --
+
+/// Number of sessions: one for the first event and one more for every gap
+/// between consecutive timestamps exceeding `gap_us`. Zero without events.
+pub fn session_count(timestamps: &[i64], gap_us: i64) -> i64 {
+    let mut sorted = timestamps.to_vec();
+    sorted.sort_unstable();
+    if sorted.is_empty() {
+        return 0;
+    }
+    let breaks = sorted
+        .windows(2)
+        .filter(|w| i128::from(w[1]) - i128::from(w[0]) > i128::from(gap_us))
+        .count();
+    breaks as i64 + 1
+}
+
+// -- window_funnel --
+
+/// The funnel modes the reference covers. Modes that pick one chain among
+/// several (`strict_increase`, `strict_time`, `strict_order`, `skip`) are left
+/// out: the longest chain is not what they specify.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FunnelOptions {
+    /// `strict_once`: an event matches at most one step.
+    pub strict_once: bool,
+    /// `entry_advances`: the entry event may also match the following steps.
+    pub entry_advances: bool,
+}
+
+/// Longest funnel prefix any chain reaches: an entry event matching
+/// condition 0, then events in sorted order matching conditions 1, 2, ...
+/// within `window_us` of the entry. An event may match several consecutive
+/// steps, except the entry (unless `entry_advances`) and under
+/// `strict_once`. Events must be sorted.
+pub fn window_funnel(
+    events: &[RefEvent],
+    num_conditions: usize,
+    window_us: i64,
+    options: FunnelOptions,
+) -> usize {
+    let mut best = 0;
+    for (entry, &event) in events.iter().enumerate() {
+        if condition(event, 0) {
+            best = best.max(extend_chain(
+                events,
+                num_conditions,
+                window_us,
+                options,
+                event.0,
+                entry,
+                1,
+                true,
+            ));
+        }
+    }
+    best
+}
+
+/// Steps reached by the best chain whose step `step - 1` was matched by the
+/// event at `last`; `last_is_entry` when that event is the entry.
+fn extend_chain(
+    events: &[RefEvent],
+    num_conditions: usize,
+    window_us: i64,
+    options: FunnelOptions,
+    entry_ts: i64,
+    last: usize,
+    step: usize,
+    last_is_entry: bool,
+) -> usize {
+    if step == num_conditions {
+        return step;
+    }
+    let mut best = step;
+    let same_event_allowed = if last_is_entry {
+        options.entry_advances && !options.strict_once
+    } else {
+        !options.strict_once
+    };
+    let first = if same_event_allowed { last } else { last + 1 };
+    for next in first..events.len() {
+        let event = events[next];
+        if i128::from(event.0) - i128::from(entry_ts) > i128::from(window_us) {
+            break;
+        }
+        if !condition(event, step) {
+            continue;
+        }
+        best = best.max(extend_chain(
+            events,
+            num_conditions,
+            window_us,
+            options,
+            entry_ts,
+            next,
+            step + 1,
+            last_is_entry && next == last,
+        ));
+    }
+    best
+}
+
+// -- sequence_match / sequence_count --
+
+/// Result of matching a pattern over a whole event stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceResult {
+    /// Whether any match exists (`sequence_match`).
+    pub matched: bool,
+    /// Number of non-overlapping matches (`sequence_count`).
+    pub count: usize,
+}
+
+/// Matches `pattern` over `events`, which the caller has filtered to events
+/// with at least one condition set and sorted, as `SequenceState` stores
+/// them.
+///
+/// Matches are searched from each start in turn; the first match from a
+/// start is the one a backtracking search finds when `.*` and `(?!N)*` try
+/// to advance the pattern before consuming an event. Counting resumes after
+/// the last event that match consumed, or after its start when it consumed
+/// none. `window_us` bounds the time from the
+/// first to the last event consumed by a match (`sequence_count_within`).
+pub fn sequence(
+    pattern: &CompiledPattern,
+    events: &[RefEvent],
+    window_us: Option<i64>,
+) -> SequenceResult {
+    let mut count = 0;
+    let mut start = 0;
+    while start < events.len() && !pattern.steps.is_empty() {
+        let matcher = Matcher {
+            pattern,
+            events,
+            window_us,
+        };
+        if let Some(end) = matcher.first_match(start, 0, None, None) {
+            count += 1;
+            start = end.max(start) + 1;
+        } else {
+            start += 1;
+        }
+        if pattern.anchored_start {
+            break;
+        }
+    }
+    SequenceResult {
+        matched: count > 0,
+        count,
+    }
+}
+
+struct Matcher<'a> {
+    pattern: &'a CompiledPattern,
+    events: &'a [RefEvent],
+    window_us: Option<i64>,
+}
+
+impl Matcher<'_> {
+    /// Index of the last event consumed by the first match of the steps from
+    /// `step` on, at event `at`, after a match whose first and last consumed
+    /// events are at `first` and `last`.
+    fn first_match(
+        &self,
+        at: usize,
+        step: usize,
+        first: Option<i64>,
+        last: Option<i64>,
+    ) -> Option<usize> {
+        let Some(current) = self.pattern.steps.get(step) else {
+            if self.pattern.anchored_end && at < self.events.len() {
+                return None;
+            }
+            return Some(at.saturating_sub(1));
+        };
+        let Some(&event) = self.events.get(at) else {
+            return match current {
+                PatternStep::AnyEvents | PatternStep::AnyNotCondition(_) => {
+                    self.first_match(at, step + 1, first, last)
+                }
+                _ => None,
+            };
+        };
+        match *current {
+            PatternStep::Condition(c) if condition(event, c) => {
+                self.consume(at, step, first, event)
+            }
+            PatternStep::NotCondition(c) if !condition(event, c) => {
+                self.consume(at, step, first, event)
+            }
+            PatternStep::OneEvent => self.consume(at, step, first, event),
+            PatternStep::AnyEvents => self
+                .first_match(at, step + 1, first, last)
+                .or_else(|| self.first_match(at + 1, step, first, last)),
+            PatternStep::AnyNotCondition(c) => {
+                self.first_match(at, step + 1, first, last).or_else(|| {
+                    (!condition(event, c))
+                        .then(|| self.first_match(at + 1, step, first, last))
+                        .flatten()
+                })
+            }
+            PatternStep::TimeConstraint(op, threshold)
+            | PatternStep::TimeConstraintMicros(op, threshold)
+            | PatternStep::StartTimeConstraint(op, threshold)
+            | PatternStep::StartTimeConstraintMicros(op, threshold) => {
+                let since = if current.is_start_time_constraint() {
+                    first
+                } else {
+                    last
+                };
+                let holds = since.is_none_or(|since| {
+                    let elapsed = i128::from(event.0) - i128::from(since);
+                    if matches!(
+                        current,
+                        PatternStep::TimeConstraint(..) | PatternStep::StartTimeConstraint(..)
+                    ) {
+                        op.evaluate(elapsed / MICROS_PER_SECOND, i128::from(threshold))
+                    } else {
+                        op.evaluate(elapsed, i128::from(threshold))
+                    }
+                });
+                if holds {
+                    self.first_match(at, step + 1, first, last)
+                } else {
+                    None
+                }
+            }
+            PatternStep::Condition(_) | PatternStep::NotCondition(_) => None,
+        }
+    }
+
+    /// Continues after the step at `step` consumed `event`, the event at
+    /// `at`.
+    fn consume(
+        &self,
+        at: usize,
+        step: usize,
+        first: Option<i64>,
+        event: RefEvent,
+    ) -> Option<usize> {
+        let first = first.unwrap_or(event.0);
+        if self
+            .window_us
+            .is_some_and(|window| i128::from(event.0) - i128::from(first) > i128::from(window))
+        {
+            return None;
+        }
+        self.first_match(at + 1, step + 1, Some(first), Some(event.0))
+    }
+}